@@ -0,0 +1,9 @@
+//! Print the Arrow → JS conversion table as JSON.
+//!
+//! Run by `npm run build:conversion-table`, which writes the output to
+//! `pkg/conversion_table.json` for the TypeScript typings generator.
+
+fn main() -> arrow_rs_wasm::Result<()> {
+    println!("{}", arrow_rs_wasm::conversion_table_json()?);
+    Ok(())
+}
@@ -0,0 +1,32 @@
+#!/bin/bash
+
+# WASM size budget check
+# Fails when the release .wasm in pkg/ exceeds the budget below. Run after
+# `npm run build:wasm`; raise the budget deliberately, in the same change that
+# explains the growth.
+#
+# Budget: 2 MiB for the default feature set (the 0.3.2 release was ~1.4 MiB).
+# tests/wasm_size.rs reads the defaults below; keep them plain numbers.
+#
+# DEBUG_IMPL_BUDGET caps the `Debug::fmt` implementations linked into the
+# release build (786 for 0.3.4, nearly all arrow's, reached through
+# `{:?}` on data types in error messages). tests/wasm_size.rs checks it
+# against function names of an unstripped build; this script does not.
+
+BUDGET_BYTES=${WASM_SIZE_BUDGET:-2097152}
+DEBUG_IMPL_BUDGET=900
+WASM_FILE="$(dirname "$0")/../pkg/arrow_rs_wasm_bg.wasm"
+
+if [ ! -f "$WASM_FILE" ]; then
+    echo "Error: $WASM_FILE not found"
+    echo "Please run 'npm run build:wasm' first"
+    exit 1
+fi
+
+SIZE=$(wc -c < "$WASM_FILE")
+echo "arrow_rs_wasm_bg.wasm: $SIZE bytes (budget $BUDGET_BYTES)"
+
+if [ "$SIZE" -gt "$BUDGET_BYTES" ]; then
+    echo "Error: WASM binary exceeds the size budget by $((SIZE - BUDGET_BYTES)) bytes"
+    exit 1
+fi
@@ -0,0 +1,86 @@
+//! Allocation counters, enabled by the `alloc-metrics` feature.
+//!
+//! Wraps the system allocator (dlmalloc on `wasm32`), or talc when
+//! `small-alloc` is on, and keeps running totals in atomics so
+//! `get_memory_info` can report them. The wrapper forwards every call
+//! unchanged; nothing in the crate depends on how memory is allocated.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(all(feature = "small-alloc", target_arch = "wasm32")))]
+static INNER: std::alloc::System = std::alloc::System;
+#[cfg(all(feature = "small-alloc", target_arch = "wasm32"))]
+static INNER: crate::small_alloc::SmallAlloc = crate::small_alloc::new();
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+fn record_growth(bytes: usize) {
+    let current = CURRENT_BYTES.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { INNER.alloc(layout) };
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            record_growth(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { INNER.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            record_growth(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { INNER.dealloc(ptr, layout) };
+        CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { INNER.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            if new_size >= layout.size() {
+                record_growth(new_size - layout.size());
+            } else {
+                CURRENT_BYTES.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Snapshot of the allocation counters.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocStats {
+    /// Successful allocations (including reallocations) since startup.
+    pub total_allocations: usize,
+    /// Bytes currently allocated.
+    pub current_bytes: usize,
+    /// Highest value `current_bytes` has reached.
+    pub peak_bytes: usize,
+}
+
+/// Read the counters.
+pub fn stats() -> AllocStats {
+    AllocStats {
+        total_allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        current_bytes: CURRENT_BYTES.load(Ordering::Relaxed),
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+    }
+}
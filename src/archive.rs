@@ -0,0 +1,436 @@
+//! Multi-table archives: several named tables in one buffer.
+//!
+//! An archive is the magic bytes `ARWARC01`, the manifest length and its
+//! CRC32 as little-endian `u32`s, the manifest as UTF-8 JSON, then one IPC
+//! stream per table, each preceded by its length as a little-endian `u64`.
+//! The manifest is `{version, tables: [{name, offset, length, crc32, rows,
+//! compression}]}`, where `offset` is the absolute byte position of the
+//! table's stream (after its length prefix) and `crc32` is the IEEE CRC32 of
+//! the stream bytes. Every checksum is verified on read.
+
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::ipc::{encode_stream, read_consistent_stream};
+use crate::mem::{self, TableData, TableHandle};
+use arrow::record_batch::RecordBatch;
+use js_sys::Uint8Array;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Magic bytes opening an archive.
+const ARCHIVE_MAGIC: &[u8] = b"ARWARC01";
+/// Current manifest version.
+const ARCHIVE_VERSION: u32 = 1;
+/// Bytes before the manifest: magic, manifest length, manifest CRC32.
+const HEADER_LEN: usize = ARCHIVE_MAGIC.len() + 8;
+
+/// Lookup table of the reflected IEEE CRC32 polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0_u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// IEEE CRC32 (as used by zip and PNG) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, byte| {
+        CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Manifest of an archive.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    tables: Vec<ManifestEntry>,
+}
+
+/// Where one table's stream lives and how to check it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    name: String,
+    offset: u64,
+    length: u64,
+    crc32: u32,
+    rows: u64,
+    compression: Option<String>,
+}
+
+/// Per-table overrides of [`ArchiveOptions`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct ArchiveTableOptions {
+    enable_lz4: Option<bool>,
+}
+
+/// Options for [`write_table_archive`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct ArchiveOptions {
+    enable_lz4: bool,
+    tables: HashMap<String, ArchiveTableOptions>,
+}
+
+/// Serialize `tables` (name, table, LZ4) into one archive.
+fn encode_archive(tables: &[(String, TableData, bool)]) -> Result<Vec<u8>> {
+    let streams = tables
+        .iter()
+        .map(|(_, table, lz4)| encode_stream(&table.schema, &table.batches, *lz4))
+        .collect::<Result<Vec<_>>>()?;
+    // Offsets depend on the manifest length, which depends on the offsets;
+    // iterate until the length is stable (it can only grow a few digits).
+    let mut manifest_len = 0;
+    loop {
+        let mut offset = (HEADER_LEN + manifest_len) as u64;
+        let entries = tables
+            .iter()
+            .zip(&streams)
+            .map(|((name, table, lz4), stream)| {
+                offset += 8;
+                let entry = ManifestEntry {
+                    name: name.clone(),
+                    offset,
+                    length: stream.len() as u64,
+                    crc32: crc32(stream),
+                    rows: table.row_count() as u64,
+                    compression: lz4.then(|| "lz4".to_string()),
+                };
+                offset += stream.len() as u64;
+                entry
+            })
+            .collect();
+        let manifest = serde_json::to_vec(&Manifest {
+            version: ARCHIVE_VERSION,
+            tables: entries,
+        })?;
+        if manifest.len() != manifest_len {
+            manifest_len = manifest.len();
+            continue;
+        }
+
+        let body: usize = streams.iter().map(|stream| stream.len() + 8).sum();
+        let mut out = Vec::with_capacity(HEADER_LEN + manifest.len() + body);
+        out.extend_from_slice(ARCHIVE_MAGIC);
+        out.extend_from_slice(&(manifest.len() as u32).to_le_bytes());
+        out.extend_from_slice(&crc32(&manifest).to_le_bytes());
+        out.extend_from_slice(&manifest);
+        for stream in &streams {
+            out.extend_from_slice(&(stream.len() as u64).to_le_bytes());
+            out.extend_from_slice(stream);
+        }
+        return Ok(out);
+    }
+}
+
+fn corrupted(message: impl Into<String>) -> ArrowWasmError {
+    ArrowWasmError::InvalidInput(format!("Corrupted table archive: {}", message.into()))
+}
+
+/// `data[start..start + len]`, or `None` when it runs past the end.
+fn range(data: &[u8], start: u64, len: u64) -> Option<&[u8]> {
+    let start = usize::try_from(start).ok()?;
+    let end = start.checked_add(usize::try_from(len).ok()?)?;
+    data.get(start..end)
+}
+
+/// Parse and verify an archive, decoding every table.
+fn decode_archive(data: &[u8]) -> Result<(Manifest, Vec<(String, TableData)>)> {
+    if !data.starts_with(ARCHIVE_MAGIC) || data.len() < HEADER_LEN {
+        return Err(ArrowWasmError::InvalidInput(
+            "Data is not a table archive".to_string(),
+        ));
+    }
+    let word = |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+    let (manifest_len, manifest_crc) = (word(ARCHIVE_MAGIC.len()), word(ARCHIVE_MAGIC.len() + 4));
+    let manifest = range(data, HEADER_LEN as u64, u64::from(manifest_len))
+        .ok_or_else(|| corrupted("truncated manifest"))?;
+    if crc32(manifest) != manifest_crc {
+        return Err(corrupted("manifest checksum mismatch"));
+    }
+    let manifest: Manifest =
+        serde_json::from_slice(manifest).map_err(|e| corrupted(format!("manifest: {e}")))?;
+    if manifest.version != ARCHIVE_VERSION {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Unsupported table archive version {}",
+            manifest.version
+        )));
+    }
+
+    let mut names = HashSet::with_capacity(manifest.tables.len());
+    if let Some(entry) = manifest
+        .tables
+        .iter()
+        .find(|entry| !names.insert(entry.name.as_str()))
+    {
+        return Err(corrupted(format!("duplicate table name '{}'", entry.name)));
+    }
+
+    let mut tables = Vec::with_capacity(manifest.tables.len());
+    for entry in &manifest.tables {
+        let table_error = |message: &str| corrupted(format!("table '{}' {message}", entry.name));
+        let prefix = entry
+            .offset
+            .checked_sub(8)
+            .and_then(|start| range(data, start, 8))
+            .ok_or_else(|| table_error("is out of bounds"))?;
+        let stream = range(data, entry.offset, entry.length)
+            .filter(|_| prefix == entry.length.to_le_bytes())
+            .ok_or_else(|| table_error("is out of bounds"))?;
+        let actual = crc32(stream);
+        if actual != entry.crc32 {
+            return Err(table_error(&format!(
+                "failed its CRC32 check (expected {:08x}, got {actual:08x})",
+                entry.crc32
+            )));
+        }
+        let (schema, mut batches) = read_consistent_stream(stream, false)?;
+        if batches.is_empty() {
+            batches.push(RecordBatch::new_empty(schema));
+        }
+        tables.push((entry.name.clone(), TableData::new(batches)?));
+    }
+    Ok((manifest, tables))
+}
+
+/// `(name, handle)` pairs of a JS `Map` or plain object, in order.
+fn named_handles(tables: &JsValue) -> Result<Vec<(String, TableHandle)>> {
+    let entries = match tables.dyn_ref::<js_sys::Map>() {
+        Some(map) => js_sys::Array::from(&map.entries().into()),
+        None if tables.is_object() => js_sys::Object::entries(tables.unchecked_ref()),
+        None => {
+            return Err(ArrowWasmError::InvalidInput(
+                "tables must be a Map or an object of table handles".to_string(),
+            ))
+        }
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            let entry: js_sys::Array = entry.unchecked_into();
+            let name = entry.get(0).as_string().ok_or_else(|| {
+                ArrowWasmError::InvalidInput("Table names must be strings".to_string())
+            })?;
+            let handle: TableHandle =
+                serde_wasm_bindgen::from_value(entry.get(1)).map_err(|_| {
+                    ArrowWasmError::InvalidInput(format!("'{name}' is not a table handle"))
+                })?;
+            Ok((name, handle))
+        })
+        .collect()
+}
+
+/// Serialize several named tables into one self-describing buffer.
+///
+/// `tables` maps names to table handles, as a `Map` or a plain object; the
+/// archive keeps their order. `options` is `{enableLz4?, tables?}`, where
+/// `tables` overrides compression per table, e.g.
+/// `{tables: {events: {enableLz4: true}}}`. Read it back with
+/// `read_table_archive`; the layout is documented with the crate source.
+#[wasm_bindgen]
+pub fn write_table_archive(
+    tables: JsValue,
+    options: JsValue,
+) -> std::result::Result<Uint8Array, JsValue> {
+    let options: ArchiveOptions = if options.is_undefined() || options.is_null() {
+        ArchiveOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let named = named_handles(&tables)?;
+    let mut seen = HashSet::new();
+    let mut entries = Vec::with_capacity(named.len());
+    for (name, handle) in named {
+        if !seen.insert(name.clone()) {
+            return Err(
+                ArrowWasmError::InvalidInput(format!("Duplicate table name '{name}'")).into(),
+            );
+        }
+        let lz4 = options
+            .tables
+            .get(&name)
+            .and_then(|table| table.enable_lz4)
+            .unwrap_or(options.enable_lz4);
+        entries.push((name, mem::get_table(handle)?, lz4));
+    }
+    for name in options.tables.keys() {
+        if !seen.contains(name) {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Options name table '{name}', which is not in the archive"
+            ))
+            .into());
+        }
+    }
+    Ok(Uint8Array::from(encode_archive(&entries)?.as_slice()))
+}
+
+/// Register decoded `tables` and build the `{tables, manifest}` result,
+/// recording each new handle in `stored` so a failure can release them.
+fn register_archive(
+    manifest: &Manifest,
+    tables: Vec<(String, TableData)>,
+    stored: &mut Vec<TableHandle>,
+) -> Result<JsValue> {
+    let handles = js_sys::Object::new();
+    for (name, table) in tables {
+        let handle = mem::store_table(table)?;
+        stored.push(handle);
+        set_property(&handles, &name, &handle.into())?;
+    }
+    let result = js_sys::Object::new();
+    set_property(&result, "tables", &handles)?;
+    set_property(
+        &result,
+        "manifest",
+        &serde_wasm_bindgen::to_value(manifest).map_err(ArrowWasmError::from)?,
+    )?;
+    Ok(result.into())
+}
+
+/// Read an archive written by `write_table_archive`.
+///
+/// Returns `{tables, manifest}`: `tables` maps each name to a new table
+/// handle and `manifest` is the archive's manifest object. Every checksum is
+/// verified before any table is registered; a mismatch fails naming the
+/// corrupted table, and so do duplicate names. When building the result
+/// fails, the tables registered so far are released.
+#[wasm_bindgen]
+pub fn read_table_archive(data: &[u8]) -> std::result::Result<JsValue, JsValue> {
+    let (manifest, tables) = decode_archive(data)?;
+    let mut stored = Vec::with_capacity(tables.len());
+    register_archive(&manifest, tables, &mut stored).map_err(|e| {
+        for handle in stored {
+            let _ = mem::remove_table(handle);
+        }
+        e.into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samples::sample_batches;
+
+    /// Sample tables of different schemas, the second LZ4 compressed.
+    fn tables() -> Vec<(String, TableData, bool)> {
+        ["basic", "nested", "views"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let table = TableData::new(sample_batches(name).unwrap()).unwrap();
+                (name.to_string(), table, i == 1)
+            })
+            .collect()
+    }
+
+    fn error_message(data: &[u8]) -> String {
+        match decode_archive(data) {
+            Err(ArrowWasmError::InvalidInput(message)) => message,
+            other => panic!(
+                "expected an invalid input error, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    /// Byte position of the stream of the manifest entry called `name`.
+    fn stream_offset(data: &[u8], name: &str) -> usize {
+        let (manifest, _) = decode_archive(data).unwrap();
+        let entry = manifest
+            .tables
+            .iter()
+            .find(|entry| entry.name == name)
+            .unwrap();
+        usize::try_from(entry.offset).unwrap()
+    }
+
+    #[test]
+    fn crc32_matches_the_ieee_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn mixed_schema_tables_round_trip() {
+        let tables = tables();
+        let (manifest, decoded) = decode_archive(&encode_archive(&tables).unwrap()).unwrap();
+        assert_eq!(manifest.version, ARCHIVE_VERSION);
+        for ((name, table, lz4), (entry, (decoded_name, decoded))) in
+            tables.iter().zip(manifest.tables.iter().zip(&decoded))
+        {
+            assert_eq!((&entry.name, decoded_name), (name, name));
+            assert_eq!(entry.rows, table.row_count() as u64);
+            assert_eq!(entry.compression.is_some(), *lz4);
+            assert_eq!(decoded.schema, table.schema, "{name}");
+            assert_eq!(decoded.batches, table.batches, "{name}");
+        }
+    }
+
+    #[test]
+    fn corrupted_stream_byte_names_the_table() {
+        let mut data = encode_archive(&tables()).unwrap();
+        let at = stream_offset(&data, "nested") + 100;
+        data[at] ^= 0x01;
+        let message = error_message(&data);
+        assert!(
+            message.starts_with("Corrupted table archive: table 'nested' failed its CRC32 check"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn corrupted_manifest_fails_its_checksum() {
+        let mut data = encode_archive(&tables()).unwrap();
+        data[HEADER_LEN + 2] ^= 0x20;
+        assert_eq!(
+            error_message(&data),
+            "Corrupted table archive: manifest checksum mismatch"
+        );
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut tables = tables();
+        tables[2].0 = "basic".to_string();
+        let message = error_message(&encode_archive(&tables).unwrap());
+        assert_eq!(
+            message,
+            "Corrupted table archive: duplicate table name 'basic'"
+        );
+    }
+
+    #[test]
+    fn truncated_archives_fail() {
+        let data = encode_archive(&tables()).unwrap();
+        assert_eq!(
+            error_message(b"not an archive"),
+            "Data is not a table archive"
+        );
+        assert_eq!(
+            error_message(&data[..HEADER_LEN + 4]),
+            "Corrupted table archive: truncated manifest"
+        );
+        let message = error_message(&data[..data.len() - 1]);
+        assert_eq!(
+            message,
+            "Corrupted table archive: table 'views' is out of bounds"
+        );
+    }
+}
@@ -0,0 +1,653 @@
+//! Incremental table construction from JS row objects.
+//!
+//! [`StreamingTableBuilder`] accepts rows in chunks pushed from a JS loop
+//! (an `IndexedDB` cursor, a `ReadableStream`, an async generator), so the
+//! rows never have to be collected in JS first. Only the batch being filled
+//! is held in builders; completed batches are regular record batches.
+//! [`from_async_iterable`] drives the loop for any async iterable.
+//!
+//! Each batch's builders are sized when the batch starts: for the rows
+//! announced with `reserve` or else those of the chunk being pushed, and
+//! for text and binary values from the `reserve` byte hints or else the
+//! average length in the chunk's first rows, so filling a batch rarely
+//! reallocates.
+
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::plain::{lossless_cast, transport_type, PlainSchema};
+use crate::validation::{js_string, InvalidUtf8};
+use arrow::array::{
+    ArrayRef, BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, RecordBatch,
+    RecordBatchOptions, StringBuilder, UInt64Builder,
+};
+use arrow::datatypes::{DataType, SchemaRef};
+use js_sys::{Reflect, Uint8Array};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Options for [`StreamingTableBuilder::create`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct BuilderOptions {
+    rows_per_batch: usize,
+    on_invalid_utf8: InvalidUtf8,
+}
+
+impl Default for BuilderOptions {
+    fn default() -> Self {
+        Self {
+            rows_per_batch: 65_536,
+            on_invalid_utf8: InvalidUtf8::default(),
+        }
+    }
+}
+
+/// Rows of a chunk sampled for the length of its text and binary values.
+const SAMPLE_ROWS: usize = 64;
+
+/// Bytes per text or binary value assumed when nothing was sampled.
+const DEFAULT_VALUE_BYTES: usize = 8;
+
+/// Average of the sampled value `lengths`, rounded up; `None` without any.
+fn average_bytes(lengths: impl Iterator<Item = usize>) -> Option<usize> {
+    let (count, total) = lengths.fold((0, 0), |(count, total), len| (count + 1, total + len));
+    (count > 0).then(|| total.div_ceil(count))
+}
+
+/// Byte length of `value` as a column of `transport` Utf8 or Binary stores
+/// it; `None` for other values and transports.
+fn value_bytes(transport: &DataType, value: &JsValue) -> Option<usize> {
+    match transport {
+        DataType::Utf8 => value.as_string().map(|text| text.len()),
+        DataType::Binary => value
+            .dyn_ref::<Uint8Array>()
+            .map(|bytes| bytes.length() as usize),
+        _ => None,
+    }
+}
+
+/// Typed builder for one column, chosen by the column's transport type;
+/// the finished array is cast to the column type.
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Number(Float64Builder),
+    Integer(Int64Builder),
+    Unsigned(UInt64Builder),
+    Text(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+/// JS value of one cell, checked against its column before anything is
+/// appended.
+enum Cell {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    Integer(i64),
+    Unsigned(u64),
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl ColumnBuilder {
+    /// Builder for `capacity` values, text and binary ones `value_bytes`
+    /// long on average.
+    fn new(transport: &DataType, capacity: usize, value_bytes: usize) -> Self {
+        let data = capacity.saturating_mul(value_bytes);
+        match transport {
+            DataType::Boolean => Self::Boolean(BooleanBuilder::with_capacity(capacity)),
+            DataType::UInt64 => Self::Unsigned(UInt64Builder::with_capacity(capacity)),
+            integer if integer.is_integer() => Self::Integer(Int64Builder::with_capacity(capacity)),
+            DataType::Utf8 => Self::Text(StringBuilder::with_capacity(capacity, data)),
+            DataType::Binary => Self::Binary(BinaryBuilder::with_capacity(capacity, data)),
+            _ => Self::Number(Float64Builder::with_capacity(capacity)),
+        }
+    }
+
+    /// Convert `value` for this builder without appending it.
+    fn cell(&self, value: &JsValue, policy: InvalidUtf8, row: usize) -> Result<Cell> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(Cell::Null);
+        }
+        let cell = match self {
+            Self::Boolean(_) => value.as_bool().map(Cell::Boolean),
+            Self::Number(_) => value.as_f64().map(Cell::Number),
+            Self::Integer(_) => integer_from_js(value)?.map(Cell::Integer),
+            Self::Unsigned(_) => integer_from_js(value)?.map(Cell::Unsigned),
+            Self::Text(_) => js_string(value, policy, row)?.map(Cell::Text),
+            Self::Binary(_) => value
+                .dyn_ref::<Uint8Array>()
+                .map(|bytes| Cell::Binary(bytes.to_vec())),
+        };
+        cell.ok_or_else(|| {
+            ArrowWasmError::InvalidInput(format!(
+                "Row {row}: expected {}",
+                match self {
+                    Self::Boolean(_) => "a boolean",
+                    Self::Number(_) => "a number",
+                    Self::Integer(_) | Self::Unsigned(_) => "an integer number or bigint",
+                    Self::Text(_) => "a string",
+                    Self::Binary(_) => "a Uint8Array",
+                }
+            ))
+        })
+    }
+
+    fn append(&mut self, cell: Cell) {
+        match (self, cell) {
+            (Self::Boolean(builder), Cell::Boolean(value)) => builder.append_value(value),
+            (Self::Number(builder), Cell::Number(value)) => builder.append_value(value),
+            (Self::Integer(builder), Cell::Integer(value)) => builder.append_value(value),
+            (Self::Unsigned(builder), Cell::Unsigned(value)) => builder.append_value(value),
+            (Self::Text(builder), Cell::Text(value)) => builder.append_value(value),
+            (Self::Binary(builder), Cell::Binary(value)) => builder.append_value(value),
+            (Self::Boolean(builder), _) => builder.append_null(),
+            (Self::Number(builder), _) => builder.append_null(),
+            (Self::Integer(builder), _) => builder.append_null(),
+            (Self::Unsigned(builder), _) => builder.append_null(),
+            (Self::Text(builder), _) => builder.append_null(),
+            (Self::Binary(builder), _) => builder.append_null(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Boolean(builder) => Arc::new(builder.finish()),
+            Self::Number(builder) => Arc::new(builder.finish()),
+            Self::Integer(builder) => Arc::new(builder.finish()),
+            Self::Unsigned(builder) => Arc::new(builder.finish()),
+            Self::Text(builder) => Arc::new(builder.finish()),
+            Self::Binary(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+/// A JS integer number or bigint as `i64` or `u64`, `None` for other
+/// values.
+///
+/// Bigints are parsed from their decimal text, so the whole 64-bit range
+/// (up to 2^64 - 1 for `u64`) is exact.
+fn integer_from_js<T: std::str::FromStr>(value: &JsValue) -> Result<Option<T>> {
+    if let Some(number) = value.as_f64() {
+        if number.fract() != 0.0 || number.abs() > 9_007_199_254_740_991.0 {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "{number} is not a safe integer; pass a bigint"
+            )));
+        }
+        return (number as i64).to_string().parse().map(Some).map_err(|_| {
+            ArrowWasmError::InvalidInput(format!("{number} is out of range for the column"))
+        });
+    }
+    let Some(big) = value.dyn_ref::<js_sys::BigInt>() else {
+        return Ok(None);
+    };
+    let text = big
+        .to_string(10)
+        .map(String::from)
+        .map_err(|_| ArrowWasmError::InvalidInput("Invalid bigint".to_string()))?;
+    text.parse()
+        .map(Some)
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("{text} is out of range for the column")))
+}
+
+/// Builds a table from row objects pushed in chunks.
+#[wasm_bindgen]
+pub struct StreamingTableBuilder {
+    schema: SchemaRef,
+    transports: Vec<DataType>,
+    rows_per_batch: usize,
+    policy: InvalidUtf8,
+    /// Builders of the batch being filled; `None` between batches.
+    builders: Option<Vec<ColumnBuilder>>,
+    /// Rows announced with `reserve` that have not been pushed yet.
+    reserved: usize,
+    /// Bytes per value given to `reserve`, by column.
+    byte_hints: Vec<Option<usize>>,
+    pending: usize,
+    batches: Vec<RecordBatch>,
+    rows: usize,
+}
+
+impl StreamingTableBuilder {
+    /// Builder for rows of `schema`; `op` names the caller in errors about
+    /// column types without a row representation.
+    fn for_schema(
+        schema: SchemaRef,
+        rows_per_batch: usize,
+        policy: InvalidUtf8,
+        op: &'static str,
+    ) -> Result<Self> {
+        let transports = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                transport_type(field.data_type()).ok_or_else(|| {
+                    ArrowWasmError::InvalidInput("type has no row representation".to_string())
+                        .in_column(op, field)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            byte_hints: vec![None; transports.len()],
+            schema,
+            transports,
+            rows_per_batch,
+            policy,
+            builders: None,
+            reserved: 0,
+            pending: 0,
+            batches: Vec::new(),
+            rows: 0,
+        })
+    }
+
+    /// Builders for a batch starting with `upcoming` rows: sized for the
+    /// reserved rows or else `upcoming`, up to a full batch, with value
+    /// lengths from the byte hints or else sampled from `upcoming`.
+    fn start_batch(&self, upcoming: &[JsValue]) -> Vec<ColumnBuilder> {
+        let capacity = self.reserved.max(upcoming.len()).min(self.rows_per_batch);
+        let sample = &upcoming[..upcoming.len().min(SAMPLE_ROWS)];
+        self.schema
+            .fields()
+            .iter()
+            .zip(&self.transports)
+            .zip(&self.byte_hints)
+            .map(|((field, transport), hint)| {
+                let sampled =
+                    || {
+                        if !matches!(transport, DataType::Utf8 | DataType::Binary) {
+                            return None;
+                        }
+                        let name = JsValue::from_str(field.name());
+                        average_bytes(sample.iter().filter_map(|row| {
+                            value_bytes(transport, &Reflect::get(row, &name).ok()?)
+                        }))
+                    };
+                let bytes = hint.or_else(sampled).unwrap_or(DEFAULT_VALUE_BYTES);
+                ColumnBuilder::new(transport, capacity, bytes)
+            })
+            .collect()
+    }
+
+    /// Append one row object, checking every field before appending any;
+    /// `upcoming` are the rows from this one on, for sizing a new batch.
+    fn push_row(&mut self, row: &JsValue, upcoming: &[JsValue]) -> Result<()> {
+        if !row.is_object() {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Row {} is not an object",
+                self.rows
+            )));
+        }
+        let builders = self
+            .builders
+            .take()
+            .unwrap_or_else(|| self.start_batch(upcoming));
+        let builders = self.builders.insert(builders);
+        let mut cells = Vec::with_capacity(builders.len());
+        for (field, builder) in self.schema.fields().iter().zip(builders.iter()) {
+            let value = Reflect::get(row, &field.name().into()).unwrap_or(JsValue::UNDEFINED);
+            let context = |e: ArrowWasmError| e.in_column("push_rows", field);
+            let cell = builder
+                .cell(&value, self.policy, self.rows)
+                .map_err(context)?;
+            if matches!(cell, Cell::Null) && !field.is_nullable() {
+                return Err(context(ArrowWasmError::InvalidInput(format!(
+                    "Row {}: null in a non-nullable column",
+                    self.rows
+                ))));
+            }
+            cells.push(cell);
+        }
+        for (builder, cell) in builders.iter_mut().zip(cells) {
+            builder.append(cell);
+        }
+        self.pending += 1;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Record `hints` once all of them are known columns whose full batch
+    /// of values fits in `limit` bytes.
+    fn set_byte_hints(&mut self, hints: HashMap<String, usize>, limit: u64) -> Result<()> {
+        let mut indices = Vec::with_capacity(hints.len());
+        for (name, bytes) in hints {
+            let index = self
+                .schema
+                .index_of(&name)
+                .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+            let estimate = (bytes as u64).saturating_mul(self.rows_per_batch as u64);
+            if estimate > limit {
+                return Err(ArrowWasmError::TooLarge {
+                    estimate,
+                    limit,
+                    hint: format!(
+                        "byte hint {bytes} for column '{name}' over batches of {} rows; lower the hint or raise the limit with set_memory_limit",
+                        self.rows_per_batch
+                    ),
+                });
+            }
+            indices.push((index, bytes));
+        }
+        for (index, bytes) in indices {
+            self.byte_hints[index] = Some(bytes);
+        }
+        Ok(())
+    }
+
+    /// Finish the builders of the current batch into a record batch.
+    fn seal(&mut self) -> Result<()> {
+        let Some(mut builders) = self.builders.take() else {
+            return Ok(());
+        };
+        let mut columns = Vec::with_capacity(builders.len());
+        for (builder, field) in builders.iter_mut().zip(self.schema.fields()) {
+            let array = lossless_cast(&builder.finish(), field.data_type())
+                .map_err(|e| e.in_column("push_rows", field))?;
+            columns.push(array);
+        }
+        let options = RecordBatchOptions::new().with_row_count(Some(self.pending));
+        self.batches.push(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            columns,
+            &options,
+        )?);
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl StreamingTableBuilder {
+    /// Start a table with `schema`, a descriptor like the one
+    /// `to_plain_object` produces: `{fields: [{name, type, nullable,
+    /// metadata?}], metadata?}`.
+    ///
+    /// `options` is `{rowsPerBatch?, onInvalidUtf8?}`; a batch is sealed
+    /// every `rowsPerBatch` rows (65536 by default). Columns of nested types
+    /// are not supported.
+    pub fn create(schema: JsValue, options: JsValue) -> std::result::Result<Self, JsValue> {
+        let schema: PlainSchema =
+            serde_wasm_bindgen::from_value(schema).map_err(ArrowWasmError::from)?;
+        let options: BuilderOptions = if options.is_undefined() || options.is_null() {
+            BuilderOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+        };
+        if options.rows_per_batch == 0 {
+            return Err(ArrowWasmError::InvalidInput(
+                "rowsPerBatch must be at least 1".to_string(),
+            )
+            .into());
+        }
+        Ok(Self::for_schema(
+            Arc::new(schema.into_schema()?),
+            options.rows_per_batch,
+            options.on_invalid_utf8,
+            "create",
+        )?)
+    }
+
+    /// Append a chunk of row objects.
+    ///
+    /// Each row maps column names to values: booleans, numbers, integer
+    /// numbers or bigints for integer and temporal columns (in the column's
+    /// stored unit; `UInt64` columns take bigints up to 2^64 - 1), strings
+    /// for text and decimal columns, and `Uint8Array`s for binary ones.
+    /// Missing properties, `null` and `undefined` are nulls. A row that
+    /// does not fit fails with its row number; the rows before it stay
+    /// appended.
+    pub fn push_rows(&mut self, rows: Vec<JsValue>) -> std::result::Result<(), JsValue> {
+        for (index, row) in rows.iter().enumerate() {
+            self.push_row(row, &rows[index..])?;
+            self.reserved = self.reserved.saturating_sub(1);
+            if self.pending == self.rows_per_batch {
+                self.seal()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Announce `additional_rows` rows about to be pushed, so batches
+    /// started from now on are sized for them rather than for each chunk.
+    ///
+    /// `byte_hints` is an optional `{column: bytes}` object giving the
+    /// average byte length of a text or binary column's values (UTF-8 for
+    /// text); columns without a hint are sampled from the rows as they
+    /// arrive. A batch already being filled keeps its builders. Calling
+    /// `reserve` again replaces the announced rows; hints accumulate. A hint
+    /// whose full batch of values would not fit in the memory limit (see
+    /// `set_memory_limit`) fails, and then none of the hints are kept.
+    pub fn reserve(
+        &mut self,
+        additional_rows: usize,
+        byte_hints: JsValue,
+    ) -> std::result::Result<(), JsValue> {
+        if !byte_hints.is_undefined() && !byte_hints.is_null() {
+            let hints: HashMap<String, usize> =
+                serde_wasm_bindgen::from_value(byte_hints).map_err(ArrowWasmError::from)?;
+            self.set_byte_hints(hints, mem::memory_limit())?;
+        }
+        self.reserved = additional_rows;
+        Ok(())
+    }
+
+    /// End the current batch early, e.g. at a natural boundary of the
+    /// source. Does nothing when no rows are pending.
+    pub fn seal_batch(&mut self) -> std::result::Result<(), JsValue> {
+        Ok(self.seal()?)
+    }
+
+    /// Rows appended so far.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn row_count(&self) -> usize {
+        self.rows
+    }
+
+    /// Batches completed so far, not counting pending rows.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn batch_count(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Seal pending rows and register the table. The builder cannot be used
+    /// afterwards.
+    pub fn finish(mut self) -> std::result::Result<TableHandle, JsValue> {
+        self.seal()?;
+        if self.batches.is_empty() {
+            self.batches
+                .push(RecordBatch::new_empty(Arc::clone(&self.schema)));
+        }
+        Ok(mem::store_table(TableData::new(self.batches)?)?)
+    }
+}
+
+#[wasm_bindgen(inline_js = r"
+export async function drive_async_iterable(builder, iterable, chunkSize) {
+    let chunk = [];
+    for await (const row of iterable) {
+        chunk.push(row);
+        if (chunk.length >= chunkSize) {
+            builder.push_rows(chunk);
+            chunk = [];
+        }
+    }
+    if (chunk.length > 0) {
+        builder.push_rows(chunk);
+    }
+    return builder.finish();
+}
+")]
+extern "C" {
+    fn drive_async_iterable(
+        builder: StreamingTableBuilder,
+        iterable: &JsValue,
+        chunk_size: usize,
+    ) -> js_sys::Promise;
+}
+
+/// Build a table from an async iterable of row objects, such as an async
+/// generator or a `ReadableStream`.
+///
+/// `schema` and `options` are as for `StreamingTableBuilder.create`; rows
+/// are pushed in chunks of `rowsPerBatch`. Returns a promise of the table
+/// handle, rejected with the first error of the source or of `push_rows`.
+#[wasm_bindgen]
+pub fn from_async_iterable(
+    schema: JsValue,
+    iterable: JsValue,
+    options: JsValue,
+) -> std::result::Result<js_sys::Promise, JsValue> {
+    let builder = StreamingTableBuilder::create(schema, options)?;
+    let chunk_size = builder.rows_per_batch;
+    Ok(drive_async_iterable(builder, &iterable, chunk_size))
+}
+
+/// Append row objects to a table as one new batch, giving a new table.
+///
+/// Rows follow the table's schema and are converted like
+/// `StreamingTableBuilder.push_rows` converts them: missing properties are
+/// null, and a value that does not fit its column fails with its row
+/// number, leaving no new table behind. The existing batches are shared
+/// with the source table.
+#[wasm_bindgen]
+pub fn append_rows(
+    handle: TableHandle,
+    rows: Vec<JsValue>,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let mut builder = StreamingTableBuilder::for_schema(
+        Arc::clone(&table.schema),
+        rows.len().max(1),
+        InvalidUtf8::default(),
+        "append_rows",
+    )?;
+    builder.push_rows(rows)?;
+    builder.seal()?;
+    let mut batches = table.batches;
+    batches.extend(builder.batches);
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+
+    /// Fill a `transport` builder sized for `capacity` values of
+    /// `value_bytes` bytes with `cells`.
+    fn fill(transport: &DataType, capacity: usize, value_bytes: usize, cells: &[Cell]) -> ArrayRef {
+        let mut builder = ColumnBuilder::new(transport, capacity, value_bytes);
+        for cell in cells {
+            builder.append(match cell {
+                Cell::Text(text) => Cell::Text(text.clone()),
+                Cell::Binary(bytes) => Cell::Binary(bytes.clone()),
+                Cell::Integer(value) => Cell::Integer(*value),
+                _ => Cell::Null,
+            });
+        }
+        builder.finish()
+    }
+
+    fn words(rows: usize) -> Vec<Cell> {
+        (0..rows)
+            .map(|i| match i % 5 {
+                4 => Cell::Null,
+                _ => Cell::Text(format!("row {i:08}: {}", "x".repeat(i % 13))),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sampled_lengths_round_up() {
+        assert_eq!(average_bytes(std::iter::empty()), None);
+        assert_eq!(average_bytes([0].into_iter()), Some(0));
+        assert_eq!(average_bytes([3, 4].into_iter()), Some(4));
+        assert_eq!(average_bytes([16, 16, 16].into_iter()), Some(16));
+    }
+
+    #[test]
+    fn sizing_does_not_change_the_values() {
+        let cells = words(1_000);
+        let text = fill(&DataType::Utf8, 1_000, 24, &cells);
+        for (capacity, value_bytes) in [(0, 0), (1, DEFAULT_VALUE_BYTES), (10_000, 100)] {
+            assert_eq!(&fill(&DataType::Utf8, capacity, value_bytes, &cells), &text);
+        }
+        let bytes: Vec<Cell> = (0..100_u8).map(|i| Cell::Binary(vec![i; 3])).collect();
+        let binary = fill(&DataType::Binary, 100, 3, &bytes);
+        assert_eq!(&fill(&DataType::Binary, 0, 0, &bytes), &binary);
+        let integers: Vec<Cell> = (0..100).map(Cell::Integer).collect();
+        assert_eq!(
+            &fill(&DataType::Int64, 100, 0, &integers),
+            &fill(&DataType::Int64, 0, 0, &integers)
+        );
+    }
+
+    #[test]
+    fn sampled_sizes_fill_without_regrowing() {
+        let cells = words(1_000);
+        let lengths = cells.iter().filter_map(|cell| match cell {
+            Cell::Text(text) => Some(text.len()),
+            _ => None,
+        });
+        let value_bytes = average_bytes(lengths).unwrap();
+        let values_capacity = |array: &ArrayRef| {
+            let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+            (array.values().len(), array.values().capacity())
+        };
+
+        let (used, sized) = values_capacity(&fill(&DataType::Utf8, 1_000, value_bytes, &cells));
+        // The values buffer is the one reserved up front.
+        assert_eq!(sized, 1_000 * value_bytes);
+        assert!(used <= sized);
+        // Eight bytes a value is too little here, so that buffer regrew.
+        let (_, default) =
+            values_capacity(&fill(&DataType::Utf8, 1_000, DEFAULT_VALUE_BYTES, &cells));
+        assert_ne!(default, 1_000 * DEFAULT_VALUE_BYTES);
+    }
+
+    #[test]
+    fn byte_hints_past_the_memory_limit_are_rejected() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("text", DataType::Utf8, true),
+            Field::new("blob", DataType::Binary, true),
+        ]));
+        let mut builder =
+            StreamingTableBuilder::for_schema(schema, 1_000, InvalidUtf8::default(), "create")
+                .unwrap();
+        let hints = |pairs: &[(&str, usize)]| {
+            pairs
+                .iter()
+                .map(|&(name, bytes)| (name.to_string(), bytes))
+                .collect::<HashMap<_, _>>()
+        };
+
+        builder
+            .set_byte_hints(hints(&[("text", 1_000)]), 1_000_000)
+            .unwrap();
+        assert_eq!(builder.byte_hints, [Some(1_000), None]);
+
+        let Err(ArrowWasmError::TooLarge {
+            estimate,
+            limit,
+            hint,
+        }) = builder.set_byte_hints(hints(&[("text", 24), ("blob", usize::MAX)]), 1_000_000)
+        else {
+            panic!("a byte hint of usize::MAX was accepted");
+        };
+        assert_eq!((estimate, limit), (u64::MAX, 1_000_000));
+        assert!(hint.contains("column 'blob'"), "{hint}");
+        // The hint that fit was not kept either.
+        assert_eq!(builder.byte_hints, [Some(1_000), None]);
+
+        let Err(error) = builder.set_byte_hints(hints(&[("text", 1_001)]), 1_000_000) else {
+            panic!("a batch over the limit was accepted");
+        };
+        assert!(error.to_string().contains("1001000 bytes"), "{error}");
+    }
+}
@@ -0,0 +1,616 @@
+//! Column views over registered tables.
+//!
+//! A [`Column`] is just a table handle plus a column index; its data stays in
+//! the registry. Kernels that produce a new column register it as a
+//! single-column table and hand back a `Column` pointing at that table, so
+//! the result is freed with `free_table(column.handle)`.
+
+use crate::compute::capability::{require, Operation};
+use crate::compute::keys::column_keys;
+use crate::compute::{binning, distinct, run_end, shift, stats};
+use crate::convert::{check_field, set_property, table_options, value_to_js};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use arrow::array::{ArrayRef, AsArray, RecordBatch};
+use arrow::datatypes::{DataType, FieldRef, Schema};
+use arrow_cast::base64::{b64_encode, BASE64_STANDARD};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Whether `Column.get` throws on out-of-range indices.
+static STRICT_INDEXING: AtomicBool = AtomicBool::new(false);
+
+/// A column of a registered table.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    handle: TableHandle,
+    index: usize,
+}
+
+impl Column {
+    /// Resolve the owning table.
+    pub fn table(&self) -> Result<TableData> {
+        let table = mem::get_table(self.handle)?;
+        if self.index >= table.column_count() {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Column index {} out of bounds for table with {} columns",
+                self.index,
+                table.column_count()
+            )));
+        }
+        Ok(table)
+    }
+
+    /// Field describing this column.
+    pub fn field(&self) -> Result<FieldRef> {
+        Ok(Arc::clone(&self.table()?.schema.fields()[self.index]))
+    }
+
+    /// One-element slice of the value at global row `index`.
+    pub fn slice_at(chunks: &[ArrayRef], index: usize) -> Result<ArrayRef> {
+        let (chunk, offset) = locate(chunks, index).ok_or_else(|| {
+            ArrowWasmError::InvalidInput(format!("Row index {index} out of bounds"))
+        })?;
+        Ok(chunks[chunk].slice(offset, 1))
+    }
+
+    /// Field plus one array per batch of the owning table.
+    pub fn field_and_chunks(&self) -> Result<(FieldRef, Vec<ArrayRef>)> {
+        let table = self.table()?;
+        let field = Arc::clone(&table.schema.fields()[self.index]);
+        let chunks = table
+            .batches
+            .iter()
+            .map(|batch| Arc::clone(batch.column(self.index)))
+            .collect();
+        Ok((field, chunks))
+    }
+}
+
+#[wasm_bindgen]
+impl Column {
+    /// Handle of the table this column belongs to.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn handle(&self) -> TableHandle {
+        self.handle
+    }
+
+    /// Position of the column within its table.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Column name.
+    pub fn name(&self) -> std::result::Result<String, JsValue> {
+        Ok(self.field()?.name().clone())
+    }
+
+    /// Arrow data type, formatted like the schema JSON.
+    pub fn data_type(&self) -> std::result::Result<String, JsValue> {
+        Ok(format!("{:?}", self.field()?.data_type()))
+    }
+
+    /// Time zone of a Timestamp column (e.g. `"UTC"`, `"+09:00"`), or
+    /// `null` for naive timestamps and other types. Converted values are
+    /// instants either way; the zone says how to display them.
+    pub fn timezone(&self) -> std::result::Result<Option<String>, JsValue> {
+        Ok(match self.field()?.data_type() {
+            DataType::Timestamp(_, time_zone) => time_zone.as_deref().map(str::to_string),
+            _ => None,
+        })
+    }
+
+    /// Key/value metadata of the column's field (units, descriptions, ...)
+    /// as a plain object; empty when the field has none.
+    pub fn metadata(&self) -> std::result::Result<js_sys::Object, JsValue> {
+        let field = self.field()?;
+        let result = js_sys::Object::new();
+        for (key, value) in field.metadata() {
+            set_property(&result, key, &JsValue::from_str(value))?;
+        }
+        Ok(result)
+    }
+
+    /// Number of values across all batches.
+    pub fn length(&self) -> std::result::Result<usize, JsValue> {
+        let (_, chunks) = self.field_and_chunks()?;
+        Ok(chunks.iter().map(|chunk| chunk.len()).sum())
+    }
+
+    /// Number of null values across all batches.
+    pub fn null_count(&self) -> std::result::Result<usize, JsValue> {
+        let (_, chunks) = self.field_and_chunks()?;
+        Ok(chunks.iter().map(|chunk| chunk.null_count()).sum())
+    }
+
+    /// Number of `true` values of a Boolean column; nulls are not counted.
+    pub fn true_count(&self) -> std::result::Result<usize, JsValue> {
+        Ok(self.truth_counts()?.0)
+    }
+
+    /// Number of `false` values of a Boolean column; nulls are not counted.
+    pub fn false_count(&self) -> std::result::Result<usize, JsValue> {
+        Ok(self.truth_counts()?.1)
+    }
+
+    /// Share of the non-null values of a Boolean column that are `true`, or
+    /// `null` when every value is null.
+    pub fn true_ratio(&self) -> std::result::Result<Option<f64>, JsValue> {
+        let (trues, falses) = self.truth_counts()?;
+        Ok((trues + falses > 0).then(|| trues as f64 / (trues + falses) as f64))
+    }
+
+    /// The `q` quantile (`0` to `1`) of the non-null values of a numeric
+    /// column, interpolating linearly between neighbouring values like
+    /// `column_median`, which is `quantile(0.5)`; `null` for empty and
+    /// all-null columns.
+    pub fn quantile(&self, q: f64) -> std::result::Result<Option<f64>, JsValue> {
+        Ok(stats::quantile(self, q)?)
+    }
+
+    /// Several quantiles at once, e.g. `quantiles([0.5, 0.9, 0.99])`: one
+    /// value per `q`, in the order given, computed like `quantile` from a
+    /// single sort; all `null` for empty and all-null columns.
+    pub fn quantiles(&self, qs: Vec<f64>) -> std::result::Result<js_sys::Array, JsValue> {
+        Ok(stats::quantiles(self, &qs)?
+            .into_iter()
+            .map(|value| value.map_or(JsValue::NULL, JsValue::from_f64))
+            .collect())
+    }
+
+    /// Equal-width histogram of the non-null values of a numeric column in
+    /// `bins` buckets, `{counts, bin_edges, min, max}`, e.g. to render a
+    /// chart; the last bucket includes the maximum. `null` when the column
+    /// has no finite values.
+    pub fn histogram(&self, bins: usize) -> std::result::Result<JsValue, JsValue> {
+        Ok(stats::histogram(self, bins)?)
+    }
+
+    /// Estimated number of distinct non-null values (`HyperLogLog`), for
+    /// profiling columns too large to count exactly.
+    ///
+    /// `options` is `{precision?}`, 4 to 18 (default 14); the relative
+    /// standard error is `1.04 / sqrt(2^precision)`, 0.81% by default.
+    pub fn approx_count_distinct(&self, options: JsValue) -> std::result::Result<f64, JsValue> {
+        Ok(distinct::approx_count_distinct(self, options)?)
+    }
+
+    /// The most frequent non-null values as `{values, counts, approximate}`.
+    ///
+    /// `options` is `{topN?, maxExactDistinct?}` (defaults 1 and 100 000);
+    /// counts are estimates, flagged by `approximate`, once the column has
+    /// more than `maxExactDistinct` distinct values.
+    pub fn mode(&self, options: JsValue) -> std::result::Result<JsValue, JsValue> {
+        Ok(distinct::mode(self, options)?)
+    }
+
+    /// Run-end encode the column as a new `RunEndEncoded` column, like
+    /// `encode_run_ends`.
+    ///
+    /// `run_end_type` is `"Int16"`, `"Int32"` (the default) or `"Int64"`;
+    /// values keep the column's type, so `decode_run_length` restores the
+    /// column exactly.
+    pub fn encode_run_length(
+        &self,
+        run_end_type: Option<String>,
+    ) -> std::result::Result<Self, JsValue> {
+        run_end::encode_run_ends(self, run_end_type)
+    }
+
+    /// Expand a `RunEndEncoded` column to a plain one, like `decode_run_ends`.
+    pub fn decode_run_length(&self) -> std::result::Result<Self, JsValue> {
+        run_end::decode_run_ends(self)
+    }
+
+    /// Difference between each value and the one `periods` rows earlier,
+    /// like `diff`: `1` by default, negative compares with later rows.
+    pub fn diff(&self, periods: Option<i32>) -> std::result::Result<Self, JsValue> {
+        shift::diff(self, periods)
+    }
+
+    /// Assign every value to a bin between `edges`, like `cut`: an `Int32`
+    /// column of bin indices, or `Utf8` of `labels` when given, with null
+    /// for values outside the edges.
+    pub fn cut(
+        &self,
+        edges: JsValue,
+        labels: JsValue,
+        options: JsValue,
+    ) -> std::result::Result<Self, JsValue> {
+        binning::cut(self, edges, labels, options)
+    }
+
+    /// Number of null values among rows `[offset, offset + length)`, e.g.
+    /// for sliding data-quality checks.
+    ///
+    /// Counts bits of the validity buffers directly, without slicing the
+    /// column. A window reaching past the end is clamped to it, and one
+    /// starting past the end counts 0.
+    pub fn null_count_range(
+        &self,
+        offset: usize,
+        length: usize,
+    ) -> std::result::Result<usize, JsValue> {
+        let (_, chunks) = self.field_and_chunks()?;
+        let end = offset.saturating_add(length);
+        let mut start = 0;
+        let mut nulls = 0;
+        for chunk in &chunks {
+            let (from, to) = (offset.max(start), end.min(start + chunk.len()));
+            if from < to {
+                nulls += chunk.nulls().map_or(0, |validity| {
+                    validity.slice(from - start, to - from).null_count()
+                });
+            }
+            start += chunk.len();
+        }
+        Ok(nulls)
+    }
+
+    /// Boolean chunks of the column; other types fail.
+    fn boolean_chunks(&self) -> Result<Vec<ArrayRef>> {
+        let (field, chunks) = self.field_and_chunks()?;
+        require(Operation::BooleanCounts, &field)?;
+        Ok(chunks)
+    }
+
+    /// `(true, false)` counts of a Boolean column, ignoring nulls.
+    fn truth_counts(&self) -> Result<(usize, usize)> {
+        Ok(self
+            .boolean_chunks()?
+            .iter()
+            .map(|chunk| {
+                let trues = chunk.as_boolean().true_count();
+                (trues, chunk.len() - chunk.null_count() - trues)
+            })
+            .fold((0, 0), |(trues, falses), (t, f)| (trues + t, falses + f)))
+    }
+
+    /// Whether rows `i` and `j` hold equal values, treating two nulls as equal.
+    ///
+    /// Values compare by type: integers by value, floats by value with all
+    /// NaNs equal to each other and `-0.0 == 0.0`, strings and binaries
+    /// bytewise, dictionaries by their decoded values. A null never equals a
+    /// non-null. These are the semantics deduplication uses for keys.
+    pub fn values_equal(&self, i: usize, j: usize) -> std::result::Result<bool, JsValue> {
+        let (_, chunks) = self.field_and_chunks()?;
+        let rows = [Self::slice_at(&chunks, i)?, Self::slice_at(&chunks, j)?];
+        let keys = column_keys(&rows)?;
+        Ok(keys[0] == keys[1])
+    }
+
+    /// Value at row `index` converted to JS (see `conversionTable()`), or
+    /// `undefined` when `index` is out of range. Out-of-range indices throw
+    /// instead after `set_strict_indexing(true)`. A value that cannot be
+    /// converted throws as well, so a returned string is always data.
+    ///
+    /// Values follow the table's `ConversionPolicy`, with the fields of
+    /// `overrides` (e.g. `{temporalAs: "number"}`) replacing its own.
+    pub fn get(&self, index: usize, overrides: JsValue) -> std::result::Result<JsValue, JsValue> {
+        let (field, chunks) = self.field_and_chunks()?;
+        let options = table_options(self.handle).with_overrides(overrides)?;
+        check_field(&field, options).map_err(|e| e.in_column("get", &field))?;
+        let Some((chunk, offset)) = locate(&chunks, index) else {
+            if STRICT_INDEXING.load(Ordering::Relaxed) {
+                let length: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Row index {index} out of bounds for column of length {length}"
+                ))
+                .in_column("get", &field)
+                .into());
+            }
+            return Ok(JsValue::UNDEFINED);
+        };
+        Ok(value_to_js(chunks[chunk].as_ref(), offset, options)
+            .map_err(|e| e.in_column("get", &field))?)
+    }
+
+    /// Every value converted to JS, in row order, under the same policy and
+    /// `overrides` as `get`; throws like `get` when a value cannot be
+    /// converted.
+    pub fn to_array(&self, overrides: JsValue) -> std::result::Result<js_sys::Array, JsValue> {
+        let (field, chunks) = self.field_and_chunks()?;
+        let options = table_options(self.handle).with_overrides(overrides)?;
+        check_field(&field, options).map_err(|e| e.in_column("to_array", &field))?;
+        let result = js_sys::Array::new();
+        for chunk in &chunks {
+            for i in 0..chunk.len() {
+                let value = value_to_js(chunk.as_ref(), i, options)
+                    .map_err(|e| e.in_column("to_array", &field))?;
+                result.push(&value);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Like `toArray`, but skipping nulls: only the valid values, in row
+    /// order.
+    pub fn to_array_compact(
+        &self,
+        overrides: JsValue,
+    ) -> std::result::Result<js_sys::Array, JsValue> {
+        let (field, chunks) = self.field_and_chunks()?;
+        let options = table_options(self.handle).with_overrides(overrides)?;
+        check_field(&field, options).map_err(|e| e.in_column("to_array_compact", &field))?;
+        let result = js_sys::Array::new();
+        for chunk in &chunks {
+            let nulls = chunk.logical_nulls();
+            for i in 0..chunk.len() {
+                if nulls.as_ref().is_some_and(|nulls| nulls.is_null(i)) {
+                    continue;
+                }
+                let value = value_to_js(chunk.as_ref(), i, options)
+                    .map_err(|e| e.in_column("to_array_compact", &field))?;
+                result.push(&value);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Every value of a `Binary` or `LargeBinary` column as a standard, padded
+    /// base64 string, in row order, with `null` for null entries; e.g. to
+    /// embed small binary or geometry columns in JSON. Other types fail.
+    pub fn to_base64_array(&self) -> std::result::Result<js_sys::Array, JsValue> {
+        let (field, chunks) = self.field_and_chunks()?;
+        require(Operation::Base64, &field)?;
+        let result = js_sys::Array::new();
+        for chunk in &chunks {
+            let encoded: Vec<Option<String>> = match chunk.data_type() {
+                DataType::Binary => b64_encode(&BASE64_STANDARD, chunk.as_binary::<i32>())
+                    .iter()
+                    .map(|value| value.map(str::to_string))
+                    .collect(),
+                DataType::LargeBinary => b64_encode(&BASE64_STANDARD, chunk.as_binary::<i64>())
+                    .iter()
+                    .map(|value| value.map(str::to_string))
+                    .collect(),
+                other => {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "expected a Binary or LargeBinary column, got {other:?}"
+                    ))
+                    .in_column("to_base64_array", &field)
+                    .into())
+                }
+            };
+            for value in encoded {
+                result.push(&value.map_or(JsValue::NULL, |value| JsValue::from_str(&value)));
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Find the chunk holding global row `index` and the offset within it.
+pub fn locate(chunks: &[ArrayRef], mut index: usize) -> Option<(usize, usize)> {
+    for (chunk_index, chunk) in chunks.iter().enumerate() {
+        if index < chunk.len() {
+            return Some((chunk_index, index));
+        }
+        index -= chunk.len();
+    }
+    None
+}
+
+/// Register `chunks` as a single-column table and return a view of it.
+///
+/// `field` is made nullable when `chunks` hold nulls, so kernels pass the
+/// nullability they inherit (usually the source column's) and a result is
+/// only non-nullable when it really holds no nulls.
+pub fn store_column(field: FieldRef, chunks: Vec<ArrayRef>) -> Result<Column> {
+    let field = if !field.is_nullable() && chunks.iter().any(|chunk| chunk.logical_null_count() > 0)
+    {
+        Arc::new(field.as_ref().clone().with_nullable(true))
+    } else {
+        field
+    };
+    let schema = Arc::new(Schema::new(vec![field]));
+    let batches = if chunks.is_empty() {
+        vec![RecordBatch::new_empty(Arc::clone(&schema))]
+    } else {
+        chunks
+            .into_iter()
+            .map(|chunk| RecordBatch::try_new(Arc::clone(&schema), vec![chunk]))
+            .collect::<std::result::Result<Vec<_>, _>>()?
+    };
+    let handle = mem::store_table(TableData::new(batches)?)?;
+    Ok(Column { handle, index: 0 })
+}
+
+/// Make `Column.get` throw on out-of-range indices instead of returning
+/// `undefined`, so indexing bugs surface where they happen. Off by default;
+/// the setting is global to the module instance.
+#[wasm_bindgen]
+pub fn set_strict_indexing(strict: bool) {
+    STRICT_INDEXING.store(strict, Ordering::Relaxed);
+}
+
+/// Look up a column by name.
+#[wasm_bindgen]
+pub fn get_column(handle: TableHandle, name: &str) -> std::result::Result<Column, JsValue> {
+    let table = mem::get_table(handle)?;
+    let index = table
+        .schema
+        .index_of(name)
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+    Ok(Column { handle, index })
+}
+
+/// Look up a column by position.
+#[wasm_bindgen]
+pub fn get_column_at(handle: TableHandle, index: usize) -> std::result::Result<Column, JsValue> {
+    let column = Column { handle, index };
+    column.table()?;
+    Ok(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BooleanArray, DictionaryArray, Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Int8Type};
+
+    fn stored(chunks: Vec<ArrayRef>) -> Column {
+        let field = Field::new("x", chunks[0].data_type().clone(), true);
+        store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    #[test]
+    fn equal_values_compare_equal_across_batches() {
+        let column = stored(vec![
+            Arc::new(Int32Array::from(vec![Some(7), None, Some(3)])),
+            Arc::new(Int32Array::from(vec![Some(3), None, Some(7)])),
+        ]);
+        assert!(column.values_equal(0, 5).unwrap());
+        assert!(column.values_equal(2, 3).unwrap());
+        assert!(column.values_equal(4, 4).unwrap());
+    }
+
+    #[test]
+    fn unequal_values_and_null_against_value_differ() {
+        let column = stored(vec![Arc::new(Int32Array::from(vec![
+            Some(7),
+            Some(8),
+            None,
+            Some(0),
+        ]))]);
+        assert!(!column.values_equal(0, 1).unwrap());
+        assert!(!column.values_equal(2, 3).unwrap());
+        assert!(!column.values_equal(3, 2).unwrap());
+    }
+
+    #[test]
+    fn two_nulls_are_equal() {
+        let column = stored(vec![
+            Arc::new(StringArray::from(vec![None, Some("a")])),
+            Arc::new(StringArray::from(vec![Some("a"), None])),
+        ]);
+        assert!(column.values_equal(0, 3).unwrap());
+        assert!(column.values_equal(1, 2).unwrap());
+        assert!(!column.values_equal(0, 1).unwrap());
+    }
+
+    #[test]
+    fn floats_and_dictionaries_compare_by_value() {
+        let floats = stored(vec![Arc::new(Float64Array::from(vec![
+            f64::NAN,
+            -f64::NAN,
+            0.0,
+            -0.0,
+            1.5,
+        ]))]);
+        assert!(floats.values_equal(0, 1).unwrap());
+        assert!(floats.values_equal(2, 3).unwrap());
+        assert!(!floats.values_equal(3, 4).unwrap());
+
+        // "b" appears under two different keys.
+        let dictionary = DictionaryArray::<Int8Type>::new(
+            vec![0_i8, 1, 2].into(),
+            Arc::new(StringArray::from(vec!["b", "c", "b"])),
+        );
+        let column = stored(vec![Arc::new(dictionary)]);
+        assert!(column.values_equal(0, 2).unwrap());
+        assert!(!column.values_equal(0, 1).unwrap());
+    }
+
+    #[test]
+    fn null_count_range_matches_a_manual_count() {
+        let values: Vec<Option<i32>> = (0..40)
+            .map(|i| (i % 3 != 0 && i % 7 != 2).then_some(i))
+            .collect();
+        // A sliced chunk, so its validity bits start mid-byte, and one chunk
+        // without a validity buffer.
+        let sliced = Int32Array::from(values[..13].to_vec()).slice(3, 10);
+        let column = stored(vec![
+            Arc::new(Int32Array::from(values[..3].to_vec())),
+            Arc::new(sliced),
+            Arc::new(Int32Array::from_iter_values(13..20)),
+            Arc::new(Int32Array::from(values[20..].to_vec())),
+        ]);
+        let mut rows = values;
+        for (row, value) in rows[13..20].iter_mut().zip(13..) {
+            *row = Some(value);
+        }
+        for offset in 0..=42 {
+            for length in [0, 1, 5, 11, 40, usize::MAX] {
+                let expected = rows
+                    .iter()
+                    .skip(offset)
+                    .take(length)
+                    .filter(|value| value.is_none())
+                    .count();
+                let counted = column.null_count_range(offset, length).unwrap();
+                assert_eq!(counted, expected, "offset {offset}, length {length}");
+            }
+        }
+        assert_eq!(
+            column.null_count_range(0, 40).unwrap(),
+            column.null_count().unwrap()
+        );
+    }
+
+    #[test]
+    fn truth_counts_skip_nulls() {
+        // The second chunk's null slots hold `true` bits under the mask.
+        let masked = BooleanArray::new(
+            vec![true, true, false, true].into(),
+            Some(vec![true, false, true, false].into()),
+        );
+        let column = stored(vec![
+            Arc::new(BooleanArray::from(vec![
+                Some(true),
+                None,
+                Some(false),
+                Some(true),
+            ])),
+            Arc::new(masked),
+            Arc::new(BooleanArray::from(vec![Some(false)]).slice(0, 1)),
+        ]);
+        assert_eq!(column.true_count().unwrap(), 3);
+        assert_eq!(column.false_count().unwrap(), 3);
+        assert_eq!(column.true_ratio().unwrap(), Some(0.5));
+        assert_eq!(column.null_count().unwrap(), 3);
+
+        let nulls = stored(vec![Arc::new(BooleanArray::from(vec![None, None]))]);
+        assert_eq!(nulls.true_count().unwrap(), 0);
+        assert_eq!(nulls.false_count().unwrap(), 0);
+        assert_eq!(nulls.true_ratio().unwrap(), None);
+        let message = stored(vec![Arc::new(Int32Array::from(vec![1]))])
+            .truth_counts()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            message,
+            "booleanCounts does not support column 'x' of type Int32"
+        );
+    }
+
+    #[test]
+    fn stored_fields_are_nullable_only_when_nulls_appear() {
+        let nullable = |declared: bool, chunks: Vec<ArrayRef>| {
+            let field = Field::new("x", DataType::Int32, declared);
+            let column = store_column(Arc::new(field), chunks).unwrap();
+            column.field_and_chunks().unwrap().0.is_nullable()
+        };
+        let dense = || -> Vec<ArrayRef> {
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![3])),
+            ]
+        };
+        let sparse = || -> Vec<ArrayRef> {
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![None, Some(4)])),
+            ]
+        };
+        assert!(!nullable(false, dense()));
+        assert!(!nullable(false, Vec::new()));
+        assert!(nullable(false, sparse()));
+        assert!(nullable(true, dense()));
+    }
+}
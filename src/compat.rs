@@ -0,0 +1,460 @@
+//! IPC export tuned for other Arrow readers.
+//!
+//! Some consumers only read a subset of the Arrow type system. A
+//! [`Profile`] lists what one consumer accepts; [`export_compat`] rewrites
+//! the columns that fall outside it before writing the stream and reports
+//! every rewrite it made.
+
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::ipc::encode_stream;
+use crate::mem::{self, TableHandle};
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow_cast::{cast_with_options, CastOptions};
+use js_sys::Uint8Array;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Field metadata keys carrying an extension type.
+const EXTENSION_PREFIX: &str = "ARROW:extension:";
+
+/// What one consumer's IPC reader accepts.
+#[derive(Debug)]
+struct Profile {
+    name: &'static str,
+    /// `Utf8View`/`BinaryView` are readable.
+    views: bool,
+    /// `LargeUtf8`/`LargeBinary` are readable.
+    large_offsets: bool,
+    /// Readable timestamp units, coarsest first.
+    timestamp_units: &'static [TimeUnit],
+    /// Extension type metadata is understood rather than rejected.
+    extensions: bool,
+}
+
+const PROFILES: &[Profile] = &[
+    Profile {
+        name: "polars",
+        views: false,
+        large_offsets: false,
+        timestamp_units: &[
+            TimeUnit::Millisecond,
+            TimeUnit::Microsecond,
+            TimeUnit::Nanosecond,
+        ],
+        extensions: false,
+    },
+    Profile {
+        name: "arrow-js",
+        views: false,
+        large_offsets: true,
+        timestamp_units: &[
+            TimeUnit::Second,
+            TimeUnit::Millisecond,
+            TimeUnit::Microsecond,
+            TimeUnit::Nanosecond,
+        ],
+        extensions: true,
+    },
+    Profile {
+        name: "duckdb-wasm",
+        views: false,
+        large_offsets: true,
+        timestamp_units: &[TimeUnit::Microsecond],
+        extensions: false,
+    },
+];
+
+fn find_profile(name: &str) -> Result<&'static Profile> {
+    PROFILES
+        .iter()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = PROFILES.iter().map(|profile| profile.name).collect();
+            ArrowWasmError::InvalidInput(format!(
+                "Unknown profile '{name}', expected one of: {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// One rewrite applied to a column.
+#[derive(Debug, Serialize)]
+struct Normalization {
+    column: String,
+    rule: &'static str,
+    from: String,
+    to: String,
+}
+
+/// Coarsest unit in `supported` that is at least as fine as `unit`, so the
+/// conversion is lossless; the finest supported unit when none is.
+fn target_unit(unit: TimeUnit, supported: &[TimeUnit]) -> TimeUnit {
+    let rank = |unit: TimeUnit| match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    };
+    supported
+        .iter()
+        .copied()
+        .find(|candidate| rank(*candidate) >= rank(unit))
+        .or_else(|| supported.last().copied())
+        .unwrap_or(unit)
+}
+
+/// Type `data_type` is written as under `profile`, and the rule that
+/// rewrote it, if any.
+fn normalized_type(data_type: &DataType, profile: &Profile) -> Option<(DataType, &'static str)> {
+    match data_type {
+        DataType::Utf8View if !profile.views => Some((DataType::Utf8, "view-to-offsets")),
+        DataType::BinaryView if !profile.views => Some((DataType::Binary, "view-to-offsets")),
+        DataType::LargeUtf8 if !profile.large_offsets => Some((DataType::Utf8, "narrow-offsets")),
+        DataType::LargeBinary if !profile.large_offsets => {
+            Some((DataType::Binary, "narrow-offsets"))
+        }
+        DataType::Timestamp(unit, tz) => {
+            let target = target_unit(*unit, profile.timestamp_units);
+            (target != *unit).then(|| (DataType::Timestamp(target, tz.clone()), "timestamp-unit"))
+        }
+        _ => None,
+    }
+}
+
+/// Cast `array` to `to` without dropping information: offset overflow and
+/// timestamp values finer than the target unit are errors.
+fn convert_column(array: &ArrayRef, to: &DataType) -> Result<ArrayRef> {
+    let options = CastOptions {
+        safe: false,
+        ..CastOptions::default()
+    };
+    match array.data_type() {
+        DataType::LargeUtf8 | DataType::LargeBinary => {
+            let end = match array.data_type() {
+                DataType::LargeUtf8 => array.as_string::<i64>().value_offsets(),
+                _ => array.as_binary::<i64>().value_offsets(),
+            }
+            .last()
+            .copied()
+            .unwrap_or(0);
+            if i32::try_from(end).is_err() {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "{end} bytes of values exceed the 2 GiB limit of 32-bit offsets"
+                )));
+            }
+        }
+        DataType::Timestamp(_, _) => {
+            let converted = cast_with_options(array, to, &options)?;
+            let back = cast_with_options(&converted, array.data_type(), &options)?;
+            if back.to_data() != array.to_data() {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "values are more precise than {to:?}"
+                )));
+            }
+            return Ok(converted);
+        }
+        _ => {}
+    }
+    Ok(cast_with_options(array, to, &options)?)
+}
+
+/// Field as written under `profile`, recording each rewrite in `report`.
+fn normalize_field(field: &Field, profile: &Profile, report: &mut Vec<Normalization>) -> Field {
+    let mut normalized = field.clone();
+    if let Some((to, rule)) = normalized_type(field.data_type(), profile) {
+        report.push(Normalization {
+            column: field.name().clone(),
+            rule,
+            from: format!("{:?}", field.data_type()),
+            to: format!("{to:?}"),
+        });
+        normalized = normalized.with_data_type(to);
+    }
+    if !profile.extensions {
+        let (stripped, kept): (HashMap<_, _>, HashMap<_, _>) = field
+            .metadata()
+            .clone()
+            .into_iter()
+            .partition(|(key, _)| key.starts_with(EXTENSION_PREFIX));
+        if !stripped.is_empty() {
+            let mut keys: Vec<_> = stripped.into_keys().collect();
+            keys.sort();
+            report.push(Normalization {
+                column: field.name().clone(),
+                rule: "strip-extension",
+                from: keys.join(", "),
+                to: String::new(),
+            });
+            normalized = normalized.with_metadata(kept);
+        }
+    }
+    normalized
+}
+
+/// Encode a table as an IPC stream that `profile` can read, returning the
+/// bytes and the normalizations applied.
+fn export_with_profile(
+    handle: TableHandle,
+    profile: &str,
+) -> Result<(Vec<u8>, Vec<Normalization>)> {
+    let profile = find_profile(profile)?;
+    let table = mem::get_table(handle)?;
+    let mut report = Vec::new();
+    let fields: Vec<Field> = table
+        .schema
+        .fields()
+        .iter()
+        .map(|field| normalize_field(field, profile, &mut report))
+        .collect();
+    if report.is_empty() {
+        let bytes = encode_stream(&table.schema, &table.batches, false)?;
+        return Ok((bytes, report));
+    }
+
+    let schema = Arc::new(Schema::new_with_metadata(
+        fields,
+        table.schema.metadata().clone(),
+    ));
+    let mut batches = Vec::with_capacity(table.batches.len());
+    for batch in &table.batches {
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(schema.fields())
+            .zip(table.schema.fields())
+            .map(|((column, to), from)| {
+                if to.data_type() == from.data_type() {
+                    Ok(Arc::clone(column))
+                } else {
+                    convert_column(column, to.data_type())
+                        .map_err(|e| e.in_column("export_compat", from))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        batches.push(RecordBatch::try_new(Arc::clone(&schema), columns)?);
+    }
+    let bytes = encode_stream(&schema, &batches, false)?;
+    Ok((bytes, report))
+}
+
+/// Serialize a table as an IPC stream for a specific consumer.
+///
+/// `profile` is `"polars"`, `"arrow-js"` or `"duckdb-wasm"`:
+///
+/// | profile       | view types | large offsets | timestamp units | extension metadata |
+/// |---------------|------------|---------------|-----------------|--------------------|
+/// | `polars`      | rewritten  | narrowed      | ms, us, ns      | stripped           |
+/// | `arrow-js`    | rewritten  | kept          | any             | kept               |
+/// | `duckdb-wasm` | rewritten  | kept          | us              | stripped           |
+///
+/// View types become their offset-based equivalents; large offsets are
+/// narrowed to 32 bits, failing when a batch holds 2 GiB or more of values;
+/// timestamps move to the nearest supported unit that loses nothing,
+/// failing when only a coarser unit is available and values would be
+/// truncated. Returns `{bytes, report}` where `report` lists
+/// `{column, rule, from, to}` for every rewrite. The registered table is
+/// not modified.
+#[wasm_bindgen]
+pub fn export_compat(handle: TableHandle, profile: &str) -> std::result::Result<JsValue, JsValue> {
+    let (bytes, report) = export_with_profile(handle, profile)?;
+    let result = js_sys::Object::new();
+    set_property(&result, "bytes", &Uint8Array::from(bytes.as_slice()))?;
+    let report = serde_wasm_bindgen::to_value(&report).map_err(ArrowWasmError::from)?;
+    set_property(&result, "report", &report)?;
+    Ok(result.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::read_ipc_batches;
+    use crate::mem::TableData;
+    use arrow::array::{
+        BinaryViewArray, Int32Array, LargeBinaryArray, LargeStringArray, StringArray,
+        StringViewArray, TimestampNanosecondArray, TimestampSecondArray,
+    };
+
+    /// One batch of every type some profile rewrites, plus an `id` column no
+    /// profile touches and a `uuid` column tagged as an extension type.
+    fn batch(seconds: i64) -> RecordBatch {
+        let uuid = Field::new("uuid", DataType::Utf8, true).with_metadata(HashMap::from([
+            ("ARROW:extension:name".to_string(), "arrow.uuid".to_string()),
+            ("origin".to_string(), "sensor".to_string()),
+        ]));
+        let fields = vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("view", DataType::Utf8View, true),
+            Field::new("bytes_view", DataType::BinaryView, true),
+            Field::new("large", DataType::LargeUtf8, true),
+            Field::new("large_bytes", DataType::LargeBinary, true),
+            Field::new(
+                "seconds",
+                DataType::Timestamp(TimeUnit::Second, Some("UTC".into())),
+                true,
+            ),
+            Field::new(
+                "nanos",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true,
+            ),
+            uuid,
+        ];
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringViewArray::from(vec![
+                Some("short"),
+                None,
+                Some("a string longer than twelve bytes"),
+            ])),
+            Arc::new(BinaryViewArray::from(vec![
+                Some(b"\x00\x01".as_slice()),
+                Some(b"".as_slice()),
+                None,
+            ])),
+            Arc::new(LargeStringArray::from(vec![Some("é"), None, Some("")])),
+            Arc::new(LargeBinaryArray::from(vec![
+                Some(b"\xff".as_slice()),
+                None,
+                Some(b"ok".as_slice()),
+            ])),
+            Arc::new(
+                TimestampSecondArray::from(vec![Some(seconds), None, Some(-seconds)])
+                    .with_timezone("UTC"),
+            ),
+            Arc::new(TimestampNanosecondArray::from(vec![
+                Some(seconds * 1_000_000_000),
+                Some(1_000),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("6f1c0f2e-8f0a-4c52-9d3a-1b2c3d4e5f60"),
+                None,
+                None,
+            ])),
+        ];
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+
+    fn fixture() -> TableHandle {
+        let batches = vec![batch(1_700_000_000), batch(86_400)];
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    fn rules(report: &[Normalization]) -> Vec<(&str, &str)> {
+        report
+            .iter()
+            .map(|entry| (entry.column.as_str(), entry.rule))
+            .collect()
+    }
+
+    /// Schema of the stream `bytes`, after checking that each of its
+    /// columns casts back to exactly the source table's values.
+    fn read_back(handle: TableHandle, bytes: &[u8]) -> Arc<Schema> {
+        let table = mem::get_table(handle).unwrap();
+        let (schema, batches) = read_ipc_batches(bytes, None).unwrap();
+        assert_eq!(batches.len(), table.batches.len());
+        for (read, source) in batches.iter().zip(&table.batches) {
+            for (field, (written, original)) in table
+                .schema
+                .fields()
+                .iter()
+                .zip(read.columns().iter().zip(source.columns()))
+            {
+                let restored =
+                    cast_with_options(written, field.data_type(), &CastOptions::default()).unwrap();
+                assert_eq!(&restored, original, "{}", field.name());
+            }
+        }
+        schema
+    }
+
+    fn written_type(schema: &Schema, name: &str) -> DataType {
+        schema.field_with_name(name).unwrap().data_type().clone()
+    }
+
+    #[test]
+    fn polars_gets_offsets_and_no_extensions() {
+        let handle = fixture();
+        let (bytes, report) = export_with_profile(handle, "polars").unwrap();
+        assert_eq!(
+            rules(&report),
+            [
+                ("view", "view-to-offsets"),
+                ("bytes_view", "view-to-offsets"),
+                ("large", "narrow-offsets"),
+                ("large_bytes", "narrow-offsets"),
+                ("seconds", "timestamp-unit"),
+                ("uuid", "strip-extension"),
+            ]
+        );
+        assert_eq!(report[4].from, r#"Timestamp(Second, Some("UTC"))"#);
+        assert_eq!(report[5].from, "ARROW:extension:name");
+        let schema = read_back(handle, &bytes);
+        assert_eq!(written_type(&schema, "view"), DataType::Utf8);
+        assert_eq!(written_type(&schema, "bytes_view"), DataType::Binary);
+        assert_eq!(written_type(&schema, "large"), DataType::Utf8);
+        assert_eq!(written_type(&schema, "large_bytes"), DataType::Binary);
+        assert_eq!(
+            written_type(&schema, "seconds"),
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+        let uuid = schema.field_with_name("uuid").unwrap().metadata();
+        assert_eq!(uuid.len(), 1);
+        assert_eq!(uuid["origin"], "sensor");
+    }
+
+    #[test]
+    fn duckdb_gets_microseconds() {
+        let handle = fixture();
+        let (bytes, report) = export_with_profile(handle, "duckdb-wasm").unwrap();
+        assert_eq!(
+            rules(&report),
+            [
+                ("view", "view-to-offsets"),
+                ("bytes_view", "view-to-offsets"),
+                ("seconds", "timestamp-unit"),
+                ("nanos", "timestamp-unit"),
+                ("uuid", "strip-extension"),
+            ]
+        );
+        let schema = read_back(handle, &bytes);
+        assert_eq!(written_type(&schema, "large"), DataType::LargeUtf8);
+        for name in ["seconds", "nanos"] {
+            assert!(
+                matches!(
+                    written_type(&schema, name),
+                    DataType::Timestamp(TimeUnit::Microsecond, _)
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn arrow_js_keeps_extensions() {
+        let handle = fixture();
+        let (bytes, report) = export_with_profile(handle, "arrow-js").unwrap();
+        assert_eq!(
+            rules(&report),
+            [
+                ("view", "view-to-offsets"),
+                ("bytes_view", "view-to-offsets")
+            ]
+        );
+        let schema = read_back(handle, &bytes);
+        assert_eq!(schema.field_with_name("uuid").unwrap().metadata().len(), 2);
+    }
+
+    #[test]
+    fn lossy_rewrites_fail() {
+        let nanos: ArrayRef = Arc::new(TimestampNanosecondArray::from(vec![1_500]));
+        let error =
+            convert_column(&nanos, &DataType::Timestamp(TimeUnit::Microsecond, None)).unwrap_err();
+        assert!(error.to_string().contains("more precise than"), "{error}");
+        assert!(export_with_profile(fixture(), "spark").is_err());
+    }
+}
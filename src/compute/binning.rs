@@ -0,0 +1,593 @@
+//! Binning of numeric columns.
+//!
+//! [`binned2d`] and [`binned2d_values`] count points on a 2D grid, e.g. for
+//! scatter-density heatmaps, and return grids of plain typed arrays that can
+//! be uploaded as textures without touching the points in JS. [`cut`]
+//! assigns each value of one column to a bin, e.g. to treat histogram bins
+//! as categories.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::table::align_chunks;
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, StringArray};
+use arrow::datatypes::{DataType, Float64Type};
+use js_sys::Float64Array;
+use serde::Deserialize;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Per-bin aggregate of the value column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Aggregate {
+    #[default]
+    Count,
+    Sum,
+    Mean,
+}
+
+/// Options for [`binned2d`] and [`binned2d_values`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Binned2dOptions {
+    x_bins: usize,
+    y_bins: usize,
+    /// `[min, max]` of the x axis; the data's extent when omitted.
+    #[serde(default)]
+    x_range: Option<(f64, f64)>,
+    #[serde(default)]
+    y_range: Option<(f64, f64)>,
+    #[serde(default)]
+    agg: Aggregate,
+}
+
+/// Float64 chunks of a numeric column, re-chunked to `lengths` when given.
+fn float_chunks(
+    column: &Column,
+    lengths: Option<&[usize]>,
+    op: &'static str,
+) -> Result<Vec<ArrayRef>> {
+    let (field, chunks) = column.field_and_chunks()?;
+    if !field.data_type().is_numeric() {
+        return Err(
+            ArrowWasmError::InvalidInput("expected a numeric column".to_string())
+                .in_column(op, &field),
+        );
+    }
+    let chunks = match lengths {
+        Some(lengths) => align_chunks(&chunks, lengths).map_err(|e| e.in_column(op, &field))?,
+        None => chunks,
+    };
+    chunks
+        .iter()
+        .map(|chunk| cast_safe(chunk, &DataType::Float64).map_err(|e| e.in_column(op, &field)))
+        .collect()
+}
+
+/// One binned axis: `bins` equal-width bins over `[min, max]`.
+#[derive(Debug, Clone, Copy)]
+struct Axis {
+    min: f64,
+    max: f64,
+    bins: usize,
+}
+
+impl Axis {
+    /// Axis over `range`, or over the finite extent of `chunks` when it is
+    /// `None`. A degenerate extent is widened by 0.5 on each side.
+    fn new(
+        range: Option<(f64, f64)>,
+        bins: usize,
+        chunks: &[ArrayRef],
+        name: &str,
+    ) -> Result<Self> {
+        if bins == 0 {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "{name}Bins must be at least 1"
+            )));
+        }
+        let (min, max) = match range {
+            Some((min, max)) if min.is_finite() && max.is_finite() && min < max => (min, max),
+            Some((min, max)) => {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "{name}Range [{min}, {max}] must be finite and increasing"
+                )))
+            }
+            None => {
+                let (min, max) = chunks
+                    .iter()
+                    .flat_map(|chunk| chunk.as_primitive::<Float64Type>().iter().flatten())
+                    .filter(|value| value.is_finite())
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    });
+                if min.is_infinite() {
+                    (0.0, 1.0)
+                } else if max <= min {
+                    (min - 0.5, max + 0.5)
+                } else {
+                    (min, max)
+                }
+            }
+        };
+        Ok(Self { min, max, bins })
+    }
+
+    /// Bin holding `value`; the upper edge belongs to the last bin.
+    fn bin(&self, value: f64) -> Option<usize> {
+        if !(self.min..=self.max).contains(&value) {
+            return None;
+        }
+        let scaled = (value - self.min) / (self.max - self.min) * self.bins as f64;
+        let bin = usize::try_from(scaled as i64).unwrap_or(0);
+        Some(bin.min(self.bins - 1))
+    }
+
+    /// The `bins + 1` bin edges.
+    fn edges(&self) -> Vec<f64> {
+        let width = (self.max - self.min) / self.bins as f64;
+        let mut edges: Vec<f64> = (0..self.bins)
+            .map(|i| (i as f64).mul_add(width, self.min))
+            .collect();
+        edges.push(self.max);
+        edges
+    }
+}
+
+/// Point counts, per-bin aggregates (when there is a value column) and the
+/// number of skipped points.
+struct Grid {
+    x: Axis,
+    y: Axis,
+    counts: Vec<f64>,
+    sums: Option<Vec<f64>>,
+    out_of_range: usize,
+}
+
+fn bin_points(
+    x: &Column,
+    y: &Column,
+    values: Option<&Column>,
+    options: &Binned2dOptions,
+) -> Result<Grid> {
+    const OP: &str = "binned2d";
+    let xs = float_chunks(x, None, OP)?;
+    let lengths: Vec<usize> = xs.iter().map(Array::len).collect();
+    let ys = float_chunks(y, Some(&lengths), OP)?;
+    let vs = values
+        .map(|values| float_chunks(values, Some(&lengths), OP))
+        .transpose()?;
+    let x_axis = Axis::new(options.x_range, options.x_bins, &xs, "x")?;
+    let y_axis = Axis::new(options.y_range, options.y_bins, &ys, "y")?;
+
+    let cells = x_axis.bins * y_axis.bins;
+    let mut counts = vec![0.0; cells];
+    let mut sums = vs.as_ref().map(|_| vec![0.0; cells]);
+    let mut out_of_range = 0;
+    for (chunk, (x_chunk, y_chunk)) in xs.iter().zip(&ys).enumerate() {
+        let x_values = x_chunk.as_primitive::<Float64Type>();
+        let y_values = y_chunk.as_primitive::<Float64Type>();
+        let v_values = vs
+            .as_ref()
+            .map(|vs| vs[chunk].as_primitive::<Float64Type>());
+        for row in 0..x_chunk.len() {
+            let cell = (x_values.is_valid(row) && y_values.is_valid(row)).then(|| {
+                (
+                    x_axis.bin(x_values.value(row)),
+                    y_axis.bin(y_values.value(row)),
+                )
+            });
+            let Some((Some(column), Some(line))) = cell else {
+                out_of_range += 1;
+                continue;
+            };
+            let index = line * x_axis.bins + column;
+            match (v_values, sums.as_mut()) {
+                (Some(v_values), Some(sums)) => {
+                    if v_values.is_valid(row) {
+                        counts[index] += 1.0;
+                        sums[index] += v_values.value(row);
+                    }
+                }
+                _ => counts[index] += 1.0,
+            }
+        }
+    }
+    Ok(Grid {
+        x: x_axis,
+        y: y_axis,
+        counts,
+        sums,
+        out_of_range,
+    })
+}
+
+fn grid_to_js(grid: Grid, agg: Aggregate) -> Result<JsValue> {
+    let result = js_sys::Object::new();
+    set_property(
+        &result,
+        "counts",
+        &Float64Array::from(grid.counts.as_slice()),
+    )?;
+    if let Some(mut sums) = grid.sums.filter(|_| agg != Aggregate::Count) {
+        if agg == Aggregate::Mean {
+            for (sum, count) in sums.iter_mut().zip(&grid.counts) {
+                *sum = if *count > 0.0 { *sum / count } else { f64::NAN };
+            }
+        }
+        set_property(&result, "values", &Float64Array::from(sums.as_slice()))?;
+    }
+    set_property(
+        &result,
+        "xEdges",
+        &Float64Array::from(grid.x.edges().as_slice()),
+    )?;
+    set_property(
+        &result,
+        "yEdges",
+        &Float64Array::from(grid.y.edges().as_slice()),
+    )?;
+    set_property(
+        &result,
+        "outOfRange",
+        &JsValue::from_f64(grid.out_of_range as f64),
+    )?;
+    Ok(result.into())
+}
+
+fn parse_options(options: JsValue) -> Result<Binned2dOptions> {
+    Ok(serde_wasm_bindgen::from_value(options)?)
+}
+
+/// Count points of the numeric columns `x` and `y` on a 2D grid.
+///
+/// `options` is `{xBins, yBins, xRange?, yRange?}`. Ranges are `[min, max]`
+/// and default to the finite extent of the data. Bins are equal-width and
+/// closed on the left, except that the last bin also holds `max`.
+///
+/// Returns `{counts, xEdges, yEdges, outOfRange}`: `counts` is a
+/// `Float64Array` of `xBins * yBins` cells in row-major order (cell
+/// `y * xBins + x`, one row per y bin), the edges are `Float64Array`s of
+/// `bins + 1` values, and `outOfRange` counts the points skipped for a
+/// null, NaN or out-of-range coordinate. `x` and `y` must have the same
+/// length.
+#[wasm_bindgen]
+pub fn binned2d(x: &Column, y: &Column, options: JsValue) -> std::result::Result<JsValue, JsValue> {
+    let options = parse_options(options)?;
+    if options.agg != Aggregate::Count {
+        return Err(ArrowWasmError::InvalidInput(
+            "agg 'sum' and 'mean' need a value column; use binned2d_values".to_string(),
+        )
+        .into());
+    }
+    let grid = bin_points(x, y, None, &options)?;
+    Ok(grid_to_js(grid, Aggregate::Count)?)
+}
+
+/// Like [`binned2d`], also aggregating the numeric column `values` per bin.
+///
+/// `options` additionally takes `agg`: `"count"` (the default), `"sum"` or
+/// `"mean"`. Points with a null value are left out of every cell, so
+/// `counts` only counts points with a value. For `"sum"` and `"mean"` the
+/// result also holds `values`, the per-cell aggregate as a `Float64Array`
+/// in the same layout (`NaN` for empty cells under `"mean"`).
+#[wasm_bindgen]
+pub fn binned2d_values(
+    x: &Column,
+    y: &Column,
+    values: &Column,
+    options: JsValue,
+) -> std::result::Result<JsValue, JsValue> {
+    let options = parse_options(options)?;
+    let grid = bin_points(x, y, Some(values), &options)?;
+    Ok(grid_to_js(grid, options.agg)?)
+}
+
+/// Which end of each bin [`cut`] includes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Closed {
+    /// `(lower, upper]`.
+    #[default]
+    Right,
+    /// `[lower, upper)`.
+    Left,
+}
+
+/// Options for [`cut`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct CutOptions {
+    closed: Closed,
+}
+
+/// Bin edges parsed from JS: at least two finite, strictly increasing
+/// values.
+fn parse_edges(edges: JsValue) -> Result<Vec<f64>> {
+    let edges: Vec<f64> = serde_wasm_bindgen::from_value(edges)?;
+    if edges.len() < 2 {
+        return Err(ArrowWasmError::InvalidInput(
+            "cut needs at least two edges".to_string(),
+        ));
+    }
+    if i32::try_from(edges.len()).is_err() {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "cut supports at most {} edges",
+            i32::MAX
+        )));
+    }
+    if let Some(edge) = edges.iter().find(|edge| !edge.is_finite()) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "cut edge {edge} is not finite"
+        )));
+    }
+    if let Some(&[lower, upper]) = edges.windows(2).find(|pair| pair[0] >= pair[1]) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "cut edges must be strictly increasing, got {lower} then {upper}"
+        )));
+    }
+    Ok(edges)
+}
+
+/// Bin of `value` between `edges`, or `None` when it lies outside them
+/// (or is NaN).
+fn cut_bin(edges: &[f64], value: f64, closed: Closed) -> Option<usize> {
+    let above = match closed {
+        Closed::Right => edges.partition_point(|&edge| edge < value),
+        Closed::Left => edges.partition_point(|&edge| edge <= value),
+    };
+    (1..edges.len()).contains(&above).then(|| above - 1)
+}
+
+/// Bins of the Float64 `chunk` between `edges`: their `labels` when given,
+/// else their indices.
+fn cut_chunk(
+    chunk: &ArrayRef,
+    edges: &[f64],
+    labels: Option<&[String]>,
+    closed: Closed,
+) -> ArrayRef {
+    let bins = chunk
+        .as_primitive::<Float64Type>()
+        .iter()
+        .map(|value| value.and_then(|value| cut_bin(edges, value, closed)));
+    match labels {
+        Some(labels) => Arc::new(
+            bins.map(|bin| bin.map(|bin| labels[bin].as_str()))
+                .collect::<StringArray>(),
+        ),
+        None => Arc::new(
+            bins.map(|bin| bin.and_then(|bin| i32::try_from(bin).ok()))
+                .collect::<Int32Array>(),
+        ),
+    }
+}
+
+/// Assign every value of a numeric column to a bin between `edges`.
+///
+/// `edges` is an array of at least two finite, strictly increasing
+/// numbers; `n` edges make `n - 1` bins. `options` is `{closed?}`: with
+/// `"right"` (the default) bin `i` is `(edges[i], edges[i + 1]]`, so the
+/// first edge itself is outside every bin; with `"left"` it is
+/// `[edges[i], edges[i + 1])`, so the last edge is outside.
+///
+/// Returns an `Int32` column of bin indices, or a `Utf8` column of
+/// `labels[i]` when `labels` (one string per bin) is given. Nulls, NaNs and
+/// values outside the edges become null. The column keeps its name and
+/// batch layout.
+#[wasm_bindgen]
+pub fn cut(
+    column: &Column,
+    edges: JsValue,
+    labels: JsValue,
+    options: JsValue,
+) -> std::result::Result<Column, JsValue> {
+    const OP: &str = "cut";
+    let (field, _) = column.field_and_chunks()?;
+    require(Operation::Cut, &field)?;
+    let context = |e: ArrowWasmError| e.in_column(OP, &field);
+    let edges = parse_edges(edges).map_err(context)?;
+    let labels: Option<Vec<String>> = if labels.is_undefined() || labels.is_null() {
+        None
+    } else {
+        Some(serde_wasm_bindgen::from_value(labels).map_err(|e| context(e.into()))?)
+    };
+    if let Some(labels) = &labels {
+        if labels.len() != edges.len() - 1 {
+            return Err(context(ArrowWasmError::InvalidInput(format!(
+                "cut got {} labels for {} bins",
+                labels.len(),
+                edges.len() - 1
+            )))
+            .into());
+        }
+    }
+    let options: CutOptions = if options.is_undefined() || options.is_null() {
+        CutOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(|e| context(e.into()))?
+    };
+
+    let chunks: Vec<ArrayRef> = float_chunks(column, None, OP)?
+        .iter()
+        .map(|chunk| cut_chunk(chunk, &edges, labels.as_deref(), options.closed))
+        .collect();
+    let data_type = if labels.is_some() {
+        DataType::Utf8
+    } else {
+        DataType::Int32
+    };
+    let field = field.as_ref().clone().with_data_type(data_type);
+    Ok(column::store_column(Arc::new(field), chunks)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column;
+    use arrow::array::Float64Array as Float64Values;
+    use arrow::datatypes::{Field, Int32Type};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn stored(chunks: Vec<Vec<Option<f64>>>) -> Column {
+        let chunks = chunks
+            .into_iter()
+            .map(|values| Arc::new(Float64Values::from(values)) as ArrayRef)
+            .collect();
+        let field = Field::new("v", DataType::Float64, true);
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    fn options(bins: usize, range: Option<(f64, f64)>) -> Binned2dOptions {
+        Binned2dOptions {
+            x_bins: bins,
+            y_bins: bins,
+            x_range: range,
+            y_range: range,
+            agg: Aggregate::Count,
+        }
+    }
+
+    #[test]
+    fn points_land_in_hand_binned_cells() {
+        // On [0, 2] x [0, 2] in 2 x 2 bins: (0, 0) and (0.5, 0) in the
+        // bottom-left cell, (1, 1), (1.5, 1) and the corner (2, 2) in the
+        // top-right one; a null x, (3, -1) and a NaN x are skipped.
+        let x = stored(vec![
+            vec![Some(0.0), Some(0.5), Some(1.0), Some(1.5)],
+            vec![Some(2.0), None, Some(3.0), Some(f64::NAN)],
+        ]);
+        let y = stored(vec![vec![
+            Some(0.0),
+            Some(0.0),
+            Some(1.0),
+            Some(1.0),
+            Some(2.0),
+            Some(0.0),
+            Some(-1.0),
+            Some(0.0),
+        ]]);
+        let grid = bin_points(&x, &y, None, &options(2, Some((0.0, 2.0)))).unwrap();
+        assert_eq!(grid.counts, [2.0, 0.0, 0.0, 3.0]);
+        assert_eq!(grid.out_of_range, 3);
+        assert_eq!(grid.x.edges(), [0.0, 1.0, 2.0]);
+
+        let values = stored(vec![vec![
+            Some(1.0),
+            None,
+            Some(3.0),
+            Some(4.0),
+            Some(5.0),
+            Some(6.0),
+            Some(7.0),
+            Some(8.0),
+        ]]);
+        let grid = bin_points(&x, &y, Some(&values), &options(2, Some((0.0, 2.0)))).unwrap();
+        assert_eq!(grid.counts, [1.0, 0.0, 0.0, 3.0]);
+        assert_eq!(grid.sums.unwrap(), [1.0, 0.0, 0.0, 12.0]);
+
+        // Without ranges the axes span the finite data: x over [0, 3] and
+        // y over [-1, 2].
+        let grid = bin_points(&x, &y, None, &options(3, None)).unwrap();
+        assert_eq!(grid.x.edges(), [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(grid.y.edges(), [-1.0, 0.0, 1.0, 2.0]);
+        assert_eq!(grid.counts, [0.0, 0.0, 1.0, 2.0, 0.0, 0.0, 0.0, 2.0, 1.0]);
+        assert_eq!(grid.out_of_range, 2);
+    }
+
+    #[test]
+    fn two_million_points_bin_in_one_pass() {
+        // A fixed linear congruential sequence in [0, 1), in four batches.
+        let mut state = 0x2545_f491_u64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            Some((state >> 11) as f64 / (1_u64 << 53) as f64)
+        };
+        let chunks = |next: &mut dyn FnMut() -> Option<f64>| {
+            (0..4)
+                .map(|_| (0..500_000).map(|_| next()).collect())
+                .collect()
+        };
+        let x = stored(chunks(&mut next));
+        let y = stored(chunks(&mut next));
+
+        let started = Instant::now();
+        let grid = bin_points(&x, &y, None, &options(256, None)).unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(grid.counts.len(), 256 * 256);
+        assert_eq!(grid.out_of_range, 0);
+        assert!((grid.counts.iter().sum::<f64>() - 2_000_000.0).abs() < 0.5);
+        // Uniform points fill every cell.
+        assert!(grid.counts.iter().all(|count| *count > 0.0));
+        // Generous enough for unoptimized builds on slow CI runners.
+        assert!(elapsed.as_secs() < 20, "binning took {elapsed:?}");
+    }
+
+    #[test]
+    fn values_on_an_edge_fall_in_the_closed_side() {
+        let edges = [0.0, 10.0, 20.0, 30.0];
+        let labels = ["low", "mid", "high"].map(String::from);
+        let values: ArrayRef = Arc::new(Float64Values::from(vec![
+            Some(0.0),
+            Some(10.0),
+            Some(20.0),
+            Some(30.0),
+            Some(5.0),
+            Some(-0.5),
+            Some(30.5),
+            Some(f64::NAN),
+            None,
+        ]));
+        // The four edges, one value inside, then two outside, NaN and null.
+        for (closed, expected) in [
+            (
+                Closed::Right,
+                [
+                    None,
+                    Some(0),
+                    Some(1),
+                    Some(2),
+                    Some(0),
+                    None,
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            (
+                Closed::Left,
+                [
+                    Some(0),
+                    Some(1),
+                    Some(2),
+                    None,
+                    Some(0),
+                    None,
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+        ] {
+            let indices = cut_chunk(&values, &edges, None, closed);
+            assert_eq!(
+                indices.as_primitive::<Int32Type>(),
+                &Int32Array::from(expected.to_vec()),
+                "{closed:?}"
+            );
+
+            let named = cut_chunk(&values, &edges, Some(&labels), closed);
+            let expected: StringArray = expected
+                .iter()
+                .map(|bin| bin.map(|bin| labels[usize::try_from(bin).unwrap()].as_str()))
+                .collect();
+            assert_eq!(named.as_string::<i32>(), &expected, "{closed:?}");
+        }
+    }
+}
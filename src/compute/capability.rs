@@ -0,0 +1,532 @@
+//! Which column types each kernel supports.
+//!
+//! [`CAPABILITIES`] is the one table kernels check their input against
+//! (`require`) and `supported_operations` answers from, so a column the
+//! query lists an operation for never fails that operation's type check,
+//! and one it leaves out fails with [`ArrowWasmError::Unsupported`] naming
+//! the same operation. Types are grouped into [`TypeClass`]es; container
+//! types (lists, structs, dictionaries) are supported when the operation
+//! accepts the container and every child, recursively, belongs to the
+//! operation's element classes.
+
+use crate::column::Column;
+use crate::errors::{ArrowWasmError, Result};
+use arrow::datatypes::{DataType, Field};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// A group of Arrow types that kernels treat alike.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TypeClass {
+    Null,
+    Boolean,
+    /// Signed and unsigned integers of every width.
+    Integer,
+    Float16,
+    /// `Float32` and `Float64`.
+    Float,
+    Decimal,
+    /// `Utf8` and `LargeUtf8`.
+    String,
+    StringView,
+    /// `Binary` and `LargeBinary`.
+    Binary,
+    BinaryView,
+    FixedSizeBinary,
+    Date,
+    Time,
+    Timestamp,
+    Duration,
+    Interval,
+    /// `List` and `LargeList`.
+    List,
+    /// `ListView` and `LargeListView`.
+    ListView,
+    FixedSizeList,
+    Struct,
+    Map,
+    Union,
+    Dictionary,
+    RunEndEncoded,
+}
+
+impl TypeClass {
+    /// Class of `data_type`.
+    pub const fn of(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Null => Self::Null,
+            DataType::Boolean => Self::Boolean,
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64 => Self::Integer,
+            DataType::Float16 => Self::Float16,
+            DataType::Float32 | DataType::Float64 => Self::Float,
+            DataType::Decimal32(_, _)
+            | DataType::Decimal64(_, _)
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _) => Self::Decimal,
+            DataType::Utf8 | DataType::LargeUtf8 => Self::String,
+            DataType::Utf8View => Self::StringView,
+            DataType::Binary | DataType::LargeBinary => Self::Binary,
+            DataType::BinaryView => Self::BinaryView,
+            DataType::FixedSizeBinary(_) => Self::FixedSizeBinary,
+            DataType::Date32 | DataType::Date64 => Self::Date,
+            DataType::Time32(_) | DataType::Time64(_) => Self::Time,
+            DataType::Timestamp(_, _) => Self::Timestamp,
+            DataType::Duration(_) => Self::Duration,
+            DataType::Interval(_) => Self::Interval,
+            DataType::List(_) | DataType::LargeList(_) => Self::List,
+            DataType::ListView(_) | DataType::LargeListView(_) => Self::ListView,
+            DataType::FixedSizeList(_, _) => Self::FixedSizeList,
+            DataType::Struct(_) => Self::Struct,
+            DataType::Map(_, _) => Self::Map,
+            DataType::Union(_, _) => Self::Union,
+            DataType::Dictionary(_, _) => Self::Dictionary,
+            DataType::RunEndEncoded(_, _) => Self::RunEndEncoded,
+        }
+    }
+}
+
+/// Child types a container's support depends on.
+fn children(data_type: &DataType) -> Vec<&DataType> {
+    match data_type {
+        DataType::List(item)
+        | DataType::LargeList(item)
+        | DataType::ListView(item)
+        | DataType::LargeListView(item)
+        | DataType::FixedSizeList(item, _)
+        | DataType::Map(item, _) => vec![item.data_type()],
+        DataType::Struct(fields) => fields.iter().map(|field| field.data_type()).collect(),
+        DataType::Union(fields, _) => fields.iter().map(|(_, field)| field.data_type()).collect(),
+        DataType::Dictionary(_, values) => vec![values.as_ref()],
+        DataType::RunEndEncoded(_, values) => vec![values.data_type()],
+        _ => Vec::new(),
+    }
+}
+
+/// An operation with a type precondition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Sum,
+    SumExact,
+    Mean,
+    Median,
+    Variance,
+    Quantile,
+    Min,
+    Max,
+    Sort,
+    Abs,
+    Negate,
+    Round,
+    Clip,
+    Diff,
+    PercentChange,
+    Shift,
+    Fill,
+    StringOps,
+    Parse,
+    DecodeUtf8,
+    Base64,
+    BooleanCounts,
+    DatePart,
+    DateTrunc,
+    TimeZoneOps,
+    RunEndEncode,
+    ValidateWkb,
+    Distinct,
+    Cut,
+    JoinAsof,
+    Histogram,
+}
+
+/// Types an [`Operation`] accepts.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capability {
+    /// Operation name, as reported by `supported_operations` and errors.
+    pub operation: &'static str,
+    /// Kernels the entry covers.
+    pub kernels: &'static [&'static str],
+    /// Classes accepted directly.
+    pub types: &'static [TypeClass],
+    /// Container classes accepted when their children are.
+    pub nested: &'static [TypeClass],
+    /// Classes accepted as children of containers in `nested`.
+    pub elements: &'static [TypeClass],
+}
+
+const NUMERIC: &[TypeClass] = &[
+    TypeClass::Integer,
+    TypeClass::Float16,
+    TypeClass::Float,
+    TypeClass::Decimal,
+];
+
+const INTEGER_OR_FLOAT: &[TypeClass] = &[TypeClass::Integer, TypeClass::Float];
+
+/// Scalar types arrow-rs can order.
+const ORDERABLE: &[TypeClass] = &[
+    TypeClass::Boolean,
+    TypeClass::Integer,
+    TypeClass::Float16,
+    TypeClass::Float,
+    TypeClass::Decimal,
+    TypeClass::String,
+    TypeClass::StringView,
+    TypeClass::Binary,
+    TypeClass::BinaryView,
+    TypeClass::FixedSizeBinary,
+    TypeClass::Date,
+    TypeClass::Time,
+    TypeClass::Timestamp,
+    TypeClass::Duration,
+    TypeClass::Interval,
+];
+
+/// Scalar types arrow-rs can rank, as sorting list and dictionary values
+/// for `min`/`max` requires.
+const RANKABLE: &[TypeClass] = &[
+    TypeClass::Boolean,
+    TypeClass::Integer,
+    TypeClass::Float16,
+    TypeClass::Float,
+    TypeClass::Decimal,
+    TypeClass::String,
+    TypeClass::Binary,
+    TypeClass::Date,
+    TypeClass::Time,
+    TypeClass::Timestamp,
+    TypeClass::Duration,
+    TypeClass::Interval,
+];
+
+/// Containers arrow-rs can compare value by value.
+const COMPARABLE_NESTED: &[TypeClass] = &[
+    TypeClass::List,
+    TypeClass::FixedSizeList,
+    TypeClass::Struct,
+    TypeClass::Map,
+    TypeClass::Dictionary,
+];
+
+/// Containers whose values arrow-rs can rank.
+const RANKABLE_NESTED: &[TypeClass] = &[
+    TypeClass::List,
+    TypeClass::FixedSizeList,
+    TypeClass::Dictionary,
+    TypeClass::RunEndEncoded,
+];
+
+/// Scalar types with canonical keys (see `compute::keys`).
+const KEYED: &[TypeClass] = &[
+    TypeClass::Boolean,
+    TypeClass::Integer,
+    TypeClass::Float16,
+    TypeClass::Float,
+    TypeClass::String,
+    TypeClass::StringView,
+    TypeClass::Binary,
+    TypeClass::BinaryView,
+    TypeClass::Date,
+    TypeClass::Time,
+    TypeClass::Timestamp,
+    TypeClass::Duration,
+];
+
+const ANY: &[TypeClass] = &[
+    TypeClass::Null,
+    TypeClass::Boolean,
+    TypeClass::Integer,
+    TypeClass::Float16,
+    TypeClass::Float,
+    TypeClass::Decimal,
+    TypeClass::String,
+    TypeClass::StringView,
+    TypeClass::Binary,
+    TypeClass::BinaryView,
+    TypeClass::FixedSizeBinary,
+    TypeClass::Date,
+    TypeClass::Time,
+    TypeClass::Timestamp,
+    TypeClass::Duration,
+    TypeClass::Interval,
+    TypeClass::List,
+    TypeClass::ListView,
+    TypeClass::FixedSizeList,
+    TypeClass::Struct,
+    TypeClass::Map,
+    TypeClass::Union,
+    TypeClass::Dictionary,
+    TypeClass::RunEndEncoded,
+];
+
+const fn flat(
+    operation: &'static str,
+    kernels: &'static [&'static str],
+    types: &'static [TypeClass],
+) -> Capability {
+    Capability {
+        operation,
+        kernels,
+        types,
+        nested: &[],
+        elements: &[],
+    }
+}
+
+/// The capability matrix, in [`Operation`] order.
+pub const CAPABILITIES: &[Capability] = &[
+    flat("sum", &["column_sum"], NUMERIC),
+    flat("sumExact", &["column_sum_exact"], &[TypeClass::Integer]),
+    flat("mean", &["column_mean"], NUMERIC),
+    flat("median", &["column_median"], NUMERIC),
+    flat("variance", &["column_variance", "column_std_dev"], NUMERIC),
+    flat(
+        "quantile",
+        &["Column.quantile", "Column.quantiles"],
+        NUMERIC,
+    ),
+    Capability {
+        operation: "min",
+        kernels: &["column_min"],
+        types: ORDERABLE,
+        nested: RANKABLE_NESTED,
+        elements: RANKABLE,
+    },
+    Capability {
+        operation: "max",
+        kernels: &["column_max"],
+        types: ORDERABLE,
+        nested: RANKABLE_NESTED,
+        elements: RANKABLE,
+    },
+    Capability {
+        operation: "sort",
+        kernels: &["sort_by", "top_k", "bottom_k"],
+        types: ORDERABLE,
+        nested: COMPARABLE_NESTED,
+        elements: ORDERABLE,
+    },
+    flat("abs", &["abs"], INTEGER_OR_FLOAT),
+    flat("negate", &["negate"], INTEGER_OR_FLOAT),
+    flat(
+        "round",
+        &["column_round", "column_floor", "column_ceil"],
+        INTEGER_OR_FLOAT,
+    ),
+    flat("clip", &["clip"], INTEGER_OR_FLOAT),
+    flat(
+        "diff",
+        &["diff", "Column.diff"],
+        &[
+            TypeClass::Integer,
+            TypeClass::Float16,
+            TypeClass::Float,
+            TypeClass::Decimal,
+            TypeClass::Date,
+            TypeClass::Timestamp,
+            TypeClass::Duration,
+        ],
+    ),
+    flat("percentChange", &["percent_change"], NUMERIC),
+    flat("shift", &["shift"], ANY),
+    flat(
+        "fill",
+        &["fill_forward", "fill_backward"],
+        &[
+            TypeClass::Integer,
+            TypeClass::Float16,
+            TypeClass::Float,
+            TypeClass::Decimal,
+            TypeClass::String,
+            TypeClass::StringView,
+        ],
+    ),
+    flat(
+        "stringOps",
+        &["count_matches"],
+        &[TypeClass::String, TypeClass::StringView],
+    ),
+    flat(
+        "parse",
+        &["parse_numbers", "parse_dates"],
+        &[TypeClass::String, TypeClass::StringView],
+    ),
+    flat(
+        "decodeUtf8",
+        &["decode_utf8"],
+        &[TypeClass::Binary, TypeClass::BinaryView],
+    ),
+    flat("base64", &["Column.to_base64_array"], &[TypeClass::Binary]),
+    flat(
+        "booleanCounts",
+        &[
+            "Column.true_count",
+            "Column.false_count",
+            "Column.true_ratio",
+        ],
+        &[TypeClass::Boolean],
+    ),
+    flat(
+        "datePart",
+        &["date_part"],
+        &[TypeClass::Date, TypeClass::Timestamp],
+    ),
+    flat("dateTrunc", &["date_trunc"], &[TypeClass::Timestamp]),
+    flat(
+        "timeZoneOps",
+        &["convert_timezone", "localize_naive", "remove_timezone"],
+        &[TypeClass::Timestamp],
+    ),
+    Capability {
+        operation: "runEndEncode",
+        kernels: &["encode_run_ends", "Column.encode_run_length"],
+        types: ORDERABLE,
+        nested: COMPARABLE_NESTED,
+        elements: ORDERABLE,
+    },
+    flat(
+        "validateWkb",
+        &["validate_wkb"],
+        &[TypeClass::Binary, TypeClass::BinaryView],
+    ),
+    Capability {
+        operation: "distinct",
+        kernels: &["Column.approx_count_distinct", "Column.mode"],
+        types: KEYED,
+        nested: &[TypeClass::Dictionary],
+        elements: KEYED,
+    },
+    flat("cut", &["cut", "Column.cut"], NUMERIC),
+    flat(
+        "joinAsof",
+        &["join_asof"],
+        &[
+            TypeClass::Integer,
+            TypeClass::Float16,
+            TypeClass::Float,
+            TypeClass::Date,
+            TypeClass::Time,
+            TypeClass::Timestamp,
+            TypeClass::Duration,
+        ],
+    ),
+    flat("histogram", &["Column.histogram"], NUMERIC),
+];
+
+impl Operation {
+    /// Every operation, in matrix order.
+    pub const ALL: [Self; 31] = [
+        Self::Sum,
+        Self::SumExact,
+        Self::Mean,
+        Self::Median,
+        Self::Variance,
+        Self::Quantile,
+        Self::Min,
+        Self::Max,
+        Self::Sort,
+        Self::Abs,
+        Self::Negate,
+        Self::Round,
+        Self::Clip,
+        Self::Diff,
+        Self::PercentChange,
+        Self::Shift,
+        Self::Fill,
+        Self::StringOps,
+        Self::Parse,
+        Self::DecodeUtf8,
+        Self::Base64,
+        Self::BooleanCounts,
+        Self::DatePart,
+        Self::DateTrunc,
+        Self::TimeZoneOps,
+        Self::RunEndEncode,
+        Self::ValidateWkb,
+        Self::Distinct,
+        Self::Cut,
+        Self::JoinAsof,
+        Self::Histogram,
+    ];
+
+    /// Matrix entry of the operation.
+    pub const fn capability(self) -> &'static Capability {
+        &CAPABILITIES[self as usize]
+    }
+
+    /// Name used in the matrix and in errors.
+    pub const fn name(self) -> &'static str {
+        self.capability().operation
+    }
+
+    /// Whether columns of `data_type` support the operation.
+    pub fn supports(self, data_type: &DataType) -> bool {
+        let capability = self.capability();
+        let class = TypeClass::of(data_type);
+        capability.types.contains(&class)
+            || (capability.nested.contains(&class)
+                && children(data_type)
+                    .into_iter()
+                    .all(|child| element_supported(capability, child)))
+    }
+}
+
+fn element_supported(capability: &Capability, data_type: &DataType) -> bool {
+    let class = TypeClass::of(data_type);
+    capability.elements.contains(&class)
+        || (capability.nested.contains(&class)
+            && children(data_type)
+                .into_iter()
+                .all(|child| element_supported(capability, child)))
+}
+
+/// Fail with [`ArrowWasmError::Unsupported`] unless `field`'s type supports
+/// `operation`.
+pub fn require(operation: Operation, field: &Field) -> Result<()> {
+    if operation.supports(field.data_type()) {
+        return Ok(());
+    }
+    Err(ArrowWasmError::Unsupported {
+        operation: operation.name(),
+        column: field.name().clone(),
+        data_type: format!("{:?}", field.data_type()),
+    })
+}
+
+/// The capability matrix as pretty-printed JSON, for build scripts
+/// generating TS types.
+pub fn capability_matrix_json() -> Result<String> {
+    Ok(serde_json::to_string_pretty(CAPABILITIES)?)
+}
+
+/// The capability matrix as `[{operation, kernels, types, nested,
+/// elements}]`.
+///
+/// `types` lists the type classes the operation accepts,
+/// `nested` the containers it accepts when every child type, at any
+/// depth, is in `elements` (or is itself such a container).
+#[wasm_bindgen]
+pub fn capability_matrix() -> std::result::Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(CAPABILITIES).map_err(ArrowWasmError::from)?)
+}
+
+/// Names of the operations the column's type supports, in matrix order;
+/// any other operation fails on it with an error naming the operation and
+/// the column type.
+#[wasm_bindgen]
+pub fn supported_operations(column: &Column) -> std::result::Result<Vec<String>, JsValue> {
+    let (field, _) = column.field_and_chunks()?;
+    Ok(Operation::ALL
+        .iter()
+        .filter(|operation| operation.supports(field.data_type()))
+        .map(|operation| operation.name().to_string())
+        .collect())
+}
@@ -0,0 +1,669 @@
+//! Type casts between columns.
+
+use crate::column::{self, Column};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::plain::PlainSchema;
+use crate::table::rebuild_table;
+use arrow::array::{make_array, new_null_array, Array, ArrayRef, AsArray, StringArray};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{DataType, Field, Float16Type, Float32Type, Float64Type, Schema};
+use arrow_cast::{can_cast_types, cast_with_options, CastOptions};
+use arrow_ipc::convert::try_schema_from_ipc_buffer;
+use js_sys::Uint8Array;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Parse a data type name in arrow-rs `Display` form, e.g. `Int64` or
+/// `Timestamp(Millisecond, None)`.
+pub fn parse_data_type(name: &str) -> Result<DataType> {
+    name.parse()
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("Unknown data type '{name}'")))
+}
+
+/// Format every value of a Float16/32/64 array with `precision` decimal
+/// places, keeping nulls.
+pub fn format_floats(array: &dyn Array, precision: usize) -> Result<StringArray> {
+    let values: Vec<Option<f64>> = match array.data_type() {
+        DataType::Float16 => array
+            .as_primitive::<Float16Type>()
+            .iter()
+            .map(|v| v.map(f64::from))
+            .collect(),
+        DataType::Float32 => array
+            .as_primitive::<Float32Type>()
+            .iter()
+            .map(|v| v.map(f64::from))
+            .collect(),
+        DataType::Float64 => array.as_primitive::<Float64Type>().iter().collect(),
+        other => {
+            return Err(ArrowWasmError::Internal(format!(
+                "format_floats called on {other:?}"
+            )))
+        }
+    };
+    Ok(values
+        .into_iter()
+        .map(|v| v.map(|v| format!("{v:.precision$}")))
+        .collect())
+}
+
+/// Cast in "safe" mode: values the target type cannot represent (integer
+/// overflow, unparsable strings) become null instead of wrapping or
+/// failing, and every null of the input stays null in the output.
+///
+/// arrow-rs casts already null out unrepresentable values in safe mode, but
+/// a few type pairs derive the output validity from the converted values
+/// alone; the input validity is re-applied so no null is ever filled in.
+pub fn cast_safe(array: &ArrayRef, to: &DataType) -> Result<ArrayRef> {
+    let options = CastOptions {
+        safe: true,
+        ..CastOptions::default()
+    };
+    let result = cast_with_options(array, to, &options)?;
+    if matches!(to, DataType::Null | DataType::RunEndEncoded(_, _)) {
+        return Ok(result);
+    }
+    let Some(source_nulls) = array.logical_nulls() else {
+        return Ok(result);
+    };
+    let result_nulls = result.logical_nulls();
+    let combined = NullBuffer::union(Some(&source_nulls), result_nulls.as_ref());
+    if combined.as_ref().map(NullBuffer::null_count)
+        == result_nulls.as_ref().map(NullBuffer::null_count)
+    {
+        return Ok(result);
+    }
+    let data = result.to_data().into_builder().nulls(combined).build()?;
+    Ok(make_array(data))
+}
+
+/// Cast `array` to `to` via [`cast_safe`]; float → string casts use
+/// `float_precision` decimal places when given, arrow-rs formatting
+/// otherwise.
+pub fn cast_array(
+    array: &ArrayRef,
+    to: &DataType,
+    float_precision: Option<usize>,
+) -> Result<ArrayRef> {
+    let to_string = matches!(
+        to,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+    );
+    match float_precision {
+        Some(precision) if to_string && array.data_type().is_floating() => {
+            let formatted: ArrayRef = Arc::new(format_floats(array.as_ref(), precision)?);
+            cast_safe(&formatted, to)
+        }
+        _ => cast_safe(array, to),
+    }
+}
+
+/// First row where `source` is valid but `result` is null, i.e. a value the
+/// safe cast could not represent.
+fn first_lost_value(source: &dyn Array, result: &dyn Array) -> Option<usize> {
+    (0..source.len()).find(|&i| source.is_valid(i) && result.is_null(i))
+}
+
+/// Cast a column to `data_type` (arrow-rs type name, e.g. `"Utf8"`).
+///
+/// Nulls stay null, and values that cannot be represented in the target
+/// type (e.g. Int64 → Int32 overflow) become null rather than wrapping;
+/// with `strict` such a value fails the cast instead, reporting its row.
+/// `float_precision` fixes the number of decimal places when casting floats
+/// to strings.
+#[wasm_bindgen]
+pub fn cast_column(
+    column: &Column,
+    data_type: &str,
+    float_precision: Option<usize>,
+    strict: Option<bool>,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let to = parse_data_type(data_type)?;
+    let cast_chunks = cast_chunks(
+        &field,
+        &chunks,
+        &to,
+        float_precision,
+        strict.unwrap_or(false),
+    )?;
+    let field =
+        Field::new(field.name(), to, field.is_nullable()).with_metadata(field.metadata().clone());
+    Ok(column::store_column(Arc::new(field), cast_chunks)?)
+}
+
+/// Cast the `chunks` of `field` for [`cast_column`].
+fn cast_chunks(
+    field: &Field,
+    chunks: &[ArrayRef],
+    to: &DataType,
+    float_precision: Option<usize>,
+    strict: bool,
+) -> Result<Vec<ArrayRef>> {
+    let mut offset = 0;
+    let mut cast_chunks = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let cast =
+            cast_array(chunk, to, float_precision).map_err(|e| e.in_column("cast", field))?;
+        if strict {
+            if let Some(row) = first_lost_value(chunk.as_ref(), cast.as_ref()) {
+                return Err(ArrowWasmError::Compute {
+                    op: "cast",
+                    column: field.name().clone(),
+                    data_type: format!("{:?}", field.data_type()),
+                    row: Some(offset + row),
+                    message: format!("value cannot be represented as {to:?}"),
+                });
+            }
+        }
+        offset += chunk.len();
+        cast_chunks.push(cast);
+    }
+    Ok(cast_chunks)
+}
+
+/// Cast several columns of a table at once into a new table.
+///
+/// `spec` maps column names to arrow-rs type names, e.g.
+/// `{id: "Int64", score: "Utf8"}`; unlisted columns pass through unchanged
+/// and keep sharing buffers with the source. Casts follow `cast_column`:
+/// unrepresentable values become null. A name that is not a column, an
+/// unknown type or a type pair arrow-rs cannot cast fails before any data is
+/// converted, naming the column.
+#[wasm_bindgen]
+pub fn cast_columns(
+    handle: TableHandle,
+    spec: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let spec: HashMap<String, String> =
+        serde_wasm_bindgen::from_value(spec).map_err(ArrowWasmError::from)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(cast_table(&table, &spec)?)?)
+}
+
+/// `table` with the columns named in `spec` cast; see [`cast_columns`].
+fn cast_table(table: &TableData, spec: &HashMap<String, String>) -> Result<TableData> {
+    let mut targets = vec![None; table.column_count()];
+    for (name, type_name) in spec {
+        let index = table
+            .schema
+            .index_of(name)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+        let field = &table.schema.fields()[index];
+        let to = parse_data_type(type_name).map_err(|e| e.in_column("cast_columns", field))?;
+        if !can_cast_types(field.data_type(), &to) {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "cannot cast {:?} to {to:?}",
+                field.data_type()
+            ))
+            .in_column("cast_columns", field));
+        }
+        targets[index] = Some(to);
+    }
+
+    let mut batch_columns: Vec<Vec<ArrayRef>> = table
+        .batches
+        .iter()
+        .map(|batch| batch.columns().to_vec())
+        .collect();
+    let mut fields = Vec::with_capacity(table.column_count());
+    for (index, (field, target)) in table.schema.fields().iter().zip(targets).enumerate() {
+        let Some(to) = target else {
+            fields.push(Arc::clone(field));
+            continue;
+        };
+        let mut nullable = field.is_nullable();
+        for columns in &mut batch_columns {
+            let cast = cast_array(&columns[index], &to, None)
+                .map_err(|e| e.in_column("cast_columns", field))?;
+            nullable |= cast.null_count() > 0;
+            columns[index] = cast;
+        }
+        fields.push(Arc::new(
+            Field::new(field.name(), to, nullable).with_metadata(field.metadata().clone()),
+        ));
+    }
+    rebuild_table(table, fields, batch_columns)
+}
+
+/// Options for [`conform_to_schema`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct ConformOptions {
+    drop_extra: bool,
+}
+
+/// `table` with the fields of `target`; see [`conform_to_schema`].
+pub fn conform_table(table: &TableData, target: &Schema, drop_extra: bool) -> Result<TableData> {
+    let extra: Vec<&str> = table
+        .schema
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .filter(|name| target.field_with_name(name).is_err())
+        .collect();
+    if !extra.is_empty() && !drop_extra {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Columns not in the target schema: {}; set dropExtra to drop them",
+            extra.join(", ")
+        )));
+    }
+
+    let mut batch_columns: Vec<Vec<ArrayRef>> = table
+        .batches
+        .iter()
+        .map(|_| Vec::with_capacity(target.fields().len()))
+        .collect();
+    for field in target.fields() {
+        let context = |e: ArrowWasmError| e.in_column("conform_to_schema", field);
+        let source = table.schema.index_of(field.name()).ok();
+        if let Some(index) = source {
+            let from = table.schema.field(index).data_type();
+            if !can_cast_types(from, field.data_type()) {
+                return Err(context(ArrowWasmError::InvalidInput(format!(
+                    "cannot cast {from:?} to {:?}",
+                    field.data_type()
+                ))));
+            }
+        }
+        for (batch, columns) in table.batches.iter().zip(&mut batch_columns) {
+            let column = match source {
+                Some(index) => {
+                    cast_safe(batch.column(index), field.data_type()).map_err(context)?
+                }
+                None => new_null_array(field.data_type(), batch.num_rows()),
+            };
+            if !field.is_nullable() && column.null_count() > 0 {
+                let reason = if source.is_some() {
+                    "the column holds nulls"
+                } else {
+                    "the table has no such column"
+                };
+                return Err(context(ArrowWasmError::InvalidInput(format!(
+                    "target field is not nullable but {reason}"
+                ))));
+            }
+            columns.push(column);
+        }
+    }
+    rebuild_table(
+        table,
+        target.fields().iter().cloned().collect(),
+        batch_columns,
+    )
+}
+
+/// The schema `conform_to_schema` was given: Arrow IPC stream bytes, of
+/// which only the leading schema message is read, a `{fields, metadata?}`
+/// descriptor or a bare list of fields.
+fn target_schema(schema: &JsValue) -> Result<Schema> {
+    if let Some(bytes) = schema.dyn_ref::<Uint8Array>() {
+        return try_schema_from_ipc_buffer(&bytes.to_vec()).map_err(|e| {
+            ArrowWasmError::InvalidInput(format!("Target schema is not an IPC schema message: {e}"))
+        });
+    }
+    let plain = if js_sys::Array::is_array(schema) {
+        PlainSchema {
+            fields: serde_wasm_bindgen::from_value(schema.clone())?,
+            metadata: HashMap::new(),
+        }
+    } else {
+        serde_wasm_bindgen::from_value(schema.clone())?
+    };
+    plain.into_schema()
+}
+
+/// Make a table follow a target schema.
+///
+/// `schema` is either Arrow IPC stream bytes, such as `write_table_to_ipc`
+/// of a table that already has the schema (an empty one will do; only the
+/// schema message is read), or a descriptor like the one
+/// `to_plain_object` produces: `{fields: [{name, type, nullable,
+/// metadata?}], metadata?}`, or just its `fields` list, which is also what
+/// `JSON.parse(get_table_schema_json(handle))` gives.
+///
+/// Columns come in the target's field order, each cast to the target type
+/// as `cast_column` does (unrepresentable values become null), and target
+/// fields missing from the table are added as all-null columns.
+///
+/// `options` is `{dropExtra?}`: columns of the table the target lacks fail
+/// unless `dropExtra` is set, which drops them. The result takes the target
+/// fields, names, nullability and metadata included, so a non-nullable
+/// target field fails when its column is missing or would hold nulls.
+#[wasm_bindgen]
+pub fn conform_to_schema(
+    handle: TableHandle,
+    schema: JsValue,
+    options: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let options: ConformOptions = if options.is_undefined() || options.is_null() {
+        ConformOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let target = target_schema(&schema)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(conform_table(
+        &table,
+        &target,
+        options.drop_extra,
+    )?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float32Array, Float64Array, Int32Array, Int64Array, RecordBatch};
+    use arrow::datatypes::{Fields, Int32Type, Int64Type};
+
+    fn stored(chunks: Vec<ArrayRef>) -> Column {
+        let field = Field::new("x", chunks[0].data_type().clone(), true);
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    fn strings(column: &Column) -> Vec<Option<String>> {
+        let (_, chunks) = column.field_and_chunks().unwrap();
+        chunks
+            .iter()
+            .flat_map(|chunk| {
+                let chunk = cast_safe(chunk, &DataType::Utf8).unwrap();
+                let values: Vec<Option<String>> = chunk
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|value| value.map(String::from))
+                    .collect();
+                values
+            })
+            .collect()
+    }
+
+    fn owned(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|value| value.map(String::from)).collect()
+    }
+
+    #[test]
+    fn floats_cast_to_strings_at_a_fixed_precision() {
+        let column = stored(vec![
+            Arc::new(Float64Array::from(vec![
+                Some(1.005),
+                Some(2.0),
+                None,
+                Some(1234.5678),
+            ])),
+            Arc::new(Float64Array::from(vec![
+                Some(0.125),
+                Some(0.375),
+                Some(-0.001),
+                Some(f64::NAN),
+                Some(f64::NEG_INFINITY),
+            ])),
+        ]);
+        let cast = cast_column(&column, "Utf8", Some(2), None).unwrap();
+        assert_eq!(cast.data_type().unwrap(), "Utf8");
+        // 1.005 is stored just below the half, and exact halves round to even.
+        assert_eq!(
+            strings(&cast),
+            owned(&[
+                Some("1.00"),
+                Some("2.00"),
+                None,
+                Some("1234.57"),
+                Some("0.12"),
+                Some("0.38"),
+                Some("-0.00"),
+                Some("NaN"),
+                Some("-inf"),
+            ])
+        );
+
+        let large = cast_column(&column, "LargeUtf8", Some(2), None).unwrap();
+        assert_eq!(large.data_type().unwrap(), "LargeUtf8");
+        assert_eq!(strings(&large), strings(&cast));
+    }
+
+    #[test]
+    fn float32_values_format_from_their_stored_value() {
+        let column = stored(vec![Arc::new(Float32Array::from(vec![0.1, 2.675]))]);
+        let cast = cast_column(&column, "Utf8", Some(2), None).unwrap();
+        assert_eq!(strings(&cast), owned(&[Some("0.10"), Some("2.67")]));
+        let cast = cast_column(&column, "Utf8", Some(0), None).unwrap();
+        assert_eq!(strings(&cast), owned(&[Some("0"), Some("3")]));
+        // Without a precision arrow-rs prints the shortest round-trip form.
+        let cast = cast_column(&column, "Utf8", None, None).unwrap();
+        assert_eq!(strings(&cast), owned(&[Some("0.1"), Some("2.675")]));
+    }
+
+    /// `b` (text) and `a` (Int32) in batches of two and one rows.
+    fn shuffled() -> TableData {
+        let batch = |b: Vec<&str>, a: Vec<i32>| {
+            RecordBatch::try_from_iter([
+                ("b", Arc::new(StringArray::from(b)) as ArrayRef),
+                ("a", Arc::new(Int32Array::from(a))),
+            ])
+            .unwrap()
+        };
+        TableData::new(vec![
+            batch(vec!["1", "x"], vec![1, 2]),
+            batch(vec!["3"], vec![3]),
+        ])
+        .unwrap()
+    }
+
+    fn message(result: Result<TableData>) -> String {
+        result.map(drop).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn columns_follow_the_target_order_and_types() {
+        let unit = HashMap::from([("unit".to_string(), "ms".to_string())]);
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false).with_metadata(unit),
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Float64, true),
+        ]);
+        let conformed = conform_table(&shuffled(), &schema, false).unwrap();
+        assert_eq!(conformed.schema.as_ref(), &schema);
+        assert_eq!(conformed.batches.len(), 2);
+        let column = |name: &str| -> Vec<ArrayRef> { conformed.get_column_by_name(name).unwrap() };
+        let a: Vec<Option<i64>> = column("a")
+            .iter()
+            .flat_map(|chunk| chunk.as_primitive::<Int64Type>().iter().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(a, [Some(1), Some(2), Some(3)]);
+        // Text that does not parse becomes null, as in cast_column.
+        let b: Vec<Option<i32>> = column("b")
+            .iter()
+            .flat_map(|chunk| chunk.as_primitive::<Int32Type>().iter().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(b, [Some(1), None, Some(3)]);
+        let c = column("c");
+        assert_eq!(c.iter().map(Array::null_count).sum::<usize>(), 3);
+        assert!(c
+            .iter()
+            .all(|chunk| chunk.data_type() == &DataType::Float64));
+    }
+
+    #[test]
+    fn extra_columns_fail_unless_dropped() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        assert!(message(conform_table(&shuffled(), &schema, false))
+            .contains("Columns not in the target schema: b; set dropExtra to drop them"));
+        let conformed = conform_table(&shuffled(), &schema, true).unwrap();
+        assert_eq!(conformed.schema.as_ref(), &schema);
+        assert_eq!(conformed.batches.len(), 2);
+    }
+
+    #[test]
+    fn non_nullable_targets_need_values() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]);
+        let error = message(conform_table(&shuffled(), &schema, false));
+        assert!(
+            error.contains("target field is not nullable but the column holds nulls"),
+            "{error}"
+        );
+
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+            Field::new("c", DataType::Int32, false),
+        ]);
+        let error = message(conform_table(&shuffled(), &schema, false));
+        assert!(
+            error.contains("not nullable but the table has no such column"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn impossible_casts_fail_before_converting() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Struct(Fields::empty()), true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let error = message(conform_table(&shuffled(), &schema, false));
+        assert!(error.contains("cannot cast Int32 to Struct"), "{error}");
+    }
+
+    fn int32s(array: &ArrayRef) -> Vec<Option<i32>> {
+        array.as_primitive::<Int32Type>().iter().collect()
+    }
+
+    #[test]
+    fn overflowing_values_become_null() {
+        let wide: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(1),
+            Some(i64::from(i32::MAX) + 1),
+            None,
+            Some(i64::from(i32::MIN)),
+            Some(i64::MIN),
+        ]));
+        let narrow = cast_safe(&wide, &DataType::Int32).unwrap();
+        assert_eq!(int32s(&narrow), [Some(1), None, None, Some(i32::MIN), None]);
+    }
+
+    #[test]
+    fn unparsable_strings_become_null() {
+        let text: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("42"),
+            Some("4.2"),
+            Some("x"),
+            None,
+            Some("-7"),
+            Some("99999999999"),
+        ]));
+        let ints = cast_safe(&text, &DataType::Int32).unwrap();
+        assert_eq!(int32s(&ints), [Some(42), None, None, None, Some(-7), None]);
+    }
+
+    #[test]
+    fn input_nulls_stay_null_for_every_target() {
+        let ints: ArrayRef = Arc::new(Int32Array::from(vec![Some(0), None, Some(1), None]));
+        for to in [
+            DataType::Int8,
+            DataType::Int64,
+            DataType::UInt32,
+            DataType::Float64,
+            DataType::Boolean,
+            DataType::Utf8,
+            DataType::Decimal128(10, 2),
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Int32)),
+        ] {
+            let cast = cast_safe(&ints, &to).unwrap();
+            assert_eq!(cast.data_type(), &to);
+            let nulls: Vec<bool> = (0..cast.len()).map(|i| cast.is_null(i)).collect();
+            assert_eq!(nulls, [false, true, false, true], "{to:?}");
+        }
+    }
+
+    #[test]
+    fn strict_casts_report_the_first_lost_value() {
+        let field = Field::new("x", DataType::Int64, true);
+        let chunks: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(1), None])),
+            Arc::new(Int64Array::from(vec![Some(2), Some(i64::MAX)])),
+        ];
+        let cast = cast_chunks(&field, &chunks, &DataType::Int32, None, false).unwrap();
+        assert_eq!(cast.iter().map(Array::null_count).sum::<usize>(), 2);
+        let error = cast_chunks(&field, &chunks, &DataType::Int32, None, true)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "cast failed on column 'x' (Int64) at row 3: value cannot be represented as Int32"
+        );
+    }
+
+    #[test]
+    fn failed_casts_name_the_column_without_a_nested_prefix() {
+        let field = Field::new("x", DataType::Utf8, true);
+        let chunks: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec!["a"]))];
+        let to = DataType::Struct(Fields::empty());
+        let error = cast_chunks(&field, &chunks, &to, None, false)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with("cast failed on column 'x' (Utf8): Cast error: "),
+            "{error}"
+        );
+        assert!(!error.contains("Arrow error"), "{error}");
+    }
+
+    #[test]
+    fn several_columns_cast_in_one_call() {
+        let batch = |ids: Vec<i32>, scores: Vec<Option<f64>>| {
+            RecordBatch::try_from_iter([
+                ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+                ("score", Arc::new(Float64Array::from(scores))),
+                ("kept", Arc::new(StringArray::from(vec!["k"; 2]))),
+            ])
+            .unwrap()
+        };
+        let table = TableData::new(vec![
+            batch(vec![1, -2], vec![Some(0.5), None]),
+            batch(vec![i32::MAX, 0], vec![Some(-1.25), Some(1e21)]),
+        ])
+        .unwrap();
+        let spec = HashMap::from([
+            ("id".to_string(), "Int64".to_string()),
+            ("score".to_string(), "Utf8".to_string()),
+        ]);
+        let cast = cast_table(&table, &spec).unwrap();
+
+        let types: Vec<&DataType> = cast.schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(types, [&DataType::Int64, &DataType::Utf8, &DataType::Utf8]);
+        let ids: Vec<i64> = cast
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(ids, [1, -2, i64::from(i32::MAX), 0]);
+        let scores: Vec<Option<&str>> = cast
+            .batches
+            .iter()
+            .flat_map(|batch| batch.column(1).as_string::<i32>().iter())
+            .collect();
+        assert_eq!(scores, [Some("0.5"), None, Some("-1.25"), Some("1e21")]);
+        // The unlisted column is shared, not copied.
+        assert!(Arc::ptr_eq(
+            cast.batches[1].column(2),
+            table.batches[1].column(2)
+        ));
+
+        let spec = HashMap::from([("kept".to_string(), "Struct".to_string())]);
+        let message = message(cast_table(&table, &spec));
+        assert!(message.contains("column 'kept' (Utf8)"), "{message}");
+    }
+}
@@ -0,0 +1,169 @@
+//! Null imputation by propagating neighbouring values.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::errors::Result;
+use crate::table::align_chunks;
+use arrow::array::{Array, ArrayRef, UInt64Array};
+use arrow_select::concat::concat;
+use arrow_select::take::take;
+use wasm_bindgen::prelude::*;
+
+/// Fill nulls from the previous (`forward`) or next non-null value.
+///
+/// The column is treated as one sequence across batches; the result keeps
+/// the source field and batch layout.
+fn fill(column: &Column, forward: bool) -> Result<Column> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::Fill, &field)?;
+    let op = if forward {
+        "fill_forward"
+    } else {
+        "fill_backward"
+    };
+    let filled = fill_chunks(&chunks, forward).map_err(|e| e.in_column(op, &field))?;
+    column::store_column(field, filled)
+}
+
+fn fill_chunks(chunks: &[ArrayRef], forward: bool) -> Result<Vec<ArrayRef>> {
+    if chunks.iter().all(|chunk| chunk.null_count() == 0) {
+        return Ok(chunks.to_vec());
+    }
+
+    let lengths: Vec<usize> = chunks.iter().map(Array::len).collect();
+    let refs: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+    let combined = concat(&refs)?;
+    let len = combined.len();
+
+    let mut indices = vec![None; len];
+    let mut last = None;
+    let mut visit = |i: usize| {
+        if combined.is_valid(i) {
+            last = Some(i as u64);
+        }
+        indices[i] = last;
+    };
+    if forward {
+        (0..len).for_each(&mut visit);
+    } else {
+        (0..len).rev().for_each(&mut visit);
+    }
+
+    let filled: ArrayRef = take(combined.as_ref(), &UInt64Array::from(indices), None)?;
+    align_chunks(&[filled], &lengths)
+}
+
+/// Replace each null with the last non-null value before it.
+///
+/// Leading nulls stay null. Supports numeric and string columns.
+#[wasm_bindgen]
+pub fn fill_forward(column: &Column) -> std::result::Result<Column, JsValue> {
+    Ok(fill(column, true)?)
+}
+
+/// Replace each null with the next non-null value after it.
+///
+/// Trailing nulls stay null. Supports numeric and string columns.
+#[wasm_bindgen]
+pub fn fill_backward(column: &Column) -> std::result::Result<Column, JsValue> {
+    Ok(fill(column, false)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{AsArray, Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Float64Type, Int32Type};
+    use std::sync::Arc;
+
+    fn stored(chunks: Vec<ArrayRef>) -> Column {
+        let field = Field::new("x", chunks[0].data_type().clone(), true);
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    /// Values per batch of an Int32 column.
+    fn ints(column: &Column) -> Vec<Vec<Option<i32>>> {
+        let (_, chunks) = column.field_and_chunks().unwrap();
+        chunks
+            .iter()
+            .map(|chunk| chunk.as_primitive::<Int32Type>().iter().collect())
+            .collect()
+    }
+
+    /// `[null, 1]`, `[null, null]` and `[4, null]`: nulls at both edges, and
+    /// a gap spanning a whole batch.
+    fn gappy() -> Column {
+        stored(vec![
+            Arc::new(Int32Array::from(vec![None, Some(1)])),
+            Arc::new(Int32Array::from(vec![None, None])),
+            Arc::new(Int32Array::from(vec![Some(4), None])),
+        ])
+    }
+
+    #[test]
+    fn forward_fills_interior_and_trailing_nulls() {
+        let filled = fill_forward(&gappy()).unwrap();
+        assert_eq!(
+            ints(&filled),
+            [
+                vec![None, Some(1)],
+                vec![Some(1), Some(1)],
+                vec![Some(4), Some(4)]
+            ]
+        );
+    }
+
+    #[test]
+    fn backward_fills_interior_and_leading_nulls() {
+        let filled = fill_backward(&gappy()).unwrap();
+        assert_eq!(
+            ints(&filled),
+            [
+                vec![Some(1), Some(1)],
+                vec![Some(4), Some(4)],
+                vec![Some(4), None]
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_fill_and_nan_is_a_value() {
+        let column = stored(vec![Arc::new(StringArray::from(vec![
+            None,
+            Some("a"),
+            None,
+            Some("b"),
+            None,
+        ]))]);
+        let (_, chunks) = fill_forward(&column).unwrap().field_and_chunks().unwrap();
+        let values: Vec<Option<&str>> = chunks[0].as_string::<i32>().iter().collect();
+        assert_eq!(values, [None, Some("a"), Some("a"), Some("b"), Some("b")]);
+        let (_, chunks) = fill_backward(&column).unwrap().field_and_chunks().unwrap();
+        let values: Vec<Option<&str>> = chunks[0].as_string::<i32>().iter().collect();
+        assert_eq!(values, [Some("a"), Some("a"), Some("b"), Some("b"), None]);
+
+        let column = stored(vec![Arc::new(Float64Array::from(vec![
+            Some(f64::NAN),
+            None,
+            Some(2.0),
+        ]))]);
+        let (_, chunks) = fill_forward(&column).unwrap().field_and_chunks().unwrap();
+        let values = chunks[0].as_primitive::<Float64Type>();
+        assert_eq!(values.null_count(), 0);
+        assert!(values.value(0).is_nan() && values.value(1).is_nan());
+    }
+
+    #[test]
+    fn columns_without_values_or_gaps_stay_as_they_are() {
+        let empty = stored(vec![Arc::new(Int32Array::from(vec![None, None]))]);
+        assert_eq!(ints(&fill_forward(&empty).unwrap()), [vec![None, None]]);
+        assert_eq!(ints(&fill_backward(&empty).unwrap()), [vec![None, None]]);
+
+        let dense: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
+        let (_, chunks) = fill_forward(&stored(vec![Arc::clone(&dense)]))
+            .unwrap()
+            .field_and_chunks()
+            .unwrap();
+        assert!(Arc::ptr_eq(&chunks[0], &dense));
+    }
+}
@@ -0,0 +1,342 @@
+//! Canonical, hashable views of column values.
+//!
+//! Membership tests, deduplication and row hashing all need to compare values
+//! across arrays that may not share an exact type (Int32 vs Int64 ids,
+//! dictionary vs plain strings). [`visit_keys`] walks an array and yields one
+//! [`Key`] per slot, borrowing string and binary data from the array instead
+//! of allocating.
+
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::datatypes::{
+    DataType, Date32Type, Date64Type, DurationMicrosecondType, DurationMillisecondType,
+    DurationNanosecondType, DurationSecondType, Int16Type, Int32Type, Int64Type, Int8Type,
+    Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType, TimeUnit,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use std::hash::Hasher;
+use twox_hash::XxHash64;
+
+/// A single non-null value in canonical form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key<'a> {
+    /// Any integer or integer-backed temporal value.
+    Int(i128),
+    /// A float, stored as the bits of its `f64` value with `-0.0` folded into
+    /// `0.0` and every NaN folded into one canonical NaN.
+    Float(u64),
+    /// Utf8, `LargeUtf8` or `Utf8View` data.
+    Str(&'a str),
+    /// Binary, `LargeBinary` or `BinaryView` data.
+    Bytes(&'a [u8]),
+    /// Boolean value.
+    Bool(bool),
+}
+
+/// Comparable family of a data type; keys only match within one domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDomain {
+    /// Signed/unsigned integers.
+    Int,
+    /// Float16/32/64.
+    Float,
+    /// String types.
+    Str,
+    /// Binary types.
+    Bytes,
+    /// Booleans.
+    Bool,
+    /// Temporal types, compared as their underlying integers.
+    Temporal,
+}
+
+/// Domain of `data_type`, or `None` when its values cannot be used as keys.
+pub fn key_domain(data_type: &DataType) -> Option<KeyDomain> {
+    match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => Some(KeyDomain::Int),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => Some(KeyDomain::Float),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(KeyDomain::Str),
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => Some(KeyDomain::Bytes),
+        DataType::Boolean => Some(KeyDomain::Bool),
+        DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(_, _)
+        | DataType::Time32(_)
+        | DataType::Time64(_)
+        | DataType::Duration(_) => Some(KeyDomain::Temporal),
+        DataType::Dictionary(_, value_type) => key_domain(value_type),
+        _ => None,
+    }
+}
+
+/// Type the raw key values of `data_type` are measured in: timestamps
+/// compare across time zones, but not across units.
+fn key_unit(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Timestamp(unit, _) => DataType::Timestamp(*unit, None),
+        DataType::Dictionary(_, values) => key_unit(values),
+        other => other.clone(),
+    }
+}
+
+/// Whether keys of `left` and `right` can be compared with each other.
+///
+/// Temporal keys compare as raw integers, so they must also share a type
+/// and unit: day 19000 of a Date32 is not millisecond 19000.
+pub fn keys_comparable(left: &DataType, right: &DataType) -> bool {
+    match (key_domain(left), key_domain(right)) {
+        (None, _) | (_, None) => false,
+        (Some(KeyDomain::Temporal), Some(KeyDomain::Temporal)) => key_unit(left) == key_unit(right),
+        (left_domain, right_domain) => left_domain == right_domain,
+    }
+}
+
+/// Hasher behind [`key_hash`] and `row_hashes`: xxHash64 with seed 0,
+/// whose output is the same on every platform and Rust release.
+pub fn stable_hasher() -> XxHash64 {
+    XxHash64::with_seed(0)
+}
+
+/// Feed `key` (`None` for a null) to `hasher` as a type tag followed by
+/// little-endian bytes. Unlike the derived `Hash`, which writes lengths as
+/// `usize`, this gives the same bytes on 32- and 64-bit targets.
+pub fn write_key(hasher: &mut impl Hasher, key: Option<&Key<'_>>) {
+    match key {
+        None => hasher.write_u8(0),
+        Some(Key::Int(value)) => {
+            hasher.write_u8(1);
+            hasher.write(&value.to_le_bytes());
+        }
+        Some(Key::Float(bits)) => {
+            hasher.write_u8(2);
+            hasher.write(&bits.to_le_bytes());
+        }
+        Some(Key::Str(text)) => {
+            hasher.write_u8(3);
+            hasher.write(&(text.len() as u64).to_le_bytes());
+            hasher.write(text.as_bytes());
+        }
+        Some(Key::Bytes(bytes)) => {
+            hasher.write_u8(4);
+            hasher.write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(bytes);
+        }
+        Some(Key::Bool(value)) => {
+            hasher.write_u8(5);
+            hasher.write_u8(u8::from(*value));
+        }
+    }
+}
+
+/// 64-bit hash of `key`, as `row_hashes` mixes it into a row's hash.
+pub fn key_hash(key: &Key<'_>) -> u64 {
+    let mut hasher = stable_hasher();
+    write_key(&mut hasher, Some(key));
+    hasher.finish()
+}
+
+/// Fold a float into its canonical key bits.
+pub fn float_key(value: f64) -> u64 {
+    if value == 0.0 {
+        0.0_f64.to_bits()
+    } else if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+macro_rules! visit_primitive {
+    ($array:expr, $ty:ty, $f:expr, |$v:ident| $conv:expr) => {{
+        let array = $array.as_primitive::<$ty>();
+        for (i, value) in array.iter().enumerate() {
+            $f(i, value.map(|$v| $conv));
+        }
+    }};
+}
+
+/// Call `f(row, key)` for every slot of `array`; `key` is `None` for nulls.
+///
+/// Dictionary arrays decode each dictionary value once and then map keys,
+/// so repeated strings are never re-hashed from scratch.
+pub fn visit_keys<'a>(
+    array: &'a dyn Array,
+    f: &mut dyn FnMut(usize, Option<Key<'a>>),
+) -> Result<()> {
+    match array.data_type() {
+        DataType::Int8 => visit_primitive!(array, Int8Type, f, |v| Key::Int(v.into())),
+        DataType::Int16 => visit_primitive!(array, Int16Type, f, |v| Key::Int(v.into())),
+        DataType::Int32 => visit_primitive!(array, Int32Type, f, |v| Key::Int(v.into())),
+        DataType::Int64 => visit_primitive!(array, Int64Type, f, |v| Key::Int(v.into())),
+        DataType::UInt8 => visit_primitive!(array, UInt8Type, f, |v| Key::Int(v.into())),
+        DataType::UInt16 => visit_primitive!(array, UInt16Type, f, |v| Key::Int(v.into())),
+        DataType::UInt32 => visit_primitive!(array, UInt32Type, f, |v| Key::Int(v.into())),
+        DataType::UInt64 => visit_primitive!(array, UInt64Type, f, |v| Key::Int(v.into())),
+        DataType::Float16 => {
+            let array = array.as_primitive::<arrow::datatypes::Float16Type>();
+            for (i, value) in array.iter().enumerate() {
+                f(i, value.map(|v| Key::Float(float_key(v.to_f64()))));
+            }
+        }
+        DataType::Float32 => {
+            visit_primitive!(array, arrow::datatypes::Float32Type, f, |v| Key::Float(
+                float_key(f64::from(v))
+            ));
+        }
+        DataType::Float64 => {
+            visit_primitive!(array, arrow::datatypes::Float64Type, f, |v| Key::Float(
+                float_key(v)
+            ));
+        }
+        DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(_, _)
+        | DataType::Time32(_)
+        | DataType::Time64(_)
+        | DataType::Duration(_) => visit_temporal_keys(array, f)?,
+        DataType::Boolean => {
+            for (i, value) in array.as_boolean().iter().enumerate() {
+                f(i, value.map(Key::Bool));
+            }
+        }
+        DataType::Utf8 => {
+            for (i, value) in array.as_string::<i32>().iter().enumerate() {
+                f(i, value.map(Key::Str));
+            }
+        }
+        DataType::LargeUtf8 => {
+            for (i, value) in array.as_string::<i64>().iter().enumerate() {
+                f(i, value.map(Key::Str));
+            }
+        }
+        DataType::Utf8View => {
+            for (i, value) in array.as_string_view().iter().enumerate() {
+                f(i, value.map(Key::Str));
+            }
+        }
+        DataType::Binary => {
+            for (i, value) in array.as_binary::<i32>().iter().enumerate() {
+                f(i, value.map(Key::Bytes));
+            }
+        }
+        DataType::LargeBinary => {
+            for (i, value) in array.as_binary::<i64>().iter().enumerate() {
+                f(i, value.map(Key::Bytes));
+            }
+        }
+        DataType::BinaryView => {
+            for (i, value) in array.as_binary_view().iter().enumerate() {
+                f(i, value.map(Key::Bytes));
+            }
+        }
+        DataType::Dictionary(_, _) => {
+            let dictionary = array.as_any_dictionary();
+            let mut decoded = vec![None; dictionary.values().len()];
+            visit_keys(dictionary.values().as_ref(), &mut |i, key| decoded[i] = key)?;
+            let normalized = dictionary.normalized_keys();
+            for (i, key_index) in normalized.into_iter().enumerate() {
+                let key = if dictionary.keys().is_valid(i) {
+                    decoded[key_index]
+                } else {
+                    None
+                };
+                f(i, key);
+            }
+        }
+        _ => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Unsupported key type: {:?}",
+                array.data_type()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Key of every row of a column split into `chunks`, in row order.
+///
+/// Two rows hold equal values exactly when their keys are equal; this is
+/// the comparison behind both `Column.values_equal` and `drop_duplicates`.
+pub fn column_keys(chunks: &[ArrayRef]) -> Result<Vec<Option<Key<'_>>>> {
+    let mut keys = Vec::with_capacity(chunks.iter().map(Array::len).sum());
+    for chunk in chunks {
+        visit_keys(chunk.as_ref(), &mut |_, key| keys.push(key))?;
+    }
+    Ok(keys)
+}
+
+/// Key of the first slot of `array` (typically a one-element slice).
+pub fn first_key(array: &dyn Array) -> Result<Option<Key<'_>>> {
+    let mut first = None;
+    visit_keys(array, &mut |i, key| {
+        if i == 0 {
+            first = key;
+        }
+    })?;
+    Ok(first)
+}
+
+/// Temporal arm of [`visit_keys`]: values are keyed by their raw integers.
+fn visit_temporal_keys<'a>(
+    array: &'a dyn Array,
+    f: &mut dyn FnMut(usize, Option<Key<'a>>),
+) -> Result<()> {
+    match array.data_type() {
+        DataType::Date32 => visit_primitive!(array, Date32Type, f, |v| Key::Int(v.into())),
+        DataType::Date64 => visit_primitive!(array, Date64Type, f, |v| Key::Int(v.into())),
+        DataType::Timestamp(unit, _) => match unit {
+            TimeUnit::Second => {
+                visit_primitive!(array, TimestampSecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Millisecond => {
+                visit_primitive!(array, TimestampMillisecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Microsecond => {
+                visit_primitive!(array, TimestampMicrosecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Nanosecond => {
+                visit_primitive!(array, TimestampNanosecondType, f, |v| Key::Int(v.into()));
+            }
+        },
+        DataType::Time32(TimeUnit::Second) => {
+            visit_primitive!(array, Time32SecondType, f, |v| Key::Int(v.into()));
+        }
+        DataType::Time32(_) => {
+            visit_primitive!(array, Time32MillisecondType, f, |v| Key::Int(v.into()));
+        }
+        DataType::Time64(TimeUnit::Microsecond) => {
+            visit_primitive!(array, Time64MicrosecondType, f, |v| Key::Int(v.into()));
+        }
+        DataType::Time64(_) => {
+            visit_primitive!(array, Time64NanosecondType, f, |v| Key::Int(v.into()));
+        }
+        DataType::Duration(unit) => match unit {
+            TimeUnit::Second => {
+                visit_primitive!(array, DurationSecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Millisecond => {
+                visit_primitive!(array, DurationMillisecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Microsecond => {
+                visit_primitive!(array, DurationMicrosecondType, f, |v| Key::Int(v.into()));
+            }
+            TimeUnit::Nanosecond => {
+                visit_primitive!(array, DurationNanosecondType, f, |v| Key::Int(v.into()));
+            }
+        },
+        other => {
+            return Err(ArrowWasmError::Internal(format!(
+                "visit_temporal_keys called with {other:?}"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,342 @@
+//! Value-to-value lookups, e.g. status codes to labels, and mapping values
+//! through a JS callback.
+
+use crate::column::{self, Column};
+use crate::compute::keys::{visit_keys, Key};
+use crate::convert::{check_field, table_options, value_to_js};
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{Array, ArrayRef, BooleanArray, Float64Array, StringArray};
+use arrow::datatypes::{DataType, Field};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// A mapped output value.
+#[derive(Debug, Clone)]
+enum Output {
+    Null,
+    Str(String),
+    Num(f64),
+    Bool(bool),
+}
+
+impl Output {
+    fn from_js(value: &JsValue) -> Result<Self> {
+        if value.is_undefined() || value.is_null() {
+            Ok(Self::Null)
+        } else if let Some(s) = value.as_string() {
+            Ok(Self::Str(s))
+        } else if let Some(n) = value.as_f64() {
+            Ok(Self::Num(n))
+        } else if let Some(b) = value.as_bool() {
+            Ok(Self::Bool(b))
+        } else {
+            Err(ArrowWasmError::InvalidInput(
+                "Mapped values must be strings, numbers, booleans or null".to_string(),
+            ))
+        }
+    }
+
+    const fn data_type(&self) -> Option<DataType> {
+        match self {
+            Self::Null => None,
+            Self::Str(_) => Some(DataType::Utf8),
+            Self::Num(_) => Some(DataType::Float64),
+            Self::Bool(_) => Some(DataType::Boolean),
+        }
+    }
+}
+
+/// `n` as JS `String(n)` writes it: the shortest digits that round-trip,
+/// in fixed notation for decimal exponents from -7 to 20 and as `1.5e+21`
+/// outside them.
+fn js_number_string(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    // `{:e}` gives the shortest round-trip digits, e.g. `1.2345e-7`.
+    let scientific = format!("{:e}", n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    let count = i32::try_from(digits.len()).unwrap_or(i32::MAX);
+    // Position of the decimal point relative to the first digit.
+    let point = exponent.parse::<i32>().unwrap_or(0) + 1;
+    let sign = if n < 0.0 { "-" } else { "" };
+    let zeros = |count: i32| "0".repeat(count.unsigned_abs() as usize);
+    let body = if (count..=21).contains(&point) {
+        format!("{digits}{}", zeros(point - count))
+    } else if (1..=21).contains(&point) {
+        let (whole, fraction) = digits.split_at(point.unsigned_abs() as usize);
+        format!("{whole}.{fraction}")
+    } else if (-5..=0).contains(&point) {
+        format!("0.{}{digits}", zeros(point))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let exponent = point - 1;
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        format!("{first}{rest}e{exponent_sign}{}", exponent.unsigned_abs())
+    };
+    format!("{sign}{body}")
+}
+
+/// Lookup key parsed from the JS side, in the domain of the input column.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum LookupKey {
+    Int(i128),
+    Str(String),
+}
+
+/// Parse a mapping key for a column whose keys are integers (`integers`)
+/// or strings. Object keys are always strings, so integers also accept
+/// their decimal form.
+fn lookup_key(key: &JsValue, integers: bool) -> Result<LookupKey> {
+    let invalid = || ArrowWasmError::InvalidInput(format!("Invalid mapping key {key:?}"));
+    if !integers {
+        return key.as_string().map(LookupKey::Str).ok_or_else(invalid);
+    }
+    if let Some(n) = key.as_f64() {
+        if n.fract() != 0.0 || !n.is_finite() {
+            return Err(invalid());
+        }
+        return Ok(LookupKey::Int(n as i128));
+    }
+    let text = if key.is_bigint() {
+        key.dyn_ref::<js_sys::BigInt>()
+            .and_then(|b| b.to_string(10).ok())
+            .map(String::from)
+    } else {
+        key.as_string()
+    };
+    text.and_then(|text| text.trim().parse().ok())
+        .map(LookupKey::Int)
+        .ok_or_else(invalid)
+}
+
+/// `[key, value]` pairs of a JS `Map` or plain object.
+fn mapping_entries(mapping: &JsValue) -> Result<Vec<(JsValue, JsValue)>> {
+    let mut entries = Vec::new();
+    if let Some(map) = mapping.dyn_ref::<js_sys::Map>() {
+        map.for_each(&mut |value, key| entries.push((key, value)));
+    } else if mapping.is_object() {
+        for entry in js_sys::Object::entries(mapping.unchecked_ref()).iter() {
+            let pair: js_sys::Array = entry.unchecked_into();
+            entries.push((pair.get(0), pair.get(1)));
+        }
+    } else {
+        return Err(ArrowWasmError::InvalidInput(
+            "Mapping must be an object or a Map".to_string(),
+        ));
+    }
+    Ok(entries)
+}
+
+/// Build the output chunk for one input chunk.
+fn mapped_chunk(outputs: &[Output], data_type: &DataType) -> ArrayRef {
+    match data_type {
+        DataType::Float64 => Arc::new(
+            outputs
+                .iter()
+                .map(|output| match output {
+                    Output::Num(n) => Some(*n),
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        DataType::Boolean => Arc::new(
+            outputs
+                .iter()
+                .map(|output| match output {
+                    Output::Bool(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<BooleanArray>(),
+        ),
+        _ => Arc::new(
+            outputs
+                .iter()
+                .map(|output| match output {
+                    Output::Str(s) => Some(s.as_str()),
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+    }
+}
+
+fn map_chunks(
+    field: &Field,
+    chunks: &[ArrayRef],
+    mapping: &JsValue,
+    default: &JsValue,
+) -> Result<(DataType, Vec<ArrayRef>)> {
+    let integers = match field.data_type() {
+        DataType::Int32 | DataType::Int64 => true,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => false,
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Expected an Int32, Int64 or string column, got {other:?}"
+            )))
+        }
+    };
+    let mut lookup = HashMap::new();
+    for (key, value) in mapping_entries(mapping)? {
+        lookup.insert(lookup_key(&key, integers)?, Output::from_js(&value)?);
+    }
+    let default = Output::from_js(default)?;
+
+    let mut types = lookup
+        .values()
+        .chain([&default])
+        .filter_map(Output::data_type);
+    let data_type = types.next().unwrap_or(DataType::Utf8);
+    if let Some(other) = types.find(|other| *other != data_type) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Mapped values mix {data_type:?} and {other:?}"
+        )));
+    }
+
+    let mut mapped = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let mut outputs = vec![Output::Null; chunk.len()];
+        visit_keys(chunk.as_ref(), &mut |i, key| {
+            let key = match key {
+                Some(Key::Int(n)) => LookupKey::Int(n),
+                Some(Key::Str(s)) => LookupKey::Str(s.to_string()),
+                _ => return,
+            };
+            outputs[i] = lookup.get(&key).unwrap_or(&default).clone();
+        })?;
+        mapped.push(mapped_chunk(&outputs, &data_type));
+    }
+    Ok((data_type, mapped))
+}
+
+/// Replace each value with its entry in `mapping`, a JS object or `Map`.
+///
+/// Keys are matched against Int32, Int64 or string (Utf8, `LargeUtf8`,
+/// `Utf8View`) values; plain-object keys are strings, so integer columns
+/// also match their decimal form. Values must all be strings, numbers or
+/// booleans, giving a Utf8, Float64 or Boolean column. Unmapped values
+/// take `default` (`null` when omitted); nulls stay null.
+#[wasm_bindgen]
+pub fn map_values(
+    column: &Column,
+    mapping: JsValue,
+    default: JsValue,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let (data_type, mapped) = map_chunks(&field, &chunks, &mapping, &default)
+        .map_err(|e| e.in_column("map_values", &field))?;
+    let field = Field::new(field.name(), data_type, field.is_nullable());
+    Ok(column::store_column(Arc::new(field), mapped)?)
+}
+
+/// Replace each non-null value with the result of `callback(value, row)`.
+///
+/// Values are converted under the table's `ConversionPolicy`, and `row`
+/// counts from the start of the column. Nulls stay null without calling
+/// `callback`. The callback must return a string, number, boolean or
+/// `null`/`undefined` (giving null); the output type follows the first
+/// non-null result: Float64 for numbers, Utf8 for strings, Boolean for
+/// booleans. When results mix those types the column falls back to Utf8,
+/// with numbers and booleans in their JS string form. A column without
+/// non-null results is Utf8. An exception thrown by `callback` is rethrown.
+#[wasm_bindgen]
+pub fn map_values_with(
+    column: &Column,
+    callback: &js_sys::Function,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let options = table_options(column.handle());
+    check_field(&field, options)?;
+    let mut outputs = Vec::with_capacity(chunks.len());
+    let mut row = 0_u32;
+    for chunk in &chunks {
+        let nulls = chunk.logical_nulls();
+        let mut chunk_outputs = Vec::with_capacity(chunk.len());
+        for index in 0..chunk.len() {
+            if nulls.as_ref().is_some_and(|nulls| nulls.is_null(index)) {
+                chunk_outputs.push(Output::Null);
+            } else {
+                let value = value_to_js(chunk.as_ref(), index, options)
+                    .map_err(|e| e.in_column("map_values_with", &field))?;
+                let result = callback.call2(&JsValue::NULL, &value, &row.into())?;
+                chunk_outputs.push(
+                    Output::from_js(&result).map_err(|e| e.in_column("map_values_with", &field))?,
+                );
+            }
+            row += 1;
+        }
+        outputs.push(chunk_outputs);
+    }
+
+    let mut types = outputs.iter().flatten().filter_map(Output::data_type);
+    let first = types.next();
+    let mixed = first
+        .as_ref()
+        .is_some_and(|first| types.any(|other| other != *first));
+    let data_type = if mixed {
+        DataType::Utf8
+    } else {
+        first.unwrap_or(DataType::Utf8)
+    };
+    if mixed {
+        for output in outputs.iter_mut().flatten() {
+            match output {
+                Output::Num(n) => {
+                    *output = Output::Str(js_number_string(*n));
+                }
+                Output::Bool(b) => *output = Output::Str(b.to_string()),
+                Output::Null | Output::Str(_) => {}
+            }
+        }
+    }
+    let mapped = outputs
+        .iter()
+        .map(|outputs| mapped_chunk(outputs, &data_type))
+        .collect();
+    let field = Field::new(field.name(), data_type, field.is_nullable());
+    Ok(column::store_column(Arc::new(field), mapped)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_print_like_js_strings() {
+        for (n, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-42.0, "-42"),
+            (123.456, "123.456"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (1e21, "1e+21"),
+            (1.5e21, "1.5e+21"),
+            (123_456_789_012_345_680_000.0, "123456789012345680000"),
+            (1e-6, "0.000001"),
+            (1.5e-7, "1.5e-7"),
+            (-2.5e-10, "-2.5e-10"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (5e-324, "5e-324"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ] {
+            assert_eq!(js_number_string(n), expected, "{n:e}");
+        }
+    }
+}
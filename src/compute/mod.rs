@@ -0,0 +1,250 @@
+//! Column kernels.
+//!
+//! Kernels take [`Column`] views and return new columns registered as
+//! single-column tables (see [`crate::column`]).
+
+pub mod binning;
+pub mod capability;
+pub mod cast;
+pub mod distinct;
+pub mod fill;
+pub mod keys;
+pub mod mapping;
+pub mod numeric;
+pub mod parse;
+pub mod run_end;
+pub mod shift;
+pub mod stats;
+pub mod string_ops;
+pub mod temporal;
+pub mod wkb;
+
+use crate::column::{self, Column};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use arrow::array::{ArrayRef, BooleanArray};
+use arrow::datatypes::{DataType, Field, FieldRef};
+use keys::{keys_comparable, visit_keys, Key};
+use std::collections::HashSet;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Per-batch key arrays of `name` in the table behind `handle`.
+fn key_chunks(handle: TableHandle, name: &str) -> Result<(FieldRef, Vec<ArrayRef>)> {
+    let table = mem::get_table(handle)?;
+    let (_, field) = table
+        .schema
+        .column_with_name(name)
+        .ok_or_else(|| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+    let field = Arc::new(field.clone());
+    Ok((field, table.get_column_by_name(name)?))
+}
+
+/// Boolean mask over the left table: `true` where the left key occurs in the
+/// right key column. Null keys never match.
+fn membership_mask(
+    left: TableHandle,
+    left_key: &str,
+    right: TableHandle,
+    right_key: &str,
+    negate: bool,
+) -> Result<Column> {
+    let op = if negate {
+        "anti_join_mask"
+    } else {
+        "semi_join_mask"
+    };
+    let (left_field, left_chunks) = key_chunks(left, left_key)?;
+    let (right_field, right_chunks) = key_chunks(right, right_key)?;
+    if !keys_comparable(left_field.data_type(), right_field.data_type()) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "cannot match against right key '{right_key}' ({:?}); cast one of them with \
+             cast_column first",
+            right_field.data_type()
+        ))
+        .in_column(op, &left_field));
+    }
+
+    let mut set: HashSet<Key<'_>> = HashSet::new();
+    for chunk in &right_chunks {
+        visit_keys(chunk.as_ref(), &mut |_, key| {
+            if let Some(key) = key {
+                set.insert(key);
+            }
+        })
+        .map_err(|e| e.in_column(op, &right_field))?;
+    }
+
+    let mut masks: Vec<ArrayRef> = Vec::with_capacity(left_chunks.len());
+    for chunk in &left_chunks {
+        let mut values = vec![negate; chunk.len()];
+        visit_keys(chunk.as_ref(), &mut |i, key| {
+            if let Some(key) = key {
+                values[i] = set.contains(&key) != negate;
+            }
+        })
+        .map_err(|e| e.in_column(op, &left_field))?;
+        masks.push(Arc::new(BooleanArray::from(values)));
+    }
+
+    column::store_column(Arc::new(Field::new(op, DataType::Boolean, false)), masks)
+}
+
+/// Mask over `left` that is `true` where `left_key` appears in `right_key`.
+///
+/// Combine with `filter_by_mask` for a semi join. Keys are compared by value
+/// across integer widths (Int32 matches Int64); temporal keys must share a
+/// type and unit, so cast one side first to match e.g. dates against
+/// timestamps. Null keys never match.
+#[wasm_bindgen]
+pub fn semi_join_mask(
+    left: TableHandle,
+    left_key: &str,
+    right: TableHandle,
+    right_key: &str,
+) -> std::result::Result<Column, JsValue> {
+    Ok(membership_mask(left, left_key, right, right_key, false)?)
+}
+
+/// Negation of [`semi_join_mask`]: `true` where no matching right key exists,
+/// which includes every row with a null left key.
+#[wasm_bindgen]
+pub fn anti_join_mask(
+    left: TableHandle,
+    left_key: &str,
+    right: TableHandle,
+    right_key: &str,
+) -> std::result::Result<Column, JsValue> {
+    Ok(membership_mask(left, left_key, right, right_key, true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::TableData;
+    use arrow::array::{
+        AsArray, Date32Array, Int32Array, Int64Array, RecordBatch, StringArray,
+        TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
+    };
+
+    /// Table with one column `key` split into `chunks` batches.
+    fn table(chunks: Vec<ArrayRef>) -> TableHandle {
+        let batches = chunks
+            .into_iter()
+            .map(|chunk| RecordBatch::try_from_iter_with_nullable([("key", chunk, true)]).unwrap())
+            .collect();
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    fn mask(left: TableHandle, right: TableHandle, negate: bool) -> Result<Vec<Vec<bool>>> {
+        let column = membership_mask(left, "key", right, "key", negate)?;
+        let (field, chunks) = column.field_and_chunks()?;
+        assert!(!field.is_nullable());
+        Ok(chunks
+            .iter()
+            .map(|chunk| chunk.as_boolean().values().iter().collect())
+            .collect())
+    }
+
+    #[test]
+    fn int64_keys_match_across_batches_and_widths() {
+        let left = table(vec![
+            Arc::new(Int64Array::from(vec![Some(1), None, Some(3)])),
+            Arc::new(Int64Array::from(vec![Some(4), Some(1 << 40)])),
+        ]);
+        let right = table(vec![
+            Arc::new(Int64Array::from(vec![Some(1 << 40), None])),
+            Arc::new(Int64Array::from(vec![3, 3, 7, 8, 9])),
+        ]);
+        let semi = mask(left, right, false).unwrap();
+        assert_eq!(semi, [vec![false, false, true], vec![false, true]]);
+        let anti = mask(left, right, true).unwrap();
+        assert_eq!(anti, [vec![true, true, false], vec![true, false]]);
+
+        let narrow = table(vec![Arc::new(Int32Array::from(vec![3, 5]))]);
+        assert_eq!(mask(narrow, left, false).unwrap(), [vec![true, false]]);
+    }
+
+    #[test]
+    fn utf8_keys_match_and_nulls_never_do() {
+        let left = table(vec![
+            Arc::new(StringArray::from(vec![Some("a"), None])),
+            Arc::new(StringArray::from(vec![Some(""), Some("b"), Some("c")])),
+        ]);
+        let right = table(vec![Arc::new(StringArray::from(vec![
+            None,
+            Some("c"),
+            Some(""),
+        ]))]);
+        assert_eq!(
+            mask(left, right, false).unwrap(),
+            [vec![false, false], vec![true, false, true]]
+        );
+        assert_eq!(
+            mask(left, right, true).unwrap(),
+            [vec![true, true], vec![false, true, false]]
+        );
+    }
+
+    #[test]
+    fn sides_may_differ_in_size() {
+        let small = table(vec![Arc::new(Int64Array::from(vec![2]))]);
+        let large = table(vec![
+            Arc::new(Int64Array::from_iter_values(0..1000)),
+            Arc::new(Int64Array::from_iter_values(1000..2000)),
+        ]);
+        assert_eq!(mask(small, large, false).unwrap(), [vec![true]]);
+        let masks = mask(large, small, false).unwrap();
+        assert_eq!(masks.iter().map(Vec::len).collect::<Vec<_>>(), [1000, 1000]);
+        let matches: Vec<usize> = masks
+            .concat()
+            .iter()
+            .enumerate()
+            .filter_map(|(row, hit)| hit.then_some(row))
+            .collect();
+        assert_eq!(matches, [2]);
+
+        let empty = table(vec![Arc::new(Int64Array::from(Vec::<i64>::new()))]);
+        assert_eq!(mask(small, empty, false).unwrap(), [vec![false]]);
+        assert_eq!(mask(empty, large, true).unwrap(), [Vec::<bool>::new()]);
+    }
+
+    #[test]
+    fn temporal_keys_need_the_same_unit() {
+        let dates = table(vec![Arc::new(Date32Array::from(vec![19_000]))]);
+        let millis = table(vec![Arc::new(TimestampMillisecondArray::from(vec![
+            19_000,
+        ]))]);
+        let seconds = table(vec![Arc::new(TimestampSecondArray::from(vec![19_000]))]);
+        let nanos = table(vec![Arc::new(TimestampNanosecondArray::from(vec![19_000]))]);
+        for (left, right) in [(dates, millis), (seconds, nanos), (millis, seconds)] {
+            let Err(ArrowWasmError::Compute { op, message, .. }) = mask(left, right, false) else {
+                panic!("raw values in different units must not match");
+            };
+            assert_eq!(op, "semi_join_mask");
+            assert!(
+                message.ends_with("cast one of them with cast_column first"),
+                "{message}"
+            );
+        }
+
+        let utc = table(vec![Arc::new(
+            TimestampMillisecondArray::from(vec![19_000, 5]).with_timezone("UTC"),
+        )]);
+        assert_eq!(mask(utc, millis, false).unwrap(), [vec![true, false]]);
+        assert_eq!(mask(dates, dates, false).unwrap(), [vec![true]]);
+    }
+
+    #[test]
+    fn keys_of_other_domains_are_rejected() {
+        let ints = table(vec![Arc::new(Int64Array::from(vec![1]))]);
+        let strings = table(vec![Arc::new(StringArray::from(vec!["1"]))]);
+        assert!(matches!(
+            mask(ints, strings, true),
+            Err(ArrowWasmError::Compute {
+                op: "anti_join_mask",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,284 @@
+//! Element-wise numeric cleanup: `abs`, `negate`, `column_round`,
+//! `column_floor`, `column_ceil` and `clip`.
+//!
+//! Every kernel keeps the column's type, name and batch layout, and null
+//! values stay null. Integer and float columns are supported.
+//!
+//! Exports are linker symbols on `wasm32`, so a kernel exported as `round`,
+//! `floor` or `ceil` would replace the libm function `f64::round` and
+//! friends call there; those three carry a `column_` prefix instead.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::edit::scalar_from_js;
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{
+    Array, ArrayRef, ArrowNativeTypeOp, ArrowPrimitiveType, AsArray, PrimitiveArray,
+};
+use arrow::compute::kernels::arity::unary;
+use arrow::datatypes::{DataType, Field, Float32Type, Float64Type};
+use arrow::error::ArrowError;
+use serde::Deserialize;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// What to do when a result does not fit the column type (e.g. `abs` of
+/// the smallest signed integer).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OnOverflow {
+    /// Fail.
+    #[default]
+    Error,
+    /// Write null.
+    Null,
+}
+
+/// Options for [`abs`] and [`negate`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct OverflowOptions {
+    on_overflow: OnOverflow,
+}
+
+macro_rules! typed {
+    ($t:ty, $f:ident $(, $arg:expr)*) => {
+        Ok($f::<$t>($($arg),*))
+    };
+}
+
+/// `Ok($f::<T>(args))` for the arrow type `T` of an integer or float
+/// column.
+macro_rules! dispatch_numeric {
+    ($data_type:expr, $f:ident $(, $arg:expr)*) => {
+        arrow_array::downcast_integer! {
+            $data_type => (typed, $f $(, $arg)*),
+            DataType::Float32 => Ok($f::<Float32Type>($($arg),*)),
+            DataType::Float64 => Ok($f::<Float64Type>($($arg),*)),
+            other => Err(ArrowWasmError::InvalidInput(format!(
+                "expected an integer or float column, got {other:?}"
+            ))),
+        }
+    };
+}
+
+fn parse_overflow(options: JsValue) -> Result<OnOverflow> {
+    let options: OverflowOptions = if options.is_undefined() || options.is_null() {
+        OverflowOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)?
+    };
+    Ok(options.on_overflow)
+}
+
+/// Apply a fallible `op` to every value, failing or writing null on error
+/// as `on_overflow` says.
+fn checked_map<T: ArrowPrimitiveType>(
+    chunk: &dyn Array,
+    on_overflow: OnOverflow,
+    op: impl Fn(T::Native) -> std::result::Result<T::Native, ArrowError>,
+) -> Result<ArrayRef> {
+    let array = chunk.as_primitive::<T>();
+    let result: PrimitiveArray<T> = match on_overflow {
+        OnOverflow::Error => array.try_unary(op)?,
+        OnOverflow::Null => array.unary_opt(|value| op(value).ok()),
+    };
+    Ok(Arc::new(result.with_data_type(chunk.data_type().clone())))
+}
+
+fn abs_chunk<T: ArrowPrimitiveType>(
+    chunk: &dyn Array,
+    on_overflow: OnOverflow,
+) -> Result<ArrayRef> {
+    checked_map::<T>(chunk, on_overflow, |value| {
+        if value.is_lt(T::Native::ZERO) {
+            value.neg_checked()
+        } else {
+            Ok(value)
+        }
+    })
+}
+
+fn negate_chunk<T: ArrowPrimitiveType>(
+    chunk: &dyn Array,
+    on_overflow: OnOverflow,
+) -> Result<ArrayRef> {
+    checked_map::<T>(chunk, on_overflow, ArrowNativeTypeOp::neg_checked)
+}
+
+fn clip_chunk<T: ArrowPrimitiveType>(
+    chunk: &dyn Array,
+    min: Option<&ArrayRef>,
+    max: Option<&ArrayRef>,
+) -> ArrayRef {
+    let min = min.map(|min| min.as_primitive::<T>().value(0));
+    let max = max.map(|max| max.as_primitive::<T>().value(0));
+    let result: PrimitiveArray<T> = unary(chunk.as_primitive::<T>(), |value| match (min, max) {
+        (Some(min), _) if value < min => min,
+        (_, Some(max)) if value > max => max,
+        _ => value,
+    });
+    Arc::new(result.with_data_type(chunk.data_type().clone()))
+}
+
+/// Map every chunk of `column` with `op`, the kernel `name` implementing
+/// `operation`, and store the result under the column's field, nullable if
+/// any value became null.
+fn map_column(
+    column: &Column,
+    name: &'static str,
+    operation: Operation,
+    op: impl Fn(&Field, &ArrayRef) -> Result<ArrayRef>,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(operation, &field)?;
+    let context = |e: ArrowWasmError| e.in_column(name, &field);
+    let chunks = chunks
+        .iter()
+        .map(|chunk| op(&field, chunk))
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    Ok(column::store_column(Arc::clone(&field), chunks)?)
+}
+
+/// Absolute value of every element.
+///
+/// The smallest value of a signed integer type has no absolute value in
+/// that type: `options` (`{onOverflow?}`) decides whether it fails
+/// (`"error"`, the default) or becomes null (`"null"`).
+#[wasm_bindgen]
+pub fn abs(column: &Column, options: JsValue) -> std::result::Result<Column, JsValue> {
+    let on_overflow = parse_overflow(options)?;
+    map_column(column, "abs", Operation::Abs, |field, chunk| {
+        dispatch_numeric!(field.data_type(), abs_chunk, chunk.as_ref(), on_overflow)?
+    })
+}
+
+/// Negation of every element.
+///
+/// Values with no negation in the column type (the smallest signed
+/// integer, any non-zero unsigned integer) follow `options.onOverflow` as
+/// for `abs`.
+#[wasm_bindgen]
+pub fn negate(column: &Column, options: JsValue) -> std::result::Result<Column, JsValue> {
+    let on_overflow = parse_overflow(options)?;
+    map_column(column, "negate", Operation::Negate, |field, chunk| {
+        dispatch_numeric!(field.data_type(), negate_chunk, chunk.as_ref(), on_overflow)?
+    })
+}
+
+/// Apply `op` to the values of a float chunk (computed in Float64, stored
+/// back in the chunk's type); integer chunks are returned as they are.
+fn float_map(field: &Field, chunk: &ArrayRef, op: impl Fn(f64) -> f64) -> Result<ArrayRef> {
+    match field.data_type() {
+        DataType::Float32 | DataType::Float64 => {
+            let values = cast_safe(chunk, &DataType::Float64)?;
+            let result: ArrayRef = Arc::new(unary::<Float64Type, _, Float64Type>(
+                values.as_primitive(),
+                op,
+            ));
+            cast_safe(&result, field.data_type())
+        }
+        integer if integer.is_integer() => Ok(Arc::clone(chunk)),
+        other => Err(ArrowWasmError::InvalidInput(format!(
+            "expected an integer or float column, got {other:?}"
+        ))),
+    }
+}
+
+/// Round every element to `decimals` decimal places (`0` by default),
+/// halves away from zero.
+///
+/// Negative `decimals` round to tens, hundreds and so on. Integer columns
+/// are returned unchanged for `decimals >= 0`; cast them to a float type to
+/// round to negative places.
+#[wasm_bindgen]
+pub fn column_round(
+    column: &Column,
+    decimals: Option<i32>,
+) -> std::result::Result<Column, JsValue> {
+    let decimals = decimals.unwrap_or(0);
+    let factor = 10_f64.powi(decimals);
+    map_column(column, "column_round", Operation::Round, |field, chunk| {
+        if decimals < 0 && field.data_type().is_integer() {
+            return Err(ArrowWasmError::InvalidInput(
+                "negative decimals need a float column; cast the column first".to_string(),
+            ));
+        }
+        float_map(field, chunk, |value| {
+            let scaled = value * factor;
+            if scaled.is_finite() && factor.is_finite() && factor != 0.0 {
+                scaled.round() / factor
+            } else {
+                value
+            }
+        })
+    })
+}
+
+/// Largest integer not greater than each element; integer columns are
+/// returned unchanged.
+#[wasm_bindgen]
+pub fn column_floor(column: &Column) -> std::result::Result<Column, JsValue> {
+    map_column(column, "column_floor", Operation::Round, |field, chunk| {
+        float_map(field, chunk, f64::floor)
+    })
+}
+
+/// Smallest integer not less than each element; integer columns are
+/// returned unchanged.
+#[wasm_bindgen]
+pub fn column_ceil(column: &Column) -> std::result::Result<Column, JsValue> {
+    map_column(column, "column_ceil", Operation::Round, |field, chunk| {
+        float_map(field, chunk, f64::ceil)
+    })
+}
+
+/// `bound` as a one-element array of `data_type`, or `None` when absent.
+fn clip_bound(bound: &JsValue, data_type: &DataType) -> Result<Option<ArrayRef>> {
+    if bound.is_undefined() || bound.is_null() {
+        return Ok(None);
+    }
+    if data_type.is_integer() && bound.as_f64().is_some_and(|bound| bound.fract() != 0.0) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "clip bound {} is not an integer; cast the column to a float type first",
+            bound.as_f64().unwrap_or_default()
+        )));
+    }
+    scalar_from_js(bound, data_type).map(Some)
+}
+
+/// Limit every element to `[min, max]`; either bound may be `null` or
+/// `undefined` to leave that side open.
+///
+/// Bounds are converted to the column type, so an integer column needs
+/// integer bounds. Float NaNs are left as they are.
+#[wasm_bindgen]
+pub fn clip(column: &Column, min: JsValue, max: JsValue) -> std::result::Result<Column, JsValue> {
+    let (field, _) = column.field_and_chunks()?;
+    require(Operation::Clip, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("clip", &field);
+    let min = clip_bound(&min, field.data_type()).map_err(context)?;
+    let max = clip_bound(&max, field.data_type()).map_err(context)?;
+    if let (Some(min), Some(max)) = (&min, &max) {
+        if arrow_ord::cmp::gt(min, max)
+            .map_err(|e| context(e.into()))?
+            .value(0)
+        {
+            return Err(context(ArrowWasmError::InvalidInput(
+                "clip min is greater than max".to_string(),
+            ))
+            .into());
+        }
+    }
+    map_column(column, "clip", Operation::Clip, |field, chunk| {
+        dispatch_numeric!(
+            field.data_type(),
+            clip_chunk,
+            chunk.as_ref(),
+            min.as_ref(),
+            max.as_ref()
+        )
+    })
+}
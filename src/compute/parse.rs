@@ -0,0 +1,717 @@
+//! Parsing of string columns into numbers and timestamps.
+//!
+//! CSV imports often leave numbers as text with thousands separators,
+//! decimal commas or currency symbols (`"1.234,56 €"`), and dates in
+//! whatever format the exporting tool chose. Both kernels parse every
+//! value of a Utf8, `LargeUtf8` or `Utf8View` column batch by batch, keep
+//! the field name and note the source column in the field metadata under
+//! [`PARSED_FROM`]. Values that do not parse become null, or fail the
+//! kernel with the first few offending rows when `onError` is `"fail"`.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::string_ops::map_strings;
+use crate::errors::{ArrowWasmError, Result};
+use crate::tz::{days_from_civil, parse_fixed_offset, Ambiguous, Zone};
+use arrow::array::{ArrayRef, Float64Array, Int64Array, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, FieldRef, TimeUnit};
+use serde::Deserialize;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Field metadata key naming the column a parsed column was read from.
+pub const PARSED_FROM: &str = "parsed_from";
+
+/// Unparsable values listed in a `"fail"` error.
+const REPORTED_FAILURES: usize = 5;
+
+/// What happens to a value that does not parse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OnError {
+    /// It becomes null.
+    #[default]
+    Null,
+    /// The kernel fails, listing the first few such values.
+    Fail,
+}
+
+/// Outcome of parsing one string.
+enum Parsed<T> {
+    Value(T),
+    Empty,
+    Invalid,
+}
+
+/// Rows that did not parse, with the first few values for the error.
+#[derive(Default)]
+struct Failures {
+    count: usize,
+    samples: Vec<(usize, String)>,
+}
+
+impl Failures {
+    fn record(&mut self, row: usize, value: &str) {
+        self.count += 1;
+        if self.samples.len() < REPORTED_FAILURES {
+            self.samples.push((row, value.to_string()));
+        }
+    }
+
+    /// Error listing the failures, or `None` when every value parsed.
+    fn into_error(self, op: &'static str, field: &Field) -> Option<ArrowWasmError> {
+        let first = self.samples.first()?.0;
+        let listed: Vec<String> = self
+            .samples
+            .iter()
+            .map(|(row, value)| format!("row {row} {value:?}"))
+            .collect();
+        let more = if self.count > self.samples.len() {
+            format!(" and {} more", self.count - self.samples.len())
+        } else {
+            String::new()
+        };
+        Some(ArrowWasmError::Compute {
+            op,
+            column: field.name().clone(),
+            data_type: format!("{:?}", field.data_type()),
+            row: Some(first),
+            message: format!(
+                "{} values could not be parsed: {}{more}",
+                self.count,
+                listed.join(", ")
+            ),
+        })
+    }
+}
+
+/// Parse every string of `chunks` with `parse`, one `Vec` per chunk, with
+/// empty values null when `empty_as_null` is set and invalid otherwise.
+fn parse_chunks<T>(
+    chunks: &[ArrayRef],
+    empty_as_null: bool,
+    failures: &mut Failures,
+    parse: impl Fn(&str) -> Parsed<T>,
+) -> Result<Vec<Vec<Option<T>>>> {
+    let mut offset = 0;
+    let mut parsed = Vec::with_capacity(chunks.len());
+    for chunk in chunks {
+        let values = map_strings(chunk.as_ref(), |text| match parse(text) {
+            Parsed::Value(value) => Ok(Some(value)),
+            Parsed::Empty if empty_as_null => Ok(None),
+            Parsed::Empty | Parsed::Invalid => Err(text.to_string()),
+        })?;
+        let values = values
+            .into_iter()
+            .enumerate()
+            .map(|(row, value)| match value? {
+                Ok(value) => value,
+                Err(text) => {
+                    failures.record(offset + row, &text);
+                    None
+                }
+            })
+            .collect();
+        offset += chunk.len();
+        parsed.push(values);
+    }
+    Ok(parsed)
+}
+
+/// Field of a column parsed from `source`.
+fn parsed_field(source: &Field, data_type: DataType) -> FieldRef {
+    let mut metadata = source.metadata().clone();
+    metadata.insert(PARSED_FROM.to_string(), source.name().clone());
+    Arc::new(Field::new(source.name(), data_type, source.is_nullable()).with_metadata(metadata))
+}
+
+/// Decimal separator accepted by [`parse_numbers`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+enum DecimalSeparator {
+    #[default]
+    #[serde(rename = ".")]
+    Dot,
+    #[serde(rename = ",")]
+    Comma,
+}
+
+/// Thousands separator accepted by [`parse_numbers`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+enum ThousandsSeparator {
+    #[serde(rename = ",")]
+    Comma,
+    #[serde(rename = ".")]
+    Dot,
+    /// Any whitespace, including no-break spaces.
+    #[serde(rename = " ")]
+    Space,
+    #[default]
+    #[serde(rename = "none")]
+    None,
+}
+
+/// Options for [`parse_numbers`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct NumberOptions {
+    decimal_separator: DecimalSeparator,
+    thousands_separator: ThousandsSeparator,
+    currency_symbols: Vec<String>,
+    empty_as_null: bool,
+    on_error: OnError,
+    integers: bool,
+}
+
+impl Default for NumberOptions {
+    fn default() -> Self {
+        Self {
+            decimal_separator: DecimalSeparator::default(),
+            thousands_separator: ThousandsSeparator::default(),
+            currency_symbols: Vec::new(),
+            empty_as_null: true,
+            on_error: OnError::default(),
+            integers: false,
+        }
+    }
+}
+
+impl NumberOptions {
+    const fn is_thousands(&self, c: char) -> bool {
+        match self.thousands_separator {
+            ThousandsSeparator::Comma => c == ',',
+            ThousandsSeparator::Dot => c == '.',
+            ThousandsSeparator::Space => c.is_whitespace(),
+            ThousandsSeparator::None => false,
+        }
+    }
+
+    /// `text` without surrounding whitespace and one leading or trailing
+    /// currency symbol.
+    fn strip_currency<'a>(&self, text: &'a str) -> &'a str {
+        let text = text.trim();
+        self.currency_symbols
+            .iter()
+            .find_map(|symbol| {
+                text.strip_prefix(symbol.as_str())
+                    .or_else(|| text.strip_suffix(symbol.as_str()))
+            })
+            .map_or(text, str::trim)
+    }
+
+    fn parse(&self, text: &str) -> Parsed<f64> {
+        if text.trim().is_empty() {
+            return Parsed::Empty;
+        }
+        let mut rest = self.strip_currency(text);
+        let negative = rest.starts_with('-');
+        if let Some(unsigned) = rest.strip_prefix(['-', '+']) {
+            rest = self.strip_currency(unsigned);
+        }
+        let decimal = match self.decimal_separator {
+            DecimalSeparator::Dot => '.',
+            DecimalSeparator::Comma => ',',
+        };
+        let mut normalized = String::with_capacity(rest.len());
+        for c in rest.chars() {
+            match c {
+                '0'..='9' | 'e' | 'E' => normalized.push(c),
+                '+' | '-' if normalized.ends_with(['e', 'E']) => normalized.push(c),
+                c if c == decimal => normalized.push('.'),
+                c if self.is_thousands(c) && normalized.ends_with(|c: char| c.is_ascii_digit()) => {
+                }
+                _ => return Parsed::Invalid,
+            }
+        }
+        if !normalized.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return Parsed::Invalid;
+        }
+        match normalized.parse::<f64>() {
+            Ok(value) if negative => Parsed::Value(-value),
+            Ok(value) => Parsed::Value(value),
+            Err(_) => Parsed::Invalid,
+        }
+    }
+}
+
+/// Parse a string column of numbers into a Float64 column.
+///
+/// `options` is `{decimalSeparator?, thousandsSeparator?, currencySymbols?,
+/// emptyAsNull?, onError?, integers?}`:
+///
+/// - `decimalSeparator` is `"."` (the default) or `","`, so `"1.234,56"`
+///   reads as 1234.56 with `{decimalSeparator: ",", thousandsSeparator: "."}`.
+/// - `thousandsSeparator` is `","`, `"."`, `" "` (any whitespace, including
+///   no-break spaces) or `"none"` (the default); it must differ from the
+///   decimal separator and is only skipped after a digit.
+/// - `currencySymbols` (e.g. `["$", "€", "EUR"]`) may precede or follow the
+///   number, with or without a space and on either side of a sign.
+/// - Empty and whitespace-only strings are null with `emptyAsNull` (the
+///   default) and unparsable otherwise.
+/// - `onError` is `"null"` (the default) or `"fail"`, which reports the
+///   number of unparsable values and the first few with their rows.
+/// - With `integers`, the result is Int64 when every value is a whole
+///   number in the 64-bit range (exactly so up to 2^53), Float64 otherwise.
+///
+/// Surrounding whitespace is ignored everywhere; nulls stay null.
+#[wasm_bindgen]
+pub fn parse_numbers(column: &Column, options: JsValue) -> std::result::Result<Column, JsValue> {
+    let options: NumberOptions = if options.is_undefined() || options.is_null() {
+        NumberOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::Parse, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("parse_numbers", &field);
+    let clash = matches!(
+        (options.decimal_separator, options.thousands_separator),
+        (DecimalSeparator::Dot, ThousandsSeparator::Dot)
+            | (DecimalSeparator::Comma, ThousandsSeparator::Comma)
+    );
+    if clash {
+        return Err(context(ArrowWasmError::InvalidInput(
+            "decimalSeparator and thousandsSeparator must differ".to_string(),
+        ))
+        .into());
+    }
+    let mut failures = Failures::default();
+    let parsed = parse_chunks(&chunks, options.empty_as_null, &mut failures, |text| {
+        options.parse(text)
+    })
+    .map_err(context)?;
+    if options.on_error == OnError::Fail {
+        if let Some(error) = failures.into_error("parse_numbers", &field) {
+            return Err(error.into());
+        }
+    }
+    let floats: Vec<Float64Array> = parsed.into_iter().map(Float64Array::from).collect();
+    // `i64::MAX as f64` rounds up to 2^63, the first value past the range.
+    let integral = |value: f64| value.fract() == 0.0 && value.abs() < i64::MAX as f64;
+    let as_integers = options.integers
+        && floats
+            .iter()
+            .all(|chunk| chunk.iter().flatten().all(integral));
+    let (data_type, chunks): (DataType, Vec<ArrayRef>) = if as_integers {
+        let chunks = floats
+            .iter()
+            .map(|chunk| {
+                let values: Int64Array = chunk.iter().map(|v| v.map(|v| v as i64)).collect();
+                Arc::new(values) as ArrayRef
+            })
+            .collect();
+        (DataType::Int64, chunks)
+    } else {
+        let chunks = floats
+            .into_iter()
+            .map(|chunk| Arc::new(chunk) as ArrayRef)
+            .collect();
+        (DataType::Float64, chunks)
+    };
+    Ok(column::store_column(
+        parsed_field(&field, data_type),
+        chunks,
+    )?)
+}
+
+/// One element of a compiled date format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `%Y`: four-digit year.
+    Year,
+    /// `%y`: two-digit year, 69-99 in the 1900s and 00-68 in the 2000s.
+    ShortYear,
+    /// `%m`: month, one or two digits.
+    Month,
+    /// `%b`: English month abbreviation, any case.
+    MonthName,
+    /// `%d`: day of month, one or two digits.
+    Day,
+    /// `%H`: hour 0-23, one or two digits.
+    Hour,
+    /// `%M`: minute, one or two digits.
+    Minute,
+    /// `%S`: second, one or two digits.
+    Second,
+    /// `%f`: fraction of a second, one to nine digits.
+    Fraction,
+    /// `%z`: `Z` or an offset `+HH:MM`, `+HHMM` or `+HH`.
+    Offset,
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Compile a `strftime`-style format.
+fn compile(format: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            tokens.push(Token::Literal(c));
+            continue;
+        }
+        tokens.push(match chars.next() {
+            Some('Y') => Token::Year,
+            Some('y') => Token::ShortYear,
+            Some('m') => Token::Month,
+            Some('b') => Token::MonthName,
+            Some('d') => Token::Day,
+            Some('H') => Token::Hour,
+            Some('M') => Token::Minute,
+            Some('S') => Token::Second,
+            Some('f') => Token::Fraction,
+            Some('z') => Token::Offset,
+            Some('%') => Token::Literal('%'),
+            other => {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Unsupported specifier '%{}' in date format {format:?}; use %Y, %y, %m, %b, %d, %H, %M, %S, %f, %z or %%",
+                    other.map(String::from).unwrap_or_default()
+                )))
+            }
+        });
+    }
+    Ok(tokens)
+}
+
+/// Split up to `max` leading ASCII digits (at least `min`) off `text`.
+fn digits(text: &str, min: usize, max: usize) -> Option<(u32, usize, &str)> {
+    let len = text
+        .bytes()
+        .take(max)
+        .take_while(u8::is_ascii_digit)
+        .count();
+    if len < min {
+        return None;
+    }
+    Some((text[..len].parse().ok()?, len, &text[len..]))
+}
+
+/// Seconds east of UTC of a `%z` offset at the start of `text`.
+fn offset(text: &str) -> Option<(i32, &str)> {
+    if let Some(rest) = text.strip_prefix('Z') {
+        return Some((0, rest));
+    }
+    [6, 5, 3].into_iter().find_map(|len| {
+        let candidate = text.get(..len)?;
+        parse_fixed_offset(candidate).map(|seconds| (seconds, &text[len..]))
+    })
+}
+
+/// Local epoch seconds, milliseconds and explicit offset of `text` read
+/// with `tokens`, or `None` when it does not match.
+fn read_date(tokens: &[Token], text: &str) -> Option<(i64, i64, Option<i32>)> {
+    let (mut year, mut month, mut day) = (1970_i64, 1_u32, 1_u32);
+    let (mut hour, mut minute, mut second, mut millis) = (0_u32, 0_u32, 0_u32, 0_i64);
+    let mut zone_offset = None;
+    let mut rest = text;
+    for token in tokens {
+        match *token {
+            Token::Literal(c) => rest = rest.strip_prefix(c)?,
+            Token::Year => {
+                let (value, _, tail) = digits(rest, 4, 4)?;
+                (year, rest) = (i64::from(value), tail);
+            }
+            Token::ShortYear => {
+                let (value, _, tail) = digits(rest, 2, 2)?;
+                let century = if value >= 69 { 1900 } else { 2000 };
+                (year, rest) = (century + i64::from(value), tail);
+            }
+            Token::Month => (month, _, rest) = digits(rest, 1, 2)?,
+            Token::MonthName => {
+                let name = rest.get(..3)?.to_ascii_lowercase();
+                let index = MONTH_NAMES.iter().position(|month| *month == name)?;
+                (month, rest) = (index as u32 + 1, &rest[3..]);
+            }
+            Token::Day => (day, _, rest) = digits(rest, 1, 2)?,
+            Token::Hour => (hour, _, rest) = digits(rest, 1, 2)?,
+            Token::Minute => (minute, _, rest) = digits(rest, 1, 2)?,
+            Token::Second => (second, _, rest) = digits(rest, 1, 2)?,
+            Token::Fraction => {
+                let (value, len, tail) = digits(rest, 1, 9)?;
+                let scale = 10_i64.pow(len.abs_diff(3) as u32);
+                millis = if len >= 3 {
+                    i64::from(value) / scale
+                } else {
+                    i64::from(value) * scale
+                };
+                rest = tail;
+            }
+            Token::Offset => {
+                let (seconds, tail) = offset(rest)?;
+                (zone_offset, rest) = (Some(seconds), tail);
+            }
+        }
+    }
+    let next_month = if month == 12 {
+        days_from_civil(year + 1, 1, 1)
+    } else {
+        days_from_civil(year, month + 1, 1)
+    };
+    let valid = rest.is_empty()
+        && (1..=12).contains(&month)
+        && day >= 1
+        && i64::from(day) <= next_month - days_from_civil(year, month, 1)
+        && hour < 24
+        && minute < 60
+        && second < 60;
+    if !valid {
+        return None;
+    }
+    let local =
+        days_from_civil(year, month, day) * 86_400 + i64::from(hour * 3_600 + minute * 60 + second);
+    Some((local, millis, zone_offset))
+}
+
+/// Options for [`parse_dates`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct DateOptions {
+    formats: Vec<String>,
+    timezone: Option<String>,
+    ambiguous: Ambiguous,
+    empty_as_null: bool,
+    on_error: OnError,
+}
+
+impl Default for DateOptions {
+    fn default() -> Self {
+        Self {
+            formats: Vec::new(),
+            timezone: None,
+            ambiguous: Ambiguous::default(),
+            empty_as_null: true,
+            on_error: OnError::default(),
+        }
+    }
+}
+
+/// Parse a string column of dates and times into a millisecond Timestamp
+/// column, trying each of `options.formats` in order.
+///
+/// `options` is `{formats, timezone?, ambiguous?, emptyAsNull?, onError?}`.
+/// Formats use `strftime` specifiers: `%Y` (four digits), `%y`, `%m`,
+/// `%b` (`Jan`...), `%d`, `%H`, `%M`, `%S`, `%f` (fraction, kept to the
+/// millisecond), `%z` (`Z`, `+05:30`, `+0530`, `+05`) and `%%`; other
+/// characters must match exactly, and unset fields default to the start of
+/// the day or year. A value must match a whole format, so list the most
+/// specific first, e.g. `["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%d"]`.
+///
+/// The result is annotated with `timezone` (see `list_time_zones`), and
+/// values without a `%z` offset are read as wall-clock times there, with
+/// `ambiguous` resolving daylight saving transitions as in
+/// `localize_naive`. Without `timezone` the result is naive, and values
+/// with an offset are converted to UTC wall-clock times. `emptyAsNull` and
+/// `onError` work as for `parse_numbers`; a local time `ambiguous` rejects
+/// counts as unparsable.
+#[wasm_bindgen]
+pub fn parse_dates(column: &Column, options: JsValue) -> std::result::Result<Column, JsValue> {
+    let options: DateOptions =
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?;
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::Parse, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("parse_dates", &field);
+    if options.formats.is_empty() {
+        return Err(context(ArrowWasmError::InvalidInput(
+            "formats must list at least one date format".to_string(),
+        ))
+        .into());
+    }
+    let formats = options
+        .formats
+        .iter()
+        .map(|format| compile(format))
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    let zone = options
+        .timezone
+        .as_deref()
+        .map(Zone::parse)
+        .transpose()
+        .map_err(context)?;
+
+    let parse = |text: &str| {
+        let text = text.trim();
+        if text.is_empty() {
+            return Parsed::Empty;
+        }
+        let Some((local, millis, zone_offset)) =
+            formats.iter().find_map(|tokens| read_date(tokens, text))
+        else {
+            return Parsed::Invalid;
+        };
+        let utc = match (zone_offset, zone) {
+            (Some(seconds), _) => Some(local - i64::from(seconds)),
+            (None, Some(zone)) => zone.to_utc(local, options.ambiguous).ok(),
+            (None, None) => Some(local),
+        };
+        utc.and_then(|utc| utc.checked_mul(1_000)?.checked_add(millis))
+            .map_or(Parsed::Invalid, Parsed::Value)
+    };
+    let mut failures = Failures::default();
+    let parsed =
+        parse_chunks(&chunks, options.empty_as_null, &mut failures, parse).map_err(context)?;
+    if options.on_error == OnError::Fail {
+        if let Some(error) = failures.into_error("parse_dates", &field) {
+            return Err(error.into());
+        }
+    }
+    let time_zone: Option<Arc<str>> = options.timezone.as_deref().map(Arc::from);
+    let chunks = parsed
+        .into_iter()
+        .map(|values| {
+            Arc::new(TimestampMillisecondArray::from(values).with_timezone_opt(time_zone.clone()))
+                as ArrayRef
+        })
+        .collect();
+    let data_type = DataType::Timestamp(TimeUnit::Millisecond, time_zone);
+    Ok(column::store_column(
+        parsed_field(&field, data_type),
+        chunks,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+
+    fn european() -> NumberOptions {
+        NumberOptions {
+            decimal_separator: DecimalSeparator::Comma,
+            thousands_separator: ThousandsSeparator::Dot,
+            currency_symbols: vec!["€".to_string(), "EUR".to_string()],
+            ..NumberOptions::default()
+        }
+    }
+
+    fn value(options: &NumberOptions, text: &str) -> Option<f64> {
+        match options.parse(text) {
+            Parsed::Value(value) => Some(value),
+            Parsed::Empty | Parsed::Invalid => None,
+        }
+    }
+
+    fn strings(values: &[Option<&str>]) -> ArrayRef {
+        Arc::new(StringArray::from(values.to_vec()))
+    }
+
+    #[test]
+    fn european_numbers_use_decimal_commas() {
+        let options = european();
+        for (text, expected) in [
+            ("1.234,56 €", 1234.56),
+            ("-1.234.567,8", -1_234_567.8),
+            ("€ 12,5", 12.5),
+            ("EUR -3", -3.0),
+            ("-€3", -3.0),
+            ("0,5", 0.5),
+            ("1.000", 1000.0),
+        ] {
+            assert_eq!(value(&options, text), Some(expected), "{text:?}");
+        }
+        let spaced = NumberOptions {
+            thousands_separator: ThousandsSeparator::Space,
+            ..european()
+        };
+        assert_eq!(value(&spaced, "1 234 567,5"), Some(1_234_567.5));
+        assert_eq!(value(&spaced, "1\u{a0}234,5 €"), Some(1234.5));
+        // A dot is no decimal point here, and a separator needs a digit before it.
+        assert_eq!(value(&spaced, "1.5"), None);
+        assert_eq!(value(&options, ".500"), None);
+    }
+
+    #[test]
+    fn currency_symbols_sit_on_either_side_of_the_sign() {
+        let options = NumberOptions {
+            thousands_separator: ThousandsSeparator::Comma,
+            currency_symbols: vec!["$".to_string(), "US$".to_string()],
+            ..NumberOptions::default()
+        };
+        for (text, expected) in [
+            ("$1,234.50", 1234.5),
+            ("-$5", -5.0),
+            ("$-5", -5.0),
+            ("+$5", 5.0),
+            ("US$ 10", 10.0),
+            ("10 $", 10.0),
+            ("  $0.25  ", 0.25),
+        ] {
+            assert_eq!(value(&options, text), Some(expected), "{text:?}");
+        }
+        for text in ["$", "$$5", "5 USD", "€5"] {
+            assert_eq!(value(&options, text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn garbage_is_invalid_and_blanks_are_empty() {
+        let options = NumberOptions::default();
+        for text in [
+            "12abc", "--5", "1.2.3", "N/A", "e5", ",5", "1,000", "0x10", "∞",
+        ] {
+            assert!(matches!(options.parse(text), Parsed::Invalid), "{text:?}");
+        }
+        for text in ["", "   ", "\t"] {
+            assert!(matches!(options.parse(text), Parsed::Empty), "{text:?}");
+        }
+        assert_eq!(value(&options, "1e3"), Some(1000.0));
+        assert_eq!(value(&options, "-2.5E-1"), Some(-0.25));
+    }
+
+    /// Mixed values in two chunks, parsed European style.
+    fn mixed() -> Vec<ArrayRef> {
+        vec![
+            strings(&[Some("1.234,5 €"), Some("n/a"), None, Some("")]),
+            strings(&[Some("12abc"), Some("EUR 7"), Some("1,2,3")]),
+        ]
+    }
+
+    #[test]
+    fn unparsable_values_become_null_by_default() {
+        let options = european();
+        let mut failures = Failures::default();
+        let parsed =
+            parse_chunks(&mixed(), true, &mut failures, |text| options.parse(text)).unwrap();
+        assert_eq!(
+            parsed,
+            [
+                vec![Some(1234.5), None, None, None],
+                vec![None, Some(7.0), None]
+            ]
+        );
+        assert_eq!(failures.count, 3);
+    }
+
+    #[test]
+    fn failing_lists_the_first_rows() {
+        let options = european();
+        let field = Field::new("price", DataType::Utf8, true);
+        let mut failures = Failures::default();
+        parse_chunks(&mixed(), false, &mut failures, |text| options.parse(text)).unwrap();
+        let error = failures.into_error("parse_numbers", &field).unwrap();
+        let ArrowWasmError::Compute { row, message, .. } = error else {
+            panic!("expected a compute error, got {error}");
+        };
+        assert_eq!(row, Some(1));
+        assert_eq!(
+            message,
+            r#"4 values could not be parsed: row 1 "n/a", row 3 "", row 4 "12abc", row 6 "1,2,3""#
+        );
+
+        let garbage = strings(&["x"; 8].map(Some));
+        let mut failures = Failures::default();
+        parse_chunks(&[garbage], true, &mut failures, |text| options.parse(text)).unwrap();
+        let error = failures.into_error("parse_numbers", &field).unwrap();
+        assert!(
+            error.to_string().contains(r#"row 4 "x" and 3 more"#),
+            "{error}"
+        );
+
+        let clean = strings(&[Some("1"), None]);
+        let mut failures = Failures::default();
+        parse_chunks(&[clean], true, &mut failures, |text| options.parse(text)).unwrap();
+        assert!(failures.into_error("parse_numbers", &field).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Run-end encoding of columns with long stretches of repeated values.
+//!
+//! A `RunEndEncoded` array stores each run of equal consecutive values once,
+//! with the row where the run ends, so a status column that stays constant
+//! for thousands of rows shrinks to a few entries. Filtering, slicing and
+//! row gathering (`filter_by_mask`, `top_k`, `concat_tables`) work on the
+//! encoded arrays directly; row conversion (`Column.get`, `to_array`,
+//! `to_plain_object`) decodes the values it reads. Kernels that compute on
+//! values, such as sorting by the column or statistics, need a decoded
+//! column (`decode_run_ends`).
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{Array, ArrayRef, AsArray, Int64Array, RunArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Int16Type, Int32Type, Int64Type, RunEndIndexType};
+use arrow_ord::partition::partition;
+use arrow_select::take::take;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Run-end encode `array` with run ends of `run_end_type` (Int16, Int32 or
+/// Int64). Nulls form runs like any other value.
+pub fn encode(array: &ArrayRef, run_end_type: &DataType) -> Result<ArrayRef> {
+    let ranges = partition(&[Arc::clone(array)])?.ranges();
+    let starts = UInt64Array::from_iter_values(ranges.iter().map(|range| range.start as u64));
+    let ends: ArrayRef = Arc::new(Int64Array::from_iter_values(
+        ranges.iter().map(|range| (range.end as u64).cast_signed()),
+    ));
+    let values = take(array.as_ref(), &starts, None)?;
+    let ends = cast_safe(&ends, run_end_type)?;
+    if ends.null_count() > 0 {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "{} rows do not fit {run_end_type} run ends",
+            array.len()
+        )));
+    }
+    Ok(match run_end_type {
+        DataType::Int16 => Arc::new(RunArray::try_new(
+            ends.as_primitive::<Int16Type>(),
+            &values,
+        )?),
+        DataType::Int32 => Arc::new(RunArray::try_new(
+            ends.as_primitive::<Int32Type>(),
+            &values,
+        )?),
+        DataType::Int64 => Arc::new(RunArray::try_new(
+            ends.as_primitive::<Int64Type>(),
+            &values,
+        )?),
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Run ends must be Int16, Int32 or Int64, got {other}"
+            )))
+        }
+    })
+}
+
+fn decode_typed<R: RunEndIndexType>(array: &dyn Array) -> Result<ArrayRef> {
+    let array = array.as_run::<R>();
+    let logical: Vec<u64> = (0..array.len() as u64).collect();
+    let physical = UInt64Array::from_iter_values(
+        array
+            .get_physical_indices(&logical)?
+            .into_iter()
+            .map(|index| index as u64),
+    );
+    Ok(take(array.values().as_ref(), &physical, None)?)
+}
+
+/// Plain array holding the logical values of `array`; arrays that are not
+/// run-end encoded are returned as they are.
+pub fn decode(array: &ArrayRef) -> Result<ArrayRef> {
+    let DataType::RunEndEncoded(run_ends, _) = array.data_type() else {
+        return Ok(Arc::clone(array));
+    };
+    match run_ends.data_type() {
+        DataType::Int16 => decode_typed::<Int16Type>(array.as_ref()),
+        DataType::Int32 => decode_typed::<Int32Type>(array.as_ref()),
+        DataType::Int64 => decode_typed::<Int64Type>(array.as_ref()),
+        other => Err(ArrowWasmError::InvalidInput(format!(
+            "Unsupported run end type {other}"
+        ))),
+    }
+}
+
+/// Run-end encode a column, giving a `RunEndEncoded` column with the same
+/// name and batch layout.
+///
+/// `run_end_type` is `"Int16"`, `"Int32"` (the default) or `"Int64"`; it
+/// bounds the rows per batch (32767 for Int16). Equal consecutive values
+/// (and consecutive nulls) become one run, so the result is small when runs
+/// are long and larger than the input when they are not.
+#[wasm_bindgen]
+pub fn encode_run_ends(
+    column: &Column,
+    run_end_type: Option<String>,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::RunEndEncode, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("encode_run_ends", &field);
+    let run_end_type = match run_end_type.as_deref() {
+        None | Some("Int32") => DataType::Int32,
+        Some("Int16") => DataType::Int16,
+        Some("Int64") => DataType::Int64,
+        Some(other) => {
+            return Err(context(ArrowWasmError::InvalidInput(format!(
+                "Run ends must be Int16, Int32 or Int64, got {other}"
+            )))
+            .into())
+        }
+    };
+    let chunks = chunks
+        .iter()
+        .map(|chunk| encode(chunk, &run_end_type))
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    // The layout `RunArray::try_new` gives its arrays.
+    let data_type = DataType::RunEndEncoded(
+        Arc::new(Field::new("run_ends", run_end_type, false)),
+        Arc::new(Field::new("values", field.data_type().clone(), true)),
+    );
+    let field = field.as_ref().clone().with_data_type(data_type);
+    Ok(column::store_column(Arc::new(field), chunks)?)
+}
+
+/// Expand a `RunEndEncoded` column back to a plain column of its value type,
+/// with the same name and batch layout.
+#[wasm_bindgen]
+pub fn decode_run_ends(column: &Column) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let context = |e: ArrowWasmError| e.in_column("decode_run_ends", &field);
+    let DataType::RunEndEncoded(_, values) = field.data_type() else {
+        return Err(context(ArrowWasmError::InvalidInput(
+            "column is not run-end encoded".to_string(),
+        ))
+        .into());
+    };
+    let chunks = chunks
+        .iter()
+        .map(decode)
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    let field = field
+        .as_ref()
+        .clone()
+        .with_data_type(values.data_type().clone());
+    Ok(column::store_column(Arc::new(field), chunks)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::{encode_stream, read_ipc_batches};
+    use arrow::array::{Int32Array, RecordBatch, StringArray};
+    use arrow::datatypes::Schema;
+    use arrow::ipc::reader::StreamReader;
+    use arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+    use std::io::Cursor;
+
+    /// `rows` statuses in 50 equal runs cycling through three values.
+    fn statuses(rows: usize) -> ArrayRef {
+        let names = ["ok", "degraded", "down"];
+        Arc::new(StringArray::from_iter_values(
+            (0..rows).map(|row| names[row / (rows / 50) % names.len()]),
+        ))
+    }
+
+    #[test]
+    fn long_runs_take_a_fraction_of_the_memory() {
+        let plain = statuses(1_000_000);
+        let encoded = encode(&plain, &DataType::Int32).unwrap();
+        let runs = encoded.as_run::<Int32Type>();
+        assert_eq!(runs.len(), 1_000_000);
+        assert_eq!(runs.run_ends().values().len(), 50);
+
+        let (plain_bytes, encoded_bytes) = (
+            plain.get_array_memory_size(),
+            encoded.get_array_memory_size(),
+        );
+        assert!(
+            encoded_bytes * 1_000 < plain_bytes,
+            "{encoded_bytes} bytes encoded against {plain_bytes} plain"
+        );
+        assert_eq!(decode(&encoded).unwrap().as_ref(), plain.as_ref());
+
+        let error = encode(&plain, &DataType::Int16).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("1000000 rows do not fit Int16 run ends"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn nulls_form_runs() {
+        let plain: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            None,
+            Some("a"),
+            Some("a"),
+            None,
+        ]));
+        let encoded = encode(&plain, &DataType::Int16).unwrap();
+        let runs = encoded.as_run::<Int16Type>();
+        assert_eq!(runs.run_ends().values(), &[2, 4, 5]);
+        assert_eq!(runs.values().null_count(), 2);
+        assert_eq!(decode(&encoded).unwrap().as_ref(), plain.as_ref());
+    }
+
+    /// Two batches of run-end encoded statuses with `run_end_type` ends.
+    fn encoded_batches(run_end_type: &DataType) -> Vec<RecordBatch> {
+        [statuses(1_000), statuses(500)]
+            .iter()
+            .map(|plain| {
+                let column = encode(plain, run_end_type).unwrap();
+                let schema = Schema::new(vec![Field::new(
+                    "status",
+                    column.data_type().clone(),
+                    false,
+                )]);
+                RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap()
+            })
+            .collect()
+    }
+
+    fn file(batches: &[RecordBatch], enable_lz4: bool) -> Vec<u8> {
+        let options = crate::ipc::write_options(enable_lz4).unwrap();
+        let mut data = Vec::new();
+        let mut writer =
+            FileWriter::try_new_with_options(&mut data, &batches[0].schema(), options).unwrap();
+        for batch in batches {
+            writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        data
+    }
+
+    #[test]
+    fn encoded_columns_read_back_from_ipc() {
+        for run_end_type in [DataType::Int16, DataType::Int32, DataType::Int64] {
+            let batches = encoded_batches(&run_end_type);
+            let schema = batches[0].schema();
+            for enable_lz4 in [false, true] {
+                for data in [
+                    encode_stream(&schema, &batches, enable_lz4).unwrap(),
+                    file(&batches, enable_lz4),
+                ] {
+                    let (read_schema, read) = read_ipc_batches(&data, None).unwrap();
+                    assert_eq!(read_schema, schema, "{run_end_type} lz4={enable_lz4}");
+                    assert_eq!(read, batches, "{run_end_type} lz4={enable_lz4}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn written_streams_follow_the_ipc_format() {
+        // The stock arrow-rs reader, which the Arrow integration suite checks
+        // against the C++, Java, Go and JavaScript writers and readers,
+        // accepts what the crate writes.
+        let batches = encoded_batches(&DataType::Int32);
+        let data = encode_stream(&batches[0].schema(), &batches, false).unwrap();
+        let reader = StreamReader::try_new(Cursor::new(data), None).unwrap();
+        let DataType::RunEndEncoded(run_ends, values) =
+            reader.schema().field(0).data_type().clone()
+        else {
+            panic!("the column is not run-end encoded");
+        };
+        assert_eq!(
+            (run_ends.name().as_str(), run_ends.is_nullable()),
+            ("run_ends", false)
+        );
+        assert_eq!(
+            (values.name().as_str(), values.is_nullable()),
+            ("values", true)
+        );
+        let read: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(read, batches);
+
+        // A stream from a writer with default options reads back too.
+        let mut data = Vec::new();
+        let mut writer = arrow::ipc::writer::StreamWriter::try_new_with_options(
+            &mut data,
+            &batches[0].schema(),
+            IpcWriteOptions::default(),
+        )
+        .unwrap();
+        for batch in &batches {
+            writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        let (_, read) = read_ipc_batches(&data, None).unwrap();
+        let values = decode(read[1].column(0)).unwrap();
+        assert_eq!(values.as_ref(), statuses(500).as_ref());
+    }
+
+    #[test]
+    fn three_long_runs_encode_to_three_entries() {
+        let values: Vec<Option<i32>> = [Some(7), None, Some(-2)]
+            .iter()
+            .flat_map(|&value| std::iter::repeat_n(value, 10_000))
+            .collect();
+        let plain: ArrayRef = Arc::new(Int32Array::from(values));
+        let column = column::store_column(
+            Arc::new(Field::new("x", DataType::Int32, true)),
+            vec![Arc::clone(&plain)],
+        )
+        .unwrap();
+        let encoded = column.encode_run_length(None).unwrap();
+        let (field, chunks) = encoded.field_and_chunks().unwrap();
+        assert!(matches!(field.data_type(), DataType::RunEndEncoded(..)));
+        let runs = chunks[0].as_run::<Int32Type>();
+        assert_eq!(runs.len(), 30_000);
+        assert_eq!(runs.run_ends().values(), &[10_000, 20_000, 30_000]);
+        let expected = Int32Array::from(vec![Some(7), None, Some(-2)]);
+        assert_eq!(runs.values().as_ref(), &expected as &dyn Array);
+        assert!(chunks[0].get_array_memory_size() * 100 < plain.get_array_memory_size());
+
+        let decoded = encoded.decode_run_length().unwrap();
+        let (field, chunks) = decoded.field_and_chunks().unwrap();
+        assert_eq!(field.data_type(), &DataType::Int32);
+        assert_eq!(chunks[0].as_ref(), plain.as_ref());
+    }
+}
@@ -0,0 +1,382 @@
+//! Lags, leads and period-over-period deltas of a column.
+//!
+//! The column is treated as one sequence across batches, so a shift carries
+//! values over batch boundaries; results keep the source batch layout.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::edit::scalar_from_js;
+use crate::errors::{ArrowWasmError, Result};
+use crate::table::align_chunks;
+use arrow::array::{make_array, new_null_array, Array, ArrayRef, AsArray, Float64Array};
+use arrow::compute::kernels::numeric::sub;
+use arrow::datatypes::{DataType, Field, Float64Type};
+use arrow_select::concat::concat;
+use arrow_select::interleave::interleave;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// All chunks as one array, plus the chunk lengths to restore.
+fn combined(chunks: &[ArrayRef], data_type: &DataType) -> Result<(ArrayRef, Vec<usize>)> {
+    let lengths = chunks.iter().map(Array::len).collect();
+    if chunks.is_empty() {
+        return Ok((new_null_array(data_type, 0), lengths));
+    }
+    let refs: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+    Ok((concat(&refs)?, lengths))
+}
+
+/// `array` reinterpreted as `data_type`, which must share its layout.
+fn retyped(array: &dyn Array, data_type: &DataType) -> Result<ArrayRef> {
+    Ok(make_array(
+        array
+            .to_data()
+            .into_builder()
+            .data_type(data_type.clone())
+            .build()?,
+    ))
+}
+
+/// `values` moved down by `periods` rows (up when negative), with `fill`
+/// (a one-element array) in the rows left empty at the edge.
+fn shifted(values: &dyn Array, periods: i64, fill: &dyn Array) -> Result<ArrayRef> {
+    let len = i64::try_from(values.len()).unwrap_or(i64::MAX);
+    let indices: Vec<(usize, usize)> = (0..len)
+        .map(|row| {
+            let source = row - periods;
+            usize::try_from(source)
+                .ok()
+                .filter(|_| source < len)
+                .map_or((1, 0), |source| (0, source))
+        })
+        .collect();
+    Ok(interleave(&[values, fill], &indices)?)
+}
+
+/// Type deltas are computed in: integers as Int64 and floats as Float64;
+/// decimals and temporal types keep their type, timestamps without their
+/// zone.
+fn delta_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        integer if integer.is_integer() => Some(DataType::Int64),
+        float if float.is_floating() => Some(DataType::Float64),
+        DataType::Decimal32(_, _)
+        | DataType::Decimal64(_, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _)
+        | DataType::Date32
+        | DataType::Date64
+        | DataType::Duration(_) => Some(data_type.clone()),
+        DataType::Timestamp(unit, _) => Some(DataType::Timestamp(*unit, None)),
+        _ => None,
+    }
+}
+
+/// Store `values` as a column named like `field`, with `chunks`' layout.
+fn store_aligned(field: &Field, values: &ArrayRef, lengths: &[usize]) -> Result<Column> {
+    let field = Field::new(
+        field.name(),
+        values.data_type().clone(),
+        field.is_nullable(),
+    );
+    column::store_column(
+        Arc::new(field),
+        align_chunks(&[Arc::clone(values)], lengths)?,
+    )
+}
+
+/// Values of the column `periods` rows earlier (later when `periods` is
+/// negative): row `i` holds row `i - periods`.
+///
+/// Rows shifted in from past the edge are null, or `fillValue` from
+/// `options` (`{fillValue?}`), converted to the column type like
+/// `set_value` does. Values carry over batch boundaries. Any column type is
+/// supported.
+#[wasm_bindgen]
+pub fn shift(
+    column: &Column,
+    periods: i32,
+    options: JsValue,
+) -> std::result::Result<Column, JsValue> {
+    let fill_value = if options.is_object() {
+        js_sys::Reflect::get(&options, &"fillValue".into())?
+    } else {
+        JsValue::UNDEFINED
+    };
+    let (field, chunks) = column.field_and_chunks()?;
+    let context = |e: ArrowWasmError| e.in_column("shift", &field);
+    let (values, lengths) = combined(&chunks, field.data_type()).map_err(context)?;
+    let fill = if fill_value.is_undefined() || fill_value.is_null() {
+        new_null_array(field.data_type(), 1)
+    } else {
+        scalar_from_js(&fill_value, field.data_type()).map_err(context)?
+    };
+    let result = shifted(values.as_ref(), i64::from(periods), fill.as_ref()).map_err(context)?;
+    Ok(column::store_column(
+        Arc::clone(&field),
+        align_chunks(&[result], &lengths).map_err(context)?,
+    )?)
+}
+
+/// Difference between each value and the one `periods` rows earlier
+/// (`1` by default; negative compares with later rows).
+///
+/// Integer columns give Int64 and float columns Float64; decimals keep
+/// their type, and timestamps and dates give a Duration (dates in
+/// seconds). Rows without a value `periods` away, and rows where either
+/// value is null, are null. Integer overflow fails.
+#[wasm_bindgen]
+pub fn diff(column: &Column, periods: Option<i32>) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::Diff, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("diff", &field);
+    let to = delta_type(field.data_type()).ok_or_else(|| {
+        ArrowWasmError::Internal(format!(
+            "no delta type for {:?}, which supports diff",
+            field.data_type()
+        ))
+    })?;
+    let (values, lengths) = combined(&chunks, field.data_type()).map_err(context)?;
+    // Deltas do not depend on the zone, and arrow only subtracts timestamps
+    // in named zones with its chrono-tz feature.
+    let values = if let DataType::Timestamp(unit, Some(_)) = field.data_type() {
+        retyped(values.as_ref(), &DataType::Timestamp(*unit, None))
+    } else {
+        cast_safe(&values, &to)
+    }
+    .map_err(context)?;
+    let fill = new_null_array(&to, 1);
+    let previous = shifted(
+        values.as_ref(),
+        i64::from(periods.unwrap_or(1)),
+        fill.as_ref(),
+    )
+    .map_err(context)?;
+    let deltas = sub(&values, &previous).map_err(|e| context(e.into()))?;
+    Ok(store_aligned(&field, &deltas, &lengths).map_err(context)?)
+}
+
+/// Relative change `(x - previous) / previous` against the value `periods`
+/// rows earlier (`1` by default; negative compares with later rows), as
+/// Float64.
+///
+/// Rows without a value `periods` away, rows where either value is null and
+/// rows whose previous value is 0 are null.
+#[wasm_bindgen]
+pub fn percent_change(
+    column: &Column,
+    periods: Option<i32>,
+) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::PercentChange, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("percent_change", &field);
+    let (values, lengths) = combined(&chunks, field.data_type()).map_err(context)?;
+    let values = cast_safe(&values, &DataType::Float64).map_err(context)?;
+    let fill = new_null_array(&DataType::Float64, 1);
+    let previous = shifted(
+        values.as_ref(),
+        i64::from(periods.unwrap_or(1)),
+        fill.as_ref(),
+    )
+    .map_err(context)?;
+    let changes: Float64Array = values
+        .as_primitive::<Float64Type>()
+        .iter()
+        .zip(previous.as_primitive::<Float64Type>().iter())
+        .map(|(value, previous)| match (value, previous) {
+            (Some(value), Some(previous)) if previous != 0.0 => Some((value - previous) / previous),
+            _ => None,
+        })
+        .collect();
+    let changes: ArrayRef = Arc::new(changes);
+    Ok(store_aligned(&field, &changes, &lengths).map_err(context)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Date32Array, Int32Array, Int64Array, TimestampMillisecondArray};
+    use arrow::datatypes::{DurationMillisecondType, DurationSecondType, Int64Type, TimeUnit};
+
+    /// `[1, 4]`, `[9]` and `[16, 25]` as three Int32 batches.
+    fn squares() -> Vec<ArrayRef> {
+        vec![
+            Arc::new(Int32Array::from(vec![1, 4])),
+            Arc::new(Int32Array::from(vec![9])),
+            Arc::new(Int32Array::from(vec![16, 25])),
+        ]
+    }
+
+    fn stored(chunks: Vec<ArrayRef>) -> Column {
+        let field = Field::new("x", chunks[0].data_type().clone(), true);
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    /// Batch lengths and type of a column.
+    fn layout(column: &Column) -> (Vec<usize>, DataType) {
+        let (field, chunks) = column.field_and_chunks().unwrap();
+        let lengths = chunks.iter().map(Array::len).collect();
+        (lengths, field.data_type().clone())
+    }
+
+    fn values(column: &Column) -> ArrayRef {
+        let (field, chunks) = column.field_and_chunks().unwrap();
+        combined(&chunks, field.data_type()).unwrap().0
+    }
+
+    #[test]
+    fn shifts_carry_values_across_batches() {
+        let (values, lengths) = combined(&squares(), &DataType::Int32).unwrap();
+        assert_eq!(lengths, [2, 1, 2]);
+        let null = new_null_array(&DataType::Int32, 1);
+        let fill = Int32Array::from(vec![0]);
+        for (periods, fill, expected) in [
+            (
+                1,
+                null.as_ref(),
+                vec![None, Some(1), Some(4), Some(9), Some(16)],
+            ),
+            (2, &fill, vec![Some(0), Some(0), Some(1), Some(4), Some(9)]),
+            (
+                -2,
+                null.as_ref(),
+                vec![Some(9), Some(16), Some(25), None, None],
+            ),
+            (
+                -1,
+                &fill,
+                vec![Some(4), Some(9), Some(16), Some(25), Some(0)],
+            ),
+            (
+                0,
+                null.as_ref(),
+                vec![Some(1), Some(4), Some(9), Some(16), Some(25)],
+            ),
+            (7, null.as_ref(), vec![None; 5]),
+            (-7, &fill, vec![Some(0); 5]),
+        ] {
+            let result = shifted(values.as_ref(), periods, fill).unwrap();
+            assert_eq!(result.as_ref(), &Int32Array::from(expected), "{periods}");
+        }
+    }
+
+    #[test]
+    fn diff_compares_rows_in_other_batches() {
+        let column = stored(squares());
+        let forward = diff(&column, None).unwrap();
+        assert_eq!(layout(&forward), (vec![2, 1, 2], DataType::Int64));
+        assert_eq!(
+            values(&forward).as_primitive::<Int64Type>(),
+            &vec![None, Some(3), Some(5), Some(7), Some(9)].into()
+        );
+        let backward = diff(&column, Some(-2)).unwrap();
+        assert_eq!(
+            values(&backward).as_primitive::<Int64Type>(),
+            &vec![Some(-8), Some(-12), Some(-16), None, None].into()
+        );
+    }
+
+    #[test]
+    fn temporal_diffs_are_durations() {
+        let timestamps = stored(vec![
+            Arc::new(TimestampMillisecondArray::from(vec![0, 1_500]).with_timezone("Asia/Tokyo")),
+            Arc::new(
+                TimestampMillisecondArray::from(vec![Some(61_500), None])
+                    .with_timezone("Asia/Tokyo"),
+            ),
+        ]);
+        let deltas = diff(&timestamps, None).unwrap();
+        assert_eq!(
+            layout(&deltas),
+            (vec![2, 2], DataType::Duration(TimeUnit::Millisecond))
+        );
+        assert_eq!(
+            values(&deltas).as_primitive::<DurationMillisecondType>(),
+            &vec![None, Some(1_500), Some(60_000), None].into()
+        );
+
+        let dates = stored(vec![Arc::new(Date32Array::from(vec![0, 1, 31]))]);
+        let deltas = diff(&dates, Some(-1)).unwrap();
+        assert_eq!(
+            layout(&deltas),
+            (vec![3], DataType::Duration(TimeUnit::Second))
+        );
+        assert_eq!(
+            values(&deltas).as_primitive::<DurationSecondType>(),
+            &vec![Some(-86_400), Some(-30 * 86_400), None].into()
+        );
+    }
+
+    #[test]
+    fn percent_changes_skip_zero_baselines() {
+        let column = stored(vec![
+            Arc::new(Int32Array::from(vec![Some(0), Some(5)])),
+            Arc::new(Int32Array::from(vec![None, Some(10), Some(15)])),
+        ]);
+        let changes = percent_change(&column, None).unwrap();
+        assert_eq!(layout(&changes), (vec![2, 3], DataType::Float64));
+        assert_eq!(
+            values(&changes).as_primitive::<Float64Type>(),
+            &vec![None, None, None, None, Some(0.5)].into()
+        );
+        let ahead = percent_change(&column, Some(-1)).unwrap();
+        assert_eq!(
+            values(&ahead).as_primitive::<Float64Type>(),
+            &vec![Some(-1.0), None, None, Some(-1.0 / 3.0), None].into()
+        );
+    }
+
+    #[test]
+    fn diff_of_a_known_sequence_and_its_nulls() {
+        // Fibonacci numbers: the differences are the sequence shifted by two.
+        let fibonacci = stored(vec![
+            Arc::new(Int64Array::from(vec![1, 1, 2, 3])),
+            Arc::new(Int64Array::from(vec![5, 8, 13, 21])),
+        ]);
+        let deltas = fibonacci.diff(Some(1)).unwrap();
+        assert_eq!(layout(&deltas), (vec![4, 4], DataType::Int64));
+        assert_eq!(
+            values(&deltas).as_primitive::<Int64Type>(),
+            &vec![
+                None,
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(5),
+                Some(8)
+            ]
+            .into()
+        );
+
+        // A null has no difference of its own and leaves the row after it
+        // (before it, for negative periods) without a baseline.
+        let gappy = stored(vec![
+            Arc::new(Int64Array::from(vec![Some(10), Some(13)])),
+            Arc::new(Int64Array::from(vec![None, Some(20), Some(26)])),
+        ]);
+        assert_eq!(
+            values(&gappy.diff(Some(1)).unwrap()).as_primitive::<Int64Type>(),
+            &vec![None, Some(3), None, None, Some(6)].into()
+        );
+        assert_eq!(
+            values(&gappy.diff(Some(-1)).unwrap()).as_primitive::<Int64Type>(),
+            &vec![Some(-3), None, None, Some(-6), None].into()
+        );
+
+        let floats = stored(vec![Arc::new(Float64Array::from(vec![
+            Some(0.5),
+            None,
+            Some(2.0),
+            Some(1.25),
+        ]))]);
+        let deltas = floats.diff(Some(1)).unwrap();
+        assert_eq!(layout(&deltas), (vec![4], DataType::Float64));
+        assert_eq!(
+            values(&deltas).as_primitive::<Float64Type>(),
+            &vec![None, None, None, Some(-0.75)].into()
+        );
+    }
+}
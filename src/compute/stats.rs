@@ -0,0 +1,933 @@
+//! Column aggregates.
+//!
+//! Every aggregate skips nulls, and the numeric ones (sum, mean, median,
+//! variance, standard deviation, quantiles and histogram) skip NaN as
+//! well, so one NaN does not poison the result. A column with no values
+//! left (empty, all-null or all-NaN) gives `null` from every aggregate,
+//! never an error or a neutral value such as `0`; errors are reserved for
+//! column types an aggregate does not support. The sample `variance` and
+//! `std_dev` also give `null` for a single value.
+
+use crate::column::Column;
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::compute::keys::{first_key, key_domain, Key, KeyDomain};
+use crate::convert::{
+    check_field, set_property, table_options, value_to_js, ConversionOptions, TemporalAs,
+};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::datatypes::{
+    DataType, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, TimeUnit, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_ord::sort::{sort_to_indices, SortOptions};
+use arrow_select::concat::concat;
+use wasm_bindgen::prelude::*;
+
+const NANOS_PER_MILLI: i128 = 1_000_000;
+
+/// One-element array holding the smallest (or `largest`) non-null value of
+/// `chunks`, or `None` when every value is null.
+pub fn extreme(chunks: &[ArrayRef], largest: bool) -> Result<Option<ArrayRef>> {
+    let options = SortOptions {
+        descending: largest,
+        nulls_first: false,
+    };
+    let pick = |array: &dyn Array| -> Result<ArrayRef> {
+        let indices = sort_to_indices(array, Some(options), Some(1))?;
+        Ok(array.slice(indices.value(0) as usize, 1))
+    };
+    let winners = chunks
+        .iter()
+        .filter(|chunk| chunk.null_count() < chunk.len())
+        .map(|chunk| pick(chunk.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    match winners.len() {
+        0 => Ok(None),
+        1 => Ok(winners.into_iter().next()),
+        _ => {
+            let refs: Vec<&dyn Array> = winners.iter().map(AsRef::as_ref).collect();
+            Ok(Some(pick(concat(&refs)?.as_ref())?))
+        }
+    }
+}
+
+/// Name of the stored unit of a temporal type and its length in nanoseconds.
+const fn temporal_unit(data_type: &DataType) -> Option<(&'static str, i128)> {
+    let unit = match data_type {
+        DataType::Date32 => return Some(("day", 86_400 * 1_000 * NANOS_PER_MILLI)),
+        DataType::Date64 => return Some(("ms", NANOS_PER_MILLI)),
+        DataType::Timestamp(unit, _)
+        | DataType::Time32(unit)
+        | DataType::Time64(unit)
+        | DataType::Duration(unit) => unit,
+        _ => return None,
+    };
+    Some(match unit {
+        TimeUnit::Second => ("s", 1_000 * NANOS_PER_MILLI),
+        TimeUnit::Millisecond => ("ms", NANOS_PER_MILLI),
+        TimeUnit::Microsecond => ("us", 1_000),
+        TimeUnit::Nanosecond => ("ns", 1),
+    })
+}
+
+/// Stored integer of a one-element temporal array.
+fn raw_temporal(value: &dyn Array) -> Result<i128> {
+    match first_key(value)? {
+        Some(Key::Int(raw)) => Ok(raw),
+        _ => Err(ArrowWasmError::Internal(format!(
+            "expected a non-null temporal value, got {:?}",
+            value.data_type()
+        ))),
+    }
+}
+
+/// Convert a one-element array holding an aggregate result.
+///
+/// Sub-millisecond timestamps cannot be a `Date` or epoch-ms `number`
+/// without losing precision; those fall back to the raw `bigint`.
+fn extreme_to_js(value: &dyn Array, options: ConversionOptions) -> Result<JsValue> {
+    let raw_per_milli = match value.data_type() {
+        DataType::Timestamp(TimeUnit::Microsecond, _) => 1_000,
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => 1_000_000,
+        _ => 1,
+    };
+    if options.temporal_as != TemporalAs::BigInt && raw_per_milli > 1 {
+        let raw = raw_temporal(value)?;
+        if raw % raw_per_milli != 0 {
+            return Ok(js_sys::BigInt::from(raw).into());
+        }
+    }
+    value_to_js(value, 0, options)
+}
+
+/// Conversion options of the table `handle`, with `temporal_as` replacing
+/// the policy's when given.
+fn extreme_options(handle: TableHandle, temporal_as: Option<String>) -> Result<ConversionOptions> {
+    let mut options = table_options(handle);
+    if let Some(name) = temporal_as {
+        options.temporal_as = TemporalAs::parse(&name)?;
+    }
+    Ok(options)
+}
+
+fn column_extreme(column: &Column, temporal_as: Option<String>, largest: bool) -> Result<JsValue> {
+    let options = extreme_options(column.handle(), temporal_as)?;
+    let (field, chunks) = column.field_and_chunks()?;
+    let operation = if largest {
+        Operation::Max
+    } else {
+        Operation::Min
+    };
+    require(operation, &field)?;
+    check_field(&field, options)?;
+    let op = operation.name();
+    extreme(&chunks, largest)
+        .map_err(|e| e.in_column(op, &field))?
+        .map_or(Ok(JsValue::NULL), |value| {
+            extreme_to_js(value.as_ref(), options).map_err(|e| e.in_column(op, &field))
+        })
+}
+
+/// Smallest non-null value, or `null` for empty and all-null columns.
+///
+/// Any orderable type is supported. The value follows the table's
+/// `ConversionPolicy`, with `temporal_as` (`"date"`, `"number"` or
+/// `"bigint"`) replacing its temporal representation when given.
+#[wasm_bindgen]
+pub fn column_min(
+    column: &Column,
+    temporal_as: Option<String>,
+) -> std::result::Result<JsValue, JsValue> {
+    Ok(column_extreme(column, temporal_as, false)?)
+}
+
+/// Largest non-null value, or `null` for empty and all-null columns.
+///
+/// Any orderable type is supported. The value follows the table's
+/// `ConversionPolicy`, with `temporal_as` (`"date"`, `"number"` or
+/// `"bigint"`) replacing its temporal representation when given.
+#[wasm_bindgen]
+pub fn column_max(
+    column: &Column,
+    temporal_as: Option<String>,
+) -> std::result::Result<JsValue, JsValue> {
+    Ok(column_extreme(column, temporal_as, true)?)
+}
+
+fn table_time_range(
+    handle: TableHandle,
+    column: Option<String>,
+    temporal_as: Option<String>,
+) -> Result<JsValue> {
+    let options = extreme_options(handle, temporal_as)?;
+    let table = mem::get_table(handle)?;
+    let field = match &column {
+        Some(name) => table
+            .schema
+            .field_with_name(name)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?,
+        None => table
+            .schema
+            .fields()
+            .iter()
+            .find(|field| key_domain(field.data_type()) == Some(KeyDomain::Temporal))
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput("Table has no temporal column".to_string())
+            })?,
+    };
+    let Some((unit, unit_nanos)) = temporal_unit(field.data_type()) else {
+        return Err(
+            ArrowWasmError::InvalidInput("not a temporal column".to_string())
+                .in_column("time_range", field),
+        );
+    };
+    let chunks = table.get_column_by_name(field.name())?;
+
+    let result = js_sys::Object::new();
+    set_property(&result, "column", &field.name().into())?;
+    let timezone = match field.data_type() {
+        DataType::Timestamp(_, Some(tz)) => JsValue::from_str(tz),
+        _ => JsValue::NULL,
+    };
+    set_property(&result, "timezone", &timezone)?;
+
+    let context = |e: ArrowWasmError| e.in_column("time_range", field);
+    let (Some(min), Some(max)) = (
+        extreme(&chunks, false).map_err(context)?,
+        extreme(&chunks, true).map_err(context)?,
+    ) else {
+        for key in ["min", "max", "span"] {
+            set_property(&result, key, &JsValue::NULL)?;
+        }
+        return Ok(result.into());
+    };
+    let min_js = extreme_to_js(min.as_ref(), options).map_err(context)?;
+    let max_js = extreme_to_js(max.as_ref(), options).map_err(context)?;
+    set_property(&result, "min", &min_js)?;
+    set_property(&result, "max", &max_js)?;
+
+    let span = raw_temporal(max.as_ref()).map_err(context)?
+        - raw_temporal(min.as_ref()).map_err(context)?;
+    let descriptor = js_sys::Object::new();
+    set_property(&descriptor, "unit", &unit.into())?;
+    set_property(&descriptor, "value", &js_sys::BigInt::from(span).into())?;
+    let millis = (span * unit_nanos) as f64 / NANOS_PER_MILLI as f64;
+    set_property(&descriptor, "milliseconds", &millis.into())?;
+    set_property(&result, "span", &descriptor)?;
+    Ok(result.into())
+}
+
+/// Time range of a temporal column as `{column, min, max, span, timezone}`.
+///
+/// Without `column` the first Date/Timestamp/Time/Duration column is used.
+/// `min`/`max` follow `temporal_as` like `column_min`; `span` is
+/// `{unit, value, milliseconds}` with `value` the exact difference as a
+/// `bigint` in the column's stored unit. `timezone` is the column's
+/// timestamp timezone, if any. Empty and all-null columns give null
+/// `min`, `max` and `span`.
+#[wasm_bindgen]
+pub fn time_range(
+    handle: TableHandle,
+    column: Option<String>,
+    temporal_as: Option<String>,
+) -> std::result::Result<JsValue, JsValue> {
+    Ok(table_time_range(handle, column, temporal_as)?)
+}
+
+/// Non-null, non-NaN values of a numeric column as `f64`.
+fn numeric_values(column: &Column, operation: Operation) -> Result<Vec<f64>> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(operation, &field)?;
+    let op = operation.name();
+    let mut values = Vec::with_capacity(chunks.iter().map(Array::len).sum());
+    for chunk in &chunks {
+        let floats = cast_safe(chunk, &DataType::Float64).map_err(|e| e.in_column(op, &field))?;
+        values.extend(
+            floats
+                .as_primitive::<Float64Type>()
+                .iter()
+                .flatten()
+                .filter(|v| !v.is_nan()),
+        );
+    }
+    Ok(values)
+}
+
+fn number_or_null(value: Option<f64>) -> JsValue {
+    value.map_or(JsValue::NULL, JsValue::from_f64)
+}
+
+fn sum_of(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum())
+}
+
+fn mean_of(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sum of the non-null values of a numeric column, as a `number`.
+///
+/// Values are added in floating point; use `column_sum_exact` for integer
+/// columns whose sum may pass 2^53. Empty and all-null columns give `null`,
+/// not `0`.
+#[wasm_bindgen]
+pub fn column_sum(column: &Column) -> std::result::Result<JsValue, JsValue> {
+    Ok(number_or_null(sum_of(&numeric_values(
+        column,
+        Operation::Sum,
+    )?)))
+}
+
+macro_rules! checked_sum {
+    ($array:expr, $total:expr, $arrow:ty) => {
+        $array
+            .as_primitive::<$arrow>()
+            .iter()
+            .flatten()
+            .try_fold($total, |total, value| total.checked_add(i128::from(value)))
+    };
+}
+
+/// Exact sum of the non-null values of integer `chunks`, or `None` when the
+/// sum leaves the `i128` range.
+fn integer_sum(chunks: &[ArrayRef]) -> Result<Option<i128>> {
+    let mut total = 0_i128;
+    for chunk in chunks {
+        let sum = match chunk.data_type() {
+            DataType::Int8 => checked_sum!(chunk, total, Int8Type),
+            DataType::Int16 => checked_sum!(chunk, total, Int16Type),
+            DataType::Int32 => checked_sum!(chunk, total, Int32Type),
+            DataType::Int64 => checked_sum!(chunk, total, Int64Type),
+            DataType::UInt8 => checked_sum!(chunk, total, UInt8Type),
+            DataType::UInt16 => checked_sum!(chunk, total, UInt16Type),
+            DataType::UInt32 => checked_sum!(chunk, total, UInt32Type),
+            DataType::UInt64 => checked_sum!(chunk, total, UInt64Type),
+            other => {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "expected an integer column, got {other:?}"
+                )))
+            }
+        };
+        let Some(sum) = sum else {
+            return Ok(None);
+        };
+        total = sum;
+    }
+    Ok(Some(total))
+}
+
+/// Exact sum of the non-null values of an integer column, as a `bigint`.
+///
+/// Unlike `column_sum`, which adds in floating point, every value is added
+/// as a 128-bit integer, so sums past 2^53, or past the 64-bit range,
+/// stay exact. A sum that would leave the 128-bit range fails. Empty and
+/// all-null columns give `null`.
+#[wasm_bindgen]
+pub fn column_sum_exact(column: &Column) -> std::result::Result<JsValue, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::SumExact, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("sum_exact", &field);
+    let total = integer_sum(&chunks)
+        .map_err(context)?
+        .ok_or_else(|| context(ArrowWasmError::InvalidInput("sum overflows".to_string())))?;
+    if chunks.iter().all(|chunk| chunk.null_count() == chunk.len()) {
+        return Ok(JsValue::NULL);
+    }
+    Ok(js_sys::BigInt::from(total).into())
+}
+
+/// Arithmetic mean of the non-null values of a numeric column.
+///
+/// Empty and all-null columns give `null`.
+#[wasm_bindgen]
+pub fn column_mean(column: &Column) -> std::result::Result<JsValue, JsValue> {
+    Ok(number_or_null(mean_of(&numeric_values(
+        column,
+        Operation::Mean,
+    )?)))
+}
+
+/// Median of the non-null values of a numeric column; the mean of the two
+/// middle values when their count is even.
+///
+/// Empty and all-null columns give `null`.
+#[wasm_bindgen]
+pub fn column_median(column: &Column) -> std::result::Result<JsValue, JsValue> {
+    Ok(number_or_null(median_of(column)?))
+}
+
+fn median_of(column: &Column) -> Result<Option<f64>> {
+    let mut values = numeric_values(column, Operation::Median)?;
+    values.sort_by(f64::total_cmp);
+    Ok(quantile_of(&values, 0.5))
+}
+
+/// The `q` quantile of ascending `sorted`, interpolating linearly between
+/// the two values around position `q * (len - 1)`; `None` when empty.
+fn quantile_of(sorted: &[f64], q: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let position = q * last as f64;
+    let below = usize::try_from(position as i64).unwrap_or(0).min(last);
+    let fraction = position - below as f64;
+    if below == last || fraction <= 0.0 {
+        return Some(sorted[below]);
+    }
+    Some(sorted[below].mul_add(1.0 - fraction, sorted[below + 1] * fraction))
+}
+
+/// The `q` quantile of the non-null values of a numeric column, or `None`
+/// for empty and all-null columns; `q` must be in `[0, 1]`.
+pub fn quantile(column: &Column, q: f64) -> Result<Option<f64>> {
+    if !(0.0..=1.0).contains(&q) {
+        let field = column.field()?;
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "quantile must be between 0 and 1, got {q}"
+        ))
+        .in_column("quantile", &field));
+    }
+    let mut values = numeric_values(column, Operation::Quantile)?;
+    values.sort_by(f64::total_cmp);
+    Ok(quantile_of(&values, q))
+}
+
+/// The quantiles `qs` of the non-null values of a numeric column, in the
+/// order given, sorting the values once; each is `None` for empty and
+/// all-null columns. Every `q` must be in `[0, 1]`.
+pub fn quantiles(column: &Column, qs: &[f64]) -> Result<Vec<Option<f64>>> {
+    if let Some((position, q)) = qs
+        .iter()
+        .enumerate()
+        .find(|(_, q)| !(0.0..=1.0).contains(*q))
+    {
+        let field = column.field()?;
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "quantile must be between 0 and 1, got {q} at position {position}"
+        ))
+        .in_column("quantile", &field));
+    }
+    let mut values = numeric_values(column, Operation::Quantile)?;
+    values.sort_by(f64::total_cmp);
+    Ok(qs.iter().map(|&q| quantile_of(&values, q)).collect())
+}
+
+/// Variance of the non-null values of `column`: the sample variance
+/// (divisor `n - 1`) when `sample`, else the population variance (divisor
+/// `n`). `None` when there are too few values for the divisor.
+fn variance_of(column: &Column, sample: bool) -> Result<Option<f64>> {
+    let values = numeric_values(column, Operation::Variance)?;
+    let divisor = values.len().saturating_sub(usize::from(sample));
+    if divisor == 0 {
+        return Ok(None);
+    }
+    let mean = mean_of(&values).unwrap_or(0.0);
+    let squares: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    Ok(Some(squares / divisor as f64))
+}
+
+/// Variance of the non-null values of a numeric column: the sample
+/// variance (divisor `n - 1`) unless `sample` is `false`, which gives the
+/// population variance (divisor `n`).
+///
+/// Columns with fewer than two non-null values (one for the population
+/// variance) give `null`.
+#[wasm_bindgen]
+pub fn column_variance(
+    column: &Column,
+    sample: Option<bool>,
+) -> std::result::Result<JsValue, JsValue> {
+    Ok(number_or_null(variance_of(column, sample.unwrap_or(true))?))
+}
+
+/// Standard deviation of the non-null values of a numeric column, the
+/// square root of `column_variance` with the same `sample` flag.
+#[wasm_bindgen]
+pub fn column_std_dev(
+    column: &Column,
+    sample: Option<bool>,
+) -> std::result::Result<JsValue, JsValue> {
+    Ok(number_or_null(std_dev_of(column, sample.unwrap_or(true))?))
+}
+
+/// Square root of [`variance_of`].
+fn std_dev_of(column: &Column, sample: bool) -> Result<Option<f64>> {
+    Ok(variance_of(column, sample)?.map(f64::sqrt))
+}
+
+/// Edges and counts of an equal-width histogram of the finite `values`
+/// between `min` and `max` in `bins` buckets, or in one bucket when `min`
+/// equals `max`.
+fn bin_counts(values: &[f64], min: f64, max: f64, bins: usize) -> (Vec<f64>, Vec<u64>) {
+    let bins = if min < max { bins } else { 1 };
+    // A range wider than f64::MAX (say -f64::MAX to f64::MAX) overflows to
+    // infinity, which would make every edge NaN; measure it in halves then.
+    let halved = (max - min).is_infinite();
+    let scale = if halved { 2.0 } else { 1.0 };
+    let width = (max / scale - min / scale) / bins as f64;
+    let edges: Vec<f64> = (0..=bins)
+        .map(|edge| {
+            if edge == bins {
+                max
+            } else if halved {
+                (edge as f64 * width).mul_add(scale, min)
+            } else {
+                (edge as f64).mul_add(width, min)
+            }
+        })
+        .collect();
+    let mut counts = vec![0_u64; bins];
+    for &value in values {
+        let mut bin = if width > 0.0 {
+            usize::try_from(((value / scale - min / scale) / width) as i64)
+                .unwrap_or(0)
+                .min(bins - 1)
+        } else {
+            0
+        };
+        // The division can land one bucket off at an edge; settle against
+        // the edges themselves so counts agree with them.
+        if value < edges[bin] {
+            bin -= 1;
+        } else if bin + 1 < bins && value >= edges[bin + 1] {
+            bin += 1;
+        }
+        counts[bin] += 1;
+    }
+    (edges, counts)
+}
+
+/// Equal-width histogram of the non-null, finite values of a numeric
+/// column, as `{counts, bin_edges, min, max}`.
+///
+/// The range from the smallest to the largest value is split into `bins`
+/// buckets, with `bins + 1` edges. Each bucket holds values from its lower
+/// edge up to, but not including, its upper edge, except the last, which
+/// also holds the largest value. When every value is equal there is a
+/// single bucket whose edges are both that value. NaN and infinite values
+/// are left out; a column with none left gives `null`. `bins` must be at
+/// least 1.
+pub fn histogram(column: &Column, bins: usize) -> Result<JsValue> {
+    if bins == 0 {
+        let field = column.field()?;
+        return Err(
+            ArrowWasmError::InvalidInput("bins must be at least 1".to_string())
+                .in_column("histogram", &field),
+        );
+    }
+    let mut values = numeric_values(column, Operation::Histogram)?;
+    values.retain(|value| value.is_finite());
+    let Some((min, max)) = values.iter().fold(None, |range, &value| match range {
+        None => Some((value, value)),
+        Some((min, max)) => Some((f64::min(min, value), f64::max(max, value))),
+    }) else {
+        return Ok(JsValue::NULL);
+    };
+    let (edges, counts) = bin_counts(&values, min, max, bins);
+
+    let result = js_sys::Object::new();
+    let counts: js_sys::Array = counts
+        .into_iter()
+        .map(|count| JsValue::from_f64(count as f64))
+        .collect();
+    let edges: js_sys::Array = edges.into_iter().map(JsValue::from_f64).collect();
+    set_property(&result, "counts", &counts)?;
+    set_property(&result, "bin_edges", &edges)?;
+    set_property(&result, "min", &JsValue::from_f64(min))?;
+    set_property(&result, "max", &JsValue::from_f64(max))?;
+    Ok(result.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::store_column;
+    use arrow::array::{
+        AsArray, Float64Array, Int32Array, Int64Array, Int8Array, StringArray, StructArray,
+        UInt64Array,
+    };
+    use arrow::datatypes::{Field, Fields, Int32Type};
+    use std::sync::Arc;
+
+    fn stored(data_type: DataType, chunks: Vec<ArrayRef>) -> Column {
+        store_column(Arc::new(Field::new("x", data_type, true)), chunks).unwrap()
+    }
+
+    fn floats(values: &[Option<f64>]) -> Column {
+        let chunk: ArrayRef = Arc::new(Float64Array::from(values.to_vec()));
+        stored(DataType::Float64, vec![chunk])
+    }
+
+    /// Every aggregate of `column` that yields a number, by name.
+    fn aggregates(column: &Column) -> Vec<(&'static str, Option<f64>)> {
+        let values = |operation| numeric_values(column, operation).unwrap();
+        vec![
+            ("sum", sum_of(&values(Operation::Sum))),
+            ("mean", mean_of(&values(Operation::Mean))),
+            ("median", median_of(column).unwrap()),
+            ("variance", variance_of(column, true).unwrap()),
+            ("population variance", variance_of(column, false).unwrap()),
+            ("quantile", quantile(column, 0.25).unwrap()),
+        ]
+    }
+
+    #[test]
+    fn columns_without_values_give_null() {
+        let empty = stored(
+            DataType::Int32,
+            vec![Arc::new(Int32Array::from(Vec::<i32>::new()))],
+        );
+        let no_chunks = stored(DataType::Int64, vec![]);
+        let all_null = stored(
+            DataType::Int32,
+            vec![
+                Arc::new(Int32Array::from(vec![None, None])),
+                Arc::new(Int32Array::from(vec![None])),
+            ],
+        );
+        let all_nan = floats(&[Some(f64::NAN), None, Some(f64::NAN)]);
+        for (name, column) in [
+            ("empty", empty),
+            ("no chunks", no_chunks),
+            ("all-null", all_null),
+            ("all-NaN", all_nan),
+        ] {
+            for (aggregate, value) in aggregates(&column) {
+                assert_eq!(value, None, "{aggregate} of an {name} column");
+            }
+            let chunks = column.field_and_chunks().unwrap().1;
+            if name != "all-NaN" {
+                assert!(extreme(&chunks, false).unwrap().is_none(), "min of {name}");
+                assert!(extreme(&chunks, true).unwrap().is_none(), "max of {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn nan_is_skipped_like_null() {
+        let column = floats(&[Some(1.0), Some(f64::NAN), None, Some(3.0), Some(f64::NAN)]);
+        let clean = floats(&[Some(1.0), Some(3.0)]);
+        assert_eq!(numeric_values(&column, Operation::Sum).unwrap(), [1.0, 3.0]);
+        assert_eq!(aggregates(&column), aggregates(&clean));
+        assert_eq!(sum_of(&[1.0, 3.0]), Some(4.0));
+        assert_eq!(median_of(&column).unwrap(), Some(2.0));
+        assert_eq!(variance_of(&column, true).unwrap(), Some(2.0));
+    }
+
+    #[test]
+    fn single_value_has_no_sample_variance() {
+        let column = floats(&[Some(5.0), None]);
+        assert_eq!(variance_of(&column, true).unwrap(), None);
+        assert_eq!(variance_of(&column, false).unwrap(), Some(0.0));
+        assert_eq!(median_of(&column).unwrap(), Some(5.0));
+    }
+
+    #[test]
+    fn spread_of_known_values() {
+        // Mean 5 and squared deviations summing to 32 over eight values.
+        let known = [2, 4, 4, 4, 5, 5, 7, 9];
+        let mut with_gaps: Vec<Option<i32>> = known.iter().copied().map(Some).collect();
+        with_gaps.insert(3, None);
+        with_gaps.push(None);
+        let mut with_nan: Vec<Option<f64>> = known.iter().map(|&v| Some(f64::from(v))).collect();
+        with_nan.insert(0, Some(f64::NAN));
+        with_nan.insert(5, None);
+        with_nan.push(Some(f64::NAN));
+        for column in [
+            stored(DataType::Int32, vec![ints(&with_gaps)]),
+            floats(&with_nan),
+        ] {
+            assert_eq!(variance_of(&column, false).unwrap(), Some(4.0));
+            assert_eq!(std_dev_of(&column, false).unwrap(), Some(2.0));
+            assert_eq!(variance_of(&column, true).unwrap(), Some(32.0 / 7.0));
+            assert_eq!(
+                std_dev_of(&column, true).unwrap(),
+                Some((32.0_f64 / 7.0).sqrt())
+            );
+        }
+
+        let single = stored(DataType::Int32, vec![ints(&[None, Some(7), None])]);
+        assert_eq!(std_dev_of(&single, true).unwrap(), None);
+        assert_eq!(std_dev_of(&single, false).unwrap(), Some(0.0));
+    }
+
+    fn ints(values: &[Option<i32>]) -> ArrayRef {
+        Arc::new(Int32Array::from(values.to_vec()))
+    }
+
+    #[test]
+    fn extremes_span_chunks_and_skip_nulls() {
+        let chunks = [
+            ints(&[Some(4), None, Some(9)]),
+            ints(&[None, None]),
+            ints(&[]),
+            ints(&[Some(-3), Some(7)]),
+        ];
+        let value = |largest| {
+            let array = extreme(&chunks, largest).unwrap().unwrap();
+            assert_eq!(array.len(), 1);
+            array.as_primitive::<Int32Type>().value(0)
+        };
+        assert_eq!((value(false), value(true)), (-3, 9));
+
+        let only: [ArrayRef; 1] = [ints(&[None, Some(5)])];
+        let min = extreme(&only, false).unwrap().unwrap();
+        assert_eq!(min.as_primitive::<Int32Type>().value(0), 5);
+    }
+
+    #[test]
+    fn extremes_of_strings_order_bytewise() {
+        let chunks: [ArrayRef; 2] = [
+            Arc::new(StringArray::from(vec![Some("pear"), None])),
+            Arc::new(StringArray::from(vec!["Apple", "fig"])),
+        ];
+        let value = |largest| {
+            let array = extreme(&chunks, largest).unwrap().unwrap();
+            array.as_string::<i32>().value(0).to_string()
+        };
+        assert_eq!((value(false), value(true)), ("Apple".into(), "pear".into()));
+    }
+
+    #[test]
+    fn extremes_without_values_are_none() {
+        assert!(extreme(&[], false).unwrap().is_none());
+        let chunks = [ints(&[None, None]), ints(&[])];
+        assert!(extreme(&chunks, true).unwrap().is_none());
+    }
+
+    #[test]
+    fn temporal_units_report_nanoseconds() {
+        use arrow::array::TimestampMicrosecondArray;
+        let cases = [
+            (DataType::Date32, Some(("day", 86_400_000_000_000))),
+            (DataType::Date64, Some(("ms", 1_000_000))),
+            (
+                DataType::Timestamp(TimeUnit::Second, None),
+                Some(("s", 1_000_000_000)),
+            ),
+            (DataType::Time64(TimeUnit::Nanosecond), Some(("ns", 1))),
+            (
+                DataType::Duration(TimeUnit::Microsecond),
+                Some(("us", 1_000)),
+            ),
+            (DataType::Int64, None),
+        ];
+        for (data_type, expected) in cases {
+            assert_eq!(temporal_unit(&data_type), expected, "{data_type:?}");
+        }
+
+        let stamps = TimestampMicrosecondArray::from(vec![1_500, -20]);
+        let chunks: [ArrayRef; 1] = [Arc::new(stamps)];
+        let min = extreme(&chunks, false).unwrap().unwrap();
+        let max = extreme(&chunks, true).unwrap().unwrap();
+        assert_eq!(
+            raw_temporal(max.as_ref()).unwrap() - raw_temporal(min.as_ref()).unwrap(),
+            1_520
+        );
+    }
+
+    #[test]
+    fn int64_sum_passes_the_i64_range() {
+        let chunks: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(i64::MAX), None, Some(i64::MAX)])),
+            Arc::new(Int64Array::from(vec![2])),
+        ];
+        assert_eq!(
+            integer_sum(&chunks).unwrap(),
+            Some(i128::from(i64::MAX) * 2 + 2)
+        );
+        let negative: Vec<ArrayRef> =
+            vec![Arc::new(Int64Array::from(vec![i64::MIN, i64::MIN, -1]))];
+        assert_eq!(
+            integer_sum(&negative).unwrap(),
+            Some(i128::from(i64::MIN) * 2 - 1)
+        );
+    }
+
+    #[test]
+    fn uint64_sum_is_exact() {
+        let chunks: Vec<ArrayRef> = vec![Arc::new(UInt64Array::from(vec![
+            u64::MAX,
+            u64::MAX,
+            (1 << 53) + 1,
+        ]))];
+        assert_eq!(
+            integer_sum(&chunks).unwrap(),
+            Some(i128::from(u64::MAX) * 2 + (1 << 53) + 1)
+        );
+    }
+
+    #[test]
+    fn checked_sum_stops_at_the_i128_range() {
+        // Reaching the edge from zero takes 2^63 values of u64::MAX, so carry
+        // a running total close to it instead, as a chunk after many would.
+        let chunk: ArrayRef = Arc::new(UInt64Array::from(vec![u64::MAX, 1]));
+        let edge = i128::MAX - i128::from(u64::MAX);
+        assert_eq!(checked_sum!(chunk, edge - 1, UInt64Type), Some(i128::MAX));
+        assert_eq!(checked_sum!(chunk, edge, UInt64Type), None);
+        let negative: ArrayRef = Arc::new(Int64Array::from(vec![-1]));
+        assert_eq!(checked_sum!(negative, i128::MIN, Int64Type), None);
+    }
+
+    #[test]
+    fn integer_sum_mixes_widths_and_rejects_floats() {
+        let chunks: Vec<ArrayRef> = vec![Arc::new(Int8Array::from(vec![-128, 127, -1]))];
+        assert_eq!(integer_sum(&chunks).unwrap(), Some(-2));
+        let floats: Vec<ArrayRef> = vec![Arc::new(Float64Array::from(vec![1.0]))];
+        assert!(matches!(
+            integer_sum(&floats),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn quantiles_agree_with_single_quantiles() {
+        let column = floats(&[
+            Some(5.0),
+            None,
+            Some(1.0),
+            Some(f64::NAN),
+            Some(3.0),
+            Some(8.0),
+        ]);
+        let qs = [0.75, 0.0, 0.5, 0.25, 1.0, 0.5];
+        let many = quantiles(&column, &qs).unwrap();
+        let single: Vec<_> = qs.iter().map(|&q| quantile(&column, q).unwrap()).collect();
+        assert_eq!(many, single);
+        assert_eq!(many[..3], [Some(5.75), Some(1.0), Some(4.0)]);
+
+        let all_null = floats(&[None, None]);
+        assert_eq!(quantiles(&all_null, &[0.5, 1.0]).unwrap(), [None, None]);
+        assert!(quantiles(&column, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn quantiles_name_the_out_of_range_position() {
+        let column = floats(&[Some(1.0)]);
+        let Err(ArrowWasmError::Compute { op, message, .. }) = quantiles(&column, &[0.5, 1.5])
+        else {
+            panic!("1.5 is not a quantile");
+        };
+        assert_eq!(op, "quantile");
+        assert_eq!(
+            message,
+            "quantile must be between 0 and 1, got 1.5 at position 1"
+        );
+        assert!(quantiles(&column, &[f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn quantile_of_interpolates_between_neighbours() {
+        let sorted = [1.0, 2.0, 4.0, 8.0];
+        assert_eq!(quantile_of(&sorted, 0.0), Some(1.0));
+        assert_eq!(quantile_of(&sorted, 1.0), Some(8.0));
+        assert_eq!(quantile_of(&sorted, 0.5), Some(3.0));
+        assert_eq!(quantile_of(&sorted, 0.75), Some(5.0));
+        assert_eq!(quantile_of(&[7.0], 0.3), Some(7.0));
+        assert_eq!(quantile_of(&[], 0.5), None);
+    }
+
+    #[test]
+    fn bins_split_the_range_evenly() {
+        let values = [0.0, 1.0, 2.5, 5.0, 7.5, 9.9, 10.0];
+        let (edges, counts) = bin_counts(&values, 0.0, 10.0, 4);
+        assert_eq!(edges, [0.0, 2.5, 5.0, 7.5, 10.0]);
+        // Lower edges are inclusive; the last bucket also takes the maximum.
+        assert_eq!(counts, [2, 1, 1, 3]);
+    }
+
+    #[test]
+    fn equal_values_fill_one_bin() {
+        let (edges, counts) = bin_counts(&[3.0, 3.0, 3.0], 3.0, 3.0, 5);
+        assert_eq!(edges, [3.0, 3.0]);
+        assert_eq!(counts, [3]);
+    }
+
+    #[test]
+    fn counts_agree_with_inexact_edges() {
+        // 0.1 steps are inexact in binary, so values on an edge must be
+        // settled against the edge rather than the quotient.
+        let values: Vec<f64> = (0..=10).map(|tenth| f64::from(tenth) / 10.0).collect();
+        let (edges, counts) = bin_counts(&values, 0.0, 1.0, 10);
+        for (bin, &count) in counts.iter().enumerate() {
+            let last = bin + 1 == counts.len();
+            let expected = values
+                .iter()
+                .filter(|&&v| v >= edges[bin] && (v < edges[bin + 1] || last))
+                .count();
+            assert_eq!(count, expected as u64, "bin {bin}");
+        }
+        assert_eq!(counts.iter().sum::<u64>(), 11);
+    }
+
+    #[test]
+    fn range_past_f64_max_keeps_finite_edges() {
+        let values = [-f64::MAX, -1.0, 0.0, 1.0, f64::MAX / 2.0, f64::MAX];
+        let (edges, counts) = bin_counts(&values, -f64::MAX, f64::MAX, 4);
+        assert_eq!((edges[0], edges[2], edges[4]), (-f64::MAX, 0.0, f64::MAX));
+        assert!(edges.iter().all(|edge| edge.is_finite()));
+        assert!(edges.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(counts, [1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn aggregates_name_the_operation_column_and_type() {
+        let text = stored(DataType::Utf8, vec![Arc::new(StringArray::from(vec!["a"]))]);
+        for operation in [Operation::Sum, Operation::Mean] {
+            let error = numeric_values(&text, operation).unwrap_err().to_string();
+            assert_eq!(
+                error,
+                format!(
+                    "{} does not support column 'x' of type Utf8",
+                    operation.name()
+                )
+            );
+        }
+        let structs = stored(
+            DataType::Struct(Fields::empty()),
+            vec![Arc::new(StructArray::new_empty_fields(1, None))],
+        );
+        for largest in [false, true] {
+            let error = column_extreme(&structs, None, largest)
+                .map(drop)
+                .unwrap_err()
+                .to_string();
+            let op = if largest { "max" } else { "min" };
+            assert_eq!(
+                error,
+                format!("{op} does not support column 'x' of type Struct([])")
+            );
+        }
+        let error = quantile(&floats(&[Some(1.0)]), -0.5)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "quantile failed on column 'x' (Float64): quantile must be between 0 and 1, got -0.5"
+        );
+    }
+
+    #[test]
+    fn half_quantile_is_the_median() {
+        let columns = [
+            floats(&[Some(3.0), None, Some(1.0), Some(2.0)]),
+            floats(&[Some(4.0), Some(1.0), None, Some(3.0), Some(2.0)]),
+            floats(&[Some(-7.5)]),
+            floats(&[None, None]),
+            stored(
+                DataType::Int64,
+                vec![
+                    Arc::new(Int64Array::from(vec![10, 40])),
+                    Arc::new(Int64Array::from(vec![Some(20), None, Some(30), Some(50)])),
+                ],
+            ),
+        ];
+        let expected = [Some(2.0), Some(2.5), Some(-7.5), None, Some(30.0)];
+        for (column, expected) in columns.iter().zip(expected) {
+            assert_eq!(median_of(column).unwrap(), expected);
+            assert_eq!(column.quantile(0.5).unwrap(), expected);
+            assert_eq!(quantiles(column, &[0.5]).unwrap(), [expected]);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! String kernels over Utf8, `LargeUtf8` and `Utf8View` columns, plus
+//! decoding of binary columns into text.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::errors::{ArrowWasmError, Result};
+use crate::validation::InvalidUtf8;
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, StringArray};
+use arrow::datatypes::{DataType, Field};
+use arrow_cast::cast;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Apply `f` to every non-null string of `array`, keeping nulls.
+pub fn map_strings<T>(array: &dyn Array, mut f: impl FnMut(&str) -> T) -> Result<Vec<Option<T>>> {
+    let values = match array.data_type() {
+        DataType::Utf8 => array
+            .as_string::<i32>()
+            .iter()
+            .map(|v| v.map(&mut f))
+            .collect(),
+        DataType::LargeUtf8 => array
+            .as_string::<i64>()
+            .iter()
+            .map(|v| v.map(&mut f))
+            .collect(),
+        DataType::Utf8View => array
+            .as_string_view()
+            .iter()
+            .map(|v| v.map(&mut f))
+            .collect(),
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Expected a string column, got {other:?}"
+            )))
+        }
+    };
+    Ok(values)
+}
+
+/// Number of non-overlapping occurrences of `pattern` in each string.
+///
+/// Strings without a match count 0; null strings stay null.
+#[wasm_bindgen]
+pub fn count_matches(column: &Column, pattern: &str) -> std::result::Result<Column, JsValue> {
+    if pattern.is_empty() {
+        return Err(ArrowWasmError::InvalidInput("Pattern must not be empty".to_string()).into());
+    }
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::StringOps, &field)?;
+    let counts = chunks
+        .iter()
+        .map(|chunk| {
+            let counts = map_strings(chunk.as_ref(), |value| {
+                i32::try_from(value.matches(pattern).count()).unwrap_or(i32::MAX)
+            })
+            .map_err(|e| e.in_column("count_matches", &field))?;
+            Ok(Arc::new(Int32Array::from(counts)) as ArrayRef)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let field = Field::new(field.name(), DataType::Int32, field.is_nullable());
+    Ok(column::store_column(Arc::new(field), counts)?)
+}
+
+/// Row and byte offset of the first value of `chunks` that is not UTF-8.
+fn first_invalid_utf8(chunks: &[ArrayRef]) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    for chunk in chunks {
+        let binary = chunk.as_binary::<i32>();
+        for (i, value) in binary.iter().enumerate() {
+            if let Some(Err(e)) = value.map(std::str::from_utf8) {
+                return Some((offset + i, e.valid_up_to()));
+            }
+        }
+        offset += chunk.len();
+    }
+    None
+}
+
+/// Decode a binary column of UTF-8 text into a Utf8 column.
+///
+/// `on_invalid` (`"replace"` by default) decides what happens when a value
+/// is not valid UTF-8: `"replace"` substitutes U+FFFD for each invalid
+/// sequence, `"error"` fails with the row and byte offset of the first one,
+/// and `"binary"` returns the column unchanged as Binary so no bytes are
+/// lost. Nulls stay null.
+#[wasm_bindgen]
+pub fn decode_utf8(column: &Column, on_invalid: JsValue) -> std::result::Result<Column, JsValue> {
+    let policy: InvalidUtf8 = if on_invalid.is_undefined() || on_invalid.is_null() {
+        InvalidUtf8::default()
+    } else {
+        serde_wasm_bindgen::from_value(on_invalid).map_err(ArrowWasmError::from)?
+    };
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::DecodeUtf8, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("decode_utf8", &field);
+    let chunks = chunks
+        .iter()
+        .map(|chunk| cast(chunk, &DataType::Binary))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| context(e.into()))?;
+
+    let decoded: Vec<ArrayRef> = match (first_invalid_utf8(&chunks), policy) {
+        (None, _) => chunks
+            .iter()
+            .map(|chunk| cast(chunk, &DataType::Utf8))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| context(e.into()))?,
+        (Some((row, byte)), InvalidUtf8::Error) => {
+            return Err(ArrowWasmError::Compute {
+                op: "decode_utf8",
+                column: field.name().clone(),
+                data_type: format!("{:?}", field.data_type()),
+                row: Some(row),
+                message: format!("invalid UTF-8 at byte offset {byte}"),
+            }
+            .into());
+        }
+        (Some(_), InvalidUtf8::Binary) => {
+            return Ok(column::store_column(
+                Arc::new(field.as_ref().clone().with_data_type(DataType::Binary)),
+                chunks,
+            )?);
+        }
+        (Some(_), InvalidUtf8::Replace) => chunks
+            .iter()
+            .map(|chunk| {
+                let strings: StringArray = chunk
+                    .as_binary::<i32>()
+                    .iter()
+                    .map(|value| value.map(String::from_utf8_lossy))
+                    .collect();
+                Arc::new(strings) as ArrayRef
+            })
+            .collect(),
+    };
+    let field = field.as_ref().clone().with_data_type(DataType::Utf8);
+    Ok(column::store_column(Arc::new(field), decoded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BinaryArray, LargeStringArray, StringViewArray};
+    use arrow::datatypes::Int32Type;
+
+    fn binary(values: &[Option<&[u8]>]) -> ArrayRef {
+        Arc::new(BinaryArray::from(values.to_vec()))
+    }
+
+    fn stored(chunks: Vec<ArrayRef>) -> Column {
+        let field = Field::new("s", chunks[0].data_type().clone(), true);
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    fn counts(column: &Column, pattern: &str) -> Vec<Option<i32>> {
+        let counted = count_matches(column, pattern).unwrap();
+        let (field, chunks) = counted.field_and_chunks().unwrap();
+        assert_eq!(field.data_type(), &DataType::Int32);
+        chunks
+            .iter()
+            .flat_map(|chunk| chunk.as_primitive::<Int32Type>().iter())
+            .collect()
+    }
+
+    #[test]
+    fn matches_are_counted_per_row() {
+        let column = stored(vec![
+            Arc::new(StringArray::from(vec![Some("error"), Some("ok"), None])),
+            Arc::new(StringArray::from(vec![
+                Some("error: error error"),
+                Some(""),
+            ])),
+        ]);
+        assert_eq!(
+            counts(&column, "error"),
+            [Some(1), Some(0), None, Some(3), Some(0)]
+        );
+        assert_eq!(
+            counts(&column, "o"),
+            [Some(1), Some(1), None, Some(3), Some(0)]
+        );
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        let column = stored(vec![Arc::new(StringArray::from(vec![
+            "aaaa", "aaa", "ééé",
+        ]))]);
+        assert_eq!(counts(&column, "aa"), [Some(2), Some(1), Some(0)]);
+        assert_eq!(counts(&column, "é"), [Some(0), Some(0), Some(3)]);
+    }
+
+    #[test]
+    fn every_string_type_is_counted() {
+        let values = ["a-b-c", "", "-"];
+        for array in [
+            Arc::new(StringArray::from(values.to_vec())) as ArrayRef,
+            Arc::new(LargeStringArray::from(values.to_vec())),
+            Arc::new(StringViewArray::from(values.to_vec())),
+        ] {
+            let column = stored(vec![array]);
+            assert_eq!(counts(&column, "-"), [Some(2), Some(0), Some(1)]);
+        }
+    }
+
+    #[test]
+    fn malformed_bytes_are_found_by_row_and_offset() {
+        for (bytes, offset) in [
+            (&b"ab\x80"[..], 2),          // stray continuation byte
+            (b"\xC0\xAF", 0),             // overlong encoding of '/'
+            (b"x\xED\xA0\x80", 1),        // UTF-8 encoded surrogate
+            (b"\xF4\x90\x80\x80", 0),     // past U+10FFFF
+            (b"caf\xC3", 3),              // truncated at the end
+            (b"\xE2\x82\xAC\xE2\x82", 3), // a euro sign, then half of one
+        ] {
+            let chunks = [
+                binary(&[Some(b"ok"), None]),
+                binary(&[Some(b""), Some(bytes)]),
+            ];
+            assert_eq!(first_invalid_utf8(&chunks), Some((3, offset)), "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn valid_text_and_nulls_are_accepted() {
+        let chunks = [
+            binary(&[Some("é€😀".as_bytes()), None]),
+            binary(&[]),
+            binary(&[Some(b""), None]),
+        ];
+        assert_eq!(first_invalid_utf8(&chunks), None);
+    }
+
+    #[test]
+    fn rows_count_from_the_slice_start() {
+        let array = binary(&[Some(b"\xFF"), Some(b"ok"), Some(b"\xFF")]);
+        assert_eq!(first_invalid_utf8(&[array.slice(1, 2)]), Some((1, 0)));
+        assert_eq!(first_invalid_utf8(&[array.slice(1, 1)]), None);
+    }
+}
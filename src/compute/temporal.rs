@@ -0,0 +1,470 @@
+//! Time zone aware timestamp kernels.
+//!
+//! Timestamps store UTC instants; the time zone in their type only says
+//! which local time they stand for. Calendar fields and truncation here
+//! follow that annotation (resolved with [`crate::tz`]), naive timestamps
+//! are taken as wall-clock times, and conversions between the two are
+//! explicit.
+
+use crate::column::{self, Column};
+use crate::compute::capability::{require, Operation};
+use crate::compute::cast::cast_safe;
+use crate::errors::{ArrowWasmError, Result};
+use crate::tz::{civil_from_days, days_from_civil, iso_weekday, Ambiguous, Zone};
+use arrow::array::{make_array, Array, ArrayRef, AsArray, Int32Array, Int64Array};
+use arrow::datatypes::{DataType, Field, Int64Type, TimeUnit};
+use serde::Deserialize;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+const fn units_per_second(unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+fn out_of_range() -> ArrowWasmError {
+    ArrowWasmError::InvalidInput("timestamp out of range".to_string())
+}
+
+/// `array` reinterpreted as `data_type`, which must share its layout.
+fn retyped(array: &dyn Array, data_type: &DataType) -> Result<ArrayRef> {
+    Ok(make_array(
+        array
+            .to_data()
+            .into_builder()
+            .data_type(data_type.clone())
+            .build()?,
+    ))
+}
+
+/// Unit and time zone of a timestamp field.
+fn timestamp_type(field: &Field) -> Result<(TimeUnit, Option<Arc<str>>)> {
+    match field.data_type() {
+        DataType::Timestamp(unit, time_zone) => Ok((*unit, time_zone.clone())),
+        other => Err(ArrowWasmError::InvalidInput(format!(
+            "expected a timestamp column, got {other:?}"
+        ))),
+    }
+}
+
+/// Stored values of `chunk` (a 64-bit temporal array) as Int64.
+fn raw_values(chunk: &dyn Array) -> Result<Int64Array> {
+    Ok(retyped(chunk, &DataType::Int64)?
+        .as_primitive::<Int64Type>()
+        .clone())
+}
+
+/// Apply `f` to the stored values of every chunk, giving chunks of `to`.
+fn map_chunks(
+    chunks: &[ArrayRef],
+    to: &DataType,
+    f: impl Fn(i64) -> Result<i64>,
+) -> Result<Vec<ArrayRef>> {
+    chunks
+        .iter()
+        .map(|chunk| {
+            let values = raw_values(chunk.as_ref())?
+                .iter()
+                .map(|value| value.map(&f).transpose())
+                .collect::<Result<Int64Array>>()?;
+            retyped(&values, to)
+        })
+        .collect()
+}
+
+/// Move a stored value by `seconds`, keeping its sub-second part.
+fn offset_value(value: i64, seconds: i64, per_second: i64) -> Result<i64> {
+    seconds
+        .checked_mul(per_second)
+        .and_then(|shift| value.checked_add(shift))
+        .ok_or_else(out_of_range)
+}
+
+/// Store `chunks` as `field` with its type replaced by `data_type`.
+fn store_retyped(field: &Field, data_type: DataType, chunks: Vec<ArrayRef>) -> Result<Column> {
+    column::store_column(Arc::new(field.clone().with_data_type(data_type)), chunks)
+}
+
+/// Change the time zone a timestamp column is shown in.
+///
+/// The instants are unchanged; only the annotation becomes `time_zone`, so
+/// calendar fields afterwards follow the new zone. The column must already
+/// have a time zone (see `localize_naive`). `time_zone` is a fixed offset
+/// like `+05:30` or a name from `list_time_zones`.
+#[wasm_bindgen]
+pub fn convert_timezone(column: &Column, time_zone: &str) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::TimeZoneOps, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("convert_timezone", &field);
+    let (unit, current) = timestamp_type(&field).map_err(context)?;
+    if current.is_none() {
+        return Err(context(ArrowWasmError::InvalidInput(
+            "column has no time zone; use localize_naive to attach one".to_string(),
+        ))
+        .into());
+    }
+    Zone::parse(time_zone).map_err(context)?;
+    let to = DataType::Timestamp(unit, Some(time_zone.into()));
+    let chunks = chunks
+        .iter()
+        .map(|chunk| retyped(chunk.as_ref(), &to))
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    Ok(store_retyped(&field, to, chunks)?)
+}
+
+/// Options for [`localize_naive`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct LocalizeOptions {
+    ambiguous: Ambiguous,
+}
+
+/// Read a naive timestamp column as wall-clock times in `time_zone`,
+/// giving a column annotated with that zone.
+///
+/// Values change to the UTC instants of those local times. `options` is
+/// `{ambiguous?}`: `"error"` (the default) fails on local times that occur
+/// twice or never around a daylight saving transition, `"earliest"` and
+/// `"latest"` pick one of a repeated time's instants, and both map a
+/// skipped time to the transition instant.
+#[wasm_bindgen]
+pub fn localize_naive(
+    column: &Column,
+    time_zone: &str,
+    options: JsValue,
+) -> std::result::Result<Column, JsValue> {
+    let options: LocalizeOptions = if options.is_undefined() || options.is_null() {
+        LocalizeOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::TimeZoneOps, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("localize_naive", &field);
+    let (unit, current) = timestamp_type(&field).map_err(context)?;
+    if let Some(current) = current {
+        return Err(context(ArrowWasmError::InvalidInput(format!(
+            "column already has time zone '{current}'; use convert_timezone to change it"
+        )))
+        .into());
+    }
+    let zone = Zone::parse(time_zone).map_err(context)?;
+    let per_second = units_per_second(unit);
+    let to = DataType::Timestamp(unit, Some(time_zone.into()));
+    let chunks = map_chunks(&chunks, &to, |value| {
+        let local = value.div_euclid(per_second);
+        let utc = zone.to_utc(local, options.ambiguous)?;
+        offset_value(value, utc - local, per_second)
+    })
+    .map_err(context)?;
+    Ok(store_retyped(&field, to, chunks)?)
+}
+
+/// Turn a timestamp column with a time zone into naive wall-clock times in
+/// that zone, dropping the annotation.
+#[wasm_bindgen]
+pub fn remove_timezone(column: &Column) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::TimeZoneOps, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("remove_timezone", &field);
+    let (unit, current) = timestamp_type(&field).map_err(context)?;
+    let Some(current) = current else {
+        return Err(context(ArrowWasmError::InvalidInput(
+            "column has no time zone".to_string(),
+        ))
+        .into());
+    };
+    let zone = Zone::parse(&current).map_err(context)?;
+    let per_second = units_per_second(unit);
+    let to = DataType::Timestamp(unit, None);
+    let chunks = map_chunks(&chunks, &to, |value| {
+        let offset = zone.offset_at(value.div_euclid(per_second));
+        offset_value(value, i64::from(offset), per_second)
+    })
+    .map_err(context)?;
+    Ok(store_retyped(&field, to, chunks)?)
+}
+
+/// Calendar fields [`date_part`] can extract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Part {
+    Year,
+    Quarter,
+    Month,
+    Day,
+    Weekday,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Part {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "year" => Self::Year,
+            "quarter" => Self::Quarter,
+            "month" => Self::Month,
+            "day" => Self::Day,
+            "weekday" => Self::Weekday,
+            "hour" => Self::Hour,
+            "minute" => Self::Minute,
+            "second" => Self::Second,
+            other => {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Unknown date part '{other}'; expected year, quarter, month, day, weekday, hour, minute or second"
+                )))
+            }
+        })
+    }
+
+    /// This field of the local time `local` (epoch seconds).
+    const fn of(self, local: i64) -> i32 {
+        let days = local.div_euclid(SECONDS_PER_DAY);
+        let time = local.rem_euclid(SECONDS_PER_DAY) as i32;
+        let (year, month, day) = civil_from_days(days);
+        match self {
+            Self::Year => year as i32,
+            Self::Quarter => (month.cast_signed() - 1) / 3 + 1,
+            Self::Month => month.cast_signed(),
+            Self::Day => day.cast_signed(),
+            Self::Weekday => iso_weekday(days).cast_signed(),
+            Self::Hour => time / 3_600,
+            Self::Minute => time / 60 % 60,
+            Self::Second => time % 60,
+        }
+    }
+}
+
+/// Timestamp chunks of a timestamp or date column, with its unit and zone.
+fn timestamp_chunks(
+    field: &Field,
+    chunks: &[ArrayRef],
+) -> Result<(TimeUnit, Option<Zone>, Vec<ArrayRef>)> {
+    let unit = match field.data_type() {
+        DataType::Date32 => TimeUnit::Second,
+        DataType::Date64 => TimeUnit::Millisecond,
+        DataType::Timestamp(unit, time_zone) => {
+            let zone = time_zone.as_deref().map(Zone::parse).transpose()?;
+            return Ok((*unit, zone, chunks.to_vec()));
+        }
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "expected a timestamp or date column, got {other:?}"
+            )))
+        }
+    };
+    let to = DataType::Timestamp(unit, None);
+    let chunks = chunks
+        .iter()
+        .map(|chunk| cast_safe(chunk, &to))
+        .collect::<Result<_>>()?;
+    Ok((unit, None, chunks))
+}
+
+/// A calendar field of every value as Int32: `year`, `quarter`, `month`,
+/// `day`, `weekday` (ISO, Monday = 1), `hour`, `minute` or `second`.
+///
+/// Timestamps with a time zone give the field in that zone, so an instant
+/// near midnight UTC can fall on a different day; naive timestamps and
+/// dates are read as they are stored.
+#[wasm_bindgen]
+pub fn date_part(column: &Column, part: &str) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::DatePart, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("date_part", &field);
+    let part = Part::parse(part).map_err(context)?;
+    let (unit, zone, chunks) = timestamp_chunks(&field, &chunks).map_err(context)?;
+    let per_second = units_per_second(unit);
+    let chunks = chunks
+        .iter()
+        .map(|chunk| {
+            let values: Int32Array = raw_values(chunk.as_ref())?
+                .iter()
+                .map(|value| {
+                    value.map(|value| {
+                        let seconds = value.div_euclid(per_second);
+                        let offset = zone.map_or(0, |zone| zone.offset_at(seconds));
+                        part.of(seconds + i64::from(offset))
+                    })
+                })
+                .collect();
+            Ok(Arc::new(values) as ArrayRef)
+        })
+        .collect::<Result<Vec<_>>>()
+        .map_err(context)?;
+    Ok(store_retyped(&field, DataType::Int32, chunks)?)
+}
+
+/// Start of the `unit` period containing the local time `local`.
+fn truncate(local: i64, unit: &str) -> Result<i64> {
+    let days = local.div_euclid(SECONDS_PER_DAY);
+    let (year, month, _) = civil_from_days(days);
+    let period = match unit {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 3_600,
+        "day" => SECONDS_PER_DAY,
+        "week" => return Ok((days - i64::from(iso_weekday(days)) + 1) * SECONDS_PER_DAY),
+        "month" => return Ok(days_from_civil(year, month, 1) * SECONDS_PER_DAY),
+        "quarter" => {
+            return Ok(days_from_civil(year, (month - 1) / 3 * 3 + 1, 1) * SECONDS_PER_DAY)
+        }
+        "year" => return Ok(days_from_civil(year, 1, 1) * SECONDS_PER_DAY),
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Unknown truncation unit '{other}'; expected year, quarter, month, week, day, hour, minute or second"
+            )))
+        }
+    };
+    Ok(local - local.rem_euclid(period))
+}
+
+/// Round every timestamp down to the start of its `unit`: `year`,
+/// `quarter`, `month`, `week` (starting Monday), `day`, `hour`, `minute`
+/// or `second`.
+///
+/// Periods are local to the column's time zone, so `day` gives local
+/// midnight. A boundary repeated by a daylight saving transition takes the
+/// instant with the value's own offset; a skipped one takes the
+/// transition. The type is unchanged.
+#[wasm_bindgen]
+pub fn date_trunc(column: &Column, unit: &str) -> std::result::Result<Column, JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::DateTrunc, &field)?;
+    let context = |e: ArrowWasmError| e.in_column("date_trunc", &field);
+    let (time_unit, time_zone) = timestamp_type(&field).map_err(context)?;
+    let zone = time_zone
+        .as_deref()
+        .map(Zone::parse)
+        .transpose()
+        .map_err(context)?;
+    truncate(0, unit).map_err(context)?;
+    let per_second = units_per_second(time_unit);
+    let chunks = map_chunks(&chunks, field.data_type(), |value| {
+        let seconds = value.div_euclid(per_second);
+        let start = match zone {
+            Some(zone) => {
+                let offset = zone.offset_at(seconds);
+                let local = truncate(seconds + i64::from(offset), unit)?;
+                zone.to_utc_with_offset(local, offset)
+            }
+            None => truncate(seconds, unit)?,
+        };
+        start.checked_mul(per_second).ok_or_else(out_of_range)
+    })
+    .map_err(context)?;
+    Ok(column::store_column(Arc::clone(&field), chunks)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::TimestampMillisecondArray;
+
+    const MINUTE: i64 = 60_000;
+
+    /// Milliseconds since the epoch of a UTC time.
+    const fn utc(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        (days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3_600) * 1_000
+            + minute * MINUTE
+    }
+
+    fn timestamps(time_zone: Option<&str>, chunks: &[&[Option<i64>]]) -> Column {
+        let data_type = DataType::Timestamp(TimeUnit::Millisecond, time_zone.map(Into::into));
+        let chunks = chunks
+            .iter()
+            .map(|values| {
+                let array =
+                    TimestampMillisecondArray::from(values.to_vec()).with_timezone_opt(time_zone);
+                Arc::new(array) as ArrayRef
+            })
+            .collect();
+        column::store_column(Arc::new(Field::new("t", data_type, true)), chunks).unwrap()
+    }
+
+    fn stored_values(column: &Column) -> Vec<Option<i64>> {
+        let (_, chunks) = column.field_and_chunks().unwrap();
+        chunks
+            .iter()
+            .flat_map(|chunk| {
+                raw_values(chunk.as_ref())
+                    .unwrap()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn parts(column: &Column, part: &str) -> Vec<Option<i32>> {
+        let (_, chunks) = date_part(column, part).unwrap().field_and_chunks().unwrap();
+        chunks
+            .iter()
+            .flat_map(|chunk| chunk.as_primitive::<arrow::datatypes::Int32Type>().iter())
+            .collect()
+    }
+
+    #[test]
+    fn kolkata_fields_follow_the_half_hour_offset() {
+        // 20:15 UTC is 01:45 the next day in Kolkata.
+        let instant = utc(2024, 3, 10, 20, 15);
+        for zone in ["Asia/Kolkata", "+05:30"] {
+            let column = timestamps(Some(zone), &[&[Some(instant)], &[None]]);
+            assert_eq!(parts(&column, "day"), [Some(11), None]);
+            assert_eq!(parts(&column, "hour"), [Some(1), None]);
+            assert_eq!(parts(&column, "minute"), [Some(45), None]);
+            assert_eq!(parts(&column, "weekday"), [Some(1), None]);
+        }
+        let naive = timestamps(None, &[&[Some(instant)]]);
+        assert_eq!(parts(&naive, "day"), [Some(10)]);
+        assert_eq!(parts(&naive, "hour"), [Some(20)]);
+    }
+
+    #[test]
+    fn kolkata_truncation_uses_local_boundaries() {
+        let instant = utc(2024, 3, 10, 20, 15);
+        let column = timestamps(Some("Asia/Kolkata"), &[&[Some(instant), None]]);
+        let truncated = |unit| stored_values(&date_trunc(&column, unit).unwrap());
+        // Local 01:45 on the 11th: the hour starts at 01:00, 19:30 UTC.
+        assert_eq!(truncated("hour"), [Some(utc(2024, 3, 10, 19, 30)), None]);
+        assert_eq!(truncated("day"), [Some(utc(2024, 3, 10, 18, 30)), None]);
+        assert_eq!(truncated("month"), [Some(utc(2024, 2, 29, 18, 30)), None]);
+        assert_eq!(truncated("week"), [Some(utc(2024, 3, 10, 18, 30)), None]);
+
+        let naive = timestamps(None, &[&[Some(instant)]]);
+        let hour = stored_values(&date_trunc(&naive, "hour").unwrap());
+        assert_eq!(hour, [Some(utc(2024, 3, 10, 20, 0))]);
+        assert!(truncate(0, "fortnight").is_err());
+    }
+
+    #[test]
+    fn truncation_across_daylight_saving_keeps_local_midnight() {
+        // Midnight before spring forward is still EST; before fall back, EDT.
+        let column = timestamps(
+            Some("America/New_York"),
+            &[&[Some(utc(2024, 3, 10, 12, 0)), Some(utc(2024, 11, 3, 12, 0))]],
+        );
+        assert_eq!(
+            stored_values(&date_trunc(&column, "day").unwrap()),
+            [Some(utc(2024, 3, 10, 5, 0)), Some(utc(2024, 11, 3, 4, 0))]
+        );
+    }
+
+    #[test]
+    fn removing_a_zone_gives_wall_clock_times() {
+        let instant = utc(2024, 3, 10, 20, 15);
+        let column = timestamps(Some("Asia/Kolkata"), &[&[Some(instant), None]]);
+        let naive = remove_timezone(&column).unwrap();
+        assert_eq!(stored_values(&naive), [Some(utc(2024, 3, 11, 1, 45)), None]);
+
+        let converted = convert_timezone(&column, "Europe/Berlin").unwrap();
+        assert_eq!(stored_values(&converted), [Some(instant), None]);
+        assert_eq!(parts(&converted, "hour"), [Some(21), None]);
+    }
+}
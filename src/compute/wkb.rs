@@ -0,0 +1,345 @@
+//! Structural checks of WKB (well-known binary) geometry columns.
+//!
+//! Each value is parsed far enough to know its length: the byte order
+//! mark, the geometry type with its ISO Z/M/ZM dimension (`1001` is a
+//! Point Z), the point and ring counts, and for multi-geometries and
+//! collections each member in turn. A value must be exactly as long as its
+//! counts say. Coordinates themselves are not inspected.
+
+use crate::column::Column;
+use crate::compute::capability::{require, Operation};
+use crate::errors::ArrowWasmError;
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::DataType;
+use wasm_bindgen::prelude::*;
+
+/// Deepest nesting of collections accepted, so hostile input cannot
+/// recurse without bound.
+const MAX_DEPTH: usize = 32;
+
+/// Cursor over one WKB value.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    /// Advance past `len` bytes of `what`, failing when the value ends first.
+    fn take(&mut self, len: usize, what: &str) -> std::result::Result<&[u8], String> {
+        let available = self.bytes.len() - self.at;
+        if len > available {
+            return Err(format!(
+                "truncated {what} at byte {}: needs {len} bytes, {available} left",
+                self.at
+            ));
+        }
+        let taken = &self.bytes[self.at..self.at + len];
+        self.at += len;
+        Ok(taken)
+    }
+
+    fn u32(&mut self, little_endian: bool, what: &str) -> std::result::Result<u32, String> {
+        let word: [u8; 4] = self.take(4, what)?.try_into().unwrap_or_default();
+        Ok(if little_endian {
+            u32::from_le_bytes(word)
+        } else {
+            u32::from_be_bytes(word)
+        })
+    }
+
+    /// Skip `count` points of `dimensions` coordinates.
+    fn points(
+        &mut self,
+        count: u32,
+        dimensions: usize,
+        what: &str,
+    ) -> std::result::Result<(), String> {
+        let len = (count as usize)
+            .checked_mul(dimensions * 8)
+            .ok_or_else(|| format!("{what} at byte {} claims {count} points", self.at))?;
+        self.take(len, what).map(|_| ())
+    }
+
+    /// Read one geometry, header included.
+    fn geometry(&mut self, depth: usize) -> std::result::Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("collections nest deeper than {MAX_DEPTH} levels"));
+        }
+        let start = self.at;
+        let little_endian = match self.take(1, "byte order")?[0] {
+            0 => false,
+            1 => true,
+            other => return Err(format!("invalid byte order {other} at byte {start}")),
+        };
+        let code = self.u32(little_endian, "geometry type")?;
+        let dimensions = match code / 1000 {
+            0 => 2,
+            1 | 2 => 3,
+            3 => 4,
+            _ => return Err(format!("unknown geometry type {code} at byte {start}")),
+        };
+        match code % 1000 {
+            1 => self.points(1, dimensions, "Point"),
+            2 => {
+                let count = self.u32(little_endian, "LineString point count")?;
+                self.points(count, dimensions, "LineString")
+            }
+            3 => {
+                let rings = self.u32(little_endian, "Polygon ring count")?;
+                for _ in 0..rings {
+                    let count = self.u32(little_endian, "Polygon ring point count")?;
+                    self.points(count, dimensions, "Polygon ring")?;
+                }
+                Ok(())
+            }
+            4..=7 => {
+                let members = self.u32(little_endian, "member count")?;
+                for _ in 0..members {
+                    self.geometry(depth + 1)?;
+                }
+                Ok(())
+            }
+            _ => Err(format!("unknown geometry type {code} at byte {start}")),
+        }
+    }
+}
+
+/// Check that `bytes` is one complete WKB geometry.
+fn check_wkb(bytes: &[u8]) -> std::result::Result<(), String> {
+    let mut reader = Reader { bytes, at: 0 };
+    reader.geometry(0)?;
+    if reader.at != bytes.len() {
+        return Err(format!(
+            "{} bytes after the geometry, which ends at byte {}",
+            bytes.len() - reader.at,
+            reader.at
+        ));
+    }
+    Ok(())
+}
+
+/// First invalid value of `array` as `(index, problem)`; nulls are skipped.
+fn first_invalid(array: &dyn Array) -> Option<(usize, String)> {
+    let problem = |index: usize, bytes: &[u8]| check_wkb(bytes).err().map(|e| (index, e));
+    match array.data_type() {
+        DataType::Binary => array
+            .as_binary::<i32>()
+            .iter()
+            .enumerate()
+            .find_map(|(index, bytes)| problem(index, bytes?)),
+        DataType::LargeBinary => array
+            .as_binary::<i64>()
+            .iter()
+            .enumerate()
+            .find_map(|(index, bytes)| problem(index, bytes?)),
+        DataType::BinaryView => array
+            .as_binary_view()
+            .iter()
+            .enumerate()
+            .find_map(|(index, bytes)| problem(index, bytes?)),
+        _ => None,
+    }
+}
+
+/// Check that every value of a WKB geometry column (Binary, `LargeBinary`
+/// or `BinaryView`) is structurally complete.
+///
+/// A complete value has a known byte order and geometry type, and exactly
+/// the bytes its point, ring and member counts call for. Nulls are skipped.
+/// Fails naming the first invalid row and what is wrong with it, e.g. a
+/// truncated Polygon ring.
+#[wasm_bindgen]
+pub fn validate_wkb(column: &Column) -> std::result::Result<(), JsValue> {
+    let (field, chunks) = column.field_and_chunks()?;
+    require(Operation::ValidateWkb, &field)?;
+    let mut offset = 0;
+    for chunk in &chunks {
+        if let Some((index, message)) = first_invalid(chunk.as_ref()) {
+            return Err(ArrowWasmError::Compute {
+                op: "validate_wkb",
+                column: field.name().clone(),
+                data_type: format!("{:?}", field.data_type()),
+                row: Some(offset + index),
+                message: format!("invalid WKB: {message}"),
+            }
+            .into());
+        }
+        offset += chunk.len();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BinaryArray, BinaryViewArray};
+
+    /// Pieces of a WKB value after its header.
+    enum Part<'a> {
+        Count(u32),
+        Coordinates(&'a [f64]),
+        Member(Vec<u8>),
+    }
+
+    use Part::{Coordinates, Count, Member};
+
+    /// WKB of geometry `code` followed by `parts`, in either byte order.
+    fn wkb(little_endian: bool, code: u32, parts: &[Part]) -> Vec<u8> {
+        let mut bytes = vec![u8::from(little_endian)];
+        let word = |value: u32| {
+            if little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        };
+        bytes.extend(word(code));
+        for part in parts {
+            match part {
+                Part::Count(count) => bytes.extend(word(*count)),
+                Part::Coordinates(values) => {
+                    for value in *values {
+                        bytes.extend(if little_endian {
+                            value.to_le_bytes()
+                        } else {
+                            value.to_be_bytes()
+                        });
+                    }
+                }
+                Part::Member(member) => bytes.extend(member),
+            }
+        }
+        bytes
+    }
+
+    const SQUARE: [f64; 10] = [0.0, 0.0, 4.0, 0.0, 4.0, 4.0, 0.0, 4.0, 0.0, 0.0];
+    const HOLE: [f64; 8] = [1.0, 1.0, 2.0, 1.0, 1.0, 2.0, 1.0, 1.0];
+
+    fn polygon(little_endian: bool) -> Vec<u8> {
+        wkb(
+            little_endian,
+            3,
+            &[
+                Count(2),
+                Count(5),
+                Coordinates(&SQUARE),
+                Count(4),
+                Coordinates(&HOLE),
+            ],
+        )
+    }
+
+    #[test]
+    fn valid_geometries_pass() {
+        for little_endian in [true, false] {
+            let point = wkb(little_endian, 1, &[Coordinates(&[1.5, -2.0])]);
+            let line = wkb(little_endian, 2, &[Count(3), Coordinates(&SQUARE[..6])]);
+            let point_zm = wkb(little_endian, 3001, &[Coordinates(&[1.0, 2.0, 3.0, 4.0])]);
+            let line_z = wkb(little_endian, 1002, &[Count(2), Coordinates(&SQUARE[..6])]);
+            let empty_line = wkb(little_endian, 2, &[Count(0)]);
+            let multi = wkb(
+                !little_endian,
+                6,
+                &[Count(2), Member(polygon(true)), Member(polygon(false))],
+            );
+            let collection = wkb(
+                little_endian,
+                7,
+                &[Count(2), Member(point.clone()), Member(multi.clone())],
+            );
+            for value in [
+                &point,
+                &line,
+                &point_zm,
+                &line_z,
+                &empty_line,
+                &polygon(little_endian),
+                &multi,
+                &collection,
+            ] {
+                assert_eq!(check_wkb(value), Ok(()), "{value:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_polygon_names_the_ring() {
+        let polygon = polygon(true);
+        let message = check_wkb(&polygon[..polygon.len() - 8]).unwrap_err();
+        assert_eq!(
+            message,
+            "truncated Polygon ring at byte 97: needs 64 bytes, 56 left"
+        );
+        let message = check_wkb(&polygon[..7]).unwrap_err();
+        assert_eq!(
+            message,
+            "truncated Polygon ring count at byte 5: needs 4 bytes, 2 left"
+        );
+    }
+
+    #[test]
+    fn counts_must_match_the_length() {
+        let line = wkb(true, 2, &[Count(4), Coordinates(&SQUARE[..6])]);
+        assert!(check_wkb(&line)
+            .unwrap_err()
+            .starts_with("truncated LineString"));
+        let huge = wkb(true, 2, &[Count(u32::MAX)]);
+        assert!(check_wkb(&huge)
+            .unwrap_err()
+            .starts_with("truncated LineString"));
+        let mut point = wkb(true, 1, &[Coordinates(&[1.0, 2.0])]);
+        point.push(0);
+        assert_eq!(
+            check_wkb(&point).unwrap_err(),
+            "1 bytes after the geometry, which ends at byte 21"
+        );
+        assert_eq!(
+            check_wkb(&[]).unwrap_err(),
+            "truncated byte order at byte 0: needs 1 bytes, 0 left"
+        );
+    }
+
+    #[test]
+    fn unknown_headers_fail() {
+        let mut point = wkb(true, 1, &[Coordinates(&[1.0, 2.0])]);
+        point[0] = 2;
+        assert_eq!(
+            check_wkb(&point).unwrap_err(),
+            "invalid byte order 2 at byte 0"
+        );
+        for code in [0, 8, 4001] {
+            let value = wkb(true, code, &[]);
+            assert_eq!(
+                check_wkb(&value).unwrap_err(),
+                format!("unknown geometry type {code} at byte 0")
+            );
+        }
+    }
+
+    #[test]
+    fn nesting_is_bounded() {
+        let mut geometry = wkb(true, 1, &[Coordinates(&[0.0, 0.0])]);
+        for _ in 0..=MAX_DEPTH {
+            geometry = wkb(true, 7, &[Count(1), Member(geometry)]);
+        }
+        assert!(check_wkb(&geometry)
+            .unwrap_err()
+            .starts_with("collections nest deeper"));
+    }
+
+    #[test]
+    fn first_invalid_skips_nulls_in_every_layout() {
+        let valid = polygon(true);
+        let truncated = &valid[..20];
+        let values = [Some(valid.as_slice()), None, Some(truncated)];
+        let binary = BinaryArray::from_iter(values);
+        let views = BinaryViewArray::from_iter(values);
+        let large = arrow::array::LargeBinaryArray::from_iter(values);
+        for array in [&binary as &dyn Array, &views, &large] {
+            let (index, message) = first_invalid(array).unwrap();
+            assert_eq!(index, 2);
+            assert!(message.starts_with("truncated Polygon ring"), "{message}");
+            assert_eq!(first_invalid(&array.slice(0, 2)), None);
+        }
+    }
+}
@@ -0,0 +1,1320 @@
+//! Arrow value → JS value conversion.
+//!
+//! [`js_kind`] is the single source of truth for how each Arrow type is
+//! represented in JS. The converter ([`value_to_js`]) dispatches on it, and
+//! [`conversion_table`] enumerates it for the TypeScript typings generator,
+//! so the two cannot drift apart.
+
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::{
+    ArrowDictionaryKeyType, ArrowNativeType, DataType, Date32Type, Date64Type, Decimal128Type,
+    Decimal256Type, Decimal32Type, Decimal64Type, DurationMicrosecondType, DurationMillisecondType,
+    DurationNanosecondType, DurationSecondType, Field, Float16Type, Float32Type, Float64Type,
+    Int16Type, Int32Type, Int64Type, Int8Type, RunEndIndexType, Time32MillisecondType,
+    Time32SecondType, Time64MicrosecondType, Time64NanosecondType, TimeUnit,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_cast::base64::{Engine, BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use wasm_bindgen::prelude::*;
+
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+/// `Number.MAX_SAFE_INTEGER`, 2^53 - 1.
+const MAX_SAFE_INTEGER: u128 = (1 << 53) - 1;
+
+/// JS representation produced for a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsKind {
+    /// Always `null` (the Arrow `Null` type).
+    Null,
+    /// `boolean`.
+    Boolean,
+    /// `number`.
+    Number,
+    /// `bigint`.
+    BigInt,
+    /// `number` for safe integers, `bigint` beyond them.
+    SafeInteger,
+    /// `string`.
+    String,
+    /// `Date` object.
+    Date,
+    /// `Uint8Array` copy of the bytes.
+    Uint8Array,
+    /// Array of converted child values.
+    Array,
+    /// Plain object keyed by child field name.
+    Object,
+}
+
+impl JsKind {
+    /// Name used in the TypeScript typings.
+    pub const fn type_name(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Number => "number",
+            Self::BigInt => "bigint",
+            Self::SafeInteger => "number | bigint",
+            Self::String => "string",
+            Self::Date => "Date",
+            Self::Uint8Array => "Uint8Array",
+            Self::Array => "Array",
+            Self::Object => "object",
+        }
+    }
+}
+
+/// How Date/Timestamp values are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TemporalAs {
+    /// `Date` objects.
+    #[default]
+    Date,
+    /// Epoch milliseconds as a `number`.
+    Number,
+    /// The raw stored integer (days, or the type's time unit) as a `bigint`.
+    BigInt,
+}
+
+impl TemporalAs {
+    /// Every policy, in the order the conversion table lists them.
+    pub const ALL: [Self; 3] = [Self::Date, Self::Number, Self::BigInt];
+
+    /// Policy name used in the conversion table and options.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Date => "date",
+            Self::Number => "number",
+            Self::BigInt => "bigint",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown temporalAs '{name}', expected 'date', 'number' or 'bigint'"
+                ))
+            })
+    }
+}
+
+/// How 64-bit integers (and integer-backed Time64/Duration) are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Int64As {
+    /// `bigint`, exact.
+    #[default]
+    BigInt,
+    /// `number`, losing precision beyond 2^53.
+    Number,
+    /// `number` when the value is a safe integer (magnitude below 2^53),
+    /// else an exact `bigint`.
+    Safe,
+}
+
+impl Int64As {
+    /// Every policy, in the order the conversion table lists them.
+    pub const ALL: [Self; 3] = [Self::BigInt, Self::Number, Self::Safe];
+
+    /// Policy name used in the conversion table.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::BigInt => "bigint",
+            Self::Number => "number",
+            Self::Safe => "safe",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown int64As '{name}', expected 'bigint', 'number' or 'safe'"
+                ))
+            })
+    }
+}
+
+/// How dictionary-encoded values are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DictionaryAs {
+    /// The decoded value.
+    #[default]
+    Values,
+    /// The dictionary key, as a `number`.
+    Keys,
+}
+
+impl DictionaryAs {
+    /// Every policy.
+    pub const ALL: [Self; 2] = [Self::Values, Self::Keys];
+
+    /// Policy name used in options.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Values => "values",
+            Self::Keys => "keys",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown dictionaryAs '{name}', expected 'values' or 'keys'"
+                ))
+            })
+    }
+}
+
+/// How binary values are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BinaryAs {
+    /// A `Uint8Array` copy of the bytes.
+    #[default]
+    Uint8Array,
+    /// A standard, padded base64 `string`.
+    Base64,
+}
+
+impl BinaryAs {
+    /// Every policy.
+    pub const ALL: [Self; 2] = [Self::Uint8Array, Self::Base64];
+
+    /// Policy name used in options.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Uint8Array => "uint8array",
+            Self::Base64 => "base64",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown binaryAs '{name}', expected 'uint8array' or 'base64'"
+                ))
+            })
+    }
+}
+
+/// How decimal values are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecimalAs {
+    /// The exact decimal text, e.g. `"-12.30"` for scale 2.
+    #[default]
+    String,
+    /// The nearest `number`, losing digits beyond what an f64 holds.
+    Number,
+}
+
+impl DecimalAs {
+    /// Every policy.
+    pub const ALL: [Self; 2] = [Self::String, Self::Number];
+
+    /// Policy name used in options.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown decimalAs '{name}', expected 'string' or 'number'"
+                ))
+            })
+    }
+}
+
+/// Field metadata key naming a field's extension type.
+pub const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+
+/// How values of extension-typed fields (fields carrying
+/// `ARROW:extension:name` metadata) are represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtensionHandling {
+    /// Converted like their storage type, ignoring the extension.
+    #[default]
+    Storage,
+    /// Not converted: accessors fail, naming the extension.
+    Error,
+}
+
+impl ExtensionHandling {
+    /// Every policy.
+    pub const ALL: [Self; 2] = [Self::Storage, Self::Error];
+
+    /// Policy name used in options.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Storage => "storage",
+            Self::Error => "error",
+        }
+    }
+
+    /// Parse a policy name.
+    pub fn parse(name: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name)
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown extensionHandling '{name}', expected 'storage' or 'error'"
+                ))
+            })
+    }
+}
+
+/// Default [`ConversionOptions::max_nesting_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
+/// Options controlling [`value_to_js`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionOptions {
+    /// Date/Timestamp representation.
+    pub temporal_as: TemporalAs,
+    /// 64-bit integer representation.
+    pub int64_as: Int64As,
+    /// Decimal representation.
+    pub decimal_as: DecimalAs,
+    /// Dictionary representation.
+    pub dictionary_as: DictionaryAs,
+    /// Binary representation.
+    pub binary_as: BinaryAs,
+    /// Strings longer than this many characters are cut to it.
+    pub max_string_length: Option<usize>,
+    /// Extension-typed field handling.
+    pub extension_handling: ExtensionHandling,
+    /// Most list and struct levels a value may nest; deeper values fail
+    /// instead of recursing further.
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        Self {
+            temporal_as: TemporalAs::default(),
+            int64_as: Int64As::default(),
+            decimal_as: DecimalAs::default(),
+            dictionary_as: DictionaryAs::default(),
+            binary_as: BinaryAs::default(),
+            max_string_length: None,
+            extension_handling: ExtensionHandling::default(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+        }
+    }
+}
+
+/// Fields of a [`ConversionPolicy`], each optional so an object can
+/// override some of them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct PolicyFields {
+    temporal_as: Option<String>,
+    int64_as: Option<String>,
+    decimal_as: Option<String>,
+    dictionary_as: Option<String>,
+    binary_as: Option<String>,
+    max_string_length: Option<usize>,
+    extension_handling: Option<String>,
+    max_nesting_depth: Option<usize>,
+}
+
+impl ConversionOptions {
+    /// These options with the fields set in `fields` replaced.
+    fn with_fields(mut self, fields: PolicyFields) -> Result<Self> {
+        if let Some(name) = fields.temporal_as {
+            self.temporal_as = TemporalAs::parse(&name)?;
+        }
+        if let Some(name) = fields.int64_as {
+            self.int64_as = Int64As::parse(&name)?;
+        }
+        if let Some(name) = fields.decimal_as {
+            self.decimal_as = DecimalAs::parse(&name)?;
+        }
+        if let Some(name) = fields.dictionary_as {
+            self.dictionary_as = DictionaryAs::parse(&name)?;
+        }
+        if let Some(name) = fields.binary_as {
+            self.binary_as = BinaryAs::parse(&name)?;
+        }
+        if fields.max_string_length.is_some() {
+            self.max_string_length = fields.max_string_length;
+        }
+        if let Some(name) = fields.extension_handling {
+            self.extension_handling = ExtensionHandling::parse(&name)?;
+        }
+        if let Some(depth) = fields.max_nesting_depth {
+            self.max_nesting_depth = depth;
+        }
+        Ok(self)
+    }
+
+    /// These options with the fields of the JS object `overrides` replaced;
+    /// `undefined` and `null` change nothing.
+    pub fn with_overrides(self, overrides: JsValue) -> Result<Self> {
+        if overrides.is_undefined() || overrides.is_null() {
+            return Ok(self);
+        }
+        self.with_fields(serde_wasm_bindgen::from_value(overrides).map_err(ArrowWasmError::from)?)
+    }
+}
+
+/// How the value accessors represent values in JS.
+///
+/// Accessors resolve the policy per call, in this order of precedence: the
+/// call's own overrides (an object with the same fields, e.g.
+/// `column.get(i, {temporalAs: "number"})`), then the policy set on the
+/// table with `set_table_conversion_policy`, then the global one set with
+/// `set_conversion_policy`, then the defaults. A table policy replaces the
+/// global one entirely; call overrides replace single fields.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConversionPolicy {
+    options: ConversionOptions,
+}
+
+#[wasm_bindgen]
+impl ConversionPolicy {
+    /// Policy from `{temporalAs?, int64As?, decimalAs?, dictionaryAs?,
+    /// binaryAs?, maxStringLength?, extensionHandling?, maxNestingDepth?}`;
+    /// unset fields keep their defaults (`"date"`, `"bigint"`, `"string"`,
+    /// `"values"`, `"uint8array"`, no limit, `"storage"`, 64).
+    #[wasm_bindgen(constructor)]
+    pub fn new(fields: JsValue) -> std::result::Result<Self, JsValue> {
+        Ok(Self {
+            options: ConversionOptions::default().with_overrides(fields)?,
+        })
+    }
+
+    /// `"date"`, `"number"` or `"bigint"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = temporalAs)]
+    pub fn temporal_as(&self) -> String {
+        self.options.temporal_as.name().to_string()
+    }
+
+    /// `"bigint"`, `"number"` or `"safe"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = int64As)]
+    pub fn int64_as(&self) -> String {
+        self.options.int64_as.name().to_string()
+    }
+
+    /// `"string"` or `"number"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = decimalAs)]
+    pub fn decimal_as(&self) -> String {
+        self.options.decimal_as.name().to_string()
+    }
+
+    /// `"values"` or `"keys"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = dictionaryAs)]
+    pub fn dictionary_as(&self) -> String {
+        self.options.dictionary_as.name().to_string()
+    }
+
+    /// `"uint8array"` or `"base64"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = binaryAs)]
+    pub fn binary_as(&self) -> String {
+        self.options.binary_as.name().to_string()
+    }
+
+    /// Character limit for strings, if any.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = maxStringLength)]
+    pub fn max_string_length(&self) -> Option<usize> {
+        self.options.max_string_length
+    }
+
+    /// `"storage"` or `"error"`.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = extensionHandling)]
+    pub fn extension_handling(&self) -> String {
+        self.options.extension_handling.name().to_string()
+    }
+
+    /// Most list and struct levels a value may nest before conversion fails.
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = maxNestingDepth)]
+    pub fn max_nesting_depth(&self) -> usize {
+        self.options.max_nesting_depth
+    }
+
+    /// Copy of the policy with the fields set in `overrides` replaced.
+    pub fn with(&self, overrides: JsValue) -> std::result::Result<Self, JsValue> {
+        Ok(Self {
+            options: self.options.with_overrides(overrides)?,
+        })
+    }
+}
+
+/// Policy of accessors on tables without one of their own.
+static GLOBAL_POLICY: LazyLock<Mutex<ConversionOptions>> =
+    LazyLock::new(|| Mutex::new(ConversionOptions::default()));
+
+/// Policies set with [`set_table_conversion_policy`], by table.
+static TABLE_POLICIES: LazyLock<Mutex<HashMap<TableHandle, ConversionOptions>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Options accessors on `handle` use before call overrides: its table
+/// policy, else the global one.
+pub fn table_options(handle: TableHandle) -> ConversionOptions {
+    TABLE_POLICIES
+        .lock()
+        .ok()
+        .and_then(|policies| policies.get(&handle).copied())
+        .or_else(|| GLOBAL_POLICY.lock().ok().map(|policy| *policy))
+        .unwrap_or_default()
+}
+
+/// Set the policy of every table without a policy of its own.
+#[wasm_bindgen]
+pub fn set_conversion_policy(policy: &ConversionPolicy) -> std::result::Result<(), JsValue> {
+    *GLOBAL_POLICY
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire policy lock".to_string()))? =
+        policy.options;
+    Ok(())
+}
+
+/// The global policy.
+#[wasm_bindgen]
+pub fn conversion_policy() -> ConversionPolicy {
+    ConversionPolicy {
+        options: GLOBAL_POLICY
+            .lock()
+            .map(|policy| *policy)
+            .unwrap_or_default(),
+    }
+}
+
+/// Set the policy of the table `handle`, taking precedence over the global
+/// one for accessors on it and its columns.
+///
+/// Tables derived from it (filters, selections, kernel results) do not
+/// inherit it. Policies of freed tables are dropped on the next call.
+#[wasm_bindgen]
+pub fn set_table_conversion_policy(
+    handle: TableHandle,
+    policy: &ConversionPolicy,
+) -> std::result::Result<(), JsValue> {
+    mem::get_table(handle)?;
+    let mut policies = TABLE_POLICIES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire policy lock".to_string()))?;
+    policies.retain(|handle, _| mem::table_exists(*handle));
+    policies.insert(handle, policy.options);
+    drop(policies);
+    Ok(())
+}
+
+/// Remove the policy of the table `handle`, so it follows the global one
+/// again; returns whether it had one.
+#[wasm_bindgen]
+pub fn clear_table_conversion_policy(handle: TableHandle) -> bool {
+    TABLE_POLICIES
+        .lock()
+        .is_ok_and(|mut policies| policies.remove(&handle).is_some())
+}
+
+/// JS representation of `data_type` under `options`, or `None` when the type
+/// cannot be converted.
+pub fn js_kind(data_type: &DataType, options: ConversionOptions) -> Option<JsKind> {
+    let int64 = match options.int64_as {
+        Int64As::BigInt => JsKind::BigInt,
+        Int64As::Number => JsKind::Number,
+        Int64As::Safe => JsKind::SafeInteger,
+    };
+    let temporal = match options.temporal_as {
+        TemporalAs::Date => JsKind::Date,
+        TemporalAs::Number => JsKind::Number,
+        TemporalAs::BigInt => JsKind::BigInt,
+    };
+    match data_type {
+        DataType::Null => Some(JsKind::Null),
+        DataType::Boolean => Some(JsKind::Boolean),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Time32(_) => Some(JsKind::Number),
+        DataType::Int64 | DataType::UInt64 | DataType::Time64(_) | DataType::Duration(_) => {
+            Some(int64)
+        }
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => Some(temporal),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(JsKind::String),
+        DataType::Decimal32(_, _)
+        | DataType::Decimal64(_, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => Some(match options.decimal_as {
+            DecimalAs::String => JsKind::String,
+            DecimalAs::Number => JsKind::Number,
+        }),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => Some(match options.binary_as {
+            BinaryAs::Uint8Array => JsKind::Uint8Array,
+            BinaryAs::Base64 => JsKind::String,
+        }),
+        DataType::Dictionary(_, value_type) => match options.dictionary_as {
+            DictionaryAs::Values => js_kind(value_type, options),
+            DictionaryAs::Keys => Some(JsKind::Number),
+        },
+        DataType::RunEndEncoded(_, values) => js_kind(values.data_type(), options),
+        DataType::List(child) | DataType::LargeList(child) | DataType::FixedSizeList(child, _) => {
+            js_kind(child.data_type(), options).map(|_| JsKind::Array)
+        }
+        DataType::Struct(fields) => fields
+            .iter()
+            .all(|field| js_kind(field.data_type(), options).is_some())
+            .then_some(JsKind::Object),
+        _ => None,
+    }
+}
+
+/// Whether a `data_type` value can convert to `null` where the column's
+/// validity marks it valid: the Null type has no values, and dictionary and
+/// run-end encoded values keep their nulls outside that validity, so a
+/// non-nullable field of these types can still give `null`.
+pub fn null_without_validity(data_type: &DataType, options: ConversionOptions) -> bool {
+    match data_type {
+        DataType::Null | DataType::RunEndEncoded(_, _) => true,
+        DataType::Dictionary(_, _) => options.dictionary_as == DictionaryAs::Values,
+        _ => false,
+    }
+}
+
+/// Representative instances of every type family [`js_kind`] supports.
+pub fn supported_types() -> Vec<DataType> {
+    let item = |data_type| Arc::new(Field::new("item", data_type, true));
+    let mut types = vec![
+        DataType::Null,
+        DataType::Boolean,
+        DataType::Int8,
+        DataType::Int16,
+        DataType::Int32,
+        DataType::Int64,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt32,
+        DataType::UInt64,
+        DataType::Float16,
+        DataType::Float32,
+        DataType::Float64,
+        DataType::Time32(TimeUnit::Second),
+        DataType::Time32(TimeUnit::Millisecond),
+        DataType::Time64(TimeUnit::Microsecond),
+        DataType::Time64(TimeUnit::Nanosecond),
+        DataType::Date32,
+        DataType::Date64,
+        DataType::Utf8,
+        DataType::LargeUtf8,
+        DataType::Utf8View,
+        DataType::Decimal32(9, 2),
+        DataType::Decimal64(18, 4),
+        DataType::Decimal128(38, 10),
+        DataType::Decimal256(76, 20),
+        DataType::Binary,
+        DataType::LargeBinary,
+        DataType::BinaryView,
+        DataType::FixedSizeBinary(16),
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            item(DataType::Utf8),
+        ),
+        DataType::List(item(DataType::Int32)),
+        DataType::LargeList(item(DataType::Utf8)),
+        DataType::FixedSizeList(item(DataType::Float32), 3),
+        DataType::Struct(vec![Field::new("x", DataType::Float64, true)].into()),
+    ];
+    for unit in [
+        TimeUnit::Second,
+        TimeUnit::Millisecond,
+        TimeUnit::Microsecond,
+        TimeUnit::Nanosecond,
+    ] {
+        types.push(DataType::Timestamp(unit, None));
+        types.push(DataType::Duration(unit));
+    }
+    types
+}
+
+/// One row of the conversion table.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionEntry {
+    /// Arrow type, in arrow-rs `Display` form.
+    pub arrow_type: String,
+    /// `temporalAs` policy the row applies to.
+    pub temporal_as: &'static str,
+    /// `int64As` policy the row applies to.
+    pub int64_as: &'static str,
+    /// Resulting JS type name.
+    pub js_type: &'static str,
+    /// Whether the converted value may be `null` even in a non-nullable
+    /// field; values of nullable fields always may.
+    pub nullable: bool,
+}
+
+/// Every (supported type, policy) combination and the JS type it yields.
+pub fn conversion_entries() -> Vec<ConversionEntry> {
+    let mut entries = Vec::new();
+    for data_type in supported_types() {
+        for temporal_as in TemporalAs::ALL {
+            for int64_as in Int64As::ALL {
+                let options = ConversionOptions {
+                    temporal_as,
+                    int64_as,
+                    ..ConversionOptions::default()
+                };
+                if let Some(kind) = js_kind(&data_type, options) {
+                    entries.push(ConversionEntry {
+                        arrow_type: data_type.to_string(),
+                        temporal_as: temporal_as.name(),
+                        int64_as: int64_as.name(),
+                        js_type: kind.type_name(),
+                        nullable: null_without_validity(&data_type, options),
+                    });
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// The conversion table as pretty-printed JSON, for build scripts.
+pub fn conversion_table_json() -> Result<String> {
+    Ok(serde_json::to_string_pretty(&conversion_entries())?)
+}
+
+/// Conversion rules as `[{arrowType, temporalAs, int64As, jsType, nullable}]`.
+#[wasm_bindgen]
+pub fn conversion_table() -> std::result::Result<JsValue, JsValue> {
+    Ok(serde_wasm_bindgen::to_value(&conversion_entries()).map_err(ArrowWasmError::from)?)
+}
+
+/// Set `object[key] = value`.
+pub fn set_property(object: &js_sys::Object, key: &str, value: &JsValue) -> Result<()> {
+    js_sys::Reflect::set(object, &key.into(), value)
+        .map_err(|_| ArrowWasmError::Other(format!("Failed to set property '{key}'")))?;
+    Ok(())
+}
+
+fn unsupported(data_type: &DataType) -> ArrowWasmError {
+    ArrowWasmError::InvalidInput(format!("Unsupported data type: {data_type:?}"))
+}
+
+/// An extractor was handed a type [`js_kind`] does not route to it.
+fn kind_mismatch(data_type: &DataType) -> ArrowWasmError {
+    ArrowWasmError::Internal(format!("no JS conversion for {data_type:?} in this kind"))
+}
+
+/// Numeric value of a `JsKind::Number` slot.
+fn number_at(array: &dyn Array, index: usize) -> Result<f64> {
+    let value = match array.data_type() {
+        DataType::Int8 => f64::from(array.as_primitive::<Int8Type>().value(index)),
+        DataType::Int16 => f64::from(array.as_primitive::<Int16Type>().value(index)),
+        DataType::Int32 => f64::from(array.as_primitive::<Int32Type>().value(index)),
+        DataType::Int64 => array.as_primitive::<Int64Type>().value(index) as f64,
+        DataType::UInt8 => f64::from(array.as_primitive::<UInt8Type>().value(index)),
+        DataType::UInt16 => f64::from(array.as_primitive::<UInt16Type>().value(index)),
+        DataType::UInt32 => f64::from(array.as_primitive::<UInt32Type>().value(index)),
+        DataType::UInt64 => array.as_primitive::<UInt64Type>().value(index) as f64,
+        DataType::Float16 => array.as_primitive::<Float16Type>().value(index).to_f64(),
+        DataType::Float32 => f64::from(array.as_primitive::<Float32Type>().value(index)),
+        DataType::Float64 => array.as_primitive::<Float64Type>().value(index),
+        DataType::Time32(TimeUnit::Second) => {
+            f64::from(array.as_primitive::<Time32SecondType>().value(index))
+        }
+        DataType::Time32(_) => {
+            f64::from(array.as_primitive::<Time32MillisecondType>().value(index))
+        }
+        DataType::Time64(_) | DataType::Duration(_) => integer_at(array, index)? as f64,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+            epoch_millis_at(array, index)?
+        }
+        // The decimal text parses to the nearest f64.
+        DataType::Decimal32(_, _)
+        | DataType::Decimal64(_, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => decimal_text_at(array, index)?
+            .parse()
+            .map_err(|e| ArrowWasmError::Internal(format!("decimal text: {e}")))?,
+        other => return Err(kind_mismatch(other)),
+    };
+    Ok(value)
+}
+
+/// Exact decimal text of a Decimal slot, e.g. `"-12.30"` for scale 2.
+fn decimal_text_at(array: &dyn Array, index: usize) -> Result<String> {
+    Ok(match array.data_type() {
+        DataType::Decimal32(_, _) => array.as_primitive::<Decimal32Type>().value_as_string(index),
+        DataType::Decimal64(_, _) => array.as_primitive::<Decimal64Type>().value_as_string(index),
+        DataType::Decimal128(_, _) => array
+            .as_primitive::<Decimal128Type>()
+            .value_as_string(index),
+        DataType::Decimal256(_, _) => array
+            .as_primitive::<Decimal256Type>()
+            .value_as_string(index),
+        other => return Err(kind_mismatch(other)),
+    })
+}
+
+/// Raw integer of an Int64/UInt64 or temporal slot.
+fn integer_at(array: &dyn Array, index: usize) -> Result<i128> {
+    let value = match array.data_type() {
+        DataType::Int64 => array.as_primitive::<Int64Type>().value(index).into(),
+        DataType::UInt64 => array.as_primitive::<UInt64Type>().value(index).into(),
+        DataType::Date32 => array.as_primitive::<Date32Type>().value(index).into(),
+        DataType::Date64 => array.as_primitive::<Date64Type>().value(index).into(),
+        DataType::Timestamp(TimeUnit::Second, _) => array
+            .as_primitive::<TimestampSecondType>()
+            .value(index)
+            .into(),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => array
+            .as_primitive::<TimestampMillisecondType>()
+            .value(index)
+            .into(),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => array
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(index)
+            .into(),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => array
+            .as_primitive::<TimestampNanosecondType>()
+            .value(index)
+            .into(),
+        DataType::Time32(TimeUnit::Second) => {
+            array.as_primitive::<Time32SecondType>().value(index).into()
+        }
+        DataType::Time32(_) => array
+            .as_primitive::<Time32MillisecondType>()
+            .value(index)
+            .into(),
+        DataType::Time64(TimeUnit::Microsecond) => array
+            .as_primitive::<Time64MicrosecondType>()
+            .value(index)
+            .into(),
+        DataType::Time64(_) => array
+            .as_primitive::<Time64NanosecondType>()
+            .value(index)
+            .into(),
+        DataType::Duration(TimeUnit::Second) => array
+            .as_primitive::<DurationSecondType>()
+            .value(index)
+            .into(),
+        DataType::Duration(TimeUnit::Millisecond) => array
+            .as_primitive::<DurationMillisecondType>()
+            .value(index)
+            .into(),
+        DataType::Duration(TimeUnit::Microsecond) => array
+            .as_primitive::<DurationMicrosecondType>()
+            .value(index)
+            .into(),
+        DataType::Duration(TimeUnit::Nanosecond) => array
+            .as_primitive::<DurationNanosecondType>()
+            .value(index)
+            .into(),
+        other => return Err(kind_mismatch(other)),
+    };
+    Ok(value)
+}
+
+/// Milliseconds since the Unix epoch of a Date/Timestamp slot.
+fn epoch_millis_at(array: &dyn Array, index: usize) -> Result<f64> {
+    let millis = match array.data_type() {
+        DataType::Date32 => f64::from(array.as_primitive::<Date32Type>().value(index)) * MS_PER_DAY,
+        DataType::Date64 => array.as_primitive::<Date64Type>().value(index) as f64,
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            array.as_primitive::<TimestampSecondType>().value(index) as f64 * 1_000.0
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => array
+            .as_primitive::<TimestampMillisecondType>()
+            .value(index) as f64,
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            array
+                .as_primitive::<TimestampMicrosecondType>()
+                .value(index) as f64
+                / 1_000.0
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            array.as_primitive::<TimestampNanosecondType>().value(index) as f64 / 1_000_000.0
+        }
+        other => return Err(kind_mismatch(other)),
+    };
+    Ok(millis)
+}
+
+fn bytes_at(array: &dyn Array, index: usize) -> Result<&[u8]> {
+    Ok(match array.data_type() {
+        DataType::Binary => array.as_binary::<i32>().value(index),
+        DataType::LargeBinary => array.as_binary::<i64>().value(index),
+        DataType::BinaryView => array.as_binary_view().value(index),
+        DataType::FixedSizeBinary(_) => array.as_fixed_size_binary().value(index),
+        other => return Err(kind_mismatch(other)),
+    })
+}
+
+fn string_at(array: &dyn Array, index: usize) -> Result<&str> {
+    Ok(match array.data_type() {
+        DataType::Utf8 => array.as_string::<i32>().value(index),
+        DataType::LargeUtf8 => array.as_string::<i64>().value(index),
+        DataType::Utf8View => array.as_string_view().value(index),
+        other => return Err(kind_mismatch(other)),
+    })
+}
+
+fn dictionary_value_to_js<K: ArrowDictionaryKeyType>(
+    array: &dyn Array,
+    index: usize,
+    options: ConversionOptions,
+) -> Result<JsValue> {
+    let dictionary = array.as_dictionary::<K>();
+    let key = dictionary.keys().value(index).as_usize();
+    if options.dictionary_as == DictionaryAs::Keys {
+        return Ok(JsValue::from_f64(key as f64));
+    }
+    value_to_js(dictionary.values().as_ref(), key, options)
+}
+
+fn run_value_to_js<R: RunEndIndexType>(
+    array: &dyn Array,
+    index: usize,
+    options: ConversionOptions,
+) -> Result<JsValue> {
+    let runs = array.as_run::<R>();
+    value_to_js(
+        runs.values().as_ref(),
+        runs.get_physical_index(index),
+        options,
+    )
+}
+
+fn list_to_js(values: &dyn Array, options: ConversionOptions) -> Result<JsValue> {
+    let result = js_sys::Array::new_with_length(values.len() as u32);
+    for i in 0..values.len() {
+        result.set(i as u32, value_to_js(values, i, options)?);
+    }
+    Ok(result.into())
+}
+
+/// Whether `data_type` nests lists and structs more than `max` levels
+/// deep. Dictionaries and run-end encoding add no level. Stops descending
+/// once past `max`, so arbitrarily deep types are rejected cheaply.
+fn nests_deeper_than(data_type: &DataType, max: usize) -> bool {
+    match data_type {
+        DataType::List(child) | DataType::LargeList(child) | DataType::FixedSizeList(child, _) => {
+            max == 0 || nests_deeper_than(child.data_type(), max - 1)
+        }
+        DataType::Struct(fields) => {
+            max == 0
+                || fields
+                    .iter()
+                    .any(|field| nests_deeper_than(field.data_type(), max - 1))
+        }
+        DataType::Dictionary(_, values) => nests_deeper_than(values, max),
+        DataType::RunEndEncoded(_, values) => nests_deeper_than(values.data_type(), max),
+        _ => false,
+    }
+}
+
+/// Check that values of `field` may be converted under `options`: its type
+/// may not nest lists and structs deeper than `options.max_nesting_depth`,
+/// and with `ExtensionHandling::Error`, neither it nor any field nested in
+/// it may carry an extension type.
+///
+/// Accessors call this once per column before converting any value with
+/// [`value_to_js`], which checks neither.
+pub fn check_field(field: &Field, options: ConversionOptions) -> Result<()> {
+    if nests_deeper_than(field.data_type(), options.max_nesting_depth) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "value nests lists and structs deeper than maxNestingDepth ({}); raise it in the \
+             ConversionPolicy to convert it",
+            options.max_nesting_depth
+        )));
+    }
+    if options.extension_handling == ExtensionHandling::Error {
+        if let Some(name) = extension_name(field) {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "extension type '{name}' is not converted; set extensionHandling to \
+                 'storage' to read its storage values"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Extension name of `field` or of the first field nested in it that has
+/// one.
+fn extension_name(field: &Field) -> Option<&str> {
+    if let Some(name) = field.metadata().get(EXTENSION_NAME_KEY) {
+        return Some(name);
+    }
+    match field.data_type() {
+        DataType::List(child)
+        | DataType::LargeList(child)
+        | DataType::FixedSizeList(child, _)
+        | DataType::RunEndEncoded(_, child) => extension_name(child),
+        DataType::Struct(fields) => fields.iter().find_map(|field| extension_name(field)),
+        _ => None,
+    }
+}
+
+/// Convert the slot at `index` of `array` to a JS value.
+///
+/// Nulls become `null`; the representation of non-null values follows
+/// [`js_kind`]. Conversion failures are always an `Err`, never encoded in
+/// the returned value. Callers are responsible for bounds checking, and
+/// for calling [`check_field`] on the column first so that the recursion
+/// into lists and structs is bounded.
+pub fn value_to_js(array: &dyn Array, index: usize, options: ConversionOptions) -> Result<JsValue> {
+    // Run-end encoded arrays keep their nulls in the values.
+    if let DataType::RunEndEncoded(run_ends, _) = array.data_type() {
+        return match run_ends.data_type() {
+            DataType::Int16 => run_value_to_js::<Int16Type>(array, index, options),
+            DataType::Int32 => run_value_to_js::<Int32Type>(array, index, options),
+            DataType::Int64 => run_value_to_js::<Int64Type>(array, index, options),
+            other => Err(kind_mismatch(other)),
+        };
+    }
+    if array.is_null(index) {
+        return Ok(JsValue::NULL);
+    }
+    let data_type = array.data_type();
+    let kind = js_kind(data_type, options).ok_or_else(|| unsupported(data_type))?;
+
+    if let DataType::Dictionary(key_type, _) = data_type {
+        return match key_type.as_ref() {
+            DataType::Int8 => dictionary_value_to_js::<Int8Type>(array, index, options),
+            DataType::Int16 => dictionary_value_to_js::<Int16Type>(array, index, options),
+            DataType::Int32 => dictionary_value_to_js::<Int32Type>(array, index, options),
+            DataType::Int64 => dictionary_value_to_js::<Int64Type>(array, index, options),
+            DataType::UInt8 => dictionary_value_to_js::<UInt8Type>(array, index, options),
+            DataType::UInt16 => dictionary_value_to_js::<UInt16Type>(array, index, options),
+            DataType::UInt32 => dictionary_value_to_js::<UInt32Type>(array, index, options),
+            DataType::UInt64 => dictionary_value_to_js::<UInt64Type>(array, index, options),
+            _ => Err(kind_mismatch(data_type)),
+        };
+    }
+
+    Ok(match kind {
+        JsKind::Null => JsValue::NULL,
+        JsKind::Boolean => JsValue::from_bool(array.as_boolean().value(index)),
+        JsKind::Number => JsValue::from_f64(number_at(array, index)?),
+        JsKind::BigInt => js_sys::BigInt::from(integer_at(array, index)?).into(),
+        JsKind::SafeInteger => {
+            let value = integer_at(array, index)?;
+            if value.unsigned_abs() <= MAX_SAFE_INTEGER {
+                JsValue::from_f64(value as f64)
+            } else {
+                js_sys::BigInt::from(value).into()
+            }
+        }
+        JsKind::Date => {
+            js_sys::Date::new(&JsValue::from_f64(epoch_millis_at(array, index)?)).into()
+        }
+        JsKind::String
+            if matches!(
+                data_type,
+                DataType::Binary
+                    | DataType::LargeBinary
+                    | DataType::BinaryView
+                    | DataType::FixedSizeBinary(_)
+            ) =>
+        {
+            JsValue::from_str(&BASE64_STANDARD.encode(bytes_at(array, index)?))
+        }
+        JsKind::String
+            if matches!(
+                data_type,
+                DataType::Decimal32(_, _)
+                    | DataType::Decimal64(_, _)
+                    | DataType::Decimal128(_, _)
+                    | DataType::Decimal256(_, _)
+            ) =>
+        {
+            JsValue::from_str(&decimal_text_at(array, index)?)
+        }
+        JsKind::String => {
+            let text = string_at(array, index)?;
+            JsValue::from_str(options.max_string_length.map_or(text, |max| {
+                text.char_indices()
+                    .nth(max)
+                    .map_or(text, |(end, _)| &text[..end])
+            }))
+        }
+        JsKind::Uint8Array => js_sys::Uint8Array::from(bytes_at(array, index)?).into(),
+        JsKind::Array => match data_type {
+            DataType::List(_) => list_to_js(array.as_list::<i32>().value(index).as_ref(), options)?,
+            DataType::LargeList(_) => {
+                list_to_js(array.as_list::<i64>().value(index).as_ref(), options)?
+            }
+            DataType::FixedSizeList(_, _) => {
+                list_to_js(array.as_fixed_size_list().value(index).as_ref(), options)?
+            }
+            other => return Err(kind_mismatch(other)),
+        },
+        JsKind::Object => {
+            let structure = array.as_struct();
+            let object = js_sys::Object::new();
+            for (field, child) in structure.fields().iter().zip(structure.columns()) {
+                let value = value_to_js(child.as_ref(), index, options)?;
+                set_property(&object, field.name(), &value)?;
+            }
+            object.into()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{i256, IntervalUnit};
+
+    /// Every combination of the enumerated policies.
+    fn all_options() -> Vec<ConversionOptions> {
+        let mut all = Vec::new();
+        for temporal_as in TemporalAs::ALL {
+            for int64_as in Int64As::ALL {
+                for dictionary_as in DictionaryAs::ALL {
+                    for binary_as in BinaryAs::ALL {
+                        for decimal_as in DecimalAs::ALL {
+                            all.push(ConversionOptions {
+                                temporal_as,
+                                int64_as,
+                                decimal_as,
+                                dictionary_as,
+                                binary_as,
+                                ..ConversionOptions::default()
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn every_supported_type_converts_under_every_policy() {
+        for data_type in supported_types() {
+            for options in all_options() {
+                assert!(
+                    js_kind(&data_type, options).is_some(),
+                    "{data_type} under {options:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_table_covers_every_supported_type() {
+        let entries = conversion_entries();
+        let policies = TemporalAs::ALL.len() * Int64As::ALL.len();
+        assert_eq!(entries.len(), supported_types().len() * policies);
+        for data_type in supported_types() {
+            let name = data_type.to_string();
+            let rows = entries.iter().filter(|entry| entry.arrow_type == name);
+            assert_eq!(rows.count(), policies, "{name}");
+        }
+        let json: serde_json::Value =
+            serde_json::from_str(&conversion_table_json().unwrap()).unwrap();
+        assert_eq!(json.as_array().map(Vec::len), Some(entries.len()));
+    }
+
+    #[test]
+    fn entries_follow_the_policies() {
+        let entry = |arrow_type: &str, temporal_as: &str, int64_as: &str| {
+            conversion_entries()
+                .into_iter()
+                .find(|entry| {
+                    entry.arrow_type == arrow_type
+                        && entry.temporal_as == temporal_as
+                        && entry.int64_as == int64_as
+                })
+                .unwrap()
+        };
+        assert_eq!(entry("Int64", "date", "number").js_type, "number");
+        assert_eq!(entry("Int64", "date", "bigint").js_type, "bigint");
+        assert_eq!(entry("Int64", "date", "safe").js_type, "number | bigint");
+        assert_eq!(entry("Date32", "date", "number").js_type, "Date");
+        assert_eq!(entry("Date32", "bigint", "number").js_type, "bigint");
+        assert_eq!(entry("Null", "date", "bigint").js_type, "null");
+    }
+
+    #[test]
+    fn nullability_comes_from_the_type() {
+        let options = ConversionOptions::default();
+        let keys = ConversionOptions {
+            dictionary_as: DictionaryAs::Keys,
+            ..options
+        };
+        let dictionary = DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+        let run_ends = DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", DataType::Int64, true)),
+        );
+        assert!(null_without_validity(&DataType::Null, options));
+        assert!(null_without_validity(&dictionary, options));
+        assert!(!null_without_validity(&dictionary, keys));
+        assert!(null_without_validity(&run_ends, options));
+        assert!(!null_without_validity(&DataType::Int32, options));
+
+        let nullable: Vec<String> = conversion_entries()
+            .into_iter()
+            .filter(|entry| entry.nullable)
+            .map(|entry| entry.arrow_type)
+            .collect();
+        let supported = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        assert!(nullable.contains(&supported.to_string()));
+        assert!(nullable.iter().all(|name| name == "Null"
+            || name.starts_with("Dictionary")
+            || name.starts_with("RunEndEncoded")));
+    }
+
+    #[test]
+    fn unsupported_types_have_no_kind() {
+        let options = ConversionOptions::default();
+        let interval = DataType::Interval(IntervalUnit::MonthDayNano);
+        assert_eq!(js_kind(&interval, options), None);
+        let unsupported = DataType::Struct(vec![Field::new("span", interval.clone(), true)].into());
+        assert_eq!(js_kind(&unsupported, options), None);
+        assert_eq!(
+            js_kind(
+                &DataType::List(Arc::new(Field::new("item", interval, true))),
+                options
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn decimals_follow_decimal_as() {
+        let decimal = DataType::Decimal128(10, 2);
+        let options = ConversionOptions::default();
+        assert_eq!(js_kind(&decimal, options), Some(JsKind::String));
+        let numbers = ConversionOptions {
+            decimal_as: DecimalAs::Number,
+            ..options
+        };
+        assert_eq!(js_kind(&decimal, numbers), Some(JsKind::Number));
+        assert!(DecimalAs::parse("float").is_err());
+
+        let array = arrow::array::Decimal128Array::from(vec![Some(-1230), None, Some(5)])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        assert_eq!(decimal_text_at(&array, 0).unwrap(), "-12.30");
+        assert_eq!(decimal_text_at(&array, 2).unwrap(), "0.05");
+        let wide = arrow::array::Decimal256Array::from(vec![i256::from_i128(i128::MAX)])
+            .with_precision_and_scale(76, 3)
+            .unwrap();
+        assert_eq!(
+            decimal_text_at(&wide, 0).unwrap(),
+            "170141183460469231731687303715884105.727"
+        );
+    }
+
+    #[test]
+    fn extension_fields_fail_only_when_asked() {
+        let tagged = |name: &str| {
+            Field::new(name, DataType::Utf8, true).with_metadata(HashMap::from([(
+                EXTENSION_NAME_KEY.to_string(),
+                "arrow.uuid".to_string(),
+            )]))
+        };
+        let nested = Field::new(
+            "ids",
+            DataType::List(Arc::new(Field::new(
+                "item",
+                DataType::Struct(vec![tagged("id")].into()),
+                true,
+            ))),
+            true,
+        );
+        let plain = Field::new("n", DataType::Int32, true);
+        let error = ConversionOptions {
+            extension_handling: ExtensionHandling::Error,
+            ..ConversionOptions::default()
+        };
+        for field in [tagged("id"), nested.clone(), plain.clone()] {
+            assert!(check_field(&field, ConversionOptions::default()).is_ok());
+        }
+        assert!(check_field(&plain, error).is_ok());
+        for field in [tagged("id"), nested] {
+            let message = check_field(&field, error).unwrap_err().to_string();
+            assert!(message.contains("extension type 'arrow.uuid'"), "{message}");
+        }
+        assert!(ExtensionHandling::parse("drop").is_err());
+    }
+
+    #[test]
+    fn nesting_past_the_limit_is_rejected() {
+        // `depth` lists around an Int32, each level also dictionary
+        // encoded, which adds no level.
+        let nested = |depth: usize| {
+            (0..depth).fold(DataType::Int32, |inner, _| {
+                let list = DataType::List(Arc::new(Field::new("item", inner, true)));
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(list))
+            })
+        };
+        let limit = |max_nesting_depth| ConversionOptions {
+            max_nesting_depth,
+            ..ConversionOptions::default()
+        };
+        let field = |data_type| Field::new("x", data_type, true);
+        assert!(check_field(&field(nested(3)), limit(3)).is_ok());
+        let message = check_field(&field(nested(4)), limit(3))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("deeper than maxNestingDepth (3)"),
+            "{message}"
+        );
+
+        let point = DataType::Struct(vec![Field::new("x", DataType::Int32, true)].into());
+        assert!(check_field(&field(point.clone()), limit(1)).is_ok());
+        assert!(check_field(&field(point), limit(0)).is_err());
+        assert!(check_field(&field(DataType::Int32), limit(0)).is_ok());
+
+        // Far past the default limit, without walking the whole type.
+        let deep = field(nested(1_000));
+        assert!(check_field(&deep, ConversionOptions::default()).is_err());
+        assert!(check_field(&field(nested(64)), ConversionOptions::default()).is_ok());
+    }
+}
@@ -0,0 +1,289 @@
+//! CSV output.
+
+use crate::compute::cast::format_floats;
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use arrow::array::{Array, ArrayRef, AsArray, BooleanArray};
+use arrow::datatypes::{DataType, Float64Type};
+use arrow_cast::cast;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_select::nullif::nullif;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Append `value` to `out`, quoting it when it contains a delimiter, quote
+/// or line break.
+fn push_field(out: &mut String, value: &str) {
+    if value.contains([',', '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&value.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(value);
+    }
+}
+
+/// How NaN and the infinities are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum NonFinite {
+    /// `NaN`, `inf` and `-inf`.
+    #[default]
+    Text,
+    /// Empty fields, like nulls.
+    Empty,
+}
+
+/// Options for [`write_table_to_csv_with_options`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct CsvOptions {
+    float_precision: Option<usize>,
+    non_finite: NonFinite,
+}
+
+/// `column` with its NaN and infinite values nulled out.
+fn null_non_finite(column: &ArrayRef) -> Result<ArrayRef> {
+    let finite = cast(column, &DataType::Float64)?;
+    let finite: BooleanArray = finite
+        .as_primitive::<Float64Type>()
+        .iter()
+        .map(|value| value.map(|value| !value.is_finite()))
+        .collect();
+    Ok(nullif(column.as_ref(), &finite)?)
+}
+
+fn table_to_csv(handle: TableHandle, options: &CsvOptions) -> Result<String> {
+    let table = mem::get_table(handle)?;
+    let mut out = String::new();
+    for (i, field) in table.schema.fields().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_field(&mut out, field.name());
+    }
+    out.push('\n');
+
+    let format = FormatOptions::default().with_null("");
+    let mut cell = String::new();
+    for batch in &table.batches {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| {
+                if !column.data_type().is_floating() {
+                    return Ok(Arc::clone(column));
+                }
+                let column = match options.non_finite {
+                    NonFinite::Text => Arc::clone(column),
+                    NonFinite::Empty => null_non_finite(column)?,
+                };
+                match options.float_precision {
+                    Some(precision) => {
+                        Ok(Arc::new(format_floats(column.as_ref(), precision)?) as ArrayRef)
+                    }
+                    None => Ok(column),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let formatters = columns
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), &format))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for row in 0..batch.num_rows() {
+            for (i, formatter) in formatters.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                cell.clear();
+                write!(cell, "{}", formatter.value(row))
+                    .map_err(|e| ArrowWasmError::Other(e.to_string()))?;
+                push_field(&mut out, &cell);
+            }
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Serialize a table as CSV with a header row.
+///
+/// Nulls are written as empty fields. `float_precision` fixes the number of
+/// decimal places of float columns; without it arrow-rs formatting is used.
+/// See `write_table_to_csv_with_options` for how floats are written.
+#[wasm_bindgen]
+pub fn write_table_to_csv(
+    handle: TableHandle,
+    float_precision: Option<usize>,
+) -> std::result::Result<String, JsValue> {
+    let options = CsvOptions {
+        float_precision,
+        ..CsvOptions::default()
+    };
+    Ok(table_to_csv(handle, &options)?)
+}
+
+/// Serialize a table as CSV with a header row, with `options`
+/// `{floatPrecision?, nonFinite?}`.
+///
+/// By default floats are written in their shortest round-trip form, so
+/// parsing a field gives back the identical value: `0.1 + 0.2` is written
+/// as `0.30000000000000004` and `-0.0` as `-0.0`. `floatPrecision` fixes
+/// the number of decimal places instead, which does not round-trip.
+/// `nonFinite` is `"text"` (the default) for `NaN`, `inf` and `-inf`, or
+/// `"empty"` to write them as empty fields like nulls. Integers, including
+/// 64-bit integers, are written exactly and never pass through a float.
+#[wasm_bindgen]
+pub fn write_table_to_csv_with_options(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<String, JsValue> {
+    let options: CsvOptions = if options.is_undefined() || options.is_null() {
+        CsvOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    Ok(table_to_csv(handle, &options)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::TableData;
+    use crate::rng::SplitMix64;
+    use arrow::array::{Float32Array, Float64Array, Int64Array, RecordBatch, StringArray};
+
+    /// A table of `columns`, stored in the registry.
+    fn table(columns: Vec<(&str, ArrayRef)>) -> TableHandle {
+        let batch = RecordBatch::try_from_iter(columns).unwrap();
+        mem::store_table(TableData::new(vec![batch]).unwrap()).unwrap()
+    }
+
+    /// The data rows of `csv`, split into fields.
+    fn fields(csv: &str) -> Vec<Vec<&str>> {
+        csv.lines()
+            .skip(1)
+            .map(|line| line.split(',').collect())
+            .collect()
+    }
+
+    #[test]
+    fn float_precision_fixes_decimals_of_float_columns_only() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int64Array::from(vec![9_007_199_254_740_993, 2, 3])) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![Some(1.005), None, Some(f64::NAN)])),
+            ),
+            (
+                "note",
+                Arc::new(StringArray::from(vec![Some("a,b"), Some("1.5"), None])),
+            ),
+        ])
+        .unwrap();
+        let table = mem::store_table(TableData::new(vec![batch]).unwrap()).unwrap();
+        assert_eq!(
+            write_table_to_csv(table, Some(2)).unwrap(),
+            "id,score,note\n9007199254740993,1.00,\"a,b\"\n2,,1.5\n3,NaN,\n"
+        );
+        assert_eq!(
+            write_table_to_csv(table, None).unwrap(),
+            "id,score,note\n9007199254740993,1.005,\"a,b\"\n2,,1.5\n3,NaN,\n"
+        );
+    }
+
+    #[test]
+    fn floats_round_trip_bit_exact() {
+        let mut rng = SplitMix64::new(709);
+        // Random bit patterns cover every exponent, subnormals included.
+        let mut doubles: Vec<f64> = (0..20_000)
+            .map(|_| f64::from_bits(rng.next_u64()))
+            .filter(|value| !value.is_nan())
+            .collect();
+        doubles.extend([
+            0.0,
+            -0.0,
+            0.1 + 0.2,
+            f64::MIN_POSITIVE,
+            f64::MIN_POSITIVE - f64::from_bits(1),
+            f64::from_bits(1),
+            -f64::from_bits(1),
+            f64::MAX,
+            f64::MIN,
+            f64::EPSILON,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ]);
+        let singles: Vec<f32> = doubles
+            .iter()
+            .map(|_| f32::from_bits(rng.next_u64() as u32))
+            .map(|value| {
+                if value.is_nan() {
+                    f32::MIN_POSITIVE
+                } else {
+                    value
+                }
+            })
+            .collect();
+        let handle = table(vec![
+            ("double", Arc::new(Float64Array::from(doubles.clone()))),
+            ("single", Arc::new(Float32Array::from(singles.clone()))),
+        ]);
+        let csv = write_table_to_csv(handle, None).unwrap();
+        let rows = fields(&csv);
+        assert_eq!(rows.len(), doubles.len());
+        for ((row, double), single) in rows.iter().zip(&doubles).zip(&singles) {
+            let parsed: f64 = row[0].parse().unwrap();
+            assert_eq!(parsed.to_bits(), double.to_bits(), "{}", row[0]);
+            let parsed: f32 = row[1].parse().unwrap();
+            assert_eq!(parsed.to_bits(), single.to_bits(), "{}", row[1]);
+        }
+    }
+
+    #[test]
+    fn special_floats_follow_the_options() {
+        let handle = table(vec![(
+            "x",
+            Arc::new(Float64Array::from(vec![
+                Some(-0.0),
+                Some(f64::NAN),
+                Some(f64::INFINITY),
+                Some(f64::NEG_INFINITY),
+                None,
+            ])),
+        )]);
+        let column = |non_finite, float_precision| {
+            let options = CsvOptions {
+                float_precision,
+                non_finite,
+            };
+            let csv = table_to_csv(handle, &options).unwrap();
+            fields(&csv)
+                .into_iter()
+                .map(|row| row[0])
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+        assert_eq!(column(NonFinite::Text, None), "-0.0|NaN|inf|-inf|");
+        assert_eq!(column(NonFinite::Empty, None), "-0.0||||");
+        assert_eq!(column(NonFinite::Text, Some(1)), "-0.0|NaN|inf|-inf|");
+        assert_eq!(column(NonFinite::Empty, Some(1)), "-0.0||||");
+    }
+
+    #[test]
+    fn int64_is_written_exactly() {
+        let values = [i64::MIN, i64::MAX, (1 << 53) + 1, -(1 << 53) - 1, 0];
+        let handle = table(vec![("n", Arc::new(Int64Array::from(values.to_vec())))]);
+        let csv = write_table_to_csv(handle, Some(2)).unwrap();
+        let parsed: Vec<i64> = fields(&csv)
+            .iter()
+            .map(|row| row[0].parse().unwrap())
+            .collect();
+        assert_eq!(parsed, values);
+    }
+}
@@ -0,0 +1,166 @@
+//! Opt-in automatic disposal of tables held by JS wrapper objects.
+//!
+//! Tables live until `free_table`; a wrapper that is dropped without being
+//! disposed leaks its table. With `configure_auto_dispose` every object
+//! passed to `track_handle` is registered in a `FinalizationRegistry`, and
+//! when it is garbage collected its table is either freed
+//! (`"finalization"`) or reported with `console.warn` (`"warn"`, with the
+//! stack of the `track_handle` call in `devMode`). Finalizers run at the
+//! engine's discretion, possibly never, so this is a safety net for
+//! `dispose_handle`, not a replacement.
+
+use crate::errors::ArrowWasmError;
+use crate::mem::{self, TableHandle};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+/// What happens to a tracked table whose wrapper is collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AutoDispose {
+    /// Nothing is tracked.
+    #[default]
+    Off,
+    /// The table is freed.
+    Finalization,
+    /// The table is kept and a warning is logged if it is still registered.
+    Warn,
+}
+
+impl AutoDispose {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Finalization => "finalization",
+            Self::Warn => "warn",
+        }
+    }
+}
+
+/// Options for [`configure_auto_dispose`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct DisposeOptions {
+    auto_dispose: AutoDispose,
+    dev_mode: bool,
+}
+
+#[wasm_bindgen(inline_js = r#"
+let mode = "off";
+let devMode = false;
+let settle = null;
+let registry = null;
+
+function collected(held) {
+    if (mode === "finalization") {
+        settle(held.handle, true);
+    } else if (mode === "warn" && settle(held.handle, false)) {
+        const origin = held.stack === undefined ? "" : `\n${held.stack}`;
+        console.warn(`arrow-rs-wasm: table ${held.handle} was garbage collected without being freed${origin}`);
+    }
+}
+
+export function configure_dispose(newMode, newDevMode, newSettle) {
+    if (newMode !== "off" && typeof FinalizationRegistry === "undefined") {
+        return false;
+    }
+    mode = newMode;
+    devMode = newDevMode;
+    settle = newSettle;
+    if (registry === null && mode !== "off") {
+        registry = new FinalizationRegistry(collected);
+    }
+    return true;
+}
+
+export function track_wrapper(wrapper, handle) {
+    if (mode === "off" || registry === null) {
+        return false;
+    }
+    const stack = devMode ? new Error("table created").stack : undefined;
+    registry.register(wrapper, { handle, stack }, wrapper);
+    return true;
+}
+
+export function untrack_wrapper(wrapper) {
+    if (registry !== null) {
+        registry.unregister(wrapper);
+    }
+}
+"#)]
+extern "C" {
+    fn configure_dispose(mode: &str, dev_mode: bool, settle: &JsValue) -> bool;
+    fn track_wrapper(wrapper: &JsValue, handle: TableHandle) -> bool;
+    fn untrack_wrapper(wrapper: &JsValue);
+}
+
+thread_local! {
+    /// Finalizer callback: frees the table when asked to, and reports
+    /// whether it was still registered.
+    static SETTLE: Closure<dyn Fn(TableHandle, bool) -> bool> =
+        Closure::new(|handle, free| {
+            if free {
+                mem::release_table(handle)
+            } else {
+                mem::table_exists(handle)
+            }
+        });
+}
+
+/// Choose what happens to tables whose wrappers are garbage collected.
+///
+/// `options` is `{autoDispose?, devMode?}`. `autoDispose` is `"off"` (the
+/// default: `track_handle` does nothing), `"finalization"` (free the table)
+/// or `"warn"` (keep it and log a warning if it was never freed, with the
+/// stack of the `track_handle` call when `devMode` is set). Wrappers
+/// tracked earlier follow the current mode. Fails when the engine has no
+/// `FinalizationRegistry`.
+#[wasm_bindgen]
+pub fn configure_auto_dispose(options: JsValue) -> std::result::Result<(), JsValue> {
+    let options: DisposeOptions = if options.is_undefined() || options.is_null() {
+        DisposeOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let configured = SETTLE.with(|settle| {
+        configure_dispose(
+            options.auto_dispose.name(),
+            options.dev_mode,
+            settle.as_ref(),
+        )
+    });
+    if !configured {
+        return Err(ArrowWasmError::InvalidInput(
+            "Automatic disposal needs FinalizationRegistry, which this engine lacks".to_string(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Tie table `handle` to the lifetime of `wrapper`, any JS object such as
+/// a `Column` or an application's own table class.
+///
+/// Returns whether the wrapper was registered, which is only the case
+/// while automatic disposal is on. Call `dispose_handle` with the same
+/// wrapper to free the table explicitly.
+#[wasm_bindgen]
+pub fn track_handle(wrapper: &JsValue, handle: TableHandle) -> std::result::Result<bool, JsValue> {
+    if !wrapper.is_object() && !wrapper.is_function() {
+        return Err(ArrowWasmError::InvalidInput("wrapper must be an object".to_string()).into());
+    }
+    Ok(track_wrapper(wrapper, handle))
+}
+
+/// Free table `handle` now and stop tracking `wrapper`.
+///
+/// Safe to call more than once and after the table was freed by other
+/// means; returns whether this call freed it.
+#[must_use]
+#[wasm_bindgen]
+pub fn dispose_handle(wrapper: &JsValue, handle: TableHandle) -> bool {
+    if wrapper.is_object() || wrapper.is_function() {
+        untrack_wrapper(wrapper);
+    }
+    mem::release_table(handle)
+}
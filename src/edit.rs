@@ -0,0 +1,532 @@
+//! Single-cell edits with copy-on-write buffers.
+//!
+//! Arrow arrays are immutable and usually share their buffers: slices,
+//! IPC bodies and zero-copy table operations all point at the same memory.
+//! An edit writes to a buffer directly only when the table holds the sole
+//! reference to it; otherwise it copies just the buffers it touches and
+//! edits the copies, so no other table or column ever sees the change.
+
+use crate::column::locate;
+use crate::compute::cast::cast_safe;
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use arrow::array::{
+    make_array, new_empty_array, new_null_array, Array, ArrayData, ArrayRef, ArrowPrimitiveType,
+    AsArray, BooleanArray, Float64Array, PrimitiveArray, RecordBatch, StringArray,
+    TimestampMillisecondArray,
+};
+use arrow::buffer::{BooleanBuffer, Buffer, MutableBuffer, NullBuffer, ScalarBuffer};
+use arrow::datatypes::DataType;
+use arrow::util::bit_util::{set_bit, unset_bit};
+use arrow_select::interleave::interleave;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// An edited array, plus the untouched original when its buffers were
+/// shared and had to be copied.
+struct Edited {
+    array: ArrayRef,
+    original: Option<ArrayRef>,
+}
+
+/// Take ownership of every buffer for writing, or hand them all back
+/// unchanged if any of them is shared.
+fn acquire(buffers: Vec<Buffer>) -> std::result::Result<Vec<MutableBuffer>, Vec<Buffer>> {
+    let mut attempts = Vec::with_capacity(buffers.len());
+    let mut shared = false;
+    for buffer in buffers {
+        // A buffer that starts partway into its allocation is a slice.
+        let attempt = if shared || buffer.ptr_offset() != 0 {
+            Err(buffer)
+        } else {
+            buffer.into_mutable()
+        };
+        shared |= attempt.is_err();
+        attempts.push(attempt);
+    }
+    if shared {
+        return Err(attempts
+            .into_iter()
+            .map(|attempt| attempt.map_or_else(|buffer| buffer, Buffer::from))
+            .collect());
+    }
+    Ok(attempts.into_iter().flatten().collect())
+}
+
+fn copy_buffer(buffer: &Buffer) -> MutableBuffer {
+    let mut copy = MutableBuffer::new(buffer.len());
+    copy.extend_from_slice(buffer.as_slice());
+    copy
+}
+
+/// Apply `edit` to `buffers` in place when they are exclusively owned, or
+/// to copies otherwise; `rebuild` turns a buffer list back into an array.
+fn edit_buffers(
+    buffers: Vec<Buffer>,
+    rebuild: impl Fn(Vec<Buffer>) -> ArrayRef,
+    edit: impl FnOnce(&mut [MutableBuffer]),
+) -> Edited {
+    match acquire(buffers) {
+        Ok(mut owned) => {
+            edit(&mut owned);
+            Edited {
+                array: rebuild(owned.into_iter().map(Buffer::from).collect()),
+                original: None,
+            }
+        }
+        Err(shared) => {
+            let mut copies: Vec<MutableBuffer> = shared.iter().map(copy_buffer).collect();
+            edit(&mut copies);
+            Edited {
+                array: rebuild(copies.into_iter().map(Buffer::from).collect()),
+                original: Some(rebuild(shared)),
+            }
+        }
+    }
+}
+
+/// Validity bitmap to edit: the existing one, or a fresh all-valid one when
+/// a null is written into an array without nulls.
+fn validity(nulls: Option<NullBuffer>, len: usize, writing_null: bool) -> Option<(Buffer, usize)> {
+    match nulls {
+        Some(nulls) => {
+            let bits = nulls.into_inner();
+            let offset = bits.offset();
+            Some((bits.into_inner(), offset))
+        }
+        None if writing_null => Some((BooleanBuffer::new_set(len).into_inner(), 0)),
+        None => None,
+    }
+}
+
+fn null_buffer(bits: Buffer, offset: usize, len: usize) -> NullBuffer {
+    NullBuffer::new(BooleanBuffer::new(bits, offset, len))
+}
+
+fn write_bit(bits: &mut [u8], index: usize, value: bool) {
+    if value {
+        set_bit(bits, index);
+    } else {
+        unset_bit(bits, index);
+    }
+}
+
+/// Buffers an edit writes to, in order: the value buffer unless a null is
+/// written, then the validity bitmap when there is one. The value buffer
+/// is returned separately when it is left alone.
+fn touched_buffers(
+    values: Buffer,
+    validity: Option<Buffer>,
+    writing_null: bool,
+) -> (Vec<Buffer>, Option<Buffer>) {
+    let mut buffers = Vec::with_capacity(2);
+    let kept_values = if writing_null {
+        Some(values)
+    } else {
+        buffers.push(values);
+        None
+    };
+    buffers.extend(validity);
+    (buffers, kept_values)
+}
+
+/// Write `value` (a one-element array of the same type, `None` for null)
+/// into slot `row`.
+fn edit_primitive<T: ArrowPrimitiveType>(
+    data: ArrayData,
+    row: usize,
+    value: Option<&ArrayRef>,
+) -> Edited {
+    let (data_type, values, nulls) = PrimitiveArray::<T>::from(data).into_parts();
+    let len = values.len();
+    let new_value = value.map(|value| value.as_primitive::<T>().value(0));
+    let (bits, null_offset) = validity(nulls, len, new_value.is_none()).unzip();
+    let null_offset = null_offset.unwrap_or(0);
+    let has_nulls = bits.is_some();
+    let (buffers, kept_values) = touched_buffers(values.into_inner(), bits, new_value.is_none());
+
+    let rebuild = |mut buffers: Vec<Buffer>| -> ArrayRef {
+        let values = kept_values.clone().unwrap_or_else(|| buffers.remove(0));
+        let nulls = buffers
+            .pop()
+            .map(|bits| null_buffer(bits, null_offset, len));
+        Arc::new(
+            PrimitiveArray::<T>::new(ScalarBuffer::new(values, 0, len), nulls)
+                .with_data_type(data_type.clone()),
+        )
+    };
+    edit_buffers(buffers, rebuild, |buffers| {
+        if let Some(value) = new_value {
+            buffers[0].typed_data_mut::<T::Native>()[row] = value;
+        }
+        if has_nulls {
+            if let Some(bits) = buffers.last_mut() {
+                write_bit(bits.as_slice_mut(), null_offset + row, new_value.is_some());
+            }
+        }
+    })
+}
+
+fn edit_boolean(data: ArrayData, row: usize, value: Option<&ArrayRef>) -> Edited {
+    let (values, nulls) = BooleanArray::from(data).into_parts();
+    let len = values.len();
+    let value_offset = values.offset();
+    let new_value = value.map(|value| value.as_boolean().value(0));
+    let (bits, null_offset) = validity(nulls, len, new_value.is_none()).unzip();
+    let null_offset = null_offset.unwrap_or(0);
+    let has_nulls = bits.is_some();
+    let (buffers, kept_values) = touched_buffers(values.into_inner(), bits, new_value.is_none());
+
+    let rebuild = |mut buffers: Vec<Buffer>| -> ArrayRef {
+        let values = kept_values.clone().unwrap_or_else(|| buffers.remove(0));
+        let nulls = buffers
+            .pop()
+            .map(|bits| null_buffer(bits, null_offset, len));
+        Arc::new(BooleanArray::new(
+            BooleanBuffer::new(values, value_offset, len),
+            nulls,
+        ))
+    };
+    edit_buffers(buffers, rebuild, |buffers| {
+        if let Some(value) = new_value {
+            write_bit(buffers[0].as_slice_mut(), value_offset + row, value);
+        }
+        if has_nulls {
+            if let Some(bits) = buffers.last_mut() {
+                write_bit(bits.as_slice_mut(), null_offset + row, new_value.is_some());
+            }
+        }
+    })
+}
+
+macro_rules! primitive_edit {
+    ($t:ty, $data:ident, $row:ident, $value:ident) => {
+        edit_primitive::<$t>($data, $row, $value)
+    };
+}
+
+/// Write `value` into slot `row` of a fixed-width `chunk`, consuming it so
+/// that its buffers can be reused when nothing else refers to them.
+///
+/// Other types are handed back untouched as the error.
+fn edit_chunk(
+    chunk: ArrayRef,
+    row: usize,
+    value: Option<&ArrayRef>,
+) -> std::result::Result<Edited, ArrayRef> {
+    let data = chunk.to_data();
+    drop(chunk);
+    let data_type = data.data_type().clone();
+    Ok(arrow_array::downcast_primitive! {
+        data_type => (primitive_edit, data, row, value),
+        DataType::Boolean => edit_boolean(data, row, value),
+        _ => return Err(make_array(data)),
+    })
+}
+
+/// Copy of `chunk` with slot `row` replaced, for types without a
+/// fixed-width layout.
+fn rebuild_chunk(chunk: &ArrayRef, row: usize, value: Option<&ArrayRef>) -> Result<ArrayRef> {
+    let replacement = value.map_or_else(|| new_null_array(chunk.data_type(), 1), Arc::clone);
+    let indices: Vec<(usize, usize)> = (0..chunk.len())
+        .map(|i| if i == row { (1, 0) } else { (0, i) })
+        .collect();
+    Ok(interleave(
+        &[chunk.as_ref(), replacement.as_ref()],
+        &indices,
+    )?)
+}
+
+/// One-element array of type `to` holding `value`.
+pub fn scalar_from_js(value: &JsValue, to: &DataType) -> Result<ArrayRef> {
+    let source: ArrayRef = if let Some(flag) = value.as_bool() {
+        Arc::new(BooleanArray::from(vec![flag]))
+    } else if let Some(number) = value.as_f64() {
+        if to.is_integer() && number.fract() != 0.0 {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "{number} is not an integer"
+            )));
+        }
+        Arc::new(Float64Array::from(vec![number]))
+    } else if let Some(big) = value.dyn_ref::<js_sys::BigInt>() {
+        let text = big
+            .to_string(10)
+            .map(String::from)
+            .map_err(|_| ArrowWasmError::InvalidInput("Invalid bigint".to_string()))?;
+        Arc::new(StringArray::from(vec![text]))
+    } else if let Some(text) = value.as_string() {
+        Arc::new(StringArray::from(vec![text]))
+    } else if let Some(date) = value.dyn_ref::<js_sys::Date>() {
+        Arc::new(TimestampMillisecondArray::from(
+            vec![date.get_time() as i64],
+        ))
+    } else {
+        return Err(ArrowWasmError::InvalidInput(
+            "Value must be a boolean, number, bigint, string or Date".to_string(),
+        ));
+    };
+    let scalar = cast_safe(&source, to)?;
+    if scalar.is_null(0) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Value cannot be represented as {to:?}"
+        )));
+    }
+    Ok(scalar)
+}
+
+/// Copy of `table` with column `column` of batch `batch` replaced by
+/// `array`; every other array is shared.
+fn with_column(
+    table: &TableData,
+    batch: usize,
+    column: usize,
+    array: ArrayRef,
+) -> Result<TableData> {
+    let mut columns = table.batches[batch].columns().to_vec();
+    columns[column] = array;
+    let mut batches = table.batches.clone();
+    batches[batch] = RecordBatch::try_new(Arc::clone(&table.schema), columns)?;
+    TableData::new(batches)
+}
+
+/// Write `value` (`None` for null) into one cell.
+///
+/// With `in_place` the table is taken out of the registry so that its
+/// arrays are referenced only here; if every buffer the edit touches is
+/// then exclusively owned it is modified directly and the same handle is
+/// returned. In every other case the edit goes to copies of the touched
+/// buffers and a new table is registered.
+fn set_cell(
+    handle: TableHandle,
+    column: &str,
+    row: usize,
+    value: Option<&JsValue>,
+    in_place: bool,
+) -> Result<TableHandle> {
+    let (column_index, batch_index, offset, scalar) = {
+        let table = mem::get_table(handle)?;
+        let column_index = table
+            .schema
+            .index_of(column)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{column}' not found")))?;
+        let field = table.schema.field(column_index);
+        let chunks = table.get_column_by_name(column)?;
+        let (batch_index, offset) = locate(&chunks, row).ok_or_else(|| {
+            ArrowWasmError::InvalidInput(format!("Row index {row} out of bounds"))
+        })?;
+        if value.is_none() && !field.is_nullable() {
+            return Err(
+                ArrowWasmError::InvalidInput("column is not nullable".to_string())
+                    .in_column("set_null", field),
+            );
+        }
+        let scalar = value
+            .map(|value| scalar_from_js(value, field.data_type()))
+            .transpose()
+            .map_err(|e| e.in_column("set_value", field))?;
+        (column_index, batch_index, offset, scalar)
+    };
+
+    let mut table = if in_place {
+        mem::take_table(handle)?
+    } else {
+        mem::get_table(handle)?
+    };
+    let placeholder = RecordBatch::new_empty(Arc::clone(&table.schema));
+    let (schema, mut columns, _) =
+        std::mem::replace(&mut table.batches[batch_index], placeholder).into_parts();
+    let data_type = columns[column_index].data_type().clone();
+    let chunk = std::mem::replace(&mut columns[column_index], new_empty_array(&data_type));
+
+    // Put the source column back before anything can fail, so an in-place
+    // table always returns to the registry.
+    let (edited, exclusive) = match edit_chunk(chunk, offset, scalar.as_ref()) {
+        Ok(Edited {
+            array,
+            original: None,
+        }) => {
+            columns[column_index] = Arc::clone(&array);
+            (Ok(array), true)
+        }
+        Ok(Edited {
+            array,
+            original: Some(original),
+        }) => {
+            columns[column_index] = original;
+            (Ok(array), false)
+        }
+        Err(chunk) => {
+            let rebuilt = rebuild_chunk(&chunk, offset, scalar.as_ref());
+            columns[column_index] = chunk;
+            (rebuilt, false)
+        }
+    };
+    // The edit keeps the chunk's type and length, so this does not fail in
+    // practice; if it does, the error waits until the table is back.
+    let reassembled = RecordBatch::try_new(schema, columns)
+        .map(|batch| table.batches[batch_index] = batch)
+        .map_err(ArrowWasmError::from);
+
+    let result = reassembled.and(edited).and_then(|array| {
+        if exclusive && in_place {
+            Ok(handle)
+        } else {
+            mem::store_table(with_column(&table, batch_index, column_index, array)?)
+        }
+    });
+    if in_place {
+        mem::restore_table(handle, table)?;
+    }
+    result
+}
+
+/// Set one cell of `column` to `value`, returning the handle of the result.
+///
+/// Booleans, numbers, bigints, strings and `Date`s are converted to the
+/// column type, failing when the value cannot be represented (a
+/// fractional number for an integer column, an out-of-range value);
+/// `null` or `undefined` clears the cell like `set_null`.
+///
+/// Tables are otherwise never modified: the result is a new table that
+/// shares every buffer except the edited ones. With `in_place`, a table
+/// whose edited buffers are not shared with any other table or column is
+/// changed directly and its own handle is returned instead. Fixed-width
+/// columns edit just the affected buffer; other types rebuild the column
+/// chunk holding the row.
+#[wasm_bindgen]
+pub fn set_value(
+    handle: TableHandle,
+    column: &str,
+    row: usize,
+    value: JsValue,
+    in_place: bool,
+) -> std::result::Result<TableHandle, JsValue> {
+    let value = (!(value.is_undefined() || value.is_null())).then_some(&value);
+    Ok(set_cell(handle, column, row, value, in_place)?)
+}
+
+/// Set one cell of `column` to null; see `set_value` for `in_place`.
+///
+/// A column without nulls gains a validity bitmap.
+#[wasm_bindgen]
+pub fn set_null(
+    handle: TableHandle,
+    column: &str,
+    row: usize,
+    in_place: bool,
+) -> std::result::Result<TableHandle, JsValue> {
+    Ok(set_cell(handle, column, row, None, in_place)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::Int32Type;
+
+    fn values(array: &ArrayRef) -> Vec<Option<i32>> {
+        array.as_primitive::<Int32Type>().iter().collect()
+    }
+
+    fn value_ptr(array: &ArrayRef) -> *const u8 {
+        array.as_primitive::<Int32Type>().values().inner().as_ptr()
+    }
+
+    fn stored(column: Int32Array) -> TableHandle {
+        let batch =
+            RecordBatch::try_from_iter_with_nullable([("x", Arc::new(column) as ArrayRef, true)])
+                .unwrap();
+        mem::store_table(TableData::new(vec![batch]).unwrap()).unwrap()
+    }
+
+    fn column(handle: TableHandle) -> ArrayRef {
+        Arc::clone(mem::get_table(handle).unwrap().batches[0].column(0))
+    }
+
+    #[test]
+    fn exclusive_buffers_are_edited_where_they_are() {
+        let chunk: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let before = value_ptr(&chunk);
+        let scalar: ArrayRef = Arc::new(Int32Array::from(vec![20]));
+        let edited = edit_chunk(chunk, 1, Some(&scalar)).unwrap();
+
+        assert!(edited.original.is_none());
+        assert_eq!(value_ptr(&edited.array), before);
+        assert_eq!(values(&edited.array), [Some(1), Some(20), Some(3)]);
+    }
+
+    #[test]
+    fn shared_buffers_are_copied() {
+        let chunk: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let view = chunk.slice(1, 2);
+        let before = value_ptr(&chunk);
+        let scalar: ArrayRef = Arc::new(Int32Array::from(vec![20]));
+        let edited = edit_chunk(chunk, 1, Some(&scalar)).unwrap();
+
+        let original = edited.original.expect("the slice keeps the buffer shared");
+        assert_eq!(value_ptr(&original), before);
+        assert_ne!(value_ptr(&edited.array), before);
+        assert_eq!(values(&edited.array), [Some(1), Some(20), Some(3)]);
+        assert_eq!(values(&original), [Some(1), Some(2), Some(3)]);
+        assert_eq!(values(&view), [Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn null_adds_a_validity_bitmap() {
+        let chunk: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        assert!(chunk.nulls().is_none());
+        let edited = edit_chunk(chunk, 2, None).unwrap();
+        assert!(edited.original.is_none());
+        assert_eq!(edited.array.null_count(), 1);
+        assert_eq!(values(&edited.array), [Some(1), Some(2), None]);
+
+        let flags: ArrayRef = Arc::new(BooleanArray::from(vec![true, false]));
+        let edited = edit_chunk(flags, 0, None).unwrap();
+        let flags = edited.array.as_boolean();
+        assert_eq!(flags.iter().collect::<Vec<_>>(), [None, Some(false)]);
+    }
+
+    #[test]
+    fn in_place_edits_keep_the_handle() {
+        let handle = stored(Int32Array::from(vec![1, 2, 3]));
+        assert_eq!(set_cell(handle, "x", 0, None, true).unwrap(), handle);
+        assert_eq!(values(&column(handle)), [None, Some(2), Some(3)]);
+        mem::remove_table(handle).unwrap();
+    }
+
+    #[test]
+    fn other_tables_never_see_an_edit() {
+        let handle = stored(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let other = mem::store_table(mem::get_table(handle).unwrap()).unwrap();
+
+        // The validity bitmap is shared with `other`, so even an in-place
+        // edit goes to a copy in a new table.
+        let edited = set_cell(handle, "x", 0, None, true).unwrap();
+        assert_ne!(edited, handle);
+        assert_eq!(values(&column(edited)), [None, None, Some(3)]);
+        let copy = set_cell(other, "x", 2, None, false).unwrap();
+        assert_eq!(values(&column(copy)), [Some(1), None, None]);
+
+        for unchanged in [handle, other] {
+            assert_eq!(values(&column(unchanged)), [Some(1), None, Some(3)]);
+        }
+        for handle in [handle, other, edited, copy] {
+            mem::remove_table(handle).unwrap();
+        }
+    }
+
+    #[test]
+    fn rebuilt_types_leave_the_source_alone() {
+        let texts: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        let Err(chunk) = edit_chunk(Arc::clone(&texts), 0, None) else {
+            panic!("strings have no fixed-width layout");
+        };
+        let rebuilt = rebuild_chunk(&chunk, 0, None).unwrap();
+        assert_eq!(
+            rebuilt.as_string::<i32>().iter().collect::<Vec<_>>(),
+            [None, Some("b")]
+        );
+        assert_eq!(texts.null_count(), 0);
+    }
+}
@@ -1,58 +1,167 @@
 use thiserror::Error;
 use wasm_bindgen::prelude::*;
 
+/// Errors produced by the library, converted to JS strings at the boundary.
 #[derive(Error, Debug)]
 pub enum ArrowWasmError {
+    /// Error raised by an arrow-rs kernel or constructor.
     #[error("Arrow error: {0}")]
     Arrow(#[from] arrow::error::ArrowError),
-    
+
+    /// Failure while reading or writing Arrow IPC data.
     #[error("IPC error: {0}")]
     Ipc(String),
-    
+
+    /// Failure while reading or writing Parquet data.
     #[error("Parquet error: {0}")]
     Parquet(#[from] parquet::errors::ParquetError),
-    
+
+    /// Caller supplied an argument the operation cannot accept.
     #[error("Invalid input: {0}")]
     InvalidInput(String),
-    
+
+    /// Registry or allocation failure.
     #[error("Memory error: {0}")]
     Memory(String),
-    
+
+    /// Decoding the input would exceed the memory available to the module.
+    #[error("Data too large: estimated {estimate} bytes exceeds the memory limit of {limit} bytes; {hint}")]
+    TooLarge {
+        /// Estimated decoded size in bytes.
+        estimate: u64,
+        /// Limit the estimate was checked against, in bytes.
+        limit: u64,
+        /// Suggested narrower read.
+        hint: String,
+    },
+
+    /// The handle was never issued by the registry.
     #[error("Invalid table handle: {0}")]
     InvalidHandle(u32),
-    
+
+    /// The handle was issued, but its table has since been freed.
+    #[error("Table handle {0} was already freed")]
+    FreedHandle(u32),
+
+    /// JSON (de)serialization failure.
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
-    
+
+    /// A buffer was missing or had an unexpected layout.
     #[error("Buffer error: {0}")]
     Buffer(String),
-    
+
+    /// Compression codec failure.
     #[error("Compression error: {0}")]
     Compression(String),
-    
+
+    /// Underlying I/O failure.
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
-    
+
+    /// A compute kernel failed on a specific column.
+    #[error("{op} failed on column '{column}' ({data_type}){}: {message}", row_suffix(*.row))]
+    Compute {
+        /// Kernel that failed, e.g. `"cast"`.
+        op: &'static str,
+        /// Name of the input column.
+        column: String,
+        /// Data type of the input column.
+        data_type: String,
+        /// First offending row, when the failure is tied to a value.
+        row: Option<usize>,
+        /// What went wrong.
+        message: String,
+    },
+
+    /// The column's type does not support the operation; see
+    /// `supported_operations`.
+    #[error("{operation} does not support column '{column}' of type {data_type}")]
+    Unsupported {
+        /// Operation name from the capability matrix, e.g. `"sum"`.
+        operation: &'static str,
+        /// Name of the input column.
+        column: String,
+        /// Data type of the input column.
+        data_type: String,
+    },
+
+    /// An internal invariant was violated. This is a bug in the library,
+    /// not a problem with the input, so retrying will not help.
+    #[error("Internal error (please report this as a bug): {0}")]
+    Internal(String),
+
+    /// Anything not covered by the variants above.
     #[error("Other error: {0}")]
     Other(String),
 }
 
+fn row_suffix(row: Option<usize>) -> String {
+    row.map(|row| format!(" at row {row}")).unwrap_or_default()
+}
+
+impl ArrowWasmError {
+    /// The error's message without the prefix naming its kind, e.g. `bad
+    /// value` for `Invalid input: bad value`. Errors whose message is all
+    /// prefix-free text (handles, size limits) give their full message.
+    fn detail(self) -> String {
+        match self {
+            Self::Arrow(e) => e.to_string(),
+            Self::Parquet(e) => e.to_string(),
+            Self::Serialization(e) => e.to_string(),
+            Self::Io(e) => e.to_string(),
+            Self::Ipc(message)
+            | Self::InvalidInput(message)
+            | Self::Memory(message)
+            | Self::Buffer(message)
+            | Self::Compression(message)
+            | Self::Internal(message)
+            | Self::Other(message) => message,
+            other => other.to_string(),
+        }
+    }
+
+    /// Attach the kernel name and input column to an error raised while
+    /// running `op` on `field`. Internal, unsupported-type and
+    /// already-contextualized errors pass through unchanged.
+    #[must_use]
+    pub fn in_column(self, op: &'static str, field: &arrow::datatypes::Field) -> Self {
+        match self {
+            Self::Internal(_) | Self::Unsupported { .. } | Self::Compute { .. } => self,
+            other => Self::Compute {
+                op,
+                column: field.name().clone(),
+                data_type: format!("{:?}", field.data_type()),
+                row: None,
+                message: other.detail(),
+            },
+        }
+    }
+}
+
 impl From<ArrowWasmError> for JsValue {
-    fn from(err: ArrowWasmError) -> JsValue {
-        JsValue::from_str(&err.to_string())
+    fn from(err: ArrowWasmError) -> Self {
+        Self::from_str(&err.to_string())
     }
 }
 
+impl From<serde_wasm_bindgen::Error> for ArrowWasmError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        Self::InvalidInput(err.to_string())
+    }
+}
+
+/// Result alias used throughout the crate.
 pub type Result<T> = std::result::Result<T, ArrowWasmError>;
 
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
-    
+
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_u32(a: u32);
-    
+
     #[wasm_bindgen(js_namespace = console, js_name = log)]
     fn log_many(a: &str, b: &str);
 }
@@ -62,10 +171,69 @@ macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
+#[allow(unused_imports)]
 pub(crate) use console_log;
 
+/// Install the panic hook so Rust panics are reported to the JS console.
 #[wasm_bindgen]
 pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{DataType, Field};
+    use arrow::error::ArrowError;
+
+    fn field() -> Field {
+        Field::new("price", DataType::Int64, true)
+    }
+
+    #[test]
+    fn column_context_replaces_the_kind_prefix() {
+        let error =
+            ArrowWasmError::InvalidInput("bad value".to_string()).in_column("clip", &field());
+        assert_eq!(
+            error.to_string(),
+            "clip failed on column 'price' (Int64): bad value"
+        );
+        let error = ArrowWasmError::Arrow(ArrowError::ComputeError("overflow".to_string()))
+            .in_column("sum", &field());
+        assert_eq!(
+            error.to_string(),
+            "sum failed on column 'price' (Int64): Compute error: overflow"
+        );
+    }
+
+    #[test]
+    fn contextualized_errors_pass_through() {
+        let inner = ArrowWasmError::Compute {
+            op: "cast",
+            column: "id".to_string(),
+            data_type: "Utf8".to_string(),
+            row: Some(4),
+            message: "value cannot be represented as Int32".to_string(),
+        };
+        let error = inner.in_column("cast_columns", &field());
+        assert_eq!(
+            error.to_string(),
+            "cast failed on column 'id' (Utf8) at row 4: value cannot be represented as Int32"
+        );
+        let unsupported = ArrowWasmError::Unsupported {
+            operation: "abs",
+            column: "name".to_string(),
+            data_type: "Utf8".to_string(),
+        };
+        assert!(matches!(
+            unsupported.in_column("abs", &field()),
+            ArrowWasmError::Unsupported { .. }
+        ));
+        let internal = ArrowWasmError::Internal("downcast failed".to_string());
+        assert!(matches!(
+            internal.in_column("abs", &field()),
+            ArrowWasmError::Internal(_)
+        ));
+    }
+}
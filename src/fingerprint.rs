@@ -0,0 +1,362 @@
+//! Stable schema fingerprints, e.g. as cache keys for derived artifacts.
+//!
+//! A fingerprint is `"v1:"` followed by the 128-bit FNV-1a hash of a
+//! canonical serialization of the schema, as 32 lowercase hex digits. The
+//! serialization is fixed for `v1` and does not depend on arrow-rs `Debug`
+//! or `Display` output, so fingerprints stay comparable across releases;
+//! any change to it gets a new version prefix.
+//!
+//! The `v1` serialization is a sequence of tokens. Strings (names, time
+//! zones, metadata) are written as `<byte length>:<bytes>`. The schema is
+//! `schema{<fields>}` followed by its metadata; a field is
+//! `field(<name>,<type>,<n|r>)` (nullable or required) followed by its
+//! metadata; metadata is `meta{<key>=<value>;...}` with entries sorted by
+//! key and ignored keys left out. Types are lowercase names with their
+//! parameters in parentheses, e.g. `timestamp(ms,3:UTC)`, `decimal128(10,2)`
+//! or `list(<child field>)`; child fields of nested types are serialized in
+//! full.
+
+use crate::mem::{self, TableHandle};
+use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit, UnionMode};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Version prefix of the current canonicalization.
+const VERSION: &str = "v1";
+
+const FNV_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+fn fnv1a(bytes: &[u8]) -> u128 {
+    bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn push_str(out: &mut String, value: &str) {
+    let _ = write!(out, "{}:{value}", value.len());
+}
+
+fn push_metadata(out: &mut String, metadata: &HashMap<String, String>, ignore: &HashSet<&str>) {
+    let sorted: BTreeMap<&String, &String> = metadata
+        .iter()
+        .filter(|(key, _)| !ignore.contains(key.as_str()))
+        .collect();
+    out.push_str("meta{");
+    for (key, value) in sorted {
+        push_str(out, key);
+        out.push('=');
+        push_str(out, value);
+        out.push(';');
+    }
+    out.push('}');
+}
+
+fn push_field(out: &mut String, field: &Field, ignore: &HashSet<&str>) {
+    out.push_str("field(");
+    push_str(out, field.name());
+    out.push(',');
+    push_type(out, field.data_type(), ignore);
+    out.push_str(if field.is_nullable() { ",n)" } else { ",r)" });
+    push_metadata(out, field.metadata(), ignore);
+}
+
+const fn unit_name(unit: TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Second => "s",
+        TimeUnit::Millisecond => "ms",
+        TimeUnit::Microsecond => "us",
+        TimeUnit::Nanosecond => "ns",
+    }
+}
+
+/// Name of a type without parameters.
+const fn simple_type_name(data_type: &DataType) -> Option<&'static str> {
+    Some(match data_type {
+        DataType::Null => "null",
+        DataType::Boolean => "bool",
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Int64 => "int64",
+        DataType::UInt8 => "uint8",
+        DataType::UInt16 => "uint16",
+        DataType::UInt32 => "uint32",
+        DataType::UInt64 => "uint64",
+        DataType::Float16 => "float16",
+        DataType::Float32 => "float32",
+        DataType::Float64 => "float64",
+        DataType::Date32 => "date32",
+        DataType::Date64 => "date64",
+        DataType::Binary => "binary",
+        DataType::LargeBinary => "large_binary",
+        DataType::BinaryView => "binary_view",
+        DataType::Utf8 => "utf8",
+        DataType::LargeUtf8 => "large_utf8",
+        DataType::Utf8View => "utf8_view",
+        _ => return None,
+    })
+}
+
+fn push_type(out: &mut String, data_type: &DataType, ignore: &HashSet<&str>) {
+    if let Some(name) = simple_type_name(data_type) {
+        out.push_str(name);
+        return;
+    }
+    match data_type {
+        DataType::Timestamp(unit, time_zone) => {
+            let _ = write!(out, "timestamp({},", unit_name(*unit));
+            match time_zone {
+                Some(time_zone) => push_str(out, time_zone),
+                None => out.push('-'),
+            }
+            out.push(')');
+        }
+        DataType::Time32(unit) => {
+            let _ = write!(out, "time32({})", unit_name(*unit));
+        }
+        DataType::Time64(unit) => {
+            let _ = write!(out, "time64({})", unit_name(*unit));
+        }
+        DataType::Duration(unit) => {
+            let _ = write!(out, "duration({})", unit_name(*unit));
+        }
+        DataType::Interval(unit) => {
+            let unit = match unit {
+                IntervalUnit::YearMonth => "year_month",
+                IntervalUnit::DayTime => "day_time",
+                IntervalUnit::MonthDayNano => "month_day_nano",
+            };
+            let _ = write!(out, "interval({unit})");
+        }
+        DataType::FixedSizeBinary(size) => {
+            let _ = write!(out, "fixed_size_binary({size})");
+        }
+        DataType::Decimal32(precision, scale) => {
+            let _ = write!(out, "decimal32({precision},{scale})");
+        }
+        DataType::Decimal64(precision, scale) => {
+            let _ = write!(out, "decimal64({precision},{scale})");
+        }
+        DataType::Decimal128(precision, scale) => {
+            let _ = write!(out, "decimal128({precision},{scale})");
+        }
+        DataType::Decimal256(precision, scale) => {
+            let _ = write!(out, "decimal256({precision},{scale})");
+        }
+        DataType::Dictionary(key, value) => {
+            out.push_str("dictionary(");
+            push_type(out, key, ignore);
+            out.push(',');
+            push_type(out, value, ignore);
+            out.push(')');
+        }
+        nested => push_nested_type(out, nested, ignore),
+    }
+}
+
+/// Types with child fields.
+fn push_nested_type(out: &mut String, data_type: &DataType, ignore: &HashSet<&str>) {
+    let (name, children): (&str, Vec<&Field>) = match data_type {
+        DataType::List(child) => ("list", vec![child]),
+        DataType::ListView(child) => ("list_view", vec![child]),
+        DataType::LargeList(child) => ("large_list", vec![child]),
+        DataType::LargeListView(child) => ("large_list_view", vec![child]),
+        DataType::FixedSizeList(child, _) => ("fixed_size_list", vec![child]),
+        DataType::Map(entries, _) => ("map", vec![entries]),
+        DataType::RunEndEncoded(run_ends, values) => ("run_end_encoded", vec![run_ends, values]),
+        DataType::Struct(fields) => ("struct", fields.iter().map(AsRef::as_ref).collect()),
+        DataType::Union(fields, mode) => {
+            let mode = match mode {
+                UnionMode::Sparse => "sparse",
+                UnionMode::Dense => "dense",
+            };
+            let _ = write!(out, "union({mode}");
+            for (type_id, field) in fields.iter() {
+                let _ = write!(out, ",{type_id}=");
+                push_field(out, field, ignore);
+            }
+            out.push(')');
+            return;
+        }
+        other => unreachable!("{other:?} has no child fields"),
+    };
+    let _ = write!(out, "{name}(");
+    for (i, child) in children.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_field(out, child, ignore);
+    }
+    match data_type {
+        DataType::FixedSizeList(_, size) => {
+            let _ = write!(out, ",{size}");
+        }
+        DataType::Map(_, sorted) => out.push_str(if *sorted { ",sorted" } else { ",unsorted" }),
+        _ => {}
+    }
+    out.push(')');
+}
+
+/// Canonical `v1` serialization of `schema`, leaving out metadata keys in
+/// `ignore`.
+pub fn canonical_schema(schema: &Schema, ignore: &HashSet<&str>) -> String {
+    let mut out = String::from("schema{");
+    for field in schema.fields() {
+        push_field(&mut out, field, ignore);
+    }
+    out.push('}');
+    push_metadata(&mut out, schema.metadata(), ignore);
+    out
+}
+
+/// Versioned fingerprint of `schema`; see the module docs.
+pub fn schema_fingerprint_of(schema: &Schema, ignore: &HashSet<&str>) -> String {
+    let hash = fnv1a(canonical_schema(schema, ignore).as_bytes());
+    format!("{VERSION}:{hash:032x}")
+}
+
+/// Stable fingerprint of a table's schema, for "same schema" cache keys.
+///
+/// Covers every field in order (name, type with all its parameters,
+/// nullability, metadata, recursively for nested types) and the schema
+/// metadata. Metadata key order never matters, and keys listed in
+/// `ignore_metadata_keys` (e.g. creation timestamps) are left out at every
+/// level. The result looks like `"v1:" + 32 hex digits`; the `v1`
+/// canonicalization is documented with the crate source and will not
+/// change, so fingerprints can be persisted.
+#[wasm_bindgen]
+pub fn schema_fingerprint(
+    handle: TableHandle,
+    ignore_metadata_keys: Option<Vec<String>>,
+) -> std::result::Result<String, JsValue> {
+    let table = mem::get_table(handle)?;
+    let ignore: HashSet<&str> = ignore_metadata_keys
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    Ok(schema_fingerprint_of(&table.schema, &ignore))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn metadata(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    fn fingerprint(fields: Vec<Field>) -> String {
+        schema_fingerprint_of(&Schema::new(fields), &HashSet::new())
+    }
+
+    fn id() -> Field {
+        Field::new("id", DataType::Int64, false)
+    }
+
+    fn at() -> Field {
+        Field::new(
+            "at",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            true,
+        )
+    }
+
+    #[test]
+    fn fingerprints_are_versioned_hex() {
+        let fingerprint = fingerprint(vec![id(), at()]);
+        let hash = fingerprint.strip_prefix("v1:").unwrap();
+        assert_eq!(hash.len(), 32);
+        assert!(hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')));
+        assert_eq!(
+            canonical_schema(&Schema::new(vec![id(), at()]), &HashSet::new()),
+            "schema{field(2:id,int64,r)meta{}field(2:at,timestamp(ms,3:UTC),n)meta{}}meta{}"
+        );
+    }
+
+    #[test]
+    fn fingerprints_are_pinned() {
+        // Persisted fingerprints must keep matching; a change here needs a
+        // new version prefix.
+        assert_eq!(fingerprint(vec![]), "v1:9f351a1aeb1198c61697202df07394cf");
+        assert_eq!(
+            fingerprint(vec![id(), at()]),
+            "v1:286e8ebc4ebad1e53789331eaf5da258"
+        );
+    }
+
+    #[test]
+    fn field_order_and_details_change_the_fingerprint() {
+        let base = fingerprint(vec![id(), at()]);
+        assert_ne!(fingerprint(vec![at(), id()]), base);
+        assert_ne!(fingerprint(vec![id().with_nullable(true), at()]), base);
+        assert_ne!(fingerprint(vec![id().with_name("ID"), at()]), base);
+        let naive = at().with_data_type(DataType::Timestamp(TimeUnit::Millisecond, None));
+        assert_ne!(fingerprint(vec![id(), naive]), base);
+        let micros = at().with_data_type(DataType::Timestamp(
+            TimeUnit::Microsecond,
+            Some("UTC".into()),
+        ));
+        assert_ne!(fingerprint(vec![id(), micros]), base);
+    }
+
+    #[test]
+    fn metadata_key_order_does_not_matter() {
+        let entries: Vec<(String, String)> = (0..16)
+            .map(|i| (format!("key{i}"), format!("value{i}")))
+            .collect();
+        let forward: HashMap<String, String> = entries.iter().cloned().collect();
+        let backward: HashMap<String, String> = entries.iter().rev().cloned().collect();
+        let schema = |metadata: &HashMap<String, String>| {
+            Schema::new(vec![id().with_metadata(metadata.clone())]).with_metadata(metadata.clone())
+        };
+        assert_eq!(
+            schema_fingerprint_of(&schema(&forward), &HashSet::new()),
+            schema_fingerprint_of(&schema(&backward), &HashSet::new())
+        );
+        let mut changed = forward.clone();
+        changed.insert("key3".to_string(), "other".to_string());
+        assert_ne!(
+            schema_fingerprint_of(&schema(&forward), &HashSet::new()),
+            schema_fingerprint_of(&schema(&changed), &HashSet::new())
+        );
+    }
+
+    #[test]
+    fn ignored_keys_are_left_out_at_every_level() {
+        let ignore: HashSet<&str> = ["created_at"].into();
+        let schema = |created_at: &str| {
+            let stamped = metadata(&[("unit", "ms"), ("created_at", created_at)]);
+            let child = Field::new("item", DataType::Utf8, true).with_metadata(stamped.clone());
+            Schema::new(vec![
+                id().with_metadata(stamped.clone()),
+                Field::new("tags", DataType::List(Arc::new(child)), true),
+            ])
+            .with_metadata(stamped)
+        };
+        let (monday, tuesday) = (schema("2026-10-12"), schema("2026-10-13"));
+        assert_eq!(
+            schema_fingerprint_of(&monday, &ignore),
+            schema_fingerprint_of(&tuesday, &ignore)
+        );
+        assert_ne!(
+            schema_fingerprint_of(&monday, &HashSet::new()),
+            schema_fingerprint_of(&tuesday, &HashSet::new())
+        );
+        // Keys that are not ignored still count.
+        let unstamped = Schema::new(vec![id().with_metadata(metadata(&[("unit", "ms")]))]);
+        let stamped = Schema::new(vec![
+            id().with_metadata(metadata(&[("unit", "s"), ("created_at", "2026-10-12")]))
+        ]);
+        assert_ne!(
+            schema_fingerprint_of(&unstamped, &ignore),
+            schema_fingerprint_of(&stamped, &ignore)
+        );
+    }
+}
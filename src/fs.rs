@@ -0,0 +1,818 @@
+//! Format detection and readers that dispatch on the detected format, plus
+//! the Parquet writer.
+
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::ipc::check_ipc_fits;
+use crate::mem::{self, TableData, TableHandle};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::{ArrowSchemaConverter, ArrowWriter};
+use parquet::basic::Compression;
+use parquet::file::metadata::{KeyValue, ParquetMetaData};
+use parquet::file::properties::{WriterProperties, WriterPropertiesBuilder};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Magic bytes opening an Arrow IPC file.
+const IPC_FILE_MAGIC: &[u8] = b"ARROW1";
+/// Magic bytes opening a Parquet file.
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+
+/// Serialized table formats the crate can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Arrow IPC file (Feather v2), with a footer indexing its batches.
+    IpcFile,
+    /// Arrow IPC stream.
+    IpcStream,
+    /// Apache Parquet.
+    Parquet,
+}
+
+impl FileFormat {
+    /// Detect the format of `data` from its leading magic bytes; anything
+    /// else is assumed to be an IPC stream.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(IPC_FILE_MAGIC) {
+            Self::IpcFile
+        } else if data.starts_with(PARQUET_MAGIC) {
+            Self::Parquet
+        } else {
+            Self::IpcStream
+        }
+    }
+
+    /// Name reported to JS.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::IpcFile => "ipc_file",
+            Self::IpcStream => "ipc_stream",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Options for [`read_preview`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct PreviewOptions {
+    max_rows: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self { max_rows: 100 }
+    }
+}
+
+/// Leading batches of a preview read.
+struct Preview {
+    batches: Vec<RecordBatch>,
+    total_rows: Option<usize>,
+    truncated: bool,
+}
+
+/// Pull batches from `batches` until `max_rows` rows are collected, slicing
+/// the last one. Returns the batches and whether rows were cut from the last
+/// batch; the iterator is not advanced past the batch that hit the limit.
+fn take_rows(
+    batches: &mut impl Iterator<Item = std::result::Result<RecordBatch, ArrowError>>,
+    max_rows: usize,
+) -> Result<(Vec<RecordBatch>, bool)> {
+    let mut result = Vec::new();
+    let mut rows = 0;
+    while rows < max_rows {
+        let Some(batch) = batches.next() else {
+            break;
+        };
+        let batch = batch.map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        let take = batch.num_rows().min(max_rows - rows);
+        rows += take;
+        if take < batch.num_rows() {
+            result.push(batch.slice(0, take));
+            return Ok((result, true));
+        }
+        result.push(batch);
+    }
+    Ok((result, false))
+}
+
+/// Read to retry with when a preview does not fit in memory.
+const SMALLER_PREVIEW: &str = "preview fewer rows with a smaller maxRows";
+
+/// Rows `[offset, offset + length)` of `batches`, decoding nothing past the
+/// window. Batches before the window are still decoded, since IPC messages
+/// do not record their row counts up front.
+fn window_rows(
+    mut batches: impl Iterator<Item = std::result::Result<RecordBatch, ArrowError>>,
+    offset: usize,
+    length: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut skipped = 0;
+    let mut first = None;
+    while skipped < offset {
+        let Some(batch) = batches.next() else {
+            return Ok(Vec::new());
+        };
+        let batch = batch.map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        let rows = batch.num_rows();
+        if skipped + rows > offset {
+            let start = offset - skipped;
+            first = Some(Ok(batch.slice(start, rows - start)));
+        }
+        skipped += rows;
+    }
+    let (batches, _) = take_rows(&mut first.into_iter().chain(batches), length)?;
+    Ok(batches)
+}
+
+fn preview_ipc_file(data: &[u8], max_rows: usize) -> Result<Preview> {
+    check_ipc_fits(data, FileFormat::IpcFile, Some(max_rows), SMALLER_PREVIEW)?;
+    let mut reader = FileReader::try_new(Cursor::new(data), None)
+        .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+    let schema = reader.schema();
+    let block_count = reader.num_batches();
+    let (mut batches, sliced) = take_rows(&mut reader, max_rows)?;
+    let truncated = sliced || batches.len() < block_count;
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(schema));
+    }
+    let total_rows = (!truncated).then(|| rows_of(&batches));
+    Ok(Preview {
+        batches,
+        total_rows,
+        truncated,
+    })
+}
+
+/// Whether the bytes at `position` end the stream: nothing left, or an
+/// end-of-stream marker (with or without continuation prefix).
+fn at_end_of_stream(data: &[u8], position: usize) -> bool {
+    let rest = &data[position.min(data.len())..];
+    rest.len() < 4
+        || rest.starts_with(&[0, 0, 0, 0])
+        || rest.starts_with(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0])
+}
+
+fn preview_ipc_stream(data: &[u8], max_rows: usize) -> Result<Preview> {
+    check_ipc_fits(data, FileFormat::IpcStream, Some(max_rows), SMALLER_PREVIEW)?;
+    let mut reader = StreamReader::try_new(Cursor::new(data), None)
+        .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+    let schema = reader.schema();
+    let (mut batches, sliced) = take_rows(&mut reader, max_rows)?;
+    let position = usize::try_from(reader.get_ref().position()).unwrap_or(usize::MAX);
+    let truncated = sliced || (!reader.is_finished() && !at_end_of_stream(data, position));
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(schema));
+    }
+    let total_rows = (!truncated).then(|| rows_of(&batches));
+    Ok(Preview {
+        batches,
+        total_rows,
+        truncated,
+    })
+}
+
+/// Uncompressed bytes of each row group a read of the first `rows` rows
+/// (every row when `None`) decodes, from the file's footer metadata.
+fn row_group_sizes(metadata: &ParquetMetaData, rows: Option<usize>) -> Vec<u64> {
+    let mut before = 0_u64;
+    metadata
+        .row_groups()
+        .iter()
+        .take_while(|group| {
+            let needed = rows.is_none_or(|rows| before < rows as u64);
+            before = before.saturating_add(u64::try_from(group.num_rows()).unwrap_or(0));
+            needed
+        })
+        .map(|group| {
+            group
+                .columns()
+                .iter()
+                .map(|column| u64::try_from(column.uncompressed_size()).unwrap_or(0))
+                .fold(0_u64, u64::saturating_add)
+        })
+        .collect()
+}
+
+/// Fail with [`ArrowWasmError::TooLarge`] when the row groups a read of the
+/// first `rows` rows decodes are estimated not to fit in
+/// [`mem::memory_limit`], before any of them is decoded; `narrower` names
+/// the read to retry with.
+fn check_parquet_fits(
+    metadata: &ParquetMetaData,
+    rows: Option<usize>,
+    narrower: &str,
+) -> Result<()> {
+    let sizes = row_group_sizes(metadata, rows);
+    mem::check_fits(&sizes, mem::memory_limit(), "row groups", narrower)
+}
+
+fn preview_parquet(data: &[u8], max_rows: usize, narrower: &str) -> Result<Preview> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(data))?;
+    check_parquet_fits(builder.metadata(), Some(max_rows), narrower)?;
+    let total_rows = usize::try_from(builder.metadata().file_metadata().num_rows()).ok();
+    let schema = builder.schema().clone();
+    let reader = builder
+        .with_batch_size(max_rows.clamp(1, 8192))
+        .with_limit(max_rows)
+        .build()?;
+    let mut batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(schema));
+    }
+    let truncated = total_rows.is_some_and(|total| total > rows_of(&batches));
+    Ok(Preview {
+        batches,
+        total_rows,
+        truncated,
+    })
+}
+
+fn rows_of(batches: &[RecordBatch]) -> usize {
+    batches.iter().map(RecordBatch::num_rows).sum()
+}
+
+/// Read the schema and the first rows of an IPC file, IPC stream or
+/// Parquet buffer, decoding no more than needed.
+///
+/// `options` is `{maxRows?}` (100 by default). IPC files stop after the
+/// footer blocks covering `maxRows`, IPC streams stop reading messages, and
+/// Parquet reads with a row limit. Returns `{table, totalRowsIfKnown,
+/// format, truncated}`; `totalRowsIfKnown` is null when counting would
+/// require decoding the rest (IPC streams and files cut short). A preview
+/// whose batches or row groups would not fit in the memory limit fails
+/// before decoding (see `set_memory_limit`).
+#[wasm_bindgen]
+pub fn read_preview(data: &[u8], options: JsValue) -> std::result::Result<JsValue, JsValue> {
+    let options: PreviewOptions = if options.is_undefined() || options.is_null() {
+        PreviewOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let format = FileFormat::detect(data);
+    let preview = match format {
+        FileFormat::IpcFile => preview_ipc_file(data, options.max_rows)?,
+        FileFormat::IpcStream => preview_ipc_stream(data, options.max_rows)?,
+        FileFormat::Parquet => preview_parquet(data, options.max_rows, SMALLER_PREVIEW)?,
+    };
+    let handle = mem::store_table(TableData::new(preview.batches)?)?;
+
+    let result = js_sys::Object::new();
+    set_property(&result, "table", &handle.into())?;
+    let total_rows = preview
+        .total_rows
+        .map_or(JsValue::NULL, |rows| JsValue::from_f64(rows as f64));
+    set_property(&result, "totalRowsIfKnown", &total_rows)?;
+    set_property(&result, "format", &format.name().into())?;
+    set_property(&result, "truncated", &preview.truncated.into())?;
+    Ok(result.into())
+}
+
+/// Read the first `limit` rows of a Parquet buffer into a new table.
+///
+/// Decoding stops once `limit` rows are read, so large files are never
+/// fully materialized. A limit past the file's row count reads every row;
+/// `limit` must be at least 1. Fails before decoding when the row groups
+/// holding those rows would not fit in the memory limit.
+#[wasm_bindgen]
+pub fn read_parquet_limit(data: &[u8], limit: usize) -> std::result::Result<TableHandle, JsValue> {
+    if limit == 0 {
+        return Err(ArrowWasmError::InvalidInput("limit must be at least 1".to_string()).into());
+    }
+    if FileFormat::detect(data) != FileFormat::Parquet {
+        return Err(ArrowWasmError::InvalidInput("Data is not a Parquet file".to_string()).into());
+    }
+    let preview = preview_parquet(data, limit, "read fewer rows with a smaller limit")?;
+    Ok(mem::store_table(TableData::new(preview.batches)?)?)
+}
+
+/// Every batch of a Parquet file, or one empty batch with its schema when it
+/// has no rows.
+fn decode_parquet(data: &[u8]) -> Result<Vec<RecordBatch>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(data))?;
+    check_parquet_fits(
+        builder.metadata(),
+        None,
+        "read fewer rows with read_parquet_limit",
+    )?;
+    let schema = builder.schema().clone();
+    let mut batches = builder
+        .build()?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(schema));
+    }
+    Ok(batches)
+}
+
+/// Read a whole Parquet file into a new table.
+///
+/// A file without rows gives an empty table with the file's schema. Data
+/// that is not Parquet, and corrupt row groups, fail with the Parquet
+/// reader's message; use `read_parquet_limit` to read only leading rows,
+/// e.g. when the file would not fit in the memory limit, which is checked
+/// from the row group metadata before decoding.
+#[wasm_bindgen]
+pub fn read_table_from_parquet(data: &[u8]) -> std::result::Result<TableHandle, JsValue> {
+    if FileFormat::detect(data) != FileFormat::Parquet {
+        return Err(ArrowWasmError::InvalidInput("Data is not a Parquet file".to_string()).into());
+    }
+    Ok(mem::store_table(TableData::new(decode_parquet(data)?)?)?)
+}
+
+/// Read rows `[offset, offset + length)` of an IPC file or stream into a new
+/// table, e.g. to serve one page of a large payload.
+///
+/// Reading stops after the batch that completes the window. A window past
+/// the end of the data is cut short, and one that starts past the end gives
+/// an empty table with the data's schema. Fails before decoding when the
+/// batches up to the end of the window would not fit in the memory limit.
+#[wasm_bindgen]
+pub fn read_ipc_range(
+    data: &[u8],
+    offset: usize,
+    length: usize,
+) -> std::result::Result<TableHandle, JsValue> {
+    let format = FileFormat::detect(data);
+    if format != FileFormat::Parquet {
+        check_ipc_fits(
+            data,
+            format,
+            Some(offset.saturating_add(length)),
+            "read a smaller range",
+        )?;
+    }
+    let (schema, mut batches) = match format {
+        FileFormat::IpcFile => {
+            let reader = FileReader::try_new(Cursor::new(data), None)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+            (reader.schema(), window_rows(reader, offset, length)?)
+        }
+        FileFormat::IpcStream => {
+            let reader = StreamReader::try_new(Cursor::new(data), None)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+            (reader.schema(), window_rows(reader, offset, length)?)
+        }
+        FileFormat::Parquet => {
+            return Err(ArrowWasmError::InvalidInput(
+                "Data is not an Arrow IPC file or stream".to_string(),
+            )
+            .into())
+        }
+    };
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(schema));
+    }
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+/// Parquet codec named `name` (case-insensitive).
+///
+/// Only the codecs compiled into the module are accepted; the others fail
+/// naming the ones that are.
+fn parse_codec(name: &str) -> Result<Compression> {
+    match name.to_ascii_lowercase().as_str() {
+        "uncompressed" | "none" => Ok(Compression::UNCOMPRESSED),
+        "lz4_raw" => Ok(Compression::LZ4_RAW),
+        "lz4" => Ok(Compression::LZ4),
+        "snappy" => Ok(Compression::SNAPPY),
+        "gzip" | "brotli" | "zstd" | "lzo" => Err(ArrowWasmError::InvalidInput(format!(
+            "Codec '{name}' is not available in this build; use uncompressed, snappy, lz4_raw or lz4"
+        ))),
+        _ => Err(ArrowWasmError::InvalidInput(format!(
+            "Unknown codec '{name}', expected uncompressed, snappy, lz4_raw or lz4"
+        ))),
+    }
+}
+
+/// Encode `table` as a Parquet file with `properties`, compressing each
+/// column listed in `codecs` with its codec.
+pub fn encode_parquet(
+    table: &TableData,
+    codecs: &HashMap<String, String>,
+    mut properties: WriterPropertiesBuilder,
+) -> Result<Vec<u8>> {
+    if !codecs.is_empty() {
+        // A nested column is stored as one Parquet column per leaf; each
+        // leaf under a listed top-level column gets its codec.
+        let descriptor = ArrowSchemaConverter::new().convert(&table.schema)?;
+        for (name, codec) in codecs {
+            if table.schema.index_of(name).is_err() {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Column '{name}' not found"
+                )));
+            }
+            let codec = parse_codec(codec)?;
+            for column in descriptor.columns() {
+                if column.path().parts().first() == Some(name) {
+                    properties = properties.set_column_compression(column.path().clone(), codec);
+                }
+            }
+        }
+    }
+
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(
+        &mut buffer,
+        Arc::clone(&table.schema),
+        Some(properties.build()),
+    )?;
+    for batch in &table.batches {
+        writer.write(batch)?;
+    }
+    writer.close()?;
+    Ok(buffer)
+}
+
+/// Serialize a table as a Parquet file with a compression codec per
+/// column.
+///
+/// `codecs` maps column names to codecs; columns it leaves out use
+/// `default_codec` (`"uncompressed"` by default). Codecs are
+/// `"uncompressed"`, `"snappy"`, `"lz4_raw"` and `"lz4"`; gzip, brotli,
+/// zstd and lzo are not compiled into the module and fail, as do unknown
+/// codecs and column names. Read back with `read_table_from_parquet`.
+#[wasm_bindgen]
+pub fn write_table_to_parquet_per_column(
+    handle: TableHandle,
+    codecs: JsValue,
+    default_codec: Option<String>,
+) -> std::result::Result<js_sys::Uint8Array, JsValue> {
+    let codecs: HashMap<String, String> = if codecs.is_undefined() || codecs.is_null() {
+        HashMap::new()
+    } else {
+        serde_wasm_bindgen::from_value(codecs).map_err(ArrowWasmError::from)?
+    };
+    let default_codec = default_codec
+        .as_deref()
+        .map_or(Ok(Compression::UNCOMPRESSED), parse_codec)?;
+    let table = mem::get_table(handle)?;
+    let properties = WriterProperties::builder().set_compression(default_codec);
+    let bytes = encode_parquet(&table, &codecs, properties)?;
+    Ok(js_sys::Uint8Array::from(bytes.as_slice()))
+}
+
+/// Options for [`write_table_to_parquet`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct ParquetWriteOptions {
+    compression: Option<String>,
+    max_row_group_size: Option<usize>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl ParquetWriteOptions {
+    /// Writer properties for these options.
+    fn into_properties(self) -> Result<WriterPropertiesBuilder> {
+        let codec = self
+            .compression
+            .as_deref()
+            .map_or(Ok(Compression::UNCOMPRESSED), parse_codec)?;
+        let mut properties = WriterProperties::builder().set_compression(codec);
+        if let Some(rows) = self.max_row_group_size {
+            if rows == 0 {
+                return Err(ArrowWasmError::InvalidInput(
+                    "maxRowGroupSize must be at least 1".to_string(),
+                ));
+            }
+            properties = properties.set_max_row_group_size(rows);
+        }
+        if !self.metadata.is_empty() {
+            properties = properties.set_key_value_metadata(Some(
+                self.metadata
+                    .into_iter()
+                    .map(|(key, value)| KeyValue::new(key, value))
+                    .collect(),
+            ));
+        }
+        Ok(properties)
+    }
+}
+
+/// Serialize a table as a Parquet file.
+///
+/// `options` is `{compression?, maxRowGroupSize?, metadata?}`:
+/// `compression` is `"uncompressed"` (the default, also spelled `"none"`),
+/// `"snappy"`, `"lz4_raw"` or `"lz4"`, and other codecs fail as in
+/// `write_table_to_parquet_per_column`; `maxRowGroupSize` caps the rows per
+/// row group (at least 1, 1 048 576 by default); `metadata` is an object of
+/// string key-value pairs stored in the file footer. Other Parquet readers,
+/// e.g. `PyArrow` and `DuckDB`, read the output. Read it back with
+/// `read_table_from_parquet`.
+#[wasm_bindgen]
+pub fn write_table_to_parquet(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<js_sys::Uint8Array, JsValue> {
+    let options: ParquetWriteOptions = if options.is_undefined() || options.is_null() {
+        ParquetWriteOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let properties = options.into_properties()?;
+    let table = mem::get_table(handle)?;
+    let bytes = encode_parquet(&table, &HashMap::new(), properties)?;
+    Ok(js_sys::Uint8Array::from(bytes.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, AsArray, Int64Array, ListArray, StringArray, UInt64Array};
+    use arrow::datatypes::{DataType, Field, Int64Type, Schema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+
+    /// A Parquet file of `rows` Int64 values in row groups of `group` rows.
+    fn parquet(rows: i64, group: usize) -> Vec<u8> {
+        let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::Int64, false)]));
+        let column: ArrayRef = Arc::new(Int64Array::from_iter_values(0..rows));
+        let batch = RecordBatch::try_new(Arc::clone(&schema), vec![column]).unwrap();
+        let table = TableData::new(vec![batch]).unwrap();
+        let properties = WriterProperties::builder().set_max_row_group_size(group);
+        encode_parquet(&table, &HashMap::new(), properties).unwrap()
+    }
+
+    /// The values of the Int64 column `n` of table `handle`.
+    fn values(handle: TableHandle) -> Vec<i64> {
+        let table = mem::get_table(handle).unwrap();
+        let column = table.schema.index_of("n").unwrap();
+        table
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch.column(column).as_primitive::<Int64Type>().values();
+                values.to_vec()
+            })
+            .collect()
+    }
+
+    fn metadata(data: &[u8]) -> Arc<ParquetMetaData> {
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(Bytes::copy_from_slice(data)).unwrap();
+        Arc::clone(builder.metadata())
+    }
+
+    #[test]
+    fn row_group_sizes_come_from_the_footer() {
+        let data = parquet(1_000, 400);
+        let metadata = metadata(&data);
+        let sizes = row_group_sizes(&metadata, None);
+        assert_eq!(sizes.len(), 3);
+        // Uncompressed pages hold at least the raw 8-byte values.
+        assert!(sizes[0] >= 400 * 8 && sizes[2] >= 200 * 8, "{sizes:?}");
+        for (rows, groups) in [(Some(0), 0), (Some(1), 1), (Some(400), 1), (Some(401), 2)] {
+            assert_eq!(row_group_sizes(&metadata, rows).len(), groups, "{rows:?}");
+        }
+        assert_eq!(row_group_sizes(&metadata, Some(5_000)), sizes);
+    }
+
+    #[test]
+    fn oversized_row_groups_fail_before_decoding() {
+        let data = parquet(1_000, 400);
+        let sizes = row_group_sizes(&metadata(&data), None);
+        let limit = sizes[0] + sizes[1] / 2;
+        let Err(ArrowWasmError::TooLarge { estimate, hint, .. }) =
+            mem::check_fits(&sizes, limit, "row groups", "read fewer rows")
+        else {
+            panic!("three row groups must not fit in one and a half");
+        };
+        assert_eq!(estimate, sizes.iter().sum::<u64>());
+        assert!(hint.starts_with("the first 1 of 3 row groups"), "{hint}");
+
+        // Natively the limit is unbounded, so every reader decodes.
+        let batches = decode_parquet(&data).unwrap();
+        assert_eq!(rows_of(&batches), 1_000);
+        let preview = preview_parquet(&data, 10, SMALLER_PREVIEW).unwrap();
+        assert_eq!(rows_of(&preview.batches), 10);
+        assert!(preview.truncated);
+    }
+
+    #[test]
+    fn view_columns_round_trip() {
+        let batches = crate::samples::sample_batches("views").unwrap();
+        let table = TableData::new(batches.clone()).unwrap();
+        let data = encode_parquet(&table, &HashMap::new(), WriterProperties::builder()).unwrap();
+        assert_eq!(decode_parquet(&data).unwrap(), batches);
+    }
+
+    #[test]
+    fn limited_read_takes_the_leading_rows() {
+        // 100 rows in row groups of 30, so 5 rows come from the first group.
+        let data = parquet(100, 30);
+        for (limit, expected) in [(5, 0..5), (35, 0..35), (500, 0..100)] {
+            let handle = read_parquet_limit(&data, limit).unwrap();
+            assert_eq!(
+                values(handle),
+                expected.collect::<Vec<_>>(),
+                "limit {limit}"
+            );
+        }
+    }
+
+    #[test]
+    fn ipc_range_reads_a_window_across_batches() {
+        // 50 rows in batches of 7, so rows 10..20 span three batches.
+        let batches: Vec<RecordBatch> = (0..50)
+            .step_by(7)
+            .map(|start: i64| {
+                let column: ArrayRef =
+                    Arc::new(Int64Array::from_iter_values(start..50.min(start + 7)));
+                RecordBatch::try_from_iter([("n", column)]).unwrap()
+            })
+            .collect();
+        let schema = batches[0].schema();
+        let mut file = Vec::new();
+        let mut writer = FileWriter::try_new(&mut file, &schema).unwrap();
+        let mut stream = Vec::new();
+        let mut stream_writer = StreamWriter::try_new(&mut stream, &schema).unwrap();
+        for batch in &batches {
+            writer.write(batch).unwrap();
+            stream_writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        stream_writer.finish().unwrap();
+        drop((writer, stream_writer));
+
+        for data in [&file, &stream] {
+            let range = |offset, length| values(read_ipc_range(data, offset, length).unwrap());
+            assert_eq!(range(10, 10), (10..20).collect::<Vec<_>>());
+            assert_eq!(range(0, 7), (0..7).collect::<Vec<_>>());
+            assert_eq!(range(45, 10), (45..50).collect::<Vec<_>>());
+            assert!(range(60, 10).is_empty());
+        }
+    }
+
+    #[test]
+    fn uint64_extremes_round_trip_exactly() {
+        let values = [
+            Some(u64::MAX - 1),
+            Some(u64::MAX),
+            None,
+            Some(1 << 63),
+            Some((1 << 53) + 1),
+            Some(0),
+        ];
+        let column: ArrayRef = Arc::new(UInt64Array::from(values.to_vec()));
+        let batch = RecordBatch::try_from_iter([("id", column)]).unwrap();
+        let table = TableData::new(vec![batch.clone()]).unwrap();
+
+        let ipc = crate::ipc::encode_stream(&table.schema, &table.batches, false).unwrap();
+        let (_, read) = crate::ipc::read_ipc_batches(&ipc, None).unwrap();
+        assert_eq!(read, std::slice::from_ref(&batch));
+
+        let parquet = encode_parquet(&table, &HashMap::new(), WriterProperties::builder()).unwrap();
+        let read = decode_parquet(&parquet).unwrap();
+        assert_eq!(read[0].column(0).data_type(), &DataType::UInt64);
+        assert_eq!(read[0].column(0), batch.column(0));
+
+        let csv = crate::csv::write_table_to_csv(mem::store_table(table).unwrap(), None).unwrap();
+        let parsed: Vec<Option<u64>> = csv.lines().skip(1).map(|line| line.parse().ok()).collect();
+        assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn per_column_codecs_read_back_intact() {
+        let tags = ListArray::from_iter_primitive::<Int64Type, _, _>(
+            (0..2_000).map(|i| (i % 5 != 0).then(|| vec![Some(i), None, Some(i % 7)])),
+        );
+        let batch = RecordBatch::try_from_iter([
+            (
+                "n",
+                Arc::new(Int64Array::from_iter_values(0..2_000)) as ArrayRef,
+            ),
+            (
+                "text",
+                Arc::new(
+                    (0..2_000)
+                        .map(|i| (i % 3 != 0).then(|| format!("row {}", i % 10)))
+                        .collect::<StringArray>(),
+                ),
+            ),
+            ("tags", Arc::new(tags)),
+        ])
+        .unwrap();
+        let table = TableData::new(vec![batch.clone()]).unwrap();
+        let codecs = HashMap::from([
+            ("n".to_string(), "LZ4_RAW".to_string()),
+            ("text".to_string(), "Snappy".to_string()),
+            ("tags".to_string(), "lz4".to_string()),
+        ]);
+        let data = encode_parquet(&table, &codecs, WriterProperties::builder()).unwrap();
+
+        let metadata = metadata(&data);
+        let used: Vec<(String, Compression)> = metadata
+            .row_group(0)
+            .columns()
+            .iter()
+            .map(|column| (column.column_path().string(), column.compression()))
+            .collect();
+        assert_eq!(
+            used,
+            [
+                ("n".to_string(), Compression::LZ4_RAW),
+                ("text".to_string(), Compression::SNAPPY),
+                ("tags.list.item".to_string(), Compression::LZ4),
+            ]
+        );
+        // The reader hands back batches of 1024 rows.
+        let read = decode_parquet(&data).unwrap();
+        let read = arrow::compute::concat_batches(&read[0].schema(), &read).unwrap();
+        assert_eq!(read, batch);
+
+        for (codecs, expected) in [
+            (
+                HashMap::from([("missing".to_string(), "lz4".to_string())]),
+                "Column 'missing' not found",
+            ),
+            (
+                HashMap::from([("n".to_string(), "zstd".to_string())]),
+                "Codec 'zstd' is not available in this build",
+            ),
+        ] {
+            let error = encode_parquet(&table, &codecs, WriterProperties::builder()).unwrap_err();
+            assert!(error.to_string().contains(expected), "{error}");
+        }
+    }
+
+    #[test]
+    fn whole_file_reads_every_row_group() {
+        let data = parquet(1000, 400);
+        assert_eq!(metadata(&data).num_row_groups(), 3);
+
+        let handle = read_table_from_parquet(&data).unwrap();
+        let table = mem::get_table(handle).unwrap();
+        let expected = Schema::new(vec![Field::new("n", DataType::Int64, false)]);
+        assert_eq!(table.schema.fields(), expected.fields());
+        assert_eq!(table.row_count(), 1000);
+        assert_eq!(values(handle), (0..1000).collect::<Vec<i64>>());
+
+        // A file without rows keeps its schema.
+        let empty = mem::get_table(read_table_from_parquet(&parquet(0, 400)).unwrap()).unwrap();
+        assert_eq!(empty.schema.fields(), expected.fields());
+        assert_eq!(empty.row_count(), 0);
+    }
+
+    #[test]
+    fn written_files_read_back_with_their_options() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "n",
+                Arc::new(Int64Array::from_iter_values((0..1_000).map(|i| i % 4))) as ArrayRef,
+            ),
+            (
+                "text",
+                Arc::new(
+                    (0..1_000)
+                        .map(|i| (i % 9 != 0).then_some("the same words over and over"))
+                        .collect::<StringArray>(),
+                ),
+            ),
+        ])
+        .unwrap();
+        let table = TableData::new(vec![batch.clone()]).unwrap();
+        let write = |compression: &str| {
+            let options = ParquetWriteOptions {
+                compression: Some(compression.to_string()),
+                max_row_group_size: Some(300),
+                metadata: BTreeMap::from([("source".to_string(), "unit test".to_string())]),
+            };
+            encode_parquet(&table, &HashMap::new(), options.into_properties().unwrap()).unwrap()
+        };
+        let data = write("none");
+
+        let read = mem::get_table(read_table_from_parquet(&data).unwrap()).unwrap();
+        let read = arrow::compute::concat_batches(&read.schema, &read.batches).unwrap();
+        assert_eq!(read.columns(), batch.columns());
+
+        let metadata = metadata(&data);
+        assert_eq!(metadata.num_row_groups(), 4);
+        let footer = metadata.file_metadata().key_value_metadata().unwrap();
+        assert!(footer
+            .iter()
+            .any(|pair| pair.key == "source" && pair.value.as_deref() == Some("unit test")));
+
+        for codec in ["snappy", "lz4_raw", "lz4"] {
+            let compressed = write(codec);
+            assert!(compressed.len() < data.len(), "{codec}");
+        }
+
+        let options = ParquetWriteOptions {
+            max_row_group_size: Some(0),
+            ..ParquetWriteOptions::default()
+        };
+        let Err(error) = options.into_properties() else {
+            panic!("a row group size of 0 was accepted");
+        };
+        assert!(error
+            .to_string()
+            .contains("maxRowGroupSize must be at least 1"));
+    }
+}
@@ -0,0 +1,2021 @@
+//! Arrow IPC helpers shared by the read/write entry points.
+
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::fs::FileFormat;
+use crate::mem::{self, TableData, TableHandle};
+use crate::table::{coerce_batches, reconcile_batches};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use arrow_data::{layout, ArrayData, BufferSpec};
+use arrow_ipc::writer::{DictionaryTracker, IpcDataGenerator, IpcWriteOptions};
+use arrow_select::concat::concat_batches;
+use js_sys::{Function, Uint8Array};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::io::{self, Write};
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Size at which [`JsChunkWriter`] hands its buffer to JS.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Writer options for the crate's IPC output, optionally LZ4 compressed.
+pub fn write_options(enable_lz4: bool) -> Result<IpcWriteOptions> {
+    IpcWriteOptions::default()
+        .try_with_compression(enable_lz4.then_some(arrow_ipc::CompressionType::LZ4_FRAME))
+        .map_err(|e| ArrowWasmError::Ipc(e.to_string()))
+}
+
+/// Buffer alignment of [`write_options`], the `IpcWriteOptions` default.
+const IPC_ALIGNMENT: usize = 64;
+
+/// `len` rounded up to [`IPC_ALIGNMENT`].
+const fn align(len: usize) -> usize {
+    len.div_ceil(IPC_ALIGNMENT) * IPC_ALIGNMENT
+}
+
+/// Largest flatbuffer header of a record batch or dictionary message
+/// besides its 16-byte field node and buffer entries.
+const MESSAGE_HEADER_BOUND: usize = 160;
+
+/// Running upper bound on the body of one IPC message, following what
+/// arrow-ipc's writer emits for each array: a validity bitmap for every
+/// type that has one (written even when there are no nulls), buffers
+/// truncated to the slice in view, and dictionary values in messages of
+/// their own.
+struct BodyBound {
+    compressed: bool,
+    nodes: usize,
+    buffers: usize,
+    bytes: usize,
+    dictionaries: Vec<ArrayData>,
+}
+
+impl BodyBound {
+    const fn new(compressed: bool) -> Self {
+        Self {
+            compressed,
+            nodes: 0,
+            buffers: 0,
+            bytes: 0,
+            dictionaries: Vec::new(),
+        }
+    }
+
+    /// A compressed buffer is at most its raw bytes plus the 8-byte length
+    /// prefix, as the writer stores buffers that do not shrink uncompressed.
+    const fn buffer(&mut self, len: usize) {
+        let prefix = if self.compressed && len > 0 { 8 } else { 0 };
+        self.buffers += 1;
+        self.bytes += align(len + prefix);
+    }
+
+    fn array(&mut self, data: &ArrayData) {
+        let len = data.len();
+        self.nodes += 1;
+        if !matches!(
+            data.data_type(),
+            DataType::Null | DataType::Union(_, _) | DataType::RunEndEncoded(_, _)
+        ) {
+            self.buffer(len.div_ceil(8));
+        }
+        match data.data_type() {
+            DataType::Utf8 | DataType::Binary => {
+                let offsets = &data.buffers()[0].typed_data::<i32>()[data.offset()..];
+                self.buffer((len + 1) * 4);
+                self.buffer((offsets[len] - offsets[0]).cast_unsigned() as usize);
+            }
+            DataType::LargeUtf8 | DataType::LargeBinary => {
+                let offsets = &data.buffers()[0].typed_data::<i64>()[data.offset()..];
+                self.buffer((len + 1) * 8);
+                self.buffer(usize::try_from(offsets[len] - offsets[0]).unwrap_or(usize::MAX));
+            }
+            DataType::List(_) | DataType::Map(_, _) => {
+                let offsets = &data.buffers()[0].typed_data::<i32>()[data.offset()..];
+                let (start, end) = (offsets[0].cast_unsigned(), offsets[len].cast_unsigned());
+                self.buffer((len + 1) * 4);
+                self.array(&data.child_data()[0].slice(start as usize, (end - start) as usize));
+            }
+            DataType::LargeList(_) => {
+                let offsets = &data.buffers()[0].typed_data::<i64>()[data.offset()..];
+                let start = usize::try_from(offsets[0]).unwrap_or_default();
+                let end = usize::try_from(offsets[len]).unwrap_or_default();
+                self.buffer((len + 1) * 8);
+                self.array(&data.child_data()[0].slice(start, end - start));
+            }
+            DataType::FixedSizeList(_, size) => {
+                let size = size.cast_unsigned() as usize;
+                self.array(&data.child_data()[0].slice(data.offset() * size, len * size));
+            }
+            DataType::Boolean => self.buffer(len.div_ceil(8)),
+            DataType::Dictionary(_, _) => {
+                self.fixed_width(data);
+                self.dictionaries.push(data.child_data()[0].clone());
+            }
+            data_type
+                if data_type.is_numeric()
+                    || data_type.is_temporal()
+                    || matches!(data_type, DataType::FixedSizeBinary(_)) =>
+            {
+                self.fixed_width(data);
+            }
+            _ => {
+                for buffer in data.buffers() {
+                    self.buffer(buffer.len());
+                }
+                for child in data.child_data() {
+                    self.array(child);
+                }
+            }
+        }
+    }
+
+    fn fixed_width(&mut self, data: &ArrayData) {
+        let width = match layout(data.data_type()).buffers.first() {
+            Some(BufferSpec::FixedWidth { byte_width, .. }) => *byte_width,
+            _ => 0,
+        };
+        self.buffer((data.len() * width).min(data.buffers()[0].len()));
+    }
+
+    /// Bytes of the whole message: prefix, padded header and body.
+    const fn message(&self) -> usize {
+        align(8 + MESSAGE_HEADER_BOUND + 16 * (self.nodes + self.buffers)) + self.bytes
+    }
+}
+
+/// Exact size of the schema message `encode_stream` writes for `schema`,
+/// continuation marker and length prefix included.
+fn schema_message_size(schema: &SchemaRef, enable_lz4: bool) -> Result<usize> {
+    let schema_message = IpcDataGenerator::default()
+        .schema_to_bytes_with_dictionary_tracker(
+            schema,
+            &mut DictionaryTracker::new(false),
+            &write_options(enable_lz4)?,
+        )
+        .ipc_message
+        .len();
+    Ok(align(8 + schema_message))
+}
+
+/// Upper bound on the messages `encode_stream` writes for `batch`: the
+/// record batch and the dictionaries it references.
+fn batch_message_bound(batch: &RecordBatch, enable_lz4: bool) -> usize {
+    let mut body = BodyBound::new(enable_lz4);
+    for column in batch.columns() {
+        body.array(&column.to_data());
+    }
+    let mut size = 0;
+    let mut pending = std::mem::take(&mut body.dictionaries);
+    while let Some(values) = pending.pop() {
+        let mut dictionary = BodyBound::new(enable_lz4);
+        dictionary.array(&values);
+        size += dictionary.message();
+        pending.append(&mut dictionary.dictionaries);
+    }
+    size + body.message()
+}
+
+/// Bytes of the end-of-stream marker.
+const END_OF_STREAM: usize = 8;
+
+/// Upper bound on the size of the IPC stream `encode_stream` writes for
+/// `batches`: the exact schema message, a bound per record batch and
+/// dictionary message from the buffer lengths, and the end-of-stream
+/// marker. With compression every buffer is counted uncompressed.
+pub fn estimate_stream_size(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    enable_lz4: bool,
+) -> Result<usize> {
+    Ok(schema_message_size(schema, enable_lz4)?
+        + END_OF_STREAM
+        + batches
+            .iter()
+            .map(|batch| batch_message_bound(batch, enable_lz4))
+            .sum::<usize>())
+}
+
+/// Encode `batches` as one IPC stream.
+///
+/// The output buffer is reserved at [`estimate_stream_size`], so writing
+/// does not grow it for the layouts the bound covers; a batch it undercounts
+/// costs a reallocation, not an error.
+pub fn encode_stream(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    enable_lz4: bool,
+) -> Result<Vec<u8>> {
+    let options = write_options(enable_lz4)?;
+    let capacity = estimate_stream_size(schema, batches, enable_lz4)?;
+    let mut buffer = Vec::with_capacity(capacity);
+    {
+        let mut writer = StreamWriter::try_new_with_options(&mut buffer, schema, options)
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        for batch in batches {
+            writer
+                .write(batch)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+    }
+    Ok(buffer)
+}
+
+/// Continuation marker preceding each message length in current IPC streams.
+const CONTINUATION_MARKER: u32 = 0xFFFF_FFFF;
+
+/// A message of an IPC stream, as described by its header.
+#[derive(Debug, Clone, Copy)]
+struct StreamMessage {
+    /// Offset of the message's continuation marker or length prefix.
+    start: usize,
+    header: arrow_ipc::MessageHeader,
+    /// Rows of a record batch; 0 for other messages.
+    rows: u64,
+    /// Body length the header claims.
+    body: u64,
+}
+
+fn parse_message(metadata: &[u8]) -> Result<arrow_ipc::Message<'_>> {
+    arrow_ipc::root_as_message(metadata)
+        .map_err(|e| ArrowWasmError::Ipc(format!("Invalid IPC message header: {e}")))
+}
+
+/// Rows of a record batch message; 0 for other messages.
+fn message_rows(message: &arrow_ipc::Message) -> u64 {
+    message
+        .header_as_record_batch()
+        .map_or(0, |batch| u64::try_from(batch.length()).unwrap_or(0))
+}
+
+/// Messages of an IPC stream, read from their headers alone; end-of-stream
+/// markers are skipped, so concatenated streams list their messages in
+/// order.
+///
+/// Bodies are skipped rather than validated, so a header may claim more
+/// bytes than the buffer holds; the walk stops there.
+fn stream_messages(data: &[u8]) -> Result<Vec<StreamMessage>> {
+    let mut messages = Vec::new();
+    let mut position = 0_usize;
+    let read_u32 = |at: usize| {
+        data.get(at..at + 4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    };
+
+    while let Some(mut word) = read_u32(position) {
+        let start = position;
+        position += 4;
+        if word == CONTINUATION_MARKER {
+            let Some(length) = read_u32(position) else {
+                break;
+            };
+            word = length;
+            position += 4;
+        }
+        if word == 0 {
+            // End-of-stream marker; a concatenated stream may follow.
+            continue;
+        }
+        let metadata = position
+            .checked_add(word as usize)
+            .and_then(|end| data.get(position..end))
+            .ok_or_else(|| ArrowWasmError::Ipc("Truncated IPC message header".to_string()))?;
+        let message = parse_message(metadata)?;
+        let body = u64::try_from(message.bodyLength()).unwrap_or(0);
+        messages.push(StreamMessage {
+            start,
+            header: message.header_type(),
+            rows: message_rows(&message),
+            body,
+        });
+        position = position
+            .saturating_add(word as usize)
+            .saturating_add(usize::try_from(body).unwrap_or(usize::MAX));
+    }
+    Ok(messages)
+}
+
+/// Rows and estimated decoded bytes of one record batch of IPC data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BatchExtent {
+    rows: u64,
+    bytes: u64,
+}
+
+/// `total + body`, failing when message headers claim more body bytes than
+/// a `u64` holds.
+fn add_body(total: u64, body: u64) -> Result<u64> {
+    total
+        .checked_add(body)
+        .ok_or_else(|| ArrowWasmError::Ipc("IPC message body lengths overflow".to_string()))
+}
+
+/// Extent of each record batch among `messages`, its bytes being the body
+/// length. Dictionary batches are charged to the record batch that
+/// follows them.
+fn batch_body_lengths(messages: &[StreamMessage]) -> Result<Vec<BatchExtent>> {
+    let mut extents = Vec::new();
+    let mut pending_dictionaries = 0_u64;
+    for message in messages {
+        match message.header {
+            arrow_ipc::MessageHeader::RecordBatch => {
+                extents.push(BatchExtent {
+                    rows: message.rows,
+                    bytes: add_body(message.body, pending_dictionaries)?,
+                });
+                pending_dictionaries = 0;
+            }
+            arrow_ipc::MessageHeader::DictionaryBatch => {
+                pending_dictionaries = add_body(pending_dictionaries, message.body)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(extents)
+}
+
+/// Bytes closing an IPC file after its footer: the footer length and the
+/// magic.
+const FILE_TRAILER: usize = 10;
+
+/// Extent of each record batch of an IPC file, from the footer blocks and
+/// the batch message headers. Every dictionary is charged to the first
+/// batch, as the reader decodes them all up front.
+fn file_body_lengths(data: &[u8]) -> Result<Vec<BatchExtent>> {
+    let invalid = |what: &str| ArrowWasmError::Ipc(format!("Invalid IPC file footer: {what}"));
+    let trailer = data
+        .len()
+        .checked_sub(FILE_TRAILER)
+        .filter(|&at| data[at + 4..] == *b"ARROW1")
+        .ok_or_else(|| invalid("missing trailing magic"))?;
+    let length = i32::from_le_bytes([
+        data[trailer],
+        data[trailer + 1],
+        data[trailer + 2],
+        data[trailer + 3],
+    ]);
+    let start = usize::try_from(length)
+        .ok()
+        .and_then(|length| trailer.checked_sub(length))
+        .ok_or_else(|| invalid("length out of range"))?;
+    let footer =
+        arrow_ipc::root_as_footer(&data[start..trailer]).map_err(|e| invalid(&e.to_string()))?;
+
+    let body = |block: &arrow_ipc::Block| u64::try_from(block.bodyLength()).unwrap_or(0);
+    let dictionaries = footer
+        .dictionaries()
+        .iter()
+        .flatten()
+        .try_fold(0, |total, block| add_body(total, body(block)))?;
+    let mut extents = footer
+        .recordBatches()
+        .iter()
+        .flatten()
+        .map(|block| {
+            let metadata = usize::try_from(block.offset())
+                .ok()
+                .zip(usize::try_from(block.metaDataLength()).ok())
+                .and_then(|(start, length)| data.get(start..start.checked_add(length)?))
+                .ok_or_else(|| invalid("record batch block out of range"))?;
+            let prefix = if metadata.starts_with(&CONTINUATION_MARKER.to_le_bytes()) {
+                8
+            } else {
+                4
+            };
+            let message = parse_message(metadata.get(prefix..).unwrap_or_default())?;
+            Ok(BatchExtent {
+                rows: message_rows(&message),
+                bytes: body(block),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(first) = extents.first_mut() {
+        first.bytes = add_body(first.bytes, dictionaries)?;
+    }
+    Ok(extents)
+}
+
+/// The leading `extents` a read of the first `rows` rows decodes, or all
+/// of them when `rows` is `None`.
+fn leading(extents: &[BatchExtent], rows: Option<u64>) -> &[BatchExtent] {
+    let Some(rows) = rows else {
+        return extents;
+    };
+    let mut before = 0_u64;
+    let count = extents
+        .iter()
+        .take_while(|extent| {
+            let needed = before < rows;
+            before = before.saturating_add(extent.rows);
+            needed
+        })
+        .count();
+    &extents[..count]
+}
+
+/// Fail with [`ArrowWasmError::TooLarge`] when the batches a read of the
+/// first `rows` rows of IPC `data` (every row when `None`) decodes are
+/// estimated not to fit in [`mem::memory_limit`]; `narrower` names the
+/// read to retry with.
+///
+/// Runs before any batch is decoded, from the message headers of a stream
+/// or the footer blocks of a file. Body lengths count as the headers claim
+/// them; for compressed data that is the compressed size, so the estimate
+/// is then a lower bound.
+pub fn check_ipc_fits(
+    data: &[u8],
+    format: FileFormat,
+    rows: Option<usize>,
+    narrower: &str,
+) -> Result<()> {
+    let extents = if format == FileFormat::IpcFile {
+        file_body_lengths(data)?
+    } else {
+        batch_body_lengths(&stream_messages(data)?)?
+    };
+    check_extents_fit(&extents, rows, narrower)
+}
+
+fn check_extents_fit(extents: &[BatchExtent], rows: Option<usize>, narrower: &str) -> Result<()> {
+    let parts: Vec<u64> = leading(extents, rows.map(|rows| rows as u64))
+        .iter()
+        .map(|extent| extent.bytes)
+        .collect();
+    mem::check_fits(&parts, mem::memory_limit(), "batches", narrower)
+}
+
+/// Decode every batch of an IPC stream, keeping each batch's own schema.
+///
+/// Buggy producers sometimes emit a second schema message mid-stream (or
+/// concatenate streams), which the reader rejects. The message headers
+/// are walked first, so each schema message starts a segment decoded with
+/// a reader of its own, and the segment's batches carry its schema. The
+/// returned schema is the one declared at the start of the stream.
+///
+/// The stream is first checked against the memory limit (see
+/// [`check_ipc_fits`]).
+pub fn read_stream_batches(data: &[u8]) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    if data.is_empty() {
+        return Err(ArrowWasmError::InvalidInput("Empty IPC stream".to_string()));
+    }
+    let messages = stream_messages(data)?;
+    check_extents_fit(
+        &batch_body_lengths(&messages)?,
+        None,
+        "read fewer rows with read_ipc_range",
+    )?;
+    let mut starts = vec![0];
+    starts.extend(
+        messages
+            .iter()
+            .filter(|message| message.header == arrow_ipc::MessageHeader::Schema)
+            .map(|message| message.start)
+            .filter(|&start| start > 0),
+    );
+    let mut stream_schema = None;
+    let mut batches = Vec::new();
+
+    for (index, &start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(data.len());
+        let mut reader = StreamReader::try_new(Cursor::new(&data[start..end]), None)
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        stream_schema.get_or_insert_with(|| reader.schema());
+        for batch in &mut reader {
+            batches.push(batch.map_err(|e| ArrowWasmError::Ipc(e.to_string()))?);
+        }
+        // Messages left in the segment follow an end-of-stream marker
+        // without a schema of their own.
+        let stopped = start + usize::try_from(reader.get_ref().position()).unwrap_or(usize::MAX);
+        if let Some(message) = messages
+            .iter()
+            .find(|message| (stopped..end).contains(&message.start))
+        {
+            return Err(ArrowWasmError::Ipc(format!(
+                "IPC stream has a {:?} message at byte {} after its end-of-stream marker",
+                message.header, message.start
+            )));
+        }
+    }
+
+    let schema = stream_schema
+        .ok_or_else(|| ArrowWasmError::InvalidInput("Empty IPC stream".to_string()))?;
+    Ok((schema, batches))
+}
+
+/// IPC format named `name`: `"file"` or `"stream"`, or `None` to detect it
+/// from the leading bytes of the data.
+pub fn parse_ipc_format(name: Option<&str>) -> Result<Option<FileFormat>> {
+    match name {
+        None => Ok(None),
+        Some("file") => Ok(Some(FileFormat::IpcFile)),
+        Some("stream") => Ok(Some(FileFormat::IpcStream)),
+        Some(other) => Err(ArrowWasmError::InvalidInput(format!(
+            "Unknown IPC format '{other}', expected 'file' or 'stream'"
+        ))),
+    }
+}
+
+/// Decode an Arrow IPC file or stream, as `format` says or else as
+/// [`FileFormat::detect`] finds from its leading bytes.
+pub fn read_ipc_batches(
+    data: &[u8],
+    format: Option<FileFormat>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    match format.unwrap_or_else(|| FileFormat::detect(data)) {
+        FileFormat::IpcStream => read_stream_batches(data),
+        FileFormat::IpcFile => {
+            check_ipc_fits(
+                data,
+                FileFormat::IpcFile,
+                None,
+                "read fewer rows with read_ipc_range",
+            )?;
+            let reader = FileReader::try_new(Cursor::new(data), None)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+            let schema = reader.schema();
+            let batches = reader
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+            Ok((schema, batches))
+        }
+        FileFormat::Parquet => Err(ArrowWasmError::InvalidInput(
+            "Data is a Parquet file, not Arrow IPC; read it with read_parquet_limit".to_string(),
+        )),
+    }
+}
+
+/// Decode an IPC file or stream and check that all batches share its
+/// schema.
+pub fn read_consistent_stream(
+    data: &[u8],
+    allow_schema_evolution: bool,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    read_consistent_ipc(data, None, allow_schema_evolution)
+}
+
+/// [`read_consistent_stream`] with the IPC format given rather than
+/// detected.
+pub fn read_consistent_ipc(
+    data: &[u8],
+    format: Option<FileFormat>,
+    allow_schema_evolution: bool,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let (schema, batches) = read_ipc_batches(data, format)?;
+    reconcile_batches(&schema, batches, allow_schema_evolution)
+}
+
+/// Read an Arrow IPC file or stream, optionally reconciling batches whose
+/// schema adds or omits fields relative to the stream schema.
+///
+/// Without `allow_schema_evolution` any mid-stream schema change fails with
+/// the batch index and the first differing field.
+#[wasm_bindgen]
+pub fn read_table_from_bytes_with_options(
+    data: &[u8],
+    allow_schema_evolution: bool,
+) -> std::result::Result<TableHandle, JsValue> {
+    Ok(read_table(data, None, allow_schema_evolution)?)
+}
+
+/// Table of the batches of an IPC file or stream in `format`, detected when
+/// `None`.
+pub fn read_table(
+    data: &[u8],
+    format: Option<FileFormat>,
+    allow_schema_evolution: bool,
+) -> Result<TableHandle> {
+    let (_, batches) = read_consistent_ipc(data, format, allow_schema_evolution)?;
+    if batches.is_empty() {
+        return Err(ArrowWasmError::InvalidInput(
+            "No record batches found in data".to_string(),
+        ));
+    }
+    mem::store_table(TableData::new(batches)?)
+}
+
+/// Result of [`read_table_from_bytes_with_coercion`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CoercedRead {
+    handle: TableHandle,
+    coerced_columns: Vec<String>,
+}
+
+/// Read an Arrow IPC file or stream, optionally treating the first batch's
+/// schema as authoritative and casting later batches to it.
+///
+/// Returns `{ handle, coercedColumns }`, listing the columns that needed a
+/// cast. Without `coerce_schema` this behaves like `read_table_from_bytes`
+/// and `coercedColumns` is empty.
+#[wasm_bindgen]
+pub fn read_table_from_bytes_with_coercion(
+    data: &[u8],
+    coerce_schema: bool,
+) -> std::result::Result<JsValue, JsValue> {
+    let (_, batches) = if coerce_schema {
+        read_ipc_batches(data, None)?
+    } else {
+        read_consistent_stream(data, false)?
+    };
+    let Some(authoritative) = batches.first().map(RecordBatch::schema) else {
+        return Err(
+            ArrowWasmError::InvalidInput("No record batches found in data".to_string()).into(),
+        );
+    };
+    let (batches, coerced_columns) = if coerce_schema {
+        coerce_batches(&authoritative, batches)?
+    } else {
+        (batches, Vec::new())
+    };
+    let handle = mem::store_table(TableData::new(batches)?)?;
+    let result = CoercedRead {
+        handle,
+        coerced_columns,
+    };
+    Ok(serde_wasm_bindgen::to_value(&result).map_err(ArrowWasmError::from)?)
+}
+
+/// Append the batches of an IPC file or stream to a table, returning a new
+/// table.
+///
+/// The incoming schema must match the table's unless
+/// `allow_schema_evolution` is set.
+#[wasm_bindgen]
+pub fn append_ipc(
+    handle: TableHandle,
+    data: &[u8],
+    allow_schema_evolution: bool,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let (_, incoming) = read_ipc_batches(data, None)?;
+    let batches = table.batches.into_iter().chain(incoming).collect();
+    let (_, batches) = reconcile_batches(&table.schema, batches, allow_schema_evolution)?;
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+/// `Write` sink that forwards bytes to a JS callback in bounded chunks.
+///
+/// Only one chunk is buffered in WASM memory at a time; each chunk is copied
+/// into a fresh `Uint8Array` before the callback runs, so JS may keep it.
+struct JsChunkWriter<'a> {
+    callback: &'a Function,
+    buffer: Vec<u8>,
+    written: usize,
+}
+
+impl<'a> JsChunkWriter<'a> {
+    fn new(callback: &'a Function) -> Self {
+        Self {
+            callback,
+            buffer: Vec::with_capacity(CHUNK_BYTES),
+            written: 0,
+        }
+    }
+
+    fn emit(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Uint8Array::from(self.buffer.as_slice());
+        self.callback
+            .call1(&JsValue::NULL, &chunk)
+            .map_err(|e| io::Error::other(format!("onChunk callback failed: {e:?}")))?;
+        self.written += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl Write for JsChunkWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // Hand over what is buffered first if `data` would push it past the
+        // chunk size, so only a single oversized write makes a larger chunk.
+        if self.buffer.len() + data.len() > CHUNK_BYTES {
+            self.emit()?;
+        }
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.emit()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.emit()
+    }
+}
+
+/// Serialize a table as an IPC stream, passing the bytes to `on_chunk` as
+/// they are produced instead of building one large buffer.
+///
+/// `on_chunk` receives `Uint8Array` pieces of up to 64 KiB (a single batch
+/// body larger than that arrives as one chunk of its own); concatenated in
+/// call order they form the same stream `write_table_to_ipc` returns.
+/// Returns the total byte count.
+#[wasm_bindgen]
+pub fn write_table_to_ipc_streaming(
+    handle: TableHandle,
+    enable_lz4: bool,
+    on_chunk: &Function,
+) -> std::result::Result<usize, JsValue> {
+    let table = mem::get_table(handle)?;
+    let options = write_options(enable_lz4)?;
+
+    let mut sink = JsChunkWriter::new(on_chunk);
+    {
+        let mut writer = StreamWriter::try_new_with_options(&mut sink, &table.schema, options)
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        for batch in &table.batches {
+            writer
+                .write(batch)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+    }
+    sink.flush().map_err(ArrowWasmError::from)?;
+    Ok(sink.written)
+}
+
+/// One self-contained IPC stream per batch of `table`; a table without
+/// batches gives a single schema-only stream.
+pub fn encode_batch_streams(table: &TableData, enable_lz4: bool) -> Result<Vec<Vec<u8>>> {
+    if table.batches.is_empty() {
+        return Ok(vec![encode_stream(&table.schema, &[], enable_lz4)?]);
+    }
+    table
+        .batches
+        .iter()
+        .map(|batch| encode_stream(&table.schema, std::slice::from_ref(batch), enable_lz4))
+        .collect()
+}
+
+/// Serialize a table as an array of IPC streams, one per record batch.
+///
+/// Each `Uint8Array` is a complete stream (schema, dictionaries, one batch
+/// and the end-of-stream marker), so it can be handed to Arrow JS
+/// `RecordBatchReader.from` on its own as it arrives. Concatenated in order
+/// they read back as the whole table with `read_table_from_bytes`.
+#[wasm_bindgen]
+pub fn write_table_to_ipc_batches(
+    handle: TableHandle,
+    enable_lz4: bool,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    let table = mem::get_table(handle)?;
+    let result = js_sys::Array::new();
+    for stream in encode_batch_streams(&table, enable_lz4)? {
+        result.push(&Uint8Array::from(stream.as_slice()));
+    }
+    Ok(result)
+}
+
+/// Group the rows of `batches` into shards of at most `max_rows` rows,
+/// slicing batches that straddle a shard boundary.
+fn shard_batches(batches: &[RecordBatch], max_rows: usize) -> Vec<Vec<RecordBatch>> {
+    let mut shards = Vec::new();
+    let mut shard: Vec<RecordBatch> = Vec::new();
+    let mut shard_rows = 0;
+    for batch in batches {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let len = (max_rows - shard_rows).min(batch.num_rows() - offset);
+            shard.push(batch.slice(offset, len));
+            shard_rows += len;
+            offset += len;
+            if shard_rows == max_rows {
+                shards.push(std::mem::take(&mut shard));
+                shard_rows = 0;
+            }
+        }
+    }
+    if shard_rows > 0 || shards.is_empty() {
+        shards.push(shard);
+    }
+    shards
+}
+
+/// Serialize a table as an array of IPC streams holding at most
+/// `max_rows_per_shard` rows each, so no single buffer has to hold the
+/// whole table.
+///
+/// Each `Uint8Array` is a complete stream with the table schema; shards are
+/// zero-copy slices of the table's batches, taken in row order, and every
+/// shard but the last holds exactly `max_rows_per_shard` rows. Read back
+/// with `read_table_from_bytes` and `concat_tables`. A table without rows
+/// gives a single schema-only stream.
+#[wasm_bindgen]
+pub fn write_table_to_ipc_sharded(
+    handle: TableHandle,
+    max_rows_per_shard: usize,
+    enable_lz4: bool,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    if max_rows_per_shard == 0 {
+        return Err(
+            ArrowWasmError::InvalidInput("maxRowsPerShard must be at least 1".to_string()).into(),
+        );
+    }
+    let table = mem::get_table(handle)?;
+    let result = js_sys::Array::new();
+    for shard in shard_batches(&table.batches, max_rows_per_shard) {
+        let stream = encode_stream(&table.schema, &shard, enable_lz4)?;
+        result.push(&Uint8Array::from(stream.as_slice()));
+    }
+    Ok(result)
+}
+
+/// Options for [`estimate_ipc_size`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct EstimateOptions {
+    enable_lz4: bool,
+}
+
+/// Upper bound on the bytes `write_table_to_ipc` produces for a table, e.g.
+/// to pre-allocate a transfer buffer.
+///
+/// `options` is `{enableLz4?}`. The bound is computed from buffer lengths
+/// without serializing; it is within a few hundred bytes per batch of the
+/// actual size, plus any dictionaries repeated across batches. Compressed
+/// buffers are counted at their uncompressed size, so with `enableLz4` the
+/// actual size is usually well below it.
+#[wasm_bindgen]
+pub fn estimate_ipc_size(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<usize, JsValue> {
+    let options: EstimateOptions = if options.is_undefined() || options.is_null() {
+        EstimateOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let table = mem::get_table(handle)?;
+    Ok(estimate_stream_size(
+        &table.schema,
+        &table.batches,
+        options.enable_lz4,
+    )?)
+}
+
+/// Options for [`write_table_to_ipc_with_options`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct WriteOptions {
+    enable_lz4: bool,
+    max_rows_per_batch: Option<usize>,
+    coalesce_small_batches: bool,
+}
+
+/// Re-chunk `batches` for writing: batches longer than `max_rows` are split
+/// into zero-copy slices, and with `coalesce` consecutive small batches are
+/// merged up to `max_rows` (or into one batch when there is no limit).
+fn rebatch(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    max_rows: Option<usize>,
+    coalesce: bool,
+) -> Result<Vec<RecordBatch>> {
+    let limit = max_rows.unwrap_or(usize::MAX);
+    if limit == 0 {
+        return Err(ArrowWasmError::InvalidInput(
+            "maxRowsPerBatch must be at least 1".to_string(),
+        ));
+    }
+    let mut slices = Vec::new();
+    for batch in batches {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let len = limit.min(batch.num_rows() - offset);
+            slices.push(batch.slice(offset, len));
+            offset += len;
+        }
+    }
+    if !coalesce {
+        return Ok(slices);
+    }
+
+    let mut result = Vec::new();
+    let mut pending: Vec<RecordBatch> = Vec::new();
+    let mut pending_rows = 0;
+    for slice in slices {
+        if pending_rows + slice.num_rows() > limit {
+            result.push(concat_batches(schema, &pending)?);
+            pending.clear();
+            pending_rows = 0;
+        }
+        pending_rows += slice.num_rows();
+        pending.push(slice);
+    }
+    if !pending.is_empty() {
+        result.push(concat_batches(schema, &pending)?);
+    }
+    Ok(result)
+}
+
+/// Serialize a table as an IPC stream with control over the batch layout.
+///
+/// `options` is `{enableLz4?, maxRowsPerBatch?, coalesceSmallBatches?}`.
+/// Batches longer than `maxRowsPerBatch` are split and, with
+/// `coalesceSmallBatches`, consecutive smaller ones are merged up to the
+/// limit. The registered table is not modified. Returns
+/// `{bytes, batches}` where `batches` lists the written batch row counts.
+#[wasm_bindgen]
+pub fn write_table_to_ipc_with_options(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<JsValue, JsValue> {
+    let options: WriteOptions = if options.is_undefined() || options.is_null() {
+        WriteOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let table = mem::get_table(handle)?;
+    let batches = rebatch(
+        &table.schema,
+        &table.batches,
+        options.max_rows_per_batch,
+        options.coalesce_small_batches,
+    )?;
+    let bytes = encode_stream(&table.schema, &batches, options.enable_lz4)?;
+
+    let layout = js_sys::Array::new();
+    for batch in &batches {
+        layout.push(&JsValue::from_f64(batch.num_rows() as f64));
+    }
+    let result = js_sys::Object::new();
+    set_property(&result, "bytes", &Uint8Array::from(bytes.as_slice()))?;
+    set_property(&result, "batches", &layout)?;
+    Ok(result.into())
+}
+
+/// Schema metadata key holding a [`ChunkInfo`] on streams written by
+/// [`export_chunked_ipc`].
+pub const CHUNKED_IPC: &str = "chunked_ipc";
+
+/// Position of a chunk written by [`export_chunked_ipc`], stored as JSON
+/// under [`CHUNKED_IPC`]. Continuation chunks carry it once, in chunk 0.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkInfo {
+    index: u64,
+    count: u64,
+    standalone: bool,
+}
+
+impl ChunkInfo {
+    /// `schema` with this chunk's position added to its metadata.
+    fn annotate(&self, schema: &SchemaRef) -> Result<SchemaRef> {
+        let mut metadata = schema.metadata().clone();
+        metadata.insert(CHUNKED_IPC.to_string(), serde_json::to_string(self)?);
+        Ok(Arc::new(schema.as_ref().clone().with_metadata(metadata)))
+    }
+
+    /// The position recorded on `schema`, if it came from a chunked export.
+    fn of(schema: &SchemaRef) -> Result<Option<Self>> {
+        schema
+            .metadata()
+            .get(CHUNKED_IPC)
+            .map(|value| {
+                serde_json::from_str(value).map_err(|e| {
+                    ArrowWasmError::Ipc(format!("Invalid {CHUNKED_IPC} metadata: {e}"))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Options for [`export_chunked_ipc`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ChunkedExportOptions {
+    max_chunk_bytes: usize,
+    #[serde(default)]
+    include_schema_in_each_chunk: bool,
+    #[serde(default)]
+    enable_lz4: bool,
+}
+
+/// Group the rows of `batches` into chunks whose encoded size stays within
+/// `max_bytes`, slicing batches at row boundaries.
+///
+/// `overhead(i)` is the bytes chunk `i` spends besides its batches (schema
+/// message, end-of-stream marker). Sizes are [`batch_message_bound`]s, so
+/// the written chunks are at most as large as planned. Fails naming the
+/// first row that does not fit in a chunk on its own.
+fn plan_chunks(
+    batches: &[RecordBatch],
+    max_bytes: usize,
+    enable_lz4: bool,
+    overhead: impl Fn(usize) -> usize,
+) -> Result<Vec<Vec<RecordBatch>>> {
+    let mut chunks = Vec::new();
+    let mut chunk: Vec<RecordBatch> = Vec::new();
+    let mut used = overhead(0);
+    let mut first_row = 0;
+    for batch in batches {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let room = max_bytes.saturating_sub(used);
+            let fits = |len| batch_message_bound(&batch.slice(offset, len), enable_lz4) <= room;
+            // Bounds grow with the slice length, so search for the longest
+            // slice that fits.
+            let (mut low, mut high) = (0, batch.num_rows() - offset);
+            if !fits(high) {
+                while low + 1 < high {
+                    let middle = low + (high - low) / 2;
+                    if fits(middle) {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                high = low;
+            }
+            if high == 0 {
+                if chunk.is_empty() {
+                    let size = used + batch_message_bound(&batch.slice(offset, 1), enable_lz4);
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "Row {} needs {size} bytes as a chunk of its own, more than maxChunkBytes {max_bytes}",
+                        first_row + offset
+                    )));
+                }
+                chunks.push(std::mem::take(&mut chunk));
+                used = overhead(chunks.len());
+                continue;
+            }
+            let slice = batch.slice(offset, high);
+            used += batch_message_bound(&slice, enable_lz4);
+            chunk.push(slice);
+            offset += high;
+        }
+        first_row += batch.num_rows();
+    }
+    if !chunk.is_empty() || chunks.is_empty() {
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+/// Encode `table` as IPC chunks of at most `max_bytes` each, aligned to
+/// whole rows.
+///
+/// With `standalone` every chunk is a complete stream; otherwise the
+/// chunks concatenate to one stream whose schema is in chunk 0 and whose
+/// end-of-stream marker is in the last chunk.
+pub fn encode_chunks(
+    table: &TableData,
+    max_bytes: usize,
+    standalone: bool,
+    enable_lz4: bool,
+) -> Result<Vec<Vec<u8>>> {
+    let widest = ChunkInfo {
+        index: u64::MAX,
+        count: u64::MAX,
+        standalone: false,
+    };
+    let schema_size = schema_message_size(&widest.annotate(&table.schema)?, enable_lz4)?;
+    let chunks = plan_chunks(&table.batches, max_bytes, enable_lz4, |index| {
+        if standalone || index == 0 {
+            schema_size + END_OF_STREAM
+        } else {
+            END_OF_STREAM
+        }
+    })?;
+    let count = chunks.len() as u64;
+
+    if standalone {
+        return chunks
+            .iter()
+            .zip(0..)
+            .map(|(batches, index)| {
+                let info = ChunkInfo {
+                    index,
+                    count,
+                    standalone,
+                };
+                encode_stream(&info.annotate(&table.schema)?, batches, enable_lz4)
+            })
+            .collect();
+    }
+
+    let info = ChunkInfo {
+        index: 0,
+        count,
+        standalone,
+    };
+    let schema = info.annotate(&table.schema)?;
+    let mut buffer = Vec::new();
+    let mut ends = Vec::with_capacity(chunks.len());
+    {
+        let mut writer =
+            StreamWriter::try_new_with_options(&mut buffer, &schema, write_options(enable_lz4)?)
+                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+        for batches in &chunks {
+            for batch in batches {
+                writer
+                    .write(batch)
+                    .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+            }
+            ends.push(writer.get_ref().len());
+        }
+        writer
+            .finish()
+            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
+    }
+    if let Some(last) = ends.last_mut() {
+        *last = buffer.len();
+    }
+    let mut start = 0;
+    Ok(ends
+        .into_iter()
+        .map(|end| {
+            let chunk = buffer[start..end].to_vec();
+            start = end;
+            chunk
+        })
+        .collect())
+}
+
+/// Serialize a table as IPC chunks of at most `maxChunkBytes` bytes, e.g.
+/// to persist it as `IndexedDB` records.
+///
+/// `options` is `{maxChunkBytes, includeSchemaInEachChunk?, enableLz4?}`.
+/// Chunk boundaries fall between rows, so every prefix of the chunks
+/// restores a valid table. With `includeSchemaInEachChunk` each
+/// `Uint8Array` is a complete stream readable on its own with
+/// `read_table_from_bytes`; otherwise the schema is only in chunk 0 and
+/// the chunks concatenate to one stream. Either way the schema carries
+/// the chunk position under the `chunked_ipc` metadata key. Fails when a
+/// single row does not fit in `maxChunkBytes`, naming the row and the
+/// bytes it needs. Read back with `import_chunked_ipc`.
+#[wasm_bindgen]
+pub fn export_chunked_ipc(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    let options: ChunkedExportOptions =
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?;
+    let table = mem::get_table(handle)?;
+    let result = js_sys::Array::new();
+    for chunk in encode_chunks(
+        &table,
+        options.max_chunk_bytes,
+        options.include_schema_in_each_chunk,
+        options.enable_lz4,
+    )? {
+        result.push(&Uint8Array::from(chunk.as_slice()));
+    }
+    Ok(result)
+}
+
+/// Restore a table from the leading `chunks` of an [`encode_chunks`]
+/// export, returning it with whether trailing chunks were missing.
+///
+/// The [`CHUNKED_IPC`] metadata key is removed from the restored schema.
+pub fn decode_chunks(chunks: &[Vec<u8>]) -> Result<(TableData, bool)> {
+    let first = chunks
+        .first()
+        .ok_or_else(|| ArrowWasmError::InvalidInput("No chunks to import".to_string()))?;
+    let (schema, _) = read_stream_batches(first)?;
+    let info = ChunkInfo::of(&schema)?.ok_or_else(|| {
+        ArrowWasmError::InvalidInput(format!(
+            "Chunk 0 has no {CHUNKED_IPC} metadata; it was not written by export_chunked_ipc"
+        ))
+    })?;
+
+    let mut batches = Vec::new();
+    if info.standalone {
+        for (chunk, index) in chunks.iter().zip(0_u64..) {
+            let (schema, chunk_batches) = read_stream_batches(chunk)?;
+            match ChunkInfo::of(&schema)? {
+                Some(found) if found.index == index && found.count == info.count => {}
+                Some(found) => {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "Chunk {index} is chunk {} of {}, expected chunk {index} of {}",
+                        found.index, found.count, info.count
+                    )))
+                }
+                None => {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "Chunk {index} has no {CHUNKED_IPC} metadata"
+                    )))
+                }
+            }
+            batches.extend(chunk_batches);
+        }
+    } else {
+        let (_, stream_batches) = read_stream_batches(&chunks.concat())?;
+        batches = stream_batches;
+    }
+    let received = chunks.len() as u64;
+    if received > info.count {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Got {received} chunks, but the export wrote {}",
+            info.count
+        )));
+    }
+
+    let mut metadata = schema.metadata().clone();
+    metadata.remove(CHUNKED_IPC);
+    let schema = Arc::new(schema.as_ref().clone().with_metadata(metadata));
+    let mut batches = batches
+        .into_iter()
+        .map(|batch| {
+            let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+            RecordBatch::try_new_with_options(
+                Arc::clone(&schema),
+                batch.columns().to_vec(),
+                &options,
+            )
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(Arc::clone(&schema)));
+    }
+    Ok((TableData { batches, schema }, received < info.count))
+}
+
+/// Result of [`import_chunked_ipc`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChunkedImport {
+    handle: TableHandle,
+    truncated: bool,
+}
+
+/// Restore a table from the `Uint8Array` chunks of `export_chunked_ipc`.
+///
+/// Returns `{ handle, truncated }`. Trailing chunks may be missing, e.g.
+/// after a partial restore: the table then holds the rows of the chunks
+/// given and `truncated` is set. Chunks must be passed in order starting
+/// at chunk 0.
+#[wasm_bindgen]
+pub fn import_chunked_ipc(chunks: js_sys::Array) -> std::result::Result<JsValue, JsValue> {
+    let chunks: Vec<Vec<u8>> = chunks
+        .iter()
+        .map(|chunk| Uint8Array::new(&chunk).to_vec())
+        .collect();
+    let (table, truncated) = decode_chunks(&chunks)?;
+    let result = ChunkedImport {
+        handle: mem::store_table(table)?,
+        truncated,
+    };
+    Ok(serde_wasm_bindgen::to_value(&result).map_err(ArrowWasmError::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use arrow::ipc::writer::FileWriter;
+
+    const GIB: u64 = 1 << 30;
+
+    fn ids(values: &[i32]) -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
+        let column: ArrayRef = Arc::new(Int32Array::from(values.to_vec()));
+        RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap()
+    }
+
+    /// `ids` plus a `label` field, as a producer adding a column would send.
+    fn labelled(values: &[i32]) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("label", DataType::Utf8, true),
+        ]);
+        let labels: Vec<String> = values.iter().map(|value| format!("#{value}")).collect();
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from(values.to_vec())),
+            Arc::new(StringArray::from(labels)),
+        ];
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    fn stream(batches: &[RecordBatch]) -> Vec<u8> {
+        encode_stream(&batches[0].schema(), batches, false).unwrap()
+    }
+
+    fn row_counts(batches: &[RecordBatch]) -> Vec<usize> {
+        batches.iter().map(RecordBatch::num_rows).collect()
+    }
+
+    #[test]
+    fn concatenated_streams_keep_their_schemas() {
+        let data = [
+            stream(&[ids(&[1, 2])]),
+            stream(&[labelled(&[3]), labelled(&[4, 5])]),
+        ]
+        .concat();
+        let (schema, batches) = read_stream_batches(&data).unwrap();
+        assert_eq!(schema, ids(&[]).schema());
+        assert_eq!(row_counts(&batches), [2, 1, 2]);
+        assert_eq!(batches[0].schema(), ids(&[]).schema());
+        assert_eq!(batches[2].schema(), labelled(&[]).schema());
+    }
+
+    #[test]
+    fn schema_message_mid_stream_starts_a_segment() {
+        // The first stream loses its end-of-stream marker, so the second
+        // schema message arrives where a record batch was expected.
+        let mut data = stream(&[ids(&[1])]);
+        data.truncate(data.len() - END_OF_STREAM);
+        data.extend(stream(&[labelled(&[2, 3])]));
+        let (_, batches) = read_stream_batches(&data).unwrap();
+        assert_eq!(row_counts(&batches), [1, 2]);
+        assert_eq!(batches[1].num_columns(), 2);
+    }
+
+    #[test]
+    fn strict_reads_fail_on_the_changed_batch() {
+        let data = [stream(&[ids(&[1]), ids(&[2])]), stream(&[labelled(&[3])])].concat();
+        let Err(ArrowWasmError::InvalidInput(message)) = read_consistent_stream(&data, false)
+        else {
+            panic!("a strict read of a changed schema must fail");
+        };
+        assert!(message.starts_with("Batch 2 schema differs"), "{message}");
+        assert!(message.contains("label"), "{message}");
+
+        let (schema, batches) = read_consistent_stream(&data, true).unwrap();
+        assert_eq!(schema.fields().len(), 2);
+        assert!(schema.field(1).is_nullable());
+        assert_eq!(batches[0].column(1).null_count(), 1);
+        assert_eq!(row_counts(&batches), [1, 1, 1]);
+    }
+
+    #[test]
+    fn batches_after_the_end_marker_need_a_schema() {
+        let second = stream(&[ids(&[2])]);
+        let schema_size = schema_message_size(&ids(&[]).schema(), false).unwrap();
+        let data = [stream(&[ids(&[1])]), second[schema_size..].to_vec()].concat();
+        let Err(ArrowWasmError::Ipc(message)) = read_stream_batches(&data) else {
+            panic!("a batch without a schema must fail");
+        };
+        assert!(
+            message.contains("after its end-of-stream marker"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn trailing_padding_and_end_markers_are_ignored() {
+        let mut data = stream(&[ids(&[1, 2, 3])]);
+        data.extend([0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+        data.extend([0; 16]);
+        let (_, batches) = read_stream_batches(&data).unwrap();
+        assert_eq!(row_counts(&batches), [3]);
+        assert!(matches!(
+            read_stream_batches(&[]),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+    }
+
+    /// A record batch message header claiming `rows` rows and a body of
+    /// `body` bytes, with no body after it.
+    fn batch_header(rows: i64, body: i64) -> Vec<u8> {
+        let mut builder = flatbuffers::FlatBufferBuilder::new();
+        let mut batch = arrow_ipc::RecordBatchBuilder::new(&mut builder);
+        batch.add_length(rows);
+        let batch = batch.finish();
+        let mut message = arrow_ipc::MessageBuilder::new(&mut builder);
+        message.add_version(arrow_ipc::MetadataVersion::V5);
+        message.add_header_type(arrow_ipc::MessageHeader::RecordBatch);
+        message.add_header(batch.as_union_value());
+        message.add_bodyLength(body);
+        let message = message.finish();
+        builder.finish(message, None);
+        let header = builder.finished_data();
+        let mut data = CONTINUATION_MARKER.to_le_bytes().to_vec();
+        data.extend(u32::try_from(header.len()).unwrap().to_le_bytes());
+        data.extend(header);
+        data
+    }
+
+    /// An IPC file of `batches` whose footer claims `body` bytes for every
+    /// record batch.
+    fn file_claiming(batches: &[RecordBatch], body: i64) -> Vec<u8> {
+        file_with_blocks(batches, |block| {
+            arrow_ipc::Block::new(block.offset(), block.metaDataLength(), body)
+        })
+    }
+
+    /// An IPC file of `batches` whose footer record batch blocks are
+    /// replaced with `patch` of them.
+    fn file_with_blocks(
+        batches: &[RecordBatch],
+        patch: impl Fn(&arrow_ipc::Block) -> arrow_ipc::Block,
+    ) -> Vec<u8> {
+        let mut data = file(batches);
+        let trailer = data.len() - FILE_TRAILER;
+        let length = u32::from_le_bytes(data[trailer..trailer + 4].try_into().unwrap());
+        let start = trailer - length as usize;
+        let footer = arrow_ipc::root_as_footer(&data[start..trailer]).unwrap();
+        let blocks: Vec<arrow_ipc::Block> =
+            footer.recordBatches().unwrap().iter().copied().collect();
+        for block in blocks {
+            let claimed = patch(&block);
+            let at = data[start..trailer]
+                .windows(24)
+                .position(|window| window == block.0)
+                .unwrap();
+            data[start + at..start + at + 24].copy_from_slice(&claimed.0);
+        }
+        data
+    }
+
+    fn fit(extents: &[BatchExtent], rows: Option<usize>, limit: u64) -> Result<()> {
+        let parts: Vec<u64> = leading(extents, rows.map(|rows| rows as u64))
+            .iter()
+            .map(|extent| extent.bytes)
+            .collect();
+        mem::check_fits(&parts, limit, "batches", "read fewer rows")
+    }
+
+    #[test]
+    fn stream_header_claiming_gigabytes_is_too_large() {
+        let schema = ids(&[]).schema();
+        let mut data = stream(&[ids(&[1, 2]), ids(&[3])]);
+        data.truncate(data.len() - END_OF_STREAM);
+        data.extend(batch_header(1_000, (5 * GIB).cast_signed()));
+        let extents = batch_body_lengths(&stream_messages(&data).unwrap()).unwrap();
+        assert_eq!(extents.len(), 3);
+        assert_eq!(
+            extents[2],
+            BatchExtent {
+                rows: 1_000,
+                bytes: 5 * GIB
+            }
+        );
+
+        let Err(ArrowWasmError::TooLarge {
+            estimate,
+            limit,
+            hint,
+        }) = fit(&extents, None, 4 * GIB)
+        else {
+            panic!("a 5 GiB batch must not fit in 4 GiB");
+        };
+        assert_eq!(limit, 4 * GIB);
+        assert_eq!(estimate, 5 * GIB + extents[0].bytes + extents[1].bytes);
+        assert!(hint.starts_with("the first 2 of 3 batches"), "{hint}");
+        // A range ending in the second batch never decodes the third.
+        assert!(fit(&extents, Some(3), 4 * GIB).is_ok());
+
+        let alone = [
+            schema_message(&schema),
+            batch_header(10, (5 * GIB).cast_signed()),
+        ]
+        .concat();
+        let extents = batch_body_lengths(&stream_messages(&alone).unwrap()).unwrap();
+        let Err(ArrowWasmError::TooLarge { hint, .. }) = fit(&extents, Some(1), 4 * GIB) else {
+            panic!("a 5 GiB batch must not fit in 4 GiB");
+        };
+        assert!(hint.starts_with("even the first of 1 batches"), "{hint}");
+    }
+
+    fn schema_message(schema: &SchemaRef) -> Vec<u8> {
+        let data = encode_stream(schema, &[], false).unwrap();
+        data[..data.len() - END_OF_STREAM].to_vec()
+    }
+
+    #[test]
+    fn file_footer_claiming_gigabytes_is_too_large() {
+        let batches = [ids(&[1, 2]), ids(&[3]), ids(&[4, 5, 6])];
+        let data = file_claiming(&batches, (2 * GIB).cast_signed());
+        let extents = file_body_lengths(&data).unwrap();
+        let rows: Vec<u64> = extents.iter().map(|extent| extent.rows).collect();
+        assert_eq!(rows, [2, 1, 3]);
+        assert!(extents.iter().all(|extent| extent.bytes == 2 * GIB));
+
+        let Err(ArrowWasmError::TooLarge { estimate, hint, .. }) = fit(&extents, None, 5 * GIB)
+        else {
+            panic!("6 GiB of batches must not fit in 5 GiB");
+        };
+        assert_eq!(estimate, 6 * GIB);
+        assert!(
+            hint.starts_with(&format!("the first 2 of 3 batches ({} bytes)", 4 * GIB)),
+            "{hint}"
+        );
+        assert!(fit(&extents, Some(3), 5 * GIB).is_ok());
+        assert!(fit(&extents, Some(4), 5 * GIB).is_err());
+
+        // The file read checks the footer before the reader touches a batch.
+        let data = file_with_blocks(&batches, |block| {
+            arrow_ipc::Block::new(i64::MAX, block.metaDataLength(), block.bodyLength())
+        });
+        let Err(ArrowWasmError::Ipc(message)) = read_ipc_batches(&data, None) else {
+            panic!("a block past the end of the file must fail");
+        };
+        assert!(message.contains("block out of range"), "{message}");
+    }
+
+    #[test]
+    fn file_extents_match_the_written_bodies() {
+        let batches = [ids(&[1, 2]), ids(&[3])];
+        let data = file(&batches);
+        let extents = file_body_lengths(&data).unwrap();
+        let stream_extents =
+            batch_body_lengths(&stream_messages(&stream(&batches)).unwrap()).unwrap();
+        assert_eq!(extents, stream_extents);
+
+        assert!(matches!(
+            file_body_lengths(&data[..data.len() - 1]),
+            Err(ArrowWasmError::Ipc(_))
+        ));
+        assert!(matches!(
+            file_body_lengths(&stream(&batches)),
+            Err(ArrowWasmError::Ipc(_))
+        ));
+    }
+
+    #[test]
+    fn body_length_overflow_is_an_ipc_error() {
+        let dictionary = |body| StreamMessage {
+            start: 0,
+            header: arrow_ipc::MessageHeader::DictionaryBatch,
+            rows: 0,
+            body,
+        };
+        let batch = StreamMessage {
+            start: 0,
+            header: arrow_ipc::MessageHeader::RecordBatch,
+            rows: 1,
+            body: 1,
+        };
+        let max = i64::MAX.cast_unsigned();
+        let messages = [dictionary(max), dictionary(max), dictionary(max), batch];
+        let Err(ArrowWasmError::Ipc(message)) = batch_body_lengths(&messages) else {
+            panic!("overflowing body lengths must fail");
+        };
+        assert!(message.contains("overflow"), "{message}");
+
+        let messages = [dictionary(max), dictionary(max), batch];
+        let extents = batch_body_lengths(&messages).unwrap();
+        assert_eq!(extents[0].bytes, 2 * max + 1);
+    }
+
+    #[test]
+    fn leading_extents_cover_the_requested_rows() {
+        let extents = [2, 3, 4].map(|rows| BatchExtent {
+            rows,
+            bytes: rows * 10,
+        });
+        for (rows, count) in [
+            (None, 3),
+            (Some(0), 0),
+            (Some(1), 1),
+            (Some(2), 1),
+            (Some(3), 2),
+            (Some(5), 2),
+            (Some(6), 3),
+            (Some(100), 3),
+        ] {
+            assert_eq!(leading(&extents, rows).len(), count, "{rows:?} rows");
+        }
+    }
+
+    fn id_values(batches: &[RecordBatch]) -> Vec<i32> {
+        use arrow::array::AsArray;
+        use arrow::datatypes::Int32Type;
+        batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rebatch_splits_long_batches() {
+        let batches = [ids(&[1, 2, 3, 4, 5]), ids(&[6]), ids(&[])];
+        let schema = batches[0].schema();
+        let split = rebatch(&schema, &batches, Some(2), false).unwrap();
+        assert_eq!(row_counts(&split), [2, 2, 1, 1]);
+        assert_eq!(id_values(&split), [1, 2, 3, 4, 5, 6]);
+
+        let unchanged = rebatch(&schema, &batches, None, false).unwrap();
+        assert_eq!(row_counts(&unchanged), [5, 1]);
+    }
+
+    #[test]
+    fn rebatch_coalesces_up_to_the_limit() {
+        let batches = [ids(&[1]), ids(&[2, 3]), ids(&[4, 5, 6, 7, 8]), ids(&[9])];
+        let schema = batches[0].schema();
+        let merged = rebatch(&schema, &batches, Some(4), true).unwrap();
+        assert_eq!(row_counts(&merged), [3, 4, 2]);
+        assert_eq!(id_values(&merged), (1..=9).collect::<Vec<_>>());
+
+        let whole = rebatch(&schema, &batches, None, true).unwrap();
+        assert_eq!(row_counts(&whole), [9]);
+        assert!(rebatch(&schema, &[], None, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rebatch_rejects_a_zero_limit() {
+        let batches = [ids(&[1])];
+        let Err(ArrowWasmError::InvalidInput(message)) =
+            rebatch(&batches[0].schema(), &batches, Some(0), false)
+        else {
+            panic!("a zero row limit cannot make progress");
+        };
+        assert_eq!(message, "maxRowsPerBatch must be at least 1");
+    }
+
+    #[test]
+    fn view_columns_round_trip() {
+        let batches = crate::samples::sample_batches("views").unwrap();
+        let schema = batches[0].schema();
+        for enable_lz4 in [false, true] {
+            let encoded = encode_stream(&schema, &batches, enable_lz4).unwrap();
+            assert!(encoded.len() <= estimate_stream_size(&schema, &batches, enable_lz4).unwrap());
+            assert_eq!(
+                read_stream_batches(&encoded).unwrap(),
+                (Arc::clone(&schema), batches.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn shards_hold_max_rows_except_the_last() {
+        let batches = [ids(&[1, 2, 3]), ids(&[]), ids(&[4, 5, 6, 7]), ids(&[8])];
+        let shards = shard_batches(&batches, 3);
+        let rows: Vec<Vec<usize>> = shards.iter().map(|shard| row_counts(shard)).collect();
+        assert_eq!(rows, [vec![3], vec![3], vec![1, 1]]);
+        let values: Vec<i32> = shards.iter().flat_map(|shard| id_values(shard)).collect();
+        assert_eq!(values, (1..=8).collect::<Vec<_>>());
+
+        assert_eq!(shard_batches(&batches, 8).len(), 1);
+        assert_eq!(shard_batches(&batches, 4).len(), 2);
+    }
+
+    #[test]
+    fn shards_share_the_source_buffers() {
+        use arrow::array::Array;
+        let batches = [ids(&[1, 2, 3, 4])];
+        let shards = shard_batches(&batches, 3);
+        let source = batches[0].column(0).to_data();
+        for shard in &shards {
+            let data = shard[0].column(0).to_data();
+            assert_eq!(data.buffers()[0].data_ptr(), source.buffers()[0].data_ptr());
+        }
+    }
+
+    #[test]
+    fn rowless_tables_give_one_empty_shard() {
+        assert_eq!(shard_batches(&[], 2), [Vec::<RecordBatch>::new()]);
+        let shards = shard_batches(&[ids(&[])], 2);
+        assert_eq!(shards.len(), 1);
+        assert!(shards[0].is_empty());
+    }
+
+    /// One column of each layout `BodyBound` distinguishes, `rows` long.
+    fn mixed(rows: usize) -> RecordBatch {
+        use arrow::array::{
+            BooleanArray, DictionaryArray, Float64Array, Int64Array, LargeBinaryArray, ListArray,
+            StructArray,
+        };
+        use arrow::datatypes::{Int32Type, Int8Type};
+        let n = i32::try_from(rows).unwrap();
+        let words = ["alpha", "beta", "gamma"];
+        let columns: Vec<(&str, ArrayRef)> = vec![
+            (
+                "int",
+                Arc::new(
+                    (0..n)
+                        .map(|i| (i % 3 != 0).then_some(i64::from(i)))
+                        .collect::<Int64Array>(),
+                ),
+            ),
+            (
+                "text",
+                Arc::new(StringArray::from_iter_values(
+                    (0..n).map(|i| words[i.unsigned_abs() as usize % 3].repeat(2)),
+                )),
+            ),
+            (
+                "blob",
+                Arc::new(LargeBinaryArray::from_iter_values(
+                    (0..n).map(|i| vec![0u8; i.unsigned_abs() as usize % 7]),
+                )),
+            ),
+            (
+                "list",
+                Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(
+                    (0..n).map(|i| Some((0..i % 4).map(Some).collect::<Vec<_>>())),
+                )),
+            ),
+            (
+                "record",
+                Arc::new(StructArray::from(vec![
+                    (
+                        Arc::new(Field::new("x", DataType::Float64, false)),
+                        Arc::new(Float64Array::from_iter_values((0..n).map(f64::from))) as ArrayRef,
+                    ),
+                    (
+                        Arc::new(Field::new("flag", DataType::Boolean, false)),
+                        Arc::new((0..n).map(|i| Some(i % 2 == 0)).collect::<BooleanArray>()),
+                    ),
+                ])),
+            ),
+            (
+                "category",
+                Arc::new(
+                    (0..n)
+                        .map(|i| words[i.unsigned_abs() as usize % 3])
+                        .collect::<DictionaryArray<Int8Type>>(),
+                ),
+            ),
+        ];
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    #[test]
+    fn stream_estimate_bounds_the_written_bytes() {
+        let whole = mixed(1000);
+        let cases = [
+            vec![],
+            vec![mixed(1)],
+            vec![whole.clone()],
+            vec![
+                whole.slice(0, 10),
+                whole.slice(10, 490),
+                whole.slice(500, 500),
+            ],
+        ];
+        for batches in &cases {
+            for enable_lz4 in [false, true] {
+                let estimate = estimate_stream_size(&whole.schema(), batches, enable_lz4).unwrap();
+                let encoded = encode_stream(&whole.schema(), batches, enable_lz4).unwrap();
+                let case = format!("{} batches, lz4 {enable_lz4}", batches.len());
+                assert!(
+                    encoded.len() <= estimate,
+                    "{case}: {estimate} < {}",
+                    encoded.len()
+                );
+                assert_eq!(encoded.capacity(), estimate, "{case}: buffer grew");
+                if !enable_lz4 {
+                    // A few hundred bytes per record batch and dictionary message.
+                    let slack = 2 * 512 * batches.len() + 512;
+                    assert!(
+                        estimate - encoded.len() <= slack,
+                        "{case}: {estimate} vs {}",
+                        encoded.len()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_stream_estimate_is_exact() {
+        let schema = mixed(0).schema();
+        let encoded = encode_stream(&schema, &[], false).unwrap();
+        assert_eq!(
+            estimate_stream_size(&schema, &[], false).unwrap(),
+            encoded.len()
+        );
+        assert_eq!(encoded.capacity(), encoded.len());
+    }
+
+    fn labelled_table(rows: i32) -> TableData {
+        let values: Vec<i32> = (0..rows).collect();
+        TableData::new(vec![labelled(&values[..50]), labelled(&values[50..])]).unwrap()
+    }
+
+    #[test]
+    fn planned_chunks_stay_within_the_limit() {
+        let table = labelled_table(300);
+        let chunks = plan_chunks(&table.batches, 2048, false, |_| 600).unwrap();
+        assert!(chunks.len() > 2);
+        for chunk in &chunks {
+            let size: usize = chunk
+                .iter()
+                .map(|batch| batch_message_bound(batch, false))
+                .sum();
+            assert!(600 + size <= 2048, "{size}");
+        }
+        let rows: Vec<usize> = chunks
+            .iter()
+            .map(|chunk| row_counts(chunk).iter().sum())
+            .collect();
+        assert_eq!(rows.iter().sum::<usize>(), 300);
+        let values: Vec<i32> = chunks.iter().flat_map(|chunk| id_values(chunk)).collect();
+        assert_eq!(values, (0..300).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rows_larger_than_a_chunk_are_rejected() {
+        let table = labelled_table(60);
+        let Err(ArrowWasmError::InvalidInput(message)) =
+            plan_chunks(&table.batches, 700, false, |_| 600)
+        else {
+            panic!("no row fits in 100 bytes besides the overhead");
+        };
+        assert!(message.starts_with("Row 0 needs "), "{message}");
+        assert!(message.ends_with(" bytes as a chunk of its own, more than maxChunkBytes 700"));
+        assert_eq!(plan_chunks(&[], 700, false, |_| 600).unwrap(), [vec![]]);
+    }
+
+    #[test]
+    fn chunks_round_trip_in_both_layouts() {
+        let table = labelled_table(300);
+        for standalone in [false, true] {
+            let chunks = encode_chunks(&table, 2048, standalone, false).unwrap();
+            assert!(chunks.len() > 2);
+            assert!(chunks.iter().all(|chunk| chunk.len() <= 2048));
+            if standalone {
+                assert!(chunks
+                    .iter()
+                    .all(|chunk| read_stream_batches(chunk).is_ok()));
+            }
+
+            let (restored, truncated) = decode_chunks(&chunks).unwrap();
+            assert!(!truncated);
+            assert_eq!(restored.schema, table.schema);
+            assert!(!restored.schema.metadata().contains_key(CHUNKED_IPC));
+            assert_eq!(id_values(&restored.batches), (0..300).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn chunk_prefixes_restore_leading_rows() {
+        let table = labelled_table(300);
+        for standalone in [false, true] {
+            let chunks = encode_chunks(&table, 2048, standalone, false).unwrap();
+            let (restored, truncated) = decode_chunks(&chunks[..2]).unwrap();
+            assert!(truncated);
+            let values = id_values(&restored.batches);
+            assert!(!values.is_empty() && values.len() < 300);
+            assert!(values.iter().zip(0..).all(|(value, row)| *value == row));
+        }
+    }
+
+    #[test]
+    fn misordered_or_foreign_chunks_are_rejected() {
+        let table = labelled_table(300);
+        let mut chunks = encode_chunks(&table, 2048, true, false).unwrap();
+        chunks.swap(1, 2);
+        let Err(ArrowWasmError::InvalidInput(message)) = decode_chunks(&chunks) else {
+            panic!("chunk 2 cannot stand in for chunk 1");
+        };
+        let count = chunks.len();
+        assert_eq!(
+            message,
+            format!("Chunk 1 is chunk 2 of {count}, expected chunk 1 of {count}")
+        );
+
+        chunks.swap(1, 2);
+        let extra = [chunks[0].clone()];
+        let Err(ArrowWasmError::InvalidInput(message)) =
+            decode_chunks(&[&chunks[..], &extra].concat())
+        else {
+            panic!("an extra chunk 0 is out of order");
+        };
+        assert_eq!(
+            message,
+            format!("Chunk {count} is chunk 0 of {count}, expected chunk {count} of {count}")
+        );
+
+        let Err(ArrowWasmError::InvalidInput(message)) = decode_chunks(&[stream(&[ids(&[1])])])
+        else {
+            panic!("plain streams carry no chunk position");
+        };
+        assert!(
+            message.starts_with("Chunk 0 has no chunked_ipc metadata"),
+            "{message}"
+        );
+        assert!(decode_chunks(&[]).is_err());
+    }
+
+    #[test]
+    fn concatenated_chunks_reject_extra_chunks() {
+        let table = labelled_table(300);
+        let chunks = encode_chunks(&table, 2048, false, false).unwrap();
+        let count = chunks.len();
+        let padded = [&chunks[..], &[vec![0; 8]]].concat();
+        let Err(ArrowWasmError::InvalidInput(message)) = decode_chunks(&padded) else {
+            panic!("more chunks than the export wrote");
+        };
+        assert_eq!(
+            message,
+            format!("Got {} chunks, but the export wrote {count}", count + 1)
+        );
+    }
+
+    fn file(batches: &[RecordBatch]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = FileWriter::try_new(&mut data, &batches[0].schema()).unwrap();
+        for batch in batches {
+            writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        data
+    }
+
+    #[test]
+    fn file_and_stream_read_to_the_same_batches() {
+        let whole = mixed(300);
+        let batches = [whole.slice(0, 100), whole.slice(100, 200)];
+        let (file, stream) = (file(&batches), stream(&batches));
+        for format in [None, Some(FileFormat::IpcFile)] {
+            assert_eq!(
+                read_ipc_batches(&file, format).unwrap(),
+                (whole.schema(), batches.to_vec())
+            );
+        }
+        for format in [None, Some(FileFormat::IpcStream)] {
+            assert_eq!(
+                read_ipc_batches(&stream, format).unwrap(),
+                (whole.schema(), batches.to_vec())
+            );
+        }
+
+        assert!(matches!(
+            read_ipc_batches(&file, Some(FileFormat::IpcStream)),
+            Err(ArrowWasmError::Ipc(_))
+        ));
+        assert!(matches!(
+            read_ipc_batches(&stream, Some(FileFormat::IpcFile)),
+            Err(ArrowWasmError::Ipc(_))
+        ));
+        assert!(matches!(
+            read_ipc_batches(&file, Some(FileFormat::Parquet)),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn ipc_format_names_parse() {
+        assert_eq!(parse_ipc_format(None).unwrap(), None);
+        assert_eq!(
+            parse_ipc_format(Some("file")).unwrap(),
+            Some(FileFormat::IpcFile)
+        );
+        assert_eq!(
+            parse_ipc_format(Some("stream")).unwrap(),
+            Some(FileFormat::IpcStream)
+        );
+        assert!(matches!(
+            parse_ipc_format(Some("parquet")),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn batch_streams_stand_alone_and_concatenate_to_the_table() {
+        use arrow::array::DictionaryArray;
+        use arrow::datatypes::Int8Type;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new_dictionary("kind", DataType::Int8, DataType::Utf8, true),
+        ]));
+        let batch = |ids: &[i32], kinds: Vec<Option<&str>>| {
+            let kinds: DictionaryArray<Int8Type> = kinds.into_iter().collect();
+            let columns: Vec<ArrayRef> =
+                vec![Arc::new(Int32Array::from(ids.to_vec())), Arc::new(kinds)];
+            RecordBatch::try_new(Arc::clone(&schema), columns).unwrap()
+        };
+        let table = TableData::new(vec![
+            batch(&[1, 2], vec![Some("a"), Some("b")]),
+            batch(&[], vec![]),
+            batch(&[3, 4, 5], vec![Some("c"), None, Some("a")]),
+        ])
+        .unwrap();
+
+        for enable_lz4 in [false, true] {
+            let streams = encode_batch_streams(&table, enable_lz4).unwrap();
+            assert_eq!(streams.len(), 3);
+            for (stream, expected) in streams.iter().zip(&table.batches) {
+                let reader = StreamReader::try_new(Cursor::new(stream), None).unwrap();
+                assert_eq!(reader.schema(), schema);
+                let batches: Vec<RecordBatch> =
+                    reader.collect::<std::result::Result<_, _>>().unwrap();
+                assert_eq!(batches, std::slice::from_ref(expected));
+            }
+            let (read_schema, batches) = read_stream_batches(&streams.concat()).unwrap();
+            assert_eq!(read_schema, schema);
+            assert_eq!(batches, table.batches);
+        }
+
+        let empty = TableData {
+            batches: Vec::new(),
+            schema: Arc::clone(&schema),
+        };
+        let streams = encode_batch_streams(&empty, false).unwrap();
+        assert_eq!(streams.len(), 1);
+        let (read_schema, batches) = read_stream_batches(&streams[0]).unwrap();
+        assert_eq!(read_schema, schema);
+        assert!(batches.is_empty());
+    }
+}
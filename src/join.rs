@@ -0,0 +1,406 @@
+//! As-of joins: matching each row to the nearest row of another table by an
+//! ordered key, e.g. to merge sensor streams sampled at different times.
+
+use crate::compute::capability::{require, Operation};
+use crate::compute::keys::{key_domain, keys_comparable, visit_keys, Key, KeyDomain};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::table::rebuild_table;
+use arrow::array::{Array, ArrayRef, UInt64Array};
+use arrow::datatypes::FieldRef;
+use arrow_select::concat::concat_batches;
+use arrow_select::take::take;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Which right rows a left row may match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    /// The last right row whose key is at most the left key.
+    Backward,
+    /// The first right row whose key is at least the left key.
+    Forward,
+}
+
+impl Direction {
+    fn parse(name: Option<&str>) -> Result<Self> {
+        match name.unwrap_or("backward") {
+            "backward" => Ok(Self::Backward),
+            "forward" => Ok(Self::Forward),
+            other => Err(ArrowWasmError::InvalidInput(format!(
+                "Unknown direction '{other}', expected 'backward' or 'forward'"
+            ))),
+        }
+    }
+}
+
+/// Key column values in row order; nulls and NaNs are `None`.
+enum KeyValues {
+    Int(Vec<Option<i128>>),
+    Float(Vec<Option<f64>>),
+}
+
+/// Field of the key column `name` of the `side` table.
+fn key_field(table: &TableData, name: &str, side: &str) -> Result<FieldRef> {
+    let (_, field) = table.schema.column_with_name(name).ok_or_else(|| {
+        ArrowWasmError::InvalidInput(format!("Column '{name}' not found in the {side} table"))
+    })?;
+    Ok(Arc::new(field.clone()))
+}
+
+/// Check that the key columns can be compared with each other.
+fn check_key_types(left: &FieldRef, right: &FieldRef) -> Result<()> {
+    require(Operation::JoinAsof, left)?;
+    require(Operation::JoinAsof, right)?;
+    let (left_type, right_type) = (left.data_type(), right.data_type());
+    if keys_comparable(left_type, right_type) {
+        return Ok(());
+    }
+    Err(ArrowWasmError::InvalidInput(format!(
+        "join_asof keys '{}' ({left_type:?}) and '{}' ({right_type:?}) are not comparable; \
+         cast one of them with cast_column first",
+        left.name(),
+        right.name()
+    )))
+}
+
+/// Values of the key column `field` of `table`, checked to be ascending.
+fn sorted_keys(field: &FieldRef, table: &TableData, side: &str) -> Result<KeyValues> {
+    let name = field.name();
+    let float = key_domain(field.data_type()) == Some(KeyDomain::Float);
+    let mut ints = Vec::new();
+    let mut floats = Vec::new();
+    for chunk in table.get_column_by_name(name)? {
+        visit_keys(chunk.as_ref(), &mut |_, key| match key {
+            Some(Key::Int(value)) => ints.push(Some(value)),
+            Some(Key::Float(bits)) => {
+                floats.push(Some(f64::from_bits(bits)).filter(|value| !value.is_nan()));
+            }
+            _ if float => floats.push(None),
+            _ => ints.push(None),
+        })?;
+    }
+    let values = if float {
+        KeyValues::Float(floats)
+    } else {
+        KeyValues::Int(ints)
+    };
+    let unsorted = match &values {
+        KeyValues::Int(values) => first_unsorted(values),
+        KeyValues::Float(values) => first_unsorted(values),
+    };
+    if let Some(row) = unsorted {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "join_asof {side} key '{name}' must be sorted ascending, but row {row} is smaller \
+             than an earlier key; sort the table with sort_by first"
+        )));
+    }
+    Ok(values)
+}
+
+/// First row whose key is smaller than an earlier non-null key.
+fn first_unsorted<T: PartialOrd + Copy>(values: &[Option<T>]) -> Option<usize> {
+    let mut largest: Option<T> = None;
+    for (row, value) in values.iter().enumerate() {
+        let Some(value) = *value else { continue };
+        if largest.is_some_and(|largest| value < largest) {
+            return Some(row);
+        }
+        largest = Some(value);
+    }
+    None
+}
+
+/// Right row matched by each left row, or `None` when there is none.
+fn match_rows<T: PartialOrd + Copy>(
+    left: &[Option<T>],
+    right: &[Option<T>],
+    direction: Direction,
+) -> Vec<Option<u64>> {
+    let right: Vec<(T, usize)> = right
+        .iter()
+        .enumerate()
+        .filter_map(|(row, key)| key.map(|key| (key, row)))
+        .collect();
+    let mut next = 0;
+    left.iter()
+        .map(|key| {
+            let key = (*key)?;
+            let matched = match direction {
+                Direction::Backward => {
+                    while next < right.len() && right[next].0 <= key {
+                        next += 1;
+                    }
+                    next.checked_sub(1).map(|index| right[index].1)
+                }
+                Direction::Forward => {
+                    while next < right.len() && right[next].0 < key {
+                        next += 1;
+                    }
+                    right.get(next).map(|&(_, row)| row)
+                }
+            };
+            matched.map(|row| row as u64)
+        })
+        .collect()
+}
+
+/// Join every row of `left` to the nearest row of `right` by the sorted
+/// numeric or temporal keys `left_on` and `right_on`.
+///
+/// With `direction` `"backward"` (the default) a left row matches the last
+/// right row whose key is less than or equal to its own; with `"forward"`,
+/// the first right row whose key is greater than or equal to it. When
+/// several right rows share the matching key, backward takes the last of
+/// them and forward the first, in row order. Left rows without a match, or
+/// with a null or NaN key, get nulls in the right columns; right rows with
+/// a null or NaN key are never matched.
+///
+/// Both keys must be ascending (nulls may appear anywhere) and of the same
+/// kind: integers, floats, or temporal values of one type and unit
+/// (timestamps may differ in time zone). The result holds every left row
+/// in order with the left columns, followed by the right columns; the
+/// right key is left out when it has the same name as the left key. Any
+/// other column name present on both sides fails.
+#[wasm_bindgen]
+pub fn join_asof(
+    left: TableHandle,
+    right: TableHandle,
+    left_on: &str,
+    right_on: &str,
+    direction: Option<String>,
+) -> std::result::Result<TableHandle, JsValue> {
+    let direction = Direction::parse(direction.as_deref())?;
+    let left = mem::get_table(left)?;
+    let right = mem::get_table(right)?;
+    Ok(mem::store_table(asof_join(
+        &left, &right, left_on, right_on, direction,
+    )?)?)
+}
+
+/// The table `join_asof` registers.
+fn asof_join(
+    left: &TableData,
+    right: &TableData,
+    left_on: &str,
+    right_on: &str,
+    direction: Direction,
+) -> Result<TableData> {
+    let left_field = key_field(left, left_on, "left")?;
+    let right_field = key_field(right, right_on, "right")?;
+    check_key_types(&left_field, &right_field)?;
+    let left_keys = sorted_keys(&left_field, left, "left")?;
+    let right_keys = sorted_keys(&right_field, right, "right")?;
+    let matches = match (&left_keys, &right_keys) {
+        (KeyValues::Int(left), KeyValues::Int(right)) => match_rows(left, right, direction),
+        (KeyValues::Float(left), KeyValues::Float(right)) => match_rows(left, right, direction),
+        _ => {
+            return Err(ArrowWasmError::Internal(
+                "join_asof keys passed the type check with different kinds".to_string(),
+            ))
+        }
+    };
+
+    let right_columns: Vec<usize> = (0..right.column_count())
+        .filter(|&index| !(right.schema.field(index).name() == right_on && left_on == right_on))
+        .collect();
+    for &index in &right_columns {
+        let name = right.schema.field(index).name();
+        if left.schema.column_with_name(name).is_some() {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Column '{name}' exists in both tables; rename it with rename_column first"
+            )));
+        }
+    }
+    let right_batch = concat_batches(&right.schema, &right.batches)?;
+
+    let mut fields: Vec<FieldRef> = left.schema.fields().iter().cloned().collect();
+    let mut nullable = vec![false; right_columns.len()];
+    let mut batch_columns = Vec::with_capacity(left.batches.len());
+    let mut offset = 0;
+    for batch in &left.batches {
+        let indices: UInt64Array = matches[offset..offset + batch.num_rows()]
+            .iter()
+            .copied()
+            .collect();
+        offset += batch.num_rows();
+        let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+        for (position, &index) in right_columns.iter().enumerate() {
+            let taken = take(right_batch.column(index).as_ref(), &indices, None)?;
+            nullable[position] |= taken.logical_null_count() > 0;
+            columns.push(taken);
+        }
+        batch_columns.push(columns);
+    }
+    for (position, &index) in right_columns.iter().enumerate() {
+        let field = right.schema.field(index);
+        fields.push(Arc::new(
+            field
+                .clone()
+                .with_nullable(field.is_nullable() || nullable[position]),
+        ));
+    }
+    rebuild_table(left, fields, batch_columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{AsArray, Float64Array, Int64Array, RecordBatch, TimestampMillisecondArray};
+    use arrow::datatypes::Float64Type;
+
+    fn timestamps(values: &[i64], time_zone: &str) -> ArrayRef {
+        Arc::new(TimestampMillisecondArray::from(values.to_vec()).with_timezone(time_zone))
+    }
+
+    fn floats(values: &[Option<f64>]) -> ArrayRef {
+        Arc::new(Float64Array::from(values.to_vec()))
+    }
+
+    /// A table of one batch per entry of `batches`, each a list of named
+    /// columns.
+    fn table(batches: Vec<Vec<(&str, ArrayRef)>>) -> TableData {
+        let batches = batches
+            .into_iter()
+            .map(|columns| {
+                RecordBatch::try_from_iter_with_nullable(
+                    columns
+                        .into_iter()
+                        .map(|(name, column)| (name, column, true)),
+                )
+                .unwrap()
+            })
+            .collect();
+        TableData::new(batches).unwrap()
+    }
+
+    fn column(table: &TableData, name: &str) -> Vec<Option<f64>> {
+        table
+            .get_column_by_name(name)
+            .unwrap()
+            .iter()
+            .flat_map(|chunk| {
+                chunk
+                    .as_primitive::<Float64Type>()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Trades at 10, 20, 30 and 40 ms in two batches, and quotes at 5, 20,
+    /// 20 and 25 ms.
+    fn trades_and_quotes() -> (TableData, TableData) {
+        let trades = table(vec![
+            vec![
+                ("t", timestamps(&[10, 20], "UTC")),
+                ("price", floats(&[Some(1.5), Some(2.5)])),
+            ],
+            vec![
+                ("t", timestamps(&[30, 40], "UTC")),
+                ("price", floats(&[Some(3.5), Some(4.5)])),
+            ],
+        ]);
+        let quotes = table(vec![vec![
+            ("t", timestamps(&[5, 20, 20, 25], "+00:00")),
+            ("bid", floats(&[Some(1.0), Some(2.0), Some(2.1), Some(3.0)])),
+        ]]);
+        (trades, quotes)
+    }
+
+    #[test]
+    fn backward_takes_the_last_quote_at_or_before_each_trade() {
+        let (trades, quotes) = trades_and_quotes();
+        let joined = asof_join(&trades, &quotes, "t", "t", Direction::Backward).unwrap();
+
+        let names: Vec<&String> = joined.schema.fields().iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["t", "price", "bid"]);
+        assert_eq!(joined.batches.len(), 2);
+        assert_eq!(column(&joined, "price"), column(&trades, "price"));
+        assert_eq!(
+            column(&joined, "bid"),
+            [Some(1.0), Some(2.1), Some(3.0), Some(3.0)]
+        );
+    }
+
+    #[test]
+    fn forward_takes_the_first_quote_at_or_after_each_trade() {
+        let (trades, quotes) = trades_and_quotes();
+        let joined = asof_join(&trades, &quotes, "t", "t", Direction::Forward).unwrap();
+        assert_eq!(column(&joined, "bid"), [Some(2.0), Some(2.0), None, None]);
+        assert!(joined.schema.field_with_name("bid").unwrap().is_nullable());
+    }
+
+    #[test]
+    fn null_and_nan_keys_never_match() {
+        let left = table(vec![vec![
+            ("x", floats(&[Some(1.0), Some(f64::NAN), None, Some(3.0)])),
+            (
+                "left",
+                floats(&[Some(10.0), Some(20.0), Some(30.0), Some(40.0)]),
+            ),
+        ]]);
+        let right = table(vec![vec![
+            ("key", floats(&[Some(0.5), Some(f64::NAN), None, Some(2.0)])),
+            (
+                "right",
+                floats(&[Some(1.0), Some(2.0), Some(3.0), Some(4.0)]),
+            ),
+        ]]);
+        let backward = asof_join(&left, &right, "x", "key", Direction::Backward).unwrap();
+        assert_eq!(
+            column(&backward, "right"),
+            [Some(1.0), None, None, Some(4.0)]
+        );
+        // Keys on different names keep both.
+        assert_eq!(column(&backward, "key"), [Some(0.5), None, None, Some(2.0)]);
+        let forward = asof_join(&left, &right, "x", "key", Direction::Forward).unwrap();
+        assert_eq!(column(&forward, "right"), [Some(4.0), None, None, None]);
+    }
+
+    #[test]
+    fn unsorted_keys_are_rejected() {
+        let (trades, _) = trades_and_quotes();
+        let quotes = table(vec![
+            vec![("t", timestamps(&[5, 30], "UTC"))],
+            vec![("t", timestamps(&[20], "UTC"))],
+        ]);
+        let error = asof_join(&trades, &quotes, "t", "t", Direction::Backward).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("right key 't' must be sorted ascending, but row 2"),
+            "{error}"
+        );
+        let error = asof_join(&quotes, &trades, "t", "t", Direction::Forward).unwrap_err();
+        assert!(error.to_string().contains("left key 't'"), "{error}");
+    }
+
+    #[test]
+    fn shared_column_names_are_rejected() {
+        let (trades, _) = trades_and_quotes();
+        let quotes = table(vec![vec![
+            ("time", timestamps(&[5], "UTC")),
+            ("price", floats(&[Some(1.0)])),
+        ]]);
+        let error = asof_join(&trades, &quotes, "t", "time", Direction::Backward).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Column 'price' exists in both tables"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn keys_of_different_kinds_are_rejected() {
+        let (trades, _) = trades_and_quotes();
+        let quotes = table(vec![vec![(
+            "t",
+            Arc::new(Int64Array::from(vec![5])) as ArrayRef,
+        )]]);
+        let error = asof_join(&trades, &quotes, "t", "t", Direction::Backward).unwrap_err();
+        assert!(error.to_string().contains("not comparable"), "{error}");
+        assert!(Direction::parse(Some("nearest")).is_err());
+    }
+}
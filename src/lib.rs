@@ -1,27 +1,111 @@
+//! WebAssembly bindings for Apache Arrow tables held in WASM memory.
+//!
+//! Tables live in a handle-based registry (see [`mem`]); JS receives opaque
+//! numeric handles and passes them back to the exported functions.
+
+#[cfg(feature = "alloc-metrics")]
+mod alloc_metrics;
+mod archive;
+mod builder;
+mod column;
+mod compat;
+mod compute;
+mod convert;
+mod csv;
+mod dispose;
+mod edit;
 mod errors;
+mod fingerprint;
+mod fs;
+mod ipc;
+mod join;
 mod mem;
+mod plain;
+mod redact;
+mod reshape;
+mod rng;
+mod rows;
+mod samples;
+mod shard;
+#[cfg(all(feature = "small-alloc", target_arch = "wasm32"))]
+mod small_alloc;
+mod sort;
+mod table;
+mod tensor;
+mod tz;
+mod validation;
 
-use wasm_bindgen::prelude::*;
 use js_sys::Uint8Array;
-use arrow::ipc::reader::StreamReader;
-use arrow::ipc::writer::StreamWriter;
-use arrow_ipc::writer::IpcWriteOptions;
-use std::io::Cursor;
-use std::sync::Arc;
-use arrow::record_batch::RecordBatch;
+use wasm_bindgen::prelude::*;
 
+pub use archive::{read_table_archive, write_table_archive};
+pub use builder::{append_rows, from_async_iterable, StreamingTableBuilder};
+pub use column::{get_column, get_column_at, set_strict_indexing, Column};
+pub use compat::export_compat;
+pub use compute::binning::{binned2d, binned2d_values, cut};
+pub use compute::capability::{capability_matrix, capability_matrix_json, supported_operations};
+pub use compute::cast::{cast_column, cast_columns, conform_to_schema};
+pub use compute::fill::{fill_backward, fill_forward};
+pub use compute::mapping::{map_values, map_values_with};
+pub use compute::numeric::{abs, clip, column_ceil, column_floor, column_round, negate};
+pub use compute::parse::{parse_dates, parse_numbers};
+pub use compute::run_end::{decode_run_ends, encode_run_ends};
+pub use compute::shift::{diff, percent_change, shift};
+pub use compute::stats::{
+    column_max, column_mean, column_median, column_min, column_std_dev, column_sum,
+    column_sum_exact, column_variance, time_range,
+};
+pub use compute::string_ops::{count_matches, decode_utf8};
+pub use compute::temporal::{
+    convert_timezone, date_part, date_trunc, localize_naive, remove_timezone,
+};
+pub use compute::wkb::validate_wkb;
+pub use compute::{anti_join_mask, semi_join_mask};
+pub use convert::{
+    clear_table_conversion_policy, conversion_policy, conversion_table, conversion_table_json,
+    set_conversion_policy, set_table_conversion_policy, ConversionPolicy,
+};
+pub use csv::{write_table_to_csv, write_table_to_csv_with_options};
+pub use dispose::{configure_auto_dispose, dispose_handle, track_handle};
+pub use edit::{set_null, set_value};
 pub use errors::{ArrowWasmError, Result};
-pub use mem::{TableHandle, TableData};
+pub use fingerprint::schema_fingerprint;
+pub use fs::{
+    read_ipc_range, read_parquet_limit, read_preview, read_table_from_parquet,
+    write_table_to_parquet, write_table_to_parquet_per_column,
+};
+pub use ipc::{
+    append_ipc, estimate_ipc_size, export_chunked_ipc, import_chunked_ipc,
+    read_table_from_bytes_with_coercion, read_table_from_bytes_with_options,
+    write_table_to_ipc_batches, write_table_to_ipc_sharded, write_table_to_ipc_streaming,
+    write_table_to_ipc_with_options,
+};
+pub use join::join_asof;
+pub use mem::{TableData, TableHandle};
 
 // Re-export core functions from mem module
 pub use mem::{
+    export_column_by_name, free_all, free_table, get_column_names, get_memory_info,
+    memory_breakdown, registry_release_snapshot, registry_restore, registry_snapshot,
+    release_table, schema_is_superset_of, schemas_equal, set_memory_limit, table_column_count,
     table_row_count,
-    export_column_by_name,
-    free_table,
-    get_memory_info,
-    table_column_count,
-    get_column_names,
 };
+pub use plain::{table_from_plain_object, to_plain_object};
+pub use redact::{apply_null_mask, apply_null_mask_bytes, redact_rows};
+pub use reshape::{melt, pivot};
+pub use rows::{table_rows, to_rows_compact, RowIterator};
+pub use samples::{create_sample_table, list_sample_tables};
+pub use shard::{shard_table, shard_table_by_bytes};
+pub use sort::{bottom_k, sort_by, top_k};
+pub use table::{
+    add_column, assign, coalesce_batches, concat_tables, drop_duplicates, drop_nulls,
+    filter_by_mask, flatten_struct, merge_field_metadata, merge_metadata, nest, positions_of,
+    rename_column, rename_columns, rename_schema_metadata_key, row_hashes, sample_fraction, select,
+    with_row_index,
+};
+pub use tensor::make_tensor_column;
+pub use tz::list_time_zones;
+pub use validation::validate_table;
 
 // Console logging setup for debugging
 #[wasm_bindgen]
@@ -30,18 +114,19 @@ extern "C" {
     fn log(s: &str);
 }
 
+/// Log a formatted message to the JS console.
 #[macro_export]
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
-// Initialize panic hook for better error reporting
+/// Module start hook: install the panic hook for better error reporting.
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
 }
 
-// Optional initialization with configuration
+/// Optional initialization with configuration.
 #[wasm_bindgen]
 pub fn init_with_options(enable_console_logs: bool) {
     if enable_console_logs {
@@ -49,150 +134,136 @@ pub fn init_with_options(enable_console_logs: bool) {
     }
 }
 
-// Core API function: Read table from bytes (Arrow IPC format)
+/// Read an Arrow IPC file or stream into a new table.
+///
+/// The format is detected from the leading bytes (IPC files open with
+/// `ARROW1`); pass `format` `"file"` or `"stream"` to skip detection and
+/// fail on data in the other format. Both formats read to the same table.
+/// Every batch must share the schema; see
+/// `read_table_from_bytes_with_options` to reconcile additive changes.
 #[wasm_bindgen]
-pub fn read_table_from_bytes(data: &[u8]) -> std::result::Result<TableHandle, JsValue> {
-    let cursor = Cursor::new(data);
-    let reader = StreamReader::try_new(cursor, None)
-        .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-    
-    let mut batches = Vec::new();
-    for batch_result in reader {
-        let batch = batch_result.map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-        batches.push(batch);
-    }
-    
-    if batches.is_empty() {
-        return Err(ArrowWasmError::InvalidInput("No record batches found in data".to_string()).into());
-    }
-    
-    let table_data = TableData::new(batches)?;
-    let handle = mem::store_table(table_data)?;
-    Ok(handle)
+pub fn read_table_from_bytes(
+    data: &[u8],
+    format: Option<String>,
+) -> std::result::Result<TableHandle, JsValue> {
+    let format = ipc::parse_ipc_format(format.as_deref())?;
+    Ok(ipc::read_table(data, format, false)?)
 }
 
-// Core API function: Write table to Arrow IPC format
+/// Serialize a table to the Arrow IPC stream format, optionally LZ4 compressed.
 #[wasm_bindgen]
-pub fn write_table_to_ipc(handle: TableHandle, enable_lz4: bool) -> std::result::Result<Uint8Array, JsValue> {
+pub fn write_table_to_ipc(
+    handle: TableHandle,
+    enable_lz4: bool,
+) -> std::result::Result<Uint8Array, JsValue> {
     let table = mem::get_table(handle)?;
-    
-    let mut buffer = Vec::new();
-    let options = IpcWriteOptions::default()
-        .try_with_compression(if enable_lz4 { 
-            Some(arrow_ipc::CompressionType::LZ4_FRAME) 
-        } else { 
-            None 
-        })
-        .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-    
-    {
-        let mut writer = StreamWriter::try_new_with_options(&mut buffer, &table.schema, options)
-            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-        
-        for batch in &table.batches {
-            writer.write(batch)
-                .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-        }
-        
-        writer.finish()
-            .map_err(|e| ArrowWasmError::Ipc(e.to_string()))?;
-    }
-    
+    let buffer = ipc::encode_stream(&table.schema, &table.batches, enable_lz4)?;
+
     // Create Uint8Array from the buffer (this creates a copy)
     let uint8_array = Uint8Array::new_with_length(buffer.len() as u32);
     uint8_array.copy_from(&buffer);
-    
+
     Ok(uint8_array)
 }
 
-// Create a simple table from column data (for testing)
+/// Create a small fixed `id`/`name` table (for testing).
+///
+/// Alias for `create_sample_table("basic")`.
 #[wasm_bindgen]
 pub fn create_test_table() -> std::result::Result<TableHandle, JsValue> {
-    use arrow::array::{Int32Array, StringArray};
-    use arrow::datatypes::{DataType, Field, Schema};
-    
-    // Create simple test data
-    let int_array = Int32Array::from(vec![1, 2, 3, 4, 5]);
-    let string_array = StringArray::from(vec!["a", "b", "c", "d", "e"]);
-    
-    let schema = Arc::new(Schema::new(vec![
-        Field::new("id", DataType::Int32, false),
-        Field::new("name", DataType::Utf8, false),
-    ]));
-    
-    let batch = RecordBatch::try_new(
-        schema.clone(),
-        vec![Arc::new(int_array), Arc::new(string_array)],
-    ).map_err(|e| ArrowWasmError::Arrow(e))?;
-    
-    let table_data = TableData::new(vec![batch])?;
-    let handle = mem::store_table(table_data)?;
-    Ok(handle)
+    samples::create_sample_table("basic")
 }
 
-// Get schema information as JSON string
+/// Schema information as a JSON string.
 #[wasm_bindgen]
 pub fn get_table_schema_json(handle: TableHandle) -> std::result::Result<String, JsValue> {
     let table = mem::get_table(handle)?;
-    let fields_info: Vec<_> = table.schema.fields().iter().map(|field| {
-        format!("{{\"name\": \"{}\", \"type\": \"{:?}\", \"nullable\": {}}}", 
-               field.name(), field.data_type(), field.is_nullable())
-    }).collect();
+    let fields_info: Vec<_> = table
+        .schema
+        .fields()
+        .iter()
+        .map(|field| {
+            format!(
+                "{{\"name\": \"{}\", \"type\": \"{:?}\", \"nullable\": {}}}",
+                field.name(),
+                field.data_type(),
+                field.is_nullable()
+            )
+        })
+        .collect();
     let schema_json = format!("[{}]", fields_info.join(","));
     Ok(schema_json)
 }
 
-// Enhanced column export with type information
+/// Export a column buffer together with its type information.
 #[wasm_bindgen]
-pub fn export_column_with_type(handle: TableHandle, column_name: &str) -> std::result::Result<JsValue, JsValue> {
+pub fn export_column_with_type(
+    handle: TableHandle,
+    column_name: &str,
+) -> std::result::Result<JsValue, JsValue> {
     let table = mem::get_table(handle)?;
     let arrays = table.get_column_by_name(column_name)?;
-    
+
     if arrays.is_empty() {
         return Err(ArrowWasmError::InvalidInput("No data in column".to_string()).into());
     }
-    
+
     // Get column type information
-    let field_index = table.schema
+    let field_index = table
+        .schema
         .index_of(column_name)
-        .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{}' not found", column_name)))?;
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{column_name}' not found")))?;
     let field = table.schema.field(field_index);
-    
+
     let result = js_sys::Object::new();
     js_sys::Reflect::set(&result, &"column_name".into(), &column_name.into())?;
-    js_sys::Reflect::set(&result, &"data_type".into(), &format!("{:?}", field.data_type()).into())?;
+    js_sys::Reflect::set(
+        &result,
+        &"data_type".into(),
+        &format!("{:?}", field.data_type()).into(),
+    )?;
     js_sys::Reflect::set(&result, &"nullable".into(), &field.is_nullable().into())?;
-    
+
     // For the first array, export raw buffer data
     let array = &arrays[0];
     let data = array.to_data();
-    
+
     if let Some(buffer) = data.buffers().first() {
-        let bytes = unsafe {
-            js_sys::Uint8Array::view(buffer.as_slice())
-        };
+        let bytes = unsafe { js_sys::Uint8Array::view(buffer.as_slice()) };
         js_sys::Reflect::set(&result, &"data".into(), &bytes)?;
     }
-    
+
     Ok(result.into())
 }
 
-// Get detailed table information
+/// Row, column and batch counts plus column names.
 #[wasm_bindgen]
 pub fn get_table_info(handle: TableHandle) -> std::result::Result<JsValue, JsValue> {
     let table = mem::get_table(handle)?;
-    
+
     let result = js_sys::Object::new();
-    js_sys::Reflect::set(&result, &"row_count".into(), &(table.row_count() as u32).into())?;
-    js_sys::Reflect::set(&result, &"column_count".into(), &(table.column_count() as u32).into())?;
-    js_sys::Reflect::set(&result, &"batch_count".into(), &(table.batches.len() as u32).into())?;
-    
+    js_sys::Reflect::set(
+        &result,
+        &"row_count".into(),
+        &(table.row_count() as u32).into(),
+    )?;
+    js_sys::Reflect::set(
+        &result,
+        &"column_count".into(),
+        &(table.column_count() as u32).into(),
+    )?;
+    js_sys::Reflect::set(
+        &result,
+        &"batch_count".into(),
+        &(table.batches.len() as u32).into(),
+    )?;
+
     // Column names array
     let column_names = js_sys::Array::new();
     for field in table.schema.fields() {
         column_names.push(&field.name().into());
     }
     js_sys::Reflect::set(&result, &"column_names".into(), &column_names)?;
-    
+
     Ok(result.into())
-}
\ No newline at end of file
+}
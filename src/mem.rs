@@ -1,47 +1,55 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use once_cell::sync::Lazy;
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{Array, ArrayData};
+use arrow::datatypes::{DataType, Schema, UnionMode};
 use arrow::record_batch::RecordBatch;
-use arrow::datatypes::Schema;
-use arrow::array::Array;
-use wasm_bindgen::prelude::*;
 use js_sys::Uint8Array;
-use crate::errors::{ArrowWasmError, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use wasm_bindgen::prelude::*;
 
+/// Opaque identifier of a table stored in the registry.
 pub type TableHandle = u32;
 
+/// A table held in WASM memory: one or more batches sharing a schema.
 #[derive(Debug, Clone)]
 pub struct TableData {
+    /// Record batches in row order.
     pub batches: Vec<RecordBatch>,
+    /// Schema shared by every batch.
     pub schema: Arc<Schema>,
 }
 
 impl TableData {
+    /// Build a table from a non-empty list of batches, taking the schema of the first.
     pub fn new(batches: Vec<RecordBatch>) -> Result<Self> {
         if batches.is_empty() {
             return Err(ArrowWasmError::InvalidInput("Empty batch list".to_string()));
         }
-        
+
         let schema = batches[0].schema();
-        Ok(Self {
-            batches,
-            schema,
-        })
+        Ok(Self { batches, schema })
     }
-    
+
+    /// Total number of rows across all batches.
     pub fn row_count(&self) -> usize {
-        self.batches.iter().map(|batch| batch.num_rows()).sum()
+        self.batches.iter().map(RecordBatch::num_rows).sum()
     }
-    
+
+    /// Number of columns in the schema.
+    #[must_use]
     pub fn column_count(&self) -> usize {
         self.schema.fields().len()
     }
-    
+
+    /// Per-batch arrays of the named column.
     pub fn get_column_by_name(&self, name: &str) -> Result<Vec<Arc<dyn Array>>> {
-        let field_index = self.schema
+        let field_index = self
+            .schema
             .index_of(name)
-            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{}' not found", name)))?;
-            
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+
         let mut arrays = Vec::new();
         for batch in &self.batches {
             arrays.push(Arc::clone(batch.column(field_index)));
@@ -50,128 +58,706 @@ impl TableData {
     }
 }
 
-static TABLES: Lazy<Arc<Mutex<HashMap<TableHandle, TableData>>>> = 
-    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+static TABLES: LazyLock<Mutex<HashMap<TableHandle, TableData>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_HANDLE: LazyLock<Mutex<TableHandle>> = LazyLock::new(|| Mutex::new(1));
+
+/// Registry contents saved by `registry_snapshot`, by token.
+static SNAPSHOTS: LazyLock<Mutex<HashMap<u32, HashMap<TableHandle, TableData>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_SNAPSHOT: AtomicU32 = AtomicU32::new(1);
 
-static NEXT_HANDLE: Lazy<Arc<Mutex<TableHandle>>> = 
-    Lazy::new(|| Arc::new(Mutex::new(1)));
+/// Address space of a wasm32 module.
+#[cfg(target_arch = "wasm32")]
+const WASM32_ADDRESS_SPACE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Memory limit set by `set_memory_limit`; 0 means "derive from the module".
+static MEMORY_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes the module can still allocate for decoded data.
+///
+/// Uses the limit configured with `set_memory_limit` when there is one,
+/// otherwise whatever remains of the wasm32 address space. Native builds
+/// without a configured limit are unbounded.
+pub fn memory_limit() -> u64 {
+    match MEMORY_LIMIT.load(Ordering::Relaxed) {
+        0 => default_memory_limit(),
+        limit => limit,
+    }
+}
 
+#[cfg(target_arch = "wasm32")]
+fn default_memory_limit() -> u64 {
+    let current = core::arch::wasm32::memory_size(0) as u64 * 65_536;
+    WASM32_ADDRESS_SPACE.saturating_sub(current)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const fn default_memory_limit() -> u64 {
+    u64::MAX
+}
+
+/// Fail with [`ArrowWasmError::TooLarge`] when `parts`, the estimated
+/// decoded bytes of the consecutive `unit`s of a read (batches, row
+/// groups), add up to more than `limit`.
+///
+/// The hint says how many leading parts would fit; `narrower` names the
+/// read to retry with, e.g. "read fewer rows with `read_ipc_range`".
+pub fn check_fits(parts: &[u64], limit: u64, unit: &str, narrower: &str) -> Result<()> {
+    let estimate = parts
+        .iter()
+        .fold(0_u64, |sum, part| sum.saturating_add(*part));
+    if estimate <= limit {
+        return Ok(());
+    }
+
+    let mut fitting = 0;
+    let mut fitting_bytes = 0_u64;
+    for part in parts {
+        match fitting_bytes.checked_add(*part) {
+            Some(total) if total <= limit => {
+                fitting += 1;
+                fitting_bytes = total;
+            }
+            _ => break,
+        }
+    }
+    let hint = if fitting == 0 {
+        format!(
+            "even the first of {} {unit} does not fit; {narrower} or raise the limit with set_memory_limit",
+            parts.len()
+        )
+    } else {
+        format!(
+            "the first {fitting} of {} {unit} ({fitting_bytes} bytes) would fit; {narrower}",
+            parts.len()
+        )
+    };
+    Err(ArrowWasmError::TooLarge {
+        estimate,
+        limit,
+        hint,
+    })
+}
+
+/// Error for a `handle` with no registered table: freed if the registry
+/// issued it, invalid otherwise.
+fn missing_handle(handle: TableHandle) -> ArrowWasmError {
+    let issued = NEXT_HANDLE
+        .lock()
+        .is_ok_and(|next_handle| handle > 0 && handle < *next_handle);
+    if issued {
+        ArrowWasmError::FreedHandle(handle)
+    } else {
+        ArrowWasmError::InvalidHandle(handle)
+    }
+}
+
+/// Register a table and return its new handle.
 pub fn store_table(table: TableData) -> Result<TableHandle> {
-    let mut tables = TABLES.lock().map_err(|_| 
-        ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
-    
-    let mut next_handle = NEXT_HANDLE.lock().map_err(|_| 
-        ArrowWasmError::Memory("Failed to acquire handle lock".to_string()))?;
-    
-    let handle = *next_handle;
-    *next_handle += 1;
-    
-    tables.insert(handle, table);
+    let handle = {
+        let mut next_handle = NEXT_HANDLE
+            .lock()
+            .map_err(|_| ArrowWasmError::Memory("Failed to acquire handle lock".to_string()))?;
+        let handle = *next_handle;
+        *next_handle += 1;
+        handle
+    };
+
+    TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .insert(handle, table);
     Ok(handle)
 }
 
+/// Fetch a (cheap, `Arc`-sharing) clone of a registered table.
 pub fn get_table(handle: TableHandle) -> Result<TableData> {
-    let tables = TABLES.lock().map_err(|_| 
-        ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
-    
-    tables.get(&handle)
+    let tables = TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
+
+    tables
+        .get(&handle)
         .cloned()
-        .ok_or_else(|| ArrowWasmError::InvalidHandle(handle))
+        .ok_or_else(|| missing_handle(handle))
 }
 
+/// Remove a table from the registry.
 pub fn remove_table(handle: TableHandle) -> Result<()> {
-    let mut tables = TABLES.lock().map_err(|_| 
-        ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
-    
-    tables.remove(&handle)
-        .ok_or_else(|| ArrowWasmError::InvalidHandle(handle))?;
-    
+    TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .remove(&handle)
+        .ok_or_else(|| missing_handle(handle))?;
+
+    Ok(())
+}
+
+/// Remove a table from the registry and hand it over, so the caller holds
+/// the only references to its arrays. Put it back with [`restore_table`].
+pub fn take_table(handle: TableHandle) -> Result<TableData> {
+    TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .remove(&handle)
+        .ok_or_else(|| missing_handle(handle))
+}
+
+/// Register `table` under a handle previously released by [`take_table`].
+pub fn restore_table(handle: TableHandle, table: TableData) -> Result<()> {
+    TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .insert(handle, table);
     Ok(())
 }
 
+/// Whether `handle` refers to a registered table.
 pub fn table_exists(handle: TableHandle) -> bool {
-    if let Ok(tables) = TABLES.lock() {
-        tables.contains_key(&handle)
-    } else {
-        false
-    }
+    TABLES
+        .lock()
+        .is_ok_and(|tables| tables.contains_key(&handle))
 }
 
+/// Number of registered tables.
 pub fn get_table_count() -> usize {
-    if let Ok(tables) = TABLES.lock() {
-        tables.len()
-    } else {
-        0
-    }
+    TABLES.lock().map_or(0, |tables| tables.len())
 }
 
+/// Number of rows in the table.
 #[wasm_bindgen]
 pub fn table_row_count(handle: TableHandle) -> std::result::Result<usize, JsValue> {
     let table = get_table(handle)?;
     Ok(table.row_count())
 }
 
+/// Number of columns in the table.
 #[wasm_bindgen]
 pub fn table_column_count(handle: TableHandle) -> std::result::Result<usize, JsValue> {
     let table = get_table(handle)?;
     Ok(table.column_count())
 }
 
+/// Column names in schema order.
 #[wasm_bindgen]
 pub fn get_column_names(handle: TableHandle) -> std::result::Result<Vec<String>, JsValue> {
     let table = get_table(handle)?;
-    let names: Vec<String> = table.schema.fields()
+    let names: Vec<String> = table
+        .schema
+        .fields()
         .iter()
         .map(|field| field.name().clone())
         .collect();
     Ok(names)
 }
 
+/// Whether two tables have exactly the same schema: the same fields in the
+/// same order, with equal names, types, nullability and metadata, and equal
+/// schema metadata.
+#[wasm_bindgen]
+pub fn schemas_equal(
+    handle: TableHandle,
+    other: TableHandle,
+) -> std::result::Result<bool, JsValue> {
+    Ok(get_table(handle)?.schema == get_table(other)?.schema)
+}
+
+/// Whether the schema of `handle` can stand in for the schema of `other`,
+/// e.g. to check tables before `concat_tables`.
+///
+/// Both schemas must have the same fields in the same order, with equal
+/// names and types; each field of `handle` may be nullable where `other`'s
+/// is not and may carry metadata keys `other` lacks, as may the schema
+/// metadata (arrow-rs `Schema::contains`). Extra columns do not qualify.
+#[wasm_bindgen]
+pub fn schema_is_superset_of(
+    handle: TableHandle,
+    other: TableHandle,
+) -> std::result::Result<bool, JsValue> {
+    Ok(get_table(handle)?
+        .schema
+        .contains(&get_table(other)?.schema))
+}
+
+/// Zero-copy view of the first data buffer of the named column.
 #[wasm_bindgen]
-pub fn export_column_by_name(handle: TableHandle, column_name: &str) -> std::result::Result<Uint8Array, JsValue> {
+pub fn export_column_by_name(
+    handle: TableHandle,
+    column_name: &str,
+) -> std::result::Result<Uint8Array, JsValue> {
     let table = get_table(handle)?;
     let arrays = table.get_column_by_name(column_name)?;
-    
+
     if arrays.is_empty() {
         return Err(ArrowWasmError::InvalidInput("No data in column".to_string()).into());
     }
-    
+
     // For simplicity, export the first array's raw data
     // In a full implementation, this would handle concatenation and proper serialization
     let array = &arrays[0];
     let data = array.to_data();
-    
+
     // Get the buffer data - this is a simplified zero-copy approach
-    let buffer = data.buffers().first()
+    let buffer = data
+        .buffers()
+        .first()
         .ok_or_else(|| ArrowWasmError::Buffer("No buffer data available".to_string()))?;
-    
+
     // Create Uint8Array view of the buffer data (zero-copy)
-    let bytes = unsafe {
-        js_sys::Uint8Array::view(buffer.as_slice())
-    };
-    
+    let bytes = unsafe { js_sys::Uint8Array::view(buffer.as_slice()) };
+
     Ok(bytes)
 }
 
+/// Cap the estimated decoded size of data read into the module, in bytes.
+///
+/// Reads whose estimate exceeds the limit fail up front instead of aborting
+/// on allocation: IPC reads estimate from the message headers or the file
+/// footer, Parquet reads from the uncompressed sizes in the row group
+/// metadata, and range, preview and limited reads count only what they
+/// decode. Pass 0 to go back to the default, the space left in the wasm32
+/// address space.
+#[wasm_bindgen]
+pub fn set_memory_limit(bytes: usize) {
+    MEMORY_LIMIT.store(bytes as u64, Ordering::Relaxed);
+}
+
+/// Release a table from the registry.
+///
+/// Fails for a handle whose table was already freed and for one the
+/// registry never issued, with different messages; see `release_table`
+/// for a variant that never throws.
 #[wasm_bindgen]
 pub fn free_table(handle: TableHandle) -> std::result::Result<(), JsValue> {
     remove_table(handle)?;
     Ok(())
 }
 
+/// Release a table if it is still registered; returns whether it was.
+///
+/// Unlike `free_table` this never throws, so it is safe to call from
+/// finalizers and more than once for the same handle (handles are never
+/// reused).
+#[must_use]
+#[wasm_bindgen]
+pub fn release_table(handle: TableHandle) -> bool {
+    remove_table(handle).is_ok()
+}
+
+/// Free every registered table except those in `except`, e.g. to clean
+/// up after a batch of work while keeping the tables still in use.
+///
+/// Handles in `except` that are not registered are ignored. Returns the
+/// number of tables freed.
+#[wasm_bindgen]
+pub fn free_all(except: Vec<TableHandle>) -> std::result::Result<usize, JsValue> {
+    let keep: HashSet<TableHandle> = except.into_iter().collect();
+    let mut tables = TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
+    let before = tables.len();
+    tables.retain(|handle, _| keep.contains(handle));
+    Ok(before - tables.len())
+}
+
+/// Save the current handle assignments and return a token for
+/// `registry_restore`, e.g. as an undo point before a transactional edit.
+///
+/// The snapshot holds a reference to every table registered now, so their
+/// memory stays allocated even after `free_table` until the snapshot is
+/// dropped with `registry_release_snapshot`. Tables share their arrays, so
+/// a snapshot itself costs little; what it keeps alive can be a lot.
+#[wasm_bindgen]
+pub fn registry_snapshot() -> std::result::Result<u32, JsValue> {
+    let tables = TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .clone();
+    let token = NEXT_SNAPSHOT.fetch_add(1, Ordering::Relaxed);
+    SNAPSHOTS
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire snapshot lock".to_string()))?
+        .insert(token, tables);
+    Ok(token)
+}
+
+/// Revert the registry to the snapshot `token`: tables registered since
+/// are freed and tables freed since are registered again under their old
+/// handles.
+///
+/// Handles created after the snapshot are not reissued, so they fail as
+/// freed. The snapshot is kept, so the same point can be restored again;
+/// release it with `registry_release_snapshot` when done.
+#[wasm_bindgen]
+pub fn registry_restore(token: u32) -> std::result::Result<(), JsValue> {
+    let saved = SNAPSHOTS
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire snapshot lock".to_string()))?
+        .get(&token)
+        .cloned()
+        .ok_or_else(|| ArrowWasmError::InvalidInput(format!("Unknown snapshot token {token}")))?;
+    *TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))? =
+        saved;
+    Ok(())
+}
+
+/// Drop the snapshot `token`, releasing the tables only it still holds;
+/// returns whether it existed.
+#[wasm_bindgen]
+pub fn registry_release_snapshot(token: u32) -> bool {
+    SNAPSHOTS
+        .lock()
+        .is_ok_and(|mut snapshots| snapshots.remove(&token).is_some())
+}
+
+/// Registry statistics for debugging leaks.
+///
+/// With the `alloc-metrics` feature the result also carries
+/// `total_allocations`, `current_bytes` and `peak_bytes`.
 #[wasm_bindgen]
 pub fn get_memory_info() -> JsValue {
     let table_count = get_table_count();
-    
-    serde_wasm_bindgen::to_value(&serde_json::json!({
+
+    #[allow(unused_mut)]
+    let mut info = serde_json::json!({
         "table_count": table_count,
-        "next_handle": if let Ok(handle) = NEXT_HANDLE.lock() { *handle } else { 0 }
-    })).unwrap_or(JsValue::NULL)
+        "snapshot_count": SNAPSHOTS.lock().map_or(0, |snapshots| snapshots.len()),
+        "next_handle": NEXT_HANDLE.lock().map_or(0, |handle| *handle)
+    });
+    #[cfg(feature = "alloc-metrics")]
+    {
+        let stats = crate::alloc_metrics::stats();
+        info["total_allocations"] = stats.total_allocations.into();
+        info["current_bytes"] = stats.current_bytes.into();
+        info["peak_bytes"] = stats.peak_bytes.into();
+    }
+    serde_wasm_bindgen::to_value(&info).unwrap_or(JsValue::NULL)
 }
 
+/// Buffer bytes of one column, by role.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferBytes {
+    /// Values, type ids and other fixed-width buffers.
+    data: usize,
+    /// Validity bitmaps.
+    validity: usize,
+    /// Offsets and sizes of variable-length values, and the views of view
+    /// types.
+    offsets: usize,
+}
+
+impl BufferBytes {
+    const fn total(self) -> usize {
+        self.data + self.validity + self.offsets
+    }
+
+    /// Add the buffers of `data` and of its children, recursively.
+    fn add(&mut self, data: &ArrayData) {
+        self.validity += data.nulls().map_or(0, |nulls| nulls.buffer().capacity());
+        let offset_buffers = match data.data_type() {
+            DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Binary
+            | DataType::LargeBinary
+            | DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::Map(_, _)
+            | DataType::Utf8View
+            | DataType::BinaryView => 0..1,
+            DataType::ListView(_) | DataType::LargeListView(_) => 0..2,
+            DataType::Union(_, UnionMode::Dense) => 1..2,
+            _ => 0..0,
+        };
+        for (i, buffer) in data.buffers().iter().enumerate() {
+            if offset_buffers.contains(&i) {
+                self.offsets += buffer.capacity();
+            } else {
+                self.data += buffer.capacity();
+            }
+        }
+        for child in data.child_data() {
+            self.add(child);
+        }
+    }
+}
+
+/// Bytes of one column of a [`memory_breakdown`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ColumnBytes {
+    name: String,
+    data_type: String,
+    total_bytes: usize,
+    #[serde(flatten)]
+    buffers: BufferBytes,
+}
+
+/// Bytes of one batch of a [`memory_breakdown`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchBytes {
+    rows: usize,
+    total_bytes: usize,
+}
+
+/// Result of [`memory_breakdown`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryBreakdown {
+    total_bytes: usize,
+    batches: Vec<BatchBytes>,
+    columns: Vec<ColumnBytes>,
+}
+
+/// Buffer sizes of a table by batch and by column, e.g. to find the
+/// columns worth dictionary encoding.
+///
+/// Returns `{totalBytes, batches: [{rows, totalBytes}], columns: [{name,
+/// dataType, totalBytes, data, validity, offsets}]}`. Column bytes are split
+/// into values (`data`), validity bitmaps and offsets (views, for the view
+/// types), with nested children and dictionary values counted in their
+/// column. Sizes are buffer capacities; a buffer shared by several arrays,
+/// such as one batch sliced into several, counts once per array. Batch and
+/// column totals both sum to `totalBytes`.
+#[wasm_bindgen]
+pub fn memory_breakdown(handle: TableHandle) -> std::result::Result<JsValue, JsValue> {
+    let breakdown = breakdown(&get_table(handle)?);
+    Ok(serde_wasm_bindgen::to_value(&breakdown).map_err(ArrowWasmError::from)?)
+}
+
+/// The [`memory_breakdown`] of `table`.
+fn breakdown(table: &TableData) -> MemoryBreakdown {
+    let mut columns = vec![BufferBytes::default(); table.column_count()];
+    let batches: Vec<BatchBytes> = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let mut total_bytes = 0;
+            for (bytes, array) in columns.iter_mut().zip(batch.columns()) {
+                let mut batch_bytes = BufferBytes::default();
+                batch_bytes.add(&array.to_data());
+                total_bytes += batch_bytes.total();
+                bytes.data += batch_bytes.data;
+                bytes.validity += batch_bytes.validity;
+                bytes.offsets += batch_bytes.offsets;
+            }
+            BatchBytes {
+                rows: batch.num_rows(),
+                total_bytes,
+            }
+        })
+        .collect();
+    MemoryBreakdown {
+        total_bytes: batches.iter().map(|batch| batch.total_bytes).sum(),
+        batches,
+        columns: table
+            .schema
+            .fields()
+            .iter()
+            .zip(columns)
+            .map(|(field, buffers)| ColumnBytes {
+                name: field.name().clone(),
+                data_type: format!("{:?}", field.data_type()),
+                total_bytes: buffers.total(),
+                buffers,
+            })
+            .collect(),
+    }
+}
+
+/// Drop every registered table.
+#[allow(dead_code)]
 pub fn clear_all_tables() -> Result<()> {
-    let mut tables = TABLES.lock().map_err(|_| 
-        ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?;
-    tables.clear();
+    TABLES
+        .lock()
+        .map_err(|_| ArrowWasmError::Memory("Failed to acquire table store lock".to_string()))?
+        .clear();
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, DictionaryArray, Int32Array, ListArray, StringArray};
+    use arrow::datatypes::{Field, Int32Type, Int8Type};
+
+    #[test]
+    fn reads_within_the_limit_pass() {
+        assert!(check_fits(&[], 0, "batches", "read less").is_ok());
+        assert!(check_fits(&[10, 20], 30, "batches", "read less").is_ok());
+        assert!(check_fits(&[u64::MAX], u64::MAX, "batches", "read less").is_ok());
+    }
+
+    #[test]
+    fn too_large_names_the_fitting_prefix() {
+        let Err(ArrowWasmError::TooLarge {
+            estimate,
+            limit,
+            hint,
+        }) = check_fits(&[10, 20, 30], 35, "row groups", "read fewer rows")
+        else {
+            panic!("60 bytes must not fit in 35");
+        };
+        assert_eq!((estimate, limit), (60, 35));
+        assert_eq!(
+            hint,
+            "the first 2 of 3 row groups (30 bytes) would fit; read fewer rows"
+        );
+
+        let Err(ArrowWasmError::TooLarge { estimate, hint, .. }) =
+            check_fits(&[u64::MAX, u64::MAX], 5, "batches", "read fewer rows")
+        else {
+            panic!("saturated estimates must not fit");
+        };
+        assert_eq!(estimate, u64::MAX);
+        assert!(
+            hint.starts_with("even the first of 2 batches does not fit; read fewer rows or raise")
+        );
+    }
+
+    /// A stored empty table with `fields` and schema metadata `metadata`.
+    fn stored(fields: Vec<Field>, metadata: &[(&str, &str)]) -> TableHandle {
+        let metadata = metadata
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        let schema = Arc::new(Schema::new(fields).with_metadata(metadata));
+        store_table(TableData::new(vec![RecordBatch::new_empty(schema)]).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn schema_comparisons() {
+        let id = || Field::new("id", DataType::Int64, false);
+        let name = || Field::new("name", DataType::Utf8, true);
+        let base = stored(vec![id(), name()], &[("v", "1")]);
+        let same = stored(vec![id(), name()], &[("v", "1")]);
+        // Nullable `id` and extra metadata keys.
+        let wider = stored(
+            vec![
+                id().with_nullable(true),
+                name().with_metadata(HashMap::from([("unit".to_string(), "x".to_string())])),
+            ],
+            &[("v", "1"), ("owner", "ops")],
+        );
+        let retyped = stored(
+            vec![id(), Field::new("name", DataType::LargeUtf8, true)],
+            &[("v", "1")],
+        );
+        let reordered = stored(vec![name(), id()], &[("v", "1")]);
+        let extra = stored(
+            vec![id(), name(), Field::new("x", DataType::Int8, true)],
+            &[("v", "1")],
+        );
+
+        assert!(schemas_equal(base, same).unwrap());
+        assert!(schema_is_superset_of(base, same).unwrap());
+        assert!(schema_is_superset_of(same, base).unwrap());
+
+        assert!(!schemas_equal(base, wider).unwrap());
+        assert!(schema_is_superset_of(wider, base).unwrap());
+        assert!(!schema_is_superset_of(base, wider).unwrap());
+
+        for other in [retyped, reordered, extra] {
+            assert!(!schemas_equal(base, other).unwrap());
+            assert!(!schema_is_superset_of(base, other).unwrap());
+            assert!(!schema_is_superset_of(other, base).unwrap());
+        }
+    }
+
+    #[test]
+    fn freed_and_unknown_handles_fail_differently() {
+        let handle = stored(vec![Field::new("n", DataType::Int32, true)], &[]);
+        assert!(remove_table(handle).is_ok());
+        for result in [remove_table(handle), get_table(handle).map(drop)] {
+            let error = result.unwrap_err();
+            assert!(matches!(error, ArrowWasmError::FreedHandle(h) if h == handle));
+            assert_eq!(
+                error.to_string(),
+                format!("Table handle {handle} was already freed")
+            );
+        }
+        assert!(take_table(handle).is_err());
+
+        for unknown in [0, u32::MAX] {
+            let error = remove_table(unknown).unwrap_err();
+            assert!(matches!(error, ArrowWasmError::InvalidHandle(h) if h == unknown));
+            assert_eq!(
+                error.to_string(),
+                format!("Invalid table handle: {unknown}")
+            );
+            assert!(!release_table(unknown));
+        }
+        assert!(!release_table(handle));
+    }
+
+    #[test]
+    fn column_and_batch_bytes_sum_to_the_total() {
+        let batch = |n: Vec<Option<i32>>, text: Vec<&str>, tags, cats: Vec<&str>| {
+            RecordBatch::try_from_iter([
+                ("n", Arc::new(Int32Array::from(n)) as ArrayRef),
+                ("text", Arc::new(StringArray::from(text))),
+                (
+                    "tags",
+                    Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(tags)),
+                ),
+                (
+                    "cat",
+                    Arc::new(cats.into_iter().collect::<DictionaryArray<Int8Type>>()),
+                ),
+            ])
+            .unwrap()
+        };
+        let batches = vec![
+            batch(
+                vec![Some(1), None, Some(3)],
+                vec!["a", "bb", "ccc"],
+                vec![Some(vec![Some(1)]), None, Some(vec![Some(2), Some(3)])],
+                vec!["x", "y", "x"],
+            ),
+            batch(
+                vec![Some(4), Some(5)],
+                vec!["dddd", ""],
+                vec![Some(vec![]), Some(vec![None])],
+                vec!["z", "z"],
+            ),
+        ];
+        let arrays_bytes: Vec<usize> = (0..4)
+            .map(|i| {
+                let arrays = batches.iter().map(|batch| batch.column(i));
+                arrays.map(Array::get_array_memory_size).sum()
+            })
+            .collect();
+        let breakdown = breakdown(&TableData::new(batches).unwrap());
+
+        let columns: usize = breakdown.columns.iter().map(|c| c.total_bytes).sum();
+        let batches: usize = breakdown.batches.iter().map(|b| b.total_bytes).sum();
+        assert!(breakdown.total_bytes > 0);
+        assert_eq!(columns, breakdown.total_bytes);
+        assert_eq!(batches, breakdown.total_bytes);
+        let rows: Vec<usize> = breakdown.batches.iter().map(|b| b.rows).collect();
+        assert_eq!(rows, [3, 2]);
+        for (column, array_bytes) in breakdown.columns.iter().zip(arrays_bytes) {
+            let name = &column.name;
+            assert_eq!(column.total_bytes, column.buffers.total(), "{name}");
+            // Buffers are part of what arrow-rs reports for the arrays.
+            assert!(column.total_bytes <= array_bytes, "{name}");
+        }
+        let buffers = |name: &str| {
+            let column = breakdown.columns.iter().find(|c| c.name == name);
+            column.unwrap().buffers
+        };
+        assert!(buffers("n").validity > 0);
+        assert_eq!(buffers("n").offsets, 0);
+        assert!(buffers("text").offsets > 0);
+        assert_eq!(buffers("text").validity, 0);
+        assert!(buffers("tags").offsets > 0);
+        // The dictionary's Utf8 values bring offsets along.
+        assert!(buffers("cat").offsets > 0);
+    }
+}
@@ -0,0 +1,512 @@
+//! Plain-object export for `postMessage`.
+//!
+//! wasm-bindgen classes and handles only mean something inside the module
+//! instance that created them, and class instances are not structured
+//! cloneable. [`to_plain_object`] turns a table into plain objects, arrays
+//! and typed arrays that survive `structuredClone`, and
+//! [`table_from_plain_object`] registers such an object as a table on the
+//! receiving side.
+//!
+//! Values travel in a transport type per column (see [`transport_type`]):
+//! fixed-width numbers as typed arrays of their stored values, so
+//! temporal columns keep their exact integers, and everything else as
+//! arrays of strings or `Uint8Array`s. The schema descriptor records the
+//! real type, which is restored with a lossless cast.
+
+use crate::compute::cast::parse_data_type;
+use crate::compute::run_end;
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::table::validate_unique_names;
+use crate::validation::{js_string, InvalidUtf8};
+use arrow::array::{
+    Array, ArrayRef, AsArray, BinaryArray, BooleanArray, PrimitiveArray, RecordBatch,
+    RecordBatchOptions,
+};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{
+    DataType, Field, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, Schema,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_select::concat::concat;
+use js_sys::{Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Default for [`PlainOptions::row_limit`].
+const DEFAULT_ROW_LIMIT: usize = 100_000;
+
+/// Options for [`to_plain_object`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct PlainOptions {
+    /// Export only the first `max_rows` rows, setting `truncated`.
+    max_rows: Option<usize>,
+    /// Refuse to export more rows than this.
+    row_limit: usize,
+}
+
+impl Default for PlainOptions {
+    fn default() -> Self {
+        Self {
+            max_rows: None,
+            row_limit: DEFAULT_ROW_LIMIT,
+        }
+    }
+}
+
+/// Options for [`table_from_plain_object`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct PlainImportOptions {
+    on_invalid_utf8: InvalidUtf8,
+}
+
+/// Schema descriptor of a plain table.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PlainSchema {
+    /// Columns in order.
+    pub fields: Vec<PlainField>,
+    /// Schema-level metadata.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// Descriptor of one column of a [`PlainSchema`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PlainField {
+    name: String,
+    /// arrow-rs type name, as accepted by `cast_column`.
+    #[serde(rename = "type")]
+    data_type: String,
+    nullable: bool,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+impl PlainSchema {
+    /// The Arrow schema this descriptor describes; field names must be
+    /// unique.
+    pub fn into_schema(self) -> Result<Schema> {
+        let fields = self
+            .fields
+            .into_iter()
+            .map(PlainField::into_field)
+            .collect::<Result<Vec<_>>>()?;
+        validate_unique_names(fields.iter().map(|field| field.name().as_str()))?;
+        Ok(Schema::new_with_metadata(fields, self.metadata))
+    }
+}
+
+impl PlainField {
+    /// The Arrow field this descriptor describes.
+    pub fn into_field(self) -> Result<Field> {
+        let data_type = parse_data_type(&self.data_type)?;
+        Ok(Field::new(self.name, data_type, self.nullable).with_metadata(self.metadata))
+    }
+}
+
+/// Type a column of `data_type` is carried as, or `None` when it has no
+/// plain representation (nested types).
+pub fn transport_type(data_type: &DataType) -> Option<DataType> {
+    Some(match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Boolean => data_type.clone(),
+        DataType::Float16 => DataType::Float32,
+        DataType::Date32 | DataType::Time32(_) => DataType::Int32,
+        DataType::Date64
+        | DataType::Timestamp(_, _)
+        | DataType::Time64(_)
+        | DataType::Duration(_) => DataType::Int64,
+        DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Decimal32(_, _)
+        | DataType::Decimal64(_, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => DataType::Utf8,
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => DataType::Binary,
+        DataType::Dictionary(_, value_type) => return transport_type(value_type),
+        DataType::RunEndEncoded(_, values) => return transport_type(values.data_type()),
+        _ => return None,
+    })
+}
+
+/// Cast that fails instead of nulling values `to` cannot represent.
+///
+/// Run-end encoded arrays are decoded first, and a run-end encoded `to` is
+/// reached by casting to its value type and encoding.
+pub fn lossless_cast(array: &ArrayRef, to: &DataType) -> Result<ArrayRef> {
+    if let DataType::RunEndEncoded(run_ends, values) = to {
+        return run_end::encode(
+            &lossless_cast(array, values.data_type())?,
+            run_ends.data_type(),
+        );
+    }
+    let array = &run_end::decode(array)?;
+    let options = CastOptions {
+        safe: false,
+        ..CastOptions::default()
+    };
+    Ok(cast_with_options(array, to, &options)?)
+}
+
+macro_rules! typed_array_to_js {
+    ($array:expr, $arrow:ty, $js:ty) => {
+        <$js>::from(&$array.as_primitive::<$arrow>().values()[..]).into()
+    };
+}
+
+/// JS representation of a transport-typed array. Null slots of typed
+/// arrays hold arbitrary values; `nulls` marks them.
+fn values_to_js(array: &dyn Array) -> Result<JsValue> {
+    Ok(match array.data_type() {
+        DataType::Int8 => typed_array_to_js!(array, Int8Type, js_sys::Int8Array),
+        DataType::Int16 => typed_array_to_js!(array, Int16Type, js_sys::Int16Array),
+        DataType::Int32 => typed_array_to_js!(array, Int32Type, js_sys::Int32Array),
+        DataType::Int64 => typed_array_to_js!(array, Int64Type, js_sys::BigInt64Array),
+        DataType::UInt8 => typed_array_to_js!(array, UInt8Type, js_sys::Uint8Array),
+        DataType::UInt16 => typed_array_to_js!(array, UInt16Type, js_sys::Uint16Array),
+        DataType::UInt32 => typed_array_to_js!(array, UInt32Type, js_sys::Uint32Array),
+        DataType::UInt64 => typed_array_to_js!(array, UInt64Type, js_sys::BigUint64Array),
+        DataType::Float32 => typed_array_to_js!(array, Float32Type, js_sys::Float32Array),
+        DataType::Float64 => typed_array_to_js!(array, Float64Type, js_sys::Float64Array),
+        DataType::Boolean => {
+            let bytes: Vec<u8> = array.as_boolean().values().iter().map(u8::from).collect();
+            Uint8Array::from(bytes.as_slice()).into()
+        }
+        DataType::Utf8 => {
+            let result = js_sys::Array::new();
+            for value in array.as_string::<i32>() {
+                result.push(&value.map_or(JsValue::NULL, JsValue::from_str));
+            }
+            result.into()
+        }
+        DataType::Binary => {
+            let result = js_sys::Array::new();
+            for value in array.as_binary::<i32>() {
+                result.push(&value.map_or(JsValue::NULL, |bytes| Uint8Array::from(bytes).into()));
+            }
+            result.into()
+        }
+        other => {
+            return Err(ArrowWasmError::Internal(format!(
+                "{other:?} is not a transport type"
+            )))
+        }
+    })
+}
+
+/// `Uint8Array` with 1 for valid and 0 for null slots, or `null` when the
+/// array has no nulls.
+fn validity_to_js(array: &dyn Array) -> JsValue {
+    array.logical_nulls().map_or(JsValue::NULL, |nulls| {
+        let bytes: Vec<u8> = nulls.iter().map(u8::from).collect();
+        Uint8Array::from(bytes.as_slice()).into()
+    })
+}
+
+fn export_plain(handle: TableHandle, options: &PlainOptions) -> Result<JsValue> {
+    let table = mem::get_table(handle)?;
+    validate_unique_names(table.schema.fields().iter().map(|f| f.name().as_str()))?;
+    let total = table.row_count();
+    let rows = options
+        .max_rows
+        .map_or(total, |max_rows| max_rows.min(total));
+    if rows > options.row_limit {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "{rows} rows exceed the plain-object row limit of {}; transfer \
+             write_table_to_ipc bytes instead, or raise rowLimit",
+            options.row_limit
+        )));
+    }
+
+    let columns = js_sys::Object::new();
+    let nulls = js_sys::Object::new();
+    let mut fields = Vec::with_capacity(table.column_count());
+    for (index, field) in table.schema.fields().iter().enumerate() {
+        let context = |e: ArrowWasmError| e.in_column("to_plain_object", field);
+        let transport = transport_type(field.data_type()).ok_or_else(|| {
+            context(ArrowWasmError::InvalidInput(
+                "nested columns have no plain representation; transfer IPC bytes instead"
+                    .to_string(),
+            ))
+        })?;
+        let chunks: Vec<&dyn Array> = table
+            .batches
+            .iter()
+            .map(|batch| batch.column(index).as_ref())
+            .collect();
+        let combined = concat(&chunks).map_err(|e| context(e.into()))?;
+        let values = lossless_cast(&combined.slice(0, rows), &transport).map_err(context)?;
+        set_property(&columns, field.name(), &values_to_js(values.as_ref())?)?;
+        set_property(&nulls, field.name(), &validity_to_js(values.as_ref()))?;
+        fields.push(PlainField {
+            name: field.name().clone(),
+            data_type: format!("{:?}", field.data_type()),
+            nullable: field.is_nullable(),
+            metadata: field.metadata().clone(),
+        });
+    }
+
+    let schema = PlainSchema {
+        fields,
+        metadata: table.schema.metadata().clone(),
+    };
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    let result = js_sys::Object::new();
+    set_property(
+        &result,
+        "schema",
+        &schema
+            .serialize(&serializer)
+            .map_err(ArrowWasmError::from)?,
+    )?;
+    set_property(&result, "columns", &columns)?;
+    set_property(&result, "nulls", &nulls)?;
+    set_property(&result, "rowCount", &JsValue::from_f64(rows as f64))?;
+    set_property(&result, "truncated", &JsValue::from_bool(rows < total))?;
+    Ok(result.into())
+}
+
+/// `target[key]`, or `undefined` when `target` is not an object.
+fn get_property(target: &JsValue, key: &str) -> JsValue {
+    if target.is_object() {
+        Reflect::get(target, &key.into()).unwrap_or(JsValue::UNDEFINED)
+    } else {
+        JsValue::UNDEFINED
+    }
+}
+
+fn missing(key: &str) -> ArrowWasmError {
+    ArrowWasmError::InvalidInput(format!("Plain table has no valid '{key}'"))
+}
+
+macro_rules! typed_array_from_js {
+    ($value:expr, $js:ty, $arrow:ty, $nulls:expr) => {{
+        let typed = $value.dyn_ref::<$js>().ok_or_else(|| {
+            ArrowWasmError::InvalidInput(format!("Expected a {}", stringify!($js)))
+        })?;
+        Arc::new(PrimitiveArray::<$arrow>::new(typed.to_vec().into(), $nulls)) as ArrayRef
+    }};
+}
+
+/// Elements of a JS array, or an error naming what was expected.
+fn array_elements(value: &JsValue) -> Result<js_sys::Array> {
+    value
+        .dyn_ref::<js_sys::Array>()
+        .cloned()
+        .ok_or_else(|| ArrowWasmError::InvalidInput("Expected an array".to_string()))
+}
+
+/// Utf8 array from JS strings or `Uint8Array`s of UTF-8 bytes; anything
+/// else is null. With [`InvalidUtf8::Binary`], bytes that are not UTF-8
+/// make the result a Binary array instead.
+fn text_from_js(elements: &js_sys::Array, policy: InvalidUtf8) -> Result<ArrayRef> {
+    let mut values = Vec::with_capacity(elements.length() as usize);
+    let mut keep_binary = false;
+    for (row, element) in elements.iter().enumerate() {
+        if let Some(text) = js_string(&element, policy, row)? {
+            values.push(Some(text.into_bytes()));
+            continue;
+        }
+        let Some(bytes) = element.dyn_ref::<Uint8Array>().map(Uint8Array::to_vec) else {
+            values.push(None);
+            continue;
+        };
+        match (std::str::from_utf8(&bytes), policy) {
+            (Ok(_), _) => values.push(Some(bytes)),
+            (Err(e), InvalidUtf8::Error) => {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Invalid UTF-8 at row {row}, byte offset {}",
+                    e.valid_up_to()
+                )))
+            }
+            (Err(_), InvalidUtf8::Replace) => {
+                values.push(Some(
+                    String::from_utf8_lossy(&bytes).into_owned().into_bytes(),
+                ));
+            }
+            (Err(_), InvalidUtf8::Binary) => {
+                keep_binary = true;
+                values.push(Some(bytes));
+            }
+        }
+    }
+    let binary: ArrayRef = Arc::new(values.iter().map(Option::as_deref).collect::<BinaryArray>());
+    if keep_binary {
+        Ok(binary)
+    } else {
+        lossless_cast(&binary, &DataType::Utf8)
+    }
+}
+
+/// Rebuild a transport-typed array from its JS representation.
+fn values_from_js(
+    value: &JsValue,
+    transport: &DataType,
+    nulls: Option<NullBuffer>,
+    policy: InvalidUtf8,
+) -> Result<ArrayRef> {
+    Ok(match transport {
+        DataType::Int8 => typed_array_from_js!(value, js_sys::Int8Array, Int8Type, nulls),
+        DataType::Int16 => typed_array_from_js!(value, js_sys::Int16Array, Int16Type, nulls),
+        DataType::Int32 => typed_array_from_js!(value, js_sys::Int32Array, Int32Type, nulls),
+        DataType::Int64 => typed_array_from_js!(value, js_sys::BigInt64Array, Int64Type, nulls),
+        DataType::UInt8 => typed_array_from_js!(value, js_sys::Uint8Array, UInt8Type, nulls),
+        DataType::UInt16 => typed_array_from_js!(value, js_sys::Uint16Array, UInt16Type, nulls),
+        DataType::UInt32 => typed_array_from_js!(value, js_sys::Uint32Array, UInt32Type, nulls),
+        DataType::UInt64 => {
+            typed_array_from_js!(value, js_sys::BigUint64Array, UInt64Type, nulls)
+        }
+        DataType::Float32 => {
+            typed_array_from_js!(value, js_sys::Float32Array, Float32Type, nulls)
+        }
+        DataType::Float64 => {
+            typed_array_from_js!(value, js_sys::Float64Array, Float64Type, nulls)
+        }
+        DataType::Boolean => {
+            let bytes = value
+                .dyn_ref::<Uint8Array>()
+                .ok_or_else(|| ArrowWasmError::InvalidInput("Expected a Uint8Array".to_string()))?
+                .to_vec();
+            let values = bytes.iter().map(|byte| *byte != 0).collect();
+            Arc::new(BooleanArray::new(values, nulls))
+        }
+        DataType::Utf8 => text_from_js(&array_elements(value)?, policy)?,
+        DataType::Binary => {
+            let bytes: Vec<Option<Vec<u8>>> = array_elements(value)?
+                .iter()
+                .map(|element| element.dyn_ref::<Uint8Array>().map(Uint8Array::to_vec))
+                .collect();
+            Arc::new(bytes.iter().map(Option::as_deref).collect::<BinaryArray>())
+        }
+        other => {
+            return Err(ArrowWasmError::Internal(format!(
+                "{other:?} is not a transport type"
+            )))
+        }
+    })
+}
+
+fn import_plain(object: &JsValue, options: &PlainImportOptions) -> Result<TableHandle> {
+    let schema: PlainSchema = serde_wasm_bindgen::from_value(get_property(object, "schema"))
+        .map_err(ArrowWasmError::from)?;
+    let row_count: usize = serde_wasm_bindgen::from_value(get_property(object, "rowCount"))
+        .map_err(|_| missing("rowCount"))?;
+    let columns = get_property(object, "columns");
+    let nulls = get_property(object, "nulls");
+
+    let mut fields = Vec::with_capacity(schema.fields.len());
+    let mut arrays = Vec::with_capacity(schema.fields.len());
+    for plain in schema.fields {
+        let field = plain.into_field()?;
+        let context = |e: ArrowWasmError| e.in_column("table_from_plain_object", &field);
+        let transport = transport_type(field.data_type()).ok_or_else(|| {
+            context(ArrowWasmError::InvalidInput(
+                "type has no plain representation".to_string(),
+            ))
+        })?;
+        let validity = get_property(&nulls, field.name())
+            .dyn_ref::<Uint8Array>()
+            .map(|bytes| {
+                bytes
+                    .to_vec()
+                    .iter()
+                    .map(|byte| *byte != 0)
+                    .collect::<NullBuffer>()
+            });
+        let values = get_property(&columns, field.name());
+        if values.is_undefined() {
+            return Err(missing(&format!("columns.{}", field.name())));
+        }
+        let array = values_from_js(&values, &transport, validity, options.on_invalid_utf8)
+            .map_err(context)?;
+        if array.len() != row_count {
+            return Err(context(ArrowWasmError::InvalidInput(format!(
+                "{} values for {row_count} rows",
+                array.len()
+            ))));
+        }
+        if array.data_type() == &DataType::Binary && transport == DataType::Utf8 {
+            // Invalid text kept as bytes under `onInvalidUtf8: "binary"`.
+            fields.push(field.with_data_type(DataType::Binary));
+            arrays.push(array);
+            continue;
+        }
+        arrays.push(lossless_cast(&array, field.data_type()).map_err(context)?);
+        fields.push(field);
+    }
+
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata));
+    let options = RecordBatchOptions::new().with_row_count(Some(row_count));
+    let batch = RecordBatch::try_new_with_options(schema, arrays, &options)?;
+    mem::store_table(TableData::new(vec![batch])?)
+}
+
+/// Export a table as a structured-clone-safe plain object.
+///
+/// The result is `{schema, columns, nulls, rowCount, truncated}`:
+/// `schema` is `{fields: [{name, type, nullable, metadata}], metadata}`,
+/// `columns` maps each name to a typed array (numbers, booleans as 0/1
+/// bytes, temporal values as their stored integers) or an array of strings
+/// or `Uint8Array`s, and `nulls` maps each name to a `Uint8Array` validity
+/// mask (1 = valid) or `null`. Nested columns are not supported.
+///
+/// `options` is `{maxRows?, rowLimit?}`: `maxRows` keeps only the first
+/// rows and sets `truncated`; exporting more than `rowLimit` rows
+/// (default 100 000) is an error, since IPC bytes are the cheaper way to
+/// move large tables between workers.
+#[wasm_bindgen]
+pub fn to_plain_object(
+    handle: TableHandle,
+    options: JsValue,
+) -> std::result::Result<JsValue, JsValue> {
+    let options: PlainOptions = if options.is_undefined() || options.is_null() {
+        PlainOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    Ok(export_plain(handle, &options)?)
+}
+
+/// Register a table from an object produced by `to_plain_object`, e.g.
+/// after it went through `postMessage`.
+///
+/// Text columns may also hold `Uint8Array`s of UTF-8 bytes in place of
+/// strings. `options` is `{onInvalidUtf8?}`, deciding what happens to
+/// strings with lone surrogates and to bytes that are not UTF-8:
+/// `"replace"` (the default) substitutes U+FFFD, `"error"` fails with the
+/// row (and byte offset), and `"binary"` imports a column with invalid
+/// bytes as Binary instead of text.
+#[wasm_bindgen]
+pub fn table_from_plain_object(
+    object: JsValue,
+    options: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let options: PlainImportOptions = if options.is_undefined() || options.is_null() {
+        PlainImportOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    Ok(import_plain(&object, &options)?)
+}
@@ -0,0 +1,300 @@
+//! Null masks laid over whole columns, e.g. to redact rows without consent.
+//!
+//! A mask only replaces validity bitmaps: the value, offset and dictionary
+//! buffers of every masked column stay shared with the source, so redacting
+//! a column costs one bitmap per chunk whatever its type.
+
+use crate::column::{self, Column};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use crate::table::rebuild_table;
+use arrow::array::{make_array, Array, ArrayRef, AsArray, BooleanBufferBuilder};
+use arrow::buffer::{BooleanBuffer, NullBuffer};
+use arrow::datatypes::{DataType, FieldRef};
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+fn check_mask_len(entries: usize, rows: usize) -> Result<()> {
+    if entries == rows {
+        return Ok(());
+    }
+    Err(ArrowWasmError::InvalidInput(format!(
+        "Mask has {entries} entries but the column has {rows} rows"
+    )))
+}
+
+/// Rows to keep: the `true` entries of the Boolean column `mask`, whose
+/// null entries count as `false`. `rows` is the length it must have.
+fn mask_bits(mask: &Column, rows: usize) -> Result<BooleanBuffer> {
+    let (field, chunks) = mask.field_and_chunks()?;
+    if field.data_type() != &DataType::Boolean {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Mask column must be Boolean, got {:?}",
+            field.data_type()
+        )));
+    }
+    check_mask_len(chunks.iter().map(Array::len).sum(), rows)?;
+    let mut bits = BooleanBufferBuilder::new(rows);
+    for chunk in &chunks {
+        let chunk = chunk.as_boolean();
+        match chunk.nulls() {
+            Some(nulls) => bits.append_buffer(&(chunk.values() & nulls.inner())),
+            None => bits.append_buffer(chunk.values()),
+        }
+    }
+    Ok(bits.finish())
+}
+
+/// `chunk` with the rows cleared in `keep` made null on top of its own
+/// nulls. The chunk itself is returned when `keep` clears nothing.
+fn overlay(chunk: &ArrayRef, keep: BooleanBuffer) -> Result<ArrayRef> {
+    let keep = NullBuffer::new(keep);
+    if keep.null_count() == 0 || chunk.data_type() == &DataType::Null {
+        return Ok(Arc::clone(chunk));
+    }
+    let nulls = NullBuffer::union(Some(&keep), chunk.nulls());
+    Ok(make_array(
+        chunk.to_data().into_builder().nulls(nulls).build()?,
+    ))
+}
+
+/// [`overlay`] over consecutive `chunks`, which `keep` spans end to end.
+fn overlay_chunks(chunks: &[ArrayRef], keep: &BooleanBuffer) -> Result<Vec<ArrayRef>> {
+    let mut offset = 0;
+    chunks
+        .iter()
+        .map(|chunk| {
+            let masked = overlay(chunk, keep.slice(offset, chunk.len()));
+            offset += chunk.len();
+            masked
+        })
+        .collect()
+}
+
+/// `field`, made nullable when `chunks` hold nulls.
+fn widened(field: &FieldRef, chunks: &[ArrayRef]) -> FieldRef {
+    if field.is_nullable() || chunks.iter().all(|chunk| chunk.null_count() == 0) {
+        return Arc::clone(field);
+    }
+    Arc::new(field.as_ref().clone().with_nullable(true))
+}
+
+fn masked_column(
+    column: &Column,
+    keep: impl FnOnce(usize) -> Result<BooleanBuffer>,
+) -> Result<Column> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let keep = keep(chunks.iter().map(Array::len).sum())?;
+    let masked = overlay_chunks(&chunks, &keep)?;
+    column::store_column(widened(&field, &masked), masked)
+}
+
+/// Null out the rows of `column` where the Boolean `mask` is `false` or
+/// null, e.g. to redact rows without consent.
+///
+/// Only the validity bitmap is replaced: it becomes the AND of the
+/// column's own validity and the mask, so existing nulls stay null, and
+/// the value buffers are shared with the source rather than copied. Works
+/// for primitive, string, binary, nested and dictionary columns; run-end
+/// encoded and union columns, which have no validity bitmap, fail. The
+/// mask must have one entry per row.
+#[wasm_bindgen]
+pub fn apply_null_mask(column: &Column, mask: &Column) -> std::result::Result<Column, JsValue> {
+    Ok(masked_column(column, |rows| mask_bits(mask, rows))?)
+}
+
+/// `apply_null_mask` with the mask as bytes, one per row: `0` makes the
+/// row null and any other value keeps it.
+#[wasm_bindgen]
+pub fn apply_null_mask_bytes(column: &Column, mask: &[u8]) -> std::result::Result<Column, JsValue> {
+    Ok(masked_column(column, |rows| {
+        check_mask_len(mask.len(), rows)?;
+        Ok(BooleanBuffer::collect_bool(rows, |row| mask[row] != 0))
+    })?)
+}
+
+/// Null out the rows where the Boolean `mask` is `false` or null in each
+/// of `columns`, like `apply_null_mask`, returning a new table.
+///
+/// Every other column, and the value buffers of the masked ones, are shared
+/// with the source. Masked fields become nullable when they gain nulls.
+/// Unknown column names and a mask without one entry per row fail.
+#[wasm_bindgen]
+pub fn redact_rows(
+    handle: TableHandle,
+    columns: Vec<String>,
+    mask: &Column,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let keep = mask_bits(mask, table.row_count())?;
+    let indices = columns
+        .iter()
+        .map(|name| {
+            table
+                .schema
+                .index_of(name)
+                .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    let mut batch_columns: Vec<Vec<ArrayRef>> = table
+        .batches
+        .iter()
+        .map(|batch| batch.columns().to_vec())
+        .collect();
+    for index in indices {
+        let chunks: Vec<ArrayRef> = table
+            .batches
+            .iter()
+            .map(|batch| Arc::clone(batch.column(index)))
+            .collect();
+        let masked = overlay_chunks(&chunks, &keep)?;
+        fields[index] = widened(&fields[index], &masked);
+        for (columns, chunk) in batch_columns.iter_mut().zip(masked) {
+            columns[index] = chunk;
+        }
+    }
+    Ok(mem::store_table(rebuild_table(
+        &table,
+        fields,
+        batch_columns,
+    )?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::TableData;
+    use arrow::array::{BooleanArray, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Int32Type, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    fn stored(field: Field, chunks: Vec<ArrayRef>) -> Column {
+        column::store_column(Arc::new(field), chunks).unwrap()
+    }
+
+    fn mask(values: &[Option<bool>]) -> Column {
+        let mask: ArrayRef = Arc::new(BooleanArray::from(values.to_vec()));
+        stored(Field::new("mask", DataType::Boolean, true), vec![mask])
+    }
+
+    fn chunks(column: &Column) -> Vec<ArrayRef> {
+        column.field_and_chunks().unwrap().1
+    }
+
+    #[test]
+    fn mask_shares_the_values_buffer() {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 4]));
+        let source = stored(Field::new("x", DataType::Int32, false), vec![values]);
+        let masked =
+            apply_null_mask(&source, &mask(&[Some(true), Some(false), None, Some(true)])).unwrap();
+
+        let before = chunks(&source)[0].to_data();
+        let after = chunks(&masked)[0].to_data();
+        assert_eq!(before.buffers()[0].as_ptr(), after.buffers()[0].as_ptr());
+        assert_eq!(masked.null_count().unwrap(), 2);
+        assert!(masked.field().unwrap().is_nullable());
+        let after = chunks(&masked)[0].as_primitive::<Int32Type>().clone();
+        assert_eq!(
+            after.iter().collect::<Vec<_>>(),
+            [Some(1), None, None, Some(4)]
+        );
+    }
+
+    #[test]
+    fn mask_shares_string_offsets_and_values() {
+        let values: ArrayRef = Arc::new(StringArray::from(vec!["a", "bb", "ccc"]));
+        let source = stored(Field::new("s", DataType::Utf8, false), vec![values]);
+        let masked = apply_null_mask_bytes(&source, &[1, 0, 7]).unwrap();
+
+        let before = chunks(&source)[0].to_data();
+        let after = chunks(&masked)[0].to_data();
+        for (before, after) in before.buffers().iter().zip(after.buffers()) {
+            assert_eq!(before.as_ptr(), after.as_ptr());
+        }
+        assert_eq!(masked.null_count().unwrap(), 1);
+        assert!(chunks(&masked)[0].is_null(1));
+    }
+
+    #[test]
+    fn null_count_keeps_existing_nulls() {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3), None]));
+        let source = stored(Field::new("x", DataType::Int32, true), vec![values]);
+        let masked = apply_null_mask(
+            &source,
+            &mask(&[Some(true), Some(true), Some(false), Some(false)]),
+        )
+        .unwrap();
+        assert_eq!(source.null_count().unwrap(), 2);
+        assert_eq!(masked.null_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn keep_everything_returns_the_same_chunks() {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
+        let source = stored(Field::new("x", DataType::Int32, false), vec![values]);
+        let masked = apply_null_mask(&source, &mask(&[Some(true), Some(true)])).unwrap();
+        assert!(Arc::ptr_eq(&chunks(&source)[0], &chunks(&masked)[0]));
+        assert!(!masked.field().unwrap().is_nullable());
+        assert_eq!(masked.null_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn mask_length_must_match() {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let source = stored(Field::new("x", DataType::Int32, false), vec![values]);
+        let short = mask(&[Some(true)]);
+        assert!(matches!(
+            masked_column(&source, |rows| mask_bits(&short, rows)),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            masked_column(&source, |rows| {
+                check_mask_len(2, rows)?;
+                Ok(BooleanBuffer::new_set(rows))
+            }),
+            Err(ArrowWasmError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn redact_rows_spans_batches() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let batch = |ids: Vec<i32>, names: Vec<&str>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(ids)),
+                    Arc::new(StringArray::from(names)),
+                ],
+            )
+            .unwrap()
+        };
+        let source = TableData::new(vec![
+            batch(vec![1, 2], vec!["a", "b"]),
+            batch(vec![3], vec!["c"]),
+        ])
+        .unwrap();
+        let handle = mem::store_table(source.clone()).unwrap();
+        let redacted = redact_rows(
+            handle,
+            vec!["name".to_string()],
+            &mask(&[Some(true), Some(false), Some(false)]),
+        )
+        .unwrap();
+
+        let table = mem::get_table(redacted).unwrap();
+        assert!(!table.schema.field(0).is_nullable());
+        assert!(table.schema.field(1).is_nullable());
+        for (before, after) in source.batches.iter().zip(&table.batches) {
+            assert!(Arc::ptr_eq(before.column(0), after.column(0)));
+        }
+        assert_eq!(table.batches[0].column(1).null_count(), 1);
+        assert_eq!(table.batches[1].column(1).null_count(), 1);
+        assert!(table.batches[1].column(1).is_null(0));
+    }
+}
@@ -0,0 +1,476 @@
+//! Reshaping tables between long and wide layouts.
+
+use crate::compute::cast::cast_safe;
+use crate::compute::keys::{visit_keys, Key};
+use crate::compute::string_ops::map_strings;
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::table::validate_unique_names;
+use arrow::array::{Array, ArrayRef, AsArray, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Float64Type, Schema};
+use arrow_select::concat::concat;
+use arrow_select::take::take;
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Aggregation applied to the values of each (index, column) cell.
+#[derive(Debug, Clone, Copy)]
+enum PivotAgg {
+    Sum,
+    Mean,
+}
+
+/// Running sum and count of one cell.
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    sum: f64,
+    count: usize,
+}
+
+fn combined(chunks: &[ArrayRef]) -> Result<ArrayRef> {
+    if let [chunk] = chunks {
+        return Ok(Arc::clone(chunk));
+    }
+    let refs: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+    Ok(concat(&refs)?)
+}
+
+fn pivot_table(
+    handle: TableHandle,
+    index: &str,
+    columns: &str,
+    values: &str,
+    agg: &str,
+) -> Result<TableData> {
+    let agg = match agg {
+        "sum" => PivotAgg::Sum,
+        "mean" => PivotAgg::Mean,
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Unknown aggregation '{other}', expected 'sum' or 'mean'"
+            )))
+        }
+    };
+    let table = mem::get_table(handle)?;
+    let index_field = table
+        .schema
+        .field_with_name(index)
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{index}' not found")))?
+        .clone();
+    let index_array = combined(&table.get_column_by_name(index)?)?;
+    let pivot_keys = map_strings(
+        combined(&table.get_column_by_name(columns)?)?.as_ref(),
+        str::to_string,
+    )?;
+    let value_array = combined(&table.get_column_by_name(values)?)?;
+    if !value_array.data_type().is_numeric() {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Values column '{values}' must be numeric, got {:?}",
+            value_array.data_type()
+        )));
+    }
+    let value_array = cast_safe(&value_array, &DataType::Float64)?;
+    let value_array = value_array.as_primitive::<Float64Type>();
+
+    let mut row_of_key: HashMap<Option<Key>, usize> = HashMap::new();
+    let mut first_rows: Vec<u64> = Vec::new();
+    let mut row_groups = vec![0; index_array.len()];
+    visit_keys(index_array.as_ref(), &mut |row, key| {
+        let next = first_rows.len();
+        let group = *row_of_key.entry(key).or_insert(next);
+        if group == next {
+            first_rows.push(row as u64);
+        }
+        row_groups[row] = group;
+    })?;
+
+    let mut column_of_key: HashMap<&str, usize> = HashMap::new();
+    let mut column_names: Vec<&str> = Vec::new();
+    let mut cells: Vec<Vec<Cell>> = Vec::new();
+    for (row, key) in pivot_keys.iter().enumerate() {
+        let Some(key) = key.as_deref() else {
+            continue;
+        };
+        let column = *column_of_key.entry(key).or_insert_with(|| {
+            column_names.push(key);
+            cells.push(vec![Cell::default(); first_rows.len()]);
+            column_names.len() - 1
+        });
+        if value_array.is_valid(row) {
+            let cell = &mut cells[column][row_groups[row]];
+            cell.sum += value_array.value(row);
+            cell.count += 1;
+        }
+    }
+
+    let mut fields = vec![Arc::new(index_field)];
+    let mut arrays = vec![take(
+        index_array.as_ref(),
+        &UInt64Array::from(first_rows),
+        None,
+    )?];
+    for (name, column) in column_names.iter().zip(cells) {
+        fields.push(Arc::new(Field::new(*name, DataType::Float64, true)));
+        let values: Float64Array = column
+            .into_iter()
+            .map(|cell| match (cell.count, agg) {
+                (0, _) => None,
+                (_, PivotAgg::Sum) => Some(cell.sum),
+                (count, PivotAgg::Mean) => Some(cell.sum / count as f64),
+            })
+            .collect();
+        arrays.push(Arc::new(values));
+    }
+    validate_unique_names(fields.iter().map(|field| field.name().as_str()))?;
+    let schema = Arc::new(Schema::new(fields));
+    TableData::new(vec![RecordBatch::try_new(schema, arrays)?])
+}
+
+/// Reshape a long table to wide.
+///
+/// The result has one row per distinct `index` value and one Float64 column
+/// per distinct (string) `columns` value, holding the `agg` (`"sum"` or
+/// `"mean"`) of the numeric `values` for that combination.
+///
+/// Rows and pivoted columns appear in first-seen order; a null `index`
+/// value forms its own row, and rows with a null `columns` value are
+/// skipped. Combinations with no non-null value are null.
+#[wasm_bindgen]
+pub fn pivot(
+    handle: TableHandle,
+    index: &str,
+    columns: &str,
+    values: &str,
+    agg: &str,
+) -> std::result::Result<TableHandle, JsValue> {
+    Ok(mem::store_table(pivot_table(
+        handle, index, columns, values, agg,
+    )?)?)
+}
+
+fn melt_table(
+    handle: TableHandle,
+    id_vars: &[String],
+    value_vars: &[String],
+    var_name: &str,
+    value_name: &str,
+) -> Result<TableData> {
+    let table = mem::get_table(handle)?;
+    let index_of = |name: &String| {
+        table
+            .schema
+            .index_of(name)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))
+    };
+    let id_indices = id_vars.iter().map(index_of).collect::<Result<Vec<_>>>()?;
+    let value_indices = if value_vars.is_empty() {
+        (0..table.column_count())
+            .filter(|index| !id_indices.contains(index))
+            .collect()
+    } else {
+        value_vars
+            .iter()
+            .map(index_of)
+            .collect::<Result<Vec<_>>>()?
+    };
+    let Some(first) = value_indices.first() else {
+        return Err(ArrowWasmError::InvalidInput(
+            "No value columns to melt".to_string(),
+        ));
+    };
+    let value_type = table.schema.field(*first).data_type();
+    for index in &value_indices {
+        let field = table.schema.field(*index);
+        if field.data_type() != value_type {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Value columns must share one type: '{}' is {:?} but '{}' is {:?}",
+                field.name(),
+                field.data_type(),
+                table.schema.field(*first).name(),
+                value_type
+            )));
+        }
+    }
+
+    let mut fields: Vec<_> = id_indices
+        .iter()
+        .map(|index| Arc::clone(&table.schema.fields()[*index]))
+        .collect();
+    fields.push(Arc::new(Field::new(var_name, DataType::Utf8, false)));
+    let value_nullable = value_indices
+        .iter()
+        .any(|index| table.schema.field(*index).is_nullable());
+    fields.push(Arc::new(Field::new(
+        value_name,
+        value_type.clone(),
+        value_nullable,
+    )));
+    validate_unique_names(fields.iter().map(|field| field.name().as_str()))?;
+    let schema = Arc::new(Schema::new_with_metadata(
+        fields,
+        table.schema.metadata().clone(),
+    ));
+
+    let mut batches = Vec::with_capacity(value_indices.len() * table.batches.len());
+    for value_index in &value_indices {
+        let name = table.schema.field(*value_index).name();
+        for batch in &table.batches {
+            let mut columns: Vec<ArrayRef> = id_indices
+                .iter()
+                .map(|index| Arc::clone(batch.column(*index)))
+                .collect();
+            columns.push(Arc::new(StringArray::from(vec![
+                name.as_str();
+                batch.num_rows()
+            ])));
+            columns.push(Arc::clone(batch.column(*value_index)));
+            batches.push(RecordBatch::try_new(Arc::clone(&schema), columns)?);
+        }
+    }
+    TableData::new(batches)
+}
+
+/// Reshape a wide table to long, the inverse of [`pivot`].
+///
+/// Each of `value_vars` (an array of names; every non-id column when empty)
+/// is stacked under `value_name`, with its name in the Utf8 column
+/// `var_name` and the `id_vars` columns repeated alongside. The value
+/// columns must share one data type. Rows are grouped by value column, in
+/// the order given, so the result has rows × value columns rows. Id and
+/// value arrays are shared with the source.
+#[wasm_bindgen]
+pub fn melt(
+    handle: TableHandle,
+    id_vars: JsValue,
+    value_vars: JsValue,
+    var_name: &str,
+    value_name: &str,
+) -> std::result::Result<TableHandle, JsValue> {
+    let id_vars: Vec<String> =
+        serde_wasm_bindgen::from_value(id_vars).map_err(ArrowWasmError::from)?;
+    let value_vars: Vec<String> = if value_vars.is_undefined() || value_vars.is_null() {
+        Vec::new()
+    } else {
+        serde_wasm_bindgen::from_value(value_vars).map_err(ArrowWasmError::from)?
+    };
+    Ok(mem::store_table(melt_table(
+        handle,
+        &id_vars,
+        &value_vars,
+        var_name,
+        value_name,
+    )?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::Int32Type;
+
+    /// Readings of `metric` per `city` in two batches, with a null city, a
+    /// null metric and a null value.
+    fn readings() -> TableHandle {
+        let batch = |city: Vec<Option<&str>>, metric: Vec<Option<&str>>, v: Vec<Option<i32>>| {
+            RecordBatch::try_from_iter([
+                ("city", Arc::new(StringArray::from(city)) as ArrayRef),
+                ("metric", Arc::new(StringArray::from(metric))),
+                ("v", Arc::new(Int32Array::from(v))),
+            ])
+            .unwrap()
+        };
+        let batches = vec![
+            batch(
+                vec![Some("A"), Some("A"), Some("B"), None],
+                vec![Some("temp"), Some("wind"), Some("temp"), Some("temp")],
+                vec![Some(1), Some(2), Some(3), Some(4)],
+            ),
+            batch(
+                vec![Some("A"), Some("B"), Some("B")],
+                vec![Some("temp"), None, Some("wind")],
+                vec![Some(5), Some(6), None],
+            ),
+        ];
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    fn column(table: &TableData, name: &str) -> Vec<Option<f64>> {
+        let chunks = table.get_column_by_name(name).unwrap();
+        combined(&chunks)
+            .unwrap()
+            .as_primitive::<Float64Type>()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn long_rows_become_wide_columns() {
+        let wide = pivot_table(readings(), "city", "metric", "v", "sum").unwrap();
+        let names: Vec<&str> = wide
+            .schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(names, ["city", "temp", "wind"]);
+        assert_eq!(wide.batches.len(), 1);
+        let cities = combined(&wide.get_column_by_name("city").unwrap()).unwrap();
+        let cities: Vec<Option<&str>> = cities.as_string::<i32>().iter().collect();
+        assert_eq!(cities, [Some("A"), Some("B"), None]);
+        // B's only wind reading is null, and the null metric row is skipped.
+        assert_eq!(column(&wide, "temp"), [Some(6.0), Some(3.0), Some(4.0)]);
+        assert_eq!(column(&wide, "wind"), [Some(2.0), None, None]);
+
+        let wide = pivot_table(readings(), "city", "metric", "v", "mean").unwrap();
+        assert_eq!(column(&wide, "temp"), [Some(3.0), Some(3.0), Some(4.0)]);
+        assert_eq!(column(&wide, "wind"), [Some(2.0), None, None]);
+    }
+
+    #[test]
+    fn pivot_rejects_bad_arguments() {
+        let message = |result: Result<TableData>| result.map(drop).unwrap_err().to_string();
+        let table = readings();
+        assert!(message(pivot_table(table, "city", "metric", "v", "max"))
+            .contains("Unknown aggregation 'max'"));
+        assert!(
+            message(pivot_table(table, "city", "metric", "metric", "sum"))
+                .contains("Values column 'metric' must be numeric")
+        );
+        assert!(message(pivot_table(table, "town", "metric", "v", "sum"))
+            .contains("Column 'town' not found"));
+        // A metric named like the index column would shadow it.
+        let batch = RecordBatch::try_from_iter([
+            ("city", Arc::new(StringArray::from(vec!["A"])) as ArrayRef),
+            ("metric", Arc::new(StringArray::from(vec!["city"]))),
+            ("v", Arc::new(Int32Array::from(vec![1]))),
+        ])
+        .unwrap();
+        let table = mem::store_table(TableData::new(vec![batch]).unwrap()).unwrap();
+        assert!(message(pivot_table(table, "city", "metric", "v", "sum"))
+            .contains("Duplicate column name 'city'"));
+    }
+
+    /// `id` plus readings `a`, `b` and `c` in batches of two and one rows;
+    /// `b` holds a null.
+    fn wide_readings() -> TableHandle {
+        let ints = |values: Vec<Option<i32>>| Arc::new(Int32Array::from(values)) as ArrayRef;
+        let batch = |id: Vec<&str>, a, b, c| {
+            RecordBatch::try_from_iter([
+                ("id", Arc::new(StringArray::from(id)) as ArrayRef),
+                ("a", ints(a)),
+                ("b", ints(b)),
+                ("c", ints(c)),
+            ])
+            .unwrap()
+        };
+        let batches = vec![
+            batch(
+                vec!["x", "y"],
+                vec![Some(1), Some(2)],
+                vec![Some(10), None],
+                vec![Some(100), Some(200)],
+            ),
+            batch(vec!["z"], vec![Some(3)], vec![Some(30)], vec![Some(300)]),
+        ];
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    fn strings(table: &TableData, name: &str) -> Vec<Option<String>> {
+        let chunks = table.get_column_by_name(name).unwrap();
+        combined(&chunks)
+            .unwrap()
+            .as_string::<i32>()
+            .iter()
+            .map(|value| value.map(String::from))
+            .collect()
+    }
+
+    fn owned(values: &[&str]) -> Vec<Option<String>> {
+        values
+            .iter()
+            .map(|value| Some((*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn melt_stacks_value_columns_under_their_names() {
+        let long = melt_table(
+            wide_readings(),
+            &["id".to_string()],
+            &[],
+            "variable",
+            "value",
+        )
+        .unwrap();
+        let names: Vec<&str> = long
+            .schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(names, ["id", "variable", "value"]);
+        assert_eq!(long.row_count(), 3 * 3);
+        assert_eq!(
+            strings(&long, "id"),
+            owned(&["x", "y", "z", "x", "y", "z", "x", "y", "z"])
+        );
+        assert_eq!(
+            strings(&long, "variable"),
+            owned(&["a", "a", "a", "b", "b", "b", "c", "c", "c"])
+        );
+        let values = combined(&long.get_column_by_name("value").unwrap()).unwrap();
+        let values: Vec<Option<i32>> = values.as_primitive::<Int32Type>().iter().collect();
+        assert_eq!(
+            values,
+            [
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(10),
+                None,
+                Some(30),
+                Some(100),
+                Some(200),
+                Some(300)
+            ]
+        );
+        assert!(long.schema.field_with_name("value").unwrap().is_nullable());
+        assert!(!long
+            .schema
+            .field_with_name("variable")
+            .unwrap()
+            .is_nullable());
+    }
+
+    #[test]
+    fn melt_follows_the_given_value_columns() {
+        let value_vars = ["c".to_string(), "a".to_string()];
+        let long = melt_table(wide_readings(), &[], &value_vars, "metric", "reading").unwrap();
+        assert_eq!(long.row_count(), 3 * 2);
+        assert_eq!(long.column_count(), 2);
+        assert_eq!(
+            strings(&long, "metric"),
+            owned(&["c", "c", "c", "a", "a", "a"])
+        );
+
+        let message = |result: Result<TableData>| result.map(drop).unwrap_err().to_string();
+        let mixed = ["a".to_string(), "id".to_string()];
+        assert!(message(melt_table(
+            wide_readings(),
+            &[],
+            &mixed,
+            "variable",
+            "value"
+        ))
+        .contains("Value columns must share one type: 'id' is Utf8 but 'a' is Int32"));
+        assert!(message(melt_table(
+            wide_readings(),
+            &["id".to_string()],
+            &[],
+            "id",
+            "value"
+        ))
+        .contains("Duplicate column name 'id'"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Row-major views of registered tables.
+//!
+//! A [`RowIterator`] is a table handle plus a cursor; each `next()` converts
+//! only the row it yields, so JS can walk a large table without building an
+//! object for every row up front. [`to_rows_compact`] converts every row at
+//! once, as positional arrays rather than objects.
+
+use crate::convert::{check_field, set_property, table_options, value_to_js, ConversionOptions};
+use crate::errors::Result;
+use crate::mem::{self, TableHandle};
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use wasm_bindgen::prelude::*;
+
+/// Call `f(name, value)` for every column of row `row` of `batch`, with
+/// values converted under `options`; `op` names the caller in errors.
+fn visit_row(
+    batch: &RecordBatch,
+    row: usize,
+    options: ConversionOptions,
+    op: &'static str,
+    mut f: impl FnMut(&str, JsValue) -> Result<()>,
+) -> Result<()> {
+    let schema = batch.schema();
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let value =
+            value_to_js(column.as_ref(), row, options).map_err(|e| e.in_column(op, field))?;
+        f(field.name(), value)?;
+    }
+    Ok(())
+}
+
+/// [`check_field`] for every column of `schema`, once per call rather than
+/// per row.
+fn check_fields(schema: &Schema, options: ConversionOptions) -> Result<()> {
+    schema
+        .fields()
+        .iter()
+        .try_for_each(|field| check_field(field, options))
+}
+
+/// Cursor over the rows of a registered table, in row order.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowIterator {
+    handle: TableHandle,
+    options: ConversionOptions,
+    batch: usize,
+    offset: usize,
+    position: usize,
+}
+
+impl RowIterator {
+    /// Object of the row under the cursor, advancing past it, or `None`
+    /// once every row has been yielded.
+    fn next_row(&mut self) -> Result<Option<js_sys::Object>> {
+        let table = mem::get_table(self.handle)?;
+        while let Some(batch) = table.batches.get(self.batch) {
+            if self.offset < batch.num_rows() {
+                let row = js_sys::Object::new();
+                visit_row(
+                    batch,
+                    self.offset,
+                    self.options,
+                    "table_rows",
+                    |name, value| set_property(&row, name, &value),
+                )?;
+                self.offset += 1;
+                self.position += 1;
+                return Ok(Some(row));
+            }
+            self.batch += 1;
+            self.offset = 0;
+        }
+        Ok(None)
+    }
+}
+
+#[wasm_bindgen]
+impl RowIterator {
+    /// Handle of the table being iterated.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn handle(&self) -> TableHandle {
+        self.handle
+    }
+
+    /// Index of the row the next call to `next` yields.
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// `{value, done}` as the JS iterator protocol expects: `value` is the
+    /// next row as an object keyed by column name, and `done` is `true`
+    /// once the table is exhausted.
+    ///
+    /// Throws when the table has been freed or a value cannot be converted.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_result(&mut self) -> std::result::Result<js_sys::Object, JsValue> {
+        let result = js_sys::Object::new();
+        let row = self.next_row()?;
+        set_property(&result, "done", &row.is_none().into())?;
+        set_property(
+            &result,
+            "value",
+            &row.map_or(JsValue::UNDEFINED, JsValue::from),
+        )?;
+        Ok(result)
+    }
+}
+
+/// Iterate the rows of the table `handle` lazily.
+///
+/// Values follow the table's `ConversionPolicy` with the fields of
+/// `overrides` replacing its own, as in `Column.get`. The iterator speaks
+/// the iterator protocol, so `for...of` works once it is wrapped as an
+/// iterable: `for (const row of {[Symbol.iterator]: () => rows}) ...`.
+///
+/// The table is looked up on every `next()`, so the iterator keeps no data
+/// alive; once the table is freed, `next()` throws.
+#[wasm_bindgen]
+pub fn table_rows(
+    handle: TableHandle,
+    overrides: JsValue,
+) -> std::result::Result<RowIterator, JsValue> {
+    let table = mem::get_table(handle)?;
+    let options = table_options(handle).with_overrides(overrides)?;
+    check_fields(&table.schema, options)?;
+    Ok(RowIterator {
+        handle,
+        options,
+        batch: 0,
+        offset: 0,
+        position: 0,
+    })
+}
+
+/// Every row of the table `handle` as an array of its values in column
+/// order, e.g. `[[1, "a"], [2, "b"]]`.
+///
+/// Leaves out the column name repeated in every row object, which matters
+/// for large exports; `get_column_names` gives the names for positions.
+/// Values follow the table's `ConversionPolicy` with `overrides`, as in
+/// `table_rows`.
+#[wasm_bindgen]
+pub fn to_rows_compact(
+    handle: TableHandle,
+    overrides: JsValue,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    let table = mem::get_table(handle)?;
+    let options = table_options(handle).with_overrides(overrides)?;
+    check_fields(&table.schema, options)?;
+    let rows = js_sys::Array::new();
+    for batch in &table.batches {
+        for row in 0..batch.num_rows() {
+            let values = js_sys::Array::new();
+            visit_row(batch, row, options, "to_rows_compact", |_, value| {
+                values.push(&value);
+                Ok(())
+            })?;
+            rows.push(&values);
+        }
+    }
+    Ok(rows)
+}
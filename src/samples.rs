@@ -0,0 +1,338 @@
+//! Built-in sample datasets for demos, docs and smoke tests.
+//!
+//! Every dataset is generated on the fly from a fixed seed, so the same name
+//! always produces the same table:
+//!
+//! - `basic`: the 5-row `id`/`name` table returned by `create_test_table`.
+//! - `numeric_1k`: 1 000 rows of Int32/Int64/Float64/Float32 with nulls in
+//!   the nullable columns.
+//! - `strings_categorical`: 1 000 rows of low-cardinality strings, one
+//!   dictionary-encoded column and a sparse free-text column.
+//! - `timeseries_100k`: 100 000 one-minute UTC timestamps (with periodic
+//!   gaps) and a noisy Float64 signal, split into 10 batches.
+//! - `nested`: 100 rows with a `List<Utf8>` and a `Struct<x, y>` column.
+//! - `views`: 200 rows of `Utf8View` and `BinaryView` values, both inline
+//!   (up to 12 bytes) and out of line, with nulls.
+
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::rng::SplitMix64;
+use arrow::array::{
+    Array, ArrayRef, BinaryViewArray, Float32Array, Float64Array, Int32Array, Int64Array,
+    ListBuilder, RecordBatch, StringArray, StringBuilder, StringDictionaryBuilder, StringViewArray,
+    StructArray, TimestampMillisecondArray,
+};
+use arrow::datatypes::{DataType, Field, Fields, Int32Type, Schema, TimeUnit};
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+const SEED: u64 = 0x00A7_7055;
+const CITIES: [&str; 6] = ["Tokyo", "Osaka", "Paris", "Lima", "Oslo", "Cairo"];
+const STATUSES: [&str; 4] = ["active", "pending", "closed", "archived"];
+
+/// Names accepted by [`create_sample_table`].
+pub const SAMPLE_TABLES: [&str; 6] = [
+    "basic",
+    "numeric_1k",
+    "strings_categorical",
+    "timeseries_100k",
+    "nested",
+    "views",
+];
+
+fn basic() -> Result<Vec<RecordBatch>> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+            Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"])),
+        ],
+    )?;
+    Ok(vec![batch])
+}
+
+fn numeric_1k() -> Result<Vec<RecordBatch>> {
+    let mut rng = SplitMix64::new(SEED);
+    let rows = 1_000_i32;
+    let ids: Int32Array = (0..rows).collect();
+    let counts: Int64Array = (0..rows)
+        .map(|i| (i % 7 != 3).then(|| rng.next_below(10_000).cast_signed()))
+        .collect();
+    let values: Float64Array = (0..rows)
+        .map(|i| (i % 11 != 5).then(|| rng.next_f64().mul_add(200.0, -100.0)))
+        .collect();
+    let ratios: Float32Array = (0..rows).map(|_| rng.next_f64() as f32).collect();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("count", DataType::Int64, true),
+        Field::new("value", DataType::Float64, true),
+        Field::new("ratio", DataType::Float32, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(ids),
+        Arc::new(counts),
+        Arc::new(values),
+        Arc::new(ratios),
+    ];
+    Ok(vec![RecordBatch::try_new(schema, columns)?])
+}
+
+fn strings_categorical() -> Result<Vec<RecordBatch>> {
+    let mut rng = SplitMix64::new(SEED + 1);
+    let rows = 1_000;
+    // Builders are sized up front so the value buffers never regrow.
+    let longest = |names: &[&str]| names.iter().map(|name| name.len()).max().unwrap_or(0);
+    let mut cities = StringBuilder::with_capacity(rows, rows * longest(&CITIES));
+    let mut statuses = StringDictionaryBuilder::<Int32Type>::with_capacity(
+        rows,
+        STATUSES.len(),
+        STATUSES.iter().map(|status| status.len()).sum(),
+    );
+    let mut comments = StringBuilder::with_capacity(rows, rows.div_ceil(4) * "note #999".len());
+    for i in 0..rows {
+        cities.append_value(CITIES[rng.next_below(CITIES.len() as u64) as usize]);
+        statuses.append_value(STATUSES[rng.next_below(STATUSES.len() as u64) as usize]);
+        if i % 4 == 0 {
+            comments.append_value(format!("note #{i}"));
+        } else {
+            comments.append_null();
+        }
+    }
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("city", DataType::Utf8, false),
+        Field::new(
+            "status",
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            false,
+        ),
+        Field::new("comment", DataType::Utf8, true),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(cities.finish()),
+        Arc::new(statuses.finish()),
+        Arc::new(comments.finish()),
+    ];
+    Ok(vec![RecordBatch::try_new(schema, columns)?])
+}
+
+fn timeseries_100k() -> Result<Vec<RecordBatch>> {
+    const ROWS: i64 = 100_000;
+    const BATCH_ROWS: i64 = 10_000;
+    const START_MS: i64 = 1_704_067_200_000; // 2024-01-01T00:00:00Z
+    const MINUTE_MS: i64 = 60_000;
+
+    let mut rng = SplitMix64::new(SEED + 2);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("value", DataType::Float64, true),
+    ]));
+
+    // Every 97th minute is skipped so the series has regular gaps.
+    let mut minute = 0_i64;
+    let mut batches = Vec::new();
+    for batch_index in 0..ROWS / BATCH_ROWS {
+        let mut timestamps = Vec::with_capacity(BATCH_ROWS as usize);
+        let mut values = Vec::with_capacity(BATCH_ROWS as usize);
+        for row in 0..BATCH_ROWS {
+            if minute % 97 == 96 {
+                minute += 1;
+            }
+            timestamps.push(START_MS + minute * MINUTE_MS);
+            let x = (batch_index * BATCH_ROWS + row) as f64;
+            let signal = (x / 240.0).sin().mul_add(10.0, rng.next_f64());
+            values.push((row % 53 != 0).then_some(signal));
+            minute += 1;
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from(timestamps).with_timezone("UTC")),
+            Arc::new(Float64Array::from(values)),
+        ];
+        batches.push(RecordBatch::try_new(Arc::clone(&schema), columns)?);
+    }
+    Ok(batches)
+}
+
+fn nested() -> Result<Vec<RecordBatch>> {
+    let mut rng = SplitMix64::new(SEED + 3);
+    let rows = 100_i32;
+    let mut tags = ListBuilder::with_capacity(StringBuilder::new(), 100);
+    for i in 0..rows {
+        if i % 10 == 9 {
+            tags.append_null();
+            continue;
+        }
+        for _ in 0..rng.next_below(4) {
+            tags.values()
+                .append_value(STATUSES[rng.next_below(STATUSES.len() as u64) as usize]);
+        }
+        tags.append(true);
+    }
+    let xs: Float64Array = (0..rows).map(|i| f64::from(i) * 0.5).collect();
+    let ys: Float64Array = (0..rows).map(|_| rng.next_f64()).collect();
+    let point_fields = Fields::from(vec![
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+    ]);
+    let point = StructArray::new(
+        point_fields.clone(),
+        vec![Arc::new(xs) as ArrayRef, Arc::new(ys)],
+        None,
+    );
+    let tags = tags.finish();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("tags", tags.data_type().clone(), true),
+        Field::new("point", DataType::Struct(point_fields), false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new((0..rows).collect::<Int32Array>()),
+        Arc::new(tags),
+        Arc::new(point),
+    ];
+    Ok(vec![RecordBatch::try_new(schema, columns)?])
+}
+
+fn views() -> Result<Vec<RecordBatch>> {
+    let mut rng = SplitMix64::new(SEED + 4);
+    let rows = 200_u8;
+    // Every third city is repeated past the 12 bytes a view holds inline.
+    let cities: StringViewArray = (0..rows)
+        .map(|i| {
+            let city = CITIES[rng.next_below(CITIES.len() as u64) as usize];
+            match i % 3 {
+                0 => Some(format!("{city} metropolitan area")),
+                1 => Some(city.to_string()),
+                _ => (i % 5 != 0).then(|| city.to_string()),
+            }
+        })
+        .collect();
+    let payloads: BinaryViewArray = (0..rows)
+        .map(|i| (i % 7 != 6).then(|| vec![i; usize::from(i % 24)]))
+        .collect();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("city", DataType::Utf8View, true),
+        Field::new("payload", DataType::BinaryView, true),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new((0..i32::from(rows)).collect::<Int32Array>()),
+        Arc::new(cities),
+        Arc::new(payloads),
+    ];
+    Ok(vec![RecordBatch::try_new(schema, columns)?])
+}
+
+/// Generate the batches of the sample dataset called `name`.
+pub fn sample_batches(name: &str) -> Result<Vec<RecordBatch>> {
+    match name {
+        "basic" => basic(),
+        "numeric_1k" => numeric_1k(),
+        "strings_categorical" => strings_categorical(),
+        "timeseries_100k" => timeseries_100k(),
+        "nested" => nested(),
+        "views" => views(),
+        other => Err(ArrowWasmError::InvalidInput(format!(
+            "Unknown sample table '{other}', expected one of: {}",
+            SAMPLE_TABLES.join(", ")
+        ))),
+    }
+}
+
+/// Create one of the built-in sample datasets (see module docs).
+#[wasm_bindgen]
+pub fn create_sample_table(name: &str) -> std::result::Result<TableHandle, JsValue> {
+    let table_data = TableData::new(sample_batches(name)?)?;
+    Ok(mem::store_table(table_data)?)
+}
+
+/// Names of the built-in sample datasets.
+#[wasm_bindgen]
+pub fn list_sample_tables() -> Vec<String> {
+    SAMPLE_TABLES.iter().map(ToString::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows and column types of each dataset, as the module docs list them.
+    fn documented(name: &str) -> (usize, Vec<DataType>) {
+        let utc = || DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+        let strings = || DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let tags = || DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true)));
+        let point = || {
+            DataType::Struct(Fields::from(vec![
+                Field::new("x", DataType::Float64, false),
+                Field::new("y", DataType::Float64, false),
+            ]))
+        };
+        match name {
+            "basic" => (5, vec![DataType::Int32, DataType::Utf8]),
+            "numeric_1k" => (
+                1_000,
+                vec![
+                    DataType::Int32,
+                    DataType::Int64,
+                    DataType::Float64,
+                    DataType::Float32,
+                ],
+            ),
+            "strings_categorical" => (1_000, vec![DataType::Utf8, strings(), DataType::Utf8]),
+            "timeseries_100k" => (100_000, vec![utc(), DataType::Float64]),
+            "nested" => (100, vec![DataType::Int32, tags(), point()]),
+            "views" => (
+                200,
+                vec![DataType::Int32, DataType::Utf8View, DataType::BinaryView],
+            ),
+            other => panic!("no documented shape for {other}"),
+        }
+    }
+
+    #[test]
+    fn every_sample_is_deterministic_and_documented() {
+        for name in SAMPLE_TABLES {
+            let batches = sample_batches(name).unwrap();
+            assert_eq!(batches, sample_batches(name).unwrap(), "{name}");
+
+            let (rows, types) = documented(name);
+            assert_eq!(
+                batches.iter().map(RecordBatch::num_rows).sum::<usize>(),
+                rows,
+                "{name}"
+            );
+            let schema = batches[0].schema();
+            let actual: Vec<DataType> = schema
+                .fields()
+                .iter()
+                .map(|field| field.data_type().clone())
+                .collect();
+            assert_eq!(actual, types, "{name}");
+        }
+        assert_eq!(sample_batches("timeseries_100k").unwrap().len(), 10);
+    }
+
+    #[test]
+    fn sample_names_are_listed() {
+        assert_eq!(list_sample_tables(), SAMPLE_TABLES);
+        let Err(ArrowWasmError::InvalidInput(message)) = sample_batches("numeric_1m") else {
+            panic!("numeric_1m is not a sample");
+        };
+        assert_eq!(
+            message,
+            "Unknown sample table 'numeric_1m', expected one of: basic, numeric_1k, \
+             strings_categorical, timeseries_100k, nested, views"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Splitting a table into contiguous row ranges, e.g. one per worker.
+//!
+//! Shards are zero-copy: each is a list of batch slices sharing buffers with
+//! the source table. Cuts fall on batch boundaries where that keeps the
+//! shards balanced, so most shards reuse whole batches. `concat_tables`
+//! over the shards, in order, gives back the original rows.
+
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use arrow::array::RecordBatch;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Rows `[start, end)` of `table` as slices of its batches.
+fn slice_rows(table: &TableData, start: usize, end: usize) -> Result<TableData> {
+    let mut batches = Vec::new();
+    let mut offset = 0;
+    for batch in &table.batches {
+        let batch_start = offset;
+        offset += batch.num_rows();
+        let (from, to) = (start.max(batch_start), end.min(offset));
+        if from < to {
+            batches.push(batch.slice(from - batch_start, to - from));
+        }
+    }
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(Arc::clone(&table.schema)));
+    }
+    TableData::new(batches)
+}
+
+/// Row positions where a batch ends, excluding the end of the table.
+fn batch_boundaries(table: &TableData) -> Vec<usize> {
+    let mut boundaries = Vec::with_capacity(table.batches.len());
+    let mut offset = 0;
+    for batch in &table.batches {
+        offset += batch.num_rows();
+        boundaries.push(offset);
+    }
+    boundaries.pop();
+    boundaries
+}
+
+/// Cut positions splitting `rows` rows into `count` near-equal shards.
+///
+/// Each ideal cut moves to the nearest batch boundary within half a shard
+/// of it, so a batch is only split when no boundary is close enough.
+fn row_cuts(boundaries: &[usize], rows: usize, count: usize) -> Vec<usize> {
+    let (shard_rows, remainder) = (rows / count, rows % count);
+    let tolerance = shard_rows / 2;
+    let mut cuts = Vec::with_capacity(count - 1);
+    let mut previous = 0;
+    for k in 1..count {
+        let ideal = shard_rows * k + remainder * k / count;
+        let snapped = boundaries
+            .iter()
+            .copied()
+            .filter(|boundary| *boundary > previous)
+            .min_by_key(|boundary| boundary.abs_diff(ideal))
+            .filter(|boundary| boundary.abs_diff(ideal) <= tolerance);
+        let cut = snapped.unwrap_or(ideal).max(previous + 1);
+        cuts.push(cut);
+        previous = cut;
+    }
+    cuts
+}
+
+/// Cut positions grouping consecutive batches into shards of at most
+/// `target_bytes`, splitting batches that are larger on their own.
+fn byte_cuts(table: &TableData, target_bytes: usize) -> Vec<usize> {
+    let mut cuts = Vec::new();
+    let mut offset = 0;
+    let mut shard_bytes = 0;
+    for batch in &table.batches {
+        let rows = batch.num_rows();
+        if rows == 0 {
+            continue;
+        }
+        let bytes = batch.get_array_memory_size();
+        if shard_bytes > 0 && shard_bytes + bytes > target_bytes {
+            cuts.push(offset);
+            shard_bytes = 0;
+        }
+        if bytes > target_bytes {
+            let pieces = bytes.div_ceil(target_bytes).min(rows);
+            let (piece_rows, remainder) = (rows / pieces, rows % pieces);
+            cuts.extend((1..pieces).map(|k| offset + piece_rows * k + remainder * k / pieces));
+            shard_bytes = bytes / pieces;
+        } else {
+            shard_bytes += bytes;
+        }
+        offset += rows;
+    }
+    cuts
+}
+
+/// Register one shard per row range between consecutive `cuts`.
+fn store_shards(table: &TableData, cuts: &[usize]) -> Result<Vec<TableHandle>> {
+    let mut handles = Vec::with_capacity(cuts.len() + 1);
+    let mut start = 0;
+    for end in cuts.iter().copied().chain([table.row_count()]) {
+        handles.push(mem::store_table(slice_rows(table, start, end)?)?);
+        start = end;
+    }
+    Ok(handles)
+}
+
+/// Split a table into `count` contiguous shards of near-equal row counts.
+///
+/// Returns the shard handles in row order. Shards share buffers with the
+/// source, and cuts snap to batch boundaries when one lies within half a
+/// shard of the even split, so whole batches are kept together where
+/// possible. A table with fewer rows than `count` gives one shard per row
+/// (or a single empty shard when it has no rows).
+#[wasm_bindgen]
+pub fn shard_table(
+    handle: TableHandle,
+    count: usize,
+) -> std::result::Result<Vec<TableHandle>, JsValue> {
+    if count == 0 {
+        return Err(ArrowWasmError::InvalidInput("count must be at least 1".to_string()).into());
+    }
+    let table = mem::get_table(handle)?;
+    let rows = table.row_count();
+    let count = count.min(rows.max(1));
+    let cuts = row_cuts(&batch_boundaries(&table), rows, count);
+    Ok(store_shards(&table, &cuts)?)
+}
+
+/// Split a table into contiguous shards of about `target_bytes` each.
+///
+/// Consecutive batches are grouped while their combined
+/// `get_array_memory_size` stays within `target_bytes`; a batch larger than
+/// that is split into near-equal row ranges. Sizes of batches that are
+/// themselves slices count their whole underlying buffers, so shards of such
+/// tables can come out smaller than the target.
+#[wasm_bindgen]
+pub fn shard_table_by_bytes(
+    handle: TableHandle,
+    target_bytes: usize,
+) -> std::result::Result<Vec<TableHandle>, JsValue> {
+    if target_bytes == 0 {
+        return Err(
+            ArrowWasmError::InvalidInput("targetBytes must be at least 1".to_string()).into(),
+        );
+    }
+    let table = mem::get_table(handle)?;
+    let cuts = byte_cuts(&table, target_bytes);
+    Ok(store_shards(&table, &cuts)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::concat_tables;
+    use arrow::array::{Array, ArrayRef, Int32Array};
+    use arrow::compute::concat_batches;
+
+    /// A table of consecutive ids in batches of `sizes` rows.
+    fn table(sizes: &[i32]) -> TableHandle {
+        let mut start = 0;
+        let batches = sizes
+            .iter()
+            .map(|size| {
+                let ids: ArrayRef = Arc::new(Int32Array::from_iter_values(start..start + size));
+                start += size;
+                RecordBatch::try_from_iter([("id", ids)]).unwrap()
+            })
+            .collect();
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    fn rows(handle: TableHandle) -> RecordBatch {
+        let table = mem::get_table(handle).unwrap();
+        concat_batches(&table.schema, &table.batches).unwrap()
+    }
+
+    fn batch_sizes(handle: TableHandle) -> Vec<usize> {
+        let table = mem::get_table(handle).unwrap();
+        table.batches.iter().map(RecordBatch::num_rows).collect()
+    }
+
+    #[test]
+    fn concatenated_shards_give_back_the_table() {
+        let handle = table(&[10, 50, 5, 80, 20, 30, 5]);
+        let original = rows(handle);
+        for count in 1..=9 {
+            let shards = shard_table(handle, count).unwrap();
+            assert_eq!(shards.len(), count);
+            let joined = concat_tables(shards, false).unwrap();
+            assert_eq!(rows(joined), original, "{count} shards");
+        }
+        for target_bytes in [1, 500, 1_000, 100_000] {
+            let shards = shard_table_by_bytes(handle, target_bytes).unwrap();
+            let joined = concat_tables(shards, false).unwrap();
+            assert_eq!(rows(joined), original, "{target_bytes} bytes");
+        }
+    }
+
+    #[test]
+    fn seven_batches_go_to_three_shards_whole() {
+        let handle = table(&[100; 7]);
+        let source = mem::get_table(handle).unwrap();
+        let shards = shard_table(handle, 3).unwrap();
+        let sizes: Vec<Vec<usize>> = shards.iter().map(|shard| batch_sizes(*shard)).collect();
+        assert_eq!(sizes, [vec![100, 100], vec![100, 100, 100], vec![100, 100]]);
+
+        // Whole batches are the source's own buffers.
+        let shard = mem::get_table(shards[1]).unwrap();
+        for (batch, original) in shard.batches.iter().zip(&source.batches[2..]) {
+            assert_eq!(
+                batch.column(0).to_data().buffers()[0].as_ptr(),
+                original.column(0).to_data().buffers()[0].as_ptr()
+            );
+        }
+    }
+
+    #[test]
+    fn uneven_batches_split_only_when_needed() {
+        // Ideal cuts at 100 and 200 rows: the boundary at 60 is within half
+        // a shard of the first, none is within 50 rows of the second.
+        let handle = table(&[10, 50, 200, 40]);
+        let shards = shard_table(handle, 3).unwrap();
+        let sizes: Vec<Vec<usize>> = shards.iter().map(|shard| batch_sizes(*shard)).collect();
+        assert_eq!(sizes, [vec![10, 50], vec![140], vec![60, 40]]);
+
+        let single = table(&[9]);
+        let sizes: Vec<Vec<usize>> = shard_table(single, 3)
+            .unwrap()
+            .iter()
+            .map(|shard| batch_sizes(*shard))
+            .collect();
+        assert_eq!(sizes, [[3], [3], [3]]);
+        assert_eq!(shard_table(table(&[2]), 5).unwrap().len(), 2);
+    }
+}
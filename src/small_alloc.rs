@@ -0,0 +1,25 @@
+//! talc as the global allocator of `wasm32` builds with the `small-alloc`
+//! feature.
+//!
+//! dlmalloc, the `wasm32` default, brings bin management and trimming code
+//! that a module holding a few large Arrow buffers barely uses; talc is
+//! smaller and grows linear memory on demand the same way. Nothing in the
+//! crate depends on the allocator; with `alloc-metrics` the counters wrap
+//! this one instead of the system allocator.
+
+#[cfg(target_feature = "atomics")]
+compile_error!("small-alloc is single-threaded; build without the atomics target feature");
+
+/// talc over the module's linear memory, without locking.
+pub type SmallAlloc = talc::TalckWasm;
+
+/// A new [`SmallAlloc`].
+pub const fn new() -> SmallAlloc {
+    // SAFETY: without the atomics feature the module is single-threaded,
+    // which is all `new_global` requires.
+    unsafe { SmallAlloc::new_global() }
+}
+
+#[cfg(not(feature = "alloc-metrics"))]
+#[global_allocator]
+static GLOBAL: SmallAlloc = new();
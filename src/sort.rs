@@ -0,0 +1,551 @@
+//! Row ordering.
+
+use crate::compute::capability::{require, Operation};
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use arrow::array::{Array, ArrayRef, RecordBatch, UInt64Array};
+use arrow_ord::sort::{lexsort_to_indices, LexicographicalComparator, SortColumn, SortOptions};
+use arrow_select::concat::{concat, concat_batches};
+use arrow_select::interleave::interleave_record_batch;
+use arrow_select::take::take_record_batch;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// One sort key: a bare column name, or a column with ordering options.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SortKey {
+    Name(String),
+    Spec(SortSpec),
+}
+
+/// Column and ordering of one sort key.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SortSpec {
+    column: String,
+    #[serde(default)]
+    descending: bool,
+    /// Defaults to nulls last for ascending keys and first for descending
+    /// ones, so nulls always sort as the largest value.
+    nulls_first: Option<bool>,
+}
+
+impl SortKey {
+    fn into_spec(self) -> SortSpec {
+        match self {
+            Self::Name(column) => SortSpec {
+                column,
+                descending: false,
+                nulls_first: None,
+            },
+            Self::Spec(spec) => spec,
+        }
+    }
+}
+
+/// One concatenated sort column per key.
+fn sort_columns(table: &TableData, keys: Vec<SortSpec>) -> Result<Vec<SortColumn>> {
+    let mut columns = Vec::with_capacity(keys.len() + 1);
+    for key in keys {
+        let chunks = table.get_column_by_name(&key.column)?;
+        require(Operation::Sort, table.schema.field_with_name(&key.column)?)?;
+        let refs: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+        columns.push(SortColumn {
+            values: concat(&refs)?,
+            options: Some(SortOptions {
+                descending: key.descending,
+                nulls_first: key.nulls_first.unwrap_or(key.descending),
+            }),
+        });
+    }
+    Ok(columns)
+}
+
+/// Sort `table` by `keys`; rows that tie on every key keep their input order.
+///
+/// arrow-rs sorts with an unstable algorithm, so the row position is added
+/// as a final ascending key to make the order deterministic.
+pub fn sort_table(table: &TableData, keys: Vec<SortSpec>) -> Result<TableData> {
+    if keys.is_empty() {
+        return Err(ArrowWasmError::InvalidInput(
+            "At least one sort key is required".to_string(),
+        ));
+    }
+    let row_count = table.row_count();
+    if row_count == 0 {
+        return Ok(table.clone());
+    }
+
+    let mut columns = sort_columns(table, keys)?;
+    let positions: ArrayRef = Arc::new(UInt64Array::from_iter_values(0..row_count as u64));
+    columns.push(SortColumn {
+        values: positions,
+        options: None,
+    });
+
+    let indices = lexsort_to_indices(&columns, None)?;
+    let combined = concat_batches(&table.schema, &table.batches)?;
+    let sorted: RecordBatch = take_record_batch(&combined, &indices)?;
+    TableData::new(vec![sorted])
+}
+
+/// Sort rows by one or more columns into a new single-batch table.
+///
+/// `keys` is an array whose entries are column names or
+/// `{column, descending?, nullsFirst?}` objects; earlier keys take
+/// precedence and Boolean keys order `false` before `true`. The sort is
+/// stable: rows that compare equal on every key keep their relative order
+/// from the input. Row positions change; carry a `with_row_index` column
+/// through to map rows back to the source.
+#[wasm_bindgen]
+pub fn sort_by(handle: TableHandle, keys: JsValue) -> std::result::Result<TableHandle, JsValue> {
+    let keys: Vec<SortKey> = serde_wasm_bindgen::from_value(keys).map_err(ArrowWasmError::from)?;
+    let table = mem::get_table(handle)?;
+    let sorted = sort_table(&table, keys.into_iter().map(SortKey::into_spec).collect())?;
+    Ok(mem::store_table(sorted)?)
+}
+
+/// Row held in the bounded heap of [`select_rows`], ordered by the sort keys
+/// and then by position, reversed when selecting from the end.
+struct Candidate<'a> {
+    row: usize,
+    comparator: &'a LexicographicalComparator,
+    reverse: bool,
+}
+
+impl Candidate<'_> {
+    fn order(&self, other: &Self) -> Ordering {
+        let order = self
+            .comparator
+            .compare(self.row, other.row)
+            .then(self.row.cmp(&other.row));
+        if self.reverse {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+}
+
+impl PartialEq for Candidate<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.row == other.row
+    }
+}
+
+impl Eq for Candidate<'_> {}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order(other)
+    }
+}
+
+/// The `k` first rows of `sort_table(table, keys)`, or the `k` last ones
+/// when `from_end` is set, in sorted order.
+///
+/// Rows stream through a max-heap of `k` candidates, so this takes
+/// O(n log k) comparisons and O(k) extra memory besides the key columns,
+/// and only the selected rows are gathered.
+pub fn select_rows(
+    table: &TableData,
+    k: usize,
+    keys: Vec<SortSpec>,
+    from_end: bool,
+) -> Result<TableData> {
+    if keys.is_empty() {
+        return Err(ArrowWasmError::InvalidInput(
+            "At least one sort key is required".to_string(),
+        ));
+    }
+    if k == 0 {
+        return Err(ArrowWasmError::InvalidInput(
+            "k must be at least 1".to_string(),
+        ));
+    }
+    let columns = sort_columns(table, keys)?;
+    let comparator = LexicographicalComparator::try_new(&columns)?;
+    let mut heap = BinaryHeap::with_capacity(k.min(table.row_count()) + 1);
+    for row in 0..table.row_count() {
+        let candidate = Candidate {
+            row,
+            comparator: &comparator,
+            reverse: from_end,
+        };
+        if heap.len() < k {
+            heap.push(candidate);
+        } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+            heap.pop();
+            heap.push(candidate);
+        }
+    }
+    let mut rows: Vec<usize> = heap.into_sorted_vec().into_iter().map(|c| c.row).collect();
+    if from_end {
+        rows.reverse();
+    }
+    if rows.is_empty() {
+        return Ok(table.clone());
+    }
+
+    let mut starts = Vec::with_capacity(table.batches.len());
+    let mut offset = 0;
+    for batch in &table.batches {
+        starts.push(offset);
+        offset += batch.num_rows();
+    }
+    let indices: Vec<(usize, usize)> = rows
+        .iter()
+        .map(|&row| {
+            let batch = starts.partition_point(|&start| start <= row) - 1;
+            (batch, row - starts[batch])
+        })
+        .collect();
+    let batches: Vec<&RecordBatch> = table.batches.iter().collect();
+    TableData::new(vec![interleave_record_batch(&batches, &indices)?])
+}
+
+fn parse_keys(keys: JsValue) -> Result<Vec<SortSpec>> {
+    let keys: Vec<SortKey> = serde_wasm_bindgen::from_value(keys)?;
+    Ok(keys.into_iter().map(SortKey::into_spec).collect())
+}
+
+/// The first `k` rows of `sort_by(handle, keys)`, without sorting the whole
+/// table, e.g. the 100 largest transactions.
+///
+/// `keys` is as for `sort_by`, including `nullsFirst`. The result is the
+/// same as sorting and slicing: rows tying on every key are taken in their
+/// input order, so ties at the cut-off are resolved deterministically. Takes
+/// O(n log k) time and O(k) extra memory beyond the key columns. `k` must be
+/// at least 1; a `k` past the row count returns every row, sorted.
+#[wasm_bindgen]
+pub fn top_k(
+    handle: TableHandle,
+    k: usize,
+    keys: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let keys = parse_keys(keys)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(select_rows(&table, k, keys, false)?)?)
+}
+
+/// The last `k` rows of `sort_by(handle, keys)`, in the same order, without
+/// sorting the whole table. See `top_k`.
+#[wasm_bindgen]
+pub fn bottom_k(
+    handle: TableHandle,
+    k: usize,
+    keys: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let keys = parse_keys(keys)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(select_rows(&table, k, keys, true)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use arrow::array::{AsArray, BooleanArray, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema, UInt64Type};
+
+    /// `id` holds the row position; `group` and `label` repeat values and
+    /// contain nulls so that ties and null placement matter.
+    fn table(batch_rows: &[usize]) -> TableData {
+        let mut rng = SplitMix64::new(7);
+        let mut id = 0_u64;
+        let batches = batch_rows
+            .iter()
+            .map(|&rows| {
+                let ids: Vec<u64> = (id..id + rows as u64).collect();
+                id += rows as u64;
+                let groups: Int32Array = (0..rows)
+                    .map(|_| match rng.next_below(5) {
+                        0 => None,
+                        group => Some(group as i32),
+                    })
+                    .collect();
+                let labels: StringArray = (0..rows)
+                    .map(|_| match rng.next_below(4) {
+                        0 => None,
+                        label => Some(format!("l{label}")),
+                    })
+                    .collect();
+                RecordBatch::try_from_iter([
+                    ("id", Arc::new(UInt64Array::from(ids)) as ArrayRef),
+                    ("group", Arc::new(groups)),
+                    ("label", Arc::new(labels)),
+                ])
+                .unwrap()
+            })
+            .collect();
+        TableData::new(batches).unwrap()
+    }
+
+    fn spec(column: &str, descending: bool, nulls_first: Option<bool>) -> SortSpec {
+        SortSpec {
+            column: column.to_string(),
+            descending,
+            nulls_first,
+        }
+    }
+
+    /// Key list `index` of `KEY_SETS`, together covering both directions,
+    /// explicit null placement and secondary keys.
+    fn key_set(index: usize) -> Vec<SortSpec> {
+        match index {
+            0 => vec![spec("group", false, None)],
+            1 => vec![spec("group", true, None)],
+            2 => vec![spec("group", false, Some(true)), spec("label", true, None)],
+            _ => vec![
+                spec("label", false, Some(false)),
+                spec("group", true, Some(false)),
+            ],
+        }
+    }
+
+    const KEY_SETS: usize = 4;
+
+    fn ids(table: &TableData) -> Vec<u64> {
+        table
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column_by_name("id").unwrap();
+                column.as_primitive::<UInt64Type>().values().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selected_rows_match_the_sorted_table() {
+        let table = table(&[17, 0, 40]);
+        for index in 0..KEY_SETS {
+            let sorted = ids(&sort_table(&table, key_set(index)).unwrap());
+            for k in [1, 5, 23, 56, 57, 100] {
+                let top = select_rows(&table, k, key_set(index), false).unwrap();
+                let bottom = select_rows(&table, k, key_set(index), true).unwrap();
+                let taken = k.min(sorted.len());
+                assert_eq!(ids(&top), sorted[..taken], "keys {index}, k {k}");
+                assert_eq!(
+                    ids(&bottom),
+                    sorted[sorted.len() - taken..],
+                    "keys {index}, k {k}"
+                );
+                assert_eq!(top.schema, table.schema);
+            }
+        }
+    }
+
+    #[test]
+    fn sorting_is_stable_across_batches() {
+        let table = table(&[9, 12, 6]);
+        let sorted = sort_table(&table, vec![spec("group", false, None)]).unwrap();
+        let batch = &sorted.batches[0];
+        let groups = batch
+            .column_by_name("group")
+            .unwrap()
+            .as_primitive::<Int32Type>();
+        let ids = ids(&sorted);
+        for row in 1..batch.num_rows() {
+            if groups.is_valid(row) == groups.is_valid(row - 1)
+                && (groups.is_null(row) || groups.value(row) == groups.value(row - 1))
+            {
+                assert!(ids[row - 1] < ids[row], "row {row}");
+            }
+        }
+        assert!(
+            groups.is_null(batch.num_rows() - 1),
+            "nulls sort last ascending"
+        );
+    }
+
+    #[test]
+    fn empty_tables_select_nothing() {
+        let empty = table(&[0]);
+        let top = select_rows(&empty, 3, vec![spec("group", false, None)], false).unwrap();
+        assert_eq!(top.row_count(), 0);
+        assert_eq!(top.schema, empty.schema);
+        let sorted = sort_table(&empty, vec![spec("group", false, None)]).unwrap();
+        assert_eq!(sorted.row_count(), 0);
+    }
+
+    #[test]
+    fn invalid_selections_fail() {
+        let table = table(&[4]);
+        let cases = [
+            (
+                select_rows(&table, 1, Vec::new(), false),
+                "At least one sort key is required",
+            ),
+            (
+                select_rows(&table, 0, vec![spec("group", false, None)], false),
+                "k must be at least 1",
+            ),
+            (
+                sort_table(&table, Vec::new()),
+                "At least one sort key is required",
+            ),
+        ];
+        for (result, expected) in cases {
+            let Err(ArrowWasmError::InvalidInput(message)) = result else {
+                panic!("{expected}");
+            };
+            assert_eq!(message, expected);
+        }
+        assert!(select_rows(&table, 1, vec![spec("missing", false, None)], false).is_err());
+    }
+
+    #[test]
+    fn unsortable_keys_name_the_operation_column_and_type() {
+        let batch = RecordBatch::try_from_iter([(
+            "blank",
+            Arc::new(arrow::array::NullArray::new(2)) as ArrayRef,
+        )])
+        .unwrap();
+        let table = TableData::new(vec![batch]).unwrap();
+        let error = sort_table(&table, vec![spec("blank", false, None)])
+            .map(drop)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "sort does not support column 'blank' of type Null");
+        let error = select_rows(&table, 1, vec![spec("blank", true, None)], false)
+            .map(drop)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "sort does not support column 'blank' of type Null");
+    }
+
+    #[test]
+    fn ties_keep_input_order_in_both_directions() {
+        // Three groups over 150 rows in four batches: every key is tied
+        // with about fifty other rows, most of them in other batches.
+        let group = |id: u64| (id * 7 % 3) as i32;
+        let batches: Vec<RecordBatch> = [(0, 50), (50, 0), (50, 70), (120, 30)]
+            .iter()
+            .map(|&(start, rows)| {
+                let ids: Vec<u64> = (start..start + rows).collect();
+                let groups: Int32Array = ids.iter().map(|&id| group(id)).collect();
+                RecordBatch::try_from_iter([
+                    ("id", Arc::new(UInt64Array::from(ids)) as ArrayRef),
+                    ("group", Arc::new(groups)),
+                ])
+                .unwrap()
+            })
+            .collect();
+        let table = TableData::new(batches).unwrap();
+        for descending in [false, true] {
+            let sorted = sort_table(&table, vec![spec("group", descending, None)]).unwrap();
+            let ids = ids(&sorted);
+            // Tied rows come in input order either way, so the descending
+            // result is not the ascending one reversed.
+            let mut expected: Vec<u64> = (0..150).collect();
+            if descending {
+                expected.sort_by_key(|&id| std::cmp::Reverse(group(id)));
+            } else {
+                expected.sort_by_key(|&id| group(id));
+            }
+            assert_eq!(ids, expected, "descending {descending}");
+        }
+    }
+
+    #[test]
+    fn boolean_keys_keep_values_and_nulls() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("flag", DataType::Boolean, true),
+            Field::new("id", DataType::Int32, false),
+        ]));
+        let batch = |flags: Vec<Option<bool>>, ids: Vec<i32>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(BooleanArray::from(flags)),
+                    Arc::new(Int32Array::from(ids)),
+                ],
+            )
+            .unwrap()
+        };
+        let table = TableData::new(vec![
+            batch(
+                vec![Some(true), None, Some(false), Some(true)],
+                vec![0, 1, 2, 3],
+            ),
+            batch(vec![None, Some(false)], vec![4, 5]),
+        ])
+        .unwrap();
+        let rows = |table: &TableData| -> Vec<(Option<bool>, i32)> {
+            table
+                .batches
+                .iter()
+                .flat_map(|batch| {
+                    let flags = batch.column(0).as_boolean().iter();
+                    flags.zip(
+                        batch
+                            .column(1)
+                            .as_primitive::<Int32Type>()
+                            .values()
+                            .to_vec(),
+                    )
+                })
+                .collect()
+        };
+
+        let sorted = sort_table(&table, vec![spec("flag", false, None)]).unwrap();
+        assert_eq!(sorted.schema, table.schema);
+        let expected = [
+            (Some(false), 2),
+            (Some(false), 5),
+            (Some(true), 0),
+            (Some(true), 3),
+            (None, 1),
+            (None, 4),
+        ];
+        assert_eq!(rows(&sorted), expected);
+        assert_eq!(sorted.batches[0].column(0).null_count(), 2);
+
+        let top = select_rows(&table, 3, vec![spec("flag", true, None)], false).unwrap();
+        assert_eq!(top.schema, table.schema);
+        assert_eq!(rows(&top), [(None, 1), (None, 4), (Some(true), 0)]);
+        let top = select_rows(&table, 3, vec![spec("flag", false, Some(false))], false).unwrap();
+        assert_eq!(rows(&top), expected[..3]);
+        assert!(top.batches[0]
+            .column(0)
+            .nulls()
+            .is_none_or(|nulls| nulls.null_count() == 0));
+    }
+
+    #[test]
+    fn taken_rows_keep_field_nullability() {
+        // `table` with `id`, which never holds nulls, declared non-nullable.
+        let source = table(&[5, 7, 3]);
+        let fields = source.schema.fields().iter().map(|field| {
+            let nullable = field.name() != "id";
+            field.as_ref().clone().with_nullable(nullable)
+        });
+        let schema = Arc::new(Schema::new(fields.collect::<Vec<_>>()));
+        let batches = source.batches.iter().map(|batch| {
+            RecordBatch::try_new(Arc::clone(&schema), batch.columns().to_vec()).unwrap()
+        });
+        let table = TableData::new(batches.collect()).unwrap();
+        let nullability = |table: &TableData| -> Vec<bool> {
+            let fields = table.schema.fields().iter();
+            fields.map(|field| field.is_nullable()).collect()
+        };
+        assert_eq!(nullability(&table), [false, true, true]);
+        for set in 0..KEY_SETS {
+            let sorted = sort_table(&table, key_set(set)).unwrap();
+            assert_eq!(nullability(&sorted), [false, true, true], "key set {set}");
+            let selected = select_rows(&table, 4, key_set(set), false).unwrap();
+            assert_eq!(nullability(&selected), [false, true, true], "key set {set}");
+        }
+    }
+}
@@ -0,0 +1,2217 @@
+//! Table-level transformations.
+//!
+//! Every operation leaves its input table untouched and registers the result
+//! under a new handle. Column arrays are shared with the source wherever the
+//! batch layout allows it.
+
+use crate::column::Column;
+use crate::compute::keys::{column_keys, stable_hasher, visit_keys, write_key, Key};
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableData, TableHandle};
+use crate::rng::SplitMix64;
+use arrow::array::{
+    make_array, new_null_array, Array, ArrayRef, AsArray, BooleanArray, Int32Array, Int64Array,
+    RecordBatch, RecordBatchOptions, StructArray, UInt64Array,
+};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow_cast::{can_cast_types, cast};
+use arrow_select::concat::{concat, concat_batches};
+use arrow_select::filter::filter_record_batch;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+/// Reject a column list containing the same name twice.
+///
+/// arrow-rs accepts duplicate field names, but `Schema::index_of` only finds
+/// the first one, so every name-based lookup would silently ignore the rest.
+pub fn validate_unique_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Duplicate column name '{name}'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Build a table from `fields` and per-batch column arrays, keeping the
+/// schema-level metadata of `source`.
+pub fn rebuild_table(
+    source: &TableData,
+    fields: Vec<FieldRef>,
+    batch_columns: Vec<Vec<ArrayRef>>,
+) -> Result<TableData> {
+    validate_unique_names(fields.iter().map(|field| field.name().as_str()))?;
+    let schema = Arc::new(Schema::new_with_metadata(
+        fields,
+        source.schema.metadata().clone(),
+    ));
+    let batches = batch_columns
+        .into_iter()
+        .map(|columns| RecordBatch::try_new(Arc::clone(&schema), columns))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    TableData::new(batches)
+}
+
+/// Re-chunk `chunks` so they line up with `lengths`, reusing the arrays
+/// untouched when the layouts already match.
+pub fn align_chunks(chunks: &[ArrayRef], lengths: &[usize]) -> Result<Vec<ArrayRef>> {
+    let total: usize = chunks.iter().map(Array::len).sum();
+    let expected: usize = lengths.iter().sum();
+    if total != expected {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Column has {total} rows but table has {expected}"
+        )));
+    }
+    if chunks.len() == lengths.len()
+        && chunks
+            .iter()
+            .zip(lengths)
+            .all(|(chunk, len)| chunk.len() == *len)
+    {
+        return Ok(chunks.to_vec());
+    }
+
+    let refs: Vec<&dyn Array> = chunks.iter().map(AsRef::as_ref).collect();
+    let combined = concat(&refs)?;
+    let mut offset = 0;
+    Ok(lengths
+        .iter()
+        .map(|len| {
+            let slice = combined.slice(offset, *len);
+            offset += len;
+            slice
+        })
+        .collect())
+}
+
+fn batch_lengths(table: &TableData) -> Vec<usize> {
+    table.batches.iter().map(RecordBatch::num_rows).collect()
+}
+
+fn rename_fields(table: &TableData, mapping: &HashMap<String, String>) -> Result<TableData> {
+    for old_name in mapping.keys() {
+        table
+            .schema
+            .index_of(old_name)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{old_name}' not found")))?;
+    }
+
+    let fields = table
+        .schema
+        .fields()
+        .iter()
+        .map(|field| {
+            mapping.get(field.name()).map_or_else(
+                || Arc::clone(field),
+                |new_name| Arc::new(field.as_ref().clone().with_name(new_name)),
+            )
+        })
+        .collect();
+    let batch_columns = table
+        .batches
+        .iter()
+        .map(|batch| batch.columns().to_vec())
+        .collect();
+    rebuild_table(table, fields, batch_columns)
+}
+
+/// Rename a single column.
+#[wasm_bindgen]
+pub fn rename_column(
+    handle: TableHandle,
+    old_name: &str,
+    new_name: &str,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let mapping = HashMap::from([(old_name.to_string(), new_name.to_string())]);
+    Ok(mem::store_table(rename_fields(&table, &mapping)?)?)
+}
+
+/// Rename several columns at once from an `{oldName: newName}` object.
+#[wasm_bindgen]
+pub fn rename_columns(
+    handle: TableHandle,
+    mapping: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let mapping: HashMap<String, String> =
+        serde_wasm_bindgen::from_value(mapping).map_err(ArrowWasmError::from)?;
+    Ok(mem::store_table(rename_fields(&table, &mapping)?)?)
+}
+
+/// Copy of `table` with its schema-level metadata replaced by `metadata`;
+/// arrays are shared.
+fn with_schema_metadata(table: &TableData, metadata: HashMap<String, String>) -> Result<TableData> {
+    let schema = Arc::new(table.schema.as_ref().clone().with_metadata(metadata));
+    let batches = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+            RecordBatch::try_new_with_options(
+                Arc::clone(&schema),
+                batch.columns().to_vec(),
+                &options,
+            )
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    TableData::new(batches)
+}
+
+/// Add or overwrite schema-level metadata keys from an `{key: value}`
+/// object, keeping every key not mentioned.
+#[wasm_bindgen]
+pub fn merge_metadata(
+    handle: TableHandle,
+    entries: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let entries: HashMap<String, String> =
+        serde_wasm_bindgen::from_value(entries).map_err(ArrowWasmError::from)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(merge_schema_metadata(&table, entries)?)?)
+}
+
+fn merge_schema_metadata(table: &TableData, entries: HashMap<String, String>) -> Result<TableData> {
+    let mut metadata = table.schema.metadata().clone();
+    metadata.extend(entries);
+    with_schema_metadata(table, metadata)
+}
+
+/// Rename a schema-level metadata key, keeping its value; an existing entry
+/// under `new_key` is overwritten. Fails when `old_key` is not present.
+#[wasm_bindgen]
+pub fn rename_schema_metadata_key(
+    handle: TableHandle,
+    old_key: &str,
+    new_key: &str,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let mut metadata = table.schema.metadata().clone();
+    let value = metadata.remove(old_key).ok_or_else(|| {
+        ArrowWasmError::InvalidInput(format!("Metadata key '{old_key}' not found"))
+    })?;
+    metadata.insert(new_key.to_string(), value);
+    Ok(mem::store_table(with_schema_metadata(&table, metadata)?)?)
+}
+
+/// Add or overwrite metadata keys of one column's field from an
+/// `{key: value}` object, keeping every key not mentioned.
+#[wasm_bindgen]
+pub fn merge_field_metadata(
+    handle: TableHandle,
+    column: &str,
+    entries: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let entries: HashMap<String, String> =
+        serde_wasm_bindgen::from_value(entries).map_err(ArrowWasmError::from)?;
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(merge_column_metadata(
+        &table, column, entries,
+    )?)?)
+}
+
+fn merge_column_metadata(
+    table: &TableData,
+    column: &str,
+    entries: HashMap<String, String>,
+) -> Result<TableData> {
+    let index = table
+        .schema
+        .index_of(column)
+        .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{column}' not found")))?;
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    let mut metadata = fields[index].metadata().clone();
+    metadata.extend(entries);
+    fields[index] = Arc::new(fields[index].as_ref().clone().with_metadata(metadata));
+    let batch_columns = table
+        .batches
+        .iter()
+        .map(|batch| batch.columns().to_vec())
+        .collect();
+    rebuild_table(table, fields, batch_columns)
+}
+
+/// Append `column` under `name`; the name must not already exist.
+#[wasm_bindgen]
+pub fn add_column(
+    handle: TableHandle,
+    name: &str,
+    column: &Column,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let (field, chunks) = column.field_and_chunks()?;
+    let chunks = align_chunks(&chunks, &batch_lengths(&table))?;
+
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    fields.push(Arc::new(
+        Field::new(name, field.data_type().clone(), field.is_nullable())
+            .with_metadata(field.metadata().clone()),
+    ));
+    let batch_columns = table
+        .batches
+        .iter()
+        .zip(chunks)
+        .map(|(batch, chunk)| {
+            let mut columns = batch.columns().to_vec();
+            columns.push(chunk);
+            columns
+        })
+        .collect();
+    Ok(mem::store_table(rebuild_table(
+        &table,
+        fields,
+        batch_columns,
+    )?)?)
+}
+
+/// Add or replace every column of `other` in `handle`, matching by name.
+///
+/// Existing columns keep their position; new ones are appended in the order
+/// they appear in `other`.
+#[wasm_bindgen]
+pub fn assign(
+    handle: TableHandle,
+    other: TableHandle,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let other = mem::get_table(other)?;
+    validate_unique_names(
+        other
+            .schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str()),
+    )?;
+    let lengths = batch_lengths(&table);
+
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    let mut columns_by_position: Vec<Option<Vec<ArrayRef>>> = vec![None; fields.len()];
+    for (index, field) in other.schema.fields().iter().enumerate() {
+        let chunks: Vec<ArrayRef> = other
+            .batches
+            .iter()
+            .map(|batch| Arc::clone(batch.column(index)))
+            .collect();
+        let chunks = align_chunks(&chunks, &lengths)?;
+        if let Ok(position) = table.schema.index_of(field.name()) {
+            fields[position] = Arc::clone(field);
+            columns_by_position[position] = Some(chunks);
+        } else {
+            fields.push(Arc::clone(field));
+            columns_by_position.push(Some(chunks));
+        }
+    }
+
+    let batch_columns = table
+        .batches
+        .iter()
+        .enumerate()
+        .map(|(batch_index, batch)| {
+            columns_by_position
+                .iter()
+                .enumerate()
+                .map(|(position, replacement)| {
+                    replacement.as_ref().map_or_else(
+                        || Arc::clone(batch.column(position)),
+                        |chunks| Arc::clone(&chunks[batch_index]),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    Ok(mem::store_table(rebuild_table(
+        &table,
+        fields,
+        batch_columns,
+    )?)?)
+}
+
+/// Options for [`filter_by_mask`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct FilterOptions {
+    with_stats: bool,
+}
+
+/// What a filter did, as returned by `filter_by_mask` with `withStats`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterStats {
+    input_rows: usize,
+    output_rows: usize,
+    /// `outputRows / inputRows`, or null for an empty input.
+    selectivity: Option<f64>,
+    /// Null mask entries, which count as `false`.
+    null_mask_entries: usize,
+    columns: Vec<ColumnNullStats>,
+}
+
+/// Null counts of one column before and after a filter.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ColumnNullStats {
+    name: String,
+    nulls_before: usize,
+    nulls_after: usize,
+}
+
+impl FilterStats {
+    fn new(schema: &Schema) -> Self {
+        Self {
+            input_rows: 0,
+            output_rows: 0,
+            selectivity: None,
+            null_mask_entries: 0,
+            columns: schema
+                .fields()
+                .iter()
+                .map(|field| ColumnNullStats {
+                    name: field.name().clone(),
+                    nulls_before: 0,
+                    nulls_after: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Add the counts of one batch, read from the existing null buffers.
+    fn record(&mut self, input: &RecordBatch, mask: &ArrayRef, output: &RecordBatch) {
+        self.input_rows += input.num_rows();
+        self.output_rows += output.num_rows();
+        self.null_mask_entries += mask.null_count();
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            column.nulls_before += input.column(index).null_count();
+            column.nulls_after += output.column(index).null_count();
+        }
+    }
+}
+
+/// `table` filtered by the Boolean `mask` chunks, with the filter's
+/// [`FilterStats`] when `with_stats` is set; see [`filter_by_mask`].
+fn filter_table(
+    table: &TableData,
+    mask: &[ArrayRef],
+    with_stats: bool,
+) -> Result<(TableData, Option<FilterStats>)> {
+    let mask = align_chunks(mask, &batch_lengths(table))?;
+    let mut stats = FilterStats::new(&table.schema);
+    let batches = table
+        .batches
+        .iter()
+        .zip(&mask)
+        .map(|(batch, chunk)| {
+            let filtered = filter_record_batch(batch, chunk.as_boolean())?;
+            if with_stats {
+                stats.record(batch, chunk, &filtered);
+            }
+            Ok(filtered)
+        })
+        .collect::<std::result::Result<Vec<_>, ArrowError>>()?;
+    let filtered = TableData::new(batches)?;
+    if !with_stats {
+        return Ok((filtered, None));
+    }
+    stats.selectivity =
+        (stats.input_rows > 0).then(|| stats.output_rows as f64 / stats.input_rows as f64);
+    Ok((filtered, Some(stats)))
+}
+
+/// Keep the rows where the Boolean `mask` column is `true`.
+///
+/// The mask must have one entry per row; null entries count as `false`.
+/// Kept rows retain their values and nulls in every column, and fields keep
+/// their type and nullability. Row positions change; carry a
+/// `with_row_index` column through to map rows back to the source.
+///
+/// Returns the new table handle. With `options.withStats` it returns
+/// `{table, stats}` instead, where `stats` is `{inputRows, outputRows,
+/// selectivity, nullMaskEntries, columns}` and `columns` lists `{name,
+/// nullsBefore, nullsAfter}` per column. The counts come from the null
+/// buffers the filter reads anyway, so they cost no extra scan.
+#[wasm_bindgen]
+pub fn filter_by_mask(
+    handle: TableHandle,
+    mask: &Column,
+    options: JsValue,
+) -> std::result::Result<JsValue, JsValue> {
+    let options: FilterOptions = if options.is_undefined() || options.is_null() {
+        FilterOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let table = mem::get_table(handle)?;
+    let (field, chunks) = mask.field_and_chunks()?;
+    if field.data_type() != &DataType::Boolean {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Mask column must be Boolean, got {:?}",
+            field.data_type()
+        ))
+        .into());
+    }
+    let (filtered, stats) = filter_table(&table, &chunks, options.with_stats)?;
+    let filtered = mem::store_table(filtered)?;
+    let Some(stats) = stats else {
+        return Ok(filtered.into());
+    };
+    let result = js_sys::Object::new();
+    set_property(&result, "table", &filtered.into())?;
+    let stats = serde_wasm_bindgen::to_value(&stats).map_err(ArrowWasmError::from)?;
+    set_property(&result, "stats", &stats)?;
+    Ok(result.into())
+}
+
+/// Keep each row independently with probability `fraction`.
+///
+/// `fraction` must lie in `[0, 1]`; `1` keeps every row and `0` none. The
+/// number of rows kept varies around `fraction * rowCount` and is not
+/// exact. Passing `seed` makes the selection reproducible; without it a
+/// random seed is drawn. Kept rows keep their order.
+#[wasm_bindgen]
+pub fn sample_fraction(
+    handle: TableHandle,
+    fraction: f64,
+    seed: Option<u64>,
+) -> std::result::Result<TableHandle, JsValue> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "fraction must be between 0 and 1, got {fraction}"
+        ))
+        .into());
+    }
+    let table = mem::get_table(handle)?;
+    let mut rng = SplitMix64::new(seed.unwrap_or_else(|| js_sys::Math::random().to_bits()));
+    let batches = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let keep: BooleanArray = (0..batch.num_rows())
+                .map(|_| Some(rng.next_f64() < fraction))
+                .collect();
+            filter_record_batch(batch, &keep)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(ArrowWasmError::from)?;
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+/// Keep only the named columns, in the given order.
+///
+/// Fields keep their metadata and the schema keeps its own. Arrays are
+/// shared with the source; selecting every column in schema order reuses
+/// the source batches as they are. Keep the `with_row_index` column in the
+/// selection to preserve row identity.
+#[wasm_bindgen]
+pub fn select(
+    handle: TableHandle,
+    names: Vec<String>,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    validate_unique_names(names.iter().map(String::as_str))?;
+    let indices = names
+        .iter()
+        .map(|name| {
+            table
+                .schema
+                .index_of(name)
+                .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if indices.iter().copied().eq(0..table.column_count()) {
+        return Ok(mem::store_table(table)?);
+    }
+    let batches = table
+        .batches
+        .iter()
+        .map(|batch| batch.project(&indices))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(ArrowWasmError::from)?;
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+/// Drop rows whose `keys` columns repeat the values of another row.
+///
+/// `keys` is an array of column names. Key values compare like
+/// `Column.values_equal`: nulls equal each other, floats compare by value
+/// and dictionaries by their decoded values. Per key combination,
+/// `keep = "first"` keeps the earliest row and `keep = "last"` the latest;
+/// the surviving rows keep their original relative order.
+#[wasm_bindgen]
+pub fn drop_duplicates(
+    handle: TableHandle,
+    keys: JsValue,
+    keep: &str,
+) -> std::result::Result<TableHandle, JsValue> {
+    let keep_last = match keep {
+        "first" => false,
+        "last" => true,
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Unknown keep '{other}', expected 'first' or 'last'"
+            ))
+            .into())
+        }
+    };
+    let names: Vec<String> = serde_wasm_bindgen::from_value(keys).map_err(ArrowWasmError::from)?;
+    if names.is_empty() {
+        return Err(ArrowWasmError::InvalidInput(
+            "At least one key column is required".to_string(),
+        )
+        .into());
+    }
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(unique_rows(&table, &names, keep_last)?)?)
+}
+
+/// The rows of `table` left after `drop_duplicates` on the `names` columns.
+fn unique_rows(table: &TableData, names: &[String], keep_last: bool) -> Result<TableData> {
+    let columns = names
+        .iter()
+        .map(|name| table.get_column_by_name(name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut row_keys: Vec<Vec<Option<Key>>> =
+        vec![Vec::with_capacity(names.len()); table.row_count()];
+    for chunks in &columns {
+        for (row, key) in column_keys(chunks)?.into_iter().enumerate() {
+            row_keys[row].push(key);
+        }
+    }
+
+    let mut keep_row = vec![false; row_keys.len()];
+    let mut kept: HashMap<&[Option<Key>], usize> = HashMap::new();
+    for (row, key) in row_keys.iter().enumerate() {
+        match kept.entry(key.as_slice()) {
+            Entry::Vacant(entry) => {
+                entry.insert(row);
+                keep_row[row] = true;
+            }
+            Entry::Occupied(mut entry) if keep_last => {
+                keep_row[*entry.get()] = false;
+                keep_row[row] = true;
+                entry.insert(row);
+            }
+            Entry::Occupied(_) => {}
+        }
+    }
+
+    let mut offset = 0;
+    let batches = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let rows = offset..offset + batch.num_rows();
+            offset = rows.end;
+            filter_record_batch(batch, &BooleanArray::from(keep_row[rows].to_vec()))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(ArrowWasmError::from)?;
+    TableData::new(batches)
+}
+
+/// Drop every row holding a null in any column, or only in the columns
+/// named by `subset` (an array of names; `null` or `undefined` means every
+/// column).
+///
+/// A row counts as null where the column's value is null (for dictionary
+/// and run-end encoded columns, where the decoded value is); nulls nested
+/// inside struct or list values do not count. Surviving rows keep their
+/// order, and batches without nulls are shared with the source.
+#[wasm_bindgen]
+pub fn drop_nulls(
+    handle: TableHandle,
+    subset: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let indices: Vec<usize> = if subset.is_undefined() || subset.is_null() {
+        (0..table.column_count()).collect()
+    } else {
+        let names: Vec<String> =
+            serde_wasm_bindgen::from_value(subset).map_err(ArrowWasmError::from)?;
+        names
+            .iter()
+            .map(|name| {
+                table
+                    .schema
+                    .index_of(name)
+                    .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))
+            })
+            .collect::<Result<_>>()?
+    };
+    Ok(mem::store_table(drop_null_rows(&table, &indices)?)?)
+}
+
+/// `table` without the rows holding a null in any of the columns at
+/// `indices`; see [`drop_nulls`].
+fn drop_null_rows(table: &TableData, indices: &[usize]) -> Result<TableData> {
+    let batches = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let valid = indices.iter().fold(None, |valid, &index| {
+                NullBuffer::union(valid.as_ref(), batch.column(index).logical_nulls().as_ref())
+            });
+            match valid {
+                Some(valid) if valid.null_count() > 0 => {
+                    filter_record_batch(batch, &BooleanArray::new(valid.into_inner(), None))
+                }
+                _ => Ok(batch.clone()),
+            }
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    TableData::new(batches)
+}
+
+/// xxHash64 of every row of `table`; see [`row_hashes`].
+fn hash_rows(table: &TableData) -> Result<Vec<u64>> {
+    let mut hashers = vec![stable_hasher(); table.row_count()];
+    for (index, field) in table.schema.fields().iter().enumerate() {
+        let mut offset = 0;
+        for batch in &table.batches {
+            let chunk = batch.column(index);
+            visit_keys(chunk.as_ref(), &mut |i, key| {
+                write_key(&mut hashers[offset + i], key.as_ref());
+            })
+            .map_err(|e| e.in_column("row_hashes", field))?;
+            offset += chunk.len();
+        }
+    }
+    Ok(hashers.iter().map(Hasher::finish).collect())
+}
+
+/// Append a `UInt64` column `name` holding a hash of each row.
+///
+/// Every column contributes, in schema order, with values canonicalized as
+/// for `drop_duplicates` keys, so rows whose columns are pairwise
+/// `Column.values_equal` hash alike, whatever batch they are in. The hash
+/// is xxHash64 over a fixed byte encoding of the values, so it does not
+/// depend on the platform or the Rust release the module was built with;
+/// it may still change between versions of this crate, so do not persist
+/// hashes across upgrades. Nested columns are not supported.
+#[wasm_bindgen]
+pub fn row_hashes(handle: TableHandle, name: &str) -> std::result::Result<TableHandle, JsValue> {
+    let table = mem::get_table(handle)?;
+    let hashes = hash_rows(&table)?;
+
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    fields.push(Arc::new(Field::new(name, DataType::UInt64, false)));
+    let mut finished = hashes.into_iter();
+    let batch_columns = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let mut columns = batch.columns().to_vec();
+            let chunk = UInt64Array::from_iter_values(finished.by_ref().take(batch.num_rows()));
+            columns.push(Arc::new(chunk) as ArrayRef);
+            columns
+        })
+        .collect();
+    Ok(mem::store_table(rebuild_table(
+        &table,
+        fields,
+        batch_columns,
+    )?)?)
+}
+
+/// Integer type of a [`with_row_index`] column.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RowIndexType {
+    #[default]
+    Int32,
+    Int64,
+}
+
+/// Where [`with_row_index`] puts its column.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RowIndexPosition {
+    #[default]
+    Start,
+    End,
+}
+
+/// Options for [`with_row_index`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct RowIndexOptions {
+    start_at: i64,
+    #[serde(rename = "type")]
+    index_type: RowIndexType,
+    position: RowIndexPosition,
+}
+
+/// Add a column numbering the rows `startAt, startAt + 1, ...`.
+///
+/// `name` defaults to `"__row_index"`; `options` is `{startAt?, type?,
+/// position?}` with `type` `"int32"` (the default) or `"int64"` and
+/// `position` `"start"` (the default) or `"end"`. Filters, sorts and
+/// selections carry the column along with its rows, which makes it the
+/// supported way to track original positions; `positions_of` maps ids back
+/// to current rows.
+#[wasm_bindgen]
+pub fn with_row_index(
+    handle: TableHandle,
+    name: Option<String>,
+    options: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let options: RowIndexOptions = if options.is_undefined() || options.is_null() {
+        RowIndexOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let name = name.unwrap_or_else(|| "__row_index".to_string());
+    let table = mem::get_table(handle)?;
+    let rows = i64::try_from(table.row_count()).unwrap_or(i64::MAX);
+    let last = options.start_at.checked_add(rows - 1);
+    let data_type = match options.index_type {
+        RowIndexType::Int32 => DataType::Int32,
+        RowIndexType::Int64 => DataType::Int64,
+    };
+    let fits = last.is_some_and(|last| match options.index_type {
+        RowIndexType::Int32 => {
+            i32::try_from(last).is_ok() && i32::try_from(options.start_at).is_ok()
+        }
+        RowIndexType::Int64 => true,
+    });
+    if !fits && rows > 0 {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Row index starting at {} overflows {data_type:?} over {rows} rows",
+            options.start_at
+        ))
+        .into());
+    }
+
+    let field = Arc::new(Field::new(&name, data_type, false));
+    let mut fields: Vec<FieldRef> = table.schema.fields().iter().cloned().collect();
+    let mut start = options.start_at;
+    let batch_columns = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let range = start..start + i64::try_from(batch.num_rows()).unwrap_or(i64::MAX);
+            start = range.end;
+            let index: ArrayRef = match options.index_type {
+                RowIndexType::Int32 => {
+                    Arc::new(Int32Array::from_iter_values(range.map(|row| row as i32)))
+                }
+                RowIndexType::Int64 => Arc::new(Int64Array::from_iter_values(range)),
+            };
+            let mut columns = batch.columns().to_vec();
+            match options.position {
+                RowIndexPosition::Start => columns.insert(0, index),
+                RowIndexPosition::End => columns.push(index),
+            }
+            columns
+        })
+        .collect();
+    match options.position {
+        RowIndexPosition::Start => fields.insert(0, field),
+        RowIndexPosition::End => fields.push(field),
+    }
+    Ok(mem::store_table(rebuild_table(
+        &table,
+        fields,
+        batch_columns,
+    )?)?)
+}
+
+/// Ids passed to `positions_of`: an array of numbers and bigints, or a
+/// `BigInt64Array`/`BigUint64Array`. Non-integer numbers match no row.
+fn ids_from_js(ids: &JsValue) -> Result<Vec<Option<i128>>> {
+    if let Some(ids) = ids.dyn_ref::<js_sys::BigInt64Array>() {
+        return Ok(ids.to_vec().into_iter().map(|id| Some(id.into())).collect());
+    }
+    if let Some(ids) = ids.dyn_ref::<js_sys::BigUint64Array>() {
+        return Ok(ids.to_vec().into_iter().map(|id| Some(id.into())).collect());
+    }
+    let Some(ids) = ids.dyn_ref::<js_sys::Array>() else {
+        return Err(ArrowWasmError::InvalidInput(
+            "ids must be an array, BigInt64Array or BigUint64Array".to_string(),
+        ));
+    };
+    ids.iter()
+        .map(|id| {
+            if let Some(number) = id.as_f64() {
+                if number.fract() != 0.0 {
+                    return Ok(None);
+                }
+                if number.abs() > 9_007_199_254_740_991.0 {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "{number} is not a safe integer; pass a bigint"
+                    )));
+                }
+                return Ok(Some(number as i128));
+            }
+            id.dyn_ref::<js_sys::BigInt>()
+                .and_then(|big| big.to_string(10).ok())
+                .and_then(|text| String::from(text).parse().ok())
+                .map(Some)
+                .ok_or_else(|| {
+                    ArrowWasmError::InvalidInput(format!(
+                        "Invalid id {id:?}; pass a number or bigint"
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Position of the first row of `chunks` holding each of `ids`.
+fn row_positions(chunks: &[ArrayRef], ids: &[Option<i128>]) -> Result<Vec<Option<usize>>> {
+    let mut positions: HashMap<i128, usize> = HashMap::new();
+    let mut offset = 0;
+    for chunk in chunks {
+        visit_keys(chunk.as_ref(), &mut |i, key| {
+            if let Some(Key::Int(id)) = key {
+                positions.entry(id).or_insert(offset + i);
+            }
+        })?;
+        offset += chunk.len();
+    }
+    Ok(ids
+        .iter()
+        .map(|id| id.and_then(|id| positions.get(&id).copied()))
+        .collect())
+}
+
+/// Current row position of each id in `ids` within the integer column
+/// `index_column`, typically one added by `with_row_index`.
+///
+/// `ids` is an array of numbers or bigints, or a `BigInt64Array` or
+/// `BigUint64Array`; ids are matched exactly, so 64-bit integer ids past
+/// 2^53 must be passed as bigints. Returns an array with one entry per id:
+/// the position of the first row holding it, or `null` when no row does
+/// (e.g. it was filtered out).
+#[wasm_bindgen]
+pub fn positions_of(
+    handle: TableHandle,
+    index_column: &str,
+    ids: JsValue,
+) -> std::result::Result<js_sys::Array, JsValue> {
+    let table = mem::get_table(handle)?;
+    let chunks = table.get_column_by_name(index_column)?;
+    let field = table
+        .schema
+        .field_with_name(index_column)
+        .map_err(ArrowWasmError::from)?;
+    if !field.data_type().is_integer() {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Index column must be an integer column, got {:?}",
+            field.data_type()
+        ))
+        .into());
+    }
+    let ids = ids_from_js(&ids)?;
+    let result = js_sys::Array::new();
+    for position in row_positions(&chunks, &ids)? {
+        result.push(&position.map_or(JsValue::NULL, |row| JsValue::from_f64(row as f64)));
+    }
+    Ok(result)
+}
+
+/// Options for [`flatten_struct`].
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct FlattenOptions {
+    separator: String,
+    max_depth: usize,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: ".".to_string(),
+            max_depth: usize::MAX,
+        }
+    }
+}
+
+/// Overlay `nulls` on `array`'s own validity.
+fn mask_nulls(array: &ArrayRef, nulls: Option<&NullBuffer>) -> Result<ArrayRef> {
+    let Some(nulls) = nulls.filter(|nulls| nulls.null_count() > 0) else {
+        return Ok(Arc::clone(array));
+    };
+    if array.data_type() == &DataType::Null {
+        return Ok(Arc::clone(array));
+    }
+    let combined = NullBuffer::union(Some(nulls), array.nulls());
+    let data = array.to_data().into_builder().nulls(combined).build()?;
+    Ok(make_array(data))
+}
+
+/// Collects the flattened fields and columns of one batch.
+struct Flattener<'a> {
+    separator: &'a str,
+    fields: Vec<FieldRef>,
+    columns: Vec<ArrayRef>,
+}
+
+impl Flattener<'_> {
+    /// Append the leaves of `field`/`array`, expanding structs down to
+    /// `depth_left` levels. `parent_nulls` is the combined validity of the
+    /// enclosing structs.
+    fn push(
+        &mut self,
+        name: String,
+        field: &Field,
+        array: &ArrayRef,
+        parent_nulls: Option<&NullBuffer>,
+        parent_nullable: bool,
+        depth_left: usize,
+    ) -> Result<()> {
+        if let (DataType::Struct(children), true) = (field.data_type(), depth_left > 0) {
+            let structure = array.as_struct();
+            let nulls = NullBuffer::union(parent_nulls, structure.nulls());
+            for (child, child_array) in children.iter().zip(structure.columns()) {
+                self.push(
+                    format!("{name}{}{}", self.separator, child.name()),
+                    child,
+                    child_array,
+                    nulls.as_ref(),
+                    parent_nullable || field.is_nullable(),
+                    depth_left - 1,
+                )?;
+            }
+            return Ok(());
+        }
+        self.fields.push(Arc::new(
+            Field::new(
+                name,
+                field.data_type().clone(),
+                parent_nullable || field.is_nullable(),
+            )
+            .with_metadata(field.metadata().clone()),
+        ));
+        self.columns.push(mask_nulls(array, parent_nulls)?);
+        Ok(())
+    }
+}
+
+/// Replace Struct columns with one top-level column per child.
+///
+/// Children are named `parent{separator}child` (`"."` by default) and
+/// nested structs are expanded up to `maxDepth` levels (unlimited by
+/// default). A null struct row nulls every child output in that row. Child
+/// field metadata carries over. Without `column`, every Struct column is
+/// flattened. `options` is `{separator?, maxDepth?}`.
+#[wasm_bindgen]
+pub fn flatten_struct(
+    handle: TableHandle,
+    column: Option<String>,
+    options: JsValue,
+) -> std::result::Result<TableHandle, JsValue> {
+    let options: FlattenOptions = if options.is_undefined() || options.is_null() {
+        FlattenOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+    };
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(flatten_table(
+        &table,
+        column.as_deref(),
+        &options,
+    )?)?)
+}
+
+/// `table` with its Struct columns flattened; see [`flatten_struct`].
+fn flatten_table(
+    table: &TableData,
+    column: Option<&str>,
+    options: &FlattenOptions,
+) -> Result<TableData> {
+    if let Some(name) = column {
+        let field = table
+            .schema
+            .field_with_name(name)
+            .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))?;
+        if !matches!(field.data_type(), DataType::Struct(_)) {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Column '{name}' is {:?}, not a Struct",
+                field.data_type()
+            )));
+        }
+    }
+    let selected = |field: &Field| {
+        matches!(field.data_type(), DataType::Struct(_))
+            && column.is_none_or(|name| name == field.name())
+    };
+
+    let mut fields = None;
+    let mut batch_columns = Vec::with_capacity(table.batches.len());
+    for batch in &table.batches {
+        let mut flattener = Flattener {
+            separator: &options.separator,
+            fields: Vec::new(),
+            columns: Vec::new(),
+        };
+        for (field, array) in table.schema.fields().iter().zip(batch.columns()) {
+            if selected(field) {
+                flattener.push(
+                    field.name().clone(),
+                    field,
+                    array,
+                    None,
+                    false,
+                    options.max_depth,
+                )?;
+            } else {
+                flattener.fields.push(Arc::clone(field));
+                flattener.columns.push(Arc::clone(array));
+            }
+        }
+        fields.get_or_insert(flattener.fields);
+        batch_columns.push(flattener.columns);
+    }
+    rebuild_table(table, fields.unwrap_or_default(), batch_columns)
+}
+
+/// Pack `columns` into a new Struct column `into_name`.
+///
+/// The struct takes the position of the first listed column and has no
+/// nulls of its own. Child names drop a leading `into_name{separator}`
+/// prefix (separator `"."` by default), so `nest` undoes `flatten_struct`.
+#[wasm_bindgen]
+pub fn nest(
+    handle: TableHandle,
+    columns: JsValue,
+    into_name: &str,
+    separator: Option<String>,
+) -> std::result::Result<TableHandle, JsValue> {
+    let names: Vec<String> =
+        serde_wasm_bindgen::from_value(columns).map_err(ArrowWasmError::from)?;
+    if names.is_empty() {
+        return Err(
+            ArrowWasmError::InvalidInput("At least one column is required".to_string()).into(),
+        );
+    }
+    let table = mem::get_table(handle)?;
+    Ok(mem::store_table(nest_columns(
+        &table,
+        &names,
+        into_name,
+        separator.as_deref().unwrap_or("."),
+    )?)?)
+}
+
+/// `table` with the `names` columns packed into `into_name`; see [`nest`].
+fn nest_columns(
+    table: &TableData,
+    names: &[String],
+    into_name: &str,
+    separator: &str,
+) -> Result<TableData> {
+    validate_unique_names(names.iter().map(String::as_str))?;
+    let indices = names
+        .iter()
+        .map(|name| {
+            table
+                .schema
+                .index_of(name)
+                .map_err(|_| ArrowWasmError::InvalidInput(format!("Column '{name}' not found")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let prefix = format!("{into_name}{separator}");
+    let children: Fields = indices
+        .iter()
+        .map(|index| {
+            let field = table.schema.field(*index);
+            let name = field.name().strip_prefix(&prefix).unwrap_or(field.name());
+            Arc::new(field.clone().with_name(name))
+        })
+        .collect();
+    let position = indices.iter().copied().min().unwrap_or(0);
+    let keep = |index: usize| !indices.contains(&index);
+
+    let mut fields: Vec<FieldRef> = Vec::new();
+    for (index, field) in table.schema.fields().iter().enumerate() {
+        if index == position {
+            fields.push(Arc::new(Field::new(
+                into_name,
+                DataType::Struct(children.clone()),
+                false,
+            )));
+        }
+        if keep(index) {
+            fields.push(Arc::clone(field));
+        }
+    }
+    let batch_columns = table
+        .batches
+        .iter()
+        .map(|batch| {
+            let packed = StructArray::try_new(
+                children.clone(),
+                indices
+                    .iter()
+                    .map(|index| Arc::clone(batch.column(*index)))
+                    .collect(),
+                None,
+            )?;
+            let mut columns = Vec::with_capacity(fields.len());
+            for (index, array) in batch.columns().iter().enumerate() {
+                if index == position {
+                    columns.push(Arc::new(packed.clone()) as ArrayRef);
+                }
+                if keep(index) {
+                    columns.push(Arc::clone(array));
+                }
+            }
+            Ok(columns)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    rebuild_table(table, fields, batch_columns)
+}
+
+/// Describe the first difference between a batch schema and the expected one.
+fn describe_schema_difference(expected: &Schema, actual: &Schema) -> String {
+    for field in actual.fields() {
+        match expected.field_with_name(field.name()) {
+            Err(_) => return format!("unexpected field '{}'", field.name()),
+            Ok(expected_field) if expected_field.data_type() != field.data_type() => {
+                return format!(
+                    "field '{}' is {:?} but the stream schema has {:?}",
+                    field.name(),
+                    field.data_type(),
+                    expected_field.data_type()
+                );
+            }
+            Ok(expected_field) if expected_field.is_nullable() != field.is_nullable() => {
+                return format!("field '{}' differs in nullability", field.name());
+            }
+            Ok(_) => {}
+        }
+    }
+    for field in expected.fields() {
+        if actual.field_with_name(field.name()).is_err() {
+            return format!("missing field '{}'", field.name());
+        }
+    }
+    "field order differs".to_string()
+}
+
+/// Check that every batch matches `schema`, failing on the first mismatch.
+///
+/// With `allow_evolution`, batches that only add or omit fields are
+/// reconciled instead: the result uses the merged schema (fields in first-
+/// seen order, types must agree) and absent columns are filled with nulls.
+pub fn reconcile_batches(
+    schema: &SchemaRef,
+    batches: Vec<RecordBatch>,
+    allow_evolution: bool,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let mismatch = batches
+        .iter()
+        .position(|batch| batch.schema().fields() != schema.fields());
+    let Some(first_mismatch) = mismatch else {
+        return Ok((Arc::clone(schema), batches));
+    };
+    if !allow_evolution {
+        let batch_schema = batches[first_mismatch].schema();
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "Batch {first_mismatch} schema differs from the stream schema: {}",
+            describe_schema_difference(schema, &batch_schema)
+        )));
+    }
+
+    let mut merged: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    for (index, batch) in batches.iter().enumerate() {
+        for field in batch.schema().fields() {
+            match merged.iter_mut().find(|m| m.name() == field.name()) {
+                Some(existing) if existing.data_type() != field.data_type() => {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "Batch {index} cannot be reconciled: {}",
+                        describe_schema_difference(schema, &batch.schema())
+                    )));
+                }
+                Some(existing) => {
+                    if field.is_nullable() {
+                        existing.set_nullable(true);
+                    }
+                }
+                None => merged.push(field.as_ref().clone().with_nullable(true)),
+            }
+        }
+    }
+    for field in &mut merged {
+        if batches
+            .iter()
+            .any(|batch| batch.schema().field_with_name(field.name()).is_err())
+        {
+            field.set_nullable(true);
+        }
+    }
+
+    let merged = Arc::new(Schema::new_with_metadata(merged, schema.metadata().clone()));
+    let batches = batches
+        .into_iter()
+        .map(|batch| {
+            let columns = merged
+                .fields()
+                .iter()
+                .map(|field| {
+                    batch.column_by_name(field.name()).map_or_else(
+                        || new_null_array(field.data_type(), batch.num_rows()),
+                        Arc::clone,
+                    )
+                })
+                .collect();
+            RecordBatch::try_new(Arc::clone(&merged), columns)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok((merged, batches))
+}
+
+/// Cast every batch to `schema`, matching columns by name.
+///
+/// Returns the cast batches plus the names of the columns that had to be
+/// cast in at least one batch, in schema order. Batches must carry exactly
+/// the schema's columns, and each differing type must be castable.
+pub fn coerce_batches(
+    schema: &SchemaRef,
+    batches: Vec<RecordBatch>,
+) -> Result<(Vec<RecordBatch>, Vec<String>)> {
+    let mut coerced = vec![false; schema.fields().len()];
+    let mut result = Vec::with_capacity(batches.len());
+    for (index, batch) in batches.into_iter().enumerate() {
+        if batch.schema().fields() == schema.fields() {
+            result.push(batch);
+            continue;
+        }
+        if batch.num_columns() != schema.fields().len() {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "Batch {index} cannot be coerced: {}",
+                describe_schema_difference(schema, &batch.schema())
+            )));
+        }
+        let mut columns = Vec::with_capacity(schema.fields().len());
+        for (position, field) in schema.fields().iter().enumerate() {
+            let column = batch.column_by_name(field.name()).ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Batch {index} cannot be coerced: missing field '{}'",
+                    field.name()
+                ))
+            })?;
+            if column.data_type() == field.data_type() {
+                columns.push(Arc::clone(column));
+                continue;
+            }
+            if !can_cast_types(column.data_type(), field.data_type()) {
+                return Err(ArrowWasmError::InvalidInput(format!(
+                    "Batch {index} cannot be coerced: field '{}' is {:?} and cannot be cast to {:?}",
+                    field.name(),
+                    column.data_type(),
+                    field.data_type()
+                )));
+            }
+            columns.push(cast(column, field.data_type())?);
+            coerced[position] = true;
+        }
+        result.push(RecordBatch::try_new(Arc::clone(schema), columns)?);
+    }
+    let names = schema
+        .fields()
+        .iter()
+        .zip(coerced)
+        .filter(|(_, coerced)| *coerced)
+        .map(|(field, _)| field.name().clone())
+        .collect();
+    Ok((result, names))
+}
+
+/// Concatenate tables row-wise into one multi-batch table.
+///
+/// Schemas must match exactly unless `allow_schema_evolution` is set, in
+/// which case added fields are reconciled as in `read_table_from_bytes_with_options`.
+#[wasm_bindgen]
+pub fn concat_tables(
+    handles: Vec<TableHandle>,
+    allow_schema_evolution: bool,
+) -> std::result::Result<TableHandle, JsValue> {
+    let mut tables = handles
+        .iter()
+        .map(|handle| mem::get_table(*handle))
+        .collect::<Result<Vec<_>>>()?;
+    if tables.is_empty() {
+        return Err(ArrowWasmError::InvalidInput("No tables to concatenate".to_string()).into());
+    }
+    let schema = Arc::clone(&tables[0].schema);
+    let batches = tables
+        .iter_mut()
+        .flat_map(|t| t.batches.drain(..))
+        .collect();
+    let (_, batches) = reconcile_batches(&schema, batches, allow_schema_evolution)?;
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+/// Merge runs of adjacent small batches into batches of up to
+/// `target_rows` rows, e.g. after many `append_rows` calls.
+///
+/// Consecutive batches are concatenated while their combined row count
+/// stays within `target_rows`; a batch already larger than that is kept
+/// whole, and empty batches are dropped. Row order, values and the schema
+/// are unchanged. Batches that end up alone in their group are shared with
+/// the source rather than copied.
+#[wasm_bindgen]
+pub fn coalesce_batches(
+    handle: TableHandle,
+    target_rows: usize,
+) -> std::result::Result<TableHandle, JsValue> {
+    if target_rows == 0 {
+        return Err(
+            ArrowWasmError::InvalidInput("targetRows must be at least 1".to_string()).into(),
+        );
+    }
+    let table = mem::get_table(handle)?;
+    let mut groups: Vec<Vec<RecordBatch>> = Vec::new();
+    let mut group_rows = 0;
+    for batch in table.batches.iter().filter(|batch| batch.num_rows() > 0) {
+        match groups.last_mut() {
+            Some(group) if group_rows + batch.num_rows() <= target_rows => {
+                group.push(batch.clone());
+                group_rows += batch.num_rows();
+            }
+            _ => {
+                groups.push(vec![batch.clone()]);
+                group_rows = batch.num_rows();
+            }
+        }
+    }
+    let mut batches = groups
+        .into_iter()
+        .map(|group| match group.as_slice() {
+            [batch] => Ok(batch.clone()),
+            _ => concat_batches(&table.schema, &group),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(ArrowWasmError::from)?;
+    if batches.is_empty() {
+        batches.push(RecordBatch::new_empty(Arc::clone(&table.schema)));
+    }
+    Ok(mem::store_table(TableData::new(batches)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{
+        Array, AsArray, BooleanArray, DictionaryArray, Int32Array, Int64Array, StringArray,
+        StructArray,
+    };
+    use arrow::datatypes::{DataType, Int32Type, Int8Type};
+
+    fn ints(values: &[i32]) -> ArrayRef {
+        Arc::new(Int32Array::from(values.to_vec()))
+    }
+
+    fn lengths(chunks: &[ArrayRef]) -> Vec<usize> {
+        chunks.iter().map(Array::len).collect()
+    }
+
+    #[test]
+    fn matching_chunks_are_reused() {
+        let chunks = [ints(&[1, 2, 3]), ints(&[4, 5])];
+        let aligned = align_chunks(&chunks, &[3, 2]).unwrap();
+        assert!(aligned
+            .iter()
+            .zip(&chunks)
+            .all(|(aligned, chunk)| Arc::ptr_eq(aligned, chunk)));
+        assert!(align_chunks(&[], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn chunks_follow_the_table_lengths() {
+        let chunks = [ints(&[1, 2, 3]), ints(&[4, 5])];
+        for target in [vec![1, 4], vec![5], vec![2, 0, 2, 1], vec![0, 5, 0]] {
+            let aligned = align_chunks(&chunks, &target).unwrap();
+            assert_eq!(lengths(&aligned), target);
+            let values: Vec<i32> = aligned
+                .iter()
+                .flat_map(|chunk| chunk.as_primitive::<Int32Type>().values().to_vec())
+                .collect();
+            assert_eq!(values, [1, 2, 3, 4, 5], "{target:?}");
+        }
+    }
+
+    #[test]
+    fn row_count_mismatch_fails() {
+        let Err(ArrowWasmError::InvalidInput(message)) = align_chunks(&[ints(&[1, 2])], &[1, 2])
+        else {
+            panic!("3 rows cannot hold a 2-row column");
+        };
+        assert_eq!(message, "Column has 2 rows but table has 3");
+    }
+
+    fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+        RecordBatch::try_from_iter(columns).unwrap()
+    }
+
+    fn texts(values: &[&str]) -> ArrayRef {
+        Arc::new(arrow::array::StringArray::from(values.to_vec()))
+    }
+
+    #[test]
+    fn matching_batches_keep_the_schema() {
+        let first = batch(vec![("id", ints(&[1, 2]))]);
+        let schema = first.schema();
+        let batches = vec![first, batch(vec![("id", ints(&[3]))])];
+        for allow_evolution in [false, true] {
+            let (reconciled, result) =
+                reconcile_batches(&schema, batches.clone(), allow_evolution).unwrap();
+            assert!(Arc::ptr_eq(&reconciled, &schema));
+            assert_eq!(result, batches);
+        }
+    }
+
+    #[test]
+    fn strict_reconcile_names_the_difference() {
+        let first = batch(vec![("id", ints(&[1]))]);
+        let schema = first.schema();
+        let cases = [
+            (
+                vec![("id", ints(&[2])), ("name", texts(&["a"]))],
+                "unexpected field 'name'",
+            ),
+            (
+                vec![("id", texts(&["2"]))],
+                "field 'id' is Utf8 but the stream schema has Int32",
+            ),
+            (vec![("name", texts(&["a"]))], "unexpected field 'name'"),
+        ];
+        for (columns, difference) in cases {
+            let batches = vec![first.clone(), batch(columns)];
+            let Err(ArrowWasmError::InvalidInput(message)) =
+                reconcile_batches(&schema, batches, false)
+            else {
+                panic!("{difference}: differing schemas must fail without evolution");
+            };
+            assert_eq!(
+                message,
+                format!("Batch 1 schema differs from the stream schema: {difference}")
+            );
+        }
+    }
+
+    #[test]
+    fn evolution_merges_fields_and_fills_nulls() {
+        let first = batch(vec![("id", ints(&[1, 2]))]);
+        let schema = first.schema();
+        let second = batch(vec![("name", texts(&["c"])), ("id", ints(&[3]))]);
+        let (merged, result) = reconcile_batches(&schema, vec![first, second], true).unwrap();
+
+        let names: Vec<_> = merged.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["id", "name"]);
+        assert!(!merged.field(0).is_nullable());
+        assert!(merged.field(1).is_nullable());
+        assert!(result
+            .iter()
+            .all(|batch| Arc::ptr_eq(&batch.schema(), &merged)));
+
+        let name = result[0].column(1);
+        assert_eq!((name.len(), name.null_count()), (2, 2));
+        assert_eq!(result[1].column(0).as_primitive::<Int32Type>().value(0), 3);
+        assert_eq!(result[1].column(1).as_string::<i32>().value(0), "c");
+    }
+
+    #[test]
+    fn evolution_marks_omitted_fields_nullable() {
+        let first = batch(vec![("id", ints(&[1])), ("score", ints(&[7]))]);
+        let schema = first.schema();
+        let second = batch(vec![("id", ints(&[2]))]);
+        let (merged, result) = reconcile_batches(&schema, vec![first, second], true).unwrap();
+        assert!(!merged.field(0).is_nullable());
+        assert!(merged.field(1).is_nullable());
+        assert_eq!(result[1].column(1).null_count(), 1);
+    }
+
+    #[test]
+    fn evolution_rejects_type_conflicts() {
+        let first = batch(vec![("id", ints(&[1]))]);
+        let schema = first.schema();
+        let second = batch(vec![("id", texts(&["2"]))]);
+        let Err(ArrowWasmError::InvalidInput(message)) =
+            reconcile_batches(&schema, vec![first, second], true)
+        else {
+            panic!("conflicting types cannot be merged");
+        };
+        assert_eq!(
+            message,
+            "Batch 1 cannot be reconciled: field 'id' is Utf8 but the stream schema has Int32"
+        );
+    }
+
+    #[test]
+    fn coercion_casts_to_the_first_schema() {
+        let first = batch(vec![("id", ints(&[1])), ("name", texts(&["a"]))]);
+        let schema = first.schema();
+        let int64s: ArrayRef = Arc::new(Int64Array::from(vec![2, 3]));
+        let batches = vec![
+            first,
+            batch(vec![("name", texts(&["b", "c"])), ("id", int64s)]),
+            batch(vec![("id", ints(&[4])), ("name", texts(&["d"]))]),
+        ];
+        let (result, coerced) = coerce_batches(&schema, batches).unwrap();
+        assert_eq!(coerced, ["id"]);
+        assert!(result.iter().all(|batch| batch.schema() == schema));
+        assert_eq!(
+            result[1].column(0).as_primitive::<Int32Type>().values(),
+            &[2, 3]
+        );
+        assert_eq!(result[1].column(1).as_string::<i32>().value(1), "c");
+    }
+
+    #[test]
+    fn matching_batches_need_no_coercion() {
+        let first = batch(vec![("id", ints(&[1]))]);
+        let schema = first.schema();
+        let (result, coerced) = coerce_batches(&schema, vec![first.clone()]).unwrap();
+        assert!(coerced.is_empty());
+        assert_eq!(result, [first]);
+    }
+
+    #[test]
+    fn coercion_needs_the_same_columns() {
+        let first = batch(vec![("id", ints(&[1])), ("name", texts(&["a"]))]);
+        let schema = first.schema();
+        let cases = [
+            (
+                vec![("id", ints(&[2]))],
+                "Batch 1 cannot be coerced: missing field 'name'",
+            ),
+            (
+                vec![("id", ints(&[2])), ("label", texts(&["b"]))],
+                "Batch 1 cannot be coerced: missing field 'name'",
+            ),
+            (
+                vec![
+                    ("id", ints(&[2])),
+                    ("name", texts(&["b"])),
+                    ("x", ints(&[3])),
+                ],
+                "Batch 1 cannot be coerced: unexpected field 'x'",
+            ),
+        ];
+        for (columns, expected) in cases {
+            let Err(ArrowWasmError::InvalidInput(message)) =
+                coerce_batches(&schema, vec![first.clone(), batch(columns)])
+            else {
+                panic!("{expected}");
+            };
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[test]
+    fn coercion_rejects_uncastable_types() {
+        let first = batch(vec![("id", ints(&[1]))]);
+        let schema = first.schema();
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("x", DataType::Int32, false)),
+            ints(&[2]),
+        )]));
+        let Err(ArrowWasmError::InvalidInput(message)) =
+            coerce_batches(&schema, vec![first, batch(vec![("id", structs)])])
+        else {
+            panic!("a struct cannot be cast to Int32");
+        };
+        assert!(
+            message.starts_with("Batch 1 cannot be coerced: field 'id' is Struct"),
+            "{message}"
+        );
+        assert!(message.ends_with("cannot be cast to Int32"), "{message}");
+    }
+
+    #[test]
+    fn large_ids_map_to_exact_positions() {
+        let base = (1_i64 << 60) + 1;
+        let chunks: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![base + 2, base])),
+            Arc::new(Int64Array::from(vec![Some(base + 1), None, Some(base)])),
+        ];
+        let ids = [
+            Some(base.into()),
+            Some((base + 1).into()),
+            Some((base + 3).into()),
+            None,
+            Some(i128::from(base) - 1),
+        ];
+        let positions = row_positions(&chunks, &ids).unwrap();
+        assert_eq!(positions, [Some(1), Some(2), None, None, None]);
+
+        let unsigned: Vec<ArrayRef> = vec![Arc::new(UInt64Array::from(vec![u64::MAX, 0]))];
+        let ids = [
+            Some(u64::MAX.into()),
+            Some(i128::from(u64::MAX) - 1),
+            Some(0),
+        ];
+        assert_eq!(
+            row_positions(&unsigned, &ids).unwrap(),
+            [Some(0), None, Some(1)]
+        );
+    }
+
+    /// Two batches of `id`, `name` and `score`, each field tagged with its
+    /// name and the schema tagged with `source`.
+    fn tagged_table() -> TableHandle {
+        let tag = |name: &str| HashMap::from([("tag".to_string(), name.to_string())]);
+        let schema = Arc::new(Schema::new_with_metadata(
+            ["id", "name", "score"]
+                .map(|name| Field::new(name, DataType::Int32, false).with_metadata(tag(name)))
+                .to_vec(),
+            HashMap::from([("source".to_string(), "test".to_string())]),
+        ));
+        let batches = [[1, 2], [3, 4]]
+            .iter()
+            .map(|values| RecordBatch::try_new(Arc::clone(&schema), vec![ints(values); 3]).unwrap())
+            .collect();
+        mem::store_table(TableData::new(batches).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn select_keeps_field_and_schema_metadata() {
+        let handle = tagged_table();
+        let selections: [&[&str]; 3] = [&["score"], &["score", "id"], &["id", "name", "score"]];
+        for names in selections {
+            let names: Vec<String> = names.iter().map(ToString::to_string).collect();
+            let selected = mem::get_table(select(handle, names.clone()).unwrap()).unwrap();
+            assert_eq!(selected.schema.metadata()["source"], "test");
+            for (field, name) in selected.schema.fields().iter().zip(&names) {
+                assert_eq!(field.name(), name);
+                assert_eq!(&field.metadata()["tag"], name);
+            }
+            assert_eq!(batch_lengths(&selected), [2, 2]);
+        }
+    }
+
+    /// `(key, row)` batches, the keys repeating across the batch boundary.
+    fn keyed_table() -> TableData {
+        let keyed =
+            |keys: &[&str], rows: &[i32]| batch(vec![("key", texts(keys)), ("row", ints(rows))]);
+        TableData::new(vec![
+            keyed(&["a", "b", "a"], &[0, 1, 2]),
+            keyed(&["c", "b"], &[3, 4]),
+            keyed(&["a"], &[5]),
+        ])
+        .unwrap()
+    }
+
+    fn rows(table: &TableData) -> Vec<i32> {
+        table
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(1)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drop_duplicates_keeps_the_first_row_per_key() {
+        let unique = unique_rows(&keyed_table(), &["key".to_string()], false).unwrap();
+        assert_eq!(rows(&unique), [0, 1, 3]);
+        assert_eq!(batch_lengths(&unique), [2, 1, 0]);
+    }
+
+    #[test]
+    fn drop_duplicates_keeps_the_last_row_per_key_across_batches() {
+        let unique = unique_rows(&keyed_table(), &["key".to_string()], true).unwrap();
+        assert_eq!(rows(&unique), [3, 4, 5]);
+        assert_eq!(batch_lengths(&unique), [0, 2, 1]);
+        let keys = unique.batches[1].column(0).as_string::<i32>();
+        assert_eq!(keys.iter().collect::<Vec<_>>(), [Some("c"), Some("b")]);
+    }
+
+    #[test]
+    fn drop_duplicates_on_every_column_keeps_distinct_rows() {
+        let names = ["key".to_string(), "row".to_string()];
+        let unique = unique_rows(&keyed_table(), &names, true).unwrap();
+        assert_eq!(rows(&unique), [0, 1, 2, 3, 4, 5]);
+    }
+
+    /// `id` and a `point` struct of `x` and an `inner` struct holding a
+    /// tagged `label`, in two batches. `point` is null in row 1, whose child
+    /// slots still hold values, and `inner` is null in row 2.
+    fn nested_table() -> TableData {
+        let unit = HashMap::from([("unit".to_string(), "m".to_string())]);
+        let label = Arc::new(Field::new("label", DataType::Utf8, false).with_metadata(unit));
+        let inner = Arc::new(Field::new(
+            "inner",
+            DataType::Struct(vec![Arc::clone(&label)].into()),
+            true,
+        ));
+        let x = Arc::new(Field::new("x", DataType::Int32, false));
+        let point_fields: Fields = vec![Arc::clone(&x), Arc::clone(&inner)].into();
+        let point = |xs: &[i32], labels: &[&str], inner_valid: &[bool], valid: &[bool]| {
+            let inner = StructArray::try_new(
+                vec![Arc::clone(&label)].into(),
+                vec![texts(labels)],
+                Some(NullBuffer::from(inner_valid.to_vec())),
+            )
+            .unwrap();
+            Arc::new(
+                StructArray::try_new(
+                    point_fields.clone(),
+                    vec![ints(xs), Arc::new(inner)],
+                    Some(NullBuffer::from(valid.to_vec())),
+                )
+                .unwrap(),
+            ) as ArrayRef
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("point", DataType::Struct(point_fields.clone()), true),
+        ]));
+        TableData::new(vec![
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    ints(&[0, 1]),
+                    point(&[10, 11], &["a", "b"], &[true, true], &[true, false]),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                schema,
+                vec![ints(&[2]), point(&[12], &["c"], &[false], &[true])],
+            )
+            .unwrap(),
+        ])
+        .unwrap()
+    }
+
+    fn column_nulls(table: &TableData, name: &str) -> Vec<bool> {
+        table
+            .get_column_by_name(name)
+            .unwrap()
+            .iter()
+            .flat_map(|chunk| {
+                (0..chunk.len())
+                    .map(|i| chunk.is_null(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flatten_masks_children_of_null_structs() {
+        let flat = flatten_table(&nested_table(), None, &FlattenOptions::default()).unwrap();
+        let names: Vec<&str> = flat
+            .schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(names, ["id", "point.x", "point.inner.label"]);
+        assert_eq!(batch_lengths(&flat), [2, 1]);
+        assert_eq!(column_nulls(&flat, "id"), [false, false, false]);
+        assert_eq!(column_nulls(&flat, "point.x"), [false, true, false]);
+        assert_eq!(
+            column_nulls(&flat, "point.inner.label"),
+            [false, true, true]
+        );
+        let label = flat.schema.field_with_name("point.inner.label").unwrap();
+        assert!(label.is_nullable());
+        assert_eq!(label.metadata()["unit"], "m");
+        assert!(flat
+            .schema
+            .field_with_name("point.x")
+            .unwrap()
+            .is_nullable());
+        assert!(!flat.schema.field_with_name("id").unwrap().is_nullable());
+    }
+
+    #[test]
+    fn flatten_stops_at_max_depth() {
+        let options = FlattenOptions {
+            separator: "_".to_string(),
+            max_depth: 1,
+        };
+        let flat = flatten_table(&nested_table(), Some("point"), &options).unwrap();
+        let inner = flat.schema.field_with_name("point_inner").unwrap();
+        assert!(matches!(inner.data_type(), DataType::Struct(_)));
+        assert_eq!(column_nulls(&flat, "point_inner"), [false, true, true]);
+        let message = flatten_table(&nested_table(), Some("id"), &options)
+            .map(drop)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("Column 'id' is Int32, not a Struct"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn nest_undoes_flatten() {
+        let children: Fields = vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Utf8, false),
+        ]
+        .into();
+        let point = StructArray::try_new(
+            children.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                texts(&["a", "b", "c"]),
+            ],
+            None,
+        )
+        .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("point", DataType::Struct(children), false),
+            Field::new("tail", DataType::Int32, false),
+        ]));
+        let original = TableData::new(vec![RecordBatch::try_new(
+            schema,
+            vec![ints(&[0, 1, 2]), Arc::new(point), ints(&[5, 6, 7])],
+        )
+        .unwrap()])
+        .unwrap();
+
+        let flat = flatten_table(&original, None, &FlattenOptions::default()).unwrap();
+        let names = ["point.x".to_string(), "point.y".to_string()];
+        let nested = nest_columns(&flat, &names, "point", ".").unwrap();
+        assert_eq!(nested.schema, original.schema);
+        assert_eq!(nested.batches, original.batches);
+    }
+
+    #[test]
+    fn identical_rows_hash_alike_across_batches() {
+        let pair = |ids: &[i32], names: Vec<Option<&str>>| {
+            batch(vec![
+                ("id", ints(ids)),
+                ("name", Arc::new(arrow::array::StringArray::from(names))),
+            ])
+        };
+        let table = TableData::new(vec![
+            pair(&[1, 2], vec![Some("a"), None]),
+            pair(&[1, 2, 2], vec![Some("a"), None, Some("b")]),
+        ])
+        .unwrap();
+        let hashes = hash_rows(&table).unwrap();
+        assert_eq!(hashes[0], hashes[2]);
+        assert_eq!(hashes[1], hashes[3]);
+        assert_ne!(hashes[0], hashes[1]);
+        assert_ne!(hashes[3], hashes[4]);
+
+        // Values equal under `values_equal` hash alike whatever their type.
+        let wide = TableData::new(vec![batch(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 1, 2, 2]))),
+            (
+                "name",
+                Arc::new(
+                    [Some("a"), None, Some("a"), None, Some("b")]
+                        .into_iter()
+                        .collect::<arrow::array::DictionaryArray<Int32Type>>(),
+                ),
+            ),
+        ])])
+        .unwrap();
+        assert_eq!(hash_rows(&wide).unwrap(), hashes);
+    }
+
+    #[test]
+    fn row_hashes_have_a_fixed_output() {
+        // The xxHash64 reference value for empty input and seed 0.
+        assert_eq!(stable_hasher().finish(), 0xEF46_DB37_51D8_E999);
+        let table = TableData::new(vec![batch(vec![
+            ("id", ints(&[1])),
+            ("name", texts(&["a"])),
+        ])])
+        .unwrap();
+        // Pinned: a change here changes every persisted hash.
+        assert_eq!(hash_rows(&table).unwrap(), [9_031_955_686_581_628_717]);
+    }
+
+    fn entries(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merged_metadata_keeps_existing_keys() {
+        let unit = entries(&[("unit", "m"), ("source", "sonar")]);
+        let schema = Arc::new(
+            Schema::new(vec![
+                Field::new("depth", DataType::Int32, false).with_metadata(unit.clone()),
+                Field::new("n", DataType::Int32, false),
+            ])
+            .with_metadata(entries(&[("owner", "ops"), ("version", "1")])),
+        );
+        let table = TableData::new(vec![RecordBatch::try_new(
+            schema,
+            vec![ints(&[1, 2]), ints(&[3, 4])],
+        )
+        .unwrap()])
+        .unwrap();
+
+        let merged =
+            merge_schema_metadata(&table, entries(&[("version", "2"), ("note", "x")])).unwrap();
+        assert_eq!(
+            merged.schema.metadata(),
+            &entries(&[("owner", "ops"), ("version", "2"), ("note", "x")])
+        );
+        assert_eq!(merged.schema.field(0).metadata(), &unit);
+        assert_eq!(merged.batches[0].schema(), merged.schema);
+
+        let merged = merge_column_metadata(&table, "depth", entries(&[("unit", "cm")])).unwrap();
+        assert_eq!(
+            merged.schema.field(0).metadata(),
+            &entries(&[("unit", "cm"), ("source", "sonar")])
+        );
+        assert!(merged.schema.field(1).metadata().is_empty());
+        assert_eq!(merged.schema.metadata(), table.schema.metadata());
+        let merged = merge_column_metadata(&table, "n", entries(&[("unit", "s")])).unwrap();
+        assert_eq!(merged.schema.field(0).metadata(), &unit);
+        assert_eq!(
+            merged.schema.field(1).metadata(),
+            &entries(&[("unit", "s")])
+        );
+        // The source table is untouched.
+        assert_eq!(table.schema.field(0).metadata(), &unit);
+        assert!(merge_column_metadata(&table, "missing", HashMap::new()).is_err());
+    }
+
+    /// `flag` (nullable Boolean) and `id` (non-nullable Int32) over batches
+    /// of four and two rows.
+    fn flags() -> TableData {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("flag", DataType::Boolean, true),
+            Field::new("id", DataType::Int32, false),
+        ]));
+        let batch = |flags: Vec<Option<bool>>, ids: &[i32]| {
+            let flags: ArrayRef = Arc::new(BooleanArray::from(flags));
+            RecordBatch::try_new(Arc::clone(&schema), vec![flags, ints(ids)]).unwrap()
+        };
+        TableData::new(vec![
+            batch(
+                vec![Some(true), None, Some(false), Some(true)],
+                &[0, 1, 2, 3],
+            ),
+            batch(vec![None, Some(false)], &[4, 5]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn boolean_columns_keep_values_and_nulls_through_filters() {
+        let table = flags();
+        let mask: Vec<ArrayRef> = vec![Arc::new(BooleanArray::from(vec![
+            Some(true),
+            Some(true),
+            Some(false),
+            None,
+            Some(true),
+            Some(true),
+        ]))];
+        let (filtered, stats) = filter_table(&table, &mask, false).unwrap();
+        assert!(stats.is_none());
+        assert_eq!(filtered.schema, table.schema);
+        let flags: Vec<Option<bool>> = filtered
+            .batches
+            .iter()
+            .flat_map(|batch| batch.column(0).as_boolean().iter())
+            .collect();
+        assert_eq!(flags, [Some(true), None, None, Some(false)]);
+        let ids: Vec<i32> = filtered
+            .batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(1)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+        assert_eq!(ids, [0, 1, 4, 5]);
+        assert!(filtered
+            .batches
+            .iter()
+            .all(|batch| batch.column(1).nulls().is_none()));
+    }
+
+    #[test]
+    fn sampling_everything_or_nothing_is_exact() {
+        let ids: Vec<i32> = (0..1_000).collect();
+        let handle = mem::store_table(
+            TableData::new(vec![
+                batch(vec![("id", ints(&ids[..600]))]),
+                batch(vec![("id", ints(&ids[600..]))]),
+            ])
+            .unwrap(),
+        )
+        .unwrap();
+        let sample = |fraction, seed| {
+            mem::get_table(sample_fraction(handle, fraction, Some(seed)).unwrap()).unwrap()
+        };
+        for seed in [0, 1, u64::MAX] {
+            let all = sample(1.0, seed);
+            let values: Vec<i32> = all
+                .batches
+                .iter()
+                .flat_map(|batch| {
+                    batch
+                        .column(0)
+                        .as_primitive::<Int32Type>()
+                        .values()
+                        .to_vec()
+                })
+                .collect();
+            assert_eq!(values, ids);
+            let none = sample(0.0, seed);
+            assert_eq!(none.row_count(), 0);
+            assert_eq!(none.schema, all.schema);
+        }
+    }
+
+    #[test]
+    fn filter_stats_count_rows_and_nulls() {
+        let table = flags();
+        // Mask chunks that do not line up with the batches keep rows 0, 1
+        // and 4, two of them null in `flag`.
+        let mask: Vec<ArrayRef> = vec![
+            Arc::new(BooleanArray::from(vec![Some(true), Some(true), None])),
+            Arc::new(BooleanArray::from(vec![Some(false), Some(true), None])),
+        ];
+        let (plain, none) = filter_table(&table, &mask, false).unwrap();
+        assert!(none.is_none());
+        let (filtered, stats) = filter_table(&table, &mask, true).unwrap();
+        assert_eq!(filtered.batches, plain.batches);
+        assert_eq!(batch_lengths(&filtered), [2, 1]);
+
+        let stats = serde_json::to_value(stats.unwrap()).unwrap();
+        assert_eq!(
+            stats,
+            serde_json::json!({
+                "inputRows": 6,
+                "outputRows": 3,
+                "selectivity": 0.5,
+                "nullMaskEntries": 2,
+                "columns": [
+                    {"name": "flag", "nullsBefore": 2, "nullsAfter": 2},
+                    {"name": "id", "nullsBefore": 0, "nullsAfter": 0},
+                ],
+            })
+        );
+
+        let empty =
+            TableData::new(vec![RecordBatch::new_empty(Arc::clone(&table.schema))]).unwrap();
+        let mask: Vec<ArrayRef> = vec![Arc::new(BooleanArray::from(Vec::<bool>::new()))];
+        let (_, stats) = filter_table(&empty, &mask, true).unwrap();
+        let stats = stats.unwrap();
+        assert_eq!((stats.input_rows, stats.output_rows), (0, 0));
+        assert_eq!(stats.selectivity, None);
+    }
+
+    #[test]
+    fn rows_with_nulls_are_dropped() {
+        let a = |values: Vec<Option<i32>>| Arc::new(Int32Array::from(values)) as ArrayRef;
+        let b = |values: Vec<Option<&str>>| Arc::new(StringArray::from(values)) as ArrayRef;
+        // `c` is a dictionary whose key 1 points at a null value.
+        let c = |keys: Vec<i8>| {
+            let values = Arc::new(StringArray::from(vec![Some("x"), None]));
+            Arc::new(DictionaryArray::<Int8Type>::new(keys.into(), values)) as ArrayRef
+        };
+        let table = TableData::new(vec![
+            batch(vec![
+                ("a", a(vec![Some(0), None, Some(2), Some(3)])),
+                ("b", b(vec![Some("p"), Some("q"), None, Some("s")])),
+                ("c", c(vec![0, 0, 0, 1])),
+            ]),
+            batch(vec![
+                ("a", a(vec![Some(4), Some(5), None])),
+                ("b", b(vec![Some("t"), None, Some("v")])),
+                ("c", c(vec![0, 0, 0])),
+            ]),
+            batch(vec![
+                ("a", a(vec![Some(7)])),
+                ("b", b(vec![Some("w")])),
+                ("c", c(vec![0])),
+            ]),
+        ])
+        .unwrap();
+        let kept = |indices: &[usize]| {
+            let dropped = drop_null_rows(&table, indices).unwrap();
+            assert_eq!(dropped.schema, table.schema);
+            let values: Vec<Option<i32>> = dropped
+                .batches
+                .iter()
+                .flat_map(|batch| batch.column(0).as_primitive::<Int32Type>().iter())
+                .collect();
+            (values, dropped)
+        };
+        let (all, dropped) = kept(&[0, 1, 2]);
+        assert_eq!(all, [Some(0), Some(4), Some(7)]);
+        // The batch without nulls is shared, not filtered.
+        assert_eq!(dropped.batches[2], table.batches[2]);
+        assert_eq!(kept(&[0]).0, [0, 2, 3, 4, 5, 7].map(Some));
+        assert_eq!(
+            kept(&[1]).0,
+            [Some(0), None, Some(3), Some(4), None, Some(7)]
+        );
+        assert_eq!(
+            kept(&[2]).0,
+            [Some(0), None, Some(2), Some(4), Some(5), None, Some(7)]
+        );
+        assert_eq!(kept(&[]).0.len(), 8);
+    }
+
+    #[test]
+    fn single_row_batches_coalesce_into_fewer_batches() {
+        let single = |i: i32| {
+            let label = format!("row {i}");
+            batch(vec![
+                ("id", ints(&[i])),
+                ("label", texts(&[label.as_str()])),
+            ])
+        };
+        let mut batches: Vec<RecordBatch> = (0..10).map(single).collect();
+        // Empty batches are dropped along the way.
+        batches.insert(4, single(0).slice(0, 0));
+        let source = TableData::new(batches).unwrap();
+        let rows = |table: &TableData| -> Vec<(i32, String)> {
+            let table = concat_batches(&table.schema, &table.batches).unwrap();
+            let ids = table.column(0).as_primitive::<Int32Type>().values().iter();
+            let labels = table.column(1).as_string::<i32>().iter();
+            ids.zip(labels)
+                .map(|(id, label)| (*id, label.unwrap().to_string()))
+                .collect()
+        };
+        let handle = mem::store_table(source.clone()).unwrap();
+
+        for (target, sizes) in [
+            (5, vec![5, 5]),
+            (3, vec![3, 3, 3, 1]),
+            (1, vec![1; 10]),
+            (100, vec![10]),
+        ] {
+            let coalesced = mem::get_table(coalesce_batches(handle, target).unwrap()).unwrap();
+            let batch_rows: Vec<usize> = coalesced
+                .batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect();
+            assert_eq!(batch_rows, sizes, "target {target}");
+            assert_eq!(coalesced.schema, source.schema);
+            assert_eq!(rows(&coalesced), rows(&source), "target {target}");
+        }
+        assert_eq!(mem::get_table(handle).unwrap().batches.len(), 11);
+    }
+}
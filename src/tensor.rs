@@ -0,0 +1,414 @@
+//! Fixed-shape tensor columns (the canonical `arrow.fixed_shape_tensor`
+//! extension type).
+//!
+//! Such a column stores each row's tensor as one `FixedSizeList` entry of
+//! `product(shape)` values, and names the extension and its
+//! `{shape, dim_names?, permutation?}` JSON in the field metadata. Only
+//! columns carrying that metadata are tensors; a bare `FixedSizeList` is an
+//! ordinary list column. Values are exchanged with JS as typed arrays in the
+//! stored (row-major, permuted when `permutation` is set) order.
+
+use crate::column::{self, Column};
+use crate::convert::set_property;
+use crate::errors::{ArrowWasmError, Result};
+use arrow::array::{
+    Array, ArrayRef, ArrowPrimitiveType, AsArray, BooleanArray, FixedSizeListArray, Float32Array,
+    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, UInt16Array, UInt32Array,
+    UInt64Array, UInt8Array,
+};
+use arrow::buffer::BooleanBuffer;
+use arrow::datatypes::{
+    DataType, Field, FieldRef, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_select::concat::concat;
+use arrow_select::nullif::nullif;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Field metadata key naming the extension type.
+const EXTENSION_NAME: &str = "ARROW:extension:name";
+/// Field metadata key holding the extension parameters.
+const EXTENSION_METADATA: &str = "ARROW:extension:metadata";
+/// Canonical name of the fixed-shape tensor extension.
+const FIXED_SHAPE_TENSOR: &str = "arrow.fixed_shape_tensor";
+
+/// Parameters of the extension, as stored in the field metadata.
+#[derive(Debug, Serialize, Deserialize)]
+struct TensorMetadata {
+    shape: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dim_names: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    permutation: Option<Vec<usize>>,
+}
+
+/// The shape of a tensor column, as returned by `tensor_info`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TensorInfo<'a> {
+    shape: &'a [usize],
+    dim_names: Option<&'a [String]>,
+    permutation: Option<&'a [usize]>,
+    value_type: String,
+}
+
+/// What `tensors_as_contiguous` does with null rows and null values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum NullTensors {
+    /// Fail.
+    #[default]
+    Error,
+    /// Write zeros.
+    Zero,
+}
+
+/// Options for `tensors_as_contiguous`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct ContiguousOptions {
+    nulls: NullTensors,
+}
+
+/// Extension parameters and value type of a fixed-shape tensor field.
+fn tensor_metadata(field: &Field) -> Result<(TensorMetadata, DataType)> {
+    let not_tensor = || {
+        ArrowWasmError::InvalidInput(format!(
+            "column is not an {FIXED_SHAPE_TENSOR} column, got {:?}",
+            field.data_type()
+        ))
+    };
+    if field.metadata().get(EXTENSION_NAME).map(String::as_str) != Some(FIXED_SHAPE_TENSOR) {
+        return Err(not_tensor());
+    }
+    let DataType::FixedSizeList(item, size) = field.data_type() else {
+        return Err(not_tensor());
+    };
+    let invalid = |reason: String| {
+        ArrowWasmError::InvalidInput(format!("invalid {FIXED_SHAPE_TENSOR} metadata: {reason}"))
+    };
+    let parameters = field
+        .metadata()
+        .get(EXTENSION_METADATA)
+        .ok_or_else(|| invalid("missing".to_string()))?;
+    let metadata: TensorMetadata =
+        serde_json::from_str(parameters).map_err(|e| invalid(e.to_string()))?;
+    let elements: usize = metadata.shape.iter().product();
+    if usize::try_from(*size).ok() != Some(elements) {
+        return Err(invalid(format!(
+            "shape {:?} holds {elements} values but the list size is {size}",
+            metadata.shape
+        )));
+    }
+    if metadata
+        .dim_names
+        .as_ref()
+        .is_some_and(|names| names.len() != metadata.shape.len())
+    {
+        return Err(invalid("dim_names must name every dimension".to_string()));
+    }
+    if let Some(permutation) = &metadata.permutation {
+        let mut sorted = permutation.clone();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(0..metadata.shape.len()) {
+            return Err(invalid(format!(
+                "permutation {permutation:?} does not reorder {} dimensions",
+                metadata.shape.len()
+            )));
+        }
+    }
+    Ok((metadata, item.data_type().clone()))
+}
+
+/// Values of `array` as natives, nulls becoming zero when `zero_nulls` is
+/// set and failing otherwise.
+fn natives<T: ArrowPrimitiveType>(array: &dyn Array, zero_nulls: bool) -> Result<Vec<T::Native>> {
+    let array = array.as_primitive::<T>();
+    if array.null_count() == 0 {
+        return Ok(array.values().to_vec());
+    }
+    if !zero_nulls {
+        return Err(ArrowWasmError::InvalidInput(
+            "tensor holds null values; pass {nulls: \"zero\"} to write zeros".to_string(),
+        ));
+    }
+    Ok(array.iter().map(Option::unwrap_or_default).collect())
+}
+
+/// Copy a primitive array into the matching JS typed array (`BigInt64Array`
+/// and `BigUint64Array` for 64-bit integers).
+fn to_typed_array(values: &dyn Array, zero_nulls: bool) -> Result<JsValue> {
+    macro_rules! typed {
+        ($arrow:ty, $js:ty) => {
+            <$js>::from(natives::<$arrow>(values, zero_nulls)?.as_slice()).into()
+        };
+    }
+    Ok(match values.data_type() {
+        DataType::Int8 => typed!(Int8Type, js_sys::Int8Array),
+        DataType::Int16 => typed!(Int16Type, js_sys::Int16Array),
+        DataType::Int32 => typed!(Int32Type, js_sys::Int32Array),
+        DataType::Int64 => typed!(Int64Type, js_sys::BigInt64Array),
+        DataType::UInt8 => typed!(UInt8Type, js_sys::Uint8Array),
+        DataType::UInt16 => typed!(UInt16Type, js_sys::Uint16Array),
+        DataType::UInt32 => typed!(UInt32Type, js_sys::Uint32Array),
+        DataType::UInt64 => typed!(UInt64Type, js_sys::BigUint64Array),
+        DataType::Float32 => typed!(Float32Type, js_sys::Float32Array),
+        DataType::Float64 => typed!(Float64Type, js_sys::Float64Array),
+        other => {
+            return Err(ArrowWasmError::InvalidInput(format!(
+                "tensor values must be integers or floats, got {other:?}"
+            )))
+        }
+    })
+}
+
+/// Copy a JS typed array into an Arrow array of the matching type.
+fn from_typed_array(values: &JsValue) -> Result<ArrayRef> {
+    macro_rules! typed {
+        ($($js:ty => $arrow:ty),*) => {
+            $(if let Some(values) = values.dyn_ref::<$js>() {
+                return Ok(Arc::new(<$arrow>::from(values.to_vec())));
+            })*
+        };
+    }
+    typed!(
+        js_sys::Int8Array => Int8Array,
+        js_sys::Int16Array => Int16Array,
+        js_sys::Int32Array => Int32Array,
+        js_sys::BigInt64Array => Int64Array,
+        js_sys::Uint8Array => UInt8Array,
+        js_sys::Uint16Array => UInt16Array,
+        js_sys::Uint32Array => UInt32Array,
+        js_sys::BigUint64Array => UInt64Array,
+        js_sys::Float32Array => Float32Array,
+        js_sys::Float64Array => Float64Array
+    );
+    Err(ArrowWasmError::InvalidInput(
+        "tensor values must be a typed array".to_string(),
+    ))
+}
+
+/// Field and chunks of a tensor column, with its extension parameters.
+fn tensor_column(
+    column: &Column,
+    op: &'static str,
+) -> Result<(FieldRef, Vec<ArrayRef>, TensorMetadata)> {
+    let (field, chunks) = column.field_and_chunks()?;
+    let (metadata, _) = tensor_metadata(&field).map_err(|e| e.in_column(op, &field))?;
+    Ok((field, chunks, metadata))
+}
+
+#[wasm_bindgen]
+impl Column {
+    /// `{shape, dimNames, permutation, valueType}` of a fixed-shape tensor
+    /// column; `dimNames` and `permutation` are null when the metadata omits
+    /// them. Fails for any other column, including bare `FixedSizeList`s.
+    pub fn tensor_info(&self) -> std::result::Result<JsValue, JsValue> {
+        let (field, _) = self.field_and_chunks()?;
+        let (metadata, value_type) =
+            tensor_metadata(&field).map_err(|e| e.in_column("tensor_info", &field))?;
+        let info = TensorInfo {
+            shape: &metadata.shape,
+            dim_names: metadata.dim_names.as_deref(),
+            permutation: metadata.permutation.as_deref(),
+            value_type: format!("{value_type:?}"),
+        };
+        Ok(serde_wasm_bindgen::to_value(&info).map_err(ArrowWasmError::from)?)
+    }
+
+    /// Tensor at row `row` as `{values, shape}`, `values` being a typed array
+    /// of `product(shape)` elements; `null` for a null row. Null values
+    /// inside the tensor fail.
+    pub fn tensor_at(&self, row: usize) -> std::result::Result<JsValue, JsValue> {
+        let (field, chunks, metadata) = tensor_column(self, "tensor_at")?;
+        let context = |e: ArrowWasmError| e.in_column("tensor_at", &field);
+        let Some((chunk, offset)) = column::locate(&chunks, row) else {
+            let length: usize = chunks.iter().map(Array::len).sum();
+            return Err(context(ArrowWasmError::InvalidInput(format!(
+                "Row index {row} out of bounds for column of length {length}"
+            )))
+            .into());
+        };
+        let list = chunks[chunk].as_fixed_size_list();
+        if list.is_null(offset) {
+            return Ok(JsValue::NULL);
+        }
+        let values = to_typed_array(list.value(offset).as_ref(), false).map_err(context)?;
+        let result = js_sys::Object::new();
+        set_property(&result, "values", &values)?;
+        let shape = serde_wasm_bindgen::to_value(&metadata.shape).map_err(ArrowWasmError::from)?;
+        set_property(&result, "shape", &shape)?;
+        Ok(result.into())
+    }
+
+    /// Every tensor of the column in one typed array of
+    /// `rows * product(shape)` elements, row after row, e.g. to feed an
+    /// inference session as a batch of shape `[rows, ...shape]`.
+    ///
+    /// `options` is `{nulls?}`: null rows and null values fail with
+    /// `"error"` (the default) or are written as zeros with `"zero"`.
+    pub fn tensors_as_contiguous(&self, options: JsValue) -> std::result::Result<JsValue, JsValue> {
+        let options: ContiguousOptions = if options.is_undefined() || options.is_null() {
+            ContiguousOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(ArrowWasmError::from)?
+        };
+        let (field, chunks, _) = tensor_column(self, "tensors_as_contiguous")?;
+        let context = |e: ArrowWasmError| e.in_column("tensors_as_contiguous", &field);
+        let zero_nulls = options.nulls == NullTensors::Zero;
+        let values = chunks
+            .iter()
+            .map(|chunk| {
+                let list = chunk.as_fixed_size_list();
+                if list.null_count() > 0 && !zero_nulls {
+                    return Err(ArrowWasmError::InvalidInput(format!(
+                        "column holds {} null tensors; pass {{nulls: \"zero\"}} to write zeros",
+                        list.null_count()
+                    )));
+                }
+                flat_values(list)
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(context)?;
+        let values: Vec<&dyn Array> = values.iter().map(AsRef::as_ref).collect();
+        let values = concat(&values).map_err(|e| context(e.into()))?;
+        Ok(to_typed_array(values.as_ref(), zero_nulls).map_err(context)?)
+    }
+}
+
+/// Items of `list`, with the items of null rows made null.
+fn flat_values(list: &FixedSizeListArray) -> Result<ArrayRef> {
+    let Some(rows) = list.nulls() else {
+        return Ok(Arc::clone(list.values()));
+    };
+    let size = list.value_length().cast_unsigned() as usize;
+    let hidden = BooleanBuffer::collect_bool(list.values().len(), |item| rows.is_null(item / size));
+    Ok(nullif(list.values(), &BooleanArray::new(hidden, None))?)
+}
+
+/// Build a fixed-shape tensor column from the typed array `values`, holding
+/// `values.length / product(shape)` tensors stored one after another.
+///
+/// The column type is a `FixedSizeList` of the typed array's element type,
+/// with `arrow.fixed_shape_tensor` field metadata carrying `shape` and, when
+/// given, `dim_names` (one per dimension).
+#[wasm_bindgen]
+pub fn make_tensor_column(
+    name: &str,
+    values: &JsValue,
+    shape: Vec<usize>,
+    dim_names: Option<Vec<String>>,
+) -> std::result::Result<Column, JsValue> {
+    let values = from_typed_array(values)?;
+    let elements: usize = shape.iter().product();
+    if shape.is_empty() || elements == 0 {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "tensor shape must have positive dimensions, got {shape:?}"
+        ))
+        .into());
+    }
+    if values.len() % elements != 0 {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "{} values do not split into tensors of shape {shape:?}",
+            values.len()
+        ))
+        .into());
+    }
+    let size = i32::try_from(elements).map_err(|_| {
+        ArrowWasmError::InvalidInput(format!("tensor shape {shape:?} is too large"))
+    })?;
+    let item = Arc::new(Field::new("item", values.data_type().clone(), false));
+    let list = FixedSizeListArray::try_new(Arc::clone(&item), size, values, None)
+        .map_err(ArrowWasmError::from)?;
+    let metadata = TensorMetadata {
+        shape,
+        dim_names,
+        permutation: None,
+    };
+    let field = Field::new(name, DataType::FixedSizeList(item, size), false).with_metadata(
+        HashMap::from([
+            (EXTENSION_NAME.to_string(), FIXED_SHAPE_TENSOR.to_string()),
+            (
+                EXTENSION_METADATA.to_string(),
+                serde_json::to_string(&metadata).map_err(ArrowWasmError::from)?,
+            ),
+        ]),
+    );
+    tensor_metadata(&field)?;
+    Ok(column::store_column(Arc::new(field), vec![Arc::new(list)])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(size: i32) -> DataType {
+        DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, false)), size)
+    }
+
+    fn tensor_field(size: i32, parameters: &str) -> Field {
+        Field::new("t", list(size), false).with_metadata(HashMap::from([
+            (EXTENSION_NAME.to_string(), FIXED_SHAPE_TENSOR.to_string()),
+            (EXTENSION_METADATA.to_string(), parameters.to_string()),
+        ]))
+    }
+
+    #[test]
+    fn three_dimensional_shapes_are_read() {
+        let field = tensor_field(
+            24,
+            r#"{"shape":[2,3,4],"dim_names":["C","H","W"],"permutation":[2,0,1]}"#,
+        );
+        let (metadata, value_type) = tensor_metadata(&field).unwrap();
+        assert_eq!(metadata.shape, [2, 3, 4]);
+        assert_eq!(metadata.dim_names.unwrap(), ["C", "H", "W"]);
+        assert_eq!(metadata.permutation.unwrap(), [2, 0, 1]);
+        assert_eq!(value_type, DataType::Float32);
+    }
+
+    #[test]
+    fn bare_fixed_size_lists_are_not_tensors() {
+        let bare = Field::new("t", list(24), false);
+        let error = tensor_metadata(&bare).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("is not an arrow.fixed_shape_tensor column"),
+            "{error}"
+        );
+        let other = bare.with_metadata(HashMap::from([(
+            EXTENSION_NAME.to_string(),
+            "arrow.uuid".to_string(),
+        )]));
+        assert!(tensor_metadata(&other).is_err());
+    }
+
+    #[test]
+    fn metadata_must_match_the_list() {
+        for (size, parameters, reason) in [
+            (
+                24,
+                r#"{"shape":[2,3,5]}"#,
+                "holds 30 values but the list size is 24",
+            ),
+            (
+                24,
+                r#"{"shape":[2,3,4],"dim_names":["C","H"]}"#,
+                "dim_names",
+            ),
+            (
+                24,
+                r#"{"shape":[2,3,4],"permutation":[0,1,1]}"#,
+                "does not reorder 3",
+            ),
+            (24, "[2,3,4]", "invalid arrow.fixed_shape_tensor metadata"),
+        ] {
+            let error = tensor_metadata(&tensor_field(size, parameters)).unwrap_err();
+            assert!(error.to_string().contains(reason), "{error}");
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! A small bundled time zone database.
+//!
+//! Arrow is built without `chrono-tz` (the IANA database is too large for
+//! the module), so its own kernels only understand fixed offsets. Zones are
+//! resolved here instead: fixed offsets (`+05:30`, `-0800`, `+09`), `UTC`,
+//! and the named zones in [`ZONES`]. Named zones apply their current
+//! daylight saving rules to every year: the US rules in force since 2007,
+//! the EU rules since 1996 and the Australian rules since 2008. Historical
+//! offset changes are not modelled.
+
+use crate::errors::{ArrowWasmError, Result};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const HOUR: i32 = 3_600;
+
+/// When a zone observes daylight saving time (one hour ahead of standard).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rule {
+    /// No daylight saving time.
+    Fixed,
+    /// Second Sunday of March 02:00 to first Sunday of November 02:00 local.
+    Us,
+    /// Last Sunday of March to last Sunday of October, at 01:00 UTC.
+    Eu,
+    /// First Sunday of October 02:00 to first Sunday of April 03:00 local.
+    Australia,
+}
+
+/// Bundled named zones: name, standard offset in seconds east of UTC, rule.
+const ZONES: &[(&str, i32, Rule)] = &[
+    ("UTC", 0, Rule::Fixed),
+    ("Etc/UTC", 0, Rule::Fixed),
+    ("GMT", 0, Rule::Fixed),
+    ("America/New_York", -5 * HOUR, Rule::Us),
+    ("America/Toronto", -5 * HOUR, Rule::Us),
+    ("America/Chicago", -6 * HOUR, Rule::Us),
+    ("America/Mexico_City", -6 * HOUR, Rule::Fixed),
+    ("America/Denver", -7 * HOUR, Rule::Us),
+    ("America/Phoenix", -7 * HOUR, Rule::Fixed),
+    ("America/Los_Angeles", -8 * HOUR, Rule::Us),
+    ("America/Vancouver", -8 * HOUR, Rule::Us),
+    ("America/Anchorage", -9 * HOUR, Rule::Us),
+    ("Pacific/Honolulu", -10 * HOUR, Rule::Fixed),
+    ("America/Sao_Paulo", -3 * HOUR, Rule::Fixed),
+    ("Europe/London", 0, Rule::Eu),
+    ("Europe/Dublin", 0, Rule::Eu),
+    ("Europe/Lisbon", 0, Rule::Eu),
+    ("Europe/Amsterdam", HOUR, Rule::Eu),
+    ("Europe/Berlin", HOUR, Rule::Eu),
+    ("Europe/Brussels", HOUR, Rule::Eu),
+    ("Europe/Copenhagen", HOUR, Rule::Eu),
+    ("Europe/Madrid", HOUR, Rule::Eu),
+    ("Europe/Oslo", HOUR, Rule::Eu),
+    ("Europe/Paris", HOUR, Rule::Eu),
+    ("Europe/Prague", HOUR, Rule::Eu),
+    ("Europe/Rome", HOUR, Rule::Eu),
+    ("Europe/Stockholm", HOUR, Rule::Eu),
+    ("Europe/Vienna", HOUR, Rule::Eu),
+    ("Europe/Warsaw", HOUR, Rule::Eu),
+    ("Europe/Zurich", HOUR, Rule::Eu),
+    ("Europe/Athens", 2 * HOUR, Rule::Eu),
+    ("Europe/Helsinki", 2 * HOUR, Rule::Eu),
+    ("Europe/Istanbul", 3 * HOUR, Rule::Fixed),
+    ("Europe/Moscow", 3 * HOUR, Rule::Fixed),
+    ("Africa/Lagos", HOUR, Rule::Fixed),
+    ("Africa/Johannesburg", 2 * HOUR, Rule::Fixed),
+    ("Africa/Nairobi", 3 * HOUR, Rule::Fixed),
+    ("Asia/Dubai", 4 * HOUR, Rule::Fixed),
+    ("Asia/Karachi", 5 * HOUR, Rule::Fixed),
+    ("Asia/Kolkata", 5 * HOUR + 1_800, Rule::Fixed),
+    ("Asia/Kathmandu", 5 * HOUR + 2_700, Rule::Fixed),
+    ("Asia/Dhaka", 6 * HOUR, Rule::Fixed),
+    ("Asia/Bangkok", 7 * HOUR, Rule::Fixed),
+    ("Asia/Jakarta", 7 * HOUR, Rule::Fixed),
+    ("Asia/Hong_Kong", 8 * HOUR, Rule::Fixed),
+    ("Asia/Shanghai", 8 * HOUR, Rule::Fixed),
+    ("Asia/Singapore", 8 * HOUR, Rule::Fixed),
+    ("Asia/Taipei", 8 * HOUR, Rule::Fixed),
+    ("Australia/Perth", 8 * HOUR, Rule::Fixed),
+    ("Asia/Seoul", 9 * HOUR, Rule::Fixed),
+    ("Asia/Tokyo", 9 * HOUR, Rule::Fixed),
+    ("Australia/Adelaide", 9 * HOUR + 1_800, Rule::Australia),
+    ("Australia/Brisbane", 10 * HOUR, Rule::Fixed),
+    ("Australia/Melbourne", 10 * HOUR, Rule::Australia),
+    ("Australia/Sydney", 10 * HOUR, Rule::Australia),
+];
+
+/// How to resolve a local time that occurs twice (when clocks go back) or
+/// never (when they go forward).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ambiguous {
+    /// The earlier instant; a skipped time maps to the transition.
+    Earliest,
+    /// The later instant; a skipped time maps to the transition.
+    Latest,
+    /// Fail.
+    #[default]
+    Error,
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date.
+pub const fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian `(year, month, day)` of days since the Unix epoch.
+pub const fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1).cast_unsigned() as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    }
+    .cast_unsigned() as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// ISO weekday (Monday = 1 ... Sunday = 7) of days since the Unix epoch.
+pub const fn iso_weekday(days: i64) -> u32 {
+    ((days + 3).rem_euclid(7) + 1) as u32
+}
+
+/// Day of the `nth` Sunday of a month (`0` for the last one).
+const fn sunday(year: i64, month: u32, nth: i64) -> i64 {
+    if nth == 0 {
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        let last = days_from_civil(next_year, next_month, 1) - 1;
+        last - iso_weekday(last) as i64 % 7
+    } else {
+        let first = days_from_civil(year, month, 1);
+        first + (7 - iso_weekday(first) as i64) % 7 + 7 * (nth - 1)
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` of seconds since the epoch, for messages.
+pub fn format_seconds(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let time = seconds.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Instant of `local_hour` on `day` at `offset` seconds east of UTC.
+const fn at(day: i64, local_hour: i64, offset: i32) -> i64 {
+    day * SECONDS_PER_DAY + local_hour * 3_600 - offset as i64
+}
+
+/// A time zone: a standard offset and its daylight saving rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Zone {
+    standard: i32,
+    rule: Rule,
+}
+
+/// Seconds east of UTC of `[+-]HH:MM`, `[+-]HHMM` or `[+-]HH`.
+pub fn parse_fixed_offset(name: &str) -> Option<i32> {
+    let (sign, rest) = match name.as_bytes().first()? {
+        b'+' => (1, &name[1..]),
+        b'-' => (-1, &name[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.len() {
+        _ if !rest.is_ascii() => return None,
+        5 if rest.as_bytes()[2] == b':' => (&rest[..2], &rest[3..]),
+        4 => (&rest[..2], &rest[2..]),
+        2 => (rest, "00"),
+        _ => return None,
+    };
+    if !(hours.bytes().chain(minutes.bytes())).all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours <= 23 && minutes <= 59).then_some(sign * (hours * HOUR + minutes * 60))
+}
+
+impl Zone {
+    /// Look up a fixed offset or bundled zone name.
+    pub fn parse(name: &str) -> Result<Self> {
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(Self {
+                standard: offset,
+                rule: Rule::Fixed,
+            });
+        }
+        ZONES
+            .iter()
+            .find(|(zone, _, _)| *zone == name)
+            .map(|&(_, standard, rule)| Self { standard, rule })
+            .ok_or_else(|| {
+                ArrowWasmError::InvalidInput(format!(
+                    "Unknown time zone '{name}': use a fixed offset such as '+05:30' or a zone from list_time_zones()"
+                ))
+            })
+    }
+
+    const fn daylight(self) -> i32 {
+        self.standard + HOUR
+    }
+
+    /// Daylight saving start and end instants (epoch seconds) in `year`.
+    const fn transitions(self, year: i64) -> Option<(i64, i64)> {
+        match self.rule {
+            Rule::Fixed => None,
+            Rule::Us => Some((
+                at(sunday(year, 3, 2), 2, self.standard),
+                at(sunday(year, 11, 1), 2, self.daylight()),
+            )),
+            Rule::Eu => Some((at(sunday(year, 3, 0), 1, 0), at(sunday(year, 10, 0), 1, 0))),
+            Rule::Australia => Some((
+                at(sunday(year, 10, 1), 2, self.standard),
+                at(sunday(year, 4, 1), 3, self.daylight()),
+            )),
+        }
+    }
+
+    /// Offset in seconds east of UTC at the instant `utc` (epoch seconds).
+    pub const fn offset_at(self, utc: i64) -> i32 {
+        let local = utc + self.standard as i64;
+        let (year, _, _) = civil_from_days(local.div_euclid(SECONDS_PER_DAY));
+        let daylight = match self.transitions(year) {
+            None => false,
+            Some((start, end)) if start < end => start <= utc && utc < end,
+            Some((start, end)) => utc < end || start <= utc,
+        };
+        if daylight {
+            self.daylight()
+        } else {
+            self.standard
+        }
+    }
+
+    /// Instants (epoch seconds) whose local time is `local`, earliest first;
+    /// empty when `local` is skipped.
+    fn instants(self, local: i64) -> Vec<i64> {
+        let mut instants: Vec<i64> = [self.daylight(), self.standard]
+            .into_iter()
+            .map(|offset| local - i64::from(offset))
+            .filter(|utc| i64::from(self.offset_at(*utc)) == local - utc)
+            .collect();
+        instants.dedup();
+        instants
+    }
+
+    /// The transition instant inside a skipped local time's gap.
+    fn gap_transition(self, local: i64) -> i64 {
+        let (low, high) = (
+            local - i64::from(self.daylight()),
+            local - i64::from(self.standard),
+        );
+        let (year, _, _) = civil_from_days(local.div_euclid(SECONDS_PER_DAY));
+        (year - 1..=year + 1)
+            .filter_map(|year| self.transitions(year))
+            .flat_map(<[i64; 2]>::from)
+            .find(|transition| (low..=high).contains(transition))
+            .unwrap_or(high)
+    }
+
+    /// The instant (epoch seconds) of the local time `local`, resolving
+    /// repeated and skipped times with `policy`.
+    pub fn to_utc(self, local: i64, policy: Ambiguous) -> Result<i64> {
+        let instants = self.instants(local);
+        match (instants.as_slice(), policy) {
+            ([utc], _) => Ok(*utc),
+            ([earliest, _], Ambiguous::Earliest) => Ok(*earliest),
+            ([_, latest], Ambiguous::Latest) => Ok(*latest),
+            ([], Ambiguous::Earliest | Ambiguous::Latest) => Ok(self.gap_transition(local)),
+            ([], _) => Err(ArrowWasmError::InvalidInput(format!(
+                "Local time {} does not exist in this time zone (skipped by a daylight saving transition)",
+                format_seconds(local)
+            ))),
+            _ => Err(ArrowWasmError::InvalidInput(format!(
+                "Local time {} is ambiguous in this time zone; set ambiguous to 'earliest' or 'latest'",
+                format_seconds(local)
+            ))),
+        }
+    }
+
+    /// Like [`Self::to_utc`], preferring the instant with offset `offset`
+    /// and otherwise taking the earliest.
+    pub fn to_utc_with_offset(self, local: i64, offset: i32) -> i64 {
+        let instants = self.instants(local);
+        instants
+            .iter()
+            .copied()
+            .find(|utc| local - utc == i64::from(offset))
+            .or_else(|| instants.first().copied())
+            .unwrap_or_else(|| self.gap_transition(local))
+    }
+}
+
+/// Names of the bundled time zones, besides fixed offsets like `+05:30`.
+///
+/// Named zones apply their current daylight saving rules to every year;
+/// see the crate source for the exact rules.
+#[must_use]
+#[wasm_bindgen]
+pub fn list_time_zones() -> Vec<String> {
+    ZONES
+        .iter()
+        .map(|(name, _, _)| (*name).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Epoch seconds of a wall-clock time.
+    const fn civil(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3_600 + minute * 60
+    }
+
+    fn resolutions(zone: &str, local: i64) -> [Result<i64>; 3] {
+        let zone = Zone::parse(zone).unwrap();
+        [Ambiguous::Earliest, Ambiguous::Latest, Ambiguous::Error]
+            .map(|policy| zone.to_utc(local, policy))
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(
+            civil_from_days(days_from_civil(-4713, 11, 24)),
+            (-4713, 11, 24)
+        );
+
+        let mut previous = civil_from_days(-800_001);
+        for days in -800_000..800_000 {
+            let date = civil_from_days(days);
+            let (year, month, day) = date;
+            assert_eq!(days_from_civil(year, month, day), days, "{date:?}");
+            let next_day = (previous.0, previous.1, previous.2 + 1);
+            let next_month = (previous.0, previous.1 + 1, 1);
+            assert!(
+                date == next_day || date == next_month || date == (previous.0 + 1, 1, 1),
+                "{previous:?} is followed by {date:?}"
+            );
+            previous = date;
+        }
+    }
+
+    #[test]
+    fn us_spring_forward_skips_an_hour() {
+        // 2024-03-10 02:00 EST became 03:00 EDT, at 07:00 UTC.
+        let transition = civil(2024, 3, 10, 7, 0);
+        let [earliest, latest, error] = resolutions("America/New_York", civil(2024, 3, 10, 2, 30));
+        assert_eq!(earliest.unwrap(), transition);
+        assert_eq!(latest.unwrap(), transition);
+        assert!(error.unwrap_err().to_string().contains("does not exist"));
+
+        let zone = Zone::parse("America/New_York").unwrap();
+        assert_eq!(zone.offset_at(transition - 1), -5 * HOUR);
+        assert_eq!(zone.offset_at(transition), -4 * HOUR);
+        let [before, ..] = resolutions("America/New_York", civil(2024, 3, 10, 1, 59));
+        assert_eq!(before.unwrap(), civil(2024, 3, 10, 6, 59));
+    }
+
+    #[test]
+    fn us_fall_back_repeats_an_hour() {
+        // 2024-11-03 01:30 happened at 06:30 UTC (CDT) and 07:30 UTC (CST).
+        let [earliest, latest, error] = resolutions("America/Chicago", civil(2024, 11, 3, 1, 30));
+        assert_eq!(earliest.unwrap(), civil(2024, 11, 3, 6, 30));
+        assert_eq!(latest.unwrap(), civil(2024, 11, 3, 7, 30));
+        assert!(error.unwrap_err().to_string().contains("ambiguous"));
+
+        let [after, ..] = resolutions("America/Chicago", civil(2024, 11, 3, 2, 0));
+        assert_eq!(after.unwrap(), civil(2024, 11, 3, 8, 0));
+    }
+
+    #[test]
+    fn eu_transitions_happen_at_one_utc() {
+        // Berlin: 2024-03-31 02:00 CET became 03:00 CEST at 01:00 UTC.
+        let [earliest, latest, error] = resolutions("Europe/Berlin", civil(2024, 3, 31, 2, 30));
+        assert_eq!(earliest.unwrap(), civil(2024, 3, 31, 1, 0));
+        assert_eq!(latest.unwrap(), civil(2024, 3, 31, 1, 0));
+        assert!(error.is_err());
+
+        // 2024-10-27 02:30 happened at 00:30 UTC (CEST) and 01:30 UTC (CET).
+        let [earliest, latest, error] = resolutions("Europe/Berlin", civil(2024, 10, 27, 2, 30));
+        assert_eq!(earliest.unwrap(), civil(2024, 10, 27, 0, 30));
+        assert_eq!(latest.unwrap(), civil(2024, 10, 27, 1, 30));
+        assert!(error.is_err());
+
+        // London moves at the same instant, an hour earlier in local time.
+        let london = Zone::parse("Europe/London").unwrap();
+        assert_eq!(london.offset_at(civil(2024, 10, 27, 0, 59)), HOUR);
+        assert_eq!(london.offset_at(civil(2024, 10, 27, 1, 0)), 0);
+        let [earliest, latest, _] = resolutions("Europe/London", civil(2024, 10, 27, 1, 30));
+        assert_eq!(earliest.unwrap(), civil(2024, 10, 27, 0, 30));
+        assert_eq!(latest.unwrap(), civil(2024, 10, 27, 1, 30));
+    }
+
+    #[test]
+    fn southern_daylight_saving_spans_the_new_year() {
+        let sydney = Zone::parse("Australia/Sydney").unwrap();
+        assert_eq!(sydney.offset_at(civil(2024, 1, 15, 0, 0)), 11 * HOUR);
+        assert_eq!(sydney.offset_at(civil(2024, 7, 15, 0, 0)), 10 * HOUR);
+    }
+
+    #[test]
+    fn fixed_offsets_are_never_ambiguous() {
+        assert_eq!(parse_fixed_offset("+05:30"), Some(5 * HOUR + 1_800));
+        assert_eq!(parse_fixed_offset("-0800"), Some(-8 * HOUR));
+        assert_eq!(parse_fixed_offset("+09"), Some(9 * HOUR));
+        assert_eq!(parse_fixed_offset("+24:00"), None);
+        assert_eq!(parse_fixed_offset("05:30"), None);
+
+        let local = civil(2024, 3, 10, 2, 30);
+        for zone in ["+05:30", "Asia/Kolkata"] {
+            for resolved in resolutions(zone, local) {
+                assert_eq!(resolved.unwrap(), local - i64::from(5 * HOUR + 1_800));
+            }
+        }
+        assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! Checks for data from untrusted sources.
+//!
+//! JS strings are UTF-16 and may hold lone surrogates, which have no UTF-8
+//! encoding; wasm-bindgen replaces them with U+FFFD when copying a string
+//! into WASM. Bytes labelled as text may not be UTF-8 at all. Construction
+//! paths take an [`InvalidUtf8`] policy deciding what happens to either, and
+//! [`validate_table`] re-checks a registered table's arrays.
+
+use crate::errors::{ArrowWasmError, Result};
+use crate::mem::{self, TableHandle};
+use arrow::array::Array;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// What to do with text that is not valid Unicode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidUtf8 {
+    /// Replace each invalid sequence with U+FFFD.
+    #[default]
+    Replace,
+    /// Fail, naming the first invalid value.
+    Error,
+    /// Keep byte input as a Binary column; JS strings treat this like
+    /// `Replace`, since they have no byte form.
+    Binary,
+}
+
+/// Copy of the JS string `value`, or `None` when it is not a string.
+///
+/// Lone surrogates become U+FFFD, or fail under [`InvalidUtf8::Error`] with
+/// `row` in the message.
+pub fn js_string(value: &JsValue, policy: InvalidUtf8, row: usize) -> Result<Option<String>> {
+    let Some(text) = value.dyn_ref::<js_sys::JsString>() else {
+        return Ok(None);
+    };
+    if policy == InvalidUtf8::Error && !text.is_valid_utf16() {
+        return Err(ArrowWasmError::InvalidInput(format!(
+            "String at row {row} contains a lone UTF-16 surrogate"
+        )));
+    }
+    Ok(value.as_string())
+}
+
+/// Check every array of a table against the Arrow format rules.
+///
+/// The default check covers the buffer layout of each top-level array. With
+/// `full`, every value and nested child is also inspected: offsets must be
+/// monotonic, string data valid UTF-8 and dictionary keys in range. The
+/// first violation fails with its column and batch; a valid table returns
+/// normally.
+#[wasm_bindgen]
+pub fn validate_table(handle: TableHandle, full: bool) -> std::result::Result<(), JsValue> {
+    let table = mem::get_table(handle)?;
+    for (batch_index, batch) in table.batches.iter().enumerate() {
+        for (column, field) in batch.columns().iter().zip(table.schema.fields()) {
+            let data = column.to_data();
+            let checked = if full {
+                data.validate_full()
+            } else {
+                data.validate()
+            };
+            checked.map_err(|e| {
+                ArrowWasmError::InvalidInput(format!("batch {batch_index}: {e}"))
+                    .in_column("validate_table", field)
+            })?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Size checks on the release `.wasm`, sharing their budgets with
+//! `scripts/check-wasm-size.sh`.
+//!
+//! The budgets live in the script so CI and these tests cannot disagree.
+//! The checks on built binaries are ignored by default and fail when the
+//! binary is missing; CI runs them with `cargo test --test wasm_size --
+//! --ignored` after building:
+//!
+//! - `pkg/arrow_rs_wasm_bg.wasm`, from `npm run build:wasm`, against the
+//!   byte budget;
+//! - the unstripped `target/wasm32-unknown-unknown/release/arrow_rs_wasm.wasm`,
+//!   from `CARGO_PROFILE_RELEASE_STRIP=false cargo build --release --lib
+//!   --target wasm32-unknown-unknown`, whose function names show which
+//!   `Debug` implementations were linked in.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::{Path, PathBuf};
+
+const ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+/// Value of `variable` in the size check script: its default when the
+/// environment can override it.
+fn script_default(variable: &str) -> u64 {
+    let script = std::fs::read_to_string(Path::new(ROOT).join("scripts/check-wasm-size.sh"))
+        .expect("scripts/check-wasm-size.sh is readable");
+    let prefix = format!("{variable}=");
+    let line = script
+        .lines()
+        .find(|line| line.starts_with(&prefix))
+        .unwrap_or_else(|| panic!("the script sets {variable}"));
+    let value = &line[prefix.len()..];
+    let value = value
+        .split_once(":-")
+        .map_or(value, |(_, default)| default.trim_end_matches('}'));
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("{variable} is a plain number"))
+}
+
+/// Contents of a built binary, failing the test when it has not been built.
+fn read_built(path: &Path, build: &str) -> Vec<u8> {
+    std::fs::read(path)
+        .unwrap_or_else(|_| panic!("{} not found; run `{build}` first", path.display()))
+}
+
+fn leb128(bytes: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*pos];
+        *pos += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+fn name<'a>(bytes: &'a [u8], pos: &mut usize) -> &'a str {
+    let len = leb128(bytes, pos);
+    let name = std::str::from_utf8(&bytes[*pos..*pos + len]).expect("names are UTF-8");
+    *pos += len;
+    name
+}
+
+/// Function names of a wasm module, from the function subsection of its
+/// `name` custom section.
+fn function_names(wasm: &[u8]) -> Vec<&str> {
+    assert_eq!(&wasm[..4], b"\0asm", "not a wasm module");
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let size = leb128(wasm, &mut pos);
+        let end = pos + size;
+        if id == 0 && name(wasm, &mut pos) == "name" {
+            while pos < end {
+                let subsection = wasm[pos];
+                pos += 1;
+                let size = leb128(wasm, &mut pos);
+                if subsection == 1 {
+                    let count = leb128(wasm, &mut pos);
+                    return (0..count)
+                        .map(|_| {
+                            leb128(wasm, &mut pos);
+                            name(wasm, &mut pos)
+                        })
+                        .collect();
+                }
+                pos += size;
+            }
+        }
+        pos = end;
+    }
+    panic!("no function names; build with CARGO_PROFILE_RELEASE_STRIP=false");
+}
+
+/// Names of a wasm module's exports.
+fn export_names(wasm: &[u8]) -> Vec<&str> {
+    assert_eq!(&wasm[..4], b"\0asm", "not a wasm module");
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+        let size = leb128(wasm, &mut pos);
+        if id == 7 {
+            let count = leb128(wasm, &mut pos);
+            return (0..count)
+                .map(|_| {
+                    let export = name(wasm, &mut pos);
+                    pos += 1;
+                    leb128(wasm, &mut pos);
+                    export
+                })
+                .collect();
+        }
+        pos += size;
+    }
+    Vec::new()
+}
+
+/// Demangled `Debug::fmt` implementations in a wasm module.
+fn debug_impls(wasm: &[u8]) -> Vec<String> {
+    function_names(wasm)
+        .into_iter()
+        .map(|name| format!("{:#}", rustc_demangle::demangle(name)))
+        .filter(|name| name.ends_with(" as core::fmt::Debug>::fmt"))
+        .collect()
+}
+
+#[test]
+fn budgets_are_deliberate() {
+    // Raising either budget is a deliberate change to the script and this test.
+    assert_eq!(script_default("BUDGET_BYTES"), 2 * 1024 * 1024);
+    assert_eq!(script_default("DEBUG_IMPL_BUDGET"), 900);
+}
+
+#[test]
+fn names_are_read_from_the_name_section() {
+    // A type section, then a name section naming functions 0 and 1.
+    let mut wasm = b"\0asm\x01\0\0\0\x01\x01\0".to_vec();
+    let functions = b"\x01\x0b\x02\0\x03one\x01\x03two";
+    wasm.extend([0, 5 + functions.len() as u8, 4]);
+    wasm.extend(b"name");
+    wasm.extend(functions);
+    assert_eq!(function_names(&wasm), ["one", "two"]);
+}
+
+#[test]
+fn exports_are_read_from_the_export_section() {
+    // An export section with function 0 as `one` and memory 0 as `memory`.
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    let exports = b"\x02\x03one\0\0\x06memory\x02\0";
+    wasm.extend([7, exports.len() as u8]);
+    wasm.extend(exports);
+    assert_eq!(export_names(&wasm), ["one", "memory"]);
+}
+
+#[test]
+#[ignore = "needs pkg/ from `npm run build:wasm`"]
+fn release_wasm_fits_the_budget() {
+    let wasm = Path::new(ROOT).join("pkg/arrow_rs_wasm_bg.wasm");
+    let size = read_built(&wasm, "npm run build:wasm").len() as u64;
+    let budget = script_default("BUDGET_BYTES");
+    assert!(
+        size <= budget,
+        "{} is {size} bytes, over the {budget}-byte budget",
+        wasm.display()
+    );
+}
+
+/// The unstripped release build of the library.
+fn release_build() -> Vec<u8> {
+    let path: PathBuf = [
+        ROOT,
+        "target/wasm32-unknown-unknown/release/arrow_rs_wasm.wasm",
+    ]
+    .iter()
+    .collect();
+    read_built(
+        &path,
+        "CARGO_PROFILE_RELEASE_STRIP=false cargo build --release --lib --target wasm32-unknown-unknown",
+    )
+}
+
+/// C library functions `std` calls on `wasm32` for float methods without
+/// a wasm instruction, and the memory functions `core` calls.
+const LIBC_SYMBOLS: &[&str] = &[
+    "acos", "asin", "atan", "atan2", "cbrt", "ceil", "cos", "cosh", "exp", "exp2", "expm1", "fdim",
+    "floor", "fma", "fmod", "hypot", "ldexp", "log", "log10", "log1p", "log2", "pow", "round",
+    "sin", "sinh", "tan", "tanh", "tgamma", "trunc", "memcmp", "memcpy", "memmove", "memset",
+];
+
+#[test]
+#[ignore = "needs an unstripped release build of the library"]
+fn exports_leave_libm_alone() {
+    // An export named like a C library function takes over that symbol at
+    // link time, so `f64::round` would call the `round` kernel instead.
+    let wasm = release_build();
+    let clashes: Vec<&str> = export_names(&wasm)
+        .into_iter()
+        .filter(|export| {
+            let base = export.strip_suffix('f').unwrap_or(export);
+            LIBC_SYMBOLS.contains(export) || LIBC_SYMBOLS.contains(&base)
+        })
+        .collect();
+    assert!(clashes.is_empty(), "exports shadow libm: {clashes:?}");
+}
+
+#[test]
+#[ignore = "needs an unstripped release build of the library"]
+fn debug_formatting_stays_out_of_the_crate() {
+    let wasm = release_build();
+    let impls = debug_impls(&wasm);
+
+    // Errors format data types with `{:?}`, which brings in arrow's
+    // implementations; the crate's own types are never formatted that way.
+    let own: Vec<&String> = impls
+        .iter()
+        .filter(|name| name.starts_with("<arrow_rs_wasm::"))
+        .collect();
+    assert!(own.is_empty(), "Debug is linked in for {own:#?}");
+
+    let budget = script_default("DEBUG_IMPL_BUDGET");
+    assert!(
+        impls.len() as u64 <= budget,
+        "{} Debug implementations are linked in, over the budget of {budget}",
+        impls.len()
+    );
+}
@@ -0,0 +1,86 @@
+//! Allocation-heavy work under the `wasm32` global allocator: dlmalloc by
+//! default, talc with `small-alloc`. CI runs this suite with
+//! `wasm-pack test --node`, once per allocator.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    create_sample_table, free_table, list_sample_tables, read_table_from_bytes, table_row_count,
+    write_table_to_ipc,
+};
+use std::alloc::{alloc, dealloc, Layout};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn fill(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+#[wasm_bindgen_test]
+fn interleaved_frees_keep_live_blocks_intact() {
+    let mut blocks: Vec<Vec<u8>> = (0..4096).map(|i| vec![fill(i); i % 300 + 1]).collect();
+    for block in blocks.iter_mut().step_by(2) {
+        *block = Vec::new();
+    }
+    let refill: Vec<Vec<u8>> = (0..2048).map(|i| vec![0xAA; i % 500 + 1]).collect();
+    for (i, block) in blocks.iter().enumerate().skip(1).step_by(2) {
+        assert_eq!(block.len(), i % 300 + 1);
+        assert!(block.iter().all(|&byte| byte == fill(i)), "block {i}");
+    }
+    assert!(refill
+        .iter()
+        .all(|block| block.iter().all(|&byte| byte == 0xAA)));
+}
+
+#[wasm_bindgen_test]
+fn large_blocks_grow_memory_and_keep_their_prefix() {
+    let mut large = vec![7_u8; 3 << 20];
+    large.resize(9 << 20, 9);
+    assert!(large[..3 << 20].iter().all(|&byte| byte == 7));
+    assert!(large[3 << 20..].iter().all(|&byte| byte == 9));
+    large.truncate(1 << 10);
+    large.shrink_to_fit();
+    assert_eq!(large, vec![7; 1 << 10]);
+}
+
+#[wasm_bindgen_test]
+fn over_aligned_layouts_are_honoured() {
+    for align in [8, 64, 4096, 65_536] {
+        let layout = Layout::from_size_align(100, align).unwrap();
+        let ptr = unsafe { alloc(layout) };
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0, "align {align}");
+        unsafe { dealloc(ptr, layout) };
+    }
+}
+
+#[wasm_bindgen_test]
+fn sample_tables_round_trip_through_ipc() {
+    for name in list_sample_tables() {
+        let handle = create_sample_table(&name).unwrap();
+        let bytes = write_table_to_ipc(handle, false).unwrap().to_vec();
+        let restored = read_table_from_bytes(&bytes, None).unwrap();
+        assert_eq!(
+            table_row_count(restored).unwrap(),
+            table_row_count(handle).unwrap(),
+            "{name}"
+        );
+        free_table(handle).unwrap();
+        free_table(restored).unwrap();
+    }
+}
+
+#[cfg(feature = "alloc-metrics")]
+#[wasm_bindgen_test]
+fn counters_track_the_peak() {
+    use wasm_bindgen::{JsCast, JsValue};
+    let read = |key: &str| {
+        let info: js_sys::Map = arrow_rs_wasm::get_memory_info().unchecked_into();
+        info.get(&JsValue::from_str(key)).as_f64().unwrap()
+    };
+    let before = read("total_allocations");
+    let block = vec![1_u8; 4 << 20];
+    assert!(read("peak_bytes") >= f64::from(4 << 20));
+    assert!(read("total_allocations") > before);
+    drop(block);
+    assert!(read("current_bytes") < read("peak_bytes"));
+}
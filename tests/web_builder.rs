@@ -0,0 +1,229 @@
+//! `StreamingTableBuilder` builds the same table however its rows arrive,
+//! and reports how long ingestion takes; `append_rows` converts rows the
+//! same way onto an existing table.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    append_rows, get_column, release_table, table_from_plain_object, table_row_count,
+    to_plain_object, write_table_to_ipc, StreamingTableBuilder, TableHandle,
+};
+use js_sys::{BigInt, Object, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::{console_log, wasm_bindgen_test};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance)]
+    fn now() -> f64;
+}
+
+const ROWS: usize = 100_000;
+const ROWS_PER_BATCH: usize = 16_384;
+
+fn schema() -> JsValue {
+    JSON::parse(
+        r#"{"fields": [
+            {"name": "id", "type": "Int64", "nullable": false},
+            {"name": "name", "type": "Utf8", "nullable": true},
+            {"name": "score", "type": "Float64", "nullable": true},
+            {"name": "ok", "type": "Boolean", "nullable": true}
+        ]}"#,
+    )
+    .unwrap()
+}
+
+/// `count` row objects; names grow longer with the row number and every
+/// seventh row has null `name` and `score`.
+fn rows(count: usize) -> Vec<JsValue> {
+    (0..count)
+        .map(|i| {
+            let row = Object::new();
+            let set = |key: &str, value: JsValue| {
+                Reflect::set(&row, &key.into(), &value).unwrap();
+            };
+            set("id", JsValue::from_f64(i as f64));
+            if i % 7 != 0 {
+                set("name", JsValue::from_str(&format!("name-{}", i * i)));
+                set("score", JsValue::from_f64(i as f64 / 8.0));
+            }
+            set("ok", JsValue::from_bool(i % 3 == 0));
+            row.into()
+        })
+        .collect()
+}
+
+fn builder() -> StreamingTableBuilder {
+    let options = JSON::parse(&format!(r#"{{"rowsPerBatch": {ROWS_PER_BATCH}}}"#)).unwrap();
+    StreamingTableBuilder::create(schema(), options).unwrap()
+}
+
+/// All rows in one `push_rows` call.
+fn bulk(rows: &[JsValue]) -> TableHandle {
+    let mut builder = builder();
+    builder.push_rows(rows.to_vec()).unwrap();
+    builder.finish().unwrap()
+}
+
+/// Rows in chunks of `chunk` rows, announced up front with `reserve` when
+/// `reserve` is set.
+fn streamed(rows: &[JsValue], chunk: usize, reserve: bool) -> TableHandle {
+    let mut builder = builder();
+    if reserve {
+        let hints = JSON::parse(r#"{"name": 14}"#).unwrap();
+        builder.reserve(rows.len(), hints).unwrap();
+    }
+    for chunk in rows.chunks(chunk) {
+        builder.push_rows(chunk.to_vec()).unwrap();
+    }
+    assert_eq!(builder.row_count(), rows.len());
+    assert_eq!(builder.batch_count(), rows.len() / ROWS_PER_BATCH);
+    builder.finish().unwrap()
+}
+
+fn ipc(handle: TableHandle) -> Vec<u8> {
+    write_table_to_ipc(handle, false).unwrap().to_vec()
+}
+
+#[wasm_bindgen_test]
+fn streamed_and_bulk_builds_are_equal() {
+    let rows = rows(ROWS);
+    let bulk = bulk(&rows);
+    assert_eq!(table_row_count(bulk).unwrap(), ROWS);
+    let expected = ipc(bulk);
+    for (chunk, reserve) in [(1_000, false), (1_000, true), (777, true), (ROWS, true)] {
+        let table = streamed(&rows, chunk, reserve);
+        assert!(
+            ipc(table) == expected,
+            "chunks of {chunk}, reserve {reserve}"
+        );
+    }
+}
+
+/// Milliseconds taken by `build`, best of three runs.
+fn time(build: impl Fn() -> TableHandle) -> f64 {
+    (0..3)
+        .map(|_| {
+            let start = now();
+            let handle = build();
+            let elapsed = now() - start;
+            assert!(release_table(handle));
+            elapsed
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Not a pass/fail check: prints ingestion times so regressions show up
+/// in the test output.
+#[wasm_bindgen_test]
+fn ingestion_benchmark() {
+    let rows = rows(ROWS);
+    let cases: [(&str, &dyn Fn() -> TableHandle); 3] = [
+        ("bulk", &|| bulk(&rows)),
+        ("1k chunks", &|| streamed(&rows, 1_000, false)),
+        ("1k chunks, reserved", &|| streamed(&rows, 1_000, true)),
+    ];
+    for (name, build) in cases {
+        let millis = time(build);
+        console_log!(
+            "ingest {ROWS} rows, {name}: {millis:.1} ms ({:.0} rows/ms)",
+            ROWS as f64 / millis
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+fn appended_rows_follow_the_existing_ones() {
+    let source = bulk(&rows(10));
+    let extra = JSON::parse(r#"[{"id": 10, "name": "ten", "ok": true}, {"id": 11, "score": 2.5}]"#)
+        .unwrap();
+    let appended = append_rows(source, js_sys::Array::from(&extra).to_vec()).unwrap();
+    assert_eq!(table_row_count(appended).unwrap(), 12);
+    assert_eq!(table_row_count(source).unwrap(), 10);
+
+    let name = get_column(appended, "name").unwrap();
+    assert_eq!(
+        name.get(10, JsValue::UNDEFINED)
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("ten")
+    );
+    assert!(name.get(11, JsValue::UNDEFINED).unwrap().is_null());
+    assert_eq!(
+        name.get(9, JsValue::UNDEFINED)
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("name-81")
+    );
+    let score = get_column(appended, "score").unwrap();
+    assert_eq!(
+        score.get(11, JsValue::UNDEFINED).unwrap().as_f64(),
+        Some(2.5)
+    );
+}
+
+/// The values of the `UInt64` column `id` of `handle` as decimal text.
+fn unsigned_ids(handle: TableHandle) -> Vec<Option<String>> {
+    let column = get_column(handle, "id").unwrap();
+    (0..table_row_count(handle).unwrap())
+        .map(|index| {
+            let value = column.get(index, JsValue::UNDEFINED).unwrap();
+            (!value.is_null()).then(|| {
+                let value: BigInt = value.dyn_into().expect("UInt64 values are bigints");
+                String::from(value.to_string(10).unwrap())
+            })
+        })
+        .collect()
+}
+
+#[wasm_bindgen_test]
+fn uint64_extremes_round_trip_through_the_builder() {
+    let schema =
+        JSON::parse(r#"{"fields": [{"name": "id", "type": "UInt64", "nullable": true}]}"#).unwrap();
+    let values = [
+        Some(u64::MAX - 1),
+        Some(u64::MAX),
+        None,
+        Some(1 << 63),
+        Some(7),
+    ];
+    let rows: Vec<JsValue> = values
+        .iter()
+        .map(|value| {
+            let row = Object::new();
+            if let Some(value) = value {
+                Reflect::set(&row, &"id".into(), &BigInt::from(*value).into()).unwrap();
+            }
+            row.into()
+        })
+        .collect();
+    let mut builder = StreamingTableBuilder::create(schema.clone(), JsValue::UNDEFINED).unwrap();
+    builder.push_rows(rows).unwrap();
+    let handle = builder.finish().unwrap();
+
+    let expected: Vec<Option<String>> = values
+        .iter()
+        .map(|value| value.map(|v| v.to_string()))
+        .collect();
+    assert_eq!(unsigned_ids(handle), expected);
+    // Through a BigUint64Array and back.
+    let object = to_plain_object(handle, JsValue::UNDEFINED).unwrap();
+    let imported = table_from_plain_object(object, JsValue::UNDEFINED).unwrap();
+    assert_eq!(unsigned_ids(imported), expected);
+
+    let row = JSON::parse("{}").unwrap();
+    let too_big = BigInt::from(u64::MAX) + BigInt::from(1_u64);
+    Reflect::set(&row, &"id".into(), &too_big.into()).unwrap();
+    let mut builder = StreamingTableBuilder::create(schema, JsValue::UNDEFINED).unwrap();
+    let message = builder
+        .push_rows(vec![row])
+        .unwrap_err()
+        .as_string()
+        .unwrap();
+    assert!(
+        message.contains("18446744073709551616 is out of range for the column"),
+        "{message}"
+    );
+}
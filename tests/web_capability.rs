@@ -0,0 +1,182 @@
+//! Every kernel in the capability matrix accepts exactly the column types
+//! `supported_operations` reports, and rejects the rest with the
+//! operation's `Unsupported` error.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    abs, add_column, bottom_k, capability_matrix, cast_column, clip, column_ceil, column_floor,
+    column_max, column_mean, column_median, column_min, column_round, column_std_dev, column_sum,
+    column_sum_exact, column_variance, convert_timezone, count_matches, create_sample_table, cut,
+    date_part, date_trunc, decode_utf8, diff, encode_run_ends, fill_backward, fill_forward,
+    get_column, join_asof, localize_naive, negate, parse_dates, parse_numbers, percent_change,
+    remove_timezone, shift, sort_by, supported_operations, top_k, validate_wkb, Column,
+    TableHandle,
+};
+use js_sys::JSON;
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[derive(Deserialize)]
+struct Entry {
+    operation: String,
+    kernels: Vec<String>,
+}
+
+/// A column to run every kernel on, and the table holding it for the
+/// table-level kernels.
+struct Case {
+    table: TableHandle,
+    name: String,
+}
+
+/// Casts of `basic`'s `id` and `name` columns, added to the table under
+/// their type names.
+const CASTS: &[(&str, &str)] = &[
+    ("id", "Boolean"),
+    ("id", "UInt8"),
+    ("id", "Int64"),
+    ("id", "Float16"),
+    ("id", "Float32"),
+    ("id", "Float64"),
+    ("id", "Decimal128(10, 2)"),
+    ("id", "Date32"),
+    ("id", "Time32(Second)"),
+    ("id", "Timestamp(Millisecond, None)"),
+    ("id", "Duration(Second)"),
+    ("name", "LargeUtf8"),
+    ("name", "Binary"),
+];
+
+fn cases() -> Vec<Case> {
+    let mut basic = create_sample_table("basic").unwrap();
+    for (source, data_type) in CASTS {
+        let column = get_column(basic, source).unwrap();
+        let cast = cast_column(&column, data_type, None, None).unwrap();
+        basic = add_column(basic, data_type, &cast).unwrap();
+    }
+    let zoned = get_column(basic, "Timestamp(Millisecond, None)").unwrap();
+    let zoned = localize_naive(&zoned, "Asia/Kolkata", JsValue::UNDEFINED).unwrap();
+    basic = add_column(basic, "Timestamp(Millisecond, Asia/Kolkata)", &zoned).unwrap();
+
+    let mut cases: Vec<Case> = ["id", "name"]
+        .into_iter()
+        .chain(CASTS.iter().map(|(_, data_type)| *data_type))
+        .chain(["Timestamp(Millisecond, Asia/Kolkata)"])
+        .map(|name| Case {
+            table: basic,
+            name: name.to_string(),
+        })
+        .collect();
+    for (sample, name) in [
+        ("strings_categorical", "status"),
+        ("nested", "tags"),
+        ("nested", "point"),
+        ("views", "city"),
+        ("views", "payload"),
+    ] {
+        cases.push(Case {
+            table: create_sample_table(sample).unwrap(),
+            name: name.to_string(),
+        });
+    }
+    cases
+}
+
+fn json(text: &str) -> JsValue {
+    JSON::parse(text).unwrap()
+}
+
+/// Call `kernel` on the case's column with arguments it accepts.
+fn run(kernel: &str, case: &Case, column: &Column) -> Result<(), JsValue> {
+    let keys = || json(&format!(r#"[{{"column":{:?}}}]"#, case.name));
+    let none = JsValue::UNDEFINED;
+    match kernel {
+        "column_sum" => column_sum(column).map(drop),
+        "column_sum_exact" => column_sum_exact(column).map(drop),
+        "column_mean" => column_mean(column).map(drop),
+        "column_median" => column_median(column).map(drop),
+        "column_variance" => column_variance(column, None).map(drop),
+        "column_std_dev" => column_std_dev(column, None).map(drop),
+        "Column.quantile" => column.quantile(0.5).map(drop),
+        "Column.quantiles" => column.quantiles(vec![0.25, 0.75]).map(drop),
+        "column_min" => column_min(column, None).map(drop),
+        "column_max" => column_max(column, None).map(drop),
+        "sort_by" => sort_by(case.table, keys()).map(drop),
+        "top_k" => top_k(case.table, 2, keys()).map(drop),
+        "bottom_k" => bottom_k(case.table, 2, keys()).map(drop),
+        "abs" => abs(column, none).map(drop),
+        "negate" => negate(column, none).map(drop),
+        "column_round" => column_round(column, Some(1)).map(drop),
+        "column_floor" => column_floor(column).map(drop),
+        "column_ceil" => column_ceil(column).map(drop),
+        "clip" => clip(column, 1.into(), 3.into()).map(drop),
+        "diff" => diff(column, None).map(drop),
+        "Column.diff" => column.diff(Some(2)).map(drop),
+        "percent_change" => percent_change(column, None).map(drop),
+        "shift" => shift(column, 1, none).map(drop),
+        "fill_forward" => fill_forward(column).map(drop),
+        "fill_backward" => fill_backward(column).map(drop),
+        "count_matches" => count_matches(column, "a").map(drop),
+        "parse_numbers" => parse_numbers(column, none).map(drop),
+        "parse_dates" => parse_dates(column, json(r#"{"formats":["%Y-%m-%d"]}"#)).map(drop),
+        "decode_utf8" => decode_utf8(column, none).map(drop),
+        "Column.to_base64_array" => column.to_base64_array().map(drop),
+        "Column.true_count" => column.true_count().map(drop),
+        "Column.false_count" => column.false_count().map(drop),
+        "Column.true_ratio" => column.true_ratio().map(drop),
+        "date_part" => date_part(column, "month").map(drop),
+        "date_trunc" => date_trunc(column, "day").map(drop),
+        "convert_timezone" => convert_timezone(column, "Europe/Paris").map(drop),
+        "localize_naive" => localize_naive(column, "Europe/Paris", none).map(drop),
+        "remove_timezone" => remove_timezone(column).map(drop),
+        "encode_run_ends" => encode_run_ends(column, None).map(drop),
+        "Column.encode_run_length" => column.encode_run_length(None).map(drop),
+        "Column.approx_count_distinct" => column.approx_count_distinct(none).map(drop),
+        "Column.mode" => column.mode(none).map(drop),
+        "cut" => cut(column, json("[0, 2, 4]"), none.clone(), none).map(drop),
+        "Column.cut" => column.cut(json("[0, 2, 4]"), none.clone(), none).map(drop),
+        "join_asof" => join_asof(case.table, case.table, &case.name, &case.name, None).map(drop),
+        "Column.histogram" => column.histogram(4).map(drop),
+        "validate_wkb" => validate_wkb(column).map(drop),
+        other => panic!("the conformance test has no call for kernel {other}"),
+    }
+}
+
+#[wasm_bindgen_test]
+fn kernels_accept_exactly_the_supported_types() {
+    let matrix: Vec<Entry> = serde_wasm_bindgen::from_value(capability_matrix().unwrap()).unwrap();
+    let mut mismatches = Vec::new();
+    for case in cases() {
+        let column = get_column(case.table, &case.name).unwrap();
+        let supported = supported_operations(&column).unwrap();
+        for entry in &matrix {
+            let unsupported = format!("{} does not support column", entry.operation);
+            let expected = supported.contains(&entry.operation);
+            for kernel in &entry.kernels {
+                let message = run(kernel, &case, &column)
+                    .err()
+                    .map(|error| error.as_string().unwrap_or_default());
+                let rejected = message
+                    .as_deref()
+                    .is_some_and(|message| message.starts_with(&unsupported));
+                if rejected == expected {
+                    mismatches.push(format!(
+                        "{kernel} on {}: listed {expected}, got {message:?}",
+                        case.name
+                    ));
+                }
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[wasm_bindgen_test]
+fn the_matrix_lists_every_kernel_once() {
+    let matrix: Vec<Entry> = serde_wasm_bindgen::from_value(capability_matrix().unwrap()).unwrap();
+    let kernels: Vec<&String> = matrix.iter().flat_map(|entry| &entry.kernels).collect();
+    let distinct: std::collections::BTreeSet<&String> = kernels.iter().copied().collect();
+    assert_eq!(kernels.len(), distinct.len());
+}
@@ -0,0 +1,594 @@
+//! Values of a `Column` as they reach JS, including temporal values read
+//! back from Parquet, and how the global, per-table and per-call conversion
+//! policies combine.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{
+    ArrayRef, BinaryArray, Date32Array, Date64Array, Decimal128Array, Int32Array, Int64Array,
+    LargeBinaryArray, ListArray, RecordBatch, StringArray, StructArray, TimestampMicrosecondArray,
+    TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use arrow::ipc::writer::StreamWriter;
+use arrow_cast::base64::{b64_decode, BASE64_STANDARD};
+use arrow_rs_wasm::{
+    clear_table_conversion_policy, get_column, merge_field_metadata, read_table_from_bytes,
+    read_table_from_parquet, set_conversion_policy, set_strict_indexing,
+    set_table_conversion_policy, table_rows, to_rows_compact, write_table_to_parquet,
+    ConversionPolicy, TableHandle,
+};
+use js_sys::{Array, BigInt, Date, Object, Reflect, JSON};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Register `batches` as a table, through the IPC reader like JS data.
+fn table(batches: &[RecordBatch]) -> TableHandle {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+fn json(array: &Array) -> String {
+    JSON::stringify(array).unwrap().into()
+}
+
+/// `n`, `tags` (a list) and `point` (a struct) over two batches, each
+/// column null in half of its six rows.
+fn sparse() -> TableHandle {
+    let batch = |n: Vec<Option<i32>>, tags, point: Vec<Option<(i32, &str)>>| {
+        let tags = ListArray::from_iter_primitive::<Int32Type, _, _>(tags);
+        let valid: Vec<bool> = point.iter().map(Option::is_some).collect();
+        let (x, label): (Vec<i32>, Vec<&str>) =
+            point.iter().map(|point| point.unwrap_or((0, ""))).unzip();
+        let point = StructArray::try_new(
+            vec![
+                Field::new("x", DataType::Int32, false),
+                Field::new("label", DataType::Utf8, false),
+            ]
+            .into(),
+            vec![
+                Arc::new(Int32Array::from(x)),
+                Arc::new(StringArray::from(label)),
+            ],
+            Some(valid.into()),
+        )
+        .unwrap();
+        RecordBatch::try_from_iter([
+            ("n", Arc::new(Int32Array::from(n)) as ArrayRef),
+            ("tags", Arc::new(tags)),
+            ("point", Arc::new(point)),
+        ])
+        .unwrap()
+    };
+    table(&[
+        batch(
+            vec![Some(1), None, None, Some(4)],
+            vec![Some(vec![Some(1), None]), None, Some(vec![]), None],
+            vec![None, Some((1, "a")), None, Some((2, "b"))],
+        ),
+        batch(
+            vec![None, Some(6)],
+            vec![None, Some(vec![Some(3)])],
+            vec![Some((3, "c")), None],
+        ),
+    ])
+}
+
+#[wasm_bindgen_test]
+fn compact_arrays_keep_only_valid_values() {
+    let handle = sparse();
+    let cases = [
+        ("n", "[1,4,6]"),
+        ("tags", "[[1,null],[],[3]]"),
+        (
+            "point",
+            r#"[{"x":1,"label":"a"},{"x":2,"label":"b"},{"x":3,"label":"c"}]"#,
+        ),
+    ];
+    for (name, expected) in cases {
+        let column = get_column(handle, name).unwrap();
+        let full = column.to_array(JsValue::UNDEFINED).unwrap();
+        let compact = column.to_array_compact(JsValue::UNDEFINED).unwrap();
+        assert_eq!(full.length(), 6, "{name}");
+        assert_eq!(compact.length(), 3, "{name}");
+        assert_eq!(json(&compact), expected, "{name}");
+        // The compact array is the full one without its nulls.
+        let valid: Array = full.iter().filter(|value| !value.is_null()).collect();
+        assert_eq!(json(&valid), expected, "{name}");
+    }
+}
+
+#[wasm_bindgen_test]
+fn out_of_range_get_follows_the_indexing_mode() {
+    let column = get_column(sparse(), "n").unwrap();
+    let last = column.get(5, JsValue::UNDEFINED).unwrap();
+    assert_eq!(last.as_f64(), Some(6.0));
+    assert!(column.get(1, JsValue::UNDEFINED).unwrap().is_null());
+
+    for index in [6, 7, usize::MAX] {
+        assert!(column
+            .get(index, JsValue::UNDEFINED)
+            .unwrap()
+            .is_undefined());
+    }
+
+    set_strict_indexing(true);
+    let [past_end, far] = [6, usize::MAX].map(|index| column.get(index, JsValue::UNDEFINED));
+    let in_range = column.get(5, JsValue::UNDEFINED);
+    set_strict_indexing(false);
+
+    assert_eq!(
+        past_end.unwrap_err().as_string().unwrap(),
+        "get failed on column 'n' (Int32): Row index 6 out of bounds for column of length 6"
+    );
+    assert!(far.is_err());
+    assert_eq!(in_range.unwrap().as_f64(), Some(6.0));
+    // Back to the default.
+    assert!(column.get(6, JsValue::UNDEFINED).unwrap().is_undefined());
+}
+
+/// The metadata object of column `name` as sorted `(key, value)` pairs.
+fn metadata(handle: TableHandle, name: &str) -> Vec<(String, String)> {
+    let object = get_column(handle, name).unwrap().metadata().unwrap();
+    let mut entries: Vec<(String, String)> = Object::keys(&object)
+        .iter()
+        .map(|key| {
+            let value = Reflect::get(&object, &key).unwrap();
+            (key.as_string().unwrap(), value.as_string().unwrap())
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+    entries
+        .iter()
+        .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+        .collect()
+}
+
+#[wasm_bindgen_test]
+fn field_metadata_is_read_through_the_column() {
+    let unit = HashMap::from([("unit".to_string(), "m".to_string())]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("depth", DataType::Int32, false).with_metadata(unit),
+        Field::new("n", DataType::Int32, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int32Array::from(vec![1, 2])),
+        Arc::new(Int32Array::from(vec![3, 4])),
+    ];
+    let handle = table(&[RecordBatch::try_new(schema, columns).unwrap()]);
+    assert_eq!(metadata(handle, "depth"), pairs(&[("unit", "m")]));
+    assert!(metadata(handle, "n").is_empty());
+
+    let entries = JSON::parse(r#"{"source": "sonar", "unit": "cm"}"#).unwrap();
+    let merged = merge_field_metadata(handle, "depth", entries).unwrap();
+    assert_eq!(
+        metadata(merged, "depth"),
+        pairs(&[("source", "sonar"), ("unit", "cm")])
+    );
+    assert!(metadata(merged, "n").is_empty());
+    assert_eq!(metadata(handle, "depth"), pairs(&[("unit", "m")]));
+}
+
+#[wasm_bindgen_test]
+fn error_like_strings_are_data() {
+    let texts = [
+        "Internal error (please report this as a bug): boom",
+        "Internal error: boom",
+        "Invalid input: x",
+    ];
+    let batch = RecordBatch::try_from_iter([(
+        "s",
+        Arc::new(StringArray::from(texts.to_vec())) as ArrayRef,
+    )])
+    .unwrap();
+    let column = get_column(table(&[batch]), "s").unwrap();
+    for (index, text) in texts.iter().enumerate() {
+        let value = column.get(index, JsValue::UNDEFINED).unwrap();
+        assert_eq!(value.as_string().as_deref(), Some(*text));
+    }
+    let values: Vec<Option<String>> = column
+        .to_array(JsValue::UNDEFINED)
+        .unwrap()
+        .iter()
+        .map(|value| value.as_string())
+        .collect();
+    assert_eq!(values, texts.map(|text| Some(text.to_string())));
+}
+
+#[wasm_bindgen_test]
+fn base64_of_large_binary_decodes_to_the_bytes() {
+    let every_byte: Vec<u8> = (0..=255).collect();
+    let values: Vec<Option<&[u8]>> = vec![
+        Some(&every_byte),
+        None,
+        Some(b""),
+        Some(b"a"),
+        Some(b"ab"),
+        Some(&[0xff, 0x00, 0xfe]),
+    ];
+    let batch = RecordBatch::try_from_iter([(
+        "blob",
+        Arc::new(LargeBinaryArray::from(values.clone())) as ArrayRef,
+    )])
+    .unwrap();
+    let encoded = get_column(table(&[batch]), "blob")
+        .unwrap()
+        .to_base64_array()
+        .unwrap();
+    assert_eq!(encoded.length(), 6);
+    let encoded: Vec<Option<String>> = encoded.iter().map(|value| value.as_string()).collect();
+    assert_eq!(encoded[1], None);
+    assert_eq!(encoded[4].as_deref(), Some("YWI="));
+    let encoded: BinaryArray = encoded.iter().map(|value| value.as_deref()).collect();
+    let decoded = b64_decode(&BASE64_STANDARD, &encoded).unwrap();
+    let decoded: Vec<Option<&[u8]>> = decoded.iter().collect();
+    assert_eq!(decoded, values);
+}
+
+fn policy(fields: &str) -> ConversionPolicy {
+    ConversionPolicy::new(JSON::parse(fields).unwrap()).unwrap()
+}
+
+/// `amount` (Decimal128 with scale 2: -12.30, null, 0.05) and `id`, a Utf8
+/// column tagged as the `arrow.uuid` extension type.
+fn priced() -> TableHandle {
+    let amount = Decimal128Array::from(vec![Some(-1230), None, Some(5)])
+        .with_precision_and_scale(10, 2)
+        .unwrap();
+    let uuid = HashMap::from([("ARROW:extension:name".to_string(), "arrow.uuid".to_string())]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("amount", DataType::Decimal128(10, 2), true),
+        Field::new("id", DataType::Utf8, false).with_metadata(uuid),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(amount),
+        Arc::new(StringArray::from(vec!["a", "b", "c"])),
+    ];
+    table(&[RecordBatch::try_new(schema, columns).unwrap()])
+}
+
+/// `get(0)`, `toArray` and `toArrayCompact` of `amount` under `overrides`,
+/// as JSON.
+fn amounts(handle: TableHandle, overrides: &str) -> [String; 3] {
+    let column = get_column(handle, "amount").unwrap();
+    let overrides = || JSON::parse(overrides).unwrap();
+    let first = column.get(0, overrides()).unwrap();
+    [
+        JSON::stringify(&first).unwrap().into(),
+        json(&column.to_array(overrides()).unwrap()),
+        json(&column.to_array_compact(overrides()).unwrap()),
+    ]
+}
+
+#[wasm_bindgen_test]
+fn per_call_beats_per_table_beats_global() {
+    let handle = priced();
+    let other = priced();
+    let strings = [
+        r#""-12.30""#.to_string(),
+        r#"["-12.30",null,"0.05"]"#.to_string(),
+        r#"["-12.30","0.05"]"#.to_string(),
+    ];
+    let numbers = [
+        "-12.3".to_string(),
+        "[-12.3,null,0.05]".to_string(),
+        "[-12.3,0.05]".to_string(),
+    ];
+    assert_eq!(amounts(handle, "null"), strings);
+
+    set_conversion_policy(&policy(r#"{"decimalAs": "number"}"#)).unwrap();
+    let global = amounts(handle, "null");
+    set_table_conversion_policy(handle, &policy(r#"{"decimalAs": "string"}"#)).unwrap();
+    let table = amounts(handle, "null");
+    let untouched = amounts(other, "null");
+    let call = amounts(handle, r#"{"decimalAs": "number"}"#);
+    let call_over_global = amounts(other, r#"{"decimalAs": "string"}"#);
+    assert!(clear_table_conversion_policy(handle));
+    let cleared = amounts(handle, "null");
+    set_conversion_policy(&policy("{}")).unwrap();
+
+    assert_eq!(global, numbers);
+    assert_eq!(table, strings);
+    assert_eq!(untouched, numbers);
+    assert_eq!(call, numbers);
+    assert_eq!(call_over_global, strings);
+    assert_eq!(cleared, numbers);
+    assert_eq!(amounts(handle, "null"), strings);
+}
+
+#[wasm_bindgen_test]
+fn extension_columns_follow_extension_handling() {
+    let handle = priced();
+    let id = get_column(handle, "id").unwrap();
+    let storage = r#"{"extensionHandling": "storage"}"#;
+    assert_eq!(
+        json(&id.to_array(JsValue::UNDEFINED).unwrap()),
+        r#"["a","b","c"]"#
+    );
+
+    set_table_conversion_policy(handle, &policy(r#"{"extensionHandling": "error"}"#)).unwrap();
+    let message = id
+        .get(0, JsValue::UNDEFINED)
+        .unwrap_err()
+        .as_string()
+        .unwrap();
+    let compact = id.to_array_compact(JsValue::UNDEFINED);
+    let overridden = id.to_array(JSON::parse(storage).unwrap());
+    let amount = amounts(handle, "null");
+    assert!(clear_table_conversion_policy(handle));
+
+    assert!(
+        message.contains("extension type 'arrow.uuid' is not converted"),
+        "{message}"
+    );
+    assert!(compact.is_err());
+    assert_eq!(json(&overridden.unwrap()), r#"["a","b","c"]"#);
+    assert_eq!(amount[0], r#""-12.30""#);
+}
+
+#[wasm_bindgen_test]
+fn nesting_past_max_nesting_depth_is_rejected() {
+    let handle = sparse();
+    let depth = |max: usize| JSON::parse(&format!(r#"{{"maxNestingDepth": {max}}}"#)).unwrap();
+    let tags = get_column(handle, "tags").unwrap();
+    assert_eq!(tags.to_array(depth(1)).unwrap().length(), 6);
+    let message = tags.to_array(depth(0)).unwrap_err().as_string().unwrap();
+    assert!(
+        message.contains("deeper than maxNestingDepth (0)"),
+        "{message}"
+    );
+    // Rejected before any value is read, null or not.
+    assert!(tags.get(1, depth(0)).is_err());
+    assert!(tags.to_array_compact(depth(0)).is_err());
+    let n = get_column(handle, "n").unwrap();
+    assert_eq!(n.to_array(depth(0)).unwrap().length(), 6);
+
+    assert!(to_rows_compact(handle, depth(0)).is_err());
+    assert!(table_rows(handle, depth(0)).is_err());
+    assert_eq!(to_rows_compact(handle, depth(1)).unwrap().length(), 6);
+}
+
+/// Timestamps in all four units, two of them zoned, and both date types,
+/// each with a null, written to Parquet and read back.
+fn temporal() -> TableHandle {
+    let columns: Vec<(&str, ArrayRef)> = vec![
+        (
+            "s",
+            Arc::new(TimestampSecondArray::from(vec![
+                Some(1_700_000_000),
+                None,
+                Some(-2),
+            ])),
+        ),
+        (
+            "ms",
+            Arc::new(
+                TimestampMillisecondArray::from(vec![None, Some(1_700_000_000_123), Some(-1)])
+                    .with_timezone("+09:00"),
+            ),
+        ),
+        (
+            "us",
+            Arc::new(
+                TimestampMicrosecondArray::from(vec![
+                    Some(1_700_000_000_123_500),
+                    Some(-1_000),
+                    None,
+                ])
+                .with_timezone("UTC"),
+            ),
+        ),
+        (
+            "ns",
+            Arc::new(TimestampNanosecondArray::from(vec![
+                Some(86_400_123_250_000),
+                None,
+                Some(-2_000_000),
+            ])),
+        ),
+        (
+            "day",
+            Arc::new(Date32Array::from(vec![Some(19_675), None, Some(-1)])),
+        ),
+        (
+            "date64",
+            Arc::new(Date64Array::from(vec![
+                None,
+                Some(1_700_000_000_123),
+                Some(0),
+            ])),
+        ),
+    ];
+    let handle = table(&[RecordBatch::try_from_iter(columns).unwrap()]);
+    let parquet = write_table_to_parquet(handle, JsValue::UNDEFINED).unwrap();
+    read_table_from_parquet(&parquet.to_vec()).unwrap()
+}
+
+/// Values of column `name` under `temporalAs`, as epoch milliseconds read
+/// from the `Date` or number; `get` and `toArray` must agree.
+fn instants(handle: TableHandle, name: &str, temporal_as: &str) -> Vec<Option<f64>> {
+    let column = get_column(handle, name).unwrap();
+    let overrides = || JSON::parse(&format!(r#"{{"temporalAs": "{temporal_as}"}}"#)).unwrap();
+    let millis = |value: JsValue| -> Option<f64> {
+        if value.is_null() {
+            return None;
+        }
+        Some(match temporal_as {
+            "date" => value.dyn_into::<Date>().expect("a Date").get_time(),
+            _ => value.as_f64().expect("a number"),
+        })
+    };
+    let values: Vec<Option<f64>> = column
+        .to_array(overrides())
+        .unwrap()
+        .iter()
+        .map(millis)
+        .collect();
+    for (index, value) in values.iter().enumerate() {
+        assert_eq!(millis(column.get(index, overrides()).unwrap()), *value);
+    }
+    values
+}
+
+/// Epoch milliseconds of the three rows of a `temporal` column.
+type Millis = [Option<f64>; 3];
+
+#[wasm_bindgen_test]
+fn temporal_values_round_trip_in_every_unit() {
+    let handle = temporal();
+    let day = 86_400_000.0;
+    let cases = [
+        ("s", "Timestamp(Second, None)", None),
+        (
+            "ms",
+            r#"Timestamp(Millisecond, Some("+09:00"))"#,
+            Some("+09:00"),
+        ),
+        ("us", r#"Timestamp(Microsecond, Some("UTC"))"#, Some("UTC")),
+        ("ns", "Timestamp(Nanosecond, None)", None),
+        ("day", "Date32", None),
+        ("date64", "Date64", None),
+    ];
+    for (name, data_type, timezone) in cases {
+        let column = get_column(handle, name).unwrap();
+        assert_eq!(column.data_type().unwrap(), data_type);
+        assert_eq!(column.timezone().unwrap().as_deref(), timezone, "{name}");
+    }
+
+    // Epoch numbers keep sub-millisecond precision; a Date truncates it.
+    let expected: [(&str, Millis, Millis); 6] = [
+        (
+            "s",
+            [Some(1_700_000_000_000.0), None, Some(-2_000.0)],
+            [Some(1_700_000_000_000.0), None, Some(-2_000.0)],
+        ),
+        (
+            "ms",
+            [None, Some(1_700_000_000_123.0), Some(-1.0)],
+            [None, Some(1_700_000_000_123.0), Some(-1.0)],
+        ),
+        (
+            "us",
+            [Some(1_700_000_000_123.5), Some(-1.0), None],
+            [Some(1_700_000_000_123.0), Some(-1.0), None],
+        ),
+        (
+            "ns",
+            [Some(86_400_123.25), None, Some(-2.0)],
+            [Some(86_400_123.0), None, Some(-2.0)],
+        ),
+        (
+            "day",
+            [Some(19_675.0 * day), None, Some(-day)],
+            [Some(19_675.0 * day), None, Some(-day)],
+        ),
+        (
+            "date64",
+            [None, Some(1_700_000_000_123.0), Some(0.0)],
+            [None, Some(1_700_000_000_123.0), Some(0.0)],
+        ),
+    ];
+    for (name, numbers, dates) in expected {
+        assert_eq!(instants(handle, name, "number"), numbers, "{name}");
+        assert_eq!(instants(handle, name, "date"), dates, "{name}");
+    }
+}
+
+/// `Number.MAX_SAFE_INTEGER`, 2^53 - 1.
+const MAX_SAFE: i64 = (1 << 53) - 1;
+
+/// `signed` (`Int64`: 2^53 - 1, -(2^53 - 1), 2^53 + 1, null) and `unsigned`
+/// (`UInt64`: 2^53 - 1, 0, 2^53 + 1, null).
+fn wide() -> TableHandle {
+    let batch = RecordBatch::try_from_iter([
+        (
+            "signed",
+            Arc::new(Int64Array::from(vec![
+                Some(MAX_SAFE),
+                Some(-MAX_SAFE),
+                Some(MAX_SAFE + 2),
+                None,
+            ])) as ArrayRef,
+        ),
+        (
+            "unsigned",
+            Arc::new(UInt64Array::from(vec![
+                Some(MAX_SAFE as u64),
+                Some(0),
+                Some(MAX_SAFE as u64 + 2),
+                None,
+            ])),
+        ),
+    ])
+    .unwrap();
+    table(&[batch])
+}
+
+/// Check that `value` is `expected` as "safe" gives it: a Number up to
+/// `MAX_SAFE` in magnitude, a `BigInt` beyond it, and null for null.
+fn assert_safe(value: &JsValue, expected: Option<i64>) {
+    match expected {
+        None => assert!(value.is_null(), "{value:?}"),
+        Some(expected) if expected.abs() <= MAX_SAFE => {
+            assert_eq!(value.as_f64(), Some(expected as f64), "{value:?}");
+        }
+        Some(expected) => {
+            let big = value.dyn_ref::<BigInt>().expect("a BigInt");
+            assert_eq!(
+                String::from(big.to_string(10).unwrap()),
+                expected.to_string()
+            );
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn safe_int64_gives_numbers_up_to_the_safe_limit() {
+    let handle = wide();
+    let safe = || JSON::parse(r#"{"int64As": "safe"}"#).unwrap();
+    let columns = [
+        (
+            "signed",
+            [Some(MAX_SAFE), Some(-MAX_SAFE), Some(MAX_SAFE + 2), None],
+        ),
+        (
+            "unsigned",
+            [Some(MAX_SAFE), Some(0), Some(MAX_SAFE + 2), None],
+        ),
+    ];
+
+    for (name, expected) in columns {
+        let column = get_column(handle, name).unwrap();
+        let array = column.to_array(safe()).unwrap();
+        for (row, expected) in expected.into_iter().enumerate() {
+            assert_safe(&column.get(row, safe()).unwrap(), expected);
+            assert_safe(&array.get(row as u32), expected);
+        }
+    }
+
+    let mut rows = table_rows(handle, safe()).unwrap();
+    let compact = to_rows_compact(handle, safe()).unwrap();
+    for row in 0..4 {
+        let object = Reflect::get(&rows.next_result().unwrap(), &"value".into()).unwrap();
+        let values = Array::from(&compact.get(row as u32));
+        for (position, (name, expected)) in columns.iter().enumerate() {
+            assert_safe(
+                &Reflect::get(&object, &(*name).into()).unwrap(),
+                expected[row],
+            );
+            assert_safe(&values.get(position as u32), expected[row]);
+        }
+    }
+}
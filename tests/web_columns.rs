@@ -0,0 +1,96 @@
+//! `rename_column`, `rename_columns`, `add_column` and `assign` refuse to
+//! produce a table with two columns of the same name.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    add_column, assign, get_column, get_column_names, read_table_from_bytes, rename_column,
+    rename_columns, TableHandle,
+};
+use js_sys::JSON;
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn table(columns: Vec<(&str, ArrayRef)>) -> TableHandle {
+    let batch = RecordBatch::try_from_iter(columns).unwrap();
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+/// `id`, `name` and `score`, three rows.
+fn people() -> TableHandle {
+    table(vec![
+        ("id", Arc::new(Int32Array::from(vec![1, 2, 3]))),
+        ("name", Arc::new(StringArray::from(vec!["a", "b", "c"]))),
+        ("score", Arc::new(Int32Array::from(vec![7, 8, 9]))),
+    ])
+}
+
+fn duplicate(result: Result<TableHandle, JsValue>) -> String {
+    let error = result.map(drop).unwrap_err().as_string().unwrap();
+    assert!(error.contains("Duplicate column name"), "{error}");
+    error
+}
+
+#[wasm_bindgen_test]
+fn renames_reject_taken_names() {
+    let source = people();
+    let error = duplicate(rename_column(source, "id", "name"));
+    assert!(error.contains("'name'"), "{error}");
+
+    let mapping = JSON::parse(r#"{"id": "key", "score": "key"}"#).unwrap();
+    let error = duplicate(rename_columns(source, mapping));
+    assert!(error.contains("'key'"), "{error}");
+
+    // Swapping names, or renaming to the same name, leaves them unique.
+    let swap = JSON::parse(r#"{"id": "name", "name": "id"}"#).unwrap();
+    let swapped = rename_columns(source, swap).unwrap();
+    assert_eq!(get_column_names(swapped).unwrap(), ["name", "id", "score"]);
+    let same = rename_column(source, "score", "score").unwrap();
+    assert_eq!(get_column_names(same).unwrap(), ["id", "name", "score"]);
+    assert_eq!(get_column_names(source).unwrap(), ["id", "name", "score"]);
+}
+
+#[wasm_bindgen_test]
+fn add_column_rejects_an_existing_name() {
+    let source = people();
+    let column = get_column(source, "score").unwrap();
+    for name in ["id", "name", "score"] {
+        let error = duplicate(add_column(source, name, &column));
+        assert!(error.contains(&format!("'{name}'")), "{error}");
+    }
+    let added = add_column(source, "score2", &column).unwrap();
+    assert_eq!(
+        get_column_names(added).unwrap(),
+        ["id", "name", "score", "score2"]
+    );
+}
+
+#[wasm_bindgen_test]
+fn assign_rejects_duplicates_in_the_other_table() {
+    let source = people();
+    let twice = table(vec![
+        ("bonus", Arc::new(Int32Array::from(vec![1, 1, 1]))),
+        ("bonus", Arc::new(Int32Array::from(vec![2, 2, 2]))),
+    ]);
+    let error = duplicate(assign(source, twice));
+    assert!(error.contains("'bonus'"), "{error}");
+
+    // A name already in the table is replaced in place, not duplicated.
+    let other = table(vec![
+        ("score", Arc::new(Int32Array::from(vec![0, 0, 0]))),
+        ("bonus", Arc::new(Int32Array::from(vec![1, 1, 1]))),
+    ]);
+    let assigned = assign(source, other).unwrap();
+    assert_eq!(
+        get_column_names(assigned).unwrap(),
+        ["id", "name", "score", "bonus"]
+    );
+}
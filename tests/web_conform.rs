@@ -0,0 +1,119 @@
+//! `conform_to_schema` with each form of target schema: IPC bytes, a
+//! descriptor object and a bare field list.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    conform_to_schema, get_column, get_column_names, get_table_schema_json, read_table_from_bytes,
+    write_table_to_ipc, TableHandle,
+};
+use js_sys::{Uint8Array, JSON};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn stream(schema: Arc<Schema>, batches: &[RecordBatch]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &schema).unwrap();
+    for batch in batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    bytes
+}
+
+/// `b` (text) before `a` (Int32).
+fn shuffled() -> TableHandle {
+    let batch = RecordBatch::try_from_iter([
+        ("b", Arc::new(StringArray::from(vec!["1", "x"])) as ArrayRef),
+        ("a", Arc::new(Int32Array::from(vec![1, 2]))),
+    ])
+    .unwrap();
+    read_table_from_bytes(&stream(batch.schema(), &[batch]), None).unwrap()
+}
+
+/// `a: Int64`, `b: Int32` and a missing nullable `c: Utf8`.
+fn target() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Int32, true),
+        Field::new("c", DataType::Utf8, true),
+    ]))
+}
+
+/// An empty table with the [`target`] schema.
+fn model() -> TableHandle {
+    let bytes = stream(target(), &[RecordBatch::new_empty(target())]);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+fn check(conformed: TableHandle) {
+    assert_eq!(get_column_names(conformed).unwrap(), ["a", "b", "c"]);
+    let types: Vec<String> = ["a", "b", "c"]
+        .iter()
+        .map(|name| get_column(conformed, name).unwrap().data_type().unwrap())
+        .collect();
+    assert_eq!(types, ["Int64", "Int32", "Utf8"]);
+    let b = get_column(conformed, "b").unwrap();
+    assert_eq!(b.null_count().unwrap(), 1);
+    assert_eq!(get_column(conformed, "c").unwrap().null_count().unwrap(), 2);
+}
+
+#[wasm_bindgen_test]
+fn ipc_bytes_give_the_schema() {
+    // A schema message alone, as from a writer that never wrote a batch.
+    let bytes = stream(target(), &[]);
+    let schema = Uint8Array::from(bytes.as_slice());
+    check(conform_to_schema(shuffled(), schema.into(), JsValue::UNDEFINED).unwrap());
+
+    // The output of write_table_to_ipc works too; its batches are ignored.
+    let schema = write_table_to_ipc(model(), false).unwrap();
+    check(conform_to_schema(shuffled(), schema.into(), JsValue::UNDEFINED).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn descriptors_and_field_lists_give_the_schema() {
+    let descriptor = JSON::parse(
+        r#"{"fields": [
+            {"name": "a", "type": "Int64", "nullable": false},
+            {"name": "b", "type": "Int32", "nullable": true},
+            {"name": "c", "type": "Utf8", "nullable": true, "metadata": {"k": "v"}}
+        ], "metadata": {"source": "test"}}"#,
+    )
+    .unwrap();
+    check(conform_to_schema(shuffled(), descriptor, JsValue::UNDEFINED).unwrap());
+
+    let fields = JSON::parse(&get_table_schema_json(model()).unwrap()).unwrap();
+    check(conform_to_schema(shuffled(), fields, JsValue::UNDEFINED).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn bad_schemas_are_rejected() {
+    let error = |schema: JsValue| {
+        conform_to_schema(shuffled(), schema, JsValue::UNDEFINED)
+            .map(drop)
+            .unwrap_err()
+            .as_string()
+            .unwrap()
+    };
+    let message = error(Uint8Array::from(&[1_u8, 2][..]).into());
+    assert!(
+        message.contains("Target schema is not an IPC schema message"),
+        "{message}"
+    );
+    let message =
+        error(JSON::parse(r#"[{"name": "a", "type": "Int33", "nullable": true}]"#).unwrap());
+    assert!(message.contains("Unknown data type 'Int33'"), "{message}");
+    let message = error(
+        JSON::parse(
+            r#"[{"name": "a", "type": "Int32", "nullable": true},
+                {"name": "a", "type": "Utf8", "nullable": true}]"#,
+        )
+        .unwrap(),
+    );
+    assert!(message.contains("Duplicate column name 'a'"), "{message}");
+}
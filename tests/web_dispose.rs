@@ -0,0 +1,156 @@
+//! Automatic disposal with a stand-in `FinalizationRegistry` whose cleanup
+//! callback the tests call themselves, so collection happens exactly when
+//! a test says so.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    configure_auto_dispose, create_sample_table, dispose_handle, free_table, table_row_count,
+    track_handle, TableHandle,
+};
+use js_sys::{Object, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen(inline_js = r#"
+let fake = null;
+
+export function install_fake_registry() {
+    if (fake !== null) {
+        return;
+    }
+    fake = { cleanup: null, held: new Map() };
+    globalThis.FinalizationRegistry = class {
+        constructor(cleanup) {
+            fake.cleanup = cleanup;
+        }
+        register(target, held, token) {
+            fake.held.set(token, held);
+        }
+        unregister(token) {
+            return fake.held.delete(token);
+        }
+    };
+}
+
+export function collect(wrapper) {
+    const held = fake.held.get(wrapper);
+    if (held === undefined) {
+        return false;
+    }
+    fake.held.delete(wrapper);
+    fake.cleanup(held);
+    return true;
+}
+
+export function warnings_during(f) {
+    const original = console.warn;
+    const seen = [];
+    console.warn = (...args) => seen.push(args.join(" "));
+    try {
+        f();
+    } finally {
+        console.warn = original;
+    }
+    return seen;
+}
+"#)]
+extern "C" {
+    fn install_fake_registry();
+    /// Run the registry's cleanup for `wrapper`, as if it had been
+    /// collected; false when it is not registered.
+    fn collect(wrapper: &JsValue) -> bool;
+    /// Messages passed to `console.warn` while `f` runs.
+    fn warnings_during(f: &dyn Fn()) -> Vec<String>;
+}
+
+/// Switch to `mode` behind the stand-in registry.
+fn configure(mode: &str, dev_mode: bool) {
+    install_fake_registry();
+    let options = JSON::parse(&format!(
+        r#"{{"autoDispose":"{mode}","devMode":{dev_mode}}}"#
+    ))
+    .unwrap();
+    configure_auto_dispose(options).unwrap();
+}
+
+/// A new table and a wrapper object tracking it.
+fn tracked() -> (TableHandle, JsValue) {
+    let handle = create_sample_table("basic").unwrap();
+    let wrapper: JsValue = Object::new().into();
+    assert!(track_handle(&wrapper, handle).unwrap());
+    (handle, wrapper)
+}
+
+fn collect_and_warn(wrapper: &JsValue) -> Vec<String> {
+    warnings_during(&|| assert!(collect(wrapper)))
+}
+
+#[wasm_bindgen_test]
+fn warn_mode_reports_leaks_and_keeps_the_table() {
+    configure("warn", false);
+    let (handle, wrapper) = tracked();
+    let warnings = collect_and_warn(&wrapper);
+    assert_eq!(
+        warnings,
+        [format!(
+            "arrow-rs-wasm: table {handle} was garbage collected without being freed"
+        )]
+    );
+    assert_eq!(table_row_count(handle).unwrap(), 5);
+    free_table(handle).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn warn_mode_adds_the_creation_stack_in_dev_mode() {
+    configure("warn", true);
+    let (handle, wrapper) = tracked();
+    let warnings = collect_and_warn(&wrapper);
+    assert_eq!(warnings.len(), 1);
+    let (message, stack) = warnings[0].split_once('\n').expect("a stack follows");
+    assert!(message.ends_with(&format!(
+        "table {handle} was garbage collected without being freed"
+    )));
+    assert!(stack.starts_with("Error: table created"), "{stack}");
+    assert!(stack.contains("track_wrapper"), "{stack}");
+    assert!(dispose_handle(&wrapper, handle));
+}
+
+#[wasm_bindgen_test]
+fn warn_mode_is_quiet_for_freed_tables() {
+    configure("warn", true);
+    // Disposing unregisters the wrapper, so it is never reported.
+    let (handle, wrapper) = tracked();
+    assert!(dispose_handle(&wrapper, handle));
+    assert!(!collect(&wrapper));
+
+    // A table freed without its wrapper is still registered, but no longer
+    // a leak.
+    let (handle, wrapper) = tracked();
+    free_table(handle).unwrap();
+    assert!(collect_and_warn(&wrapper).is_empty());
+}
+
+#[wasm_bindgen_test]
+fn finalization_mode_frees_once() {
+    configure("finalization", false);
+    let (handle, wrapper) = tracked();
+    assert!(collect_and_warn(&wrapper).is_empty());
+    assert!(table_row_count(handle).is_err());
+    assert!(!dispose_handle(&wrapper, handle));
+}
+
+#[wasm_bindgen_test]
+fn wrappers_follow_the_current_mode() {
+    configure("finalization", false);
+    let (handle, wrapper) = tracked();
+    configure("warn", false);
+    assert_eq!(collect_and_warn(&wrapper).len(), 1);
+    assert_eq!(table_row_count(handle).unwrap(), 5);
+
+    configure("off", false);
+    let wrapper: JsValue = Object::new().into();
+    assert!(!track_handle(&wrapper, handle).unwrap());
+    assert!(!collect(&wrapper));
+    assert!(dispose_handle(&wrapper, handle));
+}
@@ -0,0 +1,50 @@
+//! Automatic disposal under a real garbage collector, where the engine
+//! exposes one (e.g. `NODE_OPTIONS=--expose-gc`); elsewhere the test
+//! passes without checking anything. `web_dispose` covers each mode
+//! deterministically.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{configure_auto_dispose, create_sample_table, table_row_count, track_handle};
+use js_sys::{Function, Object, Promise, Reflect, JSON};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn global(name: &str) -> Option<Function> {
+    Reflect::get(&js_sys::global(), &name.into())
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// Wait for the next macrotask, when pending finalizers may run.
+async fn tick(set_timeout: &Function) {
+    let promise = Promise::new(&mut |resolve, _| {
+        set_timeout
+            .call2(&JsValue::NULL, &resolve, &10.into())
+            .unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn collected_wrappers_free_their_tables() {
+    let (Some(gc), Some(set_timeout)) = (global("gc"), global("setTimeout")) else {
+        return;
+    };
+    configure_auto_dispose(JSON::parse(r#"{"autoDispose":"finalization"}"#).unwrap()).unwrap();
+    let handle = create_sample_table("basic").unwrap();
+    {
+        let wrapper: JsValue = Object::new().into();
+        assert!(track_handle(&wrapper, handle).unwrap());
+    }
+    for _ in 0..50 {
+        gc.call0(&JsValue::NULL).unwrap();
+        tick(&set_timeout).await;
+        if table_row_count(handle).is_err() {
+            return;
+        }
+    }
+    panic!("table {handle} outlived its collected wrapper");
+}
@@ -0,0 +1,81 @@
+//! `write_table_to_ipc_streaming` hands JS bounded chunks that reassemble
+//! into the stream `write_table_to_ipc` returns.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Int32Array, RecordBatch};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    read_table_from_bytes, table_row_count, write_table_to_ipc, write_table_to_ipc_streaming,
+    TableHandle,
+};
+use js_sys::{Array, Function, Uint8Array};
+use std::sync::Arc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// The writer's chunk size.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// 40 batches of 1 000 Int32 values (4 KB bodies), then one batch of
+/// 50 000 values whose 200 KB body is the only write past the chunk size.
+fn table() -> TableHandle {
+    let batch = |start: i32, rows: i32| {
+        let ids: ArrayRef = Arc::new((start..start + rows).collect::<Int32Array>());
+        RecordBatch::try_from_iter([("id", ids)]).unwrap()
+    };
+    let mut batches: Vec<RecordBatch> = (0..40).map(|i| batch(i * 1_000, 1_000)).collect();
+    batches.push(batch(40_000, 50_000));
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+/// A callback that pushes every chunk onto `chunks`.
+fn collector(chunks: &Array) -> Function {
+    Function::new_with_args("chunk", "this.push(chunk)").bind(chunks)
+}
+
+#[wasm_bindgen_test]
+fn chunks_reassemble_into_the_stream() {
+    let handle = table();
+    let chunks = Array::new();
+    let written = write_table_to_ipc_streaming(handle, false, &collector(&chunks)).unwrap();
+
+    let chunks: Vec<Vec<u8>> = chunks
+        .iter()
+        .map(|chunk| chunk.unchecked_into::<Uint8Array>().to_vec())
+        .collect();
+    assert!(chunks.len() > 3, "{} chunks", chunks.len());
+    let oversized: Vec<usize> = chunks
+        .iter()
+        .map(Vec::len)
+        .filter(|len| *len > CHUNK_BYTES)
+        .collect();
+    assert_eq!(oversized.len(), 1, "{oversized:?}");
+    assert!(oversized[0] >= 200_000);
+
+    let bytes = chunks.concat();
+    assert_eq!(bytes.len(), written);
+    assert_eq!(bytes, write_table_to_ipc(handle, false).unwrap().to_vec());
+    let restored = read_table_from_bytes(&bytes, None).unwrap();
+    assert_eq!(table_row_count(restored).unwrap(), 90_000);
+}
+
+#[wasm_bindgen_test]
+fn compressed_chunks_reassemble_too() {
+    let handle = table();
+    let chunks = Array::new();
+    write_table_to_ipc_streaming(handle, true, &collector(&chunks)).unwrap();
+    let bytes: Vec<u8> = chunks
+        .iter()
+        .flat_map(|chunk| chunk.unchecked_into::<Uint8Array>().to_vec())
+        .collect();
+    let restored = read_table_from_bytes(&bytes, None).unwrap();
+    assert_eq!(table_row_count(restored).unwrap(), 90_000);
+}
@@ -0,0 +1,136 @@
+//! `map_values` turning HTTP status codes into labels, with and without a
+//! default for unmapped codes.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Int32Array, Int64Array, RecordBatch};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{get_column, map_values, read_table_from_bytes, Column};
+use js_sys::{BigInt, Map, JSON};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// The column `status` of a table with one batch per array.
+fn status(arrays: Vec<ArrayRef>) -> Column {
+    let batches: Vec<RecordBatch> = arrays
+        .into_iter()
+        .map(|array| RecordBatch::try_from_iter([("status", array)]).unwrap())
+        .collect();
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    get_column(read_table_from_bytes(&bytes, None).unwrap(), "status").unwrap()
+}
+
+/// `[200, 404, null]` and `[500, 200, 301]` as Int32 batches.
+fn codes() -> Column {
+    status(vec![
+        Arc::new(Int32Array::from(vec![Some(200), Some(404), None])),
+        Arc::new(Int32Array::from(vec![Some(500), Some(200), Some(301)])),
+    ])
+}
+
+fn labels(column: &Column) -> Vec<Option<String>> {
+    column
+        .to_array(JsValue::UNDEFINED)
+        .unwrap()
+        .iter()
+        .map(|value| value.as_string())
+        .collect()
+}
+
+fn owned(values: &[Option<&str>]) -> Vec<Option<String>> {
+    values.iter().map(|value| value.map(String::from)).collect()
+}
+
+#[wasm_bindgen_test]
+fn status_codes_map_to_labels_with_a_default() {
+    let mapping =
+        JSON::parse(r#"{"200": "OK", "404": "Not Found", "500": "Server Error"}"#).unwrap();
+    let mapped = map_values(&codes(), mapping.clone(), "Other".into()).unwrap();
+    assert_eq!(mapped.data_type().unwrap(), "Utf8");
+    assert_eq!(
+        labels(&mapped),
+        owned(&[
+            Some("OK"),
+            Some("Not Found"),
+            None,
+            Some("Server Error"),
+            Some("OK"),
+            Some("Other"),
+        ])
+    );
+
+    // Without a default, unmapped codes are null like the source nulls.
+    let mapped = map_values(&codes(), mapping, JsValue::UNDEFINED).unwrap();
+    assert_eq!(
+        labels(&mapped),
+        owned(&[
+            Some("OK"),
+            Some("Not Found"),
+            None,
+            Some("Server Error"),
+            Some("OK"),
+            None,
+        ])
+    );
+    assert_eq!(mapped.null_count().unwrap(), 2);
+}
+
+#[wasm_bindgen_test]
+fn map_keys_may_be_numbers_and_bigints() {
+    let mapping = Map::new();
+    mapping.set(&200.into(), &"OK".into());
+    mapping.set(&BigInt::from(301).into(), &"Moved".into());
+    let mapped = map_values(&codes(), mapping.into(), "Other".into()).unwrap();
+    assert_eq!(
+        labels(&mapped),
+        owned(&[
+            Some("OK"),
+            Some("Other"),
+            None,
+            Some("Other"),
+            Some("OK"),
+            Some("Moved"),
+        ])
+    );
+
+    let wide = status(vec![Arc::new(Int64Array::from(vec![
+        Some(200),
+        Some(9_007_199_254_740_993),
+    ]))]);
+    let mapping = Map::new();
+    mapping.set(
+        &BigInt::new(&"9007199254740993".into()).unwrap().into(),
+        &"Huge".into(),
+    );
+    let mapped = map_values(&wide, mapping.into(), JsValue::UNDEFINED).unwrap();
+    assert_eq!(labels(&mapped), owned(&[None, Some("Huge")]));
+}
+
+#[wasm_bindgen_test]
+fn labels_must_share_a_type() {
+    let mapping = JSON::parse(r#"{"200": "OK"}"#).unwrap();
+    let error = map_values(&codes(), mapping, 0.into())
+        .map(drop)
+        .unwrap_err()
+        .as_string()
+        .unwrap();
+    assert!(
+        error.contains("Mapped values mix Utf8 and Float64"),
+        "{error}"
+    );
+
+    let mapping = JSON::parse(r#"{"2xx": "OK"}"#).unwrap();
+    let error = map_values(&codes(), mapping, JsValue::UNDEFINED)
+        .map(drop)
+        .unwrap_err()
+        .as_string()
+        .unwrap();
+    assert!(error.contains("Invalid mapping key"), "{error}");
+}
@@ -0,0 +1,227 @@
+//! Edge cases of the numeric cleanup kernels on every integer width and
+//! float type they share a code path with.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, RecordBatch};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{abs, clip, column_round, get_column, negate, read_table_from_bytes, Column};
+use js_sys::{BigInt, JSON};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// `array` as the column `x` of a new table.
+fn column(array: ArrayRef) -> Column {
+    let batch = RecordBatch::try_from_iter([("x", array)]).unwrap();
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    let table = read_table_from_bytes(&bytes, None).unwrap();
+    get_column(table, "x").unwrap()
+}
+
+/// Values of a numeric column as numbers, `BigInt`s included.
+fn values(column: &Column) -> Vec<Option<f64>> {
+    column
+        .to_array(JsValue::UNDEFINED)
+        .unwrap()
+        .iter()
+        .map(|value| {
+            value.as_f64().or_else(|| {
+                let value = BigInt::from(value).to_string(10).ok()?;
+                value.as_string()?.parse().ok()
+            })
+        })
+        .collect()
+}
+
+fn error(result: Result<Column, JsValue>) -> String {
+    match result {
+        Ok(column) => panic!("expected an error, got {:?}", values(&column)),
+        Err(error) => error.as_string().unwrap(),
+    }
+}
+
+fn on_overflow(mode: &str) -> JsValue {
+    JSON::parse(&format!(r#"{{"onOverflow":"{mode}"}}"#)).unwrap()
+}
+
+/// `[MIN, -1, null, 5]` in each signed integer width, with MIN as a number.
+fn signed_minimums() -> Vec<(ArrayRef, f64)> {
+    vec![
+        (
+            Arc::new(Int32Array::from(vec![
+                Some(i32::MIN),
+                Some(-1),
+                None,
+                Some(5),
+            ])),
+            f64::from(i32::MIN),
+        ),
+        (
+            Arc::new(Int64Array::from(vec![
+                Some(i64::MIN),
+                Some(-1),
+                None,
+                Some(5),
+            ])),
+            -(2_f64.powi(63)),
+        ),
+    ]
+}
+
+/// `[MIN, -1.5, null, 5]` as Float32 and Float64, with MIN as a number.
+fn float_minimums() -> Vec<(ArrayRef, f64)> {
+    vec![
+        (
+            Arc::new(Float32Array::from(vec![
+                Some(f32::MIN),
+                Some(-1.5),
+                None,
+                Some(5.0),
+            ])),
+            f64::from(f32::MIN),
+        ),
+        (
+            Arc::new(Float64Array::from(vec![
+                Some(f64::MIN),
+                Some(-1.5),
+                None,
+                Some(5.0),
+            ])),
+            f64::MIN,
+        ),
+    ]
+}
+
+#[wasm_bindgen_test]
+fn integer_minimums_overflow_abs_and_negate() {
+    for (array, min) in signed_minimums() {
+        let column = column(array);
+        let data_type = column.data_type().unwrap();
+        for mode in [JsValue::UNDEFINED, on_overflow("error")] {
+            let message = error(abs(&column, mode.clone()));
+            assert!(
+                message.starts_with("abs failed on column 'x'"),
+                "{data_type}: {message}"
+            );
+            let message = error(negate(&column, mode));
+            assert!(
+                message.starts_with("negate failed on column 'x'"),
+                "{data_type}: {message}"
+            );
+        }
+        let absolute = abs(&column, on_overflow("null")).unwrap();
+        assert_eq!(
+            values(&absolute),
+            [None, Some(1.0), None, Some(5.0)],
+            "{data_type}"
+        );
+        assert_eq!(absolute.data_type().unwrap(), data_type);
+        let negated = negate(&column, on_overflow("null")).unwrap();
+        assert_eq!(
+            values(&negated),
+            [None, Some(1.0), None, Some(-5.0)],
+            "{data_type}"
+        );
+        // The source column is untouched.
+        assert_eq!(values(&column)[0], Some(min));
+    }
+}
+
+#[wasm_bindgen_test]
+fn float_minimums_never_overflow() {
+    for (array, min) in float_minimums() {
+        let column = column(array);
+        let data_type = column.data_type().unwrap();
+        for mode in [on_overflow("error"), on_overflow("null")] {
+            let absolute = abs(&column, mode.clone()).unwrap();
+            assert_eq!(
+                values(&absolute),
+                [Some(-min), Some(1.5), None, Some(5.0)],
+                "{data_type}"
+            );
+            let negated = negate(&column, mode).unwrap();
+            assert_eq!(
+                values(&negated),
+                [Some(-min), Some(1.5), None, Some(-5.0)],
+                "{data_type}"
+            );
+            assert_eq!(negated.data_type().unwrap(), data_type);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn negative_decimals_round_floats_to_tens() {
+    let floats: [ArrayRef; 2] = [
+        Arc::new(Float32Array::from(vec![
+            Some(1234.5),
+            Some(-1250.0),
+            Some(0.05),
+            None,
+        ])),
+        Arc::new(Float64Array::from(vec![
+            Some(1234.5),
+            Some(-1250.0),
+            Some(0.05),
+            None,
+        ])),
+    ];
+    for array in floats {
+        let column = column(array);
+        let data_type = column.data_type().unwrap();
+        let hundreds = column_round(&column, Some(-2)).unwrap();
+        // Halves round away from zero.
+        assert_eq!(
+            values(&hundreds),
+            [Some(1200.0), Some(-1300.0), Some(0.0), None],
+            "{data_type}"
+        );
+        let thousands = column_round(&column, Some(-3)).unwrap();
+        assert_eq!(
+            values(&thousands),
+            [Some(1000.0), Some(-1000.0), Some(0.0), None],
+            "{data_type}"
+        );
+        assert_eq!(thousands.data_type().unwrap(), data_type);
+    }
+    for (array, _) in signed_minimums() {
+        let column = column(array);
+        let message = error(column_round(&column, Some(-1)));
+        assert!(
+            message.contains("negative decimals need a float column"),
+            "{message}"
+        );
+        let unchanged = column_round(&column, Some(1)).unwrap();
+        assert_eq!(values(&unchanged), values(&column));
+    }
+}
+
+#[wasm_bindgen_test]
+fn clip_rejects_crossed_bounds() {
+    for (array, _) in signed_minimums().into_iter().chain(float_minimums()) {
+        let column = column(array);
+        let data_type = column.data_type().unwrap();
+        let message = error(clip(&column, 3.into(), 1.into()));
+        assert!(
+            message.contains("clip min is greater than max"),
+            "{data_type}: {message}"
+        );
+        let clipped = clip(&column, 1.into(), 3.into()).unwrap();
+        assert_eq!(
+            values(&clipped),
+            [Some(1.0), Some(1.0), None, Some(3.0)],
+            "{data_type}"
+        );
+        let pinned = clip(&column, 2.into(), 2.into()).unwrap();
+        assert_eq!(
+            values(&pinned),
+            [Some(2.0), Some(2.0), None, Some(2.0)],
+            "{data_type}"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Plain objects from `to_plain_object` survive `structuredClone` and
+//! import back to the same table.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Date32Array, Float64Array, Int32Array, Int64Array,
+    RecordBatch, StringArray, TimestampMillisecondArray,
+};
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    read_table_from_bytes, table_from_plain_object, to_plain_object, write_table_to_ipc,
+    TableHandle,
+};
+use js_sys::{Array, ArrayBuffer, Function, Object, Reflect};
+use std::sync::Arc;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Eight columns of different transport types, nulls in most of them.
+fn source() -> RecordBatch {
+    let columns: [(&str, ArrayRef); 8] = [
+        (
+            "id",
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+        ),
+        (
+            "big",
+            Arc::new(Int64Array::from(vec![Some(i64::MAX), Some(-1), None])),
+        ),
+        (
+            "score",
+            Arc::new(Float64Array::from(vec![Some(0.5), None, Some(f64::NAN)])),
+        ),
+        (
+            "name",
+            Arc::new(StringArray::from(vec![Some("ä"), None, Some("")])),
+        ),
+        (
+            "ok",
+            Arc::new(BooleanArray::from(vec![Some(true), Some(false), None])),
+        ),
+        (
+            "at",
+            Arc::new(
+                TimestampMillisecondArray::from(vec![Some(1_700_000_000_123), None, Some(-1)])
+                    .with_timezone("UTC"),
+            ),
+        ),
+        (
+            "day",
+            Arc::new(Date32Array::from(vec![Some(19_000), Some(0), None])),
+        ),
+        (
+            "raw",
+            Arc::new(BinaryArray::from(vec![
+                Some(&[0xff_u8, 0][..]),
+                None,
+                Some(&[][..]),
+            ])),
+        ),
+    ];
+    RecordBatch::try_from_iter(columns).unwrap()
+}
+
+fn table(batch: &RecordBatch) -> TableHandle {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+    writer.write(batch).unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+/// The table's rows as one batch.
+fn batch(handle: TableHandle) -> RecordBatch {
+    let bytes = write_table_to_ipc(handle, false).unwrap().to_vec();
+    let reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+    let batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+    arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap()
+}
+
+fn structured_clone(value: &JsValue) -> JsValue {
+    let clone: Function = Reflect::get(&js_sys::global(), &"structuredClone".into())
+        .unwrap()
+        .dyn_into()
+        .expect("structuredClone is available");
+    clone.call1(&JsValue::NULL, value).unwrap()
+}
+
+/// Whether `value` and everything it holds are plain data: no objects with
+/// a prototype other than `Object.prototype`, `Array.prototype` or a typed
+/// array's.
+fn is_plain(value: &JsValue) -> bool {
+    if !value.is_object() || ArrayBuffer::is_view(value) {
+        return true;
+    }
+    let plain_prototype = Object::get_prototype_of(&Object::new());
+    if Object::get_prototype_of(value) != plain_prototype && !Array::is_array(value) {
+        return false;
+    }
+    Object::values(value.unchecked_ref())
+        .iter()
+        .all(|inner| is_plain(&inner))
+}
+
+#[wasm_bindgen_test]
+fn plain_objects_survive_structured_clone() {
+    let source = source();
+    let object = to_plain_object(table(&source), JsValue::UNDEFINED).unwrap();
+    assert!(is_plain(&object));
+
+    let clone = structured_clone(&object);
+    let imported = batch(table_from_plain_object(clone, JsValue::UNDEFINED).unwrap());
+    assert_eq!(imported.schema(), source.schema());
+    assert_eq!(imported.num_rows(), 3);
+    for (name, column) in source.schema().fields().iter().zip(source.columns()) {
+        let imported = imported.column_by_name(name.name()).unwrap();
+        // NaN never equals itself, so floats compare by their bits.
+        if let (Some(expected), Some(actual)) = (
+            column.as_any().downcast_ref::<Float64Array>(),
+            imported.as_any().downcast_ref::<Float64Array>(),
+        ) {
+            let bits = |array: &Float64Array| {
+                array
+                    .iter()
+                    .map(|value| value.map(f64::to_bits))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(bits(actual), bits(expected), "{}", name.name());
+        } else {
+            assert_eq!(imported, column, "{}", name.name());
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn truncated_exports_clone_too() {
+    let options = js_sys::JSON::parse(r#"{"maxRows":2}"#).unwrap();
+    let object = to_plain_object(table(&source()), options).unwrap();
+    let clone = structured_clone(&object);
+    assert_eq!(
+        Reflect::get(&clone, &"truncated".into()).unwrap(),
+        JsValue::TRUE
+    );
+    let imported = batch(table_from_plain_object(clone, JsValue::UNDEFINED).unwrap());
+    assert_eq!(imported, source().slice(0, 2));
+}
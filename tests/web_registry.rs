@@ -0,0 +1,56 @@
+//! Calls that act on every registered table at once. Native tests share
+//! the registry and run in parallel, so these run here instead.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    create_sample_table, free_all, free_table, registry_release_snapshot, registry_restore,
+    registry_snapshot, table_row_count,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn free_all_keeps_only_the_exceptions() {
+    let handles: Vec<_> = (0..4)
+        .map(|_| create_sample_table("basic").unwrap())
+        .collect();
+    let kept = [handles[1], handles[3]];
+    // The unregistered exception is ignored.
+    let freed = free_all(vec![kept[0], kept[1], u32::MAX]).unwrap();
+    assert!(freed >= 2, "{freed}");
+    for handle in kept {
+        assert!(table_row_count(handle).is_ok());
+    }
+    for handle in [handles[0], handles[2]] {
+        let message = table_row_count(handle).unwrap_err().as_string().unwrap();
+        assert_eq!(message, format!("Table handle {handle} was already freed"));
+    }
+
+    assert_eq!(free_all(kept.to_vec()).unwrap(), 0);
+    assert_eq!(free_all(Vec::new()).unwrap(), 2);
+    assert!(kept.iter().all(|&handle| table_row_count(handle).is_err()));
+}
+
+#[wasm_bindgen_test]
+fn restoring_a_snapshot_undoes_creates_and_frees() {
+    let before = create_sample_table("basic").unwrap();
+    let rows = table_row_count(before).unwrap();
+    let token = registry_snapshot().unwrap();
+
+    let created = create_sample_table("basic").unwrap();
+    free_table(before).unwrap();
+    assert!(table_row_count(before).is_err());
+
+    registry_restore(token).unwrap();
+    let message = table_row_count(created).unwrap_err().as_string().unwrap();
+    assert_eq!(message, format!("Table handle {created} was already freed"));
+    assert_eq!(table_row_count(before).unwrap(), rows);
+
+    // The snapshot stays usable until released.
+    free_table(before).unwrap();
+    registry_restore(token).unwrap();
+    assert_eq!(table_row_count(before).unwrap(), rows);
+    assert!(registry_release_snapshot(token));
+    assert!(!registry_release_snapshot(token));
+    assert!(registry_restore(token).is_err());
+}
@@ -0,0 +1,67 @@
+//! Row ids past 2^53 mapped back with `positions_of` from JS.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow_rs_wasm::{
+    create_sample_table, get_column, positions_of, select, sort_by, with_row_index,
+};
+use js_sys::{Array, BigInt, BigInt64Array, Object, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// `numeric_1k` with an Int64 `rid` column starting at 2^60, sorted by
+/// `value` descending and narrowed to `rid` and `value`.
+fn shuffled() -> arrow_rs_wasm::TableHandle {
+    let options = Object::new();
+    let start = BigInt::from(1_i64 << 60);
+    Reflect::set(&options, &"startAt".into(), &start).unwrap();
+    Reflect::set(&options, &"type".into(), &"int64".into()).unwrap();
+    let table = create_sample_table("numeric_1k").unwrap();
+    let indexed = with_row_index(table, Some("rid".into()), options.into()).unwrap();
+    let keys = Array::of1(&js_sys::JSON::parse(r#"{"column":"value","descending":true}"#).unwrap());
+    let sorted = sort_by(indexed, keys.into()).unwrap();
+    select(sorted, vec!["rid".into(), "value".into()]).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn bigint_ids_map_back_after_sort_and_select() {
+    let table = shuffled();
+    let ids: Vec<i64> = [0, 1, 499, 998]
+        .iter()
+        .map(|offset| (1 << 60) + offset)
+        .collect();
+    let rid = get_column(table, "rid").unwrap();
+    let check = |positions: Array| {
+        assert_eq!(positions.length(), 4);
+        for (position, id) in positions.iter().zip(&ids) {
+            let row: usize = serde_wasm_bindgen::from_value(position).unwrap();
+            let found = rid.get(row, JsValue::UNDEFINED).unwrap();
+            assert_eq!(BigInt::from(found).to_string(10).unwrap(), id.to_string());
+        }
+    };
+    check(positions_of(table, "rid", BigInt64Array::from(ids.as_slice()).into()).unwrap());
+    let array: Array = ids
+        .iter()
+        .map(|&id| JsValue::from(BigInt::from(id)))
+        .collect();
+    check(positions_of(table, "rid", array.into()).unwrap());
+
+    let missing = Array::of2(
+        &BigInt::from((1_i64 << 60) + 1_000).into(),
+        &JsValue::from(1.5),
+    );
+    let positions = positions_of(table, "rid", missing.into()).unwrap();
+    assert!(positions.iter().all(|position| position.is_null()));
+}
+
+#[wasm_bindgen_test]
+fn unsafe_numbers_are_rejected() {
+    let table = shuffled();
+    let ids = Array::of1(&JsValue::from(2_f64.powi(60)));
+    let error = positions_of(table, "rid", ids.into()).unwrap_err();
+    assert!(
+        error.as_string().unwrap().contains("pass a bigint"),
+        "{error:?}"
+    );
+    assert!(positions_of(table, "rid", JsValue::from("1")).is_err());
+}
@@ -0,0 +1,145 @@
+//! Walking a multi-batch table with `table_rows` until it is exhausted,
+//! and reading the same rows positionally with `to_rows_compact`.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, Float64Array, Int32Array, RecordBatch, StringArray};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    free_table, get_column, get_column_names, read_table_from_bytes, table_rows, to_rows_compact,
+    TableHandle,
+};
+use js_sys::{Array, Reflect, JSON};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// `id`, `amount` and `memo` in batches of 3, 0 and 2 rows; `amount` and
+/// `memo` have a null each.
+fn payments() -> TableHandle {
+    let batch = |ids: Vec<i32>, amounts: Vec<Option<f64>>, memos: Vec<Option<&str>>| {
+        RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+            ("amount", Arc::new(Float64Array::from(amounts))),
+            ("memo", Arc::new(StringArray::from(memos))),
+        ])
+        .unwrap()
+    };
+    let batches = [
+        batch(
+            vec![1, 2, 3],
+            vec![Some(10.5), Some(20.0), None],
+            vec![Some("rent"), None, Some("")],
+        ),
+        batch(vec![], vec![], vec![]),
+        batch(
+            vec![4, 5],
+            vec![Some(0.25), Some(-5.0)],
+            vec![Some("tip"), Some("refund")],
+        ),
+    ];
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    read_table_from_bytes(&bytes, None).unwrap()
+}
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn every_row_is_visited_once_in_order() {
+    let table = payments();
+    let mut rows = table_rows(table, JsValue::UNDEFINED).unwrap();
+    let mut ids = Vec::new();
+    let mut total = 0.0;
+    let mut nulls = 0;
+    loop {
+        assert_eq!(rows.position(), ids.len());
+        let result = rows.next_result().unwrap();
+        if get(&result, "done").is_truthy() {
+            assert!(get(&result, "value").is_undefined());
+            break;
+        }
+        let row = get(&result, "value");
+        ids.push(get(&row, "id").as_f64().unwrap());
+        match get(&row, "amount").as_f64() {
+            Some(amount) => total += amount,
+            None => nulls += 1,
+        }
+    }
+    assert_eq!(ids, [1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert!((total - 25.75).abs() < 1e-12, "{total}");
+    assert_eq!(nulls, 1);
+
+    // An exhausted iterator stays done.
+    let again = rows.next_result().unwrap();
+    assert!(get(&again, "done").is_truthy());
+    assert_eq!(rows.position(), 5);
+}
+
+#[wasm_bindgen_test]
+fn iterators_are_independent_and_see_frees() {
+    let table = payments();
+    let mut first = table_rows(table, JsValue::UNDEFINED).unwrap();
+    let mut second = table_rows(table, JsValue::UNDEFINED).unwrap();
+    first.next_result().unwrap();
+    first.next_result().unwrap();
+    let row = get(&second.next_result().unwrap(), "value");
+    assert_eq!(get(&row, "id").as_f64(), Some(1.0));
+    assert_eq!((first.position(), second.position()), (2, 1));
+
+    free_table(table).unwrap();
+    assert!(first.next_result().is_err());
+    assert!(table_rows(table, JsValue::UNDEFINED).is_err());
+}
+
+fn json(value: &JsValue) -> String {
+    JSON::stringify(value).unwrap().into()
+}
+
+#[wasm_bindgen_test]
+fn compact_rows_match_the_column_arrays() {
+    let table = payments();
+    let names = get_column_names(table).unwrap();
+    assert_eq!(names, ["id", "amount", "memo"]);
+    let rows = to_rows_compact(table, JsValue::UNDEFINED).unwrap();
+    assert_eq!(
+        json(&rows),
+        r#"[[1,10.5,"rent"],[2,20,null],[3,null,""],[4,0.25,"tip"],[5,-5,"refund"]]"#
+    );
+
+    // Column `i` of the compact rows is that column's `to_array`.
+    let columns: Vec<Array> = names
+        .iter()
+        .map(|name| {
+            let column = get_column(table, name).unwrap();
+            column.to_array(JsValue::UNDEFINED).unwrap()
+        })
+        .collect();
+    for (i, column) in columns.iter().enumerate() {
+        let picked: Array = rows
+            .iter()
+            .map(|row| Array::from(&row).get(i as u32))
+            .collect();
+        assert_eq!(json(&picked), json(column), "{}", names[i]);
+    }
+
+    // Zipping the names back in gives the `table_rows` objects.
+    let mut objects = table_rows(table, JsValue::UNDEFINED).unwrap();
+    for row in rows.iter() {
+        let object = get(&objects.next_result().unwrap(), "value");
+        let row = Array::from(&row);
+        assert_eq!(row.length() as usize, names.len());
+        for (i, name) in names.iter().enumerate() {
+            let expected = get(&object, name);
+            assert_eq!(json(&row.get(i as u32)), json(&expected), "{name}");
+        }
+    }
+    assert!(get(&objects.next_result().unwrap(), "done").is_truthy());
+}
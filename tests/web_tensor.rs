@@ -0,0 +1,81 @@
+//! Fixed-shape tensor columns built from and read back into typed arrays.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{ArrayRef, FixedSizeListArray, Float32Array, RecordBatch};
+use arrow::datatypes::{DataType, Field};
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{get_column, make_tensor_column, read_table_from_bytes};
+use js_sys::{Float32Array as JsFloat32Array, Reflect};
+use std::sync::Arc;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn values(array: &JsValue) -> Vec<f32> {
+    array.dyn_ref::<JsFloat32Array>().unwrap().to_vec()
+}
+
+#[wasm_bindgen_test]
+fn three_dimensional_tensors_round_trip() {
+    // Two tensors of shape [2, 3, 4], numbered in storage order.
+    let stored: Vec<f32> = (0..48_u8).map(f32::from).collect();
+    let names = vec!["C".to_string(), "H".to_string(), "W".to_string()];
+    let column = make_tensor_column(
+        "image",
+        &JsFloat32Array::from(stored.as_slice()).into(),
+        vec![2, 3, 4],
+        Some(names),
+    )
+    .unwrap();
+    assert_eq!(column.length().unwrap(), 2);
+
+    let info = column.tensor_info().unwrap();
+    let shape: Vec<usize> =
+        serde_wasm_bindgen::from_value(Reflect::get(&info, &"shape".into()).unwrap()).unwrap();
+    assert_eq!(shape, [2, 3, 4]);
+    let dim_names: Vec<String> =
+        serde_wasm_bindgen::from_value(Reflect::get(&info, &"dimNames".into()).unwrap()).unwrap();
+    assert_eq!(dim_names, ["C", "H", "W"]);
+
+    let second = column.tensor_at(1).unwrap();
+    assert_eq!(
+        values(&Reflect::get(&second, &"values".into()).unwrap()),
+        stored[24..]
+    );
+    let all = column.tensors_as_contiguous(JsValue::UNDEFINED).unwrap();
+    assert_eq!(values(&all), stored);
+    assert!(make_tensor_column(
+        "image",
+        &JsFloat32Array::from(&stored[..40]).into(),
+        vec![2, 3, 4],
+        None
+    )
+    .is_err());
+}
+
+#[wasm_bindgen_test]
+fn bare_fixed_size_lists_are_plain_lists() {
+    let item = Arc::new(Field::new("item", DataType::Float32, false));
+    let list: ArrayRef = Arc::new(
+        FixedSizeListArray::try_new(item, 4, Arc::new(Float32Array::from(vec![0.0; 8])), None)
+            .unwrap(),
+    );
+    let batch = RecordBatch::try_from_iter([("embedding", list)]).unwrap();
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+    drop(writer);
+    let table = read_table_from_bytes(&bytes, None).unwrap();
+    let column = get_column(table, "embedding").unwrap();
+
+    let error = column.tensor_info().unwrap_err().as_string().unwrap();
+    assert!(
+        error.contains("is not an arrow.fixed_shape_tensor column"),
+        "{error}"
+    );
+    assert!(column.tensor_at(0).is_err());
+    assert!(column.tensors_as_contiguous(JsValue::UNDEFINED).is_err());
+    // It still reads as an ordinary list column.
+    assert_eq!(column.to_array(JsValue::UNDEFINED).unwrap().length(), 2);
+}
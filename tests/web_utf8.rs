@@ -0,0 +1,221 @@
+//! Text that is not valid Unicode, as JS strings with lone surrogates and
+//! as bytes that are not UTF-8, under each `onInvalidUtf8` policy.
+
+#![cfg(target_arch = "wasm32")]
+
+use arrow::array::{Array, ArrayRef, AsArray, BinaryArray, RecordBatch};
+use arrow::datatypes::DataType;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow_rs_wasm::{
+    decode_utf8, get_column, read_table_from_bytes, table_from_plain_object, write_table_to_ipc,
+    Column, StreamingTableBuilder, TableHandle,
+};
+use js_sys::{Array as JsArray, JsString, Object, Reflect, Uint8Array, JSON};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// `"ok"`, a lone high surrogate, a reversed pair and an emoji written as
+/// its surrogate pair.
+fn broken_strings() -> Vec<JsValue> {
+    vec![
+        JsString::from("ok").into(),
+        JsString::from_char_code1(0xD800).into(),
+        JsString::from_char_code2(0xDE00, 0xD83D).into(),
+        JsString::from_char_code2(0xD83D, 0xDE00).into(),
+    ]
+}
+
+/// What the replace policy makes of [`broken_strings`].
+const REPLACED: [&str; 4] = ["ok", "\u{FFFD}", "\u{FFFD}\u{FFFD}", "\u{1F600}"];
+
+/// Valid text, then bytes whose third byte starts a truncated sequence.
+fn broken_bytes() -> Vec<JsValue> {
+    vec![
+        JsString::from("ok").into(),
+        Uint8Array::from(&b"fine"[..]).into(),
+        Uint8Array::from(&[b'a', b'b', 0xE2, 0x82][..]).into(),
+    ]
+}
+
+fn json(text: &str) -> JsValue {
+    JSON::parse(text).unwrap()
+}
+
+fn options(policy: &str) -> JsValue {
+    json(&format!(r#"{{"onInvalidUtf8":"{policy}"}}"#))
+}
+
+/// A plain table with the nullable text column `s` holding `values`.
+fn plain_object(values: &[JsValue]) -> JsValue {
+    let object = json(&format!(
+        r#"{{"schema":{{"fields":[{{"name":"s","type":"Utf8","nullable":true}}]}},
+            "rowCount":{},"nulls":{{"s":null}}}}"#,
+        values.len()
+    ));
+    let columns = Object::new();
+    let values: JsArray = values.iter().collect();
+    Reflect::set(&columns, &"s".into(), &values).unwrap();
+    Reflect::set(&object, &"columns".into(), &columns).unwrap();
+    object
+}
+
+fn builder(policy: &str) -> StreamingTableBuilder {
+    StreamingTableBuilder::create(
+        json(r#"{"fields":[{"name":"s","type":"Utf8","nullable":true}]}"#),
+        options(policy),
+    )
+    .unwrap()
+}
+
+fn rows(values: &[JsValue]) -> Vec<JsValue> {
+    values
+        .iter()
+        .map(|value| {
+            let row = Object::new();
+            Reflect::set(&row, &"s".into(), value).unwrap();
+            row.into()
+        })
+        .collect()
+}
+
+/// Column `s` of the table as an arrow array.
+fn column_s(handle: TableHandle) -> ArrayRef {
+    let bytes = write_table_to_ipc(handle, false).unwrap().to_vec();
+    let mut reader = StreamReader::try_new(bytes.as_slice(), None).unwrap();
+    let batch = reader.next().unwrap().unwrap();
+    assert!(reader.next().is_none());
+    Arc::clone(batch.column_by_name("s").unwrap())
+}
+
+fn strings(array: &ArrayRef) -> Vec<&str> {
+    array
+        .as_string::<i32>()
+        .iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+fn message(error: JsValue) -> String {
+    error.as_string().unwrap()
+}
+
+#[wasm_bindgen_test]
+fn lone_surrogates_are_replaced_by_default() {
+    let object = plain_object(&broken_strings());
+    for policy in [JsValue::UNDEFINED, options("replace"), options("binary")] {
+        let imported = column_s(table_from_plain_object(object.clone(), policy).unwrap());
+        assert_eq!(strings(&imported), REPLACED);
+    }
+
+    let mut built = builder("replace");
+    built.push_rows(rows(&broken_strings())).unwrap();
+    assert_eq!(strings(&column_s(built.finish().unwrap())), REPLACED);
+}
+
+#[wasm_bindgen_test]
+fn lone_surrogates_fail_with_their_row_under_error() {
+    let error = table_from_plain_object(plain_object(&broken_strings()), options("error"))
+        .map(drop)
+        .unwrap_err();
+    let error = message(error);
+    assert!(
+        error.contains("String at row 1 contains a lone UTF-16 surrogate"),
+        "{error}"
+    );
+
+    // The reversed pair is the first invalid value past the first chunk.
+    let mut built = builder("error");
+    let mut values = broken_strings();
+    let lone = values.remove(1);
+    built.push_rows(rows(&values[..1])).unwrap();
+    let error = message(built.push_rows(rows(&values)).unwrap_err());
+    assert!(
+        error.contains("String at row 2 contains a lone UTF-16 surrogate"),
+        "{error}"
+    );
+    assert_eq!(built.row_count(), 2);
+    let error = message(built.push_rows(rows(&[lone])).unwrap_err());
+    assert!(error.contains("String at row 2"), "{error}");
+}
+
+#[wasm_bindgen_test]
+fn malformed_bytes_follow_the_policy() {
+    let object = plain_object(&broken_bytes());
+
+    let replaced = column_s(table_from_plain_object(object.clone(), JsValue::UNDEFINED).unwrap());
+    assert_eq!(strings(&replaced), ["ok", "fine", "ab\u{FFFD}"]);
+
+    let error = message(
+        table_from_plain_object(object.clone(), options("error"))
+            .map(drop)
+            .unwrap_err(),
+    );
+    assert!(
+        error.contains("Invalid UTF-8 at row 2, byte offset 2"),
+        "{error}"
+    );
+
+    // Valid rows keep their bytes when the column falls back to Binary.
+    let kept = column_s(table_from_plain_object(object, options("binary")).unwrap());
+    assert_eq!(kept.data_type(), &DataType::Binary);
+    let kept: Vec<&[u8]> = kept.as_binary::<i32>().iter().map(Option::unwrap).collect();
+    assert_eq!(kept, [&b"ok"[..], b"fine", &[b'a', b'b', 0xE2, 0x82]]);
+}
+
+/// The Binary column `b` of a two-batch table: `["ok", null]`, then
+/// `["fine", <ab and a truncated sequence>]`.
+fn binary_column() -> Column {
+    let batches = [
+        BinaryArray::from(vec![Some(&b"ok"[..]), None]),
+        BinaryArray::from(vec![
+            Some(&b"fine"[..]),
+            Some(&[b'a', b'b', 0xE2, 0x82][..]),
+        ]),
+    ]
+    .map(|array| RecordBatch::try_from_iter([("b", Arc::new(array) as ArrayRef)]).unwrap());
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema()).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+    get_column(read_table_from_bytes(&bytes, None).unwrap(), "b").unwrap()
+}
+
+#[wasm_bindgen_test]
+fn decode_utf8_names_the_row_across_batches() {
+    let column = binary_column();
+    let error = message(
+        decode_utf8(&column, json(r#""error""#))
+            .map(drop)
+            .unwrap_err(),
+    );
+    assert_eq!(
+        error,
+        "decode_utf8 failed on column 'b' (Binary) at row 3: invalid UTF-8 at byte offset 2"
+    );
+
+    let replaced = decode_utf8(&column, JsValue::UNDEFINED).unwrap();
+    assert_eq!(replaced.data_type().unwrap(), "Utf8");
+    let values: Vec<JsValue> = replaced
+        .to_array(JsValue::UNDEFINED)
+        .unwrap()
+        .iter()
+        .collect();
+    assert_eq!(
+        values,
+        [
+            JsValue::from("ok"),
+            JsValue::NULL,
+            JsValue::from("fine"),
+            JsValue::from("ab\u{FFFD}")
+        ]
+    );
+
+    let kept = decode_utf8(&column, json(r#""binary""#)).unwrap();
+    assert_eq!(kept.data_type().unwrap(), "Binary");
+    assert_eq!(kept.null_count().unwrap(), 1);
+}